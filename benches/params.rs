@@ -0,0 +1,70 @@
+// Compares the allocations needed to build the request parameters for a 50-item scrobble batch using the owned
+// `Scrobble::as_map` representation against the `Scrobble::params` representation used internally.
+//
+// Run with `cargo bench --bench params`.
+
+use rustfm_scrobble::{Scrobble, ScrobbleBatch};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ITERATIONS: u32 = 2_000;
+
+fn measure<F: FnMut()>(name: &str, mut f: F) {
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed: Duration = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+
+    println!(
+        "{name:<10} {:>8} allocations/batch {:>10.2?}/batch",
+        allocations / ITERATIONS as usize,
+        elapsed / ITERATIONS
+    );
+}
+
+fn main() {
+    let batch: ScrobbleBatch = (0..50)
+        .map(|i| {
+            let mut scrobble = Scrobble::new("Example Artist", "Example Track", "Example Album");
+            scrobble.with_timestamp(1_500_000_000 + i);
+            scrobble
+        })
+        .collect::<Vec<Scrobble>>()
+        .into();
+
+    #[allow(deprecated)]
+    measure("as_map", || {
+        for scrobble in batch.iter() {
+            black_box(scrobble.as_map());
+        }
+    });
+
+    measure("params", || {
+        for scrobble in batch.iter() {
+            black_box(scrobble.params());
+        }
+    });
+}
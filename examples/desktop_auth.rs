@@ -0,0 +1,29 @@
+use rustfm_scrobble::{Scrobble, Scrobbler};
+use std::error::Error;
+use std::io;
+
+// Example rustfm-scrobble client authenticating with the Last.fm desktop auth flow: request a token, have the user
+// authorize it in their browser, then exchange it for a session.
+// Replace credential values with your own to test.
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let api_key = "{{api_key}}";
+    let api_secret = "{{api_secret}}";
+
+    let mut scrobbler = Scrobbler::new(api_key, api_secret);
+
+    let token = scrobbler.request_auth_token()?;
+    println!("Authorize this application at {}", scrobbler.authorization_url(&token));
+    println!("then press Enter to continue");
+    io::stdin().read_line(&mut String::new())?;
+
+    let response = scrobbler.authenticate_with_token(&token)?;
+    println!("Authenticated! {:#?}", response);
+    println!("Save the session key to skip this next time: {}", response.key());
+
+    let track = Scrobble::new("Los Campesinos!", "As Lucerne / The Low", "No Blues");
+    let response = scrobbler.now_playing(&track)?;
+    println!("Sent now playing! {:#?}", response);
+
+    Ok(())
+}
@@ -0,0 +1,624 @@
+// An async counterpart to Scrobbler, for applications built on an async runtime
+use crate::async_transport::{AsyncReqwestTransport, AsyncTransport};
+use crate::chunks::{self, ChunkResult, DailyLimitReached, ImportProgress};
+use crate::client::ApiOperation;
+use crate::error::ScrobblerError;
+use crate::models::metadata::{NowPlaying, Scrobble, ScrobbleBatch};
+use crate::models::responses::{BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse};
+use crate::rate_limit::RateLimit;
+use crate::retry::{CallClass, RetryPolicy};
+use crate::scrobbler::{Scrobbler, Submission};
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+
+use futures_util::stream::{self, Stream};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// The asynchronous counterpart of [`Scrobbler`], whose calls don't block the calling thread. Requires the `async`
+/// feature.
+///
+/// Requests are prepared, signed and checked exactly as a `Scrobbler`'s are, and the same response types are
+/// returned; only sending them differs. They're rate limited and retried as a `Scrobbler`'s are too, but wait for
+/// the rate limit, and between attempts, without blocking the thread.
+///
+/// # Usage
+/// ```ignore
+/// let mut scrobbler = AsyncScrobbler::new(api_key, api_secret);
+/// scrobbler.authenticate_with_password(username, password).await?;
+///
+/// let track = Scrobble::new("Los Campesinos!", "To Tundra", "No Blues");
+/// scrobbler.now_playing(&track).await?;
+/// scrobbler.scrobble(&track).await?;
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+pub struct AsyncScrobbler {
+    // Prepares and signs requests, and holds the session; never sends anything itself
+    scrobbler: Scrobbler,
+    transport: Arc<dyn AsyncTransport>,
+}
+
+impl AsyncScrobbler {
+    /// Creates a new `AsyncScrobbler` with the given Last.fm API key and API secret, which sends its requests
+    /// through an [`AsyncReqwestTransport`]. See [`Scrobbler::new`].
+    ///
+    /// [`AsyncReqwestTransport`]: struct.AsyncReqwestTransport.html
+    /// [`Scrobbler::new`]: struct.Scrobbler.html#method.new
+    pub fn new(api_key: &str, api_secret: &str) -> Self {
+        Self::with_transport(api_key, api_secret, AsyncReqwestTransport::new())
+    }
+
+    /// Creates a new `AsyncScrobbler` which sends its requests through the given [`AsyncTransport`]
+    ///
+    /// [`AsyncTransport`]: trait.AsyncTransport.html
+    pub fn with_transport<T>(api_key: &str, api_secret: &str, transport: T) -> Self
+    where
+        T: AsyncTransport + 'static,
+    {
+        Self {
+            scrobbler: Scrobbler::with_transport(api_key, api_secret, Unused),
+            transport: Arc::new(transport),
+        }
+    }
+
+    /// Creates a new `AsyncScrobbler` from a clone of `scrobbler`, sending its requests through the given
+    /// [`AsyncTransport`]. It shares the `Scrobbler`'s rate limit, so requests from both count towards it, and takes
+    /// on its session, retry policies and other settings.
+    ///
+    /// [`AsyncTransport`]: trait.AsyncTransport.html
+    pub fn from_scrobbler<T>(scrobbler: &Scrobbler, transport: T) -> Self
+    where
+        T: AsyncTransport + 'static,
+    {
+        Self {
+            scrobbler: scrobbler.clone(),
+            transport: Arc::new(transport),
+        }
+    }
+
+    /// Authenticates a Last.fm user with the given username and password. See
+    /// [`Scrobbler::authenticate_with_password`].
+    ///
+    /// [`Scrobbler::authenticate_with_password`]: struct.Scrobbler.html#method.authenticate_with_password
+    pub async fn authenticate_with_password(&mut self, username: &str, password: &str) -> Result<SessionResponse> {
+        let client = self.scrobbler.client_mut();
+        client.set_user_credentials(username, password);
+        client
+            .authenticate_async(self.transport.as_ref(), &ApiOperation::AuthMobileSession)
+            .await
+    }
+
+    /// Authenticates a Last.fm user with an authentication token. See [`Scrobbler::authenticate_with_token`].
+    ///
+    /// [`Scrobbler::authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
+    pub async fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse> {
+        let client = self.scrobbler.client_mut();
+        client.set_user_token(token);
+        client
+            .authenticate_async(self.transport.as_ref(), &ApiOperation::AuthWebSession)
+            .await
+    }
+
+    /// Authenticates a Last.fm user with a session key, which needs no request. See
+    /// [`Scrobbler::authenticate_with_session_key`].
+    ///
+    /// [`Scrobbler::authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+    pub fn authenticate_with_session_key(&mut self, session_key: &str) -> Result<()> {
+        self.scrobbler.authenticate_with_session_key(session_key)
+    }
+
+    /// Gets the session key the `AsyncScrobbler` is authenticated with, if any
+    pub fn session_key(&self) -> Option<&str> {
+        self.scrobbler.session_key()
+    }
+
+    /// Sets how old a scrobble may be, or disables the check with `None`. See [`Scrobbler::set_max_scrobble_age`].
+    ///
+    /// [`Scrobbler::set_max_scrobble_age`]: struct.Scrobbler.html#method.set_max_scrobble_age
+    pub fn set_max_scrobble_age(&mut self, max_age: Option<Duration>) {
+        self.scrobbler.set_max_scrobble_age(max_age);
+    }
+
+    /// Sets the policy for retrying Now Playing and Scrobble requests. See [`Scrobbler::set_retry_policy`].
+    ///
+    /// [`Scrobbler::set_retry_policy`]: struct.Scrobbler.html#method.set_retry_policy
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.scrobbler.set_retry_policy(retry_policy);
+    }
+
+    /// Sets the policy for retrying one class of request. See [`Scrobbler::set_retry_policy_for`].
+    ///
+    /// [`Scrobbler::set_retry_policy_for`]: struct.Scrobbler.html#method.set_retry_policy_for
+    pub fn set_retry_policy_for(&mut self, class: CallClass, retry_policy: RetryPolicy) {
+        self.scrobbler.set_retry_policy_for(class, retry_policy);
+    }
+
+    /// Limits the rate of API requests, or removes the limit with `None`. See [`Scrobbler::set_rate_limit`]. The
+    /// limit is shared with the `Scrobbler` the `AsyncScrobbler` was created from, if any.
+    ///
+    /// [`Scrobbler::set_rate_limit`]: struct.Scrobbler.html#method.set_rate_limit
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.scrobbler.set_rate_limit(rate_limit);
+    }
+
+    /// Registers the given track as the user's "now playing" track. See [`Scrobbler::now_playing`].
+    ///
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    pub async fn now_playing(&self, track: impl Into<NowPlaying>) -> Result<NowPlayingResponse> {
+        let track = track.into();
+        match self.scrobbler.now_playing_submission(&track)? {
+            Submission::DryRun(response) => Ok(response),
+            Submission::Send(params) => {
+                let client = self.scrobbler.client();
+                let response = client.send_now_playing_async(self.transport.as_ref(), params).await?;
+                Ok(response.with_expiry(track.as_scrobble()))
+            }
+        }
+    }
+
+    /// Registers a scrobble (play) of the given track. See [`Scrobbler::scrobble`].
+    ///
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    pub async fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse> {
+        let now = SystemTime::now();
+        let timestamp = Scrobbler::scrobble_timestamp(scrobble, now)?;
+        let (submission, _) = self.scrobbler.scrobble_submission(scrobble, timestamp, true, now)?;
+
+        match submission {
+            Submission::DryRun(response) => Ok(response),
+            Submission::Send(params) => {
+                let client = self.scrobbler.client();
+                let response = client.send_scrobble_async(self.transport.as_ref(), params).await?;
+                self.scrobbler.scrobble_sent(response, timestamp)
+            }
+        }
+    }
+
+    /// Registers a scrobble (play) of a batch of up to 50 tracks. See [`Scrobbler::scrobble_batch`].
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    pub async fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
+        Scrobbler::check_batch_size(batch)?;
+        let now = SystemTime::now();
+        let timestamps = Scrobbler::batch_timestamps(batch, now)?;
+        let (submission, preparation) = self.scrobbler.batch_submission(batch, &timestamps, true, now)?;
+
+        let mut response = match submission {
+            Submission::DryRun(response) => response,
+            Submission::Send(params) => {
+                let client = self.scrobbler.client();
+                let response = client.send_batch_scrobbles_async(self.transport.as_ref(), params).await?;
+                self.scrobbler.batch_sent(response, &timestamps)
+            }
+        };
+        response.preparation = preparation;
+
+        Ok(response)
+    }
+
+    /// Submits an import of any size in chunks of `chunk_size` scrobbles, one after another, returning a stream of
+    /// each chunk's result as it completes. See [`Scrobbler::scrobble_batch_chunks`].
+    ///
+    /// Each chunk is submitted as with [`AsyncScrobbler::scrobble_batch`]. A chunk which fails for transient
+    /// reasons is yielded with its error in [`ChunkResult::result`], and the import carries on with the next chunk.
+    /// The stream ends early with an `Err` item if the import can't be submitted at all, or once a chunk fails for
+    /// any other reason; that error's [`ScrobblerError::import_progress`] says how far the import got. It also ends
+    /// with the first chunk which hits Last.fm's daily scrobble limit, whose [`ChunkResult::daily_limit`] is set.
+    /// `chunk_size` must be between 1 and 50.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut chunks = pin!(scrobbler.scrobble_batch_stream(&history, 50));
+    /// while let Some(chunk) = chunks.next().await {
+    ///     let chunk = chunk?;
+    ///     if chunk.result.is_ok() {
+    ///         checkpoint(chunk.range.end);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+    /// [`AsyncScrobbler::scrobble_batch`]: struct.AsyncScrobbler.html#method.scrobble_batch
+    /// [`ChunkResult::result`]: struct.ChunkResult.html#structfield.result
+    /// [`ScrobblerError::import_progress`]: struct.ScrobblerError.html#method.import_progress
+    /// [`ChunkResult::daily_limit`]: struct.ChunkResult.html#structfield.daily_limit
+    pub fn scrobble_batch_stream<'a>(
+        &'a self,
+        batch: &ScrobbleBatch,
+        chunk_size: usize,
+    ) -> impl Stream<Item = Result<ChunkResult>> + Send + 'a {
+        let scrobbles: Vec<Scrobble> = batch.iter().cloned().collect();
+        let import = ChunkStream {
+            setup: self.scrobbler.check_chunked_import(batch, chunk_size).err(),
+            progress: ImportProgress::new(&scrobbles),
+            scrobbles,
+            chunk_size,
+            next: 0,
+            stopped: false,
+        };
+
+        stream::unfold(import, move |mut import| async move {
+            let item = import.next_chunk(self).await?;
+            Some((item, import))
+        })
+    }
+}
+
+// The state of an AsyncScrobbler::scrobble_batch_stream import, between chunks
+struct ChunkStream {
+    setup: Option<ScrobblerError>,
+    scrobbles: Vec<Scrobble>,
+    chunk_size: usize,
+    next: usize,
+    stopped: bool,
+    progress: ImportProgress,
+}
+
+impl ChunkStream {
+    // Submits the next chunk, returning `None` once the import is over
+    async fn next_chunk(&mut self, scrobbler: &AsyncScrobbler) -> Option<Result<ChunkResult>> {
+        if let Some(err) = self.setup.take() {
+            self.stopped = true;
+            return Some(Err(err));
+        }
+        if self.stopped || self.next >= self.scrobbles.len() {
+            return None;
+        }
+
+        let range = self.next..self.scrobbles.len().min(self.next + self.chunk_size);
+        self.next = range.end;
+        let batch = ScrobbleBatch::from(self.scrobbles[range.clone()].to_vec());
+        let (chunk, limited) = chunks::chunk_result(range, scrobbler.scrobble_batch(&batch).await);
+
+        // Stop, so that the caller can fix the problem and resume the import from the failed chunk
+        let mut chunk = match chunk.result {
+            Err(err) if !err.is_retryable() => {
+                self.stopped = true;
+                let stopped = format!(
+                    "Chunked batch stopped after {} of {} scrobbles were submitted",
+                    self.progress.completed(),
+                    self.scrobbles.len()
+                );
+                return Some(Err(err.context(&stopped).with_import_progress(self.progress.clone())));
+            }
+            result => ChunkResult { result, ..chunk },
+        };
+
+        // Later chunks would only be ignored too
+        if let Some((count, first)) = limited {
+            self.stopped = true;
+            let remaining = count + self.scrobbles.len() - chunk.range.end;
+            chunk.daily_limit = Some(DailyLimitReached::new(remaining, SystemTime::now()));
+            self.progress.advance(&chunk.range, first);
+        } else if chunk.result.is_ok() {
+            self.progress.advance(&chunk.range, chunk.range.end);
+        }
+
+        Some(Ok(chunk))
+    }
+}
+
+// The blocking transport of an AsyncScrobbler's inner Scrobbler, which only prepares requests
+struct Unused;
+
+impl Transport for Unused {
+    fn send(&self, _: &HttpRequest) -> Result<HttpResponse> {
+        Err(ScrobblerError::transport("An AsyncScrobbler sends requests asynchronously".to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fake;
+    use mockito::mock;
+
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    // Runs a future to completion on the current thread, which an executor-agnostic client must allow
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    // Responds to each request with the next of its responses, in turn, recording the requests' methods
+    #[derive(Default)]
+    struct Scripted {
+        responses: std::sync::Mutex<Vec<HttpResponse>>,
+        methods: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl Scripted {
+        fn respond(self, status: u16, body: &impl ToString) -> Self {
+            let response = HttpResponse {
+                status,
+                body: body.to_string(),
+                headers: crate::headers::Headers::default(),
+            };
+            self.responses.lock().unwrap().push(response);
+            self
+        }
+    }
+
+    impl AsyncTransport for Scripted {
+        fn send(&self, request: HttpRequest) -> crate::async_transport::ResponseFuture {
+            let method = request.params.get("method").unwrap_or_default().to_owned();
+            self.methods.lock().unwrap().push(method);
+            let response = self.responses.lock().unwrap().remove(0);
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    // The output of a `tracing` subscriber, for checking the events of calls
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn check_async_scrobbler_authentication() {
+        let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::UrlEncoded("method".into(), "auth.getMobileSession".into()))
+            .with_body(fake::session_response("floyd").to_string())
+            .create();
+        let session = block_on(scrobbler.authenticate_with_password("floyd", "hunter2")).unwrap();
+        assert_eq!(session.name(), "floyd");
+        assert_eq!(scrobbler.session_key(), Some(session.key()));
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::UrlEncoded("method".into(), "auth.getSession".into()))
+            .with_status(403)
+            .with_body(fake::error_response(4, "Invalid authentication token supplied").to_string())
+            .create();
+        let err = block_on(scrobbler.authenticate_with_token("token")).unwrap_err();
+        assert_eq!(err.api_code(), Some(4));
+        assert!(err.to_string().starts_with("Authentication failed: "));
+        assert_eq!(scrobbler.session_key(), None);
+    }
+
+    #[test]
+    fn check_async_scrobbler_scrobble() {
+        let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        let mut track = Scrobble::new("floyd", "bananas", "old");
+        track.with_timestamp(1_500_000_000);
+
+        // Not authenticated, so nothing is sent
+        let err = block_on(scrobbler.scrobble(&track)).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::NotAuthenticated);
+
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "track.updateNowPlaying".into()),
+                mockito::Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+            ]))
+            .with_body(fake::now_playing_response("floyd", "bananas").to_string())
+            .create();
+        let response = block_on(scrobbler.now_playing(&track)).unwrap();
+        assert_eq!(response.track().text(), "bananas");
+
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "track.scrobble".into()),
+                mockito::Matcher::UrlEncoded("timestamp".into(), "1500000000".into()),
+            ]))
+            .with_body(fake::scrobble_response("floyd", "bananas").to_string())
+            .create();
+        // Calls can be spawned on multithreaded executors
+        assert_send(&scrobbler.scrobble(&track));
+
+        let response = block_on(scrobbler.scrobble(&track)).unwrap();
+        assert!(response.accepted());
+        assert_eq!(response.submitted_timestamp(), 1_500_000_000);
+        m.assert();
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn check_async_scrobbler_tracing() {
+        use crate::models::responses::IgnoredReason;
+        use crate::test_util::EchoTransport;
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .without_time()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let track = Scrobble::new("floyd", "bananas", "old");
+        let (blocking, sent) = tracing::subscriber::with_default(subscriber, || {
+            let mut scrobbler =
+                Scrobbler::with_transport("api_key", "api_secret", EchoTransport::new().with_ignored_artist("floyd"));
+            scrobbler.authenticate_with_session_key("seshkey").unwrap();
+            let blocking = scrobbler.scrobble(&track).unwrap();
+
+            let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+            scrobbler.authenticate_with_session_key("seshkey").unwrap();
+            let ignored = fake::scrobble_response("floyd", "bananas").ignored(IgnoredReason::ArtistIgnored);
+            let _m = mock("POST", mockito::Matcher::Any).with_body(ignored.to_string()).create();
+            (blocking, block_on(scrobbler.scrobble(&track)).unwrap())
+        });
+
+        // Both calls are traced alike, in a span with their correlation IDs
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        for response in &[blocking, sent] {
+            let id = response.correlation_id().unwrap();
+            let span = format!("lastfm_call{{method=\"track.scrobble\" correlation_id=\"{id}\"}}");
+            let events: Vec<&str> = output.lines().filter(|line| line.contains(id)).collect();
+            assert_eq!(events.len(), 3, "{output}");
+            assert!(events[0].contains(&span) && events[0].contains("Last.fm API request attempt"), "{}", output);
+            assert!(events[1].contains(&span) && events[1].contains("Last.fm API call finished"), "{}", output);
+            assert!(events[1].contains("status=200 outcome=Success attempts=1"), "{}", output);
+            assert!(events[2].contains("Last.fm ignored a scrobble") && events[2].contains(" code=1 "), "{}", output);
+        }
+    }
+
+    #[test]
+    fn check_async_scrobbler_rate_limit_and_retries() {
+        use crate::test_util::EchoTransport;
+
+        let mut blocking = Scrobbler::with_transport("api_key", "api_secret", EchoTransport::new());
+        blocking.authenticate_with_session_key("seshkey").unwrap();
+        blocking.set_rate_limit(Some(RateLimit {
+            requests: 2,
+            per: Duration::from_millis(400),
+        }));
+        let transport = Scripted::default()
+            .respond(200, &fake::now_playing_response("floyd", "bananas"))
+            .respond(503, &"Service Unavailable")
+            .respond(200, &fake::scrobble_response("floyd", "bananas"));
+        let methods = Arc::clone(&transport.methods);
+        let mut scrobbler = AsyncScrobbler::from_scrobbler(&blocking, transport);
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        });
+
+        // The budget is shared with the blocking Scrobbler, and now playing updates count towards it
+        let track = Scrobble::new("floyd", "bananas", "old");
+        blocking.scrobble(&track).unwrap();
+        block_on(scrobbler.now_playing(&track)).unwrap();
+        assert!(blocking.rate_limit_utilization().unwrap() > 0.9);
+
+        // So the scrobble waits for a token, without blocking the thread polling it
+        let started = std::time::Instant::now();
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut abandoned = Box::pin(scrobbler.scrobble(&track));
+        assert!(abandoned.as_mut().poll(&mut cx).is_pending());
+        assert!(blocking.rate_limit_utilization().unwrap() > 1.0);
+        // A wait which is given up on leaves the queue
+        drop(abandoned);
+        assert!(blocking.rate_limit_utilization().unwrap() <= 1.0);
+
+        let mut scrobble = pin!(scrobbler.scrobble(&track));
+        assert!(scrobble.as_mut().poll(&mut cx).is_pending());
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        // ...and is retried once it fails transiently
+        let response = block_on(scrobble).unwrap();
+        assert!(response.accepted());
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        let methods = methods.lock().unwrap().clone();
+        assert_eq!(methods, ["track.updateNowPlaying", "track.scrobble", "track.scrobble"]);
+    }
+
+    #[test]
+    fn check_async_scrobbler_batch() {
+        let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let batch = ScrobbleBatch::from(vec![
+            ("floyd", "bananas", "old"),
+            ("floyd", "apples", "old"),
+        ]);
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("track[0]".into(), "bananas".into()),
+                mockito::Matcher::UrlEncoded("track[1]".into(), "apples".into()),
+            ]))
+            .with_body(fake::batch_response(1, 1).to_string())
+            .create();
+        let response = block_on(scrobbler.scrobble_batch(&batch)).unwrap();
+        assert_eq!((response.accepted_count(), response.ignored_count()), (1, 1));
+        // Untimed, so each was submitted as played now
+        assert!(response.scrobbles().iter().all(|scrobble| scrobble.submitted_timestamp() > 1_500_000_000));
+        assert!(!response.preparation().is_empty());
+        m.assert();
+
+        let empty = ScrobbleBatch::from(Vec::<Scrobble>::new());
+        assert_eq!(block_on(scrobbler.scrobble_batch(&empty)).unwrap_err().to_string(), "Scrobble batch is empty");
+    }
+
+    #[test]
+    fn check_async_scrobbler_batch_stream() {
+        use futures_util::StreamExt;
+
+        let mut scrobbler = AsyncScrobbler::new("api_key", "api_secret");
+        let batch = ScrobbleBatch::from(vec![
+            ("stream", "first", "old"),
+            ("stream", "second", "old"),
+            ("stream", "third", "old"),
+            ("stream", "fourth", "old"),
+            ("stream", "fifth", "old"),
+        ]);
+
+        // Not authenticated, so the only item is the error
+        let items: Vec<_> = block_on(scrobbler.scrobble_batch_stream(&batch, 2).collect());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].as_ref().unwrap_err().kind(), crate::error::ErrorKind::NotAuthenticated);
+
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let chunk = |track: &str| mockito::Matcher::UrlEncoded("track[0]".into(), track.into());
+        let first = mock("POST", mockito::Matcher::Any)
+            .match_body(chunk("first"))
+            .with_body(fake::batch_response(2, 0).to_string())
+            .create();
+        let failed = mock("POST", mockito::Matcher::Any)
+            .match_body(chunk("third"))
+            .with_status(400)
+            .with_body(fake::error_response(6, "Invalid parameters").to_string())
+            .create();
+        let last = mock("POST", mockito::Matcher::Any).match_body(chunk("fifth")).expect(0).create();
+
+        let chunks = scrobbler.scrobble_batch_stream(&batch, 2);
+        assert_send(&chunks);
+        let items: Vec<_> = block_on(chunks.collect());
+
+        // The failed middle chunk ends the stream, so the last one is never sent
+        assert_eq!(items.len(), 2);
+        let chunk = items[0].as_ref().unwrap();
+        assert_eq!(chunk.range, 0..2);
+        assert_eq!(chunk.result.as_ref().unwrap().accepted_count(), 2);
+        let err = items[1].as_ref().unwrap_err();
+        assert_eq!(err.api_code(), Some(6));
+        assert_eq!(err.import_progress().unwrap().completed(), 2);
+        assert!(err.to_string().starts_with("Chunked batch stopped after 2 of 5 scrobbles were submitted"), "{}", err);
+        first.assert();
+        failed.assert();
+        last.assert();
+
+        let items: Vec<_> = block_on(scrobbler.scrobble_batch_stream(&batch, 51).collect());
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+}
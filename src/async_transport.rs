@@ -0,0 +1,272 @@
+// Asynchronous HTTP transport used by the AsyncScrobbler to deliver Last.fm API requests
+use crate::error::ScrobblerError;
+use crate::headers::Headers;
+use crate::transport::{
+    HttpMethod, HttpRequest, HttpResponse, TransportInfo, REQWEST_TLS_BACKEND, REQWEST_USER_AGENT,
+};
+
+use futures::future::{self as future01, Either};
+use futures::{Future as _, Stream as _};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::r#async::Client;
+use reqwest::RedirectPolicy;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+/// The future an [`AsyncTransport`] responds with
+///
+/// [`AsyncTransport`]: trait.AsyncTransport.html
+pub type ResponseFuture = Pin<Box<dyn Future<Output = Result<HttpResponse, ScrobblerError>> + Send>>;
+
+/// Sends Last.fm API requests over HTTP without blocking, for an [`AsyncScrobbler`]. Requires the `async` feature.
+///
+/// The asynchronous counterpart of [`Transport`], with the same contract: implementations return `Err` only when
+/// no response could be obtained at all. An `AsyncScrobbler` uses [`AsyncReqwestTransport`] unless given a
+/// different transport with [`AsyncScrobbler::with_transport`].
+///
+/// [`AsyncScrobbler`]: struct.AsyncScrobbler.html
+/// [`Transport`]: trait.Transport.html
+/// [`AsyncReqwestTransport`]: struct.AsyncReqwestTransport.html
+/// [`AsyncScrobbler::with_transport`]: struct.AsyncScrobbler.html#method.with_transport
+pub trait AsyncTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> ResponseFuture;
+
+    /// Describes the transport's HTTP configuration, for diagnostics. By default nothing is known about it.
+    fn info(&self) -> TransportInfo {
+        TransportInfo::default()
+    }
+}
+
+/// The default [`AsyncTransport`], backed by `reqwest`'s async client. Requires the `async` feature.
+///
+/// `reqwest`'s async client runs on a `tokio` 0.1 runtime, so the transport runs its own, on a background thread,
+/// and hands each response back to whichever executor awaits it. The caller's executor is never blocked, and
+/// needn't be a `tokio` 0.1 runtime.
+///
+/// [`AsyncTransport`]: trait.AsyncTransport.html
+pub struct AsyncReqwestTransport {
+    client: Client,
+    runtime: Runtime,
+}
+
+impl AsyncReqwestTransport {
+    /// Creates a transport, whose client doesn't follow redirects, and starts its runtime
+    ///
+    /// # Panics
+    /// Panics if the runtime's thread can't be started, as `reqwest`'s own blocking client does
+    pub fn new() -> Self {
+        let runtime = Builder::new()
+            .core_threads(1)
+            .name_prefix("rustfm-scrobble-")
+            .build()
+            .expect("failed to start the HTTP runtime");
+
+        Self {
+            client: Client::builder()
+                .redirect(RedirectPolicy::none())
+                .build()
+                .expect("failed to build the HTTP client"),
+            runtime,
+        }
+    }
+}
+
+impl Default for AsyncReqwestTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsyncTransport for AsyncReqwestTransport {
+    fn send(&self, request: HttpRequest) -> ResponseFuture {
+        let mut builder = match request.http_method {
+            HttpMethod::Get => self.client.get(&request.query_url()),
+            HttpMethod::Post => match &request.json_body {
+                Some(json) => self
+                    .client
+                    .post(&request.url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(json.clone()),
+                None => self
+                    .client
+                    .post(&request.url)
+                    .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(request.params.to_form_body()),
+            },
+        };
+        if let Some(request_id) = &request.request_id {
+            builder = builder.header("X-Request-Id", request_id.as_str());
+        }
+        for (name, value) in request.headers.iter() {
+            builder = builder.header(name, value);
+        }
+
+        // Refuse to buffer a body declared too large, and stop reading one which turns out to be
+        let limit = request.max_response_size;
+        let call = builder
+            .send()
+            .map_err(|err| ScrobblerError::new(err.to_string()))
+            .and_then(move |resp| {
+                let status = resp.status().as_u16();
+                let length = resp.content_length();
+                let headers = Headers::from_response(resp.headers());
+                if let Some(length) = length.filter(|length| *length > limit) {
+                    return Either::A(future01::err(ScrobblerError::response_too_large(limit, Some(length))));
+                }
+
+                let body = resp
+                    .into_body()
+                    .map_err(|_| read_failed())
+                    .fold(Vec::new(), move |mut bytes, chunk| {
+                        bytes.extend_from_slice(&chunk);
+                        if bytes.len() as u64 > limit {
+                            return Err(ScrobblerError::response_too_large(limit, length));
+                        }
+                        Ok(bytes)
+                    })
+                    .and_then(move |bytes| {
+                        let body = String::from_utf8(bytes).map_err(|_| read_failed())?;
+                        Ok(HttpResponse { status, body, headers })
+                    });
+                Either::B(body)
+            });
+
+        let (completion, pending) = channel();
+        self.runtime.executor().spawn(call.then(move |result| {
+            completion.complete(result);
+            Ok(())
+        }));
+
+        Box::pin(pending)
+    }
+
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            user_agent: Some(REQWEST_USER_AGENT.to_owned()),
+            proxy: None,
+            tls_backend: Some(REQWEST_TLS_BACKEND.to_owned()),
+        }
+    }
+}
+
+fn read_failed() -> ScrobblerError {
+    ScrobblerError::new("Failed to read response body".to_string())
+}
+
+// Hands the result of a request sent on the transport's runtime to the future awaiting it
+#[derive(Default)]
+struct Slot {
+    result: Option<Result<HttpResponse, ScrobblerError>>,
+    completed: bool,
+    waker: Option<Waker>,
+}
+
+fn channel() -> (Completion, Pending) {
+    let slot = Arc::new(Mutex::new(Slot::default()));
+    (Completion(Arc::clone(&slot)), Pending(slot))
+}
+
+fn lock(slot: &Mutex<Slot>) -> std::sync::MutexGuard<'_, Slot> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct Completion(Arc<Mutex<Slot>>);
+
+impl Completion {
+    fn complete(self, result: Result<HttpResponse, ScrobblerError>) {
+        self.fill(result);
+    }
+
+    fn fill(&self, result: Result<HttpResponse, ScrobblerError>) {
+        let mut slot = lock(&self.0);
+        if slot.completed {
+            return;
+        }
+        slot.result = Some(result);
+        slot.completed = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// A request dropped by the runtime, ie. as it shuts down, fails rather than leaving its caller waiting forever
+impl Drop for Completion {
+    fn drop(&mut self) {
+        self.fill(Err(ScrobblerError::transport(
+            "The HTTP runtime stopped before the request completed".to_owned(),
+        )));
+    }
+}
+
+struct Pending(Arc<Mutex<Slot>>);
+
+impl Future for Pending {
+    type Output = Result<HttpResponse, ScrobblerError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = lock(&self.0);
+        if let Some(result) = slot.result.take() {
+            return Poll::Ready(result);
+        }
+
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+// Waits out `duration` without blocking the thread polling it, whichever executor that is: a timer thread wakes the
+// task once the time is up. Used between the attempts of a call, and while waiting for the rate limit.
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    let timer = Timer {
+        elapsed: duration.is_zero(),
+        waker: None,
+    };
+    Sleep {
+        timer: Arc::new(Mutex::new(timer)),
+        duration: Some(duration),
+    }
+}
+
+struct Timer {
+    elapsed: bool,
+    waker: Option<Waker>,
+}
+
+pub(crate) struct Sleep {
+    timer: Arc<Mutex<Timer>>,
+    // Until the timer thread is started, on the first poll
+    duration: Option<Duration>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut timer = self.timer.lock().unwrap_or_else(PoisonError::into_inner);
+        if timer.elapsed {
+            return Poll::Ready(());
+        }
+        timer.waker = Some(cx.waker().clone());
+        drop(timer);
+
+        let Some(duration) = self.duration.take() else {
+            return Poll::Pending;
+        };
+        let timer = Arc::clone(&self.timer);
+        thread::spawn(move || {
+            thread::sleep(duration);
+            let mut timer = timer.lock().unwrap_or_else(PoisonError::into_inner);
+            timer.elapsed = true;
+            if let Some(waker) = timer.waker.take() {
+                waker.wake();
+            }
+        });
+        Poll::Pending
+    }
+}
@@ -0,0 +1,215 @@
+// Bounded, in-memory log of recent API calls, for debugging
+use crate::codes::LastFmErrorCode;
+use crate::events::RequestOutcome;
+use crate::params::Params;
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::str;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// The outcome of an API call recorded in the call log. Unlike [`RequestOutcome`], transport errors are recorded
+/// without their message, so that recording never allocates.
+///
+/// [`RequestOutcome`]: enum.RequestOutcome.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallOutcome {
+    /// The API responded successfully
+    Success,
+    /// The API responded with the given Last.fm error code
+    ApiError(LastFmErrorCode),
+    /// The server responded with the given non-success HTTP status, and no Last.fm error
+    HttpStatus(u16),
+    /// No response was received
+    TransportError,
+}
+
+impl From<&RequestOutcome> for CallOutcome {
+    fn from(outcome: &RequestOutcome) -> Self {
+        match outcome {
+            RequestOutcome::Success => Self::Success,
+            RequestOutcome::ApiError(code) => Self::ApiError(*code),
+            RequestOutcome::HttpStatus(status) => Self::HttpStatus(*status),
+            RequestOutcome::TransportError(_) => Self::TransportError,
+        }
+    }
+}
+
+/// One API call recorded in a `Scrobbler`'s call log. See [`Scrobbler::set_call_log_capacity`].
+///
+/// Only a summary of the request's parameters is kept: how many there were, and how many tracks they described.
+/// No parameter values are recorded, so the log never holds secrets or listening history. The exception is the
+/// random `nonce` sent when enabled with [`Scrobbler::set_include_nonce`].
+///
+/// [`Scrobbler::set_call_log_capacity`]: struct.Scrobbler.html#method.set_call_log_capacity
+/// [`Scrobbler::set_include_nonce`]: struct.Scrobbler.html#method.set_include_nonce
+/// [`Scrobbler::call_signed`]: struct.Scrobbler.html#method.call_signed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallRecord {
+    /// When the call was started
+    pub started: SystemTime,
+    /// The API method called, ie. `"track.scrobble"`. The names of methods called with [`Scrobbler::call_signed`]
+    /// are kept for the life of the process, once each, so that records stay `Copy`.
+    pub method: &'static str,
+    /// The number of parameters sent, including the signature
+    pub param_count: usize,
+    /// The number of tracks submitted, loved or unloved; 0 for authentication calls
+    pub track_count: usize,
+    /// The number of attempts made, including any retries
+    pub attempts: u32,
+    /// The outcome of the last attempt
+    pub outcome: CallOutcome,
+    /// The time taken, including any retry delays
+    pub latency: Duration,
+    correlation_id: [u8; 8],
+    nonce: Option<[u8; NONCE_LEN]>,
+}
+
+// The length of a UUID, as sent as a request's `nonce`
+const NONCE_LEN: usize = 36;
+
+// Every method name recorded so far. Few methods are ever called, so a list is searched faster than it's hashed.
+static METHODS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+// The name of a method as kept by records, allocated only the first time it's called
+fn intern(method: &str) -> &'static str {
+    let mut methods = METHODS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(interned) = methods.iter().find(|interned| **interned == method) {
+        return interned;
+    }
+
+    let interned: &'static str = Box::leak(method.into());
+    methods.push(interned);
+    interned
+}
+
+impl CallRecord {
+    pub(crate) fn new(
+        started: SystemTime,
+        method: &str,
+        correlation_id: &str,
+        params: &Params,
+        attempts: u32,
+        outcome: &RequestOutcome,
+        latency: Duration,
+    ) -> Self {
+        let mut id = [b'0'; 8];
+        for (byte, id_byte) in correlation_id.bytes().zip(&mut id) {
+            *id_byte = byte;
+        }
+        let nonce = params.get("nonce").and_then(|nonce| <[u8; NONCE_LEN]>::try_from(nonce.as_bytes()).ok());
+
+        Self {
+            started,
+            method: intern(method),
+            param_count: params.len(),
+            // "track", or "track[0]", "track[1]"... in a batch
+            track_count: params.iter().filter(|(key, _)| key.starts_with("track")).count(),
+            attempts,
+            outcome: outcome.into(),
+            latency,
+            correlation_id: id,
+            nonce,
+        }
+    }
+
+    /// Returns the call's correlation ID, as attached to errors and sent in `X-Request-Id` headers
+    pub fn correlation_id(&self) -> &str {
+        str::from_utf8(&self.correlation_id).unwrap_or_default()
+    }
+
+    /// Returns the `nonce` the call was sent with, if any. See [`Scrobbler::set_include_nonce`].
+    ///
+    /// [`Scrobbler::set_include_nonce`]: struct.Scrobbler.html#method.set_include_nonce
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_ref().and_then(|nonce| str::from_utf8(nonce).ok())
+    }
+}
+
+// A ring buffer of the most recent calls, shared by all the handles to one logical client. Storage is allocated
+// up front when the capacity is set, so recording a call never allocates.
+#[derive(Debug, Default)]
+pub(crate) struct CallLog {
+    ring: Mutex<Ring>,
+}
+
+#[derive(Debug, Default)]
+struct Ring {
+    capacity: usize,
+    records: VecDeque<CallRecord>,
+}
+
+impl CallLog {
+    // Setting a capacity of 0 disables the log. Any records already held are discarded.
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        *self.lock() = Ring {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+        };
+    }
+
+    pub(crate) fn record(&self, record: CallRecord) {
+        let mut ring = self.lock();
+        if ring.capacity == 0 {
+            return;
+        }
+        if ring.records.len() == ring.capacity {
+            ring.records.pop_front();
+        }
+        ring.records.push_back(record);
+    }
+
+    pub(crate) fn records(&self) -> Vec<CallRecord> {
+        self.lock().records.iter().copied().collect()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.lock().records.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Ring> {
+        self.ring.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(latency_ms: u64) -> CallRecord {
+        CallRecord::new(
+            SystemTime::now(),
+            "track.scrobble",
+            "0badf00d",
+            &Params::new(),
+            1,
+            &RequestOutcome::Success,
+            Duration::from_millis(latency_ms),
+        )
+    }
+
+    #[test]
+    fn check_call_log_evicts_oldest() {
+        let log = CallLog::default();
+        log.record(record(0));
+        assert!(log.records().is_empty());
+
+        log.set_capacity(3);
+        for latency_ms in 1..=5 {
+            log.record(record(latency_ms));
+        }
+        let latencies: Vec<_> = log.records().iter().map(|record| record.latency.as_millis()).collect();
+        assert_eq!(latencies, vec![3, 4, 5]);
+        assert_eq!(log.records()[0].correlation_id(), "0badf00d");
+
+        log.clear();
+        assert!(log.records().is_empty());
+        log.record(record(6));
+        assert_eq!(log.records().len(), 1);
+
+        log.set_capacity(0);
+        log.record(record(7));
+        assert!(log.records().is_empty());
+    }
+}
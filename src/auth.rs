@@ -1,17 +1,24 @@
 // Authentication utilities for Last.fm Scrobble API 2.0
-use std::collections::HashMap;
+use crate::keys::{ApiKey, ApiSecret, SessionKey};
+use crate::params::Params;
+use crate::signing::{self, RequestSigner};
 
-#[derive(PartialEq, Debug)]
+#[allow(clippy::struct_field_names)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Credentials {
     // Application specific key & secret
-    api_key: String,
-    api_secret: String,
+    api_key: ApiKey,
+    api_secret: ApiSecret,
 
     // Individual user's username & pass, or auth token
     credentials: Option<CredentialsVariant>,
 
     // Long-lasting session key (used once UserCredentials are authenticated)
-    session_key: Option<String>,
+    session_key: Option<SessionKey>,
+
+    // Parameters common to every authenticated request (api_key & sk). Rebuilt whenever the session key changes,
+    // so requests only need to add their method-specific parameters.
+    request_params: Params,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -36,15 +43,21 @@ impl UserCredentials {
 }
 
 impl Credentials {
-    pub fn new_partial(api_key: &str, api_secret: &str) -> Self {
+    pub fn new_partial(api_key: impl Into<ApiKey>, api_secret: impl Into<ApiSecret>) -> Self {
+        let api_key = api_key.into();
         Self {
-            api_key: api_key.to_owned(),
-            api_secret: api_secret.to_owned(),
+            request_params: Self::build_request_params(api_key.as_str(), None),
+            api_key,
+            api_secret: api_secret.into(),
             credentials: None,
             session_key: None,
         }
     }
 
+    pub fn api_key(&self) -> &str {
+        self.api_key.as_str()
+    }
+
     pub fn set_user_credentials(&mut self, username: &str, password: &str) {
         self.credentials = Some(CredentialsVariant::UserSupplied(UserCredentials {
             username: username.to_owned(),
@@ -52,26 +65,29 @@ impl Credentials {
         }));
 
         // Invalidate session because we have new credentials
-        self.clear_session_key()
+        self.clear_session_key();
     }
 
     pub fn set_user_token(&mut self, token: &str) {
         self.credentials = Some(CredentialsVariant::Token(token.to_owned()));
-        self.clear_session_key()
+        self.clear_session_key();
     }
 
     // Invalidates session. Usually because we have new user token / credentials, which invalidates
     // the current session.
     fn clear_session_key(&mut self) {
-        self.session_key = None
+        self.session_key = None;
+        self.request_params = Self::build_request_params(self.api_key.as_str(), None);
     }
 
-    pub fn set_session_key(&mut self, key: &str) {
-        self.session_key = Some(key.to_owned());
+    pub fn set_session_key(&mut self, key: impl Into<SessionKey>) {
+        let key = key.into();
+        self.request_params = Self::build_request_params(self.api_key.as_str(), Some(key.expose()));
+        self.session_key = Some(key);
     }
 
     pub fn session_key(&self) -> Option<&str> {
-        self.session_key.as_ref().map(std::ops::Deref::deref)
+        self.session_key.as_ref().map(SessionKey::expose)
     }
 
     // Returns true if we are currently authenticated (have a valid session token set)
@@ -79,65 +95,55 @@ impl Credentials {
         self.session_key.is_some()
     }
 
-    pub fn get_auth_request_params(&self) -> Result<HashMap<String, String>, String> {
+    pub fn get_auth_request_params(&self) -> Result<Params, String> {
         let credentials = self
             .credentials
             .as_ref()
             .ok_or("No user credentials available")?;
 
-        if self.api_key.is_empty() || self.api_secret.is_empty() {
+        if self.api_key.as_str().is_empty() || self.api_secret.expose().is_empty() {
             return Err("Invalid authentication parameters".to_string());
         }
 
-        let mut params = HashMap::new();
-        params.insert("api_key".to_string(), self.api_key.clone());
+        let mut params = Params::new();
+        params.insert_api_key(self.api_key.as_str());
 
         match credentials {
             CredentialsVariant::UserSupplied(user_credentials) => {
                 if !user_credentials.can_authenticate() {
                     return Err("Invalid authentication credentials".to_string());
                 }
-                params.insert("username".to_string(), user_credentials.username.clone());
-                params.insert("password".to_string(), user_credentials.password.clone());
+                params.insert_username(user_credentials.username.clone());
+                params.insert_password(user_credentials.password.clone());
             }
             CredentialsVariant::Token(token) => {
-                params.insert("token".to_string(), token.clone());
+                params.insert_token(token.clone());
             }
         }
 
         Ok(params)
     }
 
-    pub fn get_request_params(&self) -> HashMap<String, String> {
-        let mut params = HashMap::new();
-        params.insert("api_key".to_string(), self.api_key.clone());
-        params.insert(
-            "sk".to_string(),
-            self.session_key.clone().unwrap_or_default(),
-        );
-
-        params
+    pub fn get_request_params(&self) -> &Params {
+        &self.request_params
     }
 
-    pub fn get_signature(&self, method: String, params: &HashMap<String, String>) -> String {
-        let mut sig_params = params.clone();
-        sig_params.insert("method".to_string(), method);
-
-        let mut keys = Vec::new();
-        for k in sig_params.keys() {
-            keys.push(k);
-        }
-
-        keys.sort();
+    fn build_request_params(api_key: &str, session_key: Option<&str>) -> Params {
+        let mut params = Params::new();
+        params.insert_api_key(api_key);
+        params.insert_session_key(session_key.unwrap_or_default());
 
-        let mut sig = String::new();
-        for k in keys {
-            sig.push_str((k.to_string() + sig_params[k].as_str()).as_str())
-        }
+        params
+    }
 
-        sig.push_str(self.api_secret.as_str());
+    // A standalone `LastFmReader` has no API secret, so can't sign requests
+    pub fn has_api_secret(&self) -> bool {
+        !self.api_secret.expose().is_empty()
+    }
 
-        format!("{:x}", md5::compute(sig.as_bytes()))
+    // Computes the `api_sig` for a request, signed with the application's API secret
+    pub fn get_signature(&self, signer: &dyn RequestSigner, method: &str, params: &Params) -> String {
+        signing::sign(signer, method, params, self.api_secret.expose())
     }
 }
 
@@ -148,8 +154,8 @@ mod tests {
     #[test]
     fn check_user_credentials() {
         let empty = UserCredentials {
-            username: "".into(),
-            password: "".into(),
+            username: String::new(),
+            password: String::new(),
         };
 
         assert!(!UserCredentials::can_authenticate(&empty));
@@ -169,6 +175,7 @@ mod tests {
             api_secret: "Secret".into(),
             credentials: None,
             session_key: None,
+            request_params: Credentials::build_request_params("Key", None),
         };
         let rhs = Credentials::new_partial("Key", "Secret");
 
@@ -177,14 +184,13 @@ mod tests {
 
     #[test]
     fn check_set_user_creds() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_user_credentials("Username".into(), "Password".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_user_credentials("Username", "Password");
 
         let internal_creds = auth_creds.credentials.unwrap();
 
-        let creds = match internal_creds {
-            CredentialsVariant::UserSupplied(val) => val,
-            _ => panic!("Invalid UserCredentials Value"),
+        let CredentialsVariant::UserSupplied(creds) = internal_creds else {
+            panic!("Invalid UserCredentials Value")
         };
 
         assert_eq!(creds.username, "Username");
@@ -193,14 +199,13 @@ mod tests {
 
     #[test]
     fn check_set_user_token() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_user_token("Token".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_user_token("Token");
 
         let token = auth_creds.credentials.unwrap();
 
-        let token = match token {
-            CredentialsVariant::Token(val) => val,
-            _ => panic!("Invalid Token"),
+        let CredentialsVariant::Token(token) = token else {
+            panic!("Invalid Token")
         };
 
         assert_eq!(token, "Token");
@@ -208,8 +213,8 @@ mod tests {
 
     #[test]
     fn check_set_session_key_and_is_authed() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_session_key("SomeKey".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
         let key = auth_creds.session_key().unwrap();
 
         assert_eq!(key, "SomeKey");
@@ -218,13 +223,13 @@ mod tests {
 
     #[test]
     fn check_auth_req_params_and_get_signature() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_user_token("Token".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_user_token("Token");
         let param_map = auth_creds.get_auth_request_params().unwrap();
 
         assert_eq!(param_map["token"], "Token");
 
-        auth_creds.set_user_credentials("Foo".into(), "Bar".into());
+        auth_creds.set_user_credentials("Foo", "Bar");
         let param_map = auth_creds.get_auth_request_params().unwrap();
 
         assert_eq!(param_map["username"], "Foo");
@@ -232,7 +237,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "No user credentials available")]
     fn check_get_bad_params() {
         let auth_creds = Credentials::new_partial("Key", "Secret");
         auth_creds.get_auth_request_params().unwrap();
@@ -240,11 +245,59 @@ mod tests {
 
     #[test]
     fn check_req_params() {
-        let mut auth_creds = Credentials::new_partial("Key".into(), "Secret".into());
-        auth_creds.set_session_key("SomeKey".into());
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
         let req_params = auth_creds.get_request_params();
 
-        assert_eq!(req_params["api_key".into()], "Key");
-        assert_eq!(req_params["sk".into()], "SomeKey");
+        assert_eq!(req_params["api_key"], "Key");
+        assert_eq!(req_params["sk"], "SomeKey");
+    }
+
+    #[test]
+    fn check_signature() {
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
+
+        let mut params = auth_creds.get_request_params().clone();
+        params.insert("track", "old bananas");
+        params.insert("timestamp", "1337");
+
+        let sig = auth_creds.get_signature(&signing::Md5Signer, "track.scrobble", &params);
+        assert_eq!(sig, "57c76934af208efd3636f20c52fa3d6e");
+    }
+
+    #[test]
+    fn check_signature_multibyte_values() {
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        auth_creds.set_session_key("SomeKey");
+
+        let mut params = auth_creds.get_request_params().clone();
+        params.insert("artist", "Björk");
+        params.insert("track", "Jóga 🎵");
+        params.insert("album", "目覚めぬ夢");
+
+        let sig = auth_creds.get_signature(&signing::Md5Signer, "track.updateNowPlaying", &params);
+        assert_eq!(sig, "c62eb0701213172ad428633215d2c908");
+    }
+
+    #[test]
+    fn check_req_params_invalidated() {
+        let mut auth_creds = Credentials::new_partial("Key", "Secret");
+        assert_eq!(auth_creds.get_request_params()["sk"], "");
+
+        auth_creds.set_session_key("SomeKey");
+        assert_eq!(auth_creds.get_request_params()["sk"], "SomeKey");
+
+        auth_creds.set_session_key("OtherKey");
+        assert_eq!(auth_creds.get_request_params()["sk"], "OtherKey");
+
+        // New user credentials invalidate the session, and the cached session key with it
+        auth_creds.set_user_credentials("Username", "Password");
+        assert_eq!(auth_creds.get_request_params()["sk"], "");
+        assert_eq!(auth_creds.get_request_params()["api_key"], "Key");
+
+        auth_creds.set_session_key("SomeKey");
+        auth_creds.set_user_token("Token");
+        assert_eq!(auth_creds.get_request_params()["sk"], "");
     }
 }
@@ -0,0 +1,324 @@
+// In-memory TTL + LRU cache of responses to read-only API calls
+use crate::headers::Headers;
+use crate::params::Params;
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+// Parameters which identify the caller rather than what was asked for
+const UNCACHED_PARAMS: &[&str] = &["api_key", "api_sig", "sk", "format"];
+
+/// Controls a `Scrobbler`'s cache of read-only lookups. See [`Scrobbler::set_cache`].
+///
+/// Responses to read-only API calls, ie. [`Scrobbler::track_info`], are kept for `ttl`, so that repeated lookups of
+/// the same track don't use up the API quota. At most `capacity` responses are kept; when the cache is full, the
+/// least recently used one is evicted. Calls which change anything, such as scrobbles, are never cached.
+///
+/// If the server sent an `ETag` or `Last-Modified` header with a response, it is revalidated once it expires: the
+/// lookup is sent with `If-None-Match` or `If-Modified-Since`, and a `304 Not Modified` answer keeps the cached
+/// response for another `ttl`. Responses without either header are simply fetched again.
+///
+/// # Usage
+/// ```ignore
+/// scrobbler.set_cache(Some(CachePolicy {
+///     capacity: 256,
+///     ttl: Duration::from_mins(10),
+/// }));
+/// ```
+///
+/// [`Scrobbler::set_cache`]: struct.Scrobbler.html#method.set_cache
+/// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// The largest number of responses kept
+    pub capacity: usize,
+    /// How long a response is kept
+    pub ttl: Duration,
+}
+
+// A cache shared by all the handles to one logical client
+#[derive(Debug, Default)]
+pub(crate) struct ResponseCache {
+    cache: Mutex<Option<Cache>>,
+}
+
+#[derive(Debug)]
+struct Cache {
+    policy: CachePolicy,
+    entries: HashMap<String, Entry>,
+    // Incremented on every use, to find the least recently used entry
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    body: String,
+    validators: Validators,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+// The headers a server sent to identify the version of a response, with which it can be revalidated
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    pub(crate) fn from_headers(headers: &Headers) -> Self {
+        Self {
+            etag: headers.get("etag").map(str::to_owned),
+            last_modified: headers.get("last-modified").map(str::to_owned),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    // Makes a request conditional on the response having changed. Weak ETags are sent as they were received, since
+    // `If-None-Match` compares them weakly.
+    pub(crate) fn add_conditions(&self, headers: &mut Headers) {
+        if let Some(etag) = &self.etag {
+            headers.set("if-none-match", etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.set("if-modified-since", last_modified);
+        }
+    }
+}
+
+impl ResponseCache {
+    // Disabling the cache, or changing its policy, discards everything cached
+    pub(crate) fn set_policy(&self, policy: Option<CachePolicy>) {
+        *self.lock() = policy.map(|policy| Cache {
+            policy,
+            entries: HashMap::new(),
+            clock: 0,
+        });
+    }
+
+    pub(crate) fn policy(&self) -> Option<CachePolicy> {
+        self.lock().as_ref().map(|cache| cache.policy)
+    }
+
+    // The key identifying a call: its method and parameters, without the caller's credentials. Values are compared
+    // ignoring case and surrounding whitespace, as Last.fm does when looking names up.
+    pub(crate) fn key(params: &Params) -> String {
+        let mut key = String::new();
+        for (name, value) in params.iter().filter(|(name, _)| !UNCACHED_PARAMS.contains(name)) {
+            key.push_str(name);
+            key.push('=');
+            key.push_str(&value.trim().to_lowercase());
+            key.push('\n');
+        }
+
+        key
+    }
+
+    pub(crate) fn get_at(&self, key: &str, now: Instant) -> Option<String> {
+        let mut guard = self.lock();
+        let cache = guard.as_mut()?;
+        cache.clock += 1;
+        let clock = cache.clock;
+
+        match cache.entries.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = clock;
+                Some(entry.body.clone())
+            }
+            // Expired entries are kept until evicted if they can be revalidated
+            Some(entry) if !entry.validators.is_empty() => None,
+            Some(_) => {
+                cache.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // The validators of a cached response, if the server sent any
+    pub(crate) fn validators(&self, key: &str) -> Option<Validators> {
+        let guard = self.lock();
+        let entry = guard.as_ref()?.entries.get(key)?;
+        Some(entry.validators.clone()).filter(|validators| !validators.is_empty())
+    }
+
+    // The server answered a conditional request with `304 Not Modified`: the cached response is used for another
+    // TTL. Returns `None` if it has since been evicted.
+    pub(crate) fn revalidate_at(&self, key: &str, now: Instant) -> Option<String> {
+        let mut guard = self.lock();
+        let cache = guard.as_mut()?;
+        cache.clock += 1;
+        let entry = cache.entries.get_mut(key)?;
+        entry.expires_at = now + cache.policy.ttl;
+        entry.last_used = cache.clock;
+
+        Some(entry.body.clone())
+    }
+
+    pub(crate) fn insert_at(&self, key: String, body: String, validators: Validators, now: Instant) {
+        let mut guard = self.lock();
+        let Some(cache) = guard.as_mut() else {
+            return;
+        };
+        if cache.policy.capacity == 0 {
+            return;
+        }
+
+        cache.clock += 1;
+        if !cache.entries.contains_key(&key) && cache.entries.len() >= cache.policy.capacity {
+            cache.evict(now);
+        }
+        let entry = Entry {
+            body,
+            validators,
+            expires_at: now + cache.policy.ttl,
+            last_used: cache.clock,
+        };
+        cache.entries.insert(key, entry);
+    }
+
+    pub(crate) fn clear(&self) {
+        if let Some(cache) = self.lock().as_mut() {
+            cache.entries.clear();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Cache>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Cache {
+    // Makes room for one more entry: drops every expired entry, or failing that the least recently used one
+    fn evict(&mut self, now: Instant) {
+        self.entries.retain(|_, entry| entry.expires_at > now);
+        if self.entries.len() < self.policy.capacity {
+            return;
+        }
+
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize) -> ResponseCache {
+        let cache = ResponseCache::default();
+        cache.set_policy(Some(CachePolicy {
+            capacity,
+            ttl: Duration::from_secs(10),
+        }));
+        cache
+    }
+
+    #[test]
+    fn check_cache_expiry() {
+        let cache = cache(4);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        cache.insert_at("a".to_owned(), "body a".to_owned(), Validators::default(), at(0));
+        assert_eq!(cache.get_at("a", at(9)).as_deref(), Some("body a"));
+        assert_eq!(cache.get_at("a", at(10)), None);
+        assert_eq!(cache.get_at("b", at(0)), None);
+
+        // Replacing an entry restarts its TTL
+        cache.insert_at("a".to_owned(), "body a".to_owned(), Validators::default(), at(0));
+        cache.insert_at("a".to_owned(), "new body a".to_owned(), Validators::default(), at(5));
+        assert_eq!(cache.get_at("a", at(14)).as_deref(), Some("new body a"));
+
+        cache.clear();
+        assert_eq!(cache.get_at("a", at(14)), None);
+
+        let disabled = ResponseCache::default();
+        disabled.insert_at("a".to_owned(), "body a".to_owned(), Validators::default(), at(0));
+        assert_eq!(disabled.get_at("a", at(0)), None);
+    }
+
+    #[test]
+    fn check_cache_evicts_least_recently_used() {
+        let cache = cache(2);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        cache.insert_at("a".to_owned(), "body a".to_owned(), Validators::default(), at(0));
+        cache.insert_at("b".to_owned(), "body b".to_owned(), Validators::default(), at(0));
+        assert!(cache.get_at("a", at(1)).is_some());
+
+        // "b" was used least recently
+        cache.insert_at("c".to_owned(), "body c".to_owned(), Validators::default(), at(2));
+        assert!(cache.get_at("a", at(2)).is_some());
+        assert_eq!(cache.get_at("b", at(2)), None);
+        assert!(cache.get_at("c", at(2)).is_some());
+
+        // Expired entries go first, however recently they were used
+        assert!(cache.get_at("a", at(9)).is_some());
+        cache.insert_at("d".to_owned(), "body d".to_owned(), Validators::default(), at(11));
+        assert!(cache.get_at("c", at(11)).is_some());
+        assert!(cache.get_at("d", at(11)).is_some());
+    }
+
+    #[test]
+    fn check_cache_revalidation() {
+        let cache = cache(4);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut headers = Headers::new();
+        headers.insert("ETag", "W/\"v1\"").unwrap();
+        headers.insert("Last-Modified", "Wed, 14 Oct 2026 10:00:00 GMT").unwrap();
+        let validators = Validators::from_headers(&headers);
+        let mut conditions = Headers::new();
+        validators.add_conditions(&mut conditions);
+        assert_eq!(conditions.get("If-None-Match"), Some("W/\"v1\""));
+        assert_eq!(conditions.get("If-Modified-Since"), Some("Wed, 14 Oct 2026 10:00:00 GMT"));
+
+        cache.insert_at("a".to_owned(), "body a".to_owned(), validators.clone(), at(0));
+        cache.insert_at("b".to_owned(), "body b".to_owned(), Validators::default(), at(0));
+        assert_eq!(cache.validators("a").as_ref(), Some(&validators));
+        assert_eq!(cache.validators("b"), None);
+
+        // Once expired, only responses which can be revalidated are kept
+        assert_eq!(cache.get_at("a", at(10)), None);
+        assert_eq!(cache.get_at("b", at(10)), None);
+        assert_eq!(cache.validators("a"), Some(validators));
+        assert_eq!(cache.revalidate_at("b", at(10)), None);
+
+        // Revalidating restarts the TTL
+        assert_eq!(cache.revalidate_at("a", at(10)).as_deref(), Some("body a"));
+        assert_eq!(cache.get_at("a", at(19)).as_deref(), Some("body a"));
+        assert_eq!(cache.get_at("a", at(20)), None);
+    }
+
+    #[test]
+    fn check_cache_key() {
+        let mut params = Params::new();
+        params.insert_method("track.getInfo");
+        params.insert_artist("Cher");
+        params.insert_track("Believe ");
+        params.insert_api_key("key");
+        params.insert_signature("sig");
+
+        let mut other = Params::new();
+        other.insert_track("believe");
+        other.insert_artist("cher");
+        other.insert_method("track.getInfo");
+        other.insert_api_key("other key");
+
+        assert_eq!(ResponseCache::key(&params), ResponseCache::key(&other));
+        assert_eq!(ResponseCache::key(&params), "artist=cher\nmethod=track.getinfo\ntrack=believe\n");
+    }
+}
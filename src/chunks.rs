@@ -0,0 +1,377 @@
+// Incremental submission of large imports, one batch-sized chunk at a time
+use crate::error::ScrobblerError;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::{BatchScrobbleResponse, IgnoredReason, ScrobbleResponse};
+use crate::plan::{ImportPlan, ImportStatus};
+use crate::scrobbler::Scrobbler;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+// The most scrobbles Last.fm accepts in one batch
+pub(crate) const MAX_CHUNK_SIZE: usize = 50;
+
+type ProgressCallback<'a> = Box<dyn FnMut(&ImportProgress) + 'a>;
+
+/// The outcome of submitting one chunk of a [`Scrobbler::scrobble_batch_chunks`] import
+///
+/// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+#[derive(Debug)]
+pub struct ChunkResult {
+    /// The positions of the chunk's scrobbles in the whole import
+    pub range: Range<usize>,
+    /// `Err` if the chunk couldn't be submitted, after any retries. The positions in the response's
+    /// `preparation()` report are the scrobbles' positions in the whole import, as in `range`.
+    pub result: Result<BatchScrobbleResponse, ScrobblerError>,
+    /// Set if Last.fm ignored some of the chunk's scrobbles because the daily scrobble limit was reached, in which
+    /// case this is the last chunk submitted
+    pub daily_limit: Option<DailyLimitReached>,
+}
+
+/// Reports that Last.fm has started ignoring scrobbles because the user reached the daily scrobble limit (roughly
+/// 2,800 scrobbles a day), and that the rest of an import or queue was held back rather than submitted only to be
+/// ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DailyLimitReached {
+    /// The number of scrobbles left to submit, including those Last.fm ignored because of the limit
+    pub remaining: usize,
+    /// When to resume submitting. Last.fm doesn't document when the limit resets, so this is a heuristic: the next
+    /// midnight UTC.
+    pub resume_at: SystemTime,
+}
+
+impl DailyLimitReached {
+    pub(crate) fn new(remaining: usize, now: SystemTime) -> Self {
+        let today = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / SECS_PER_DAY;
+        Self {
+            remaining,
+            resume_at: UNIX_EPOCH + Duration::from_secs((today + 1) * SECS_PER_DAY),
+        }
+    }
+}
+
+/// How far an import submitted with [`Scrobbler::scrobble_batch_chunks`] got, so that an interrupted import can be
+/// resumed without submitting any scrobble twice. See [`BatchChunks::on_progress`].
+///
+/// Progress can be serialized, ie. to a file after each chunk, and is tied to the exact import it was recorded for:
+/// it holds a hash of the import's scrobbles, and [`BatchChunks::resume_from`] rejects progress recorded for any
+/// other import.
+///
+/// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+/// [`BatchChunks::on_progress`]: struct.BatchChunks.html#method.on_progress
+/// [`BatchChunks::resume_from`]: struct.BatchChunks.html#method.resume_from
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ImportProgress {
+    completed: usize,
+    batch_hash: String,
+}
+
+impl ImportProgress {
+    pub(crate) fn new(scrobbles: &[Scrobble]) -> Self {
+        Self {
+            completed: 0,
+            batch_hash: batch_hash(scrobbles),
+        }
+    }
+
+    /// Returns the number of scrobbles at the start of the import which have been submitted, and which don't need
+    /// to be submitted again. Resuming carries on from the scrobble at this position.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    // Moves the progress up to `completed`, if the chunk that ends there followed on from the confirmed ones.
+    // Returns whether it moved.
+    pub(crate) fn advance(&mut self, range: &Range<usize>, completed: usize) -> bool {
+        if self.completed != range.start || completed == range.start {
+            return false;
+        }
+
+        self.completed = completed;
+        true
+    }
+}
+
+// Identifies an import by the parameters its scrobbles would be submitted with
+pub(crate) fn batch_hash<'a>(scrobbles: impl IntoIterator<Item = &'a Scrobble>) -> String {
+    let mut hash = md5::Context::new();
+    for scrobble in scrobbles {
+        for (key, value) in scrobble.params().iter() {
+            hash.consume(key);
+            hash.consume("=");
+            hash.consume(value);
+            hash.consume("&");
+        }
+        hash.consume("\n");
+    }
+
+    format!("{:x}", hash.compute())
+}
+
+/// An iterator which submits an import chunk by chunk, as it's advanced. See
+/// [`Scrobbler::scrobble_batch_chunks`].
+///
+/// Once Last.fm ignores any of a chunk's scrobbles because the daily scrobble limit has been reached, the remaining
+/// chunks aren't submitted: that chunk is the iterator's last item, with its [`ChunkResult::daily_limit`] set.
+///
+/// # Resuming an import
+/// The iterator tracks the import's [`ImportProgress`]: the number of scrobbles, from the start of the import,
+/// which have been submitted without error and weren't ignored because of the daily scrobble limit. Progress only
+/// moves past chunks which were confirmed in order, so if a chunk fails for a transient reason, chunks after it
+/// which succeed don't count until the import is resumed (and are submitted again then).
+///
+/// ```ignore
+/// let mut chunks = scrobbler.scrobble_batch_chunks(&history, 50)?;
+/// if let Some(progress) = load_progress()? {
+///     chunks = chunks.resume_from(&progress)?;
+/// }
+///
+/// for chunk in chunks.on_progress(|progress| save_progress(progress)) {
+///     // ...
+/// }
+/// ```
+///
+/// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+/// [`ChunkResult::daily_limit`]: struct.ChunkResult.html#structfield.daily_limit
+/// [`ImportProgress`]: struct.ImportProgress.html
+pub struct BatchChunks<'a> {
+    scrobbler: &'a Scrobbler,
+    scrobbles: Vec<Scrobble>,
+    chunk_size: usize,
+    next: usize,
+    stopped: bool,
+    progress: ImportProgress,
+    on_progress: Option<ProgressCallback<'a>>,
+}
+
+impl<'a> BatchChunks<'a> {
+    pub(crate) fn new(scrobbler: &'a Scrobbler, batch: Vec<Scrobble>, chunk_size: usize) -> Self {
+        Self {
+            scrobbler,
+            progress: ImportProgress::new(&batch),
+            scrobbles: batch,
+            chunk_size,
+            next: 0,
+            stopped: false,
+            on_progress: None,
+        }
+    }
+
+    /// Calls `on_progress` with the import's progress each time it advances, once a chunk is confirmed. Store the
+    /// progress somewhere which survives a crash to be able to [resume](#method.resume_from) the import.
+    #[must_use]
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: FnMut(&ImportProgress) + 'a,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Skips the scrobbles already submitted by an earlier, interrupted run of the same import. Must be called
+    /// before the iterator is first advanced. Fails if `progress` was recorded for a different import.
+    pub fn resume_from(mut self, progress: &ImportProgress) -> Result<Self, ScrobblerError> {
+        if progress.batch_hash != self.progress.batch_hash || progress.completed > self.scrobbles.len() {
+            return Err(ScrobblerError::new(
+                "Import progress was recorded for a different batch of scrobbles".to_owned(),
+            ));
+        }
+
+        self.progress.completed = progress.completed;
+        self.next = progress.completed;
+        Ok(self)
+    }
+
+    /// Leaves out the scrobbles which `plan` found to be probable duplicates of scrobbles already in the user's
+    /// history (see [`Scrobbler::plan_import`]). Must be called before the iterator is first advanced, and before
+    /// resuming. Fails if `plan` was made for a different import.
+    ///
+    /// The import is then made up of the remaining scrobbles only: chunk ranges and progress count those, so to
+    /// resume it later, skip the same duplicates before calling [`BatchChunks::resume_from`].
+    ///
+    /// [`Scrobbler::plan_import`]: struct.Scrobbler.html#method.plan_import
+    /// [`BatchChunks::resume_from`]: struct.BatchChunks.html#method.resume_from
+    pub fn skip_duplicates(mut self, plan: &ImportPlan) -> Result<Self, ScrobblerError> {
+        if plan.batch_hash() != self.progress.batch_hash {
+            return Err(ScrobblerError::new(
+                "Import plan was made for a different batch of scrobbles".to_owned(),
+            ));
+        }
+        if self.next > 0 {
+            return Err(ScrobblerError::new(
+                "Duplicates must be skipped before the import is started or resumed".to_owned(),
+            ));
+        }
+
+        let is_duplicate = |status: &ImportStatus| matches!(status, ImportStatus::ProbableDuplicate { .. });
+        self.scrobbles = self
+            .scrobbles
+            .into_iter()
+            .zip(plan.statuses())
+            .filter_map(|(scrobble, status)| (!is_duplicate(status)).then_some(scrobble))
+            .collect();
+        self.progress = ImportProgress::new(&self.scrobbles);
+        Ok(self)
+    }
+
+    /// Returns the import's progress so far
+    pub fn progress(&self) -> &ImportProgress {
+        &self.progress
+    }
+
+    fn advance(&mut self, range: &Range<usize>, completed: usize) {
+        if !self.progress.advance(range, completed) {
+            return;
+        }
+
+        if let Some(on_progress) = self.on_progress.as_mut() {
+            on_progress(&self.progress);
+        }
+    }
+}
+
+impl fmt::Debug for BatchChunks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchChunks")
+            .field("scrobbles", &self.scrobbles.len())
+            .field("chunk_size", &self.chunk_size)
+            .field("next", &self.next)
+            .field("stopped", &self.stopped)
+            .field("progress", &self.progress)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for BatchChunks<'_> {
+    type Item = ChunkResult;
+
+    fn next(&mut self) -> Option<ChunkResult> {
+        if self.stopped || self.next >= self.scrobbles.len() {
+            return None;
+        }
+
+        let range = self.next..self.scrobbles.len().min(self.next + self.chunk_size);
+        self.next = range.end;
+        let (mut chunk, limited) = submit_chunk(self.scrobbler, &self.scrobbles, range);
+
+        // Stop, so that the caller can fix the problem and resume the import from the failed chunk
+        if chunk.stops_import() {
+            self.stopped = true;
+        }
+
+        // Later chunks would only be ignored too
+        if let Some((count, first)) = limited {
+            self.stopped = true;
+            chunk.daily_limit = Some(DailyLimitReached::new(
+                count + self.scrobbles.len() - chunk.range.end,
+                SystemTime::now(),
+            ));
+            self.advance(&chunk.range.clone(), first);
+        } else if chunk.result.is_ok() {
+            self.advance(&chunk.range.clone(), chunk.range.end);
+        }
+
+        Some(chunk)
+    }
+}
+
+impl ChunkResult {
+    // Whether the import shouldn't carry on after this chunk, as its failure wasn't transient
+    fn stops_import(&self) -> bool {
+        self.result.as_ref().is_err_and(|err| !err.is_retryable())
+    }
+}
+
+// Submits the import's scrobbles in `range` as one batch. Also returns how many of them Last.fm ignored because of
+// the daily scrobble limit, and the position in the import of the first of those, if there were any.
+fn submit_chunk(
+    scrobbler: &Scrobbler,
+    import: &[Scrobble],
+    range: Range<usize>,
+) -> (ChunkResult, Option<(usize, usize)>) {
+    let chunk = ScrobbleBatch::from(import[range.clone()].to_vec());
+    chunk_result(range, scrobbler.scrobble_batch(&chunk))
+}
+
+// Wraps the result of submitting the import's scrobbles in `range`, as `submit_chunk` does
+pub(crate) fn chunk_result(
+    range: Range<usize>,
+    result: Result<BatchScrobbleResponse, ScrobblerError>,
+) -> (ChunkResult, Option<(usize, usize)>) {
+    let result = result.map(|response| BatchScrobbleResponse {
+        preparation: response.preparation.offset(range.start),
+        ..response
+    });
+
+    let is_limited = |scrobble: &ScrobbleResponse| {
+        scrobble.ignored_reason() == Some(IgnoredReason::DailyScrobbleLimitExceeded)
+    };
+    let limited = result.as_ref().ok().and_then(|response| {
+        let submitted = response.scrobbles();
+        let first = submitted.iter().position(is_limited)?;
+        Some((submitted.iter().filter(|scrobble| is_limited(scrobble)).count(), range.start + first))
+    });
+
+    let chunk = ChunkResult {
+        range,
+        result,
+        daily_limit: None,
+    };
+    (chunk, limited)
+}
+
+// Submits an import in chunks of up to 50 scrobbles, up to `concurrency` at a time, returning the chunks submitted
+// in the import's order. No more chunks are started once one fails for a reason which isn't transient, or hits the
+// daily scrobble limit.
+pub(crate) fn submit_concurrently(scrobbler: &Scrobbler, import: &[Scrobble], concurrency: usize) -> Vec<ChunkResult> {
+    let ranges: Vec<_> = (0..import.len())
+        .step_by(MAX_CHUNK_SIZE)
+        .map(|start| start..import.len().min(start + MAX_CHUNK_SIZE))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+
+    let mut submitted: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency.min(ranges.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut submitted = Vec::new();
+                    while !stopped.load(Ordering::SeqCst) {
+                        let Some(range) = ranges.get(next.fetch_add(1, Ordering::SeqCst)) else {
+                            break;
+                        };
+                        let (chunk, limited) = submit_chunk(scrobbler, import, range.clone());
+                        if limited.is_some() || chunk.stops_import() {
+                            stopped.store(true, Ordering::SeqCst);
+                        }
+                        submitted.push((chunk, limited.map_or(0, |(count, _)| count)));
+                    }
+                    submitted
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+    submitted.sort_by_key(|(chunk, _)| chunk.range.start);
+
+    // Every chunk which hit the limit reports everything left over: the scrobbles it ignored, and those never sent
+    let limited: usize = submitted.iter().map(|(_, limited)| limited).sum();
+    if limited > 0 {
+        let sent: usize = submitted.iter().map(|(chunk, _)| chunk.range.len()).sum();
+        let daily_limit = DailyLimitReached::new(limited + import.len() - sent, SystemTime::now());
+        for (chunk, _) in submitted.iter_mut().filter(|(_, limited)| *limited > 0) {
+            chunk.daily_limit = Some(daily_limit);
+        }
+    }
+
+    submitted.into_iter().map(|(chunk, _)| chunk).collect()
+}
@@ -0,0 +1,191 @@
+// Circuit breaker which stops calls to the API while it appears to be down
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Controls a `Scrobbler`'s circuit breaker. See [`Scrobbler::set_circuit_breaker`].
+///
+/// After `failure_threshold` consecutive attempts fail for transient reasons (the same failures that are retried
+/// under a [`RetryPolicy`]), the circuit *opens*: for the next `cooldown`, API calls fail immediately with an error
+/// of kind `ErrorKind::CircuitOpen`, without sending anything. Once the cooldown has passed the circuit is
+/// *half-open*, and the next call is sent as a probe: if it succeeds the circuit *closes* and calls are sent as
+/// normal again, and if it fails the circuit opens for another cooldown. Other calls fail immediately while the
+/// probe is in progress.
+///
+/// Any response from Last.fm other than a transient failure, including an error such as "invalid session key",
+/// shows the service is up, and counts as a success.
+///
+/// # Usage
+/// ```ignore
+/// scrobbler.set_circuit_breaker(Some(CircuitBreakerPolicy {
+///     failure_threshold: 5,
+///     cooldown: Duration::from_mins(5),
+/// }));
+/// ```
+///
+/// [`Scrobbler::set_circuit_breaker`]: struct.Scrobbler.html#method.set_circuit_breaker
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerPolicy {
+    /// The number of consecutive transient failures which opens the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a probe is sent
+    pub cooldown: Duration,
+}
+
+/// The state of a `Scrobbler`'s circuit breaker. See [`CircuitBreakerPolicy`].
+///
+/// [`CircuitBreakerPolicy`]: struct.CircuitBreakerPolicy.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CircuitState {
+    /// Calls are sent as normal
+    Closed,
+    /// Calls fail immediately, until the cooldown has passed
+    Open,
+    /// The cooldown has passed, and the next call (or the call in progress) is a probe
+    HalfOpen,
+}
+
+// A circuit breaker shared by all the handles to one logical client
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreaker {
+    circuit: Mutex<Option<Circuit>>,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    policy: CircuitBreakerPolicy,
+    state: CircuitState,
+    failures: u32,
+    opened_at: Instant,
+    probing: bool,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn set_policy(&self, policy: Option<CircuitBreakerPolicy>) {
+        *self.lock() = policy.map(|policy| Circuit {
+            policy,
+            state: CircuitState::Closed,
+            failures: 0,
+            opened_at: Instant::now(),
+            probing: false,
+        });
+    }
+
+    pub(crate) fn policy(&self) -> Option<CircuitBreakerPolicy> {
+        self.lock().as_ref().map(|circuit| circuit.policy)
+    }
+
+    pub(crate) fn state(&self) -> Option<CircuitState> {
+        self.state_at(Instant::now())
+    }
+
+    pub(crate) fn state_at(&self, now: Instant) -> Option<CircuitState> {
+        self.lock().as_ref().map(|circuit| match circuit.state {
+            CircuitState::Open if circuit.cooled_down(now) => CircuitState::HalfOpen,
+            state => state,
+        })
+    }
+
+    // Decides whether an attempt may be sent. Returns how long until the circuit may let one through if not, and
+    // the new state if it changed.
+    pub(crate) fn allow_at(&self, now: Instant) -> (Result<(), Duration>, Option<CircuitState>) {
+        let mut guard = self.lock();
+        let Some(circuit) = guard.as_mut() else {
+            return (Ok(()), None);
+        };
+
+        match circuit.state {
+            CircuitState::Closed => (Ok(()), None),
+            CircuitState::Open if circuit.cooled_down(now) => {
+                circuit.state = CircuitState::HalfOpen;
+                circuit.probing = true;
+                (Ok(()), Some(CircuitState::HalfOpen))
+            }
+            CircuitState::Open => {
+                let cooldown_end = circuit.opened_at + circuit.policy.cooldown;
+                (Err(cooldown_end.saturating_duration_since(now)), None)
+            }
+            CircuitState::HalfOpen if circuit.probing => (Err(Duration::from_secs(0)), None),
+            CircuitState::HalfOpen => {
+                circuit.probing = true;
+                (Ok(()), None)
+            }
+        }
+    }
+
+    // Records the result of an attempt, returning the new state if it changed
+    pub(crate) fn record_at(&self, transient_failure: bool, now: Instant) -> Option<CircuitState> {
+        let mut guard = self.lock();
+        let circuit = guard.as_mut()?;
+        let previous = circuit.state;
+        circuit.probing = false;
+
+        if transient_failure {
+            circuit.failures = circuit.failures.saturating_add(1);
+            if circuit.state == CircuitState::HalfOpen || circuit.failures >= circuit.policy.failure_threshold {
+                circuit.state = CircuitState::Open;
+                circuit.opened_at = now;
+            }
+        } else {
+            circuit.failures = 0;
+            circuit.state = CircuitState::Closed;
+        }
+
+        (circuit.state != previous).then_some(circuit.state)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Circuit>> {
+        self.circuit.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Circuit {
+    fn cooled_down(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.opened_at) >= self.policy.cooldown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: (Result<(), Duration>, Option<CircuitState>) = (Ok(()), None);
+
+    #[test]
+    fn check_circuit_transitions() {
+        let breaker = CircuitBreaker::default();
+        breaker.set_policy(Some(CircuitBreakerPolicy {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(10),
+        }));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // A success resets the count of consecutive failures
+        assert_eq!(breaker.record_at(true, at(0)), None);
+        assert_eq!(breaker.record_at(false, at(0)), None);
+        assert_eq!(breaker.record_at(true, at(0)), None);
+        assert_eq!(breaker.allow_at(at(0)), ALLOWED);
+        assert_eq!(breaker.record_at(true, at(1)), Some(CircuitState::Open));
+
+        assert_eq!(breaker.allow_at(at(5)), (Err(Duration::from_secs(6)), None));
+        assert_eq!(breaker.state_at(at(5)), Some(CircuitState::Open));
+        assert_eq!(breaker.state_at(at(11)), Some(CircuitState::HalfOpen));
+
+        // One probe at a time, which reopens the circuit if it fails...
+        assert_eq!(breaker.allow_at(at(11)), (Ok(()), Some(CircuitState::HalfOpen)));
+        assert_eq!(breaker.allow_at(at(11)), (Err(Duration::from_secs(0)), None));
+        assert_eq!(breaker.record_at(true, at(12)), Some(CircuitState::Open));
+        assert_eq!(breaker.allow_at(at(21)), (Err(Duration::from_secs(1)), None));
+
+        // ...and closes it if it succeeds
+        assert_eq!(breaker.allow_at(at(22)), (Ok(()), Some(CircuitState::HalfOpen)));
+        assert_eq!(breaker.record_at(false, at(22)), Some(CircuitState::Closed));
+        assert_eq!(breaker.allow_at(at(22)), ALLOWED);
+
+        breaker.set_policy(None);
+        assert_eq!(breaker.state(), None);
+        assert_eq!(breaker.record_at(true, at(23)), None);
+        assert_eq!(breaker.allow_at(at(23)), ALLOWED);
+    }
+}
@@ -1,101 +1,589 @@
 // Last.fm scrobble API 2.0 client
 
-use reqwest;
-use reqwest::{Client, StatusCode};
-use serde_json;
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
 use std::fmt;
-use std::io::Read;
-
-#[cfg(test)]
-use mockito;
-
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "async")]
+use crate::async_transport::{self, AsyncTransport};
+use crate::audit::{CallLog, CallRecord};
 use crate::auth::Credentials;
+use crate::builder::Endpoint;
+use crate::cache::{CachePolicy, ResponseCache, Validators};
+use crate::circuit::{CircuitBreaker, CircuitBreakerPolicy, CircuitState};
+use crate::codes::LastFmErrorCode;
+use crate::error::{ErrorKind, ScrobblerError};
 use crate::models::responses::{
-    AuthResponse, BatchScrobbleResponse, BatchScrobbleResponseWrapper, NowPlayingResponse,
-    NowPlayingResponseWrapper, ScrobbleResponse, ScrobbleResponseWrapper, SessionResponse,
+    AlbumInfo, AlbumInfoWrapper, ArtistInfo, ArtistInfoWrapper, AuthResponse, BatchScrobbleResponse,
+    BatchScrobbleResponseWrapper, CorrectionResponse, CorrectionWrapper, NowPlayingResponse, NowPlayingResponseWrapper,
+    RecentTracks, RecentTracksWrapper, ScrobbleResponse, ScrobbleResponseWrapper, SessionResponse, TokenResponse,
+    TrackInfo, TrackInfoWrapper, UserInfo, UserInfoWrapper,
 };
+use crate::events::{EventObserver, RequestOutcome, ScrobblerEvent};
+use crate::headers::Headers;
+use crate::health::{HealthReport, HealthTracker};
+use crate::issues::PreparationReport;
+use crate::keys::{ApiKey, ApiSecret, SessionKey};
+use crate::lang::Language;
+use crate::matching;
+use crate::params::Params;
+use crate::rate_limit::{Lane, RateLimit, RateLimiter};
+#[cfg(feature = "event-sink")]
+use crate::sink::EventSink;
+use crate::retry::{CallClass, RetryPolicy};
+use crate::signing::{self, Md5Signer, RequestSigner};
+use crate::submitted::{LastAccepted, SubmittedScrobble};
+#[cfg(feature = "metrics")]
+use crate::telemetry::Metrics;
+use crate::transport::{HttpMethod, HttpRequest, HttpResponse, ReqwestTransport, Transport, TransportInfo};
+use crate::usage::{EstimatedUsage, UsageHistory, UsageThresholds, UsageTracker};
+
+use serde::de::DeserializeOwned;
+
+const DRY_RUN_SESSION_KEY: &str = "dry-run-session-key";
+
+// Where Last.fm users authorize a request token, in the desktop authentication flow
+pub(crate) const AUTH_URL: &str = "https://www.last.fm/api/auth/";
+
+pub(crate) const API_URL: &str = "https://ws.audioscrobbler.com/2.0/?format=json";
+
+// Far larger than any genuine API response, which are at most a few KB
+const DEFAULT_MAX_RESPONSE_SIZE: u64 = 4 * 1024 * 1024;
+
+// The answer to a conditional request when the cached response is still current
+const NOT_MODIFIED: u16 = 304;
+
+// How many redirects of one read-only request are followed before giving up on it
+const MAX_REDIRECTS: u32 = 5;
 
 pub enum ApiOperation {
+    AuthToken,
     AuthWebSession,
     AuthMobileSession,
     NowPlaying,
     Scrobble,
+    Love,
+    Unlove,
+    TrackInfo,
+    TrackCorrection,
+    ArtistInfo,
+    AlbumInfo,
+    RecentTracks,
+    UserInfo,
+    // Any other method, called through `Scrobbler::call_signed`
+    Signed(String),
 }
 
 impl fmt::Display for ApiOperation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let str = match *self {
+        f.write_str(self.method())
+    }
+}
+
+impl ApiOperation {
+    fn method(&self) -> &str {
+        match self {
+            Self::AuthToken => "auth.getToken",
             Self::AuthWebSession => "auth.getSession",
             Self::AuthMobileSession => "auth.getMobileSession",
             Self::NowPlaying => "track.updateNowPlaying",
             Self::Scrobble => "track.scrobble",
-        };
-        write!(f, "{}", str)
+            Self::Love => "track.love",
+            Self::Unlove => "track.unlove",
+            Self::TrackInfo => "track.getInfo",
+            Self::TrackCorrection => "track.getCorrection",
+            Self::ArtistInfo => "artist.getInfo",
+            Self::AlbumInfo => "album.getInfo",
+            Self::RecentTracks => "user.getRecentTracks",
+            Self::UserInfo => "user.getInfo",
+            Self::Signed(method) => method,
+        }
+    }
+
+
+    // Read-only methods are sent as a `GET`, and may be cached; every other method changes something or
+    // authenticates, so is sent as a `POST`
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Self::TrackInfo | Self::TrackCorrection | Self::ArtistInfo | Self::AlbumInfo | Self::RecentTracks
+        )
+    }
+
+    // Now playing updates needn't keep their order with scrobbles, so don't wait behind them for the rate limit
+    fn lane(&self) -> Lane {
+        match self {
+            Self::NowPlaying => Lane::Priority,
+            _ => Lane::Normal,
+        }
+    }
+
+    // Each class of call is retried under its own policy
+    fn call_class(&self) -> CallClass {
+        match self {
+            Self::AuthToken | Self::AuthWebSession | Self::AuthMobileSession => CallClass::Auth,
+            // Signed with the session key, so sent as a `POST` and never cached, but read-only all the same
+            Self::UserInfo => CallClass::Read,
+            // Unknown methods may change something, so are retried as writes
+            Self::Signed(_) => CallClass::Write,
+            _ if self.is_read_only() => CallClass::Read,
+            _ => CallClass::Write,
+        }
     }
 }
 
+// One logical API call, across all of its attempts
+struct Call<'a> {
+    method: &'a str,
+    correlation_id: &'a str,
+    started: Instant,
+    started_at: SystemTime,
+    // Sent with the validators of a cached response, so may be answered with `304 Not Modified`
+    conditional: bool,
+}
+
+// Clones share the transport, signer, rate limiter, circuit breaker, call log, cache, usage tracker, last accepted
+// scrobble, health and endpoint (so that they all follow a permanent redirect); everything else is copied
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct LastFm {
     auth: Credentials,
-    http_client: Client,
+    // The name of the user the session belongs to, when known
+    username: Option<String>,
+    transport: Arc<dyn Transport>,
+    observer: Option<EventObserver>,
+    #[cfg(feature = "event-sink")]
+    event_sink: Option<EventSink>,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
+    auth_retry_policy: RetryPolicy,
+    read_retry_policy: RetryPolicy,
+    send_request_id: bool,
+    // Whether signed requests carry a random `nonce`, for relays which de-duplicate by it
+    include_nonce: bool,
+    max_response_size: u64,
+    extra_headers: Headers,
+    endpoint: Arc<RwLock<Endpoint>>,
+    // Whether a permanent redirect moves the endpoint, for every later call
+    update_base_url_on_redirect: bool,
+    signer: Arc<dyn RequestSigner>,
+    rate_limiter: Arc<RateLimiter>,
+    circuit_breaker: Arc<CircuitBreaker>,
+    call_log: Arc<CallLog>,
+    cache: Arc<ResponseCache>,
+    usage: Arc<UsageTracker>,
+    last_accepted: Arc<LastAccepted>,
+    health: Arc<HealthTracker>,
+    bypass_cache: bool,
+    // Sent as `autocorrect` with lookups which accept it, unless `None`
+    autocorrect: Option<bool>,
+    // Sent as `lang` with lookups of localized text, unless `None`
+    language: Option<Language>,
 }
 
 impl LastFm {
     
-    pub fn new(api_key: &str, api_secret: &str) -> Self {
+    pub fn new(api_key: impl Into<ApiKey>, api_secret: impl Into<ApiSecret>) -> Self {
+        Self::with_transport(api_key, api_secret, Arc::new(ReqwestTransport::new()))
+    }
+
+    pub fn with_transport(
+        api_key: impl Into<ApiKey>,
+        api_secret: impl Into<ApiSecret>,
+        transport: Arc<dyn Transport>,
+    ) -> Self {
         let partial_auth = Credentials::new_partial(api_key, api_secret);
-        let http_client = Client::new();
 
         Self {
             auth: partial_auth,
-            http_client,
+            username: None,
+            transport,
+            observer: None,
+            #[cfg(feature = "event-sink")]
+            event_sink: None,
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::default(),
+            retry_policy: CallClass::Write.default_retry_policy(),
+            auth_retry_policy: CallClass::Auth.default_retry_policy(),
+            read_retry_policy: CallClass::Read.default_retry_policy(),
+            send_request_id: false,
+            include_nonce: false,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            extra_headers: Headers::new(),
+            #[cfg(not(test))]
+            endpoint: Arc::new(RwLock::new(Endpoint::new(API_URL.to_owned(), AUTH_URL.to_owned()))),
+            #[cfg(test)]
+            endpoint: Arc::new(RwLock::new(Endpoint::new(mockito::server_url(), AUTH_URL.to_owned()))),
+            update_base_url_on_redirect: false,
+            signer: Arc::new(Md5Signer),
+            rate_limiter: Arc::default(),
+            circuit_breaker: Arc::default(),
+            call_log: Arc::default(),
+            cache: Arc::default(),
+            usage: Arc::default(),
+            last_accepted: Arc::default(),
+            health: Arc::default(),
+            bypass_cache: false,
+            autocorrect: None,
+            language: None,
+        }
+    }
+
+    /// Replaces the transport, for this client only: clones keep the one they have, and calls they have in flight
+    /// complete through it
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.transport = transport;
+    }
+
+    pub fn set_signer(&mut self, signer: Arc<dyn RequestSigner>) {
+        self.signer = signer;
+    }
+
+    /// Sets the rate limit, for this client and every clone of it
+    pub fn set_rate_limit(&self, rate_limit: Option<RateLimit>) {
+        self.rate_limiter.set_limit(rate_limit);
+    }
+
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limiter.limit()
+    }
+
+    pub fn rate_limit_utilization(&self) -> Option<f64> {
+        self.rate_limiter.utilization()
+    }
+
+    /// Sets the circuit breaker policy, for this client and every clone of it. Resets the circuit to closed.
+    pub fn set_circuit_breaker(&self, policy: Option<CircuitBreakerPolicy>) {
+        self.circuit_breaker.set_policy(policy);
+    }
+
+    pub fn circuit_breaker(&self) -> Option<CircuitBreakerPolicy> {
+        self.circuit_breaker.policy()
+    }
+
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit_breaker.state()
+    }
+
+    /// Reports on the health of this client and its clones as of `now`, without sending anything
+    pub fn health_at(&self, now: SystemTime) -> HealthReport {
+        let mut report = HealthReport::new(self.auth.is_authenticated(), self.circuit_state());
+        self.health.report_at(&mut report, now);
+        report
+    }
+
+    /// Returns true if a health probe may be sent at `now`, none having been sent by this client or its clones in
+    /// the last `interval`
+    pub fn claim_health_probe_at(&self, interval: Duration, now: SystemTime) -> bool {
+        self.health.claim_probe_at(interval, now)
+    }
+
+    /// Sets the capacity of the call log, for this client and every clone of it
+    pub fn set_call_log_capacity(&self, capacity: usize) {
+        self.call_log.set_capacity(capacity);
+    }
+
+    pub fn recent_calls(&self) -> Vec<CallRecord> {
+        self.call_log.records()
+    }
+
+    pub fn clear_recent_calls(&self) {
+        self.call_log.clear();
+    }
+
+    /// Sets the cache policy, for this client and every clone of it. Discards everything cached.
+    pub fn set_cache(&self, policy: Option<CachePolicy>) {
+        self.cache.set_policy(policy);
+    }
+
+    pub fn cache_policy(&self) -> Option<CachePolicy> {
+        self.cache.policy()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    /// Sets the usage thresholds, for this client and every clone of it. Requests already counted still count.
+    pub fn set_usage_thresholds(&self, thresholds: Option<UsageThresholds>) {
+        self.usage.set_thresholds(thresholds);
+    }
+
+    pub fn usage_thresholds(&self) -> Option<UsageThresholds> {
+        self.usage.thresholds()
+    }
+
+    pub fn estimated_usage(&self) -> EstimatedUsage {
+        self.usage.usage_at(unix_now())
+    }
+
+    pub(crate) fn usage_history(&self) -> UsageHistory {
+        self.usage.history_at(unix_now())
+    }
+
+    pub(crate) fn restore_usage(&self, history: &UsageHistory) {
+        self.usage.restore(history);
+    }
+
+    // Records Last.fm's responses to submitted scrobbles, and passes them on to the event sink
+    pub(crate) fn record_submitted<'a, I>(&self, responses: I)
+    where
+        I: IntoIterator<Item = &'a ScrobbleResponse, IntoIter: Clone>,
+    {
+        let responses = responses.into_iter();
+        #[cfg(feature = "tracing")]
+        for response in responses.clone() {
+            if let Some(reason) = response.ignored_reason() {
+                tracing::info!(
+                    correlation_id = response.correlation_id(),
+                    code = reason.code(),
+                    reason = %reason,
+                    "Last.fm ignored a scrobble"
+                );
+            }
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.record_scrobbles(responses.clone());
+        self.last_accepted.record(responses.clone());
+        #[cfg(feature = "event-sink")]
+        if let Some(sink) = &self.event_sink {
+            sink.emit(responses);
+        }
+    }
+
+    pub(crate) fn last_accepted(&self) -> Option<SubmittedScrobble> {
+        self.last_accepted.get()
+    }
+
+    pub(crate) fn restore_last_accepted(&self, submitted: SubmittedScrobble) {
+        self.last_accepted.restore(submitted);
+    }
+
+    /// Makes read-only calls through this client skip the cache, always sending a request. Responses still
+    /// replace those cached.
+    pub fn set_bypass_cache(&mut self, bypass_cache: bool) {
+        self.bypass_cache = bypass_cache;
+    }
+
+    pub fn set_autocorrect(&mut self, autocorrect: Option<bool>) {
+        self.autocorrect = autocorrect;
+    }
+
+    pub fn autocorrect(&self) -> Option<bool> {
+        self.autocorrect
+    }
+
+    pub fn set_language(&mut self, language: Option<Language>) {
+        self.language = language;
+    }
+
+    pub fn language(&self) -> Option<&Language> {
+        self.language.as_ref()
+    }
+
+    pub fn base_url(&self) -> String {
+        self.endpoint().api_url
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        self.endpoint.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    // Points the client, but not its existing clones, at another server implementing the API
+    pub fn set_endpoint(&mut self, endpoint: Endpoint) {
+        self.endpoint = Arc::new(RwLock::new(endpoint));
+    }
+
+    // Whether the endpoint is a server other than Last.fm
+    pub fn is_custom_host(&self) -> bool {
+        !self.endpoint().is_lastfm()
+    }
+
+    pub fn set_update_base_url_on_redirect(&mut self, update_base_url_on_redirect: bool) {
+        self.update_base_url_on_redirect = update_base_url_on_redirect;
+    }
+
+    pub fn api_key(&self) -> &str {
+        self.auth.api_key()
+    }
+
+    pub fn transport_info(&self) -> TransportInfo {
+        self.transport.info()
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    pub fn retry_policy_for(&self, class: CallClass) -> RetryPolicy {
+        match class {
+            CallClass::Auth => self.auth_retry_policy,
+            CallClass::Write => self.retry_policy,
+            CallClass::Read => self.read_retry_policy,
+        }
+    }
+
+    pub fn set_event_observer(&mut self, observer: Option<EventObserver>) {
+        self.observer = observer;
+    }
+
+    #[cfg(feature = "event-sink")]
+    pub fn set_event_sink(&mut self, event_sink: Option<EventSink>) {
+        self.event_sink = event_sink;
+    }
+
+    #[cfg(feature = "event-sink")]
+    pub fn event_sink(&self) -> Option<&EventSink> {
+        self.event_sink.as_ref()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics_prefix(&mut self, prefix: &str) {
+        self.metrics = Metrics::new(prefix);
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics_prefix(&self) -> &str {
+        self.metrics.prefix()
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn set_retry_policy_for(&mut self, class: CallClass, retry_policy: RetryPolicy) {
+        match class {
+            CallClass::Auth => self.auth_retry_policy = retry_policy,
+            CallClass::Write => self.retry_policy = retry_policy,
+            CallClass::Read => self.read_retry_policy = retry_policy,
         }
     }
 
+    pub fn set_send_request_id(&mut self, send_request_id: bool) {
+        self.send_request_id = send_request_id;
+    }
+
+    pub fn set_include_nonce(&mut self, include_nonce: bool) {
+        self.include_nonce = include_nonce;
+    }
+
+    pub fn include_nonce(&self) -> bool {
+        self.include_nonce
+    }
+
+    pub fn set_max_response_size(&mut self, max_response_size: u64) {
+        self.max_response_size = max_response_size;
+    }
+
+    pub fn max_response_size(&self) -> u64 {
+        self.max_response_size
+    }
+
+    pub fn extra_headers_mut(&mut self) -> &mut Headers {
+        &mut self.extra_headers
+    }
+
     pub fn set_user_credentials(&mut self, username: &str, password: &str) {
         self.auth.set_user_credentials(username, password);
+        self.username = None;
     }
 
     pub fn set_user_token(&mut self, token: &str) {
         self.auth.set_user_token(token);
+        self.username = None;
     }
 
-    pub fn authenticate_with_password(&mut self) -> Result<SessionResponse, String> {
-        let params = self.auth.get_auth_request_params()?;
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
 
-        let body = self
-            .api_request(&ApiOperation::AuthMobileSession, params)
-            .map_err(|msg| format!("Authentication failed: {}", msg))?;
+    pub fn set_username(&mut self, username: &str) {
+        self.username = Some(username.to_owned()).filter(|username| !username.is_empty());
+    }
 
-        let decoded: AuthResponse = serde_json::from_str(body.as_str())
-            .map_err(|err| format!("Authentication failed: {}", err))?;
+    // Requests a token for the desktop authentication flow. The request is signed, but needs no session or user
+    // credentials.
+    pub fn request_auth_token(&self) -> Result<String, ScrobblerError> {
+        let mut params = Params::new();
+        params.insert_api_key(self.auth.api_key());
+        let correlation_id = new_correlation_id();
+        let body = self.api_request(&ApiOperation::AuthToken, params, &correlation_id);
+        let decoded: TokenResponse = decode_call(body, "Token request failed", correlation_id)?;
 
-        self.auth.set_session_key(&decoded.session.key);
+        Ok(decoded.token)
+    }
 
-        Ok(decoded.session)
+    pub fn authorization_url(&self, token: &str) -> String {
+        let mut params = Params::new();
+        params.insert_api_key(self.auth.api_key());
+        params.insert_token(token);
+
+        format!("{}?{}", self.endpoint().auth_url, params.to_form_body())
     }
 
-    pub fn authenticate_with_token(&mut self) -> Result<SessionResponse, String> {
+    pub fn authenticate_with_password(&mut self) -> Result<SessionResponse, ScrobblerError> {
         let params = self.auth.get_auth_request_params()?;
+        let correlation_id = new_correlation_id();
+        let body = self.api_request(&ApiOperation::AuthMobileSession, params, &correlation_id);
 
-        let body = self
-            .api_request(&ApiOperation::AuthWebSession, params)
-            .map_err(|msg| format!("Authentication failed: {}", msg))?;
+        self.start_session(body, correlation_id)
+    }
 
-        let decoded: AuthResponse = serde_json::from_str(body.as_str())
-            .map_err(|err| format!("Authentication failed: {}", err))?;
+    pub fn authenticate_with_token(&mut self) -> Result<SessionResponse, ScrobblerError> {
+        let params = self.auth.get_auth_request_params()?;
+        let correlation_id = new_correlation_id();
+        let body = self.api_request(&ApiOperation::AuthWebSession, params, &correlation_id);
+
+        self.start_session(body, correlation_id)
+    }
 
-        self.auth.set_session_key(&decoded.session.key);
+    // Authenticates with the session in an authentication response
+    fn start_session(
+        &mut self,
+        body: Result<String, ScrobblerError>,
+        correlation_id: String,
+    ) -> Result<SessionResponse, ScrobblerError> {
+        let decoded: AuthResponse = decode_call(body, "Authentication failed", correlation_id)?;
+        self.auth.set_session_key(decoded.session.key.as_str());
+        self.set_username(&decoded.session.name);
 
         Ok(decoded.session)
     }
 
+    /// Validates and signs an authentication request without sending it, then authenticates with a placeholder
+    /// session key. Used for dry runs.
+    pub fn dry_run_authenticate(
+        &mut self,
+        operation: &ApiOperation,
+    ) -> Result<SessionResponse, ScrobblerError> {
+        let params = self.auth.get_auth_request_params()?;
+        self.sign_request(operation, params);
+
+        let session = SessionResponse {
+            key: DRY_RUN_SESSION_KEY.to_owned(),
+            subscriber: 0,
+            name: String::new(),
+        };
+        self.auth.set_session_key(session.key.as_str());
+
+        Ok(session)
+    }
+
     /// Authenticates with a session key 
     /// 
     /// This requires no initial authentication with the API, so we simply store the key. It must be a valid session
-    /// key. Session keys are documented at `Scrobbler::authenticate_with_session_key`.
-    pub fn authenticate_with_session_key(&mut self, session_key: &str) {
-        self.auth.set_session_key(session_key)
+    /// key. Session keys are documented at `Scrobbler::authenticate_with_session_key`. An empty key is rejected.
+    pub fn authenticate_with_session_key(&mut self, session_key: impl Into<SessionKey>) -> Result<(), ScrobblerError> {
+        self.auth.set_session_key(session_key.into().non_empty()?);
+        // The key alone doesn't say whose session it is
+        self.username = None;
+        Ok(())
     }
 
     pub fn session_key(&self) -> Option<&str> {
@@ -104,109 +592,803 @@ impl LastFm {
 
     pub fn send_now_playing(
         &self,
-        params: &HashMap<String, String>,
-    ) -> Result<NowPlayingResponse, String> {
-        let body = self
-            .send_authenticated_request(&ApiOperation::NowPlaying, params)
-            .map_err(|msg| format!("Now playing request failed: {}", msg))?;
-
-        let decoded: NowPlayingResponseWrapper = serde_json::from_str(body.as_str())
-            .map_err(|msg| format!("Now playing request failed: {}", msg))?;
+        params: Params,
+    ) -> Result<NowPlayingResponse, ScrobblerError> {
+        let correlation_id = new_correlation_id();
+        let body = self.send_authenticated_request(&ApiOperation::NowPlaying, params, &correlation_id);
+        let decoded: NowPlayingResponseWrapper = decode_call(body, "Now playing request failed", correlation_id)?;
 
         Ok(decoded.nowplaying)
     }
 
     pub fn send_scrobble(
         &self,
-        params: &HashMap<String, String>,
-    ) -> Result<ScrobbleResponse, String> {
-        let body = self
-            .send_authenticated_request(&ApiOperation::Scrobble, params)
-            .map_err(|msg| format!("Scrobble request failed: {}", msg))?;
+        params: Params,
+    ) -> Result<ScrobbleResponse, ScrobblerError> {
+        let correlation_id = new_correlation_id();
+        let body = self.send_authenticated_request(&ApiOperation::Scrobble, params, &correlation_id);
+        let decoded: ScrobbleResponseWrapper = decode_call(body, "Scrobble request failed", correlation_id.clone())?;
+
+        Ok(decoded
+            .scrobbles
+            .scrobble
+            .with_counts(decoded.scrobbles.counts)
+            .with_correlation_id(correlation_id))
+    }
 
-        let decoded: ScrobbleResponseWrapper = serde_json::from_str(body.as_str())
-            .map_err(|msg| format!("Scrobble request failed: {}", msg))?;
+    pub fn send_batch_scrobbles(
+        &self,
+        params: Params,
+    ) -> Result<BatchScrobbleResponse, ScrobblerError> {
+        let correlation_id = new_correlation_id();
+        let body = self.send_authenticated_request(&ApiOperation::Scrobble, params, &correlation_id);
+        let wrapper = decode_call(body, "Batch scrobble request failed", correlation_id.clone())?;
 
-        Ok(decoded.scrobbles.scrobble)
+        Ok(batch_response(wrapper, &correlation_id))
     }
 
-    pub fn send_batch_scrobbles(
+    /// Loves or unloves a track, retrying transient failures under the given policy
+    pub fn send_love(
         &self,
-        params: &HashMap<String, String>,
-    ) -> Result<BatchScrobbleResponse, String> {
-        let body = self
-            .send_authenticated_request(&ApiOperation::Scrobble, params)
-            .map_err(|msg| format!("Batch scrobble request failed: {}", msg))?;
+        operation: &ApiOperation,
+        params: Params,
+        retry_policy: &RetryPolicy,
+    ) -> Result<(), ScrobblerError> {
+        let context = match operation {
+            ApiOperation::Unlove => "Unlove request failed",
+            _ => "Love request failed",
+        };
+        let correlation_id = new_correlation_id();
 
-        let wrapper: BatchScrobbleResponseWrapper = serde_json::from_str(body.as_str())
-            .map_err(|msg| format!("Batch scrobble request failed: {}", msg))?;
+        let _: serde_json::Value = self
+            .authenticated_params(params)
+            .and_then(|req_params| self.api_request_with_policy(operation, req_params, &correlation_id, retry_policy))
+            .and_then(|body| decode_response(&body))
+            .map_err(|err| call_error(context, err, correlation_id))?;
 
-        Ok(BatchScrobbleResponse {
-            scrobbles: wrapper.scrobbles.scrobbles,
-        })
+        Ok(())
+    }
+
+    pub fn send_track_info(&self, params: &Params) -> Result<TrackInfo, ScrobblerError> {
+        let mut params = params.clone();
+        if let Some(autocorrect) = self.autocorrect {
+            params.insert_autocorrect(autocorrect);
+        }
+        let decoded: TrackInfoWrapper =
+            self.send_read_request(&ApiOperation::TrackInfo, &params, "Track info request failed")?;
+
+        let mut info = decoded.track;
+        let renamed = |name: &str, looked_up: Option<&str>| {
+            looked_up.is_some_and(|looked_up| matching::fold(name) != matching::fold(looked_up))
+        };
+        info.autocorrected = self.autocorrect == Some(true)
+            && (renamed(info.artist(), params.get("artist")) || renamed(info.name(), params.get("track")));
+
+        Ok(info)
+    }
+
+    pub fn send_artist_info(&self, params: &Params) -> Result<ArtistInfo, ScrobblerError> {
+        let params = self.localized_params(params);
+        let decoded: ArtistInfoWrapper =
+            self.send_read_request(&ApiOperation::ArtistInfo, &params, "Artist info request failed")?;
+
+        Ok(decoded.artist)
+    }
+
+    pub fn send_album_info(&self, params: &Params) -> Result<AlbumInfo, ScrobblerError> {
+        let params = self.localized_params(params);
+        let decoded: AlbumInfoWrapper =
+            self.send_read_request(&ApiOperation::AlbumInfo, &params, "Album info request failed")?;
+
+        Ok(decoded.album)
+    }
+
+    // The parameters of a lookup of localized text, which also accepts `autocorrect`
+    fn localized_params(&self, params: &Params) -> Params {
+        let mut params = params.clone();
+        if let Some(autocorrect) = self.autocorrect {
+            params.insert_autocorrect(autocorrect);
+        }
+        if let Some(language) = &self.language {
+            params.insert_language(language);
+        }
+
+        params
+    }
+
+    pub fn send_track_correction(&self, params: &Params) -> Result<CorrectionResponse, ScrobblerError> {
+        let decoded: CorrectionWrapper =
+            self.send_read_request(&ApiOperation::TrackCorrection, params, "Track correction request failed")?;
+
+        let param = |name| params.get(name).unwrap_or_default();
+        Ok(decoded.into_response(param("artist"), param("track")))
+    }
+
+    pub fn send_recent_tracks(&self, params: &Params) -> Result<RecentTracks, ScrobblerError> {
+        let decoded: RecentTracksWrapper =
+            self.send_read_request(&ApiOperation::RecentTracks, params, "Recent tracks request failed")?;
+
+        Ok(decoded.recenttracks)
+    }
+
+    /// Looks up the authenticated user's profile, which also confirms the session key is still valid
+    pub fn send_user_info(&self) -> Result<UserInfo, ScrobblerError> {
+        let req_params = self.authenticated_params(Params::new())?;
+        let correlation_id = new_correlation_id();
+        let body = self.api_request(&ApiOperation::UserInfo, req_params, &correlation_id);
+        let decoded: UserInfoWrapper = decode_call(body, "User info request failed", correlation_id)?;
+
+        Ok(decoded.user)
+    }
+
+    /// Sends a signed call to any API method, with the session key, returning the decoded response as it is
+    pub fn send_signed_call(&self, method: &str, params: Params) -> Result<serde_json::Value, ScrobblerError> {
+        let req_params = self.authenticated_params(params)?;
+        let correlation_id = new_correlation_id();
+        let body = self.api_request(&ApiOperation::Signed(method.to_owned()), req_params, &correlation_id);
+
+        decode_call(body, &format!("{method} request failed"), correlation_id)
+    }
+
+    // Sends a read-only request, which needs no session, answering it from the cache if possible. Only responses
+    // which decode are cached.
+    fn send_read_request<T: DeserializeOwned>(
+        &self,
+        operation: &ApiOperation,
+        params: &Params,
+        context: &str,
+    ) -> Result<T, ScrobblerError> {
+        debug_assert!(operation.is_read_only(), "{} is not read-only", operation);
+        let mut req_params = params.clone();
+        req_params.insert_api_key(self.auth.api_key());
+        req_params.insert_method(operation.method());
+
+        let key = ResponseCache::key(&req_params);
+        let mut validators = None;
+        if !self.bypass_cache {
+            let cached = self.cache.get_at(&key, Instant::now());
+            if let Some(decoded) = cached.and_then(|body| decode_response(&body).ok()) {
+                return Ok(decoded);
+            }
+            validators = self.cache.validators(&key);
+        }
+
+        let correlation_id = new_correlation_id();
+        let retry_policy = self.retry_policy_for(operation.call_class());
+        let resp = self
+            .send_call(operation, req_params, &correlation_id, &retry_policy, validators.as_ref())
+            .map_err(|err| call_error(context, err, correlation_id.clone()))?;
+        if resp.status == NOT_MODIFIED {
+            let body = self.cache.revalidate_at(&key, Instant::now()).ok_or_else(|| {
+                let err = ScrobblerError::http(NOT_MODIFIED, "Not Modified, but nothing is cached".to_owned());
+                call_error(context, err, correlation_id.clone())
+            })?;
+            return decode_response(&body).map_err(|err| call_error(context, err, correlation_id));
+        }
+
+        let decoded = decode_response(&resp.body).map_err(|err| call_error(context, err, correlation_id))?;
+        let validators = Validators::from_headers(&resp.headers);
+        self.cache.insert_at(key, resp.body, validators, Instant::now());
+
+        Ok(decoded)
     }
 
     pub fn send_authenticated_request(
         &self,
         operation: &ApiOperation,
-        params: &HashMap<String, String>,
-    ) -> Result<String, String> {
+        params: Params,
+        correlation_id: &str,
+    ) -> Result<String, ScrobblerError> {
+        let req_params = self.authenticated_params(params)?;
+
+        self.api_request(operation, req_params, correlation_id)
+    }
+
+    /// Builds and signs the full parameter set of an authenticated request, exactly as it would be sent, without
+    /// sending it. Used for dry runs.
+    pub fn sign_authenticated_request(
+        &self,
+        operation: &ApiOperation,
+        params: Params,
+    ) -> Result<Params, ScrobblerError> {
+        let req_params = self.authenticated_params(params)?;
+
+        Ok(self.sign_request(operation, req_params))
+    }
+
+    // Adds the parameters common to every authenticated request to those of one call. Only the cached `api_key` and
+    // `sk` are copied, into the call's own parameters.
+    fn authenticated_params(&self, mut params: Params) -> Result<Params, ScrobblerError> {
         if !self.auth.is_authenticated() {
-            return Err("Not authenticated".to_string());
+            return Err(ScrobblerError::not_authenticated());
         }
 
-        let mut req_params = self.auth.get_request_params();
-        for (k, v) in params {
-            req_params.insert(k.clone(), v.clone());
-        }
+        params.extend(self.auth.get_request_params());
 
-        self.api_request(&operation, req_params)
+        Ok(params)
     }
 
     fn api_request(
         &self,
         operation: &ApiOperation,
-        params: HashMap<String, String>,
-    ) -> Result<String, String> {
-        let mut resp = self
-            .send_request(&operation, params)
-            .map_err(|err| err.to_string())?;
-
-        let status = resp.status();
-        if status != StatusCode::OK {
-            return Err(format!("Non Success status ({})", status));
+        params: Params,
+        correlation_id: &str,
+    ) -> Result<String, ScrobblerError> {
+        let retry_policy = self.retry_policy_for(operation.call_class());
+
+        self.api_request_with_policy(operation, params, correlation_id, &retry_policy)
+    }
+
+    fn api_request_with_policy(
+        &self,
+        operation: &ApiOperation,
+        params: Params,
+        correlation_id: &str,
+        retry_policy: &RetryPolicy,
+    ) -> Result<String, ScrobblerError> {
+        self.send_call(operation, params, correlation_id, retry_policy, None)
+            .map(|resp| resp.body)
+    }
+
+    // Sends a call, retrying it as the policy allows. Given the validators of a cached response the request is
+    // conditional, and a `304 Not Modified` response is returned as a success.
+    fn send_call(
+        &self,
+        operation: &ApiOperation,
+        params: Params,
+        correlation_id: &str,
+        retry_policy: &RetryPolicy,
+        validators: Option<&Validators>,
+    ) -> Result<HttpResponse, ScrobblerError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("lastfm_call", method = operation.method(), correlation_id).entered();
+
+        let params = self.sign_request(operation, params);
+        let call = self.start_call(operation, correlation_id, validators.is_some())?;
+
+        let mut attempt = 1;
+        let (result, outcome) = loop {
+            self.rate_limiter.acquire(operation.lane());
+            self.record_usage();
+            let result = self.send_request(operation, &params, correlation_id, validators);
+            let outcome = Self::request_outcome(&result, call.conditional);
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                correlation_id,
+                method = %call.method,
+                nonce = params.get("nonce"),
+                attempt,
+                outcome = ?outcome,
+                "Last.fm API request attempt"
+            );
+
+            let Some(delay) = self.schedule_retry(operation, &call, retry_policy, attempt, &result, &outcome) else {
+                break (result, outcome);
+            };
+            thread::sleep(delay);
+            attempt += 1;
+        };
+
+        let result = self.finish_call(&call, &params, attempt, result, outcome);
+        self.health.record_at(&result, SystemTime::now());
+        result
+    }
+
+    // Records an attempt with the circuit breaker. If the call should be retried, announces the retry and returns
+    // how long to wait before it.
+    fn schedule_retry(
+        &self,
+        operation: &ApiOperation,
+        call: &Call<'_>,
+        retry_policy: &RetryPolicy,
+        attempt: u32,
+        result: &Result<HttpResponse, ScrobblerError>,
+        outcome: &RequestOutcome,
+    ) -> Option<Duration> {
+        let retryable = self.record_attempt(result, outcome) && operation.call_class().retries(outcome);
+        if !retryable || attempt >= retry_policy.max_attempts.max(1) {
+            return None;
         }
 
-        let mut resp_body = String::new();
-        resp.read_to_string(&mut resp_body)
-            .map_err(|_| "Failed to read response body".to_string())?;
+        let delay = retry_policy.jittered_delay(attempt);
+        if *outcome == RequestOutcome::ApiError(LastFmErrorCode::RateLimitExceeded) {
+            self.notify(&ScrobblerEvent::RateLimited { wait: delay });
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.record_retry(call.method);
+        self.notify(&ScrobblerEvent::RetryScheduled {
+            method: call.method.to_owned(),
+            attempt,
+            delay,
+        });
+        Some(delay)
+    }
 
-        Ok(resp_body)
+    // Checks that the circuit breaker and usage thresholds allow a call, and announces it
+    fn start_call<'a>(
+        &self,
+        operation: &'a ApiOperation,
+        correlation_id: &'a str,
+        conditional: bool,
+    ) -> Result<Call<'a>, ScrobblerError> {
+        let (allowed, change) = self.circuit_breaker.allow_at(Instant::now());
+        self.notify_circuit(change);
+        allowed.map_err(ScrobblerError::circuit_open)?;
+        self.usage
+            .check_at(unix_now())
+            .map_err(|(window, limit)| ScrobblerError::usage_limit_reached(window, limit))?;
+
+        let call = Call {
+            method: operation.method(),
+            correlation_id,
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            conditional,
+        };
+        self.notify(&ScrobblerEvent::RequestStarted {
+            method: call.method.to_owned(),
+        });
+
+        Ok(call)
+    }
+
+    // Counts a request towards the estimated usage, warning of any soft threshold it reaches
+    fn record_usage(&self) {
+        for (window, estimated, threshold) in self.usage.record_at(unix_now()) {
+            self.notify(&ScrobblerEvent::UsageThresholdReached {
+                window,
+                estimated,
+                threshold,
+            });
+        }
     }
 
+    // Records an attempt with the circuit breaker, returning whether it failed in a way worth retrying
+    fn record_attempt(&self, result: &Result<HttpResponse, ScrobblerError>, outcome: &RequestOutcome) -> bool {
+        // A response too large once will be too large again
+        let too_large = matches!(result, Err(err) if err.kind() == ErrorKind::ResponseTooLarge);
+        let transient = !too_large && Self::is_transient(outcome);
+        let change = self.circuit_breaker.record_at(transient, Instant::now());
+        self.notify_circuit(change);
+
+        transient && change != Some(CircuitState::Open)
+    }
+
+    // Logs a finished call, and turns the response to its last attempt into a successful response or an error
+    fn finish_call(
+        &self,
+        call: &Call<'_>,
+        params: &Params,
+        attempts: u32,
+        result: Result<HttpResponse, ScrobblerError>,
+        outcome: RequestOutcome,
+    ) -> Result<HttpResponse, ScrobblerError> {
+        let api_code = match outcome {
+            RequestOutcome::ApiError(code) => Some(code),
+            _ => None,
+        };
+        let signature_error = if api_code == Some(LastFmErrorCode::InvalidSignature) {
+            Some(self.signature_error(call.method, params, call.correlation_id))
+        } else {
+            None
+        };
+
+        let elapsed = call.started.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = result.as_ref().ok().map(|resp| resp.status),
+            outcome = ?outcome,
+            attempts,
+            elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            "Last.fm API call finished"
+        );
+        #[cfg(feature = "metrics")]
+        self.metrics.record_request(call.method, &outcome, elapsed);
+        self.call_log.record(CallRecord::new(
+            call.started_at,
+            call.method,
+            call.correlation_id,
+            params,
+            attempts,
+            &outcome,
+            elapsed,
+        ));
+        self.notify(&ScrobblerEvent::RequestFinished {
+            method: call.method.to_owned(),
+            outcome,
+            elapsed,
+        });
+
+        let resp = result.map_err(|err| match err.kind() {
+            ErrorKind::ResponseTooLarge => err,
+            _ => ScrobblerError::transport(err.to_string()),
+        })?;
+        if let Some(location) = redirect_location(&resp) {
+            return Err(ScrobblerError::redirected(resp.status, location));
+        }
+        if !Self::is_success(resp.status, call.conditional) {
+            let msg = signature_error.unwrap_or_else(|| format!("Non Success status ({})", resp.status));
+            return Err(match api_code {
+                Some(code) => ScrobblerError::api(code, resp.status, msg),
+                None => ScrobblerError::http(resp.status, msg),
+            });
+        }
+
+        Ok(resp)
+    }
+
+    // Describes a rejected signature in enough detail to debug it, without exposing any parameter values
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn signature_error(&self, method: &str, params: &Params, correlation_id: &str) -> String {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            correlation_id,
+            method,
+            base_string = %signing::redacted_base_string(params, self.signer.as_ref()),
+            "Last.fm rejected the request signature"
+        );
+
+        format!(
+            "Invalid method signature supplied (signed parameters: {}; signature base string length: {} bytes, \
+             excluding the API secret)",
+            signing::signed_param_names(params, self.signer.as_ref()).join(", "),
+            signing::base_string(params, self.signer.as_ref()).len()
+        )
+    }
+
+    fn request_outcome(result: &Result<HttpResponse, ScrobblerError>, conditional: bool) -> RequestOutcome {
+        match result {
+            Ok(resp) if Self::is_success(resp.status, conditional) => RequestOutcome::Success,
+            Ok(resp) => match api_error_code(&resp.body) {
+                Some(code) => RequestOutcome::ApiError(code),
+                None => RequestOutcome::HttpStatus(resp.status),
+            },
+            Err(err) => RequestOutcome::TransportError(err.to_string()),
+        }
+    }
+
+    fn is_success(status: u16, conditional: bool) -> bool {
+        status == 200 || (conditional && status == NOT_MODIFIED)
+    }
+
+    fn is_transient(outcome: &RequestOutcome) -> bool {
+        match outcome {
+            RequestOutcome::Success => false,
+            RequestOutcome::ApiError(code) => code.is_retryable(),
+            RequestOutcome::HttpStatus(status) => *status >= 500,
+            RequestOutcome::TransportError(_) => true,
+        }
+    }
+
+    fn notify_circuit(&self, change: Option<CircuitState>) {
+        if let Some(state) = change {
+            self.notify(&ScrobblerEvent::CircuitStateChanged { state });
+        }
+    }
+
+    pub fn notify(&self, event: &ScrobblerEvent) {
+        if let Some(observer) = &self.observer {
+            observer.notify(event);
+        }
+    }
+
+    fn sign_request(&self, operation: &ApiOperation, mut params: Params) -> Params {
+        // Read-only methods don't need a signature, so are sent unsigned by a client with nothing to sign them with
+        if operation.is_read_only() && !self.auth.has_api_secret() {
+            params.insert_method(operation.method());
+            return params;
+        }
+
+        if self.include_nonce {
+            params.insert_nonce(new_nonce());
+        }
+        let signature = self.auth.get_signature(self.signer.as_ref(), operation.method(), &params);
+
+        params.insert_method(operation.method());
+        params.insert_signature(signature);
+
+        params
+    }
+
+    // Sends one attempt at a request. Redirects of read-only requests are followed; any other redirect is returned,
+    // as re-sending a signed request somewhere else isn't safe.
     fn send_request(
         &self,
         operation: &ApiOperation,
-        mut params: HashMap<String, String>,
-    ) -> Result<reqwest::Response, reqwest::Error> {
-        #[cfg(not(test))]
-        let url = "https://ws.audioscrobbler.com/2.0/?format=json";
-        #[cfg(test)]
-        let url = &mockito::server_url();
+        params: &Params,
+        correlation_id: &str,
+        validators: Option<&Validators>,
+    ) -> Result<HttpResponse, ScrobblerError> {
+        let mut request = self.http_request(operation, params, correlation_id);
+        if let Some(validators) = validators {
+            validators.add_conditions(&mut request.headers);
+        }
 
-        let signature = self.auth.get_signature(operation.to_string(), &params);
+        let mut redirects = 0;
+        loop {
+            let resp = self.check_response_size(self.transport.send(&request)?)?;
+            let target = match self.redirect_target(&request.url, &resp, params) {
+                Some(target) if operation.is_read_only() && redirects < MAX_REDIRECTS => target,
+                _ => return Ok(resp),
+            };
+            redirects += 1;
+            request.http_method = HttpMethod::choose(true, &target.api_url, params);
+            request.url = target.api_url;
+        }
+    }
 
-        params.insert("method".to_string(), operation.to_string());
-        params.insert("api_sig".to_string(), signature);
+    // Where a response redirects a request sent to `request_url`, if it is a redirect to a valid API URL. If enabled,
+    // a permanent redirect also moves the endpoint itself.
+    fn redirect_target(&self, request_url: &str, resp: &HttpResponse, params: &Params) -> Option<Endpoint> {
+        let location = redirect_location(resp)?;
+        let target = Endpoint::redirected(request_url, location, params).ok()?;
+        if self.update_base_url_on_redirect && matches!(resp.status, 301 | 308) {
+            let mut endpoint = self.endpoint.write().unwrap_or_else(PoisonError::into_inner);
+            // Unless another call has already moved it
+            if endpoint.api_url == request_url {
+                *endpoint = target.clone();
+            }
+        }
 
-        self.http_client.post(url).form(&params).send()
+        Some(target)
     }
+
+    fn http_request(&self, operation: &ApiOperation, params: &Params, correlation_id: &str) -> HttpRequest {
+        let base_url = self.base_url();
+        HttpRequest {
+            http_method: HttpMethod::choose(operation.is_read_only(), &base_url, params),
+            url: base_url,
+            params: params.clone(),
+            request_id: if self.send_request_id {
+                Some(correlation_id.to_owned())
+            } else {
+                None
+            },
+            max_response_size: self.max_response_size,
+            headers: self.extra_headers.clone(),
+            json_body: None,
+        }
+    }
+
+    // In case the transport didn't enforce the limit itself
+    fn check_response_size(&self, resp: HttpResponse) -> Result<HttpResponse, ScrobblerError> {
+        if resp.body.len() as u64 > self.max_response_size {
+            return Err(ScrobblerError::response_too_large(self.max_response_size, None));
+        }
+
+        Ok(resp)
+    }
+}
+
+// The same calls, sent through an `AsyncTransport`. They're rate limited and retried as blocking calls are, but wait
+// without blocking the thread.
+#[cfg(feature = "async")]
+impl LastFm {
+    pub async fn authenticate_async(
+        &mut self,
+        transport: &dyn AsyncTransport,
+        operation: &ApiOperation,
+    ) -> Result<SessionResponse, ScrobblerError> {
+        let params = self.auth.get_auth_request_params()?;
+        let correlation_id = new_correlation_id();
+        let body = self.api_request_async(transport, operation, params, &correlation_id).await;
+
+        self.start_session(body, correlation_id)
+    }
+
+    pub async fn send_now_playing_async(
+        &self,
+        transport: &dyn AsyncTransport,
+        params: Params,
+    ) -> Result<NowPlayingResponse, ScrobblerError> {
+        let correlation_id = new_correlation_id();
+        let body = self
+            .send_authenticated_async(transport, &ApiOperation::NowPlaying, params, &correlation_id)
+            .await;
+        let decoded: NowPlayingResponseWrapper = decode_call(body, "Now playing request failed", correlation_id)?;
+
+        Ok(decoded.nowplaying)
+    }
+
+    pub async fn send_scrobble_async(
+        &self,
+        transport: &dyn AsyncTransport,
+        params: Params,
+    ) -> Result<ScrobbleResponse, ScrobblerError> {
+        let correlation_id = new_correlation_id();
+        let body = self
+            .send_authenticated_async(transport, &ApiOperation::Scrobble, params, &correlation_id)
+            .await;
+        let decoded: ScrobbleResponseWrapper = decode_call(body, "Scrobble request failed", correlation_id.clone())?;
+
+        Ok(decoded
+            .scrobbles
+            .scrobble
+            .with_counts(decoded.scrobbles.counts)
+            .with_correlation_id(correlation_id))
+    }
+
+    pub async fn send_batch_scrobbles_async(
+        &self,
+        transport: &dyn AsyncTransport,
+        params: Params,
+    ) -> Result<BatchScrobbleResponse, ScrobblerError> {
+        let correlation_id = new_correlation_id();
+        let body = self
+            .send_authenticated_async(transport, &ApiOperation::Scrobble, params, &correlation_id)
+            .await;
+        let wrapper = decode_call(body, "Batch scrobble request failed", correlation_id.clone())?;
+
+        Ok(batch_response(wrapper, &correlation_id))
+    }
+
+    async fn send_authenticated_async(
+        &self,
+        transport: &dyn AsyncTransport,
+        operation: &ApiOperation,
+        params: Params,
+        correlation_id: &str,
+    ) -> Result<String, ScrobblerError> {
+        let req_params = self.authenticated_params(params)?;
+
+        self.api_request_async(transport, operation, req_params, correlation_id).await
+    }
+
+    async fn api_request_async(
+        &self,
+        transport: &dyn AsyncTransport,
+        operation: &ApiOperation,
+        params: Params,
+        correlation_id: &str,
+    ) -> Result<String, ScrobblerError> {
+        let send = async {
+            let params = self.sign_request(operation, params);
+            let retry_policy = self.retry_policy_for(operation.call_class());
+            let call = self.start_call(operation, correlation_id, false)?;
+
+            let mut attempt = 1;
+            let (result, outcome) = loop {
+                self.rate_limiter.acquire_async(operation.lane()).await;
+                let request = self.http_request(operation, &params, correlation_id);
+                let url = request.url.clone();
+                self.record_usage();
+                let result = transport
+                    .send(request)
+                    .await
+                    .and_then(|resp| self.check_response_size(resp));
+                if let Ok(resp) = &result {
+                    self.redirect_target(&url, resp, &params);
+                }
+                let outcome = Self::request_outcome(&result, false);
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    correlation_id,
+                    method = %call.method,
+                    nonce = params.get("nonce"),
+                    attempt,
+                    outcome = ?outcome,
+                    "Last.fm API request attempt"
+                );
+
+                let Some(delay) = self.schedule_retry(operation, &call, &retry_policy, attempt, &result, &outcome)
+                else {
+                    break (result, outcome);
+                };
+                async_transport::sleep(delay).await;
+                attempt += 1;
+            };
+
+            let result = self.finish_call(&call, &params, attempt, result, outcome);
+            self.health.record_at(&result, SystemTime::now());
+            result.map(|resp| resp.body)
+        };
+
+        // The same span as a blocking call's, entered only while the future is polled
+        #[cfg(feature = "tracing")]
+        let send = tracing::Instrument::instrument(
+            send,
+            tracing::debug_span!("lastfm_call", method = operation.method(), correlation_id),
+        );
+        send.await
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+// The `Location` of a redirect response
+fn redirect_location(resp: &HttpResponse) -> Option<&str> {
+    if matches!(resp.status, 301 | 302 | 303 | 307 | 308) {
+        resp.headers.get("location")
+    } else {
+        None
+    }
+}
+
+// Generates a short random ID identifying one logical API call, shared by all of its attempts
+fn new_correlation_id() -> String {
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(CALLS.fetch_add(1, Ordering::Relaxed));
+    format!("{:08x}", hasher.finish() & 0xffff_ffff)
+}
+
+// Generates a random UUID (version 4), sent as the `nonce` of a signed request
+fn new_nonce() -> String {
+    let random = || RandomState::new().build_hasher().finish();
+    let mut uuid = (u128::from(random()) << 64) | u128::from(random());
+    // The version (4) and variant (RFC 4122) bits
+    uuid = (uuid & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        uuid >> 96,
+        (uuid >> 80) & 0xffff,
+        (uuid >> 64) & 0xffff,
+        (uuid >> 48) & 0xffff,
+        uuid & 0xffff_ffff_ffff
+    )
+}
+
+fn call_error(context: &str, err: ScrobblerError, correlation_id: String) -> ScrobblerError {
+    err.context(context).with_correlation_id(correlation_id)
+}
+
+// Decodes the response body of a call, or explains why it failed
+fn decode_call<T: DeserializeOwned>(
+    body: Result<String, ScrobblerError>,
+    context: &str,
+    correlation_id: String,
+) -> Result<T, ScrobblerError> {
+    body.and_then(|body| decode_response(&body))
+        .map_err(|err| call_error(context, err, correlation_id))
+}
+
+fn batch_response(wrapper: BatchScrobbleResponseWrapper, correlation_id: &str) -> BatchScrobbleResponse {
+    BatchScrobbleResponse {
+        scrobbles: wrapper
+            .scrobbles
+            .into_reconciled()
+            .into_iter()
+            .map(|scrobble| scrobble.with_correlation_id(correlation_id.to_owned()))
+            .collect(),
+        dry_run: false,
+        preparation: PreparationReport::default(),
+    }
+}
+
+fn decode_response<T: DeserializeOwned>(body: &str) -> Result<T, ScrobblerError> {
+    decode_body(body).map_err(ScrobblerError::decode)
+}
+
+// Decodes a response body. Bodies are expected to be JSON; with the `xml` feature enabled, XML bodies from servers
+// which ignore `format=json` are decoded too.
+fn decode_body<T: DeserializeOwned>(body: &str) -> Result<T, String> {
+    match serde_json::from_str(body) {
+        Ok(decoded) => Ok(decoded),
+        #[cfg(feature = "xml")]
+        Err(_) if crate::xml::is_xml(body) => {
+            serde_json::from_value(crate::xml::to_json(body)?).map_err(|err| err.to_string())
+        }
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// Extracts the error code from a Last.fm error response, ie. `{"error": 9, "message": "Invalid session key"}`
+fn api_error_code(body: &str) -> Option<LastFmErrorCode> {
+    let value: serde_json::Value = decode_body(body).ok()?;
+    u32::try_from(value.get("error")?.as_u64()?).ok().map(LastFmErrorCode::from)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use mockito::mock;
+    use crate::error::ErrorKind;
 
     #[test]
     fn check_send_api_requests() {
@@ -217,20 +1399,20 @@ mod tests {
         client.auth.set_user_credentials("username", "password");
         let params = client.auth.get_auth_request_params().unwrap();
 
-        let resp = client.api_request(&ApiOperation::AuthWebSession, params.clone());
+        let resp = client.api_request(&ApiOperation::AuthWebSession, params.clone(), "id");
         assert!(resp.is_ok());
-        let resp = client.api_request(&ApiOperation::AuthMobileSession, params.clone());
+        let resp = client.api_request(&ApiOperation::AuthMobileSession, params.clone(), "id");
         assert!(resp.is_ok());
-        let resp = client.api_request(&ApiOperation::Scrobble, params.clone());
+        let resp = client.api_request(&ApiOperation::Scrobble, params.clone(), "id");
         assert!(resp.is_ok());
-        let resp = client.api_request(&ApiOperation::NowPlaying, params.clone());
+        let resp = client.api_request(&ApiOperation::NowPlaying, params.clone(), "id");
         assert!(resp.is_ok());
 
         // authenticated request
-        let resp = client.send_authenticated_request(&ApiOperation::NowPlaying, &params);
+        let resp = client.send_authenticated_request(&ApiOperation::NowPlaying, params.clone(), "id");
         assert!(resp.is_err());
         client.auth.set_session_key("sesh");
-        let resp = client.send_authenticated_request(&ApiOperation::NowPlaying, &params);
+        let resp = client.send_authenticated_request(&ApiOperation::NowPlaying, params, "id");
         assert!(resp.is_ok());
     }
 
@@ -243,7 +1425,7 @@ mod tests {
         client.auth.set_session_key("SeshKey");
         let params = client.auth.get_auth_request_params().unwrap();
 
-        let resp = client.send_scrobble(&params);
+        let resp = client.send_scrobble(params.clone());
         assert!(resp.is_err());
 
         let _m = mock("POST", mockito::Matcher::Any)
@@ -262,7 +1444,7 @@ mod tests {
             )
             .create();
 
-        let resp = client.send_scrobble(&params);
+        let resp = client.send_scrobble(params);
         assert!(resp.is_ok());
     }
 
@@ -275,7 +1457,7 @@ mod tests {
         client.auth.set_session_key("SeshKey");
         let params = client.auth.get_auth_request_params().unwrap();
 
-        let resp = client.send_batch_scrobbles(&params);
+        let resp = client.send_batch_scrobbles(params.clone());
         assert!(resp.is_err());
 
         let _m = mock("POST", mockito::Matcher::Any)
@@ -297,7 +1479,7 @@ mod tests {
             )
             .create();
 
-        let resp = client.send_batch_scrobbles(&params);
+        let resp = client.send_batch_scrobbles(params);
         assert!(resp.is_err());
     }
 
@@ -310,7 +1492,7 @@ mod tests {
         client.auth.set_session_key("SeshKey");
         let params = client.auth.get_auth_request_params().unwrap();
 
-        let resp = client.send_now_playing(&params);
+        let resp = client.send_now_playing(params.clone());
         assert!(resp.is_err());
 
         let _m = mock("POST", mockito::Matcher::Any)
@@ -329,7 +1511,7 @@ mod tests {
             )
             .create();
 
-        let resp = client.send_now_playing(&params);
+        let resp = client.send_now_playing(params);
         assert!(resp.is_ok());
     }
 
@@ -362,11 +1544,88 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    #[cfg(feature = "xml")]
+    fn check_xml_responses() {
+        let mut client = LastFm::new("key", "secret");
+        client.set_user_credentials("user", "pass");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(include_str!("../tests/fixtures/xml/session.xml"))
+            .create();
+
+        let session = client.authenticate_with_password().unwrap();
+        assert_eq!(session.key, "d580d57f32848f5dcf574d1ce18d78b2");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(include_str!("../tests/fixtures/xml/scrobble.xml"))
+            .create();
+
+        let resp = client.send_scrobble(Params::new()).unwrap();
+        assert_eq!(resp.artist.text, "Björk");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(403)
+            .with_body(include_str!("../tests/fixtures/xml/error.xml"))
+            .create();
+
+        let result = client.send_scrobble(Params::new());
+        assert!(result.is_err());
+        assert_eq!(
+            LastFm::request_outcome(
+                &Ok(HttpResponse {
+                    status: 403,
+                    body: include_str!("../tests/fixtures/xml/error.xml").to_owned(),
+                    headers: Headers::new(),
+                }),
+                false
+            ),
+            RequestOutcome::ApiError(LastFmErrorCode::InvalidSessionKey)
+        );
+    }
+
+    #[test]
+    fn check_invalid_signature_diagnostics() {
+        let mut client = LastFm::new("key", "secret");
+        client.auth.set_session_key("SeshKey");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(403)
+            .with_body(
+                crate::test_util::fake::error_response(13, "Invalid method signature supplied")
+                    .to_string(),
+            )
+            .create();
+
+        let mut params = Params::new();
+        params.insert("artist", "floyd");
+        params.insert("track", "bananas");
+
+        let err = client.send_scrobble(params).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Api);
+        assert_eq!(err.api_code(), Some(13));
+        assert_eq!(err.http_status(), Some(403));
+        assert!(!err.is_retryable());
+
+        let err = err.to_string();
+        let base_len = "api_keykeyartistfloydmethodtrack.scrobbleskSeshKeytrackbananas".len();
+        assert_eq!(
+            err,
+            format!(
+                "Scrobble request failed: Invalid method signature supplied (signed parameters: api_key, \
+                 artist, method, sk, track; signature base string length: {base_len} bytes, excluding the API \
+                 secret)"
+            )
+        );
+        assert!(!err.contains("SeshKey"));
+        assert!(!err.contains("floyd"));
+    }
+
     #[test]
     fn check_session_key_authentication() {
         let mut client = LastFm::new("key", "secret");
         client.set_user_credentials("user", "pass");
-        client.authenticate_with_session_key("seshkey");
+        client.authenticate_with_session_key("seshkey").unwrap();
         assert_eq!("seshkey", client.session_key().unwrap());
     }
 }
@@ -0,0 +1,189 @@
+// The error codes Last.fm's API responds with, as listed at https://www.last.fm/api/errorcodes
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+// Declares `LastFmErrorCode`, converting each variant to and from its code
+macro_rules! error_codes {
+    ($($(#[$doc:meta])* $variant:ident = $code:literal: $description:literal,)*) => {
+        /// A Last.fm API error code, ie. [`InvalidSessionKey`] for `9`. See [`ScrobblerError::error_code`].
+        ///
+        /// Converts to and from the `u32` Last.fm sends, and serializes as it. Codes Last.fm doesn't document are
+        /// kept as `Unknown`. Displays as the description Last.fm documents.
+        ///
+        /// [`InvalidSessionKey`]: #variant.InvalidSessionKey
+        /// [`ScrobblerError::error_code`]: struct.ScrobblerError.html#method.error_code
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "u32", into = "u32")]
+        pub enum LastFmErrorCode {
+            $($(#[$doc])* $variant,)*
+            /// A code Last.fm doesn't document
+            Unknown(u32),
+        }
+
+        impl LastFmErrorCode {
+            /// Returns the code as sent by Last.fm
+            pub fn code(self) -> u32 {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Unknown(code) => code,
+                }
+            }
+
+            fn description(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant => Some($description),)*
+                    Self::Unknown(_) => None,
+                }
+            }
+        }
+
+        impl From<u32> for LastFmErrorCode {
+            fn from(code: u32) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    code => Self::Unknown(code),
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// This service does not exist (2)
+    InvalidService = 2: "Invalid service - This service does not exist",
+    /// No method with that name in this package (3)
+    InvalidMethod = 3: "Invalid Method - No method with that name in this package",
+    /// You do not have permissions to access the service (4)
+    AuthenticationFailed = 4: "Authentication Failed - You do not have permissions to access the service",
+    /// This service doesn't exist in that format (5)
+    InvalidFormat = 5: "Invalid format - This service doesn't exist in that format",
+    /// Your request is missing a required parameter (6)
+    InvalidParameters = 6: "Invalid parameters - Your request is missing a required parameter",
+    /// Invalid resource specified (7)
+    InvalidResource = 7: "Invalid resource specified",
+    /// Something else went wrong (8)
+    OperationFailed = 8: "Operation failed - Something else went wrong",
+    /// Please re-authenticate (9)
+    InvalidSessionKey = 9: "Invalid session key - Please re-authenticate",
+    /// You must be granted a valid key by Last.fm (10)
+    InvalidApiKey = 10: "Invalid API key - You must be granted a valid key by last.fm",
+    /// This service is temporarily offline (11)
+    ServiceOffline = 11: "Service Offline - This service is temporarily offline. Try again later.",
+    /// This station is only available to paid Last.fm subscribers (12)
+    SubscribersOnly = 12: "Subscribers Only - This station is only available to paid last.fm subscribers",
+    /// Invalid method signature supplied (13)
+    InvalidSignature = 13: "Invalid method signature supplied",
+    /// This token has not been authorized (14)
+    UnauthorizedToken = 14: "Unauthorized Token - This token has not been authorized",
+    /// This token has expired (15)
+    TokenExpired = 15: "This token has expired",
+    /// There was a temporary error processing your request (16)
+    TemporaryError = 16: "There was a temporary error processing your request. Please try again",
+    /// User requires to be logged in (17)
+    LoginRequired = 17: "Login: User requires to be logged in",
+    /// Your free trial has expired (18)
+    TrialExpired = 18: "Trial Expired - This user has no free radio plays left. Subscription required.",
+    /// There is not enough content to play this station (20)
+    NotEnoughContent = 20: "Not Enough Content - There is not enough content to play this station",
+    /// This group does not have enough members for radio (21)
+    NotEnoughMembers = 21: "Not Enough Members - This group does not have enough members for radio",
+    /// This artist does not have enough fans for radio (22)
+    NotEnoughFans = 22: "Not Enough Fans - This artist does not have enough fans for for radio",
+    /// There are not enough neighbours for radio (23)
+    NotEnoughNeighbours = 23: "Not Enough Neighbours - There are not enough neighbours for radio",
+    /// User is not logged in (24)
+    NoPeakRadio = 24: "No Peak Radio - This user is not allowed to listen to radio during peak usage",
+    /// Radio station not found (25)
+    RadioNotFound = 25: "Radio Not Found - Radio station not found",
+    /// This application is not allowed to make requests to the web services (26)
+    ApiKeySuspended = 26: "API Key Suspended - This application is not allowed to make requests to the web services",
+    /// This type of request is no longer supported (27)
+    Deprecated = 27: "Deprecated - This type of request is no longer supported",
+    /// Your IP has made too many requests in a short period (29)
+    RateLimitExceeded = 29: "Rate Limit Exceded - Your IP has made too many requests in a short period",
+}
+
+impl LastFmErrorCode {
+    // Credentials which are invalid, or no longer valid
+    pub(crate) fn is_auth_failure(self) -> bool {
+        matches!(
+            self,
+            Self::AuthenticationFailed | Self::InvalidSessionKey | Self::UnauthorizedToken | Self::TokenExpired
+        )
+    }
+
+    // Failures expected to be transient
+    pub(crate) fn is_retryable(self) -> bool {
+        matches!(self, Self::ServiceOffline | Self::TemporaryError | Self::RateLimitExceeded)
+    }
+}
+
+impl From<LastFmErrorCode> for u32 {
+    fn from(code: LastFmErrorCode) -> Self {
+        code.code()
+    }
+}
+
+impl fmt::Display for LastFmErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.description() {
+            Some(description) => f.write_str(description),
+            None => write!(f, "Unknown error code {}", self.code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_error_codes() {
+        let documented = [
+            (2, LastFmErrorCode::InvalidService),
+            (3, LastFmErrorCode::InvalidMethod),
+            (4, LastFmErrorCode::AuthenticationFailed),
+            (5, LastFmErrorCode::InvalidFormat),
+            (6, LastFmErrorCode::InvalidParameters),
+            (7, LastFmErrorCode::InvalidResource),
+            (8, LastFmErrorCode::OperationFailed),
+            (9, LastFmErrorCode::InvalidSessionKey),
+            (10, LastFmErrorCode::InvalidApiKey),
+            (11, LastFmErrorCode::ServiceOffline),
+            (12, LastFmErrorCode::SubscribersOnly),
+            (13, LastFmErrorCode::InvalidSignature),
+            (14, LastFmErrorCode::UnauthorizedToken),
+            (15, LastFmErrorCode::TokenExpired),
+            (16, LastFmErrorCode::TemporaryError),
+            (17, LastFmErrorCode::LoginRequired),
+            (18, LastFmErrorCode::TrialExpired),
+            (20, LastFmErrorCode::NotEnoughContent),
+            (21, LastFmErrorCode::NotEnoughMembers),
+            (22, LastFmErrorCode::NotEnoughFans),
+            (23, LastFmErrorCode::NotEnoughNeighbours),
+            (24, LastFmErrorCode::NoPeakRadio),
+            (25, LastFmErrorCode::RadioNotFound),
+            (26, LastFmErrorCode::ApiKeySuspended),
+            (27, LastFmErrorCode::Deprecated),
+            (29, LastFmErrorCode::RateLimitExceeded),
+        ];
+        for &(code, error_code) in &documented {
+            assert_eq!(LastFmErrorCode::from(code), error_code);
+            assert_eq!(u32::from(error_code), code);
+            assert!(!error_code.to_string().starts_with("Unknown"), "{}", code);
+        }
+        assert_eq!(LastFmErrorCode::InvalidSessionKey.to_string(), "Invalid session key - Please re-authenticate");
+
+        for &code in &[0, 1, 19, 28, 30, 1000] {
+            assert_eq!(LastFmErrorCode::from(code), LastFmErrorCode::Unknown(code));
+            assert_eq!(LastFmErrorCode::Unknown(code).code(), code);
+            assert_eq!(LastFmErrorCode::Unknown(code).to_string(), format!("Unknown error code {code}"));
+        }
+
+        let json = serde_json::to_string(&[LastFmErrorCode::RateLimitExceeded, LastFmErrorCode::Unknown(99)]);
+        assert_eq!(json.unwrap(), "[29,99]");
+        let codes: Vec<LastFmErrorCode> = serde_json::from_str("[9,42]").unwrap();
+        assert_eq!(codes, [LastFmErrorCode::InvalidSessionKey, LastFmErrorCode::Unknown(42)]);
+    }
+}
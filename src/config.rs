@@ -0,0 +1,225 @@
+// The settings of a Scrobbler which can be changed while it runs, ie. by a daemon reloading its configuration
+use crate::cache::CachePolicy;
+use crate::circuit::CircuitBreakerPolicy;
+use crate::error::ScrobblerError;
+use crate::rate_limit::RateLimit;
+use crate::retry::RetryPolicy;
+use crate::transport::ReqwestTransport;
+use crate::usage::UsageThresholds;
+
+use std::time::Duration;
+
+/// A snapshot of the settings of a [`Scrobbler`] which can be changed while it runs, ie. when a daemon reloads its
+/// configuration file. Take one with [`Scrobbler::config`], change what's needed, and apply it with
+/// [`Scrobbler::apply_config`].
+///
+/// Each field is set as the `Scrobbler`'s setter of the same name would set it. The HTTP settings (`timeout`,
+/// `proxy` and `user_agent`) are those the `Scrobbler`'s transport was created with, and can only be changed if the
+/// `Scrobbler` created it itself, rather than being given one with [`Scrobbler::with_transport`]. The proxy URL may
+/// include credentials, so unlike a [`ClientInfo`], a config shouldn't be logged as it is.
+///
+/// # Usage
+/// ```ignore
+/// let mut config = scrobbler.config();
+/// config.timeout = Some(Duration::from_secs(10));
+/// config.dry_run = settings.dry_run;
+/// scrobbler.apply_config(&config)?;
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`Scrobbler::config`]: struct.Scrobbler.html#method.config
+/// [`Scrobbler::apply_config`]: struct.Scrobbler.html#method.apply_config
+/// [`Scrobbler::with_transport`]: struct.Scrobbler.html#method.with_transport
+/// [`ClientInfo`]: struct.ClientInfo.html
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ScrobblerConfig {
+    /// The URL API requests are sent to. See [`ScrobblerBuilder::base_url`].
+    ///
+    /// [`ScrobblerBuilder::base_url`]: struct.ScrobblerBuilder.html#method.base_url
+    pub base_url: String,
+    /// The timeout of each HTTP request, if there is one
+    pub timeout: Option<Duration>,
+    /// The URL of the proxy requests are sent through, if any
+    pub proxy: Option<String>,
+    /// The `User-Agent` header sent with requests, if not `reqwest`'s own
+    pub user_agent: Option<String>,
+    pub dry_run: bool,
+    /// How authentication requests are retried. See [`Scrobbler::set_retry_policy_for`].
+    ///
+    /// [`Scrobbler::set_retry_policy_for`]: struct.Scrobbler.html#method.set_retry_policy_for
+    pub auth_retry_policy: RetryPolicy,
+    /// How Now Playing and Scrobble requests are retried
+    pub write_retry_policy: RetryPolicy,
+    /// How read-only requests are retried
+    pub read_retry_policy: RetryPolicy,
+    pub rate_limit: Option<RateLimit>,
+    pub circuit_breaker: Option<CircuitBreakerPolicy>,
+    pub usage_thresholds: Option<UsageThresholds>,
+    pub cache: Option<CachePolicy>,
+    pub max_response_size: u64,
+    pub fail_on_ignored: bool,
+    pub now_playing_best_effort: bool,
+    pub strict: bool,
+    pub max_scrobble_age: Option<Duration>,
+    pub health_probe_interval: Duration,
+}
+
+// The settings a `Scrobbler` created its own transport with, so that it can create another with different ones
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HttpSettings {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) proxy: Option<String>,
+    pub(crate) user_agent: Option<String>,
+}
+
+impl HttpSettings {
+    pub(crate) fn of(config: &ScrobblerConfig) -> Self {
+        Self {
+            timeout: config.timeout,
+            proxy: config.proxy.clone(),
+            user_agent: config.user_agent.clone(),
+        }
+    }
+
+    // The name of the first setting which differs in `other`, if any
+    pub(crate) fn changed(&self, other: &Self) -> Option<&'static str> {
+        if self.timeout != other.timeout {
+            Some("timeout")
+        } else if self.proxy != other.proxy {
+            Some("proxy")
+        } else if self.user_agent != other.user_agent {
+            Some("User-Agent")
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn transport(&self) -> Result<ReqwestTransport, ScrobblerError> {
+        if *self == Self::default() {
+            Ok(ReqwestTransport::new())
+        } else {
+            ReqwestTransport::with_settings(self.timeout, self.proxy.as_deref(), self.user_agent.as_deref())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::models::metadata::Scrobble;
+    use crate::queue::ScrobbleQueue;
+    use crate::scrobbler::Scrobbler;
+    use crate::test_util::EchoTransport;
+    use mockito::{mock, Matcher};
+    use std::time::UNIX_EPOCH;
+
+    fn scrobble(track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new("floyd", track, "bananas");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_config_round_trip() {
+        let mut scrobbler = Scrobbler::builder("api_key", "api_secret")
+            .timeout(Duration::from_secs(10))
+            .user_agent("tapedeck/1.0")
+            .build()
+            .unwrap();
+        let config = scrobbler.config();
+        assert_eq!(config.timeout, Some(Duration::from_secs(10)));
+        assert_eq!((config.proxy.as_deref(), config.user_agent.as_deref()), (None, Some("tapedeck/1.0")));
+        assert_eq!(config.write_retry_policy, RetryPolicy::none());
+        assert!(!config.dry_run);
+
+        // Applying the snapshot unchanged changes nothing
+        scrobbler.apply_config(&config).unwrap();
+        assert_eq!(scrobbler.config(), config);
+
+        let mut changed = config.clone();
+        changed.user_agent = Some("tapedeck/1.1".to_owned());
+        changed.rate_limit = Some(RateLimit::last_fm());
+        changed.circuit_breaker = Some(CircuitBreakerPolicy {
+            failure_threshold: 3,
+            cooldown: Duration::from_mins(1),
+        });
+        changed.max_scrobble_age = None;
+        changed.strict = true;
+        scrobbler.apply_config(&changed).unwrap();
+        assert_eq!(scrobbler.config(), changed);
+        assert_eq!(scrobbler.info().user_agent.as_deref(), Some("tapedeck/1.1"));
+        assert_eq!(scrobbler.rate_limit_utilization(), Some(0.0));
+
+        // Nothing is applied if any of it is invalid
+        let mut invalid = changed.clone();
+        invalid.dry_run = true;
+        invalid.base_url = "libre.fm/2.0/".to_owned();
+        assert!(scrobbler.apply_config(&invalid).is_err());
+        assert_eq!(scrobbler.config(), changed);
+    }
+
+    #[test]
+    fn check_config_with_custom_transport() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        let config = scrobbler.config();
+        assert_eq!((config.timeout, config.proxy.as_ref()), (None, None));
+
+        // The timeout of a transport the Scrobbler didn't create can't be changed
+        let mut timeout = config.clone();
+        timeout.timeout = Some(Duration::from_secs(5));
+        timeout.dry_run = true;
+        let err = scrobbler.apply_config(&timeout).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotReloadable);
+        assert!(!scrobbler.is_dry_run());
+
+        // Everything else can
+        let mut dry_run = config;
+        dry_run.dry_run = true;
+        scrobbler.apply_config(&dry_run).unwrap();
+        assert!(scrobbler.is_dry_run());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        assert!(scrobbler.scrobble(&Scrobble::new("floyd", "fruitflies", "bananas")).unwrap().dry_run());
+        assert!(echo.requests().is_empty());
+    }
+
+    #[test]
+    fn check_config_reload_keeps_queued_scrobbles() {
+        let path = std::env::temp_dir().join(format!("rustfm-config-{}.json", std::process::id()));
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let mut config = scrobbler.config();
+        config.dry_run = true;
+        scrobbler.apply_config(&config).unwrap();
+
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let mut queue = ScrobbleQueue::open(&path, scrobbler).unwrap();
+        queue.push(&scrobble("first", now - 600)).unwrap();
+        queue.push(&scrobble("second", now - 60)).unwrap();
+
+        // Moving to another server with another timeout, and out of dry-run mode, keeps everything queued
+        let relay = mock("POST", "/relay/")
+            .match_query(Matcher::UrlEncoded("format".into(), "json".into()))
+            .with_status(503)
+            .expect(1)
+            .create();
+        config.dry_run = false;
+        config.base_url = format!("{}/relay/", mockito::server_url());
+        config.timeout = Some(Duration::from_secs(5));
+        queue.apply_config(&config).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.scrobbler().base_url(), format!("{}/relay/?format=json", mockito::server_url()));
+        assert!(!queue.scrobbler().is_dry_run());
+
+        // Until they're flushed to the new server
+        assert_eq!(queue.flush().unwrap().failed, 2);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.scrobbler().config().timeout, Some(Duration::from_secs(5)));
+        relay.assert();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+// At-rest encryption of persisted files, with ChaCha20-Poly1305
+//
+// An encrypted file is laid out as:
+//
+//   magic (4 bytes, "RFSE") | format version (1 byte) | key check (16 bytes) | nonce (12 bytes) | ciphertext | tag
+//
+// The nonce is random, and fresh for every write. Everything before the ciphertext is authenticated along with it,
+// so the version can't be altered undetected. The key check is a hash of the key, which tells a file encrypted with
+// another key apart from one which has been tampered with.
+use crate::error::{ErrorKind, ScrobblerError};
+
+use openssl::rand::rand_bytes;
+use openssl::sha::Sha256;
+use openssl::symm::{self, Cipher};
+use std::fmt;
+use std::path::Path;
+
+const MAGIC: &[u8] = b"RFSE";
+const FORMAT_VERSION: u8 = 1;
+const KEY_CHECK_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + KEY_CHECK_LEN + NONCE_LEN;
+
+/// A 256-bit key for encrypting persisted files, ie. with [`ScrobbleQueue::open_encrypted`]. Requires the
+/// `crypto` feature.
+///
+/// The crate doesn't derive or store keys: the application supplies the key, ie. from the operating system's
+/// keychain. The key is redacted from `Debug` output.
+///
+/// Files are encrypted with ChaCha20-Poly1305, with a random nonce for each write. Opening a file with the wrong
+/// key fails with an error of kind `ErrorKind::WrongKey`, and opening one which has been modified fails with
+/// `ErrorKind::Tampered`.
+///
+/// [`ScrobbleQueue::open_encrypted`]: struct.ScrobbleQueue.html#method.open_encrypted
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Creates a key from its 32 bytes
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    fn check(&self) -> [u8; KEY_CHECK_LEN] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"rustfm-scrobble key check\0");
+        hasher.update(&self.0);
+        let mut check = [0; KEY_CHECK_LEN];
+        check.copy_from_slice(&hasher.finish()[..KEY_CHECK_LEN]);
+        check
+    }
+}
+
+impl From<[u8; 32]> for EncryptionKey {
+    fn from(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey([REDACTED])")
+    }
+}
+
+// Encrypts the contents of the file at `path`
+pub(crate) fn seal(key: &EncryptionKey, path: &Path, plaintext: &[u8]) -> Result<Vec<u8>, ScrobblerError> {
+    let failed = |err| ScrobblerError::new(format!("Failed to encrypt {}: {err}", path.display()));
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.push(FORMAT_VERSION);
+    header.extend_from_slice(&key.check());
+    let mut nonce = [0; NONCE_LEN];
+    rand_bytes(&mut nonce).map_err(failed)?;
+    header.extend_from_slice(&nonce);
+
+    let mut tag = [0; TAG_LEN];
+    let ciphertext =
+        symm::encrypt_aead(Cipher::chacha20_poly1305(), &key.0, Some(&nonce), &header, plaintext, &mut tag)
+            .map_err(failed)?;
+
+    let mut sealed = header;
+    sealed.extend_from_slice(&ciphertext);
+    sealed.extend_from_slice(&tag);
+    Ok(sealed)
+}
+
+// Decrypts the contents of the file at `path`, checking that they were encrypted with `key` and haven't been
+// modified since
+pub(crate) fn open(key: &EncryptionKey, path: &Path, sealed: &[u8]) -> Result<Vec<u8>, ScrobblerError> {
+    if sealed.len() < HEADER_LEN + TAG_LEN || !sealed.starts_with(MAGIC) {
+        return Err(ScrobblerError::decryption(ErrorKind::Tampered, path, "it isn't an encrypted file"));
+    }
+    let (header, body) = sealed.split_at(HEADER_LEN);
+    let version = header[MAGIC.len()];
+    let check = &header[MAGIC.len() + 1..MAGIC.len() + 1 + KEY_CHECK_LEN];
+    let nonce = &header[HEADER_LEN - NONCE_LEN..];
+
+    if check != key.check() {
+        return Err(ScrobblerError::decryption(ErrorKind::WrongKey, path, "it was encrypted with a different key"));
+    }
+    let (ciphertext, tag) = body.split_at(body.len() - TAG_LEN);
+    let plaintext = symm::decrypt_aead(Cipher::chacha20_poly1305(), &key.0, Some(nonce), header, ciphertext, tag)
+        .map_err(|_| ScrobblerError::decryption(ErrorKind::Tampered, path, "it has been modified"))?;
+
+    // The version is authenticated, so is only checked once it's known to be genuine
+    if version != FORMAT_VERSION {
+        return Err(ScrobblerError::new(format!(
+            "Unsupported encrypted file version {version} in {}",
+            path.display()
+        )));
+    }
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &[u8] = b"{\"version\":1,\"entries\":[]}";
+
+    fn path() -> &'static Path {
+        Path::new("queue.json")
+    }
+
+    #[test]
+    fn check_encryption_round_trips() {
+        let key = EncryptionKey::new([7; 32]);
+        let sealed = seal(&key, path(), PLAINTEXT).unwrap();
+        assert!(sealed.starts_with(b"RFSE\x01"));
+        assert_eq!(sealed.len(), HEADER_LEN + PLAINTEXT.len() + TAG_LEN);
+        assert_eq!(open(&key, path(), &sealed).unwrap(), PLAINTEXT);
+
+        // Every write has a fresh nonce
+        let resealed = seal(&key, path(), PLAINTEXT).unwrap();
+        assert_ne!(sealed, resealed);
+        assert_eq!(open(&key, path(), &resealed).unwrap(), PLAINTEXT);
+
+        assert_eq!(format!("{key:?}"), "EncryptionKey([REDACTED])");
+    }
+
+    #[test]
+    fn check_decryption_failures() {
+        let key = EncryptionKey::new([7; 32]);
+        let sealed = seal(&key, path(), PLAINTEXT).unwrap();
+
+        let err = open(&EncryptionKey::new([8; 32]), path(), &sealed).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WrongKey);
+        assert_eq!(err.to_string(), "Failed to decrypt queue.json: it was encrypted with a different key");
+        assert_eq!(err.path(), Some(path()));
+
+        // Flipping any bit of the ciphertext, tag or authenticated header is detected
+        for index in [HEADER_LEN, HEADER_LEN + 5, sealed.len() - 1, MAGIC.len(), HEADER_LEN - 1] {
+            let mut tampered = sealed.clone();
+            tampered[index] ^= 1;
+            let err = open(&key, path(), &tampered).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Tampered, "byte {index}");
+            assert_eq!(err.to_string(), "Failed to decrypt queue.json: it has been modified");
+        }
+
+        for invalid in [PLAINTEXT, &sealed[..HEADER_LEN + TAG_LEN - 1]] {
+            let err = open(&key, path(), invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Tampered);
+            assert_eq!(err.to_string(), "Failed to decrypt queue.json: it isn't an encrypted file");
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Request lifecycle events, delivered to a registered observer
+use crate::circuit::CircuitState;
+use crate::codes::LastFmErrorCode;
+use crate::usage::UsageWindow;
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An event in the lifecycle of a Last.fm API call, delivered to the observer registered with
+/// [`Scrobbler::set_event_observer`].
+///
+/// Each logical API call (ie. one `Scrobbler::scrobble` call) produces exactly one `RequestStarted` event, zero or
+/// more `RateLimited`/`RetryScheduled` events if the request is retried, and finally exactly one `RequestFinished`
+/// event carrying the outcome of the last attempt and the total time taken, including any retry delays. Any
+/// `FieldTruncated` events for the submitted tracks come before the `RequestStarted` event. A call rejected by an
+/// open circuit breaker produces no request events at all.
+///
+/// [`Scrobbler::set_event_observer`]: struct.Scrobbler.html#method.set_event_observer
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScrobblerEvent {
+    /// A request to the given API method is about to be sent
+    RequestStarted { method: String },
+    /// A request has completed, successfully or not, and will not be retried
+    RequestFinished {
+        method: String,
+        outcome: RequestOutcome,
+        elapsed: Duration,
+    },
+    /// A failed request will be retried after `delay`. `attempt` is the number of the attempt that failed,
+    /// starting at 1.
+    RetryScheduled {
+        method: String,
+        attempt: u32,
+        delay: Duration,
+    },
+    /// Last.fm reported that the rate limit was exceeded; no request will be sent for `wait`
+    RateLimited { wait: Duration },
+    /// An over-long artist, track or album name (`field`) was truncated before being submitted. See
+    /// [`Scrobbler::set_truncate_long_fields`].
+    ///
+    /// [`Scrobbler::set_truncate_long_fields`]: struct.Scrobbler.html#method.set_truncate_long_fields
+    FieldTruncated {
+        field: String,
+        original_chars: usize,
+        truncated_chars: usize,
+    },
+    /// The circuit breaker changed state. See [`CircuitBreakerPolicy`].
+    ///
+    /// [`CircuitBreakerPolicy`]: struct.CircuitBreakerPolicy.html
+    CircuitStateChanged { state: CircuitState },
+    /// A request took the estimated usage over `window` up to its soft `threshold`; it is sent as normal. See
+    /// [`UsageThresholds`].
+    ///
+    /// [`UsageThresholds`]: struct.UsageThresholds.html
+    UsageThresholdReached {
+        window: UsageWindow,
+        estimated: u32,
+        threshold: u32,
+    },
+    /// A now playing update failed for a transient reason, and was skipped rather than returned as an error. See
+    /// [`Scrobbler::set_now_playing_best_effort`].
+    ///
+    /// [`Scrobbler::set_now_playing_best_effort`]: struct.Scrobbler.html#method.set_now_playing_best_effort
+    NowPlayingSkipped { reason: String },
+}
+
+/// The outcome of a finished API request. See [`ScrobblerEvent::RequestFinished`].
+///
+/// [`ScrobblerEvent::RequestFinished`]: enum.ScrobblerEvent.html#variant.RequestFinished
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequestOutcome {
+    /// The API responded successfully
+    Success,
+    /// The API responded with the given Last.fm error code
+    ApiError(LastFmErrorCode),
+    /// The server responded with the given non-success HTTP status, and no Last.fm error
+    HttpStatus(u16),
+    /// No response was received
+    TransportError(String),
+}
+
+impl RequestOutcome {
+    pub fn is_success(&self) -> bool {
+        *self == Self::Success
+    }
+}
+
+// A registered observer. Observers are called on the thread making the API call, in event order, and never while
+// the client holds a lock.
+#[derive(Clone)]
+pub struct EventObserver(Arc<dyn Fn(&ScrobblerEvent) + Send + Sync>);
+
+impl EventObserver {
+    pub fn new<F>(observer: F) -> Self
+    where
+        F: Fn(&ScrobblerEvent) + Send + Sync + 'static,
+    {
+        Self(Arc::new(observer))
+    }
+
+    pub fn notify(&self, event: &ScrobblerEvent) {
+        (self.0)(event);
+    }
+}
+
+impl fmt::Debug for EventObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventObserver")
+    }
+}
@@ -0,0 +1,179 @@
+// Crediting several artists: detection of guest artists credited in the artist field, ie. "Artist feat. Guest",
+// and submission of tracks credited to a list of artists
+use serde::{Deserialize, Serialize};
+
+/// What [`Scrobble::split_featured_artists`] does with the guest artists it moves out of the artist field
+///
+/// [`Scrobble::split_featured_artists`]: struct.Scrobble.html#method.split_featured_artists
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FeaturedArtists {
+    /// Appended to the track name, as `"Track (feat. Guest)"`, unless the track name already credits a guest
+    AppendToTrack,
+    /// Dropped
+    Drop,
+}
+
+/// How a [`Scrobble`] credited to several artists (see [`Scrobble::with_artists`]) is submitted, as Last.fm only
+/// accepts one artist per track. Every policy submits the first artist as the primary one.
+///
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`Scrobble::with_artists`]: struct.Scrobble.html#method.with_artists
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ArtistJoinPolicy {
+    /// Only the first artist is submitted; the others are only kept on the `Scrobble`
+    #[default]
+    FirstOnly,
+    /// The other artists are appended to the track name, as `"Track (feat. B & C)"`, unless the track name already
+    /// credits a guest
+    AppendToTrack,
+    /// Every artist is submitted as the artist name, joined with the given separator, ie. `Join(", ".to_owned())`
+    Join(String),
+}
+
+impl ArtistJoinPolicy {
+    pub(crate) fn is_first_only(&self) -> bool {
+        *self == Self::FirstOnly
+    }
+}
+
+// Markers recognized anywhere in the artist name. A bare "ft" is too likely to be part of a name (ie. "Ft Worth").
+const MARKERS: &[&str] = &["featuring", "feat.", "feat", "ft."];
+
+// Markers recognized at the start of a parenthesized credit, ie. "(ft Guest)"
+const BRACKETED_MARKERS: &[&str] = &["featuring", "feat.", "feat", "ft.", "ft"];
+
+// Words which make a following marker part of a name, as in "The Ft. Lauderdale Sound"
+const ARTICLES: &[&str] = &["the", "a", "an"];
+
+// Splits an artist name crediting guests into the primary artist and the guests, or returns `None` if it doesn't
+// unambiguously credit any
+pub(crate) fn split(artist: &str) -> Option<(&str, &str)> {
+    split_bracketed(artist).or_else(|| split_bare(artist))
+}
+
+// "Artist (feat. Guest)" or "Artist [feat. Guest]", with the credit closing the name
+fn split_bracketed(artist: &str) -> Option<(&str, &str)> {
+    let artist = artist.trim_end();
+    let open = match artist.chars().last()? {
+        ')' => '(',
+        ']' => '[',
+        _ => return None,
+    };
+    let start = artist.rfind(open)?;
+    let credit = artist[start + 1..artist.len() - 1].trim();
+
+    let guest = BRACKETED_MARKERS
+        .iter()
+        .find_map(|marker| strip_marker(credit, marker))?;
+    let primary = artist[..start].trim();
+
+    (!primary.is_empty() && !guest.is_empty()).then_some((primary, guest))
+}
+
+// "Artist feat. Guest", split at the first marker
+fn split_bare(artist: &str) -> Option<(&str, &str)> {
+    let mut previous: Option<&str> = None;
+    for word in artist.split_whitespace() {
+        let is_marker = MARKERS.iter().any(|marker| word.eq_ignore_ascii_case(marker));
+        if let (true, Some(before)) = (is_marker, previous) {
+            if ARTICLES.iter().any(|article| before.eq_ignore_ascii_case(article)) {
+                return None;
+            }
+
+            let start = offset(artist, word);
+            let primary = artist[..start].trim();
+            let guest = artist[start + word.len()..].trim();
+            return (!guest.is_empty()).then_some((primary, guest));
+        }
+        previous = Some(word);
+    }
+
+    None
+}
+
+// Returns what follows `marker` and some whitespace at the start of `credit`, ignoring case
+fn strip_marker<'a>(credit: &'a str, marker: &str) -> Option<&'a str> {
+    let prefix = credit.get(..marker.len())?;
+    let rest = &credit[marker.len()..];
+    (prefix.eq_ignore_ascii_case(marker) && rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+// The byte offset of `word`, a subslice of `text`
+fn offset(text: &str, word: &str) -> usize {
+    word.as_ptr() as usize - text.as_ptr() as usize
+}
+
+// True if a track name already credits a guest, ie. "Track (feat. Guest)"
+pub(crate) fn credits_guest(track: &str) -> bool {
+    track.split_whitespace().any(|word| {
+        let word = word.trim_start_matches(['(', '[']);
+        BRACKETED_MARKERS.iter().any(|marker| word.eq_ignore_ascii_case(marker))
+    })
+}
+
+// Credits guests in a track name, as "Track (feat. B, C & D)"
+pub(crate) fn append_guests(track: &str, guests: &[String]) -> String {
+    let credit = match guests.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, others)) => format!("{} & {last}", others.join(", ")),
+        None => return track.to_owned(),
+    };
+
+    format!("{} (feat. {credit})", track.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_featured_artists_split() {
+        let cases = [
+            ("Mark Ronson feat. Bruno Mars", Some(("Mark Ronson", "Bruno Mars"))),
+            ("Calvin Harris ft. Rihanna", Some(("Calvin Harris", "Rihanna"))),
+            ("Daft Punk featuring Pharrell Williams", Some(("Daft Punk", "Pharrell Williams"))),
+            ("Major Lazer Feat Justin Bieber & MØ", Some(("Major Lazer", "Justin Bieber & MØ"))),
+            ("DJ Snake FEAT. Lil Jon", Some(("DJ Snake", "Lil Jon"))),
+            ("Eminem (feat. Rihanna)", Some(("Eminem", "Rihanna"))),
+            ("David Guetta [Feat. Sia]", Some(("David Guetta", "Sia"))),
+            ("Jay-Z (ft Kanye West)", Some(("Jay-Z", "Kanye West"))),
+            ("Sigur Rós (featuring Jónsi) ", Some(("Sigur Rós", "Jónsi"))),
+            ("Gorillaz feat. De La Soul feat. Gruff Rhys", Some(("Gorillaz", "De La Soul feat. Gruff Rhys"))),
+            // Names which merely contain a marker, or don't credit anyone with it
+            ("Daft Punk", None),
+            ("Craft Spells", None),
+            ("Featured Artists Club", None),
+            ("Featuring Nobody", None),
+            ("Ft. Worth Cats", None),
+            ("The Ft. Lauderdale Sound", None),
+            ("Swifty Feat", None),
+            ("DJ Ft Knox", None),
+            ("Artist (Live)", None),
+            ("Artist (feat. Guest) Remix", None),
+            ("(feat. Guest)", None),
+            ("Artist ()", None),
+            ("Björk", None),
+        ];
+
+        for (artist, expected) in &cases {
+            assert_eq!(split(artist), *expected, "{artist}");
+        }
+    }
+
+    #[test]
+    fn check_guests_appended() {
+        let guests = |names: &[&str]| names.iter().map(|name| (*name).to_owned()).collect::<Vec<_>>();
+        assert_eq!(append_guests("Track", &[]), "Track");
+        assert_eq!(append_guests("Track ", &guests(&["B"])), "Track (feat. B)");
+        assert_eq!(append_guests("Track", &guests(&["B", "C"])), "Track (feat. B & C)");
+        assert_eq!(append_guests("Track", &guests(&["B", "C", "D"])), "Track (feat. B, C & D)");
+    }
+
+    #[test]
+    fn check_track_credits_guest() {
+        assert!(credits_guest("Stay (feat. Justin Bieber)"));
+        assert!(credits_guest("Get Lucky [Ft Pharrell]"));
+        assert!(!credits_guest("Uptown Funk"));
+        assert!(!credits_guest("Left Feet"));
+    }
+}
@@ -0,0 +1,122 @@
+// Keeps the handles of one queue file in this process from flushing it at the same time
+use crate::queue::FlushReport;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+// The flushes in progress, by the canonical path of their queue's file
+static FLUSHES: Mutex<Vec<(PathBuf, Arc<Flush>)>> = Mutex::new(Vec::new());
+
+// A flush in progress, and how it turned out once it has finished
+#[derive(Default)]
+pub(crate) struct Flush {
+    outcome: Mutex<Outcome>,
+    finished: Condvar,
+}
+
+#[derive(Clone, Copy, Default)]
+enum Outcome {
+    #[default]
+    Running,
+    Succeeded(FlushReport),
+    Failed,
+}
+
+impl Flush {
+    // Waits for the flush to finish, returning its report, or `None` if it failed
+    pub(crate) fn wait(&self) -> Option<FlushReport> {
+        let outcome = lock(&self.outcome);
+        let outcome = self
+            .finished
+            .wait_while(outcome, |outcome| matches!(outcome, Outcome::Running))
+            .unwrap_or_else(PoisonError::into_inner);
+
+        match *outcome {
+            Outcome::Succeeded(report) => Some(report),
+            Outcome::Running | Outcome::Failed => None,
+        }
+    }
+}
+
+pub(crate) enum Begin {
+    // No other flush of the file is in progress, so this one may go ahead, holding the guard until it finishes
+    Started(FlushGuard),
+    InProgress(Arc<Flush>),
+}
+
+// Starts flushing the queue at `path`, unless another handle of it is already flushing it
+pub(crate) fn begin(path: &Path) -> Begin {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let mut flushes = lock(&FLUSHES);
+    if let Some((_, flush)) = flushes.iter().find(|(flushing, _)| *flushing == path) {
+        return Begin::InProgress(Arc::clone(flush));
+    }
+
+    let flush = Arc::new(Flush::default());
+    flushes.push((path, Arc::clone(&flush)));
+    Begin::Started(FlushGuard { flush })
+}
+
+// Marks the flush as finished, waking any handles waiting for it, when dropped
+pub(crate) struct FlushGuard {
+    flush: Arc<Flush>,
+}
+
+impl FlushGuard {
+    // Finishes the flush with its report, or `None` if it failed
+    pub(crate) fn finish(self, report: Option<FlushReport>) {
+        *lock(&self.flush.outcome) = report.map_or(Outcome::Failed, Outcome::Succeeded);
+    }
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        lock(&FLUSHES).retain(|(_, flush)| !Arc::ptr_eq(flush, &self.flush));
+        let mut outcome = lock(&self.flush.outcome);
+        if matches!(*outcome, Outcome::Running) {
+            *outcome = Outcome::Failed;
+        }
+        drop(outcome);
+        self.flush.finished.notify_all();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn check_flush_guard() {
+        let path = std::env::temp_dir().join(format!("rustfm-flush-{}.json", std::process::id()));
+        let Begin::Started(guard) = begin(&path) else {
+            panic!("no flush should be in progress");
+        };
+        let Begin::InProgress(flush) = begin(&path) else {
+            panic!("the first flush should be in progress");
+        };
+        assert!(matches!(begin(&path.with_extension("other")), Begin::Started(_)));
+
+        let waiter = thread::spawn(move || flush.wait());
+        guard.finish(Some(FlushReport {
+            accepted: 2,
+            ..FlushReport::default()
+        }));
+        assert_eq!(waiter.join().unwrap().map(|report| report.accepted), Some(2));
+
+        // A flush which fails, and so never finishes, wakes its waiters with no report
+        let Begin::Started(guard) = begin(&path) else {
+            panic!("the first flush should have finished");
+        };
+        let Begin::InProgress(flush) = begin(&path) else {
+            panic!("the second flush should be in progress");
+        };
+        drop(guard);
+        assert_eq!(flush.wait(), None);
+    }
+}
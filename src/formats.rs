@@ -0,0 +1,308 @@
+// Reading and writing the `.scrobbler.log` files kept by portable players, ie. those running Rockbox
+use crate::error::{FileOperation, ScrobblerError};
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+const HEADER: &str = "#AUDIOSCROBBLER/";
+
+/// The time zone of a `.scrobbler.log`'s timestamps, as given by its `#TZ/` header line
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LogTimezone {
+    /// Timestamps are in seconds since the Unix epoch, as Last.fm expects
+    Utc,
+    /// Timestamps are the player's local time, counted as if it were UTC: they're off by the player's UTC offset
+    /// until corrected with [`ScrobblerLog::adjust_for_utc_offset`]
+    ///
+    /// [`ScrobblerLog::adjust_for_utc_offset`]: struct.ScrobblerLog.html#method.adjust_for_utc_offset
+    Unknown,
+}
+
+/// The listens recorded in a `.scrobbler.log` file, the Audioscrobbler portable player log format written by
+/// Rockbox and other offline players, ie. to submit with [`Scrobbler::scrobble_batch_chunked`].
+///
+/// Only tracks rated `L` (listened to) are read as scrobbles; those rated `S` (skipped) are only counted. Each
+/// scrobble has the log's timestamp, and its track number, duration and `MusicBrainz` ID when the log gives them.
+/// Writing a log, with its `Display` output, rates every scrobble `L`.
+///
+/// # Usage
+/// ```ignore
+/// let mut log = ScrobblerLog::open("/media/player/.scrobbler.log")?;
+/// if log.timezone() == LogTimezone::Unknown {
+///     log.adjust_for_utc_offset(local_offset_secs);
+/// }
+/// scrobbler.scrobble_batch_chunked(log.scrobbles())?;
+/// ```
+///
+/// [`Scrobbler::scrobble_batch_chunked`]: struct.Scrobbler.html#method.scrobble_batch_chunked
+#[derive(Clone, Debug)]
+pub struct ScrobblerLog {
+    client: String,
+    timezone: LogTimezone,
+    scrobbles: ScrobbleBatch,
+    skipped: usize,
+}
+
+impl ScrobblerLog {
+    /// Creates a log of `batch`, to write out, naming `client` as the program which wrote it. Fails if a scrobble
+    /// has no timestamp, which every entry in a log needs.
+    pub fn from_batch(client: &str, batch: &ScrobbleBatch) -> Result<Self> {
+        if let Some(position) = batch.iter().position(|scrobble| scrobble.timestamp().is_none()) {
+            return Err(ScrobblerError::new(format!(
+                "Scrobble {position} has no timestamp, so can't be written to a .scrobbler.log"
+            )));
+        }
+
+        Ok(Self {
+            client: clean(client),
+            timezone: LogTimezone::Utc,
+            scrobbles: batch.clone(),
+            skipped: 0,
+        })
+    }
+
+    /// Reads a log from the file at `path`. See [`ScrobblerLog::parse`].
+    ///
+    /// [`ScrobblerLog::parse`]: struct.ScrobblerLog.html#method.parse
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| ScrobblerError::persistence(FileOperation::Read, path, err))?;
+        // Players write whatever their tags hold, which isn't always valid UTF-8
+        Self::parse(&String::from_utf8_lossy(&bytes))
+    }
+
+    /// Parses the contents of a log. Fails if it doesn't start with an `#AUDIOSCROBBLER/` header, or an entry
+    /// lacks its artist, title, rating or timestamp, giving the entry's line number.
+    pub fn parse(log: &str) -> Result<Self> {
+        let mut lines = log.lines().enumerate();
+        if !lines.next().is_some_and(|(_, line)| line.trim_start_matches('\u{feff}').starts_with(HEADER)) {
+            return Err(ScrobblerError::new("Not a .scrobbler.log: missing the #AUDIOSCROBBLER header".to_owned()));
+        }
+
+        let mut client = String::new();
+        let mut timezone = LogTimezone::Unknown;
+        let mut scrobbles = Vec::new();
+        let mut skipped = 0;
+        for (index, line) in lines {
+            let line = line.trim_end_matches('\r');
+            if let Some(header) = line.strip_prefix('#') {
+                if let Some(zone) = header.strip_prefix("TZ/") {
+                    timezone = if zone == "UTC" { LogTimezone::Utc } else { LogTimezone::Unknown };
+                } else if let Some(name) = header.strip_prefix("CLIENT/") {
+                    name.clone_into(&mut client);
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match parse_entry(line).map_err(|problem| {
+                ScrobblerError::new(format!("Invalid .scrobbler.log entry on line {}: {problem}", index + 1))
+            })? {
+                Some(scrobble) => scrobbles.push(scrobble),
+                None => skipped += 1,
+            }
+        }
+
+        Ok(Self {
+            client,
+            timezone,
+            scrobbles: ScrobbleBatch::from(scrobbles),
+            skipped,
+        })
+    }
+
+    /// Returns the name of the program which wrote the log, from its `#CLIENT/` header line, or an empty string
+    pub fn client(&self) -> &str {
+        &self.client
+    }
+
+    pub fn timezone(&self) -> LogTimezone {
+        self.timezone
+    }
+
+    /// Returns the tracks listened to, in the order the log lists them
+    pub fn scrobbles(&self) -> &ScrobbleBatch {
+        &self.scrobbles
+    }
+
+    /// Consumes the log, returning the tracks listened to
+    pub fn into_scrobbles(self) -> ScrobbleBatch {
+        self.scrobbles
+    }
+
+    /// Returns the number of tracks the log rates as skipped, which aren't scrobbled
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Converts the timestamps of a log in local time ([`LogTimezone::Unknown`]) to UTC, given the player's UTC
+    /// offset in seconds (ie. `3600` for UTC+1), and marks the log as UTC. Does nothing to a log already in UTC.
+    ///
+    /// [`LogTimezone::Unknown`]: enum.LogTimezone.html#variant.Unknown
+    pub fn adjust_for_utc_offset(&mut self, offset_secs: i64) {
+        if self.timezone == LogTimezone::Utc {
+            return;
+        }
+
+        let adjusted: Vec<Scrobble> = self
+            .scrobbles
+            .iter()
+            .map(|scrobble| {
+                let mut scrobble = scrobble.clone();
+                if let Some(timestamp) = scrobble.timestamp() {
+                    scrobble.with_timestamp(timestamp.saturating_add_signed(-offset_secs));
+                }
+                scrobble
+            })
+            .collect();
+        self.scrobbles = ScrobbleBatch::from(adjusted);
+        self.timezone = LogTimezone::Utc;
+    }
+}
+
+impl fmt::Display for ScrobblerLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let timezone = match self.timezone {
+            LogTimezone::Utc => "UTC",
+            LogTimezone::Unknown => "UNKNOWN",
+        };
+        writeln!(f, "{HEADER}1.1")?;
+        writeln!(f, "#TZ/{timezone}")?;
+        writeln!(f, "#CLIENT/{}", self.client)?;
+
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        for scrobble in &self.scrobbles {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\tL\t{}\t{}",
+                clean(scrobble.artist()),
+                clean(scrobble.album()),
+                clean(scrobble.track()),
+                optional(scrobble.track_number().map(|number| number.to_string())),
+                optional(scrobble.duration().map(|duration| duration.to_string())),
+                optional(scrobble.timestamp().map(|timestamp| timestamp.to_string())),
+                clean(scrobble.mbid().unwrap_or_default()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Parses an entry: artist, album, title, track number, duration, rating, timestamp and (from version 1.1)
+// MusicBrainz track ID, separated by tabs. Returns `None` for a skipped track.
+fn parse_entry(line: &str) -> std::result::Result<Option<Scrobble>, String> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if !(7..=8).contains(&fields.len()) {
+        return Err(format!("expected 7 or 8 tab-separated fields, found {}", fields.len()));
+    }
+    let (artist, album, track) = (fields[0].trim(), fields[1].trim(), fields[2].trim());
+    if artist.is_empty() || track.is_empty() {
+        return Err("missing the artist or title".to_owned());
+    }
+    match fields[5] {
+        "L" => {}
+        "S" => return Ok(None),
+        rating => return Err(format!("unknown rating {rating:?}")),
+    }
+
+    let mut scrobble = Scrobble::new(artist, track, album);
+    let timestamp = fields[6].trim().parse().map_err(|_| format!("invalid timestamp {:?}", fields[6]))?;
+    scrobble.with_timestamp(timestamp);
+    if let Ok(track_number) = fields[3].trim().parse() {
+        scrobble.with_track_number(track_number);
+    }
+    if let Some(duration) = fields[4].trim().parse().ok().filter(|duration| *duration > 0) {
+        scrobble.with_duration(duration);
+    }
+    if let Some(mbid) = fields.get(7).map(|mbid| mbid.trim()).filter(|mbid| !mbid.is_empty()) {
+        scrobble.with_mbid(mbid);
+    }
+    Ok(Some(scrobble))
+}
+
+// Replaces the tabs and line breaks which would break a log's lines
+fn clean(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "#AUDIOSCROBBLER/1.1\n\
+                       #TZ/UNKNOWN\n\
+                       #CLIENT/Rockbox sansae200 $Revision$\n\
+                       Kraftwerk\tComputerwelt\tComputerliebe\t5\t435\tL\t1500000000\t\n\
+                       Kraftwerk\tComputerwelt\tNumbers\t\t199\tS\t1500000435\n\
+                       Sigur Rós\t()\tUntitled #1\t1\t0\tL\t1500000634\t7f1d7ee8-3b4c-4a55-9e8b-4c6a1bba5a1b\r\n";
+
+    #[test]
+    fn check_scrobbler_log_parse() {
+        let mut log = ScrobblerLog::parse(LOG).unwrap();
+        assert_eq!(log.client(), "Rockbox sansae200 $Revision$");
+        assert_eq!((log.timezone(), log.skipped()), (LogTimezone::Unknown, 1));
+
+        let scrobbles: Vec<&Scrobble> = log.scrobbles().iter().collect();
+        assert_eq!(scrobbles.len(), 2);
+        assert_eq!((scrobbles[0].artist(), scrobbles[0].track()), ("Kraftwerk", "Computerliebe"));
+        assert_eq!(scrobbles[0].album(), "Computerwelt");
+        assert_eq!((scrobbles[0].track_number(), scrobbles[0].duration()), (Some(5), Some(435)));
+        assert_eq!((scrobbles[0].timestamp(), scrobbles[0].mbid()), (Some(1_500_000_000), None));
+        // A zero length means the player didn't know it
+        assert_eq!(scrobbles[1].duration(), None);
+        assert_eq!(scrobbles[1].mbid(), Some("7f1d7ee8-3b4c-4a55-9e8b-4c6a1bba5a1b"));
+
+        // Logged in UTC+2, so two hours ahead of UTC
+        log.adjust_for_utc_offset(2 * 3600);
+        assert_eq!(log.timezone(), LogTimezone::Utc);
+        assert_eq!(log.scrobbles().iter().next().unwrap().timestamp(), Some(1_500_000_000 - 7200));
+        log.adjust_for_utc_offset(2 * 3600);
+        assert_eq!(log.scrobbles().iter().next().unwrap().timestamp(), Some(1_500_000_000 - 7200));
+
+        for (log, problem) in &[
+            ("Kraftwerk\tComputerwelt\tNumbers\t\t199\tL\t1500000435\n", "missing the #AUDIOSCROBBLER header"),
+            ("#AUDIOSCROBBLER/1.1\n#TZ/UTC\nKraftwerk\tNumbers\tL\n", "line 3: expected 7 or 8"),
+            ("#AUDIOSCROBBLER/1.1\n\tComputerwelt\tNumbers\t\t199\tL\t1500000435\n", "line 2: missing the artist"),
+            ("#AUDIOSCROBBLER/1.1\nKraftwerk\tComputerwelt\tNumbers\t\t199\tX\t1\n", "line 2: unknown rating \"X\""),
+            ("#AUDIOSCROBBLER/1.1\nKraftwerk\tComputerwelt\tNumbers\t\t199\tL\t\n", "line 2: invalid timestamp"),
+        ] {
+            let err = ScrobblerLog::parse(log).unwrap_err();
+            assert!(err.to_string().contains(problem), "{}", err);
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_log_write() {
+        let mut first = Scrobble::new("Kraftwerk", "Computer\tLove", "Computer World");
+        first.with_timestamp(1_500_000_000).with_track_number(5).with_duration(435);
+        let mut second = Scrobble::new("Sigur Rós", "Hoppípolla", "");
+        second.with_timestamp(1_500_000_435).with_mbid("7f1d7ee8-3b4c-4a55-9e8b-4c6a1bba5a1b");
+        let batch = ScrobbleBatch::from(vec![first, second]);
+
+        let log = ScrobblerLog::from_batch("tapedeck 1.0", &batch).unwrap();
+        let written = log.to_string();
+        assert_eq!(
+            written,
+            "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/tapedeck 1.0\n\
+             Kraftwerk\tComputer World\tComputer Love\t5\t435\tL\t1500000000\t\n\
+             Sigur Rós\t\tHoppípolla\t\t\tL\t1500000435\t7f1d7ee8-3b4c-4a55-9e8b-4c6a1bba5a1b\n"
+        );
+
+        // What's written reads back the same, but for the cleaned names
+        let read = ScrobblerLog::parse(&written).unwrap();
+        assert_eq!((read.client(), read.timezone(), read.skipped()), ("tapedeck 1.0", LogTimezone::Utc, 0));
+        let read: Vec<&Scrobble> = read.scrobbles().iter().collect();
+        assert_eq!(read[0].track(), "Computer Love");
+        assert_eq!(read[1], batch.iter().nth(1).unwrap());
+
+        let untimed = ScrobbleBatch::from(vec![Scrobble::new("Kraftwerk", "Numbers", "Computer World")]);
+        let err = ScrobblerLog::from_batch("tapedeck 1.0", &untimed).unwrap_err();
+        assert!(err.to_string().contains("Scrobble 0 has no timestamp"), "{}", err);
+    }
+}
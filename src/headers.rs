@@ -0,0 +1,170 @@
+// Extra HTTP headers sent with API requests, ie. for relays which authenticate callers
+use crate::error::ScrobblerError;
+use crate::lang::Language;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::fmt;
+
+/// A set of extra HTTP headers to send with API requests. See [`Scrobbler::set_extra_header`].
+///
+/// Names are validated when inserted, and stored in lowercase; inserting a name which is already present replaces
+/// its value. Headers aren't part of the request signature.
+///
+/// The values of headers whose names contain `authorization` or `cookie` are redacted from the set's `Debug`
+/// output (and so from that of an [`HttpRequest`]), so that credentials never end up in logs.
+///
+/// [`Scrobbler::set_extra_header`]: struct.Scrobbler.html#method.set_extra_header
+/// [`HttpRequest`]: struct.HttpRequest.html
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Headers {
+    headers: Vec<(String, String)>,
+}
+
+impl Headers {
+    /// Creates an empty set of headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header, replacing any existing value. Fails if `name` isn't a legal header name, or `value` isn't a
+    /// legal header value (ie. because it contains a newline).
+    pub fn insert(&mut self, name: &str, value: &str) -> Result<(), ScrobblerError> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| ScrobblerError::new(format!("Invalid HTTP header name {name:?}")))?;
+        HeaderValue::from_str(value)
+            .map_err(|_| ScrobblerError::new(format!("Invalid value for HTTP header {name}")))?;
+
+        self.set(name.as_str(), value);
+        Ok(())
+    }
+
+    /// Removes a header, returning its value if it was present
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.position(name)?;
+        Some(self.headers.remove(index).1)
+    }
+
+    /// Returns the value of the given header, if present. Names are matched case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name).map(|index| self.headers[index].1.as_str())
+    }
+
+    /// Returns the number of headers in the set
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns true if the set contains no headers
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Iterates over the `(name, value)` pairs of the set, in the order they were first inserted
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    // Adds every header of `other`, replacing any with the same name
+    pub(crate) fn extend(&mut self, other: &Self) {
+        for (name, value) in other.iter() {
+            self.set(name, value);
+        }
+    }
+
+    // The headers of a response, leaving out any whose values aren't text
+    pub(crate) fn from_response(headers: &HeaderMap) -> Self {
+        let mut response_headers = Self::new();
+        for (name, value) in headers {
+            if let Ok(value) = value.to_str() {
+                response_headers.set(name.as_str(), value);
+            }
+        }
+
+        response_headers
+    }
+
+    // `name` must already be validated and lowercase
+    pub(crate) fn set(&mut self, name: &str, value: &str) {
+        match self.position(name) {
+            Some(index) => value.clone_into(&mut self.headers[index].1),
+            None => self.headers.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|(existing, _)| existing.eq_ignore_ascii_case(name))
+    }
+}
+
+fn is_sensitive(name: &str) -> bool {
+    name.contains("authorization") || name.contains("cookie")
+}
+
+impl fmt::Debug for Headers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.iter()
+                    .map(|(name, value)| (name, if is_sensitive(name) { "[REDACTED]" } else { value })),
+            )
+            .finish()
+    }
+}
+
+/// Options applied to the calls made through one handle of a `Scrobbler`. See [`Scrobbler::with_call_options`].
+///
+/// [`Scrobbler::with_call_options`]: struct.Scrobbler.html#method.with_call_options
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CallOptions {
+    /// Headers to send in addition to the `Scrobbler`'s own extra headers, replacing any with the same name
+    pub headers: Headers,
+    /// Skips the cache of read-only lookups (see [`Scrobbler::set_cache`]), always sending a request. The responses
+    /// still replace any cached ones.
+    ///
+    /// [`Scrobbler::set_cache`]: struct.Scrobbler.html#method.set_cache
+    pub bypass_cache: bool,
+    /// Whether lookups ask Last.fm to autocorrect the names looked up, replacing the `Scrobbler`'s setting (see
+    /// [`Scrobbler::set_autocorrect`]). `None` keeps the `Scrobbler`'s setting.
+    ///
+    /// [`Scrobbler::set_autocorrect`]: struct.Scrobbler.html#method.set_autocorrect
+    pub autocorrect: Option<bool>,
+    /// The language lookups ask for text in, replacing the `Scrobbler`'s (see [`Scrobbler::set_language`]). `None`
+    /// keeps the `Scrobbler`'s language.
+    ///
+    /// [`Scrobbler::set_language`]: struct.Scrobbler.html#method.set_language
+    pub language: Option<Language>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_headers_validated_and_redacted() {
+        let mut headers = Headers::new();
+        headers.insert("Authorization", "Bearer s3cret").unwrap();
+        headers.insert("X-Relay-Route", "eu").unwrap();
+        headers.insert("Proxy-Authorization", "Basic s3cret").unwrap();
+        headers.insert("x-relay-route", "us").unwrap();
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("X-RELAY-ROUTE"), Some("us"));
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["authorization", "x-relay-route", "proxy-authorization"]);
+
+        let debug = format!("{headers:?}");
+        assert!(!debug.contains("s3cret"), "{}", debug);
+        assert!(debug.contains("\"x-relay-route\": \"us\""), "{}", debug);
+
+        assert!(headers.insert("Bad Name", "value").is_err());
+        assert!(headers.insert("", "value").is_err());
+        let err = headers.insert("Cookie", "a\r\nb").unwrap_err();
+        assert!(!err.to_string().contains("a\r\nb"));
+        assert_eq!(headers.len(), 3);
+
+        assert_eq!(headers.remove("Authorization").as_deref(), Some("Bearer s3cret"));
+        assert_eq!(headers.get("authorization"), None);
+    }
+}
@@ -0,0 +1,262 @@
+// A summary of whether scrobbling works, for watchdogs and health check endpoints
+use crate::circuit::CircuitState;
+use crate::error::{ScrobblerError, SerializableError};
+use crate::transport::HttpResponse;
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Whether a `Scrobbler` (and optionally its queue) appears able to scrobble. See [`Scrobbler::health`] and
+/// [`ScrobbleQueue::health`].
+///
+/// A report is assembled from what the `Scrobbler` already knows, without sending anything, unless it comes from
+/// [`Scrobbler::health_probe`]. The last error is redacted as a [`SerializableError`] is, so a report can be served
+/// from a health check endpoint as it is. The `Display` output is a block of one line per item.
+///
+/// [`Scrobbler::health`]: struct.Scrobbler.html#method.health
+/// [`Scrobbler::health_probe`]: struct.Scrobbler.html#method.health_probe
+/// [`ScrobbleQueue::health`]: struct.ScrobbleQueue.html#method.health
+/// [`SerializableError`]: struct.SerializableError.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HealthReport {
+    /// Whether the `Scrobbler` has a session key
+    pub authenticated: bool,
+    /// The state of the circuit breaker, if one is set
+    pub circuit_state: Option<CircuitState>,
+    /// The number of scrobbles waiting in the queue, for a report on a queue
+    pub queue_depth: Option<usize>,
+    /// How long ago the oldest queued scrobble was played, for a report on a queue which isn't empty
+    pub oldest_pending_age: Option<Duration>,
+    /// How long ago an API call last succeeded, if one has
+    pub since_last_success: Option<Duration>,
+    /// How long ago an API call last failed, if one has
+    pub since_last_error: Option<Duration>,
+    /// The error the last failed API call returned, redacted
+    pub last_error: Option<SerializableError>,
+    /// Whether a request was sent to check the connection to the API, for this report
+    pub probed: bool,
+}
+
+impl HealthReport {
+    pub(crate) fn new(authenticated: bool, circuit_state: Option<CircuitState>) -> Self {
+        Self {
+            authenticated,
+            circuit_state,
+            queue_depth: None,
+            oldest_pending_age: None,
+            since_last_success: None,
+            since_last_error: None,
+            last_error: None,
+            probed: false,
+        }
+    }
+
+    /// Returns true if scrobbles can be expected to go through: the `Scrobbler` is authenticated, its circuit
+    /// breaker isn't open, and the last API call (if any) didn't fail
+    pub fn is_healthy(&self) -> bool {
+        let failing = match (self.since_last_error, self.since_last_success) {
+            (Some(error), Some(success)) => error < success,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        self.authenticated && self.circuit_state != Some(CircuitState::Open) && !failing
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn ago(elapsed: Option<Duration>) -> String {
+            elapsed.map_or_else(|| "never".to_owned(), |elapsed| format!("{}s ago", elapsed.as_secs()))
+        }
+
+        writeln!(f, "Healthy: {}", if self.is_healthy() { "yes" } else { "no" })?;
+        writeln!(f, "Authenticated: {}", if self.authenticated { "yes" } else { "no" })?;
+        match self.circuit_state {
+            Some(CircuitState::Closed) => writeln!(f, "Circuit breaker: closed")?,
+            Some(CircuitState::Open) => writeln!(f, "Circuit breaker: open")?,
+            Some(CircuitState::HalfOpen) => writeln!(f, "Circuit breaker: half-open")?,
+            None => writeln!(f, "Circuit breaker: none")?,
+        }
+        match (self.queue_depth, self.oldest_pending_age) {
+            (Some(depth), Some(age)) => {
+                writeln!(f, "Queued scrobbles: {depth}, the oldest played {}s ago", age.as_secs())?;
+            }
+            (Some(depth), None) => writeln!(f, "Queued scrobbles: {depth}")?,
+            (None, _) => {}
+        }
+        writeln!(f, "Last success: {}", ago(self.since_last_success))?;
+        match &self.last_error {
+            Some(error) => write!(f, "Last error: {} ({})", ago(self.since_last_error), error.message()),
+            None => write!(f, "Last error: none"),
+        }
+    }
+}
+
+// When API calls through a client or any of its clones last succeeded and failed, and when it was last probed
+#[derive(Debug, Default)]
+pub(crate) struct HealthTracker(Mutex<Health>);
+
+#[derive(Debug, Default)]
+struct Health {
+    succeeded_at: Option<SystemTime>,
+    // When the last failure was, and its error
+    failure: Option<(SystemTime, SerializableError)>,
+    probed_at: Option<SystemTime>,
+}
+
+impl HealthTracker {
+    pub(crate) fn record_at(&self, result: &Result<HttpResponse, ScrobblerError>, now: SystemTime) {
+        let mut health = self.lock();
+        match result {
+            Ok(_) => health.succeeded_at = Some(now),
+            Err(err) => health.failure = Some((now, SerializableError::from(err))),
+        }
+    }
+
+    // Claims the next probe, if none has been sent within `interval` of `now`
+    pub(crate) fn claim_probe_at(&self, interval: Duration, now: SystemTime) -> bool {
+        let mut health = self.lock();
+        let due = health
+            .probed_at
+            .is_none_or(|probed_at| now.duration_since(probed_at).map_or(true, |elapsed| elapsed >= interval));
+        if due {
+            health.probed_at = Some(now);
+        }
+        due
+    }
+
+    // Fills in the parts of `report` tracked here, as of `now`
+    pub(crate) fn report_at(&self, report: &mut HealthReport, now: SystemTime) {
+        let since = |time: SystemTime| now.duration_since(time).unwrap_or_default();
+        let health = self.lock();
+        report.since_last_success = health.succeeded_at.map(since);
+        report.since_last_error = health.failure.as_ref().map(|(time, _)| since(*time));
+        report.last_error = health.failure.as_ref().map(|(_, error)| error.clone());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Health> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::CircuitBreakerPolicy;
+    use crate::error::ErrorKind;
+    use crate::models::metadata::Scrobble;
+    use crate::queue::ScrobbleQueue;
+    use crate::scrobbler::Scrobbler;
+    use crate::test_util::{EchoTransport, Fault, FaultyTransport};
+    use std::time::UNIX_EPOCH;
+
+    fn scrobble(track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new("floyd", track, "old");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_health_when_healthy() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        let report = scrobbler.health();
+        assert!(!report.authenticated);
+        assert!(!report.is_healthy());
+        assert_eq!((report.since_last_success, report.last_error.as_ref()), (None, None));
+
+        scrobbler.authenticate_with_session_key("d580d57f32848f5dcf574d1ce18d78b2").unwrap();
+        scrobbler.set_circuit_breaker(Some(CircuitBreakerPolicy {
+            failure_threshold: 3,
+            cooldown: Duration::from_mins(1),
+        }));
+        scrobbler.scrobble(&Scrobble::new("floyd", "fruitflies", "bananas")).unwrap();
+        let report = scrobbler.health();
+        assert!(report.is_healthy());
+        assert_eq!(report.circuit_state, Some(CircuitState::Closed));
+        assert!(report.since_last_success.is_some());
+        assert_eq!((report.queue_depth, report.probed), (None, false));
+        assert_eq!(
+            report.to_string(),
+            "Healthy: yes\nAuthenticated: yes\nCircuit breaker: closed\nLast success: 0s ago\nLast error: none"
+        );
+
+        // A probe is sent at most once per interval, by the Scrobbler and its clones
+        let requests = echo.requests().len();
+        assert!(scrobbler.health_probe().probed);
+        assert!(!scrobbler.clone().health_probe().probed);
+        assert_eq!(echo.requests().len(), requests + 1);
+        assert_eq!(echo.requests()[requests].method(), "auth.getToken");
+        scrobbler.set_health_probe_interval(Duration::ZERO);
+        assert!(scrobbler.health_probe().is_healthy());
+        assert_eq!(echo.requests().len(), requests + 2);
+    }
+
+    #[test]
+    fn check_health_when_offline() {
+        let path = std::env::temp_dir().join(format!("rustfm-health-{}.json", std::process::id()));
+        let echo = EchoTransport::new();
+        let offline = (0..2).map(|_| Fault::ConnectError);
+        let transport = FaultyTransport::new(echo.clone()).fail_method("track.scrobble", offline);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("d580d57f32848f5dcf574d1ce18d78b2").unwrap();
+        scrobbler.scrobble(&Scrobble::new("floyd", "fruitflies", "bananas")).unwrap_err();
+
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let mut queue = ScrobbleQueue::open(&path, scrobbler).unwrap();
+        assert_eq!(queue.health().queue_depth, Some(0));
+        assert_eq!(queue.health().oldest_pending_age, None);
+        queue.push(&scrobble("second", now - 60)).unwrap();
+        queue.push(&scrobble("first", now - 600)).unwrap();
+        assert_eq!(queue.flush().unwrap().failed, 2);
+
+        let report = queue.health_at(UNIX_EPOCH + Duration::from_secs(now));
+        assert!(report.authenticated);
+        assert!(!report.is_healthy());
+        assert_eq!(report.queue_depth, Some(2));
+        assert_eq!(report.oldest_pending_age, Some(Duration::from_mins(10)));
+        assert_eq!(report.since_last_success, None);
+        assert!(report.since_last_error.is_some());
+        let error = report.last_error.as_ref().unwrap();
+        assert_eq!(error.kind(), ErrorKind::Transport);
+        assert!(!report.to_string().contains("d580d57f32848f5dcf574d1ce18d78b2"));
+        assert!(report.to_string().contains("Queued scrobbles: 2, the oldest played 600s ago\n"));
+
+        // Once it's back, and the scrobbles have backed off, the report recovers
+        assert_eq!(queue.flush_at(SystemTime::now() + Duration::from_mins(1)).unwrap().accepted, 2);
+        let report = queue.health();
+        assert!(report.is_healthy());
+        assert_eq!(report.queue_depth, Some(0));
+        assert!(report.last_error.is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_health_when_circuit_open() {
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone()).fail_method("track.scrobble", vec![Fault::HttpStatus(503)]);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_circuit_breaker(Some(CircuitBreakerPolicy {
+            failure_threshold: 1,
+            cooldown: Duration::from_mins(1),
+        }));
+        scrobbler.scrobble(&Scrobble::new("floyd", "fruitflies", "bananas")).unwrap_err();
+
+        let report = scrobbler.health();
+        assert_eq!(report.circuit_state, Some(CircuitState::Open));
+        assert!(!report.is_healthy());
+        assert_eq!(report.last_error.as_ref().unwrap().http_status(), Some(503));
+        assert!(report.to_string().contains("Circuit breaker: open\n"));
+        assert!(report.to_string().starts_with("Healthy: no\n"));
+
+        // A probe while the circuit is open fails straight away, without reaching the transport
+        let report = scrobbler.health_probe();
+        assert!(report.probed);
+        assert!(echo.requests().is_empty());
+    }
+}
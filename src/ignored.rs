@@ -0,0 +1,104 @@
+// What a queue does with scrobbles Last.fm ignores, and the scrobbles it sets aside
+use crate::models::metadata::Scrobble;
+use crate::models::responses::IgnoredReason;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::sync::Arc;
+
+// Rewrites an ignored scrobble for one more attempt, or gives up on it with `None`
+type Rewrite = dyn Fn(&Scrobble, IgnoredReason) -> Option<Scrobble> + Send + Sync;
+
+/// What a [`ScrobbleQueue`] does with a scrobble which Last.fm accepted the request for, but ignored. Set for each
+/// [`IgnoredReason`] with [`ScrobbleQueue::set_ignored_policy`].
+///
+/// Reasons without a policy are retried, which suits reasons that can pass, but not ones such as an ignored
+/// artist (ie. `"Unknown Artist"`), which Last.fm will ignore every time. Scrobbles ignored because the daily
+/// scrobble limit was reached are always parked until it resets, whatever the policy.
+///
+/// # Usage
+/// ```ignore
+/// // Try an ignored artist again as the album artist, if there is one, and set it aside otherwise
+/// queue.set_ignored_policy(
+///     IgnoredReason::ArtistIgnored,
+///     IgnoredPolicy::rewrite_and_retry(|scrobble, _| {
+///         let mut rewritten = scrobble.clone();
+///         rewritten.with_artists(&[scrobble.album_artist()]);
+///         Some(rewritten).filter(|_| !scrobble.album_artist().is_empty())
+///     }),
+/// );
+/// ```
+///
+/// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+/// [`IgnoredReason`]: responses/values/enum.IgnoredReason.html
+/// [`ScrobbleQueue::set_ignored_policy`]: struct.ScrobbleQueue.html#method.set_ignored_policy
+#[derive(Clone, Default)]
+pub enum IgnoredPolicy {
+    /// Keeps the scrobble queued, retrying it with backoff as if it had failed
+    #[default]
+    Retry,
+    /// Removes the scrobble from the queue
+    Discard,
+    /// Moves the scrobble from the queue to its dead letters (see [`ScrobbleQueue::dead_letters`])
+    ///
+    /// [`ScrobbleQueue::dead_letters`]: struct.ScrobbleQueue.html#method.dead_letters
+    DeadLetter,
+    /// Rewrites the scrobble with the given function, ie. to fix its metadata, and submits the rewritten scrobble
+    /// once more straight away. It is dead-lettered if the function returns `None`, or Last.fm ignores it again.
+    /// If the second submission fails, the rewritten scrobble stays queued, to be retried.
+    RewriteAndRetry(Arc<Rewrite>),
+}
+
+impl IgnoredPolicy {
+    /// Creates a [`IgnoredPolicy::RewriteAndRetry`] policy with the given function
+    ///
+    /// [`IgnoredPolicy::RewriteAndRetry`]: enum.IgnoredPolicy.html#variant.RewriteAndRetry
+    pub fn rewrite_and_retry<F>(rewrite: F) -> Self
+    where
+        F: Fn(&Scrobble, IgnoredReason) -> Option<Scrobble> + Send + Sync + 'static,
+    {
+        Self::RewriteAndRetry(Arc::new(rewrite))
+    }
+}
+
+impl fmt::Debug for IgnoredPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Retry => f.write_str("Retry"),
+            Self::Discard => f.write_str("Discard"),
+            Self::DeadLetter => f.write_str("DeadLetter"),
+            Self::RewriteAndRetry(_) => f.write_str("RewriteAndRetry"),
+        }
+    }
+}
+
+/// A scrobble which a [`ScrobbleQueue`] set aside, rather than retrying it, because Last.fm ignored it. See
+/// [`IgnoredPolicy::DeadLetter`].
+///
+/// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+/// [`IgnoredPolicy::DeadLetter`]: enum.IgnoredPolicy.html#variant.DeadLetter
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// The scrobble, as last submitted
+    pub scrobble: Scrobble,
+    /// Why Last.fm ignored it
+    #[serde(rename = "ignored_code", serialize_with = "serialize_reason", deserialize_with = "deserialize_reason")]
+    pub reason: IgnoredReason,
+}
+
+// Reasons are persisted as their `ignoredMessage` codes
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_reason<S>(reason: &IgnoredReason, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ser.serialize_u32(reason.code())
+}
+
+fn deserialize_reason<'de, D>(de: D) -> Result<IgnoredReason, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let code = u32::deserialize(de)?;
+    Ok(IgnoredReason::from_code(code).unwrap_or(IgnoredReason::Other(code)))
+}
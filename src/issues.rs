@@ -0,0 +1,197 @@
+// Soft problems with submitted tracks, which are fixed up in lenient mode and fail the call in strict mode
+use crate::models::metadata::Scrobble;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A problem with a track that the crate works around rather than failing the call, unless strict mode is enabled
+/// (see [`Scrobbler::set_strict`]). [`Scrobbler::validate_batch`] lists the issues with a batch up front.
+///
+/// `position` is the scrobble's index in a batch (or import), or `None` for a single scrobble.
+///
+/// [`Scrobbler::set_strict`]: struct.Scrobbler.html#method.set_strict
+/// [`Scrobbler::validate_batch`]: struct.Scrobbler.html#method.validate_batch
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ScrobbleIssue {
+    /// An over-long artist, track or album name (`field`) was truncated. See
+    /// [`Scrobbler::set_truncate_long_fields`].
+    ///
+    /// [`Scrobbler::set_truncate_long_fields`]: struct.Scrobbler.html#method.set_truncate_long_fields
+    FieldTruncated {
+        position: Option<usize>,
+        field: String,
+        original_chars: usize,
+        truncated_chars: usize,
+    },
+    /// The scrobble had no timestamp, so was submitted as played at the current time
+    TimestampGenerated { position: Option<usize> },
+    /// The album name was blank (only whitespace), so was left out
+    BlankAlbumOmitted { position: Option<usize> },
+}
+
+impl ScrobbleIssue {
+    /// Returns the index in the batch of the scrobble with the issue, or `None` for a single scrobble
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Self::FieldTruncated { position, .. }
+            | Self::TimestampGenerated { position }
+            | Self::BlankAlbumOmitted { position } => *position,
+        }
+    }
+}
+
+/// A change made to a scrobble while preparing it for submission, to work around a [`ScrobbleIssue`]. See
+/// [`PreparationReport`].
+///
+/// [`ScrobbleIssue`]: enum.ScrobbleIssue.html
+/// [`PreparationReport`]: struct.PreparationReport.html
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Adjustment {
+    /// The scrobble's index in the batch, import or queue
+    pub position: usize,
+    /// The parameter which changed: `artist`, `track`, `album`, `albumArtist` or `timestamp`
+    pub field: String,
+    /// The value before the change, or `None` if there was none
+    pub old_value: Option<String>,
+    /// The value submitted, or `None` if the parameter was left out
+    pub new_value: Option<String>,
+    /// Why the change was made
+    pub reason: ScrobbleIssue,
+}
+
+impl Adjustment {
+    // Describes how working around `issue` turned `original` into `prepared`, submitted at `timestamp`
+    pub(crate) fn new(
+        position: usize,
+        issue: &ScrobbleIssue,
+        original: &Scrobble,
+        prepared: &Scrobble,
+        timestamp: u64,
+    ) -> Self {
+        let name = |scrobble: &Scrobble, field: &str| {
+            let names = scrobble.names();
+            names.iter().find(|(name, _)| *name == field).map(|(_, value)| (*value).to_owned())
+        };
+        let (field, old_value, new_value) = match issue {
+            ScrobbleIssue::FieldTruncated { field, .. } => {
+                (field.clone(), name(original, field), name(prepared, field))
+            }
+            ScrobbleIssue::TimestampGenerated { .. } => ("timestamp".to_owned(), None, Some(timestamp.to_string())),
+            ScrobbleIssue::BlankAlbumOmitted { .. } => ("album".to_owned(), name(original, "album"), None),
+        };
+
+        Self {
+            position,
+            field,
+            old_value,
+            new_value,
+            reason: issue.clone(),
+        }
+    }
+}
+
+/// The changes made to a batch of scrobbles while preparing it for submission, so that they can be shown to the
+/// user. Returned alongside the submission's result, ie. by [`BatchScrobbleResponse::preparation`], and kept by
+/// [`ScrobbleQueue::last_preparation`] for the most recent flush.
+///
+/// In strict mode (see [`Scrobbler::set_strict`]) nothing is changed: a batch which would need changing is refused.
+///
+/// [`BatchScrobbleResponse::preparation`]: responses/struct.BatchScrobbleResponse.html#method.preparation
+/// [`ScrobbleQueue::last_preparation`]: struct.ScrobbleQueue.html#method.last_preparation
+/// [`Scrobbler::set_strict`]: struct.Scrobbler.html#method.set_strict
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreparationReport {
+    adjustments: Vec<Adjustment>,
+}
+
+impl PreparationReport {
+    pub(crate) fn new(adjustments: Vec<Adjustment>) -> Self {
+        Self { adjustments }
+    }
+
+    /// Returns every change made, in the order of the scrobbles changed
+    pub fn adjustments(&self) -> &[Adjustment] {
+        &self.adjustments
+    }
+
+    /// Returns the changes made to the scrobble at `position`
+    pub fn adjustments_at(&self, position: usize) -> impl Iterator<Item = &Adjustment> {
+        self.adjustments
+            .iter()
+            .filter(move |adjustment| adjustment.position == position)
+    }
+
+    /// Returns true if no scrobble was changed
+    pub fn is_empty(&self) -> bool {
+        self.adjustments.is_empty()
+    }
+
+    // Shifts every position by `offset`, ie. from a chunk's positions to the whole import's
+    pub(crate) fn offset(mut self, offset: usize) -> Self {
+        for adjustment in &mut self.adjustments {
+            adjustment.position += offset;
+        }
+        self
+    }
+
+    // Maps each position to the one it stands for in `positions`, ie. from a batch's positions to a queue's
+    pub(crate) fn at_positions(mut self, positions: &[usize]) -> Self {
+        for adjustment in &mut self.adjustments {
+            adjustment.position = positions[adjustment.position];
+        }
+        self
+    }
+
+    pub(crate) fn append(&mut self, other: Self) {
+        self.adjustments.extend(other.adjustments);
+    }
+}
+
+impl fmt::Display for ScrobbleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position() {
+            Some(i) => write!(f, "Scrobble {i} in batch")?,
+            None => f.write_str("Scrobble")?,
+        }
+
+        match self {
+            Self::FieldTruncated {
+                field,
+                original_chars,
+                truncated_chars,
+                ..
+            } => write!(
+                f,
+                " has a {field} name {original_chars} characters long, truncated to {truncated_chars}"
+            ),
+            Self::TimestampGenerated { .. } => f.write_str(" has no timestamp, so is submitted as played now"),
+            Self::BlankAlbumOmitted { .. } => f.write_str(" has a blank album name, which is left out"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_issue_display() {
+        let truncated = ScrobbleIssue::FieldTruncated {
+            position: Some(3),
+            field: "track".to_owned(),
+            original_chars: 1100,
+            truncated_chars: 1024,
+        };
+        assert_eq!(truncated.position(), Some(3));
+        assert_eq!(
+            truncated.to_string(),
+            "Scrobble 3 in batch has a track name 1100 characters long, truncated to 1024"
+        );
+
+        let untimed = ScrobbleIssue::TimestampGenerated { position: None };
+        assert_eq!(untimed.to_string(), "Scrobble has no timestamp, so is submitted as played now");
+        let blank = ScrobbleIssue::BlankAlbumOmitted { position: Some(0) };
+        assert_eq!(blank.to_string(), "Scrobble 0 in batch has a blank album name, which is left out");
+    }
+}
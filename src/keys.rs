@@ -0,0 +1,203 @@
+// Typed Last.fm credentials, so that an API key, API secret and session key can't be passed in each other's place
+use crate::error::ScrobblerError;
+
+use std::fmt;
+
+// Last.fm issues API keys, API secrets and session keys as 32 hexadecimal digits
+const KEY_LEN: usize = 32;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A Last.fm API key, which identifies the application. See [`Scrobbler::with_keys`].
+///
+/// Converting from a string with `From` accepts any value, as the `&str` constructors of `Scrobbler` do. Use
+/// [`ApiKey::parse`] to check that the value looks like a key Last.fm issued.
+///
+/// [`Scrobbler::with_keys`]: struct.Scrobbler.html#method.with_keys
+/// [`ApiKey::parse`]: struct.ApiKey.html#method.parse
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ApiKey(String);
+
+/// A Last.fm API secret, which signs the application's requests. See [`Scrobbler::with_keys`].
+///
+/// The secret is never revealed: its `Debug` and `Display` output is `[REDACTED]`. Converting from a string with
+/// `From` accepts any value; use [`ApiSecret::parse`] to check it.
+///
+/// [`Scrobbler::with_keys`]: struct.Scrobbler.html#method.with_keys
+/// [`ApiSecret::parse`]: struct.ApiSecret.html#method.parse
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ApiSecret(String);
+
+/// A Last.fm session key, which authenticates requests on behalf of a user. See [`Scrobbler::set_session_key`].
+///
+/// Its `Debug` and `Display` output is `[REDACTED]`; use [`SessionKey::expose`] to get at the key itself, ie. to
+/// store it. Converting from a string with `From` accepts any value; use [`SessionKey::parse`] to check it.
+///
+/// [`Scrobbler::set_session_key`]: struct.Scrobbler.html#method.set_session_key
+/// [`SessionKey::expose`]: struct.SessionKey.html#method.expose
+/// [`SessionKey::parse`]: struct.SessionKey.html#method.parse
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SessionKey(String);
+
+impl ApiKey {
+    /// Parses an API key, failing unless it's 32 hexadecimal digits
+    pub fn parse(key: &str) -> Result<Self, ScrobblerError> {
+        validate("API key", key)?;
+        Ok(Self(key.to_owned()))
+    }
+
+    /// Returns the key
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ApiSecret {
+    /// Parses an API secret, failing unless it's 32 hexadecimal digits. The error never includes the secret.
+    pub fn parse(secret: &str) -> Result<Self, ScrobblerError> {
+        validate("API secret", secret)?;
+        Ok(Self(secret.to_owned()))
+    }
+
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl SessionKey {
+    /// Parses a session key, failing unless it's 32 hexadecimal digits. The error never includes the key.
+    pub fn parse(key: &str) -> Result<Self, ScrobblerError> {
+        validate("session key", key)?;
+        Ok(Self(key.to_owned()))
+    }
+
+    /// Returns the key itself. Take care not to log it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    // Rejects an empty (or whitespace-only) key, which can never authenticate a request
+    pub(crate) fn non_empty(self) -> Result<Self, ScrobblerError> {
+        if self.0.trim().is_empty() {
+            return Err(ScrobblerError::new("Invalid session key: the value is empty".to_owned()));
+        }
+        Ok(self)
+    }
+}
+
+// Describes what's wrong with a value, without repeating it, since it may be a secret
+fn validate(name: &str, value: &str) -> Result<(), ScrobblerError> {
+    let problem = if value.trim() != value {
+        "has surrounding whitespace"
+    } else if value.len() != KEY_LEN {
+        "isn't 32 characters long"
+    } else if !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        "isn't hexadecimal"
+    } else {
+        return Ok(());
+    };
+
+    Err(ScrobblerError::new(format!("Invalid {name}: the value {problem}")))
+}
+
+impl fmt::Display for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for ApiSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ApiSecret").field(&format_args!("{REDACTED}")).finish()
+    }
+}
+
+impl fmt::Display for ApiSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SessionKey").field(&format_args!("{REDACTED}")).finish()
+    }
+}
+
+impl fmt::Display for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+macro_rules! from_strings {
+    ($($key:ident),*) => {
+        $(
+            impl From<&str> for $key {
+                fn from(value: &str) -> Self {
+                    Self(value.to_owned())
+                }
+            }
+
+            impl From<String> for $key {
+                fn from(value: String) -> Self {
+                    Self(value)
+                }
+            }
+        )*
+    };
+}
+
+from_strings!(ApiKey, ApiSecret, SessionKey);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "0123456789abcdef0123456789ABCDEF";
+
+    #[test]
+    fn check_keys_validated() {
+        assert_eq!(ApiKey::parse(KEY).unwrap().as_str(), KEY);
+        assert_eq!(ApiSecret::parse(KEY).unwrap().expose(), KEY);
+        assert_eq!(SessionKey::parse(KEY).unwrap().expose(), KEY);
+
+        let invalid = [
+            ("", "isn't 32 characters long"),
+            ("0123456789abcdef", "isn't 32 characters long"),
+            ("0123456789abcdef0123456789abcdef0", "isn't 32 characters long"),
+            ("0123456789abcdef0123456789abcdeg", "isn't hexadecimal"),
+            (" 0123456789abcdef0123456789abcdef", "has surrounding whitespace"),
+            ("0123456789abcdef0123456789abcdef\n", "has surrounding whitespace"),
+        ];
+        for (value, problem) in &invalid {
+            let err = ApiKey::parse(value).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid API key: the value {problem}"));
+            let err = ApiSecret::parse(value).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid API secret: the value {problem}"));
+            let err = SessionKey::parse(value).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid session key: the value {problem}"));
+        }
+
+        // Conversions accept anything, as the &str constructors do
+        assert_eq!(ApiKey::from("api_key").as_str(), "api_key");
+        assert_eq!(SessionKey::from(String::from("seshkey")).expose(), "seshkey");
+    }
+
+    #[test]
+    fn check_secret_keys_redacted() {
+        let secret = ApiSecret::from(KEY);
+        let session_key = SessionKey::from(KEY);
+
+        assert_eq!(format!("{secret:?}"), "ApiSecret([REDACTED])");
+        assert_eq!(format!("{secret}"), "[REDACTED]");
+        assert_eq!(format!("{session_key:?}"), "SessionKey([REDACTED])");
+        assert_eq!(format!("{session_key:#?}"), "SessionKey(\n    [REDACTED],\n)");
+        assert_eq!(format!("{session_key}"), "[REDACTED]");
+
+        // The API key isn't secret
+        let key = ApiKey::from(KEY);
+        assert_eq!(format!("{key}"), KEY);
+        assert_eq!(format!("{key:?}"), format!("ApiKey({KEY:?})"));
+    }
+}
@@ -0,0 +1,109 @@
+// Languages for the localized text of lookups, which Last.fm takes as ISO 639-1 codes
+use crate::error::ScrobblerError;
+
+use std::fmt;
+
+// The ISO 639-2 codes (bibliographic and terminological) of languages Last.fm localizes for, with their ISO 639-1
+// equivalents
+const ISO_639_2: &[(&str, &str)] = &[
+    ("ces", "cs"),
+    ("chi", "zh"),
+    ("cze", "cs"),
+    ("dan", "da"),
+    ("deu", "de"),
+    ("dut", "nl"),
+    ("eng", "en"),
+    ("fin", "fi"),
+    ("fra", "fr"),
+    ("fre", "fr"),
+    ("ger", "de"),
+    ("hun", "hu"),
+    ("ita", "it"),
+    ("jpn", "ja"),
+    ("kor", "ko"),
+    ("nld", "nl"),
+    ("nor", "no"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("rus", "ru"),
+    ("spa", "es"),
+    ("swe", "sv"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+    ("zho", "zh"),
+];
+
+/// A language to ask Last.fm for biographies and wiki text in. See [`Scrobbler::set_language`].
+///
+/// Parsed loosely from a BCP 47 language tag such as `"de"`, `"de-DE"`, `"pt_BR"` or `"zh-Hant-TW"`. Last.fm
+/// takes only an ISO 639-1 code, so only the primary language subtag is kept, and a three-letter ISO 639-2 code
+/// (ie. `"deu"`) is mapped to its two-letter code.
+///
+/// [`Scrobbler::set_language`]: struct.Scrobbler.html#method.set_language
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Language(String);
+
+impl Language {
+    /// Parses a language tag, failing if it isn't made of letters and digits in subtags separated by `-` or `_`,
+    /// starting with a language whose ISO 639-1 code is known
+    pub fn parse(tag: &str) -> Result<Self, ScrobblerError> {
+        let invalid = |problem: &str| ScrobblerError::new(format!("Invalid language tag {tag:?}: {problem}"));
+        let mut subtags = tag.trim().split(['-', '_']);
+        let primary = subtags.next().unwrap_or_default().to_ascii_lowercase();
+        if !primary.bytes().all(|b| b.is_ascii_lowercase()) || !matches!(primary.len(), 2 | 3) {
+            return Err(invalid("the language must be a 2 or 3 letter code"));
+        }
+        if !subtags.all(|subtag| (1..=8).contains(&subtag.len()) && subtag.bytes().all(|b| b.is_ascii_alphanumeric())) {
+            return Err(invalid("subtags must be 1 to 8 letters or digits"));
+        }
+
+        let code = if primary.len() == 2 {
+            primary
+        } else {
+            let (_, code) = ISO_639_2
+                .iter()
+                .find(|(alpha3, _)| *alpha3 == primary)
+                .ok_or_else(|| invalid("no ISO 639-1 code is known for the language"))?;
+            (*code).to_owned()
+        };
+
+        Ok(Self(code))
+    }
+
+    /// Returns the ISO 639-1 code sent to Last.fm, ie. `"de"`
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_language_parse() {
+        for (tag, code) in &[
+            ("de", "de"),
+            ("DE", "de"),
+            (" de-DE ", "de"),
+            ("de-AT-1996", "de"),
+            ("pt_BR", "pt"),
+            ("zh-Hant-TW", "zh"),
+            ("ger", "de"),
+            ("deu-CH", "de"),
+        ] {
+            assert_eq!(Language::parse(tag).unwrap().code(), *code, "{tag}");
+        }
+
+        for tag in &["", "d", "deutsch", "d3", "de-", "de--DE", "de-DE-toolongsubtag", "de DE", "xyz"] {
+            let err = Language::parse(tag).unwrap_err();
+            assert!(err.to_string().starts_with("Invalid language tag"), "{}: {}", tag, err);
+        }
+    }
+}
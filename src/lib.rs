@@ -1,6 +1,7 @@
 #![doc(html_root_url = "https://docs.rs/rustfm-scrobble/1.0.0")]
 #![deny(clippy::all)]
 #![deny(clippy::pedantic)]
+#![allow(clippy::must_use_candidate, clippy::missing_errors_doc)]
 //! # rustfm-scrobble
 //!
 //! Client for the Last.fm Scrobble API v2.0. Allows easy access to the most-commonly used Scrobble/Now Playing
@@ -46,15 +47,116 @@
 #[macro_use]
 extern crate wrapped_vec;
 
+#[cfg(feature = "async")]
+mod async_scrobbler;
+#[cfg(feature = "async")]
+mod async_transport;
+mod audit;
 mod auth;
+mod builder;
+mod cache;
+mod chunks;
+mod circuit;
 mod client;
+mod codes;
+mod config;
+#[cfg(feature = "crypto")]
+mod crypto;
 mod error;
+mod events;
+mod featured;
+mod flush;
+mod formats;
+mod headers;
+mod health;
+mod ignored;
+mod info;
+mod issues;
+mod keys;
+mod lang;
+#[cfg(feature = "listenbrainz")]
+mod listenbrainz;
+mod love;
+mod matching;
+mod media;
 mod models;
+mod params;
+mod plan;
+mod playback;
+mod queue;
+mod rate_limit;
+mod reader;
+mod refresh;
+mod retry;
 mod scrobbler;
+mod service;
+mod stats;
+pub mod signing;
+#[cfg(feature = "event-sink")]
+mod sink;
+mod submitted;
+#[cfg(feature = "metrics")]
+mod telemetry;
+mod timer;
+mod transport;
+mod usage;
+mod validation;
+#[cfg(feature = "xml")]
+mod xml;
 
-pub use crate::models::metadata::{Scrobble, ScrobbleBatch};
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
+pub use crate::models::metadata::{Correction, Corrections, NowPlaying, Scrobble, ScrobbleBatch};
+#[cfg(feature = "async")]
+pub use crate::async_scrobbler::AsyncScrobbler;
+#[cfg(feature = "async")]
+pub use crate::async_transport::{AsyncReqwestTransport, AsyncTransport, ResponseFuture};
+pub use crate::audit::{CallOutcome, CallRecord};
+pub use crate::builder::{ScrobblerBuilder, ServicePreset};
+pub use crate::cache::CachePolicy;
+pub use crate::chunks::{BatchChunks, ChunkResult, DailyLimitReached, ImportProgress};
+pub use crate::circuit::{CircuitBreakerPolicy, CircuitState};
+pub use crate::codes::LastFmErrorCode;
+pub use crate::config::ScrobblerConfig;
+#[cfg(feature = "crypto")]
+pub use crate::crypto::EncryptionKey;
+pub use crate::events::{RequestOutcome, ScrobblerEvent};
+pub use crate::featured::{ArtistJoinPolicy, FeaturedArtists};
+pub use crate::formats::{LogTimezone, ScrobblerLog};
+pub use crate::headers::{CallOptions, Headers};
+pub use crate::health::HealthReport;
+pub use crate::ignored::{DeadLetter, IgnoredPolicy};
+pub use crate::info::ClientInfo;
+pub use crate::issues::{Adjustment, PreparationReport, ScrobbleIssue};
+pub use crate::keys::{ApiKey, ApiSecret, SessionKey};
+pub use crate::lang::Language;
+#[cfg(feature = "listenbrainz")]
+pub use crate::listenbrainz::ListenBrainz;
+pub use crate::love::{BulkLoveOptions, BulkLoveReport, LoveOutcome};
+pub use crate::matching::TrackKey;
+pub use crate::media::MediaKind;
+pub use crate::params::Params;
+pub use crate::plan::{ImportPlan, ImportStatus};
+pub use crate::playback::{PlaybackAction, PlaybackSession};
+pub use crate::queue::{
+    ConcurrentFlush, FlushReport, RepeatPolicy, ScrobbleOutcome, ScrobbleQueue, StateImportMode,
+};
+pub use crate::rate_limit::RateLimit;
+pub use crate::reader::LastFmReader;
+pub use crate::refresh::NowPlayingRefresher;
+pub use crate::retry::{CallClass, RetryPolicy};
 pub use crate::scrobbler::Scrobbler;
-pub use crate::error::ScrobblerError;
+pub use crate::service::{ScrobbleService, ScrobbleTarget};
+#[cfg(feature = "event-sink")]
+pub use crate::sink::EventSink;
+pub use crate::stats::BatchStats;
+pub use crate::submitted::SubmittedScrobble;
+pub use crate::timer::ScrobbleTimer;
+pub use crate::transport::{HttpMethod, HttpRequest, HttpResponse, ReqwestTransport, Transport, TransportInfo};
+pub use crate::usage::{EstimatedUsage, UsageLimits, UsageThresholds, UsageWindow};
+pub use crate::validation::{Eligibility, ScrobbleValidator, ValidationError};
+pub use crate::error::{ErrorKind, FileOperation, ScrobblerError, SerializableError};
 
 
 /// Last.fm API Response Types
@@ -62,11 +164,12 @@ pub use crate::error::ScrobblerError;
 /// Types used to represent responses from the Last.fm API
 pub mod responses {
     pub use crate::models::responses::{
-        BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse,
+        AlbumInfo, ArtistInfo, BatchScrobbleResponse, CorrectionResponse, NowPlayingResponse, RecentTrack,
+        ScrobbleResponse, SessionResponse, TrackInfo, UserInfo,
     };
 
     /// Data types used to represent values in API Response types
     pub mod values {
-        pub use crate::models::responses::{CorrectableString, ScrobbleList};
+        pub use crate::models::responses::{CorrectableString, IgnoredReason, NowPlayingOutcome, ScrobbleList};
     }
 }
@@ -0,0 +1,355 @@
+// Submitting listens to ListenBrainz, as an alternative to Last.fm, with the crate's own `Scrobble`s
+use crate::error::ScrobblerError;
+use crate::headers::Headers;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::params::Params;
+use crate::transport::{HttpMethod, HttpRequest, ReqwestTransport, Transport};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::result;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+type Result<T> = result::Result<T, ScrobblerError>;
+
+#[cfg(not(test))]
+const API_URL: &str = "https://api.listenbrainz.org";
+
+// The most listens ListenBrainz accepts in a single submission
+const MAX_LISTENS_PER_REQUEST: usize = 1000;
+
+// Responses are only a status, or an error message
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// Submits listens to [ListenBrainz](https://listenbrainz.org), authenticated with a user token, so that
+/// applications can scrobble there as well as (or instead of) to Last.fm. Requires the `listenbrainz` feature.
+///
+/// Listens are built from the same [`Scrobble`]s and [`ScrobbleBatch`]es as Last.fm scrobbles, with the artist,
+/// track and album credited as [`Scrobble::params`] credits them. A track's duration, number and `MusicBrainz` ID
+/// are sent when they're set, and a scrobble without a timestamp is listened to at the time it's submitted.
+///
+/// Both `ListenBrainz` and [`Scrobbler`] implement [`ScrobbleTarget`], so an application can submit to whichever
+/// the user chose. Unlike a `Scrobbler`, a `ListenBrainz` client sends each request once, with no rate limit or
+/// retries.
+///
+/// # Usage
+/// ```ignore
+/// let listenbrainz = ListenBrainz::new(&settings.listenbrainz_token);
+/// listenbrainz.now_playing(&track)?;
+/// // ...
+/// listenbrainz.scrobble(&track)?;
+/// ```
+///
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`ScrobbleBatch`]: struct.ScrobbleBatch.html
+/// [`Scrobble::params`]: struct.Scrobble.html#method.params
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`ScrobbleTarget`]: trait.ScrobbleTarget.html
+#[derive(Clone)]
+pub struct ListenBrainz {
+    token: String,
+    base_url: String,
+    transport: Arc<dyn Transport>,
+}
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    listen_type: &'static str,
+    payload: &'a [Listen],
+}
+
+#[derive(Serialize)]
+struct Listen {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listened_at: Option<u64>,
+    track_metadata: TrackMetadata,
+}
+
+#[derive(Serialize)]
+struct TrackMetadata {
+    artist_name: String,
+    track_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_name: Option<String>,
+    additional_info: AdditionalInfo,
+}
+
+#[derive(Serialize)]
+struct AdditionalInfo {
+    // Sent only for a track credited to several artists
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artist_names: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracknumber: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recording_mbid: Option<String>,
+    submission_client: &'static str,
+    submission_client_version: &'static str,
+}
+
+// The body of a rejected submission
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl ListenBrainz {
+    /// Creates a client submitting as the user whose token is given, with the default [`ReqwestTransport`]. Users
+    /// find their token on their `ListenBrainz` settings page.
+    ///
+    /// [`ReqwestTransport`]: struct.ReqwestTransport.html
+    pub fn new(token: &str) -> Self {
+        Self::with_transport(token, ReqwestTransport::new())
+    }
+
+    /// Creates a client submitting as the user whose token is given, with the given transport
+    pub fn with_transport<T>(token: &str, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self {
+            token: token.to_owned(),
+            #[cfg(not(test))]
+            base_url: API_URL.to_owned(),
+            #[cfg(test)]
+            base_url: mockito::server_url(),
+            transport: Arc::new(transport),
+        }
+    }
+
+    /// Sets the URL of the `ListenBrainz` server, ie. `"https://listenbrainz.example.org"` for a self-hosted one.
+    /// API paths such as `/1/submit-listens` are appended to it.
+    pub fn set_base_url(&mut self, base_url: &str) {
+        base_url.trim_end_matches('/').clone_into(&mut self.base_url);
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Tells `ListenBrainz` the given track is playing now. The scrobble's timestamp is ignored.
+    pub fn now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        let mut listen = Self::listen(scrobble, 0);
+        listen.listened_at = None;
+        self.submit("playing_now", &[listen])
+    }
+
+    /// Submits a single listen
+    pub fn scrobble(&self, scrobble: &Scrobble) -> Result<()> {
+        self.submit("single", &[Self::listen(scrobble, now())])
+    }
+
+    /// Submits a batch of listens of any size, as an import, in as many requests as `ListenBrainz` needs. Listens
+    /// submitted before a failed request stay submitted.
+    pub fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()> {
+        if batch.is_empty() {
+            return Err(ScrobblerError::new("Scrobble batch is empty".to_owned()));
+        }
+
+        let now = now();
+        let listens: Vec<Listen> = batch.iter().map(|scrobble| Self::listen(scrobble, now)).collect();
+        for chunk in listens.chunks(MAX_LISTENS_PER_REQUEST) {
+            self.submit("import", chunk)?;
+        }
+        Ok(())
+    }
+
+    // The listen submitted for `scrobble`, at `now` unless it has a timestamp of its own
+    fn listen(scrobble: &Scrobble, now: u64) -> Listen {
+        let params = scrobble.params();
+        let artists = scrobble.artists();
+        Listen {
+            listened_at: Some(scrobble.timestamp().unwrap_or(now)),
+            track_metadata: TrackMetadata {
+                artist_name: params.get("artist").unwrap_or_default().to_owned(),
+                track_name: params.get("track").unwrap_or_default().to_owned(),
+                release_name: params.get("album").map(str::to_owned),
+                additional_info: AdditionalInfo {
+                    artist_names: if artists.len() > 1 {
+                        artists.into_iter().map(str::to_owned).collect()
+                    } else {
+                        Vec::new()
+                    },
+                    duration_ms: scrobble.duration().map(|secs| secs * 1000),
+                    tracknumber: scrobble.track_number(),
+                    recording_mbid: scrobble.mbid().map(str::to_owned),
+                    submission_client: env!("CARGO_PKG_NAME"),
+                    submission_client_version: env!("CARGO_PKG_VERSION"),
+                },
+            },
+        }
+    }
+
+    fn submit(&self, listen_type: &'static str, listens: &[Listen]) -> Result<()> {
+        let submission = Submission {
+            listen_type,
+            payload: listens,
+        };
+        let mut headers = Headers::new();
+        headers.insert("Authorization", &format!("Token {}", self.token))?;
+        let request = HttpRequest {
+            http_method: HttpMethod::Post,
+            url: format!("{}/1/submit-listens", self.base_url),
+            params: Params::new(),
+            request_id: None,
+            max_response_size: MAX_RESPONSE_SIZE,
+            headers,
+            json_body: Some(serde_json::to_string(&submission).map_err(|err| ScrobblerError::new(err.to_string()))?),
+        };
+
+        let response = self.transport.send(&request)?;
+        if (200..300).contains(&response.status) {
+            return Ok(());
+        }
+        let message = serde_json::from_str::<ErrorResponse>(&response.body).map_or(response.body, |body| body.error);
+        Err(ScrobblerError::http(
+            response.status,
+            format!("ListenBrainz rejected the listens: {message}"),
+        ))
+    }
+}
+
+impl fmt::Debug for ListenBrainz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListenBrainz").field("base_url", &self.base_url).finish_non_exhaustive()
+    }
+}
+
+fn now() -> u64 {
+    UNIX_EPOCH.elapsed().map_or(0, |since| since.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::scrobbler::Scrobbler;
+    use crate::service::ScrobbleTarget;
+    use crate::headers::Headers;
+    use crate::transport::HttpResponse;
+    use mockito::{mock, Matcher};
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    // Accepts every submission, keeping its body
+    #[derive(Clone, Default)]
+    struct Submissions(Arc<Mutex<Vec<Value>>>);
+
+    impl Transport for Submissions {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+            let body = serde_json::from_str(request.json_body.as_deref().unwrap_or_default()).unwrap();
+            self.0.lock().unwrap().push(body);
+            Ok(HttpResponse {
+                status: 200,
+                body: r#"{"status": "ok"}"#.to_owned(),
+                headers: Headers::new(),
+            })
+        }
+    }
+
+    fn scrobble(track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new("floyd", track, "bananas");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_listenbrainz_submissions() {
+        let listenbrainz = ListenBrainz::new("usertoken");
+        let mut playing = Scrobble::new("Kraftwerk", "Tour de France", "");
+        playing.with_artists(&["Kraftwerk", "Karl Bartos"]).with_duration(400).with_timestamp(1_500_000_000);
+        let now_playing = mock("POST", "/1/submit-listens")
+            .match_header("authorization", "Token usertoken")
+            .match_header("content-type", "application/json")
+            .match_body(Matcher::Json(json!({
+                "listen_type": "playing_now",
+                "payload": [{"track_metadata": {
+                    "artist_name": "Kraftwerk",
+                    "track_name": "Tour de France",
+                    "additional_info": {
+                        "artist_names": ["Kraftwerk", "Karl Bartos"],
+                        "duration_ms": 400_000,
+                        "submission_client": "rustfm-scrobble",
+                        "submission_client_version": env!("CARGO_PKG_VERSION"),
+                    },
+                }}],
+            })))
+            .with_body(r#"{"status": "ok"}"#)
+            .create();
+        listenbrainz.now_playing(&playing).unwrap();
+        now_playing.assert();
+
+        let mut single = scrobble("fruitflies", 1_500_000_000);
+        single.with_track_number(3).with_mbid("8f3471b5-7e6a-48da-86a9-c1c07a0f47ae");
+        let listen = mock("POST", "/1/submit-listens")
+            .match_body(Matcher::PartialJson(json!({
+                "listen_type": "single",
+                "payload": [{
+                    "listened_at": 1_500_000_000,
+                    "track_metadata": {
+                        "artist_name": "floyd",
+                        "track_name": "fruitflies",
+                        "release_name": "bananas",
+                        "additional_info": {"tracknumber": 3, "recording_mbid": "8f3471b5-7e6a-48da-86a9-c1c07a0f47ae"},
+                    },
+                }],
+            })))
+            .with_body(r#"{"status": "ok"}"#)
+            .create();
+        listenbrainz.scrobble(&single).unwrap();
+        listen.assert();
+
+        assert!(listenbrainz.scrobble_batch(&ScrobbleBatch::from(Vec::<Scrobble>::new())).is_err());
+    }
+
+    #[test]
+    fn check_listenbrainz_import_chunks() {
+        let submissions = Submissions::default();
+        let listenbrainz = ListenBrainz::with_transport("usertoken", submissions.clone());
+        let listens: Vec<Scrobble> = (0..1001).map(|n| scrobble("fruitflies", 1_500_000_000 + n * 300)).collect();
+        listenbrainz.scrobble_batch(&ScrobbleBatch::from(listens)).unwrap();
+
+        // Imports are split into as many submissions as needed
+        let bodies = submissions.0.lock().unwrap();
+        let sizes: Vec<_> = bodies.iter().map(|body| body["payload"].as_array().unwrap().len()).collect();
+        assert_eq!(sizes, [1000, 1]);
+        assert!(bodies.iter().all(|body| body["listen_type"] == "import"));
+        assert_eq!(bodies[1]["payload"][0]["listened_at"], 1_500_000_000 + 1000 * 300);
+    }
+
+    #[test]
+    fn check_listenbrainz_errors() {
+        let mut listenbrainz = ListenBrainz::new("badtoken");
+        listenbrainz.set_base_url(&format!("{}/selfhosted/", mockito::server_url()));
+        assert_eq!(listenbrainz.base_url(), format!("{}/selfhosted", mockito::server_url()));
+        assert!(!format!("{listenbrainz:?}").contains("badtoken"));
+
+        let rejected = mock("POST", "/selfhosted/1/submit-listens")
+            .with_status(401)
+            .with_body(r#"{"code": 401, "error": "Invalid authorization token."}"#)
+            .create();
+        let err = listenbrainz.scrobble(&scrobble("fruitflies", 1_500_000_000)).unwrap_err();
+        assert_eq!((err.kind(), err.http_status()), (ErrorKind::Http, Some(401)));
+        assert_eq!(err.to_string(), "ListenBrainz rejected the listens: Invalid authorization token.");
+        rejected.assert();
+    }
+
+    #[test]
+    fn check_listenbrainz_as_target() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_dry_run(true);
+        let targets: Vec<Box<dyn ScrobbleTarget>> = vec![Box::new(scrobbler), Box::new(ListenBrainz::new("usertoken"))];
+
+        // Only ListenBrainz is sent anything, as the Scrobbler is in dry-run mode
+        let listen = mock("POST", "/1/submit-listens").with_body(r#"{"status": "ok"}"#).expect(1).create();
+        let played = scrobble("fruitflies", now() - 600);
+        for target in &targets {
+            target.now_playing(&played).unwrap_or_else(|err| panic!("{}", err));
+        }
+        listen.assert();
+    }
+}
@@ -0,0 +1,66 @@
+// Options and reports for bulk Love/Unlove operations
+use crate::error::ScrobblerError;
+use crate::retry::RetryPolicy;
+
+use std::time::Duration;
+
+/// Controls the pacing and retrying of [`Scrobbler::love_many`] and [`Scrobbler::unlove_many`].
+///
+/// The default options send at most 5 requests per second, Last.fm's documented rate limit, and retry each request
+/// up to twice on transient failures.
+///
+/// [`Scrobbler::love_many`]: struct.Scrobbler.html#method.love_many
+/// [`Scrobbler::unlove_many`]: struct.Scrobbler.html#method.unlove_many
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BulkLoveOptions {
+    /// The minimum time between the starts of consecutive items
+    pub interval: Duration,
+    /// The policy for retrying an item which fails for transient reasons. See [`RetryPolicy`].
+    ///
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    pub retry_policy: RetryPolicy,
+}
+
+impl Default for BulkLoveOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(200),
+            retry_policy: RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(10),
+            },
+        }
+    }
+}
+
+/// The outcome of loving or unloving one track in a bulk operation
+#[derive(Debug)]
+pub struct LoveOutcome {
+    pub artist: String,
+    pub track: String,
+    /// `Err` if the track couldn't be loved/unloved, after any retries
+    pub result: Result<(), ScrobblerError>,
+}
+
+/// The per-item outcomes of [`Scrobbler::love_many`] or [`Scrobbler::unlove_many`], in the order the items were
+/// given
+///
+/// [`Scrobbler::love_many`]: struct.Scrobbler.html#method.love_many
+/// [`Scrobbler::unlove_many`]: struct.Scrobbler.html#method.unlove_many
+#[derive(Debug, Default)]
+pub struct BulkLoveReport {
+    pub outcomes: Vec<LoveOutcome>,
+}
+
+impl BulkLoveReport {
+    /// Returns the number of items which succeeded
+    pub fn succeeded(&self) -> usize {
+        self.outcomes.iter().filter(|outcome| outcome.result.is_ok()).count()
+    }
+
+    /// Returns the items which failed
+    pub fn failures(&self) -> impl Iterator<Item = &LoveOutcome> {
+        self.outcomes.iter().filter(|outcome| outcome.result.is_err())
+    }
+}
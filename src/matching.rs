@@ -0,0 +1,162 @@
+// The crate's single notion of "the same track", shared by everything which matches scrobbles against each other
+use crate::models::metadata::Scrobble;
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// An artist and track name, folded so that names which differ only in presentation compare equal. Import plans,
+/// queue repeat collapsing and [`BatchStats`] all match tracks by their `TrackKey`, so applications can use it to
+/// match tracks as the crate does.
+///
+/// Names are folded by:
+/// * lowercasing them, as an approximation of Unicode case folding (so `"ß"` matches `"ss"`, and `"ς"` matches
+///   `"σ"`)
+/// * collapsing runs of whitespace into a single space, and trimming it from either end
+/// * replacing full-width forms with their ASCII equivalents, ie. `"ＡＢＣ"` with `"abc"`
+/// * replacing curly and other typographic apostrophes and quotes with straight ones
+///
+/// Accents are kept: `"Sigur Rós"` and `"Sigur Ros"` have different keys, as Last.fm records them as different
+/// artists. With the `unicode` feature, [`TrackKey::ignoring_diacritics`] folds those too.
+///
+/// # Usage
+/// ```
+/// # use rustfm_scrobble::{Scrobble, TrackKey};
+/// let played = Scrobble::new("Guns N’ Roses", "Sweet  Child O' Mine", "");
+/// assert_eq!(TrackKey::from(&played), TrackKey::new("guns n' roses", "Sweet Child O’ Mine"));
+/// ```
+///
+/// [`BatchStats`]: struct.BatchStats.html
+/// [`TrackKey::ignoring_diacritics`]: struct.TrackKey.html#method.ignoring_diacritics
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TrackKey {
+    artist: String,
+    track: String,
+}
+
+impl TrackKey {
+    pub fn new(artist: &str, track: &str) -> Self {
+        Self {
+            artist: fold(artist),
+            track: fold(track),
+        }
+    }
+
+    /// Creates a key which also ignores accents and other diacritics, so that `"Sigur Rós"` matches
+    /// `"sigur ros"`. Requires the `unicode` feature.
+    ///
+    /// Names are decomposed to their compatibility forms (NFKD) before combining marks are removed, so ligatures
+    /// and other compatibility characters match their plain equivalents too, ie. `"ﬁ"` matches `"fi"`.
+    #[cfg(feature = "unicode")]
+    pub fn ignoring_diacritics(artist: &str, track: &str) -> Self {
+        let strip = |name: &str| -> String { name.nfkd().filter(|c| !is_combining_mark(*c)).collect() };
+        Self::new(&strip(artist), &strip(track))
+    }
+
+    /// Returns the folded artist name
+    pub fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    /// Returns the folded track name
+    pub fn track(&self) -> &str {
+        &self.track
+    }
+}
+
+impl From<&Scrobble> for TrackKey {
+    fn from(scrobble: &Scrobble) -> Self {
+        Self::new(scrobble.artist(), scrobble.track())
+    }
+}
+
+// Folds a single name, as for a `TrackKey`
+pub(crate) fn fold(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    let mut space = false;
+    for c in name.chars().map(fold_width) {
+        if c.is_whitespace() {
+            space = !folded.is_empty();
+            continue;
+        }
+        if space {
+            folded.push(' ');
+            space = false;
+        }
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' | '\u{02BC}' | '\u{00B4}' | '`' => {
+                folded.push('\'');
+            }
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => folded.push('"'),
+            // Lowercase forms which full case folding maps further
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            c => folded.extend(c.to_lowercase()),
+        }
+    }
+    folded
+}
+
+// Maps full-width ASCII forms, and the ideographic space, to ASCII
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_track_key() {
+        let same = [
+            (("Kraftwerk", "Computerliebe"), ("  kraftwerk", "COMPUTERLIEBE ")),
+            (("Sigur Rós", "Hoppípolla"), ("SIGUR RÓS", "hoppípolla")),
+            (("Guns N’ Roses", "Sweet Child O’ Mine"), ("Guns N' Roses", "Sweet Child O' Mine")),
+            (("Beyoncé", "“Crazy” in Love"), ("beyoncé", "\"crazy\" in love")),
+            (("ＹＭＯ", "Ｒｙｄｅｅｎ"), ("YMO", "Rydeen")),
+            (("Die Ärzte", "Straße"), ("die ärzte", "STRASSE")),
+            (("Ελευθερία", "Σίσυφος"), ("ΕΛΕΥΘΕΡΊΑ", "σίσυφοσ")),
+            (("The  Beatles", "Let\tIt\u{3000}Be"), ("the beatles", "let it be")),
+        ];
+        for ((artist, track), (other_artist, other_track)) in &same {
+            assert_eq!(TrackKey::new(artist, track), TrackKey::new(other_artist, other_track), "{artist}");
+        }
+
+        let different = [
+            (("Sigur Rós", "Hoppípolla"), ("sigur ros", "hoppipolla")),
+            (("Kraftwerk", "Computerliebe"), ("Karl Bartos", "Computerliebe")),
+            (("The Beatles", "Let It Be"), ("TheBeatles", "Let It Be")),
+        ];
+        for ((artist, track), (other_artist, other_track)) in &different {
+            assert_ne!(TrackKey::new(artist, track), TrackKey::new(other_artist, other_track), "{artist}");
+        }
+
+        let key = TrackKey::from(&Scrobble::new(" ＹＭＯ ", "Rydeen  ’79", "BGM"));
+        assert_eq!((key.artist(), key.track()), ("ymo", "rydeen '79"));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn check_track_key_ignoring_diacritics() {
+        let same = [
+            (("Sigur Rós", "Hoppípolla"), ("sigur ros", "HOPPIPOLLA")),
+            (("Beyoncé", "Déjà Vu"), ("Beyonce", "Deja Vu")),
+            (("Motörhead", "Ace of Spades"), ("Motorhead", "ace  of spades")),
+            (("Ｓｉｇｕｒ Ｒóｓ", "Ｇｌóｓóｌｉ"), ("Sigur Ros", "Glosoli")),
+            (("Guns N’ Roses", "Paradise City"), ("guns n' roses", "paradise city")),
+            (("Björk", "Hyperballad"), ("Bjo\u{308}rk", "hyperballad")),
+            (("The Knife", "ﬁlm"), ("the knife", "film")),
+        ];
+        for ((artist, track), (other_artist, other_track)) in &same {
+            let key = TrackKey::ignoring_diacritics(artist, track);
+            assert_eq!(key, TrackKey::ignoring_diacritics(other_artist, other_track), "{artist}");
+        }
+
+        // Letters which aren't a base letter with a mark are kept
+        assert_ne!(TrackKey::ignoring_diacritics("Ørjan", "x"), TrackKey::ignoring_diacritics("Orjan", "x"));
+        assert_eq!(TrackKey::ignoring_diacritics("Sigur Rós", "Hoppípolla").artist(), "sigur ros");
+    }
+}
@@ -0,0 +1,55 @@
+// Kinds of media other than music, which servers and players may want to treat differently
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// The extension parameter a Scrobble's media kind is sent as, to servers other than Last.fm
+pub(crate) const DEFAULT_MEDIA_KIND_PARAM: &str = "mediaKind";
+
+/// What kind of media a [`Scrobble`] is a listen of. Set with [`Scrobble::with_media_kind`].
+///
+/// Last.fm only knows about music, so a `Scrobbler` never sends the media kind to it. Other servers implementing
+/// the API may accept it as an extension parameter, so that long-form listens stay out of music statistics; see
+/// [`Scrobbler::set_media_kind_param`]. A [`ScrobbleTimer`] applies a different scrobble point to long-form media.
+///
+/// [`Scrobble`]: struct.Scrobble.html
+/// [`Scrobble::with_media_kind`]: struct.Scrobble.html#method.with_media_kind
+/// [`Scrobbler::set_media_kind_param`]: struct.Scrobbler.html#method.set_media_kind_param
+/// [`ScrobbleTimer`]: struct.ScrobbleTimer.html
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MediaKind {
+    #[default]
+    Music,
+    Podcast,
+    Audiobook,
+    /// Any other kind of media, sent as the given name
+    Other(String),
+}
+
+impl MediaKind {
+    /// Returns true for music, the default
+    pub fn is_music(&self) -> bool {
+        *self == Self::Music
+    }
+
+    /// Returns true for podcasts and audiobooks, which are typically listened to in long sessions
+    pub fn is_long_form(&self) -> bool {
+        matches!(self, Self::Podcast | Self::Audiobook)
+    }
+
+    /// Returns the name the media kind is sent as: `"music"`, `"podcast"`, `"audiobook"`, or the name of another
+    /// kind of media
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Music => "music",
+            Self::Podcast => "podcast",
+            Self::Audiobook => "audiobook",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for MediaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
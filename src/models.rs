@@ -1,27 +1,60 @@
 pub mod responses {
 
+    use std::convert::TryFrom;
     use std::fmt;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-    use serde::Deserialize;
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Serialize, Serializer};
     use serde_json as json;
 
+    use crate::issues::PreparationReport;
+    use crate::refresh;
+
     #[derive(Deserialize, Debug)]
     pub struct AuthResponse {
         pub session: SessionResponse,
     }
 
+    // Response to an auth.getToken request: a request token for the user to authorize in their browser
+    #[derive(Deserialize, Debug)]
+    pub struct TokenResponse {
+        pub token: String,
+    }
+
     /// Response to an Authentication request. 
     /// 
     /// Contains a Session Key and the username of the authenticated Last.fm user and a subscriber ID.
     /// Only the Session Key is used internally by the crate; the other values are exposed as they may have some value
     /// for clients.
     /// 
+    /// Serializes as Last.fm sends it, session key included, so that a session can be kept between runs.
+    ///
     /// [Authentication API Requests Documentation](https://www.last.fm/api/authspec)
-    #[derive(Deserialize, Debug, Clone)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[non_exhaustive]
     pub struct SessionResponse {
-        pub key: String,
-        pub subscriber: i64,
-        pub name: String,
+        pub(crate) key: String,
+        #[serde(deserialize_with = "deserialize_subscriber")]
+        pub(crate) subscriber: i64,
+        pub(crate) name: String,
+    }
+
+    impl SessionResponse {
+        /// Returns the session key, which authenticates further requests on behalf of the user
+        pub fn key(&self) -> &str {
+            &self.key
+        }
+
+        /// Returns `1` if the user is a Last.fm subscriber, otherwise `0`
+        pub fn subscriber(&self) -> i64 {
+            self.subscriber
+        }
+
+        /// Returns the username of the authenticated user
+        pub fn name(&self) -> &str {
+            &self.name
+        }
     }
 
     #[derive(Deserialize)]
@@ -35,14 +68,133 @@ pub mod responses {
     /// fields are [`CorrectableString`] types, which can be used to see if Last.fm applied any metadata correction
     /// to your artist, song or album. 
     /// 
+    /// Serializes as Last.fm sends it, along with what the `Scrobbler` fills in, and deserializes from either.
+    ///
     /// [Now Playing Request API Documentation](https://www.last.fm/api/show/track.updateNowPlaying)
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
+    #[non_exhaustive]
     pub struct NowPlayingResponse {
-        pub artist: CorrectableString,
-        pub album: CorrectableString,
+        pub(crate) artist: CorrectableString,
+        pub(crate) album: CorrectableString,
         #[serde(rename = "albumArtist")]
-        pub album_artist: CorrectableString,
-        pub track: CorrectableString,
+        pub(crate) album_artist: CorrectableString,
+        pub(crate) track: CorrectableString,
+        #[serde(
+            rename = "ignoredMessage",
+            default,
+            serialize_with = "IgnoredReason::serialize_ignored_message",
+            deserialize_with = "IgnoredReason::deserialize_ignored_message"
+        )]
+        ignored_reason: Option<IgnoredReason>,
+        // Filled in by the `Scrobbler`, rather than sent by Last.fm
+        #[serde(rename = "dryRun", default)]
+        dry_run: bool,
+        #[serde(default)]
+        outcome: NowPlayingOutcome,
+        #[serde(rename = "expiresAt", default)]
+        expires_at: Option<SystemTime>,
+    }
+
+    /// Whether a now playing update was sent. See [`NowPlayingResponse::outcome`].
+    ///
+    /// [`NowPlayingResponse::outcome`]: ../struct.NowPlayingResponse.html#method.outcome
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum NowPlayingOutcome {
+        /// The update was sent, or would have been in dry run mode
+        #[default]
+        Sent,
+        /// The update failed for a transient reason, given as the error's message, and was skipped. See
+        /// [`Scrobbler::set_now_playing_best_effort`].
+        ///
+        /// [`Scrobbler::set_now_playing_best_effort`]: ../../struct.Scrobbler.html#method.set_now_playing_best_effort
+        Skipped(String),
+    }
+
+    impl NowPlayingResponse {
+        // Builds a response reporting the given track back uncorrected, as if Last.fm had accepted it as-is
+        pub(crate) fn from_scrobble(scrobble: &super::metadata::Scrobble) -> Self {
+            Self {
+                artist: CorrectableString::uncorrected(scrobble.artist()),
+                album: CorrectableString::uncorrected(scrobble.album()),
+                album_artist: CorrectableString::uncorrected(""),
+                track: CorrectableString::uncorrected(scrobble.track()),
+                ignored_reason: None,
+                dry_run: false,
+                outcome: NowPlayingOutcome::Sent,
+                expires_at: Some(SystemTime::now() + refresh::now_playing_lifetime(scrobble)),
+            }
+        }
+
+        // Sets when the status is expected to lapse, for an update of `scrobble` sent just now
+        pub(crate) fn with_expiry(mut self, scrobble: &super::metadata::Scrobble) -> Self {
+            self.expires_at = Some(SystemTime::now() + refresh::now_playing_lifetime(scrobble));
+            self
+        }
+
+        /// Returns the reason Last.fm ignored the update, or `None` if the track is now playing. Last.fm ignores
+        /// now playing updates for the same reasons as scrobbles, ie. an ignored artist.
+        pub fn ignored_reason(&self) -> Option<IgnoredReason> {
+            self.ignored_reason
+        }
+
+        /// Returns the artist name, as Last.fm understood it
+        pub fn artist(&self) -> &CorrectableString {
+            &self.artist
+        }
+
+        /// Returns the album name, as Last.fm understood it
+        pub fn album(&self) -> &CorrectableString {
+            &self.album
+        }
+
+        /// Returns the album artist name, as Last.fm understood it
+        pub fn album_artist(&self) -> &CorrectableString {
+            &self.album_artist
+        }
+
+        /// Returns the track name, as Last.fm understood it
+        pub fn track(&self) -> &CorrectableString {
+            &self.track
+        }
+
+        pub(crate) fn into_dry_run(mut self) -> Self {
+            self.dry_run = true;
+            self
+        }
+
+        // A response standing in for an update which was skipped, reporting the track back uncorrected
+        pub(crate) fn into_skipped(mut self, reason: String) -> Self {
+            self.outcome = NowPlayingOutcome::Skipped(reason);
+            self.expires_at = None;
+            self
+        }
+
+        /// Returns when Last.fm is expected to clear the now playing status the update set: once the track's
+        /// duration has passed, if the update gave one, or otherwise 5 minutes after it was sent. Last.fm doesn't
+        /// report this itself, so it's worked out when the response arrives. `None` for a skipped update, which
+        /// set nothing.
+        ///
+        /// [`NowPlayingRefresher`] refreshes the status shortly before this time.
+        ///
+        /// [`NowPlayingRefresher`]: ../struct.NowPlayingRefresher.html
+        pub fn expires_at(&self) -> Option<SystemTime> {
+            self.expires_at
+        }
+
+        /// Returns whether the update was sent, or skipped because it failed with best effort updates enabled. A
+        /// skipped update's names are those of the track, uncorrected.
+        pub fn outcome(&self) -> &NowPlayingOutcome {
+            &self.outcome
+        }
+
+        /// Returns true if this response was synthesized by a [`Scrobbler`] in dry-run mode, rather than received
+        /// from Last.fm. See [`Scrobbler::set_dry_run`].
+        ///
+        /// [`Scrobbler`]: ../struct.Scrobbler.html
+        /// [`Scrobbler::set_dry_run`]: ../struct.Scrobbler.html#method.set_dry_run
+        pub fn dry_run(&self) -> bool {
+            self.dry_run
+        }
     }
 
     #[derive(Deserialize)]
@@ -53,6 +205,61 @@ pub mod responses {
     #[derive(Deserialize)]
     pub struct SingleScrobble {
         pub scrobble: ScrobbleResponse,
+        #[serde(rename = "@attr", default)]
+        pub counts: Option<ScrobbleCounts>,
+    }
+
+    // The accepted/ignored scrobble counts Last.fm sends alongside the scrobbles in a Scrobble response
+    #[derive(Deserialize, Debug, Clone, Copy)]
+    pub struct ScrobbleCounts {
+        #[serde(deserialize_with = "deserialize_count")]
+        pub accepted: u32,
+        #[serde(deserialize_with = "deserialize_count")]
+        pub ignored: u32,
+    }
+
+    fn deserialize_subscriber<'de, D>(de: D) -> Result<i64, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value: json::Value = serde::Deserialize::deserialize(de)?;
+        let subscriber = match &value {
+            json::Value::Number(n) => n.as_i64(),
+            json::Value::String(s) => s.parse().ok(),
+            _ => None,
+        };
+
+        subscriber.ok_or_else(|| serde::de::Error::custom(format!("Unexpected subscriber {value}")))
+    }
+
+    // Last.fm sends numeric fields as either JSON numbers or strings, depending on the endpoint (and always as
+    // strings in XML responses)
+    fn deserialize_count<'de, D>(de: D) -> Result<u32, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value: json::Value = serde::Deserialize::deserialize(de)?;
+        let count = match &value {
+            json::Value::Number(n) => n.as_u64(),
+            json::Value::String(s) => s.parse().ok(),
+            _ => None,
+        };
+
+        count
+            .and_then(|count| u32::try_from(count).ok())
+            .ok_or_else(|| serde::de::Error::custom(format!("Unexpected count {value}")))
+    }
+
+    fn deserialize_timestamp<'de, D>(de: D) -> Result<String, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value: json::Value = serde::Deserialize::deserialize(de)?;
+        match value {
+            json::Value::String(s) => Ok(s),
+            json::Value::Number(n) => Ok(n.to_string()),
+            value => Err(serde::de::Error::custom(format!("Unexpected timestamp {value}"))),
+        }
     }
 
     /// Response to a Scrobble request
@@ -60,17 +267,145 @@ pub mod responses {
     /// Represents a response to a Scrobble API request. Contains the results of the Scrobble call, including any 
     /// metadata corrections the Last.fm API made to the arist/track/album submitted.
     /// 
+    /// Serializes as Last.fm sends it, along with what the `Scrobbler` fills in, and deserializes from either.
+    ///
     /// [Scrobble Request API Documentation](https://www.last.fm/api/show/track.scrobble)
-    #[derive(Deserialize, Debug, WrappedVec)]
+    #[derive(Serialize, Deserialize, Debug, WrappedVec)]
     #[CollectionName = "ScrobbleList"]
-    #[CollectionDerives = "Debug, Deserialize"]
+    #[CollectionDerives = "Debug, Serialize, Deserialize"]
+    #[non_exhaustive]
     pub struct ScrobbleResponse {
-        pub artist: CorrectableString,
-        pub album: CorrectableString,
-        #[serde(rename = "albumArtist")]
-        pub album_artist: CorrectableString,
-        pub track: CorrectableString,
-        pub timestamp: String,
+        pub(crate) artist: CorrectableString,
+        pub(crate) album: CorrectableString,
+        // Libre.fm leaves the album artist out, and sends the timestamp as a number
+        #[serde(rename = "albumArtist", default)]
+        pub(crate) album_artist: CorrectableString,
+        pub(crate) track: CorrectableString,
+        #[serde(deserialize_with = "deserialize_timestamp")]
+        pub(crate) timestamp: String,
+        #[serde(
+            rename = "ignoredMessage",
+            default,
+            serialize_with = "IgnoredReason::serialize_ignored_message",
+            deserialize_with = "IgnoredReason::deserialize_ignored_message"
+        )]
+        ignored_reason: Option<IgnoredReason>,
+        // Filled in by the `Scrobbler`, rather than sent by Last.fm
+        #[serde(rename = "submittedTimestamp", default)]
+        submitted_timestamp: u64,
+        #[serde(rename = "correlationId", default)]
+        correlation_id: Option<String>,
+        #[serde(rename = "dryRun", default)]
+        dry_run: bool,
+    }
+
+    impl ScrobbleResponse {
+        // Builds a response reporting the given track back uncorrected, as if Last.fm had accepted it as-is
+        pub(crate) fn from_scrobble(scrobble: &super::metadata::Scrobble, timestamp: u64) -> Self {
+            Self {
+                artist: CorrectableString::uncorrected(scrobble.artist()),
+                album: CorrectableString::uncorrected(scrobble.album()),
+                album_artist: CorrectableString::uncorrected(""),
+                track: CorrectableString::uncorrected(scrobble.track()),
+                timestamp: timestamp.to_string(),
+                ignored_reason: None,
+                submitted_timestamp: timestamp,
+                correlation_id: None,
+                dry_run: false,
+            }
+        }
+
+        pub(crate) fn with_submitted_timestamp(mut self, timestamp: u64) -> Self {
+            self.submitted_timestamp = timestamp;
+            self
+        }
+
+        // Reconciles the response with the accepted/ignored counts sent alongside it. A response reported as
+        // ignored without an `ignoredMessage` is still treated as ignored.
+        pub(crate) fn with_counts(mut self, counts: Option<ScrobbleCounts>) -> Self {
+            if let Some(counts) = counts {
+                if self.ignored_reason.is_none() && counts.accepted == 0 && counts.ignored > 0 {
+                    self.ignored_reason = Some(IgnoredReason::Other(0));
+                }
+            }
+            self
+        }
+
+        /// Returns the artist name, as Last.fm understood it
+        pub fn artist(&self) -> &CorrectableString {
+            &self.artist
+        }
+
+        /// Returns the album name, as Last.fm understood it
+        pub fn album(&self) -> &CorrectableString {
+            &self.album
+        }
+
+        /// Returns the album artist name, as Last.fm understood it
+        pub fn album_artist(&self) -> &CorrectableString {
+            &self.album_artist
+        }
+
+        /// Returns the track name, as Last.fm understood it
+        pub fn track(&self) -> &CorrectableString {
+            &self.track
+        }
+
+        /// Returns the timestamp of the scrobble, in seconds since the Unix epoch, as Last.fm sent it
+        pub fn timestamp(&self) -> &str {
+            &self.timestamp
+        }
+
+        /// Returns the timestamp the scrobble was submitted with, in seconds since the Unix epoch: the `Scrobble`'s
+        /// own timestamp if it had one, or the time it was scrobbled at if it didn't. Unlike
+        /// [`ScrobbleResponse::timestamp`], this is filled in by the `Scrobbler` rather than read from the response.
+        ///
+        /// [`ScrobbleResponse::timestamp`]: struct.ScrobbleResponse.html#method.timestamp
+        pub fn submitted_timestamp(&self) -> u64 {
+            self.submitted_timestamp
+        }
+
+        /// Returns true if Last.fm accepted the scrobble.
+        ///
+        /// Last.fm can respond successfully to a Scrobble request while still ignoring the scrobble, in which case
+        /// the play is not counted. See [`ScrobbleResponse::ignored_reason`] for why it was ignored.
+        ///
+        /// [`ScrobbleResponse::ignored_reason`]: struct.ScrobbleResponse.html#method.ignored_reason
+        pub fn accepted(&self) -> bool {
+            self.ignored_reason.is_none()
+        }
+
+        /// Returns the reason Last.fm ignored the scrobble, or `None` if it was accepted
+        pub fn ignored_reason(&self) -> Option<IgnoredReason> {
+            self.ignored_reason
+        }
+
+        pub(crate) fn with_correlation_id(mut self, correlation_id: String) -> Self {
+            self.correlation_id = Some(correlation_id);
+            self
+        }
+
+        /// Returns the correlation ID of the API call the scrobble was submitted in (as
+        /// [`ScrobblerError::correlation_id`] is for failed calls), or `None` if it was never sent
+        ///
+        /// [`ScrobblerError::correlation_id`]: ../struct.ScrobblerError.html#method.correlation_id
+        pub fn correlation_id(&self) -> Option<&str> {
+            self.correlation_id.as_deref()
+        }
+
+        pub(crate) fn into_dry_run(mut self) -> Self {
+            self.dry_run = true;
+            self
+        }
+
+        /// Returns true if this response was synthesized by a [`Scrobbler`] in dry-run mode, rather than received
+        /// from Last.fm. See [`Scrobbler::set_dry_run`].
+        ///
+        /// [`Scrobbler`]: ../struct.Scrobbler.html
+        /// [`Scrobbler::set_dry_run`]: ../struct.Scrobbler.html#method.set_dry_run
+        pub fn dry_run(&self) -> bool {
+            self.dry_run
+        }
     }
 
     /// Response to a Batch Scrobble request
@@ -79,9 +414,52 @@ pub mod responses {
     /// any metadata corrections the Last.fm API made to the arist/track/album submitted.
     /// 
     /// [Scrobble Request API Documentation](https://www.last.fm/api/show/track.scrobble)
-    #[derive(Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
+    #[non_exhaustive]
     pub struct BatchScrobbleResponse {
-        pub scrobbles: ScrobbleList,
+        pub(crate) scrobbles: ScrobbleList,
+        #[serde(rename = "dryRun")]
+        pub(crate) dry_run: bool,
+        pub(crate) preparation: PreparationReport,
+    }
+
+    impl BatchScrobbleResponse {
+        /// Returns the responses to the individual scrobbles, in the order they were submitted
+        pub fn scrobbles(&self) -> &ScrobbleList {
+            &self.scrobbles
+        }
+
+        /// Consumes the response, returning the responses to the individual scrobbles
+        pub fn into_scrobbles(self) -> ScrobbleList {
+            self.scrobbles
+        }
+
+        /// Returns the number of scrobbles Last.fm accepted
+        pub fn accepted_count(&self) -> usize {
+            self.scrobbles.iter().filter(|scrobble| scrobble.accepted()).count()
+        }
+
+        /// Returns the number of scrobbles Last.fm ignored. See `ScrobbleResponse::ignored_reason`.
+        pub fn ignored_count(&self) -> usize {
+            self.scrobbles.len() - self.accepted_count()
+        }
+
+        /// Returns true if this response was synthesized by a [`Scrobbler`] in dry-run mode, rather than received
+        /// from Last.fm. See [`Scrobbler::set_dry_run`].
+        ///
+        /// [`Scrobbler`]: ../struct.Scrobbler.html
+        /// [`Scrobbler::set_dry_run`]: ../struct.Scrobbler.html#method.set_dry_run
+        pub fn dry_run(&self) -> bool {
+            self.dry_run
+        }
+
+        /// Returns the changes made to the batch's scrobbles before they were submitted, ie. truncated names and
+        /// generated timestamps. See [`PreparationReport`].
+        ///
+        /// [`PreparationReport`]: ../struct.PreparationReport.html
+        pub fn preparation(&self) -> &PreparationReport {
+            &self.preparation
+        }
     }
 
     #[derive(Deserialize, Debug)]
@@ -89,156 +467,1726 @@ pub mod responses {
         pub scrobbles: BatchScrobbles,
     }
 
-    #[derive(Deserialize, Debug)]
-    pub struct BatchScrobbles {
-        #[serde(rename = "scrobble")]
-        pub scrobbles: ScrobbleList,
+    #[derive(Deserialize, Debug)]
+    pub struct BatchScrobbles {
+        #[serde(rename = "scrobble", deserialize_with = "deserialize_scrobble_list")]
+        pub scrobbles: ScrobbleList,
+        #[serde(rename = "@attr", default)]
+        pub counts: Option<ScrobbleCounts>,
+    }
+
+    impl BatchScrobbles {
+        // The scrobbles, reconciled with the accepted/ignored counts. Counts can only be attributed to the
+        // scrobbles when none were accepted, in which case every one was ignored, whatever its `ignoredMessage`.
+        pub fn into_reconciled(self) -> ScrobbleList {
+            let counts = self.counts;
+            self.scrobbles.into_iter().map(|scrobble| scrobble.with_counts(counts)).collect()
+        }
+    }
+
+    // Last.fm sends the scrobbles of a batch as an array, except for a batch of one, whose scrobble is sent as a
+    // bare object (as is a lone element in XML responses)
+    fn deserialize_scrobble_list<'de, D>(de: D) -> Result<ScrobbleList, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserialize_one_or_many(de).map(ScrobbleList::from)
+    }
+
+    fn deserialize_one_or_many<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        T: serde::de::DeserializeOwned,
+    {
+        let value: json::Value = serde::Deserialize::deserialize(de)?;
+        let items = match value {
+            json::Value::Array(items) => items.into_iter().map(json::from_value).collect(),
+            item => json::from_value(item).map(|item| vec![item]),
+        };
+
+        items.map_err(serde::de::Error::custom)
+    }
+
+    #[derive(Deserialize)]
+    pub struct TrackInfoWrapper {
+        pub track: TrackInfo,
+    }
+
+    /// Response to a Track Info request
+    ///
+    /// Represents a response to a `track.getInfo` request: Last.fm's metadata and listening statistics for a track.
+    ///
+    /// [track.getInfo API Method Documentation](https://www.last.fm/api/show/track.getInfo)
+    #[derive(Deserialize, Debug, Clone)]
+    #[non_exhaustive]
+    pub struct TrackInfo {
+        pub(crate) name: String,
+        #[serde(default)]
+        pub(crate) mbid: String,
+        #[serde(default)]
+        pub(crate) url: String,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) duration: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) listeners: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) playcount: u64,
+        pub(crate) artist: TrackInfoArtist,
+        #[serde(default)]
+        pub(crate) album: Option<TrackInfoAlbum>,
+        #[serde(skip)]
+        pub(crate) autocorrected: bool,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub(crate) struct TrackInfoArtist {
+        name: String,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub(crate) struct TrackInfoAlbum {
+        title: String,
+    }
+
+    impl TrackInfo {
+        /// Returns the track name, as Last.fm knows it
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        /// Returns the artist name, as Last.fm knows it
+        pub fn artist(&self) -> &str {
+            &self.artist.name
+        }
+
+        /// Returns the title of the album Last.fm associates the track with, if any
+        pub fn album(&self) -> Option<&str> {
+            self.album.as_ref().map(|album| album.title.as_str())
+        }
+
+        /// Returns the track's `MusicBrainz` ID, if Last.fm knows it
+        pub fn mbid(&self) -> Option<&str> {
+            Some(self.mbid.as_str()).filter(|mbid| !mbid.is_empty())
+        }
+
+        /// Returns the URL of the track's Last.fm page
+        pub fn url(&self) -> &str {
+            &self.url
+        }
+
+        /// Returns the track's duration, if Last.fm knows it
+        pub fn duration(&self) -> Option<Duration> {
+            Some(self.duration).filter(|ms| *ms > 0).map(Duration::from_millis)
+        }
+
+        /// Returns the number of Last.fm users who have listened to the track
+        pub fn listeners(&self) -> u64 {
+            self.listeners
+        }
+
+        /// Returns the number of times the track has been scrobbled
+        pub fn playcount(&self) -> u64 {
+            self.playcount
+        }
+
+        /// Returns true if the lookup asked Last.fm to autocorrect the names (see [`Scrobbler::set_autocorrect`]),
+        /// and it returned a different artist or track name than was looked up. Differences only in case don't
+        /// count, since Last.fm ignores case when looking names up anyway.
+        ///
+        /// [`Scrobbler::set_autocorrect`]: ../struct.Scrobbler.html#method.set_autocorrect
+        pub fn autocorrected(&self) -> bool {
+            self.autocorrected
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct ArtistInfoWrapper {
+        pub artist: ArtistInfo,
+    }
+
+    /// Response to an Artist Info request
+    ///
+    /// Represents a response to an `artist.getInfo` request: Last.fm's metadata, listening statistics and biography
+    /// for an artist.
+    ///
+    /// [artist.getInfo API Method Documentation](https://www.last.fm/api/show/artist.getInfo)
+    #[derive(Deserialize, Debug, Clone)]
+    #[non_exhaustive]
+    pub struct ArtistInfo {
+        pub(crate) name: String,
+        #[serde(default)]
+        pub(crate) mbid: String,
+        #[serde(default)]
+        pub(crate) url: String,
+        #[serde(default)]
+        pub(crate) stats: ArtistStats,
+        #[serde(default)]
+        pub(crate) bio: Option<Wiki>,
+    }
+
+    #[derive(Deserialize, Debug, Clone, Default)]
+    pub(crate) struct ArtistStats {
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        listeners: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        playcount: u64,
+    }
+
+    // The localized text of an artist's biography or album's wiki
+    #[derive(Deserialize, Debug, Clone)]
+    pub(crate) struct Wiki {
+        #[serde(default)]
+        summary: String,
+        #[serde(default)]
+        content: String,
+    }
+
+    impl Wiki {
+        fn summary(wiki: Option<&Self>) -> Option<&str> {
+            wiki.map(|wiki| wiki.summary.as_str()).filter(|summary| !summary.trim().is_empty())
+        }
+
+        fn content(wiki: Option<&Self>) -> Option<&str> {
+            wiki.map(|wiki| wiki.content.as_str()).filter(|content| !content.trim().is_empty())
+        }
+    }
+
+    impl ArtistInfo {
+        /// Returns the artist name, as Last.fm knows it
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        /// Returns the artist's `MusicBrainz` ID, if Last.fm knows it
+        pub fn mbid(&self) -> Option<&str> {
+            Some(self.mbid.as_str()).filter(|mbid| !mbid.is_empty())
+        }
+
+        /// Returns the URL of the artist's Last.fm page
+        pub fn url(&self) -> &str {
+            &self.url
+        }
+
+        /// Returns the number of Last.fm users who have listened to the artist
+        pub fn listeners(&self) -> u64 {
+            self.stats.listeners
+        }
+
+        /// Returns the number of times the artist's tracks have been scrobbled
+        pub fn playcount(&self) -> u64 {
+            self.stats.playcount
+        }
+
+        /// Returns the short summary of the artist's biography, as HTML, if there is one. It is in the language
+        /// the lookup asked for (see [`Scrobbler::set_language`]) if Last.fm has a translation, and in English
+        /// otherwise; the response doesn't say which.
+        ///
+        /// [`Scrobbler::set_language`]: ../struct.Scrobbler.html#method.set_language
+        pub fn bio_summary(&self) -> Option<&str> {
+            Wiki::summary(self.bio.as_ref())
+        }
+
+        /// Returns the artist's full biography, as HTML, if there is one. See [`ArtistInfo::bio_summary`].
+        ///
+        /// [`ArtistInfo::bio_summary`]: struct.ArtistInfo.html#method.bio_summary
+        pub fn bio(&self) -> Option<&str> {
+            Wiki::content(self.bio.as_ref())
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct AlbumInfoWrapper {
+        pub album: AlbumInfo,
+    }
+
+    /// Response to an Album Info request
+    ///
+    /// Represents a response to an `album.getInfo` request: Last.fm's metadata, listening statistics and wiki text
+    /// for an album.
+    ///
+    /// [album.getInfo API Method Documentation](https://www.last.fm/api/show/album.getInfo)
+    #[derive(Deserialize, Debug, Clone)]
+    #[non_exhaustive]
+    pub struct AlbumInfo {
+        pub(crate) name: String,
+        pub(crate) artist: String,
+        #[serde(default)]
+        pub(crate) mbid: String,
+        #[serde(default)]
+        pub(crate) url: String,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) listeners: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) playcount: u64,
+        #[serde(default)]
+        pub(crate) wiki: Option<Wiki>,
+    }
+
+    impl AlbumInfo {
+        /// Returns the album title, as Last.fm knows it
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        /// Returns the name of the album's artist, as Last.fm knows it
+        pub fn artist(&self) -> &str {
+            &self.artist
+        }
+
+        /// Returns the album's `MusicBrainz` ID, if Last.fm knows it
+        pub fn mbid(&self) -> Option<&str> {
+            Some(self.mbid.as_str()).filter(|mbid| !mbid.is_empty())
+        }
+
+        /// Returns the URL of the album's Last.fm page
+        pub fn url(&self) -> &str {
+            &self.url
+        }
+
+        /// Returns the number of Last.fm users who have listened to the album
+        pub fn listeners(&self) -> u64 {
+            self.listeners
+        }
+
+        /// Returns the number of times the album's tracks have been scrobbled
+        pub fn playcount(&self) -> u64 {
+            self.playcount
+        }
+
+        /// Returns the short summary of the album's wiki text, as HTML, if there is one. As with
+        /// [`ArtistInfo::bio_summary`], it may be in English rather than the language asked for.
+        ///
+        /// [`ArtistInfo::bio_summary`]: struct.ArtistInfo.html#method.bio_summary
+        pub fn wiki_summary(&self) -> Option<&str> {
+            Wiki::summary(self.wiki.as_ref())
+        }
+
+        /// Returns the album's full wiki text, as HTML, if there is one
+        pub fn wiki(&self) -> Option<&str> {
+            Wiki::content(self.wiki.as_ref())
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct UserInfoWrapper {
+        pub user: UserInfo,
+    }
+
+    /// Response to a User Info request
+    ///
+    /// Represents a response to a `user.getInfo` request for the authenticated user: their profile and listening
+    /// statistics.
+    ///
+    /// [user.getInfo API Method Documentation](https://www.last.fm/api/show/user.getInfo)
+    #[derive(Deserialize, Debug, Clone)]
+    #[non_exhaustive]
+    pub struct UserInfo {
+        pub(crate) name: String,
+        #[serde(default)]
+        pub(crate) realname: String,
+        #[serde(default)]
+        pub(crate) url: String,
+        #[serde(default)]
+        pub(crate) country: String,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) subscriber: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) playcount: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) artist_count: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) track_count: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        pub(crate) album_count: u64,
+        #[serde(default)]
+        pub(crate) registered: Option<Registered>,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub(crate) struct Registered {
+        #[serde(deserialize_with = "deserialize_statistic")]
+        unixtime: u64,
+    }
+
+    impl UserInfo {
+        /// Returns the user's Last.fm username
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        /// Returns the user's real name, if they've given one
+        pub fn real_name(&self) -> Option<&str> {
+            Some(self.realname.as_str()).filter(|name| !name.is_empty())
+        }
+
+        /// Returns the URL of the user's Last.fm profile
+        pub fn url(&self) -> &str {
+            &self.url
+        }
+
+        /// Returns the user's country, if they've given one. Last.fm sends `"None"` for users who haven't.
+        pub fn country(&self) -> Option<&str> {
+            Some(self.country.as_str()).filter(|country| !country.is_empty() && *country != "None")
+        }
+
+        /// Returns true if the user is a paid Last.fm subscriber
+        pub fn subscriber(&self) -> bool {
+            self.subscriber != 0
+        }
+
+        /// Returns the number of tracks the user has scrobbled
+        pub fn playcount(&self) -> u64 {
+            self.playcount
+        }
+
+        /// Returns the number of distinct artists the user has scrobbled
+        pub fn artist_count(&self) -> u64 {
+            self.artist_count
+        }
+
+        /// Returns the number of distinct tracks the user has scrobbled
+        pub fn track_count(&self) -> u64 {
+            self.track_count
+        }
+
+        /// Returns the number of distinct albums the user has scrobbled
+        pub fn album_count(&self) -> u64 {
+            self.album_count
+        }
+
+        /// Returns when the user registered with Last.fm, if it's known
+        pub fn registered(&self) -> Option<SystemTime> {
+            let registered = self.registered.as_ref().filter(|registered| registered.unixtime > 0)?;
+            Some(UNIX_EPOCH + Duration::from_secs(registered.unixtime))
+        }
+    }
+
+    // Response to a `track.getCorrection` request. Last.fm sends `"corrections": "\n"` when it has no correction.
+    #[derive(Deserialize)]
+    pub struct CorrectionWrapper {
+        #[serde(deserialize_with = "deserialize_corrections")]
+        corrections: Option<TrackCorrection>,
+    }
+
+    #[derive(Deserialize)]
+    struct TrackCorrections {
+        #[serde(deserialize_with = "deserialize_one_or_many")]
+        correction: Vec<TrackCorrection>,
+    }
+
+    #[derive(Deserialize)]
+    struct TrackCorrection {
+        track: CorrectedTrack,
+        #[serde(rename = "@attr", default)]
+        flags: CorrectionFlags,
+    }
+
+    #[derive(Deserialize)]
+    struct CorrectedTrack {
+        name: String,
+        artist: TrackInfoArtist,
+    }
+
+    #[derive(Deserialize, Default)]
+    struct CorrectionFlags {
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        artistcorrected: u64,
+        #[serde(default, deserialize_with = "deserialize_statistic")]
+        trackcorrected: u64,
+    }
+
+    fn deserialize_corrections<'de, D>(de: D) -> Result<Option<TrackCorrection>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value: json::Value = serde::Deserialize::deserialize(de)?;
+        if !value.is_object() {
+            return Ok(None);
+        }
+
+        let corrections: TrackCorrections = json::from_value(value).map_err(serde::de::Error::custom)?;
+        Ok(corrections.correction.into_iter().next())
+    }
+
+    impl CorrectionWrapper {
+        // The correction of the given names, which are kept as they are if Last.fm has none
+        pub fn into_response(self, artist: &str, track: &str) -> CorrectionResponse {
+            match self.corrections {
+                Some(correction) => CorrectionResponse {
+                    artist: CorrectableString {
+                        corrected: correction.flags.artistcorrected == 1,
+                        text: correction.track.artist.name,
+                    },
+                    track: CorrectableString {
+                        corrected: correction.flags.trackcorrected == 1,
+                        text: correction.track.name,
+                    },
+                },
+                None => CorrectionResponse {
+                    artist: CorrectableString::uncorrected(artist),
+                    track: CorrectableString::uncorrected(track),
+                },
+            }
+        }
+    }
+
+    /// Response to a Track Correction request
+    ///
+    /// Represents a response to a `track.getCorrection` request: the artist and track names Last.fm would correct
+    /// the looked up ones to. Names which Last.fm has no correction for are returned as they were given, marked
+    /// uncorrected.
+    ///
+    /// [track.getCorrection API Method Documentation](https://www.last.fm/api/show/track.getCorrection)
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub struct CorrectionResponse {
+        pub(crate) artist: CorrectableString,
+        pub(crate) track: CorrectableString,
+    }
+
+    impl CorrectionResponse {
+        /// Returns the artist name, after any correction
+        pub fn artist(&self) -> &CorrectableString {
+            &self.artist
+        }
+
+        /// Returns the track name, after any correction
+        pub fn track(&self) -> &CorrectableString {
+            &self.track
+        }
+
+        /// Returns true if Last.fm corrected either name
+        pub fn is_corrected(&self) -> bool {
+            self.artist.corrected || self.track.corrected
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct RecentTracksWrapper {
+        pub recenttracks: RecentTracks,
+    }
+
+    // One page of a user's listening history, most recent first, as returned by `user.getRecentTracks`
+    #[derive(Deserialize, Debug)]
+    pub struct RecentTracks {
+        #[serde(rename = "track", default, deserialize_with = "deserialize_one_or_many")]
+        pub tracks: Vec<RecentTrack>,
+        #[serde(rename = "@attr")]
+        pub page: RecentTracksPage,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct RecentTracksPage {
+        #[serde(deserialize_with = "deserialize_count")]
+        pub page: u32,
+        #[serde(rename = "totalPages", deserialize_with = "deserialize_count")]
+        pub total_pages: u32,
+    }
+
+    /// A track in a user's listening history, as returned by [`Scrobbler::recent_tracks`]: a scrobble, or the
+    /// track the user is playing now.
+    ///
+    /// [`Scrobbler::recent_tracks`]: ../struct.Scrobbler.html#method.recent_tracks
+    #[derive(Deserialize, Debug, Clone)]
+    #[non_exhaustive]
+    pub struct RecentTrack {
+        pub(crate) name: String,
+        pub(crate) artist: RecentTrackText,
+        #[serde(default)]
+        pub(crate) album: RecentTrackText,
+        #[serde(default)]
+        pub(crate) date: Option<RecentTrackDate>,
+        #[serde(rename = "@attr", default)]
+        pub(crate) attr: RecentTrackAttr,
+    }
+
+    #[derive(Deserialize, Debug, Clone, Default)]
+    pub(crate) struct RecentTrackText {
+        #[serde(rename = "#text", default)]
+        text: String,
+    }
+
+    #[derive(Deserialize, Debug, Clone, Default)]
+    pub(crate) struct RecentTrackAttr {
+        #[serde(default)]
+        nowplaying: String,
+    }
+
+    #[derive(Deserialize, Debug, Clone)]
+    pub(crate) struct RecentTrackDate {
+        #[serde(deserialize_with = "deserialize_statistic")]
+        uts: u64,
+    }
+
+    impl RecentTrack {
+        /// Returns the track name, as it was scrobbled
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        /// Returns the artist name, as it was scrobbled
+        pub fn artist(&self) -> &str {
+            &self.artist.text
+        }
+
+        /// Returns the album title, if the track was scrobbled with one
+        pub fn album(&self) -> Option<&str> {
+            Some(self.album.text.as_str()).filter(|album| !album.is_empty())
+        }
+
+        /// Returns the time the track was scrobbled, in seconds since the Unix epoch. The track being played now
+        /// hasn't been, so has none.
+        pub fn timestamp(&self) -> Option<u64> {
+            self.date.as_ref().map(|date| date.uts)
+        }
+
+        /// Returns true for the track the user is playing now, rather than one they've scrobbled
+        pub fn now_playing(&self) -> bool {
+            self.attr.nowplaying == "true"
+        }
+    }
+
+    // Statistics are sent as strings, or occasionally numbers; an empty or missing one is treated as 0
+    fn deserialize_statistic<'de, D>(de: D) -> Result<u64, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value: json::Value = serde::Deserialize::deserialize(de)?;
+        let statistic = match &value {
+            json::Value::Number(n) => n.as_u64(),
+            json::Value::String(s) if s.is_empty() => Some(0),
+            json::Value::String(s) => s.parse().ok(),
+            json::Value::Null => Some(0),
+            _ => None,
+        };
+
+        statistic.ok_or_else(|| serde::de::Error::custom(format!("Unexpected statistic {value}")))
+    }
+
+    /// The reason Last.fm gave for ignoring a submitted scrobble.
+    ///
+    /// Last.fm can accept a scrobble request but still ignore some (or all) of the scrobbles it contains. Each ignored
+    /// scrobble carries an `ignoredMessage` code which maps onto one of these variants; codes the crate doesn't know
+    /// about are preserved in `IgnoredReason::Other`.
+    ///
+    /// [Scrobble Response Documentation](https://www.last.fm/api/show/track.scrobble)
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum IgnoredReason {
+        ArtistIgnored,
+        TrackIgnored,
+        TimestampTooOld,
+        TimestampTooNew,
+        DailyScrobbleLimitExceeded,
+        Other(u32),
+    }
+
+    impl IgnoredReason {
+        /// Converts an `ignoredMessage` code into an `IgnoredReason`. Code `0` means the scrobble was not ignored,
+        /// and gives `None`.
+        pub fn from_code(code: u32) -> Option<Self> {
+            match code {
+                0 => None,
+                1 => Some(Self::ArtistIgnored),
+                2 => Some(Self::TrackIgnored),
+                3 => Some(Self::TimestampTooOld),
+                4 => Some(Self::TimestampTooNew),
+                5 => Some(Self::DailyScrobbleLimitExceeded),
+                code => Some(Self::Other(code)),
+            }
+        }
+
+        /// Returns the `ignoredMessage` code Last.fm uses for this reason
+        pub fn code(self) -> u32 {
+            match self {
+                Self::ArtistIgnored => 1,
+                Self::TrackIgnored => 2,
+                Self::TimestampTooOld => 3,
+                Self::TimestampTooNew => 4,
+                Self::DailyScrobbleLimitExceeded => 5,
+                Self::Other(code) => code,
+            }
+        }
+
+        // Deserializes an `ignoredMessage` object, ie. `{"code": "3", "#text": "Timestamp was too old"}`
+        fn deserialize_ignored_message<'de, D>(de: D) -> Result<Option<Self>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value: json::Value = serde::Deserialize::deserialize(de)?;
+            let code = match value.get("code") {
+                Some(json::Value::Number(n)) => n.as_u64(),
+                Some(json::Value::String(s)) => s.parse().ok(),
+                None => Some(0),
+                _ => None,
+            };
+
+            code.and_then(|code| u32::try_from(code).ok())
+                .map(Self::from_code)
+                .ok_or_else(|| serde::de::Error::custom(format!("Unexpected ignoredMessage {value}")))
+        }
+
+        // Serializes an `ignoredMessage` object as Last.fm sends it, with code `0` and no text when not ignored. Serde
+        // passes the field by reference.
+        #[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+        fn serialize_ignored_message<S>(reason: &Option<Self>, ser: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut message = ser.serialize_map(Some(2))?;
+            message.serialize_entry("code", &reason.map_or(0, Self::code).to_string())?;
+            message.serialize_entry("#text", reason.map_or("", Self::message))?;
+            message.end()
+        }
+
+        /// Returns the message Last.fm sends alongside the code
+        pub fn message(self) -> &'static str {
+            match self {
+                Self::ArtistIgnored => "Artist was ignored",
+                Self::TrackIgnored => "Track was ignored",
+                Self::TimestampTooOld => "Timestamp was too old",
+                Self::TimestampTooNew => "Timestamp was too new",
+                Self::DailyScrobbleLimitExceeded => "Daily scrobble limit exceeded",
+                Self::Other(_) => "Scrobble was ignored",
+            }
+        }
+    }
+
+    impl fmt::Display for IgnoredReason {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+
+    /// Represents a string that can be marked as 'corrected' by the Last.fm API. 
+    /// 
+    /// All Scrobble/NowPlaying responses have their fields as `CorrectableString`'s. The API will sometimes change
+    /// the artist/song name/album name data that you have submitted. For example - it is common for Bjork to be turned
+    /// into Björk by the API; the modified artist field would be marked `corrected = true`, `text = "Björk"`. 
+    /// 
+    /// Most clients can ignore these corrections, but the information is exposed for clients that require it.
+    /// 
+    /// Last.fm and compatible servers disagree on how corrections are sent: as an object holding a `corrected` flag
+    /// of `"1"`, `1` or `true` (or `"0"`, `0`, `false`) alongside the `#text`, or as a bare string. Every one of
+    /// these is accepted, and a flag in any other form is treated as uncorrected, keeping the text.
+    ///
+    /// [Meta-Data Correction Documentation](https://www.last.fm/api/scrobbling#meta-data-corrections)
+    #[derive(Debug, Default)]
+    #[non_exhaustive]
+    pub struct CorrectableString {
+        pub(crate) corrected: bool,
+        pub(crate) text: String,
+    }
+
+    impl CorrectableString {
+        pub(crate) fn uncorrected(text: &str) -> Self {
+            Self {
+                corrected: false,
+                text: text.to_owned(),
+            }
+        }
+
+        /// Returns true if Last.fm changed the value that was submitted
+        pub fn corrected(&self) -> bool {
+            self.corrected
+        }
+
+        /// Returns the value, after any correction
+        pub fn text(&self) -> &str {
+            &self.text
+        }
+
+        fn from_value(value: &json::Value) -> Self {
+            match value {
+                json::Value::Object(fields) => Self {
+                    corrected: fields.get("corrected").is_some_and(Self::is_corrected),
+                    text: fields.get("#text").map(Self::text_of).unwrap_or_default(),
+                },
+                value => Self::uncorrected(&Self::text_of(value)),
+            }
+        }
+
+        fn is_corrected(flag: &json::Value) -> bool {
+            match flag {
+                json::Value::Bool(corrected) => *corrected,
+                json::Value::Number(n) => n.as_u64() == Some(1),
+                json::Value::String(s) => s == "1" || s.eq_ignore_ascii_case("true"),
+                _ => false,
+            }
+        }
+
+        fn text_of(value: &json::Value) -> String {
+            match value {
+                json::Value::String(s) => s.clone(),
+                json::Value::Number(n) => n.to_string(),
+                _ => String::new(),
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for CorrectableString {
+        fn deserialize<D>(de: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let value: json::Value = Deserialize::deserialize(de)?;
+            Ok(Self::from_value(&value))
+        }
+    }
+
+    // Serialized as Last.fm sends corrections
+    impl Serialize for CorrectableString {
+        fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut value = ser.serialize_map(Some(2))?;
+            value.serialize_entry("corrected", if self.corrected { "1" } else { "0" })?;
+            value.serialize_entry("#text", &self.text)?;
+            value.end()
+        }
+    }
+
+    impl fmt::Display for CorrectableString {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.text)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use serde_json::json;
+
+        #[test]
+        fn check_correctable_string_representations() {
+            let cases = vec![
+                (json!({"corrected": "1", "#text": "Björk"}), true, "Björk"),
+                (json!({"corrected": "0", "#text": "Björk"}), false, "Björk"),
+                (json!({"corrected": 1, "#text": "Björk"}), true, "Björk"),
+                (json!({"corrected": 0, "#text": "Björk"}), false, "Björk"),
+                (json!({"corrected": true, "#text": "Björk"}), true, "Björk"),
+                (json!({"corrected": false, "#text": "Björk"}), false, "Björk"),
+                (json!({"corrected": "true", "#text": "Björk"}), true, "Björk"),
+                (json!({"#text": "Björk"}), false, "Björk"),
+                (json!({"corrected": "1"}), true, ""),
+                (json!("Björk"), false, "Björk"),
+                (json!(""), false, ""),
+                (json!(1999), false, "1999"),
+                (json!(null), false, ""),
+                // Unknown flags are treated as uncorrected, keeping the value
+                (json!({"corrected": "yes", "#text": "Björk"}), false, "Björk"),
+                (json!({"corrected": {"0": 1}, "#text": "Björk"}), false, "Björk"),
+                (json!({"corrected": 2, "#text": "Björk"}), false, "Björk"),
+            ];
+
+            for (value, corrected, text) in cases {
+                let decoded: CorrectableString = json::from_value(value.clone()).unwrap();
+                assert_eq!(decoded.corrected(), corrected, "corrected for {value}");
+                assert_eq!(decoded.text(), text, "text for {value}");
+            }
+        }
+
+        #[test]
+        fn check_correction_fixtures() {
+            let fixtures = [
+                include_str!("../tests/fixtures/corrections/lastfm.json"),
+                include_str!("../tests/fixtures/corrections/librefm.json"),
+            ];
+
+            for fixture in &fixtures {
+                let decoded: ScrobbleResponseWrapper = json::from_str(fixture).unwrap();
+                let scrobble = decoded.scrobbles.scrobble;
+                assert!(scrobble.artist().corrected());
+                assert_eq!(scrobble.artist().text(), "Björk");
+                assert!(!scrobble.album().corrected());
+                assert_eq!(scrobble.album().text(), "Homogenic");
+                assert!(!scrobble.track().corrected());
+                assert_eq!(scrobble.track().text(), "Jóga");
+                assert_eq!(scrobble.album_artist().text(), "");
+                assert!(scrobble.accepted());
+            }
+        }
+
+        #[test]
+        fn check_track_correction() {
+            let decoded: CorrectionWrapper =
+                json::from_str(include_str!("../tests/fixtures/corrections/track_correction.json")).unwrap();
+            let correction = decoded.into_response("Guns N Roses", "Sweet Child O' Mine");
+            assert!(correction.is_corrected());
+            assert!(correction.artist().corrected());
+            assert_eq!(correction.artist().text(), "Guns N' Roses");
+            assert!(!correction.track().corrected());
+            assert_eq!(correction.track().text(), "Sweet Child O' Mine");
+
+            // Without a correction, the names are kept as they were looked up
+            let decoded: CorrectionWrapper = json::from_value(json!({"corrections": "\n"})).unwrap();
+            let correction = decoded.into_response("Cher", "Believe");
+            assert!(!correction.is_corrected());
+            assert_eq!((correction.artist().text(), correction.track().text()), ("Cher", "Believe"));
+        }
+
+        fn decode_batch(body: &str) -> ScrobbleList {
+            json::from_str::<BatchScrobbleResponseWrapper>(body).unwrap().scrobbles.into_reconciled()
+        }
+
+        #[test]
+        fn check_batch_fixtures() {
+            let accepted = decode_batch(include_str!("../tests/fixtures/batches/accepted.json"));
+            assert_eq!(accepted.len(), 2);
+            assert!(accepted.iter().all(ScrobbleResponse::accepted));
+            assert_eq!(accepted.iter().last().unwrap().track().text(), "As Lucerne / The Low");
+
+            let partial = decode_batch(include_str!("../tests/fixtures/batches/partially_ignored.json"));
+            let reasons: Vec<_> = partial.iter().map(ScrobbleResponse::ignored_reason).collect();
+            assert_eq!(reasons, [None, Some(IgnoredReason::ArtistIgnored), Some(IgnoredReason::TimestampTooOld)]);
+
+            // A batch of one is sent as a bare object
+            let corrected = decode_batch(include_str!("../tests/fixtures/batches/corrected.json"));
+            let scrobble = corrected.iter().next().unwrap();
+            assert_eq!(corrected.len(), 1);
+            assert!(scrobble.accepted());
+            assert!(scrobble.artist().corrected() && scrobble.track().corrected());
+            assert_eq!((scrobble.artist().text(), scrobble.track().text()), ("Sigur Rós", "Svefn-g-englar"));
+            assert!(!scrobble.album().corrected());
+
+            // Scrobbles the counts say were all ignored are ignored, even without an `ignoredMessage` saying why
+            let mut value: json::Value =
+                json::from_str(include_str!("../tests/fixtures/batches/accepted.json")).unwrap();
+            value["scrobbles"]["@attr"] = json!({"accepted": "0", "ignored": "2"});
+            let ignored = decode_batch(&value.to_string());
+            assert!(ignored.iter().all(|scrobble| scrobble.ignored_reason() == Some(IgnoredReason::Other(0))));
+        }
+
+        #[test]
+        fn check_now_playing_ignored() {
+            let body = json!({
+                "nowplaying": {
+                    "artist": {"corrected": "0", "#text": "Unknown Artist"},
+                    "album": {"corrected": "0", "#text": ""},
+                    "track": {"corrected": "0", "#text": "Track 01"},
+                    "albumArtist": {"corrected": "0", "#text": ""},
+                    "ignoredMessage": {"code": "1", "#text": "Artist was ignored"}
+                }
+            });
+            let decoded: NowPlayingResponseWrapper = json::from_value(body).unwrap();
+            assert_eq!(decoded.nowplaying.ignored_reason(), Some(IgnoredReason::ArtistIgnored));
+
+            let fixture: json::Value =
+                json::from_str(include_str!("../tests/fixtures/corrections/lastfm.json")).unwrap();
+            let decoded: NowPlayingResponseWrapper =
+                json::from_value(json!({ "nowplaying": fixture["scrobbles"]["scrobble"] })).unwrap();
+            assert_eq!(decoded.nowplaying.ignored_reason(), None);
+        }
+
+        // Serializes `value`, and checks that what it deserializes to serializes the same
+        fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> (T, json::Value) {
+            let serialized = json::to_value(value).unwrap();
+            let decoded: T = json::from_value(serialized.clone()).unwrap();
+            assert_eq!(json::to_value(&decoded).unwrap(), serialized);
+            (decoded, serialized)
+        }
+
+        #[test]
+        fn check_response_serde_round_trip() {
+            let session: SessionResponse =
+                json::from_value(json!({"key": "seshkey", "subscriber": "0", "name": "floyd"})).unwrap();
+            let (session, serialized) = round_trip(&session);
+            assert_eq!((session.key(), session.subscriber(), session.name()), ("seshkey", 0, "floyd"));
+            assert_eq!(serialized, json!({"key": "seshkey", "subscriber": 0, "name": "floyd"}));
+
+            let partial = decode_batch(include_str!("../tests/fixtures/batches/partially_ignored.json"));
+            let scrobbles = partial
+                .into_iter()
+                .map(|scrobble| scrobble.with_submitted_timestamp(1_500_000_000).with_correlation_id("c0ffee".into()))
+                .collect::<Vec<_>>();
+            let batch = BatchScrobbleResponse {
+                scrobbles: ScrobbleList::from(scrobbles),
+                dry_run: false,
+                preparation: PreparationReport::default(),
+            };
+            let (batch, serialized) = round_trip(&batch);
+            let reasons: Vec<_> = batch.scrobbles().iter().map(ScrobbleResponse::ignored_reason).collect();
+            assert_eq!(reasons, [None, Some(IgnoredReason::ArtistIgnored), Some(IgnoredReason::TimestampTooOld)]);
+            let scrobble = batch.scrobbles().iter().nth(1).unwrap();
+            assert_eq!((scrobble.submitted_timestamp(), scrobble.correlation_id()), (1_500_000_000, Some("c0ffee")));
+            assert_eq!(serialized["scrobbles"][0]["ignoredMessage"], json!({"code": "0", "#text": ""}));
+            let ignored = json!({"code": "1", "#text": "Artist was ignored"});
+            assert_eq!(serialized["scrobbles"][1]["ignoredMessage"], ignored);
+
+            // A corrected name is serialized as Last.fm sends it
+            let fixture = include_str!("../tests/fixtures/corrections/lastfm.json");
+            let scrobble = json::from_str::<ScrobbleResponseWrapper>(fixture).unwrap().scrobbles.scrobble;
+            let (scrobble, serialized) = round_trip(&scrobble);
+            assert!(scrobble.artist().corrected() && scrobble.accepted());
+            assert_eq!(serialized["artist"], json!({"corrected": "1", "#text": "Björk"}));
+
+            let track = super::super::metadata::Scrobble::new("floyd", "fruitflies", "");
+            let skipped = NowPlayingResponse::from_scrobble(&track).into_skipped("Service offline".to_owned());
+            let (skipped, _) = round_trip(&skipped);
+            assert_eq!(skipped.outcome(), &NowPlayingOutcome::Skipped("Service offline".to_owned()));
+            assert_eq!(skipped.expires_at(), None);
+        }
+    }
+}
+
+pub mod metadata {
+
+    use super::responses::{CorrectableString, NowPlayingResponse, RecentTrack, ScrobbleResponse};
+    use crate::featured::{self, ArtistJoinPolicy, FeaturedArtists};
+    use crate::media::MediaKind;
+    use crate::params::Params;
+    use crate::plan;
+    use crate::stats::BatchStats;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+    #[cfg(feature = "chrono")]
+    use std::convert::TryFrom;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// Repesents a single music track played at a point in time. In the Last.fm universe, this is known as a 
+    /// "scrobble".
+    /// 
+    /// Takes an artist, track and album name. Can hold a timestamp indicating when the track was listened to, which
+    /// [`Scrobbler::now_playing`] ignores; see [`NowPlaying`].
+    /// `Scrobble` objects are submitted via [`Scrobbler::now_playing`], [`Scrobbler::scrobble`] and batches of
+    /// Scrobbles are sent via [`Scrobbler::scrobble_batch`].
+    /// 
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`NowPlaying`]: struct.NowPlaying.html
+    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize, WrappedVec)]
+    #[CollectionName = "ScrobbleBatch"]
+    #[CollectionDoc = "A batch of Scrobbles to be submitted to Last.fm together."]
+    #[CollectionDerives = "Clone, Debug, Serialize, Deserialize"]
+    pub struct Scrobble {
+        artist: String,
+        track: String,
+        album: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        album_artist: String,
+
+        timestamp: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duration: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        track_number: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mbid: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        chosen_by_user: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        context: Option<String>,
+        // The artists credited after `artist`, by `with_artists`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        other_artists: Vec<String>,
+        #[serde(default, skip_serializing_if = "ArtistJoinPolicy::is_first_only")]
+        artist_join: ArtistJoinPolicy,
+        #[serde(default, skip_serializing_if = "MediaKind::is_music")]
+        media_kind: MediaKind,
+    }
+
+    impl Scrobble {
+    
+        /// Constructs a new Scrobble instance, representing a single playthrough of a music track. `Scrobble`s are 
+        /// submitted to Last.fm via an instance of [`Scrobbler`]. A new `Scrobble` requires an artist name, song/track
+        /// name, and an album name.
+        /// 
+        /// # Example
+        /// ```ignore
+        /// let scrobble = Scrobble::new("Example Artist", "Example Track", "Example Album")
+        /// ```
+        /// 
+        /// [`Scrobbler`]: struct.Scrobbler.html
+        pub fn new(artist: &str, track: &str, album: &str) -> Self {
+            Self {
+                artist: artist.to_owned(),
+                track: track.to_owned(),
+                album: album.to_owned(),
+                album_artist: String::new(),
+                timestamp: None,
+                duration: None,
+                track_number: None,
+                mbid: None,
+                chosen_by_user: None,
+                context: None,
+                other_artists: Vec::new(),
+                artist_join: ArtistJoinPolicy::FirstOnly,
+                media_kind: MediaKind::Music,
+            }
+        }
+
+        /// Constructs a new Scrobble of a track which isn't from an album, ie. a single. No `album` parameter is
+        /// sent for it, as for a Scrobble constructed with an empty album name.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let scrobble = Scrobble::without_album("Example Artist", "Example Single");
+        /// ```
+        pub fn without_album(artist: &str, track: &str) -> Self {
+            Self::new(artist, track, "")
+        }
+
+        /// Sets the timestamp (date/time of play) of a Scrobble. Used in a builder-style pattern, typically after 
+        /// [`Scrobble::new`].
+        /// 
+        /// # Example
+        /// ```ignore
+        /// let mut scrobble = Scrobble::new(...).with_timestamp(12345);
+        /// ```
+        ///
+        /// # Note on Timestamps
+        /// Scrobbles without timestamps are automatically assigned a timestamp of the current time when
+        /// submitted via [`Scrobbler::scrobble`] or [`Scrobbler::scrobble_batch`]. Timestamps only need to be 
+        /// explicitly set when you are submitting a Scrobble at a point in the past, or in the future.
+        ///
+        /// Timestamps are seconds since the Unix epoch, and are checked when the Scrobble is submitted: one earlier
+        /// than the Scrobbler's minimum (by default 2002-01-01, see [`Scrobbler::set_min_timestamp`]), more than 14
+        /// days old, in the future, or apparently in milliseconds (see [`Scrobbler::set_max_scrobble_age`]) is
+        /// rejected with an error of kind `ErrorKind::InvalidTimestamp`. [`Scrobble::with_time`] avoids mixing up the
+        /// units.
+        /// 
+        /// [`Scrobble::new`]: struct.Scrobble.html#method.new
+        /// [`Scrobble::with_time`]: struct.Scrobble.html#method.with_time
+        /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+        /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+        /// [`Scrobbler::set_min_timestamp`]: struct.Scrobbler.html#method.set_min_timestamp
+        /// [`Scrobbler::set_max_scrobble_age`]: struct.Scrobbler.html#method.set_max_scrobble_age
+        pub fn with_timestamp(&mut self, timestamp: u64) -> &mut Self {
+            self.timestamp = Some(timestamp);
+            self
+        }
+
+        /// Sets the time the Scrobble was played, as [`Scrobble::with_timestamp`] does. Fractions of a second are
+        /// dropped; a time before the Unix epoch gives a timestamp of 0, which is rejected when submitted.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let mut scrobble = Scrobble::new(...);
+        /// scrobble.with_time(SystemTime::now() - Duration::from_secs(240));
+        /// ```
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_time(&mut self, time: SystemTime) -> &mut Self {
+            self.with_timestamp(time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs()))
+        }
+
+        /// Sets the time the Scrobble was played from a `chrono` date, as [`Scrobble::with_time`] does. Requires
+        /// the `chrono` feature.
+        ///
+        /// [`Scrobble::with_time`]: struct.Scrobble.html#method.with_time
+        #[cfg(feature = "chrono")]
+        pub fn with_datetime(&mut self, time: &chrono::DateTime<chrono::Utc>) -> &mut Self {
+            self.with_timestamp(u64::try_from(time.timestamp()).unwrap_or(0))
+        }
+
+        /// Sets the album name of a Scrobble, replacing the one it was constructed with. Used in a builder-style
+        /// pattern, like [`Scrobble::with_timestamp`]. An empty album clears it, as for [`Scrobble::without_album`].
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        /// [`Scrobble::without_album`]: struct.Scrobble.html#method.without_album
+        pub fn with_album(&mut self, album: &str) -> &mut Self {
+            album.clone_into(&mut self.album);
+            self
+        }
+
+        /// Sets the album artist of a Scrobble, ie. `"Various Artists"` for a track from a compilation. Used in a
+        /// builder-style pattern, like [`Scrobble::with_timestamp`]. See [`ScrobbleBatch::set_album_artist_all`] to set
+        /// the album artist of every Scrobble from one album.
+        ///
+        /// Last.fm assumes the album artist is the track artist unless told otherwise, so an album artist which is
+        /// empty or identical to the artist isn't sent.
+        ///
+        /// The album artist is independent of the album, and each is sent (as `"album"` and `"albumArtist"`) only if
+        /// it is set, so a Scrobble may have either, both or neither. Last.fm accepts all four combinations. An album
+        /// artist without an album, ie. a radio rip from a compilation whose title isn't known, is accepted, though
+        /// with no album to credit, Last.fm doesn't show it.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let mut scrobble = Scrobble::new("Blur", "Song 2", "Now That's What I Call Music! 37")
+        ///     .with_album_artist("Various Artists");
+        /// let mut radio_rip = Scrobble::without_album("Blur", "Song 2")
+        ///     .with_album_artist("Various Artists");
+        /// ```
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        /// [`ScrobbleBatch::set_album_artist_all`]: struct.ScrobbleBatch.html#method.set_album_artist_all
+        pub fn with_album_artist(&mut self, album_artist: &str) -> &mut Self {
+            album_artist.clone_into(&mut self.album_artist);
+            self
+        }
+
+        /// Sets the length of the track, in seconds. Used in a builder-style pattern, like
+        /// [`Scrobble::with_timestamp`]. Sent as `"duration"`.
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_duration(&mut self, secs: u64) -> &mut Self {
+            self.duration = Some(secs);
+            self
+        }
+
+        /// Sets the track's number on its album. Used in a builder-style pattern, like
+        /// [`Scrobble::with_timestamp`]. Sent as `"trackNumber"`.
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_track_number(&mut self, track_number: u32) -> &mut Self {
+            self.track_number = Some(track_number);
+            self
+        }
+
+        /// Sets the track's `MusicBrainz` ID. Used in a builder-style pattern, like [`Scrobble::with_timestamp`].
+        /// Sent as `"mbid"`.
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_mbid(&mut self, mbid: &str) -> &mut Self {
+            self.mbid = Some(mbid.to_owned());
+            self
+        }
+
+        /// Sets whether the user chose the track themselves, rather than it being picked for them, ie. by a radio
+        /// station or recommendation service. Used in a builder-style pattern, like [`Scrobble::with_timestamp`].
+        /// Sent as `"chosenByUser"` (`"1"` or `"0"`).
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_chosen_by_user(&mut self, chosen_by_user: bool) -> &mut Self {
+            self.chosen_by_user = Some(chosen_by_user);
+            self
+        }
+
+        /// Sets the context the track was played in, a sub-client identifier which Last.fm only accepts from some
+        /// API keys. Used in a builder-style pattern, like [`Scrobble::with_timestamp`]. Sent as `"context"`.
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_context(&mut self, context: &str) -> &mut Self {
+            self.context = Some(context.to_owned());
+            self
+        }
+
+        /// Credits the track to several artists, ie. `["Silk Sonic", "Bruno Mars", "Anderson .Paak"]`, replacing its
+        /// artist. Used in a builder-style pattern, like [`Scrobble::with_timestamp`]. An empty list is ignored.
+        ///
+        /// Last.fm only accepts one artist, so the first is the `Scrobble`'s artist, and how the others are
+        /// submitted is chosen with [`Scrobble::with_artist_join`]. By default only the first is submitted.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let mut scrobble = Scrobble::new("", "Leave the Door Open", "An Evening with Silk Sonic");
+        /// scrobble.with_artists(&["Bruno Mars", "Anderson .Paak"]);
+        /// assert_eq!(scrobble.artist(), "Bruno Mars");
+        /// ```
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        /// [`Scrobble::with_artist_join`]: struct.Scrobble.html#method.with_artist_join
+        pub fn with_artists(&mut self, artists: &[&str]) -> &mut Self {
+            if let Some((first, others)) = artists.split_first() {
+                (*first).clone_into(&mut self.artist);
+                self.other_artists = others.iter().map(|artist| (*artist).to_owned()).collect();
+            }
+            self
+        }
+
+        /// Sets how a track credited to several artists (see [`Scrobble::with_artists`]) is submitted. Used in a
+        /// builder-style pattern, like [`Scrobble::with_timestamp`]. Has no effect on a track with one artist.
+        ///
+        /// # Example
+        /// ```ignore
+        /// scrobble.with_artist_join(ArtistJoinPolicy::Join(" & ".to_owned()));
+        /// assert_eq!(scrobble.params().get("artist"), Some("Bruno Mars & Anderson .Paak"));
+        /// ```
+        ///
+        /// [`Scrobble::with_artists`]: struct.Scrobble.html#method.with_artists
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        pub fn with_artist_join(&mut self, policy: ArtistJoinPolicy) -> &mut Self {
+            self.artist_join = policy;
+            self
+        }
+
+        /// Marks the Scrobble as a listen of something other than music, ie. `MediaKind::Podcast`. Used in a
+        /// builder-style pattern, like [`Scrobble::with_timestamp`]. Never sent to Last.fm; see [`MediaKind`].
+        ///
+        /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+        /// [`MediaKind`]: enum.MediaKind.html
+        pub fn with_media_kind(&mut self, media_kind: MediaKind) -> &mut Self {
+            self.media_kind = media_kind;
+            self
+        }
+
+        /// Converts the Scrobble metadata (track name, artist & album name) into a [`Params`] set, as sent to the
+        /// Last.fm API. Parameter names are `"track"`, `"artist"` and `"album"`. If a timestamp is set, it will be
+        /// present under `"timestamp"`. The album is optional, so is left out if it's empty or only whitespace. The
+        /// album artist is sent as `"albumArtist"`, unless it's empty or exactly the same as the artist. The
+        /// duration, track number, MBID, whether the track was chosen by the user and context are sent as
+        /// `"duration"`, `"trackNumber"`, `"mbid"`, `"chosenByUser"` and `"context"`, only if they've been set. A
+        /// track credited to several artists has its artist and track name built as [`Scrobble::with_artist_join`]
+        /// chooses.
+        ///
+        /// Unlike [`Scrobble::as_map`], building a [`Params`] set does not allocate for the parameter names.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let scrobble = Scrobble::new("Example Artist", ...);
+        /// let params = scrobble.params();
+        /// assert_eq!(params.get("artist"), Some("Example Artist"));
+        /// ```
+        ///
+        /// [`Params`]: struct.Params.html
+        /// [`Scrobble::as_map`]: struct.Scrobble.html#method.as_map
+        /// [`Scrobble::with_artist_join`]: struct.Scrobble.html#method.with_artist_join
+        pub fn params(&self) -> Params {
+            let mut params = Params::new();
+            // Only a track credited to several artists is affected by the join policy
+            let several = !self.other_artists.is_empty();
+            let (track, artist) = match &self.artist_join {
+                ArtistJoinPolicy::AppendToTrack if several && !featured::credits_guest(&self.track) => {
+                    (featured::append_guests(&self.track, &self.other_artists), self.artist.clone())
+                }
+                ArtistJoinPolicy::Join(separator) if several => (self.track.clone(), self.artists().join(separator)),
+                _ => (self.track.clone(), self.artist.clone()),
+            };
+            // Last.fm defaults the album artist to the track artist, so sending it again only adds to the payload
+            let send_album_artist = !self.album_artist.is_empty() && self.album_artist != artist;
+            params.insert_track(track);
+            params.insert_artist(artist);
+            // Some servers store an empty album as a literal, blank album
+            if !self.album.trim().is_empty() {
+                params.insert_album(self.album.clone());
+            }
+            if send_album_artist {
+                params.insert_album_artist(self.album_artist.clone());
+            }
+
+            if let Some(timestamp) = self.timestamp {
+                params.insert_timestamp(timestamp);
+            }
+            if let Some(duration) = self.duration {
+                params.insert_duration(duration);
+            }
+            if let Some(track_number) = self.track_number {
+                params.insert_track_number(track_number);
+            }
+            if let Some(mbid) = &self.mbid {
+                params.insert_mbid(mbid.clone());
+            }
+            if let Some(chosen_by_user) = self.chosen_by_user {
+                params.insert_chosen_by_user(chosen_by_user);
+            }
+            if let Some(context) = &self.context {
+                params.insert_context(context.clone());
+            }
+
+            params
+        }
+
+        /// Converts the Scrobble metadata (track name, artist & album name) into a `HashMap`. Map keys are 
+        /// `"track"`, `"artist"` and `"album"`. If a timestamp is set, it will be present in the map under key 
+        /// `"timestamp"`. As with [`Scrobble::params`], an empty album is left out.
+        ///
+        /// Deprecated: use [`Scrobble::params`], and [`Params::into_map`] if a `HashMap` is really needed.
+        ///
+        /// [`Scrobble::params`]: struct.Scrobble.html#method.params
+        /// [`Params::into_map`]: struct.Params.html#method.into_map
+        /// 
+        /// # Example
+        /// ```ignore
+        /// let scrobble = Scrobble::new("Example Artist", ...);
+        /// let scrobble_map = scrobble.as_map();
+        /// assert_eq!(scrobble_map.get("artist"), "Example Artist");
+        /// ```
+        #[deprecated(since = "2.0.0", note = "use `Scrobble::params` instead")]
+        pub fn as_map(&self) -> HashMap<String, String> {
+            self.params().into_map()
+        }
+
+        /// Returns the `Scrobble`'s artist name
+        pub fn artist(&self) -> &str {
+            &self.artist
+        }
+
+        /// Returns every artist the track is credited to: those given to [`Scrobble::with_artists`], or else just
+        /// the `Scrobble`'s artist
+        ///
+        /// [`Scrobble::with_artists`]: struct.Scrobble.html#method.with_artists
+        pub fn artists(&self) -> Vec<&str> {
+            let mut artists = vec![self.artist.as_str()];
+            artists.extend(self.other_artists.iter().map(String::as_str));
+            artists
+        }
+
+        /// Returns how a track credited to several artists is submitted. See [`Scrobble::with_artist_join`].
+        ///
+        /// [`Scrobble::with_artist_join`]: struct.Scrobble.html#method.with_artist_join
+        pub fn artist_join(&self) -> &ArtistJoinPolicy {
+            &self.artist_join
+        }
+
+        /// Returns the `Scrobble`'s track name
+        pub fn track(&self) -> &str {
+            &self.track
+        }
+
+        /// Returns the `Scrobble`'s album name
+        pub fn album(&self) -> &str {
+            &self.album
+        }
+
+        /// Returns the `Scrobble`'s album artist, or an empty string if none has been set
+        pub fn album_artist(&self) -> &str {
+            &self.album_artist
+        }
+
+        /// Returns the `Scrobble`'s timestamp, if one has been set
+        pub fn timestamp(&self) -> Option<u64> {
+            self.timestamp
+        }
+
+        /// Returns the length of the track in seconds, if it has been set
+        pub fn duration(&self) -> Option<u64> {
+            self.duration
+        }
+
+        /// Returns the track's number on its album, if it has been set
+        pub fn track_number(&self) -> Option<u32> {
+            self.track_number
+        }
+
+        /// Returns the track's `MusicBrainz` ID, if it has been set
+        pub fn mbid(&self) -> Option<&str> {
+            self.mbid.as_deref()
+        }
+
+        /// Returns whether the user chose the track, if it has been set
+        pub fn chosen_by_user(&self) -> Option<bool> {
+            self.chosen_by_user
+        }
+
+        /// Returns the context the track was played in, if it has been set
+        pub fn context(&self) -> Option<&str> {
+            self.context.as_deref()
+        }
+
+        /// Returns what kind of media the Scrobble is a listen of: music, unless set otherwise
+        pub fn media_kind(&self) -> &MediaKind {
+            &self.media_kind
+        }
+
+        // The artist, track, album and album artist names, by parameter name
+        pub(crate) fn names(&self) -> [(&'static str, &str); 4] {
+            [
+                ("artist", &self.artist),
+                ("track", &self.track),
+                ("album", &self.album),
+                ("albumArtist", &self.album_artist),
+            ]
+        }
+
+        pub(crate) fn names_mut(&mut self) -> [(&'static str, &mut String); 4] {
+            [
+                ("artist", &mut self.artist),
+                ("track", &mut self.track),
+                ("album", &mut self.album),
+                ("albumArtist", &mut self.album_artist),
+            ]
+        }
+
+        /// Writes the metadata corrections Last.fm made in a Now Playing response back into the `Scrobble`.
+        ///
+        /// Each of the artist, track and album fields is overwritten with the value from the response, but only
+        /// if Last.fm marked that field as corrected; other fields are left untouched. Returns a summary of the
+        /// fields which changed. See [`CorrectableString`] for more on metadata corrections.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let response = scrobbler.now_playing(&scrobble)?;
+        /// let corrections = scrobble.apply_corrections(&response);
+        /// if let Some(artist) = corrections.artist {
+        ///     println!("Artist corrected from {} to {}", artist.previous, artist.corrected);
+        /// }
+        /// ```
+        ///
+        /// [`CorrectableString`]: responses/values/struct.CorrectableString.html
+        pub fn apply_corrections(&mut self, response: &NowPlayingResponse) -> Corrections {
+            self.correct(response.artist(), response.track(), response.album())
+        }
+
+        /// Writes the metadata corrections Last.fm made in a Scrobble response back into the `Scrobble`. Behaves
+        /// exactly like [`Scrobble::apply_corrections`].
+        ///
+        /// [`Scrobble::apply_corrections`]: struct.Scrobble.html#method.apply_corrections
+        pub fn apply_scrobble_corrections(&mut self, response: &ScrobbleResponse) -> Corrections {
+            self.correct(response.artist(), response.track(), response.album())
+        }
+
+        fn correct(
+            &mut self,
+            artist: &CorrectableString,
+            track: &CorrectableString,
+            album: &CorrectableString,
+        ) -> Corrections {
+            Corrections {
+                artist: Correction::apply(&mut self.artist, artist),
+                track: Correction::apply(&mut self.track, track),
+                album: Correction::apply(&mut self.album, album),
+            }
+        }
+
+        /// Moves guest artists credited in the artist field out of it, leaving only the primary artist. Returns
+        /// true if the `Scrobble` was changed.
+        ///
+        /// Guests are recognized after one of the markers `feat.`, `feat`, `ft.` or `featuring`, matched as a
+        /// whole word and ignoring case, as in `"Mark Ronson feat. Bruno Mars"`, or after a closing credit in
+        /// parentheses or brackets, which may also use `ft`, as in `"Jay-Z (ft Kanye West)"`. The rules are
+        /// deliberately conservative, and a name is left alone if:
+        ///
+        /// * the marker starts the name, or nothing follows it (`"Featuring Nobody"`, `"Swifty Feat"`)
+        /// * the marker follows an article, so is probably part of a name (`"The Ft. Lauderdale Sound"`)
+        /// * a parenthesized credit doesn't end the name (`"Artist (feat. Guest) Remix"`)
+        ///
+        /// With [`FeaturedArtists::AppendToTrack`] the guests are appended to the track name as
+        /// `"Track (feat. Guest)"`, unless the track name already credits a guest, in which case they're dropped.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let mut scrobble = Scrobble::new("Calvin Harris ft. Rihanna", "This Is What You Came For", "");
+        /// scrobble.split_featured_artists(FeaturedArtists::AppendToTrack);
+        /// assert_eq!(scrobble.artist(), "Calvin Harris");
+        /// assert_eq!(scrobble.track(), "This Is What You Came For (feat. Rihanna)");
+        /// ```
+        ///
+        /// [`FeaturedArtists::AppendToTrack`]: enum.FeaturedArtists.html#variant.AppendToTrack
+        pub fn split_featured_artists(&mut self, guests: FeaturedArtists) -> bool {
+            let Some((primary, guest)) = featured::split(&self.artist) else {
+                return false;
+            };
+
+            if guests == FeaturedArtists::AppendToTrack && !featured::credits_guest(&self.track) {
+                self.track = format!("{} (feat. {guest})", self.track.trim_end());
+            }
+            self.artist = primary.to_owned();
+            true
+        }
+    
     }
 
-    /// Represents a string that can be marked as 'corrected' by the Last.fm API. 
-    /// 
-    /// All Scrobble/NowPlaying responses have their fields as `CorrectableString`'s. The API will sometimes change
-    /// the artist/song name/album name data that you have submitted. For example - it is common for Bjork to be turned
-    /// into Björk by the API; the modified artist field would be marked `corrected = true`, `text = "Björk". 
-    /// 
-    /// Most clients can ignore these corrections, but the information is exposed for clients that require it.
-    /// 
-    /// [Meta-Data Correction Documentation](https://www.last.fm/api/scrobbling#meta-data-corrections)
-    #[derive(Deserialize, Debug)]
-    pub struct CorrectableString {
-        #[serde(deserialize_with = "CorrectableString::deserialize_corrected_field")]
-        pub corrected: bool,
-        #[serde(rename = "#text", default)]
-        pub text: String,
+    /// A track the user has started playing, as registered with [`Scrobbler::now_playing`].
+    ///
+    /// Holds only what `track.updateNowPlaying` accepts: a `NowPlaying` has no timestamp, since the status always
+    /// means "playing now" and can't be backdated, and no `chosenByUser`. Converting from a [`Scrobble`] drops both.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut track = NowPlaying::new("Example Artist", "Example Track", "Example Album");
+    /// track.with_duration(215);
+    /// scrobbler.now_playing(&track)?;
+    /// ```
+    ///
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    /// [`Scrobble`]: struct.Scrobble.html
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub struct NowPlaying {
+        // Never has a timestamp or `chosen_by_user` set
+        scrobble: Scrobble,
     }
 
-    impl CorrectableString {
-        fn deserialize_corrected_field<'de, D>(de: D) -> Result<bool, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            let deser_result: json::Value = serde::Deserialize::deserialize(de)?;
-            match deser_result {
-                json::Value::String(ref s) if &*s == "1" => Ok(true),
-                json::Value::String(ref s) if &*s == "0" => Ok(false),
-                _ => Err(serde::de::Error::custom("Unexpected value")),
+    impl NowPlaying {
+        /// Constructs a new `NowPlaying` track from an artist, track and album name, as [`Scrobble::new`] does
+        ///
+        /// [`Scrobble::new`]: struct.Scrobble.html#method.new
+        pub fn new(artist: &str, track: &str, album: &str) -> Self {
+            Self {
+                scrobble: Scrobble::new(artist, track, album),
             }
         }
-    }
 
-    impl fmt::Display for CorrectableString {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "{}", self.text)
+        /// Constructs a new `NowPlaying` track which isn't from an album. See [`Scrobble::without_album`].
+        ///
+        /// [`Scrobble::without_album`]: struct.Scrobble.html#method.without_album
+        pub fn without_album(artist: &str, track: &str) -> Self {
+            Self::new(artist, track, "")
         }
-    }
-}
 
-pub mod metadata {
+        /// Sets the album name. See [`Scrobble::with_album`].
+        ///
+        /// [`Scrobble::with_album`]: struct.Scrobble.html#method.with_album
+        pub fn with_album(&mut self, album: &str) -> &mut Self {
+            self.scrobble.with_album(album);
+            self
+        }
 
-    use std::collections::HashMap;
+        /// Sets the album artist. See [`Scrobble::with_album_artist`].
+        ///
+        /// [`Scrobble::with_album_artist`]: struct.Scrobble.html#method.with_album_artist
+        pub fn with_album_artist(&mut self, album_artist: &str) -> &mut Self {
+            self.scrobble.with_album_artist(album_artist);
+            self
+        }
 
-    /// Repesents a single music track played at a point in time. In the Last.fm universe, this is known as a 
-    /// "scrobble".
-    /// 
-    /// Takes an artist, track and album name. Can hold a timestamp indicating when the track was listened to.
-    /// `Scrobble` objects are submitted via [`Scrobbler::now_playing`], [`Scrobbler::scrobble`] and batches of
-    /// Scrobbles are sent via [`Scrobbler::scrobble_batch`].
-    /// 
-    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
-    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
-    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
-    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, WrappedVec)]
-    #[CollectionName = "ScrobbleBatch"]
-    #[CollectionDoc = "A batch of Scrobbles to be submitted to Last.fm together."]
-    #[CollectionDerives = "Clone, Debug"]
-    pub struct Scrobble {
-        artist: String,
-        track: String,
-        album: String,
+        /// Sets the length of the track, in seconds. Sent as `"duration"`.
+        pub fn with_duration(&mut self, secs: u64) -> &mut Self {
+            self.scrobble.with_duration(secs);
+            self
+        }
 
-        timestamp: Option<u64>,
-    }
+        /// Sets the track's number on its album. Sent as `"trackNumber"`.
+        pub fn with_track_number(&mut self, track_number: u32) -> &mut Self {
+            self.scrobble.with_track_number(track_number);
+            self
+        }
 
-    impl Scrobble {
-    
-        /// Constructs a new Scrobble instance, representing a single playthrough of a music track. `Scrobble`s are 
-        /// submitted to Last.fm via an instance of [`Scrobbler`]. A new `Scrobble` requires an artist name, song/track
-        /// name, and an album name.
-        /// 
-        /// # Example
-        /// ```ignore
-        /// let scrobble = Scrobble::new("Example Artist", "Example Track", "Example Album")
-        /// ```
-        /// 
-        /// [`Scrobbler`]: struct.Scrobbler.html
-        pub fn new(artist: &str, track: &str, album: &str) -> Self {
-            Self {
-                artist: artist.to_owned(),
-                track: track.to_owned(),
-                album: album.to_owned(),
-                timestamp: None,
-            }
+        /// Sets the track's `MusicBrainz` ID. Sent as `"mbid"`.
+        pub fn with_mbid(&mut self, mbid: &str) -> &mut Self {
+            self.scrobble.with_mbid(mbid);
+            self
         }
 
-        /// Sets the timestamp (date/time of play) of a Scrobble. Used in a builder-style pattern, typically after 
-        /// [`Scrobble::new`].
-        /// 
-        /// # Example
-        /// ```ignore
-        /// let mut scrobble = Scrobble::new(...).with_timestamp(12345);
-        /// ```
+        /// Sets the context the track is played in. See [`Scrobble::with_context`].
         ///
-        /// # Note on Timestamps
-        /// Scrobbles without timestamps are automatically assigned a timestamp of the current time when
-        /// submitted via [`Scrobbler::scrobble`] or [`Scrobbler::scrobble_batch`]. Timestamps only need to be 
-        /// explicitly set when you are submitting a Scrobble at a point in the past, or in the future.
-        /// 
-        /// [`Scrobble::new`]: struct.Scrobble.html#method.new
-        /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
-        /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
-        pub fn with_timestamp(&mut self, timestamp: u64) -> &mut Self {
-            self.timestamp = Some(timestamp);
+        /// [`Scrobble::with_context`]: struct.Scrobble.html#method.with_context
+        pub fn with_context(&mut self, context: &str) -> &mut Self {
+            self.scrobble.with_context(context);
             self
         }
 
-        /// Converts the Scrobble metadata (track name, artist & album name) into a `HashMap`. Map keys are 
-        /// `"track"`, `"artist"` and `"album"`. If a timestamp is set, it will be present in the map under key 
-        /// `"timestamp"`.
-        /// 
-        /// # Example
-        /// ```ignore
-        /// let scrobble = Scrobble::new("Example Artist", ...);
-        /// let scrobble_map = scrobble.as_map();
-        /// assert_eq!(scrobble_map.get("artist"), "Example Artist");
-        /// ```
-        pub fn as_map(&self) -> HashMap<String, String> {
-            let mut params = HashMap::new();
-            params.insert("track".to_string(), self.track.clone());
-            params.insert("artist".to_string(), self.artist.clone());
-            params.insert("album".to_string(), self.album.clone());
+        /// Credits the track to several artists. See [`Scrobble::with_artists`].
+        ///
+        /// [`Scrobble::with_artists`]: struct.Scrobble.html#method.with_artists
+        pub fn with_artists(&mut self, artists: &[&str]) -> &mut Self {
+            self.scrobble.with_artists(artists);
+            self
+        }
 
-            if let Some(timestamp) = self.timestamp {
-                params.insert("timestamp".to_string(), timestamp.to_string());
-            }
+        /// Sets how a track credited to several artists is submitted. See [`Scrobble::with_artist_join`].
+        ///
+        /// [`Scrobble::with_artist_join`]: struct.Scrobble.html#method.with_artist_join
+        pub fn with_artist_join(&mut self, policy: ArtistJoinPolicy) -> &mut Self {
+            self.scrobble.with_artist_join(policy);
+            self
+        }
 
-            params
+        /// Marks the track as something other than music. See [`Scrobble::with_media_kind`].
+        ///
+        /// [`Scrobble::with_media_kind`]: struct.Scrobble.html#method.with_media_kind
+        pub fn with_media_kind(&mut self, media_kind: MediaKind) -> &mut Self {
+            self.scrobble.with_media_kind(media_kind);
+            self
         }
 
-        /// Returns the `Scrobble`'s artist name
+        /// Builds the [`Params`] set sent to `track.updateNowPlaying`, as [`Scrobble::params`] does. It never holds
+        /// a `"timestamp"`.
+        ///
+        /// [`Params`]: struct.Params.html
+        /// [`Scrobble::params`]: struct.Scrobble.html#method.params
+        pub fn params(&self) -> Params {
+            self.scrobble.params()
+        }
+
+        /// Returns the track's artist name
         pub fn artist(&self) -> &str {
-            &self.artist
+            self.scrobble.artist()
         }
 
-        /// Returns the `Scrobble`'s track name
+        /// Returns the track name
         pub fn track(&self) -> &str {
-            &self.track
+            self.scrobble.track()
         }
 
-        /// Returns the `Scrobble`'s album name
+        /// Returns the track's album name
         pub fn album(&self) -> &str {
-            &self.album
+            self.scrobble.album()
+        }
+
+        /// Returns the track's album artist, or an empty string if none has been set
+        pub fn album_artist(&self) -> &str {
+            self.scrobble.album_artist()
+        }
+
+        /// Returns the length of the track in seconds, if it has been set
+        pub fn duration(&self) -> Option<u64> {
+            self.scrobble.duration()
+        }
+
+        /// Returns the track's number on its album, if it has been set
+        pub fn track_number(&self) -> Option<u32> {
+            self.scrobble.track_number()
+        }
+
+        /// Returns the track's `MusicBrainz` ID, if it has been set
+        pub fn mbid(&self) -> Option<&str> {
+            self.scrobble.mbid()
+        }
+
+        /// Returns what kind of media the track is: music, unless set otherwise
+        pub fn media_kind(&self) -> &MediaKind {
+            self.scrobble.media_kind()
+        }
+
+        pub(crate) fn as_scrobble(&self) -> &Scrobble {
+            &self.scrobble
+        }
+    }
+
+    /// Takes the track's metadata from a [`Scrobble`], dropping its timestamp and `chosenByUser`
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    impl From<Scrobble> for NowPlaying {
+        fn from(mut scrobble: Scrobble) -> Self {
+            scrobble.timestamp = None;
+            scrobble.chosen_by_user = None;
+            Self { scrobble }
+        }
+    }
+
+    impl From<&Scrobble> for NowPlaying {
+        fn from(scrobble: &Scrobble) -> Self {
+            Self::from(scrobble.clone())
+        }
+    }
+
+    impl From<&NowPlaying> for NowPlaying {
+        fn from(now_playing: &NowPlaying) -> Self {
+            now_playing.clone()
+        }
+    }
+
+    /// The fields of a [`Scrobble`] changed by [`Scrobble::apply_corrections`]. Fields which weren't corrected
+    /// are `None`.
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobble::apply_corrections`]: struct.Scrobble.html#method.apply_corrections
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct Corrections {
+        pub artist: Option<Correction>,
+        pub track: Option<Correction>,
+        pub album: Option<Correction>,
+    }
+
+    impl Corrections {
+        /// Returns true if no fields were changed
+        pub fn is_empty(&self) -> bool {
+            self.artist.is_none() && self.track.is_none() && self.album.is_none()
+        }
+    }
+
+    /// A single corrected field: its value before and after correction
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Correction {
+        pub previous: String,
+        pub corrected: String,
+    }
+
+    impl Correction {
+        // Overwrites `field` if Last.fm corrected it to a different value
+        fn apply(field: &mut String, value: &CorrectableString) -> Option<Self> {
+            if !value.corrected() || field == value.text() {
+                return None;
+            }
+
+            let previous = std::mem::replace(field, value.text().to_owned());
+            Some(Self {
+                previous,
+                corrected: value.text().to_owned(),
+            })
         }
-    
     }
 
     /// Converts from tuple of `&str`s in the form `(artist, track, album)`
@@ -259,6 +2207,67 @@ pub mod metadata {
         }
     }
 
+    impl ScrobbleBatch {
+        /// Sets the album artist of every Scrobble in the batch, ie. to `"Various Artists"` when scrobbling a
+        /// compilation. See [`Scrobble::with_album_artist`].
+        ///
+        /// [`Scrobble::with_album_artist`]: struct.Scrobble.html#method.with_album_artist
+        pub fn set_album_artist_all(&mut self, album_artist: &str) {
+            for scrobble in &mut self.0 {
+                scrobble.with_album_artist(album_artist);
+            }
+        }
+
+        /// Summarizes the batch, ie. to show before submitting an import: how many scrobbles, artists and tracks
+        /// it holds, the time range it covers, and how many scrobbles lack a timestamp, duration or album, or are
+        /// too old for Last.fm to accept now. See [`BatchStats`].
+        ///
+        /// [`BatchStats`]: struct.BatchStats.html
+        pub fn stats(&self) -> BatchStats {
+            self.stats_at(SystemTime::now())
+        }
+
+        /// Summarizes the batch as [`ScrobbleBatch::stats`] does, counting the scrobbles which would be too old to
+        /// submit at `now`, ie. when the import is planned to run
+        ///
+        /// [`ScrobbleBatch::stats`]: struct.ScrobbleBatch.html#method.stats
+        pub fn stats_at(&self, now: SystemTime) -> BatchStats {
+            BatchStats::new(self, now)
+        }
+
+        /// Returns the scrobbles of the batch which aren't in `history` already, ie. the user's tracks from
+        /// [`Scrobbler::recent_tracks`], so that a batch interrupted by a crash can be submitted again without
+        /// scrobbling anything twice.
+        ///
+        /// Scrobbles are matched as [`Scrobbler::plan_import`] matches them: a scrobble is in the history if a
+        /// scrobble there of the same [`TrackKey`] is timestamped at most `window` apart, and each one there
+        /// accounts for at most one in the batch. Scrobbles without a timestamp are always kept, and nothing
+        /// matches the track being played now.
+        ///
+        /// # Usage
+        /// ```ignore
+        /// let history = scrobbler.recent_tracks(200, Some(first_timestamp), None)?;
+        /// scrobbler.scrobble_batch_chunked(&pending.without_scrobbled(&history, Duration::from_secs(30)))?;
+        /// ```
+        ///
+        /// [`Scrobbler::recent_tracks`]: struct.Scrobbler.html#method.recent_tracks
+        /// [`Scrobbler::plan_import`]: struct.Scrobbler.html#method.plan_import
+        /// [`TrackKey`]: struct.TrackKey.html
+        #[must_use]
+        pub fn without_scrobbled(&self, history: &[RecentTrack], window: Duration) -> ScrobbleBatch {
+            let timestamps: Vec<Option<u64>> = self.iter().map(Scrobble::timestamp).collect();
+            let matches = plan::match_history(self, &timestamps, history, window);
+            let kept: Vec<Scrobble> = self
+                .iter()
+                .zip(matches)
+                .filter(|(_, existing)| existing.is_none())
+                .map(|(scrobble, _)| scrobble.clone())
+                .collect();
+
+            ScrobbleBatch::from(kept)
+        }
+    }
+
     /// Converts from vector of `&str` tuples, in the form `(artist, track, album)`.
     /// 
     /// Designed to make it easier to cooperate with other track info types.
@@ -291,6 +2300,20 @@ pub mod metadata {
     mod tests {
         use super::*;
 
+        #[test]
+        fn check_now_playing_from_scrobble() {
+            let mut scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+            scrobble.with_timestamp(1337).with_chosen_by_user(false).with_track_number(3);
+            let track = NowPlaying::from(&scrobble);
+            assert_eq!(track.track_number(), Some(3));
+            assert_eq!(track.params().get("timestamp"), None);
+            assert_eq!(track.params().get("chosenByUser"), None);
+
+            let mut untimed = Scrobble::new("foo floyd", "old bananas", "bananas");
+            untimed.with_track_number(3);
+            assert_eq!(track.params(), untimed.params());
+        }
+
         #[test]
         fn make_scrobble() {
             let mut scrobble = Scrobble::new(
@@ -306,6 +2329,7 @@ pub mod metadata {
         }
 
         #[test]
+        #[allow(deprecated)]
         fn make_scrobble_check_map() {
             let scrobble = Scrobble::new(
                 "foo floyd and the fruit flies",
@@ -318,5 +2342,272 @@ pub mod metadata {
             assert_eq!(params["track"], "old bananas");
             assert_eq!(params["album"], "old bananas");
         }
+
+        #[test]
+        #[allow(deprecated)]
+        fn make_scrobble_check_params() {
+            let mut scrobble = Scrobble::new(
+                "foo floyd and the fruit flies",
+                "old bananas",
+                "old bananas",
+            );
+            scrobble.with_timestamp(1337);
+
+            let params = scrobble.params();
+            let pairs: Vec<(&str, &str)> = params.iter().collect();
+            assert_eq!(
+                pairs,
+                vec![
+                    ("album", "old bananas"),
+                    ("artist", "foo floyd and the fruit flies"),
+                    ("timestamp", "1337"),
+                    ("track", "old bananas"),
+                ]
+            );
+            assert_eq!(params.into_map(), scrobble.as_map());
+        }
+
+        #[test]
+        fn check_track_metadata_params() {
+            let mut scrobble = Scrobble::without_album("Björk", "Jóga");
+            scrobble
+                .with_timestamp(1337)
+                .with_duration(305)
+                .with_track_number(3)
+                .with_mbid("4b5b5a3e-5f5b-4a3e-b4b4-5e3f5d5a3e4b")
+                .with_chosen_by_user(false);
+            assert_eq!(scrobble.album(), "");
+            assert_eq!((scrobble.duration(), scrobble.track_number()), (Some(305), Some(3)));
+            assert_eq!(scrobble.mbid(), Some("4b5b5a3e-5f5b-4a3e-b4b4-5e3f5d5a3e4b"));
+            assert_eq!(scrobble.chosen_by_user(), Some(false));
+
+            let params = scrobble.params();
+            let pairs: Vec<(&str, &str)> = params.iter().collect();
+            assert_eq!(
+                pairs,
+                vec![
+                    ("artist", "Björk"),
+                    ("chosenByUser", "0"),
+                    ("duration", "305"),
+                    ("mbid", "4b5b5a3e-5f5b-4a3e-b4b4-5e3f5d5a3e4b"),
+                    ("timestamp", "1337"),
+                    ("track", "Jóga"),
+                    ("trackNumber", "3"),
+                ]
+            );
+            let mut chosen = Scrobble::without_album("Björk", "Jóga");
+            chosen.with_chosen_by_user(true);
+            assert_eq!(chosen.params().get("chosenByUser"), Some("1"));
+
+            // Unset fields are left out entirely
+            let pairs: Vec<(String, String)> = Scrobble::without_album("Björk", "Jóga")
+                .params()
+                .iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect();
+            assert_eq!(
+                pairs,
+                vec![("artist".to_owned(), "Björk".to_owned()), ("track".to_owned(), "Jóga".to_owned())]
+            );
+
+            // Round trips, and scrobbles serialized before these fields were supported still deserialize
+            let json = serde_json::to_string(&scrobble).unwrap();
+            assert_eq!(serde_json::from_str::<Scrobble>(&json).unwrap(), scrobble);
+            let old: Scrobble =
+                serde_json::from_str(r#"{"artist":"Björk","track":"Jóga","album":"","timestamp":null}"#).unwrap();
+            assert_eq!(old, Scrobble::without_album("Björk", "Jóga"));
+            assert!(!serde_json::to_string(&old).unwrap().contains("duration"));
+        }
+
+        #[test]
+        fn check_album_artist_params() {
+            let mut batch = ScrobbleBatch::from(vec![
+                ("Blur", "Song 2", "Now 37"),
+                ("Various Artists", "Medley", "Now 37"),
+            ]);
+            batch.set_album_artist_all("Various Artists");
+            assert!(batch.iter().all(|scrobble| scrobble.album_artist() == "Various Artists"));
+
+            let params: Vec<Params> = batch.iter().map(Scrobble::params).collect();
+            assert_eq!(params[0].get("albumArtist"), Some("Various Artists"));
+            // Identical to the artist, so redundant
+            assert_eq!(params[1].get("albumArtist"), None);
+            assert_eq!(Scrobble::new("Blur", "Song 2", "Blur").params().get("albumArtist"), None);
+
+            // Scrobbles serialized before album artists were supported still deserialize
+            let scrobble: Scrobble =
+                serde_json::from_str(r#"{"artist":"Blur","track":"Song 2","album":"Blur","timestamp":null}"#).unwrap();
+            assert_eq!(scrobble.album_artist(), "");
+            assert!(!serde_json::to_string(&scrobble).unwrap().contains("album_artist"));
+        }
+
+        #[test]
+        fn check_scrobble_times() {
+            use std::time::Duration;
+
+            let mut scrobble = Scrobble::new("Floyd", "Song", "Album");
+            scrobble.with_time(UNIX_EPOCH + Duration::from_millis(1_500_000_000_999));
+            assert_eq!(scrobble.timestamp(), Some(1_500_000_000));
+            scrobble.with_time(UNIX_EPOCH - Duration::from_secs(1));
+            assert_eq!(scrobble.timestamp(), Some(0));
+
+            #[cfg(feature = "chrono")]
+            {
+                use chrono::TimeZone;
+
+                let played = chrono::Utc.with_ymd_and_hms(2017, 7, 14, 2, 40, 0).unwrap();
+                assert_eq!(scrobble.with_datetime(&played).timestamp(), Some(1_500_000_000));
+                let ancient = chrono::Utc.with_ymd_and_hms(1969, 12, 31, 0, 0, 0).unwrap();
+                assert_eq!(scrobble.with_datetime(&ancient).timestamp(), Some(0));
+            }
+        }
+
+        #[test]
+        fn check_media_kind_serde() {
+            let mut scrobble = Scrobble::new("Floyd", "Episode 12", "The Show");
+            scrobble.with_media_kind(MediaKind::Audiobook);
+            // Only the Scrobbler decides whether the server accepts the media kind
+            assert_eq!(scrobble.params().get("mediaKind"), None);
+
+            // Queued scrobbles keep their media kind, and music, the default, isn't written out
+            let json = serde_json::to_string(&scrobble).unwrap();
+            assert_eq!(serde_json::from_str::<Scrobble>(&json).unwrap().media_kind(), &MediaKind::Audiobook);
+            let music = Scrobble::new("Floyd", "Song", "Album");
+            assert!(!serde_json::to_string(&music).unwrap().contains("media_kind"));
+            assert!(music.media_kind().is_music());
+
+            // A batch serializes as an array of its scrobbles
+            let batch = ScrobbleBatch::from(vec![scrobble.clone(), music]);
+            let json = serde_json::to_value(&batch).unwrap();
+            assert_eq!(json.as_array().map(Vec::len), Some(2));
+            let decoded: ScrobbleBatch = serde_json::from_value(json).unwrap();
+            assert_eq!(decoded.iter().next(), Some(&scrobble));
+        }
+
+        #[test]
+        fn check_apply_corrections() {
+            let mut scrobble = Scrobble::new("Bjork", "Joga", "Homogenic");
+            scrobble.with_timestamp(1337);
+
+            let response: NowPlayingResponse = serde_json::from_str(
+                r##"{
+                    "artist": { "corrected": "1", "#text": "Björk" },
+                    "album": { "corrected": "0", "#text": "Homogenic" },
+                    "albumArtist": { "corrected": "0", "#text": "" },
+                    "track": { "corrected": "0", "#text": "Jóga" }
+                }"##,
+            )
+            .unwrap();
+
+            let corrections = scrobble.apply_corrections(&response);
+            assert_eq!(
+                corrections.artist,
+                Some(Correction {
+                    previous: "Bjork".to_owned(),
+                    corrected: "Björk".to_owned(),
+                })
+            );
+            assert_eq!(corrections.track, None);
+            assert_eq!(corrections.album, None);
+
+            assert_eq!(scrobble.artist(), "Björk");
+            assert_eq!(scrobble.track(), "Joga");
+            assert_eq!(scrobble.album(), "Homogenic");
+            assert_eq!(scrobble.timestamp(), Some(1337));
+
+            // Already applied
+            assert!(scrobble.apply_corrections(&response).is_empty());
+        }
+
+        #[test]
+        fn check_split_featured_artists() {
+            let cases = [
+                ("Mark Ronson feat. Bruno Mars", "Uptown Funk", FeaturedArtists::AppendToTrack,
+                    "Mark Ronson", "Uptown Funk (feat. Bruno Mars)"),
+                ("Eminem (Feat. Rihanna)", "Love the Way You Lie ", FeaturedArtists::AppendToTrack,
+                    "Eminem", "Love the Way You Lie (feat. Rihanna)"),
+                ("The Kid LAROI ft. Justin Bieber", "Stay (feat. Justin Bieber)", FeaturedArtists::AppendToTrack,
+                    "The Kid LAROI", "Stay (feat. Justin Bieber)"),
+                ("Daft Punk featuring Pharrell Williams", "Get Lucky", FeaturedArtists::Drop,
+                    "Daft Punk", "Get Lucky"),
+            ];
+
+            for (artist, track, guests, expected_artist, expected_track) in cases {
+                let mut scrobble = Scrobble::new(artist, track, "Album");
+                assert!(scrobble.split_featured_artists(guests), "{}", artist);
+                assert_eq!(scrobble.artist(), expected_artist);
+                assert_eq!(scrobble.track(), expected_track);
+                assert_eq!(scrobble.album(), "Album");
+            }
+
+            let mut scrobble = Scrobble::new("The Ft. Lauderdale Sound", "Track", "Album");
+            assert!(!scrobble.split_featured_artists(FeaturedArtists::AppendToTrack));
+            assert_eq!(scrobble.artist(), "The Ft. Lauderdale Sound");
+            assert_eq!(scrobble.track(), "Track");
+        }
+
+        #[test]
+        fn check_multiple_artists() {
+            let mut scrobble = Scrobble::new("", "Leave the Door Open", "An Evening with Silk Sonic");
+            scrobble.with_artists(&["Bruno Mars", "Anderson .Paak", "Silk Sonic"]);
+            assert_eq!(scrobble.artist(), "Bruno Mars");
+            assert_eq!(scrobble.artists(), vec!["Bruno Mars", "Anderson .Paak", "Silk Sonic"]);
+            assert_eq!(scrobble.artist_join(), &ArtistJoinPolicy::FirstOnly);
+
+            let submitted = |scrobble: &Scrobble| {
+                let params = scrobble.params();
+                (params.get("artist").unwrap().to_owned(), params.get("track").unwrap().to_owned())
+            };
+            let cases = [
+                (ArtistJoinPolicy::FirstOnly, "Bruno Mars", "Leave the Door Open"),
+                (ArtistJoinPolicy::AppendToTrack,
+                    "Bruno Mars", "Leave the Door Open (feat. Anderson .Paak & Silk Sonic)"),
+                (ArtistJoinPolicy::Join(" & ".to_owned()),
+                    "Bruno Mars & Anderson .Paak & Silk Sonic", "Leave the Door Open"),
+            ];
+            for (policy, artist, track) in cases {
+                scrobble.with_artist_join(policy.clone());
+                assert_eq!(submitted(&scrobble), (artist.to_owned(), track.to_owned()), "{policy:?}");
+            }
+
+            // A track name already crediting its guests isn't credited twice
+            let mut credited = Scrobble::new("", "Stay (feat. Justin Bieber)", "");
+            credited
+                .with_artists(&["The Kid LAROI", "Justin Bieber"])
+                .with_artist_join(ArtistJoinPolicy::AppendToTrack);
+            assert_eq!(submitted(&credited).1, "Stay (feat. Justin Bieber)");
+
+            // The list is part of the scrobble: serialized, and compared
+            let json = serde_json::to_string(&scrobble).unwrap();
+            assert!(json.contains(r#""other_artists":["Anderson .Paak","Silk Sonic"]"#), "{}", json);
+            assert_eq!(serde_json::from_str::<Scrobble>(&json).unwrap(), scrobble);
+            let mut reordered = scrobble.clone();
+            reordered.with_artists(&["Bruno Mars", "Silk Sonic", "Anderson .Paak"]);
+            assert_ne!(reordered, scrobble);
+
+            // An empty list leaves the artist alone
+            reordered.with_artists(&[]);
+            assert_eq!(reordered.artist(), "Bruno Mars");
+        }
+
+        #[test]
+        fn check_single_artist_unchanged() {
+            // Scrobbles saved before multiple artists were supported still load, and are saved the same way
+            let json = r#"{"artist":"Björk","track":"Jóga","album":"Homogenic","timestamp":null}"#;
+            let mut scrobble: Scrobble = serde_json::from_str(json).unwrap();
+            assert_eq!(scrobble.artists(), vec!["Björk"]);
+            assert_eq!(scrobble, Scrobble::new("Björk", "Jóga", "Homogenic"));
+            assert_eq!(serde_json::to_string(&scrobble).unwrap(), json);
+
+            // No policy changes what a single artist submits
+            let expected = scrobble.params();
+            for policy in [ArtistJoinPolicy::AppendToTrack, ArtistJoinPolicy::Join(", ".to_owned())] {
+                scrobble.with_artist_join(policy);
+                assert_eq!(scrobble.params(), expected);
+            }
+            scrobble.with_artists(&["Björk"]);
+            assert_eq!(scrobble.artists(), vec!["Björk"]);
+            assert_eq!(scrobble.params(), expected);
+        }
     }
 }
@@ -0,0 +1,428 @@
+// Request parameter handling for Last.fm API requests
+use crate::lang::Language;
+
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::ops::Index;
+
+/// An ordered set of Last.fm API request parameters.
+///
+/// Well-known parameter names (`"artist"`, `"track"`, `"api_key"` etc.) are stored as `&'static str` keys, so
+/// building a request doesn't allocate for them; only dynamic keys, such as the array-suffixed keys used by batch
+/// requests (`"artist[0]"`, `"artist[1]"`, ...), are stored as owned strings. Parameters are always iterated in
+/// key order, which is the order required when computing request signatures.
+///
+/// Key order is the byte-wise order of the key names, whatever order the parameters were inserted in, and the
+/// request body is encoded in the same order. Extension parameters and batch keys are ordered like any other, so
+/// `"artist[10]"` comes before `"artist[2]"`. The same request therefore always has the same body and signature,
+/// which request recordings and relays that verify signatures rely on.
+///
+/// `Params` are produced by [`Scrobble::params`]. Use [`Params::into_map`] to convert into a plain `HashMap`.
+///
+/// [`Scrobble::params`]: struct.Scrobble.html#method.params
+/// [`Params::into_map`]: struct.Params.html#method.into_map
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Params {
+    params: BTreeMap<Cow<'static, str>, String>,
+}
+
+impl Params {
+    /// Creates an empty parameter set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn insert(&mut self, key: &'static str, value: impl Into<String>) {
+        self.params.insert(Cow::Borrowed(key), value.into());
+    }
+
+    // Typed setters for the parameters the crate sends, so that their names are only spelled out here
+
+    pub(crate) fn insert_artist(&mut self, artist: impl Into<String>) {
+        self.insert("artist", artist);
+    }
+
+    pub(crate) fn insert_track(&mut self, track: impl Into<String>) {
+        self.insert("track", track);
+    }
+
+    pub(crate) fn insert_album(&mut self, album: impl Into<String>) {
+        self.insert("album", album);
+    }
+
+    pub(crate) fn insert_album_artist(&mut self, album_artist: impl Into<String>) {
+        self.insert("albumArtist", album_artist);
+    }
+
+    pub(crate) fn insert_timestamp(&mut self, timestamp: u64) {
+        self.insert("timestamp", timestamp.to_string());
+    }
+
+    pub(crate) fn insert_duration(&mut self, secs: u64) {
+        self.insert("duration", secs.to_string());
+    }
+
+    pub(crate) fn insert_track_number(&mut self, track_number: u32) {
+        self.insert("trackNumber", track_number.to_string());
+    }
+
+    pub(crate) fn insert_mbid(&mut self, mbid: impl Into<String>) {
+        self.insert("mbid", mbid);
+    }
+
+    pub(crate) fn insert_context(&mut self, context: impl Into<String>) {
+        self.insert("context", context);
+    }
+
+    pub(crate) fn insert_chosen_by_user(&mut self, chosen_by_user: bool) {
+        self.insert("chosenByUser", if chosen_by_user { "1" } else { "0" });
+    }
+
+    pub(crate) fn insert_autocorrect(&mut self, autocorrect: bool) {
+        self.insert("autocorrect", if autocorrect { "1" } else { "0" });
+    }
+
+    pub(crate) fn insert_language(&mut self, language: &Language) {
+        self.insert("lang", language.code());
+    }
+
+    pub(crate) fn insert_method(&mut self, method: &str) {
+        self.insert("method", method);
+    }
+
+    pub(crate) fn insert_api_key(&mut self, api_key: impl Into<String>) {
+        self.insert("api_key", api_key);
+    }
+
+    pub(crate) fn insert_session_key(&mut self, session_key: impl Into<String>) {
+        self.insert("sk", session_key);
+    }
+
+    pub(crate) fn insert_nonce(&mut self, nonce: impl Into<String>) {
+        self.insert("nonce", nonce);
+    }
+
+    pub(crate) fn insert_signature(&mut self, signature: impl Into<String>) {
+        self.insert("api_sig", signature);
+    }
+
+    pub(crate) fn insert_username(&mut self, username: impl Into<String>) {
+        self.insert("username", username);
+    }
+
+    pub(crate) fn insert_password(&mut self, password: impl Into<String>) {
+        self.insert("password", password);
+    }
+
+    pub(crate) fn insert_token(&mut self, token: impl Into<String>) {
+        self.insert("token", token);
+    }
+
+    pub(crate) fn insert_user(&mut self, user: impl Into<String>) {
+        self.insert("user", user);
+    }
+
+    // The time range of a history request, in seconds since the Unix epoch, both ends included
+    pub(crate) fn insert_time_range(&mut self, from: u64, to: u64) {
+        self.insert_from(from);
+        self.insert_to(to);
+    }
+
+    pub(crate) fn insert_from(&mut self, from: u64) {
+        self.insert("from", from.to_string());
+    }
+
+    pub(crate) fn insert_to(&mut self, to: u64) {
+        self.insert("to", to.to_string());
+    }
+
+    pub(crate) fn insert_page(&mut self, page: u32, limit: u32) {
+        self.insert("page", page.to_string());
+        self.insert("limit", limit.to_string());
+    }
+
+    // Inserts a parameter whose name is configured at runtime, ie. an extension some servers accept
+    pub(crate) fn insert_extension(&mut self, key: &str, value: impl Into<String>) {
+        self.params.insert(Cow::Owned(key.to_owned()), value.into());
+    }
+
+    // Inserts a parameter using the array notation batched requests require, ie. "artist[1]"
+    pub(crate) fn insert_indexed(&mut self, key: &str, index: usize, value: impl Into<String>) {
+        self.params
+            .insert(Cow::Owned(format!("{key}[{index}]")), value.into());
+    }
+
+    // Adds the parameters of one entry of a batch request, suffixing each key with the entry's index: one track's
+    // "artist" and "track" become "artist[2]" and "track[2]"
+    pub(crate) fn insert_batch_entry(&mut self, index: usize, entry: &Self) {
+        for (key, value) in entry.iter() {
+            debug_assert!(!key.ends_with(']'), "batch entry parameter {} is already indexed", key);
+            self.insert_indexed(key, index, value);
+        }
+    }
+
+    pub(crate) fn extend(&mut self, other: &Self) {
+        for (key, value) in &other.params {
+            self.params.insert(key.clone(), value.clone());
+        }
+    }
+
+    /// Returns the value of the given parameter, if present
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params.get(key).map(String::as_str)
+    }
+
+    /// Returns true if the given parameter is present
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.params.contains_key(key)
+    }
+
+    /// Returns the number of parameters in the set
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns true if the set contains no parameters
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Iterates over the `(key, value)` pairs of the set, ordered by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_ref(), v.as_str()))
+    }
+
+    /// Encodes the parameter set as an `application/x-www-form-urlencoded` request body, as sent to the Last.fm API.
+    ///
+    /// Keys and values are encoded as UTF-8, with every byte other than an ASCII letter, digit or one of `*-._`
+    /// percent-encoded. Spaces are sent as `%20` rather than `+`, as not every server decodes `+` back into a space.
+    /// Request signatures are computed over the raw values, not the encoded ones.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // artist=Simon%20%26%20Garfunkel&track=Cecilia
+    /// let body = params.to_form_body();
+    /// ```
+    pub fn to_form_body(&self) -> String {
+        let mut body = String::new();
+        for (key, value) in self.iter() {
+            if !body.is_empty() {
+                body.push('&');
+            }
+            form_encode(&mut body, key);
+            body.push('=');
+            form_encode(&mut body, value);
+        }
+
+        body
+    }
+
+    /// Converts the parameter set into an owned `HashMap`
+    pub fn into_map(self) -> HashMap<String, String> {
+        self.params
+            .into_iter()
+            .map(|(k, v)| (k.into_owned(), v))
+            .collect()
+    }
+}
+
+// Appends `value` to `out`, encoded as in an `application/x-www-form-urlencoded` body
+fn form_encode(out: &mut String, value: &str) {
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => out.push(char::from(byte)),
+            byte => {
+                let _ = write!(out, "%{byte:02X}");
+            }
+        }
+    }
+}
+
+impl Index<&str> for Params {
+    type Output = String;
+
+    fn index(&self, key: &str) -> &String {
+        &self.params[key]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_params_ordering() {
+        let mut params = Params::new();
+        params.insert("track", "old bananas");
+        params.insert("artist", "foo floyd");
+        params.insert_indexed("album", 1, "old bananas");
+        params.insert_indexed("album", 0, "new bananas");
+
+        let keys: Vec<&str> = params.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["album[0]", "album[1]", "artist", "track"]);
+        assert_eq!(params["album[0]"], "new bananas");
+        assert_eq!(params.get("missing"), None);
+    }
+
+    #[test]
+    fn check_params_batch_entry() {
+        let mut entry = Params::new();
+        entry.insert_artist("floyd");
+        entry.insert_timestamp(1337);
+
+        let mut params = Params::new();
+        params.insert_api_key("api_key");
+        params.insert_batch_entry(0, &entry);
+        params.insert_batch_entry(11, &entry);
+
+        let keys: Vec<&str> = params.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["api_key", "artist[0]", "artist[11]", "timestamp[0]", "timestamp[11]"]);
+        assert_eq!(params["timestamp[11]"], "1337");
+    }
+
+    #[test]
+    fn check_params_into_map() {
+        let mut params = Params::new();
+        params.insert("artist", "foo floyd");
+        params.insert_indexed("track", 3, "old bananas");
+
+        let map = params.into_map();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["artist"], "foo floyd");
+        assert_eq!(map["track[3]"], "old bananas");
+    }
+
+    // Decodes a form-encoded value, as a server would
+    fn form_decode(encoded: &str) -> String {
+        let mut bytes = Vec::new();
+        let mut rest = encoded.bytes();
+        while let Some(byte) = rest.next() {
+            match byte {
+                b'+' => bytes.push(b' '),
+                b'%' => {
+                    let hex: Vec<u8> = rest.by_ref().take(2).collect();
+                    let hex = std::str::from_utf8(&hex).unwrap();
+                    bytes.push(u8::from_str_radix(hex, 16).unwrap());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn check_params_form_encoding() {
+        let cases = [
+            ("Simon & Garfunkel", "Simon%20%26%20Garfunkel"),
+            ("1+1=2", "1%2B1%3D2"),
+            ("100% Pure", "100%25%20Pure"),
+            ("Björk", "Bj%C3%B6rk"),
+            ("Sigur Rós ♫", "Sigur%20R%C3%B3s%20%E2%99%AB"),
+            ("🎵 Emoji & Co.", "%F0%9F%8E%B5%20Emoji%20%26%20Co."),
+            ("𝄞 non-BMP", "%F0%9D%84%9E%20non-BMP"),
+            ("a/b?c#d;e", "a%2Fb%3Fc%23d%3Be"),
+            ("*-._~", "*-._%7E"),
+            ("", ""),
+        ];
+
+        for (raw, encoded) in &cases {
+            let mut params = Params::new();
+            params.insert("artist", *raw);
+            params.insert("track", *raw);
+            params.insert_indexed("album", 0, *raw);
+
+            let body = params.to_form_body();
+            assert_eq!(body, format!("album%5B0%5D={encoded}&artist={encoded}&track={encoded}"));
+
+            let decoded: Vec<(String, String)> = body
+                .split('&')
+                .map(|pair| {
+                    let (key, value) = pair.split_at(pair.find('=').unwrap());
+                    (form_decode(key), form_decode(&value[1..]))
+                })
+                .collect();
+            let original: Vec<(String, String)> =
+                params.iter().map(|(k, v)| (k.to_owned(), v.to_owned())).collect();
+            assert_eq!(decoded, original);
+        }
+
+        assert_eq!(Params::new().to_form_body(), "");
+    }
+
+    #[test]
+    fn check_params_insertion_order() {
+        use crate::signing::{self, Md5Signer};
+
+        let mut entry = Params::new();
+        entry.insert_artist("floyd");
+        entry.insert_track("old bananas");
+        entry.insert_timestamp(1337);
+        // Each part of the request, to be inserted as a whole
+        let part = |insert: &dyn Fn(&mut Params)| {
+            let mut params = Params::new();
+            insert(&mut params);
+            params
+        };
+        let mut parts = vec![
+            part(&|params| params.insert_api_key("api_key")),
+            part(&|params| params.insert_session_key("seshkey")),
+            part(&|params| params.insert_method("track.scrobble")),
+            part(&|params| params.insert_extension("mediaKind", "podcast")),
+            part(&|params| params.insert_extension("x-client", "player")),
+        ];
+        for index in &[0, 2, 10, 11] {
+            parts.push(part(&|params| params.insert_batch_entry(*index, &entry)));
+        }
+
+        // Inserting the same parameters in any order, as iterating a `HashMap` might, makes the same request
+        let count = parts.len();
+        let orders: Vec<Vec<usize>> = vec![
+            (0..count).collect(),
+            (0..count).rev().collect(),
+            (0..count).map(|i| i * 4 % count).collect(),
+            (0..count).map(|i| (i * 7 + 3) % count).collect(),
+        ];
+        let requests: Vec<(String, String)> = orders
+            .iter()
+            .map(|order| {
+                let mut params = Params::new();
+                for &i in order {
+                    params.extend(&parts[i]);
+                }
+                assert_eq!(params.len(), 5 + 4 * 3);
+                let signature = signing::sign(&Md5Signer, "track.scrobble", &params, "api_secret");
+                (params.to_form_body(), signature)
+            })
+            .collect();
+        assert!(requests.iter().all(|request| *request == requests[0]));
+
+        let body = &requests[0].0;
+        let keys: Vec<&str> = body.split('&').map(|pair| &pair[..pair.find('=').unwrap()]).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "api_key",
+                "artist%5B0%5D",
+                "artist%5B10%5D",
+                "artist%5B11%5D",
+                "artist%5B2%5D",
+                "mediaKind",
+                "method",
+                "sk",
+                "timestamp%5B0%5D",
+                "timestamp%5B10%5D",
+                "timestamp%5B11%5D",
+                "timestamp%5B2%5D",
+                "track%5B0%5D",
+                "track%5B10%5D",
+                "track%5B11%5D",
+                "track%5B2%5D",
+                "x-client",
+            ]
+        );
+    }
+}
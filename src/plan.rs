@@ -0,0 +1,134 @@
+// Planning an import against the user's listening history, to find the scrobbles it would duplicate
+use crate::chunks;
+use crate::matching::TrackKey;
+use crate::models::metadata::ScrobbleBatch;
+use crate::models::responses::RecentTrack;
+
+use std::time::Duration;
+
+// Last.fm ignores scrobbles timestamped more than two weeks ago
+pub(crate) const MAX_SCROBBLE_AGE: Duration = Duration::from_hours(14 * 24);
+
+// Matches each scrobble, submitted at the corresponding timestamp, against the history, returning the timestamp of
+// the scrobble there which it duplicates, if any. Scrobbles without a timestamp match nothing.
+pub(crate) fn match_history(
+    batch: &ScrobbleBatch,
+    timestamps: &[Option<u64>],
+    history: &[RecentTrack],
+    window: Duration,
+) -> Vec<Option<u64>> {
+    // Tracks being played now haven't been scrobbled yet
+    let history: Vec<(TrackKey, u64)> = history
+        .iter()
+        .filter_map(|track| Some((TrackKey::new(track.artist(), track.name()), track.timestamp()?)))
+        .collect();
+    let mut matched = vec![false; history.len()];
+
+    batch
+        .iter()
+        .zip(timestamps)
+        .map(|(scrobble, &timestamp)| {
+            let timestamp = timestamp?;
+            // Compare the names as they'd be submitted
+            let params = scrobble.params();
+            let name = |key| params.get(key).unwrap_or_default();
+            let key = TrackKey::new(name("artist"), name("track"));
+            let (i, existing) = (0..history.len())
+                .filter(|&i| !matched[i] && history[i].0 == key)
+                .map(|i| (i, history[i].1))
+                .filter(|(_, existing)| existing.abs_diff(timestamp) <= window.as_secs())
+                .min_by_key(|(_, existing)| existing.abs_diff(timestamp))?;
+            matched[i] = true;
+            Some(existing)
+        })
+        .collect()
+}
+
+/// What submitting one scrobble of an import would do, according to an [`ImportPlan`]
+///
+/// [`ImportPlan`]: struct.ImportPlan.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImportStatus {
+    /// Nothing in the user's history matches the scrobble, so it would be added
+    New,
+    /// The user's history already holds a scrobble of the same track within the plan's window, scrobbled at
+    /// `existing_timestamp`
+    ProbableDuplicate { existing_timestamp: u64 },
+    /// The scrobble is older than Last.fm accepts (two weeks), or than the `Scrobbler`'s minimum timestamp, so
+    /// would be ignored or rejected
+    TooOld,
+}
+
+/// The outcome of planning an import with [`Scrobbler::plan_import`]: the [`ImportStatus`] of each of its
+/// scrobbles, in the order the batch holds them. Nothing has been submitted.
+///
+/// Each scrobble in the user's history accounts for at most one duplicate, so playing a track twice in the import
+/// when the history has it once leaves one of the two new. Pass the plan to [`BatchChunks::skip_duplicates`] to
+/// submit only the scrobbles which aren't duplicates.
+///
+/// [`Scrobbler::plan_import`]: struct.Scrobbler.html#method.plan_import
+/// [`ImportStatus`]: enum.ImportStatus.html
+/// [`BatchChunks::skip_duplicates`]: struct.BatchChunks.html#method.skip_duplicates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportPlan {
+    statuses: Vec<ImportStatus>,
+    batch_hash: String,
+}
+
+impl ImportPlan {
+    // Matches each scrobble, submitted at the corresponding timestamp, against the history. Scrobbles before
+    // `oldest` are too old whatever the history holds.
+    pub(crate) fn new(
+        batch: &ScrobbleBatch,
+        timestamps: &[u64],
+        history: &[RecentTrack],
+        window: Duration,
+        oldest: u64,
+    ) -> Self {
+        // Scrobbles too old to submit aren't matched at all
+        let submittable: Vec<Option<u64>> =
+            timestamps.iter().map(|&timestamp| Some(timestamp).filter(|timestamp| *timestamp >= oldest)).collect();
+        let statuses = submittable
+            .iter()
+            .zip(match_history(batch, &submittable, history, window))
+            .map(|(timestamp, existing)| match (timestamp, existing) {
+                (None, _) => ImportStatus::TooOld,
+                (Some(_), Some(existing_timestamp)) => ImportStatus::ProbableDuplicate { existing_timestamp },
+                (Some(_), None) => ImportStatus::New,
+            })
+            .collect();
+
+        Self {
+            statuses,
+            batch_hash: chunks::batch_hash(batch.iter()),
+        }
+    }
+
+    /// Returns the status of every scrobble in the import, in the order the batch holds them
+    pub fn statuses(&self) -> &[ImportStatus] {
+        &self.statuses
+    }
+
+    /// Returns the positions in the batch of the scrobbles which would be added
+    pub fn new_scrobbles(&self) -> Vec<usize> {
+        self.positions(|status| status == ImportStatus::New)
+    }
+
+    /// Returns the positions in the batch of the scrobbles which are probably in the user's history already
+    pub fn probable_duplicates(&self) -> Vec<usize> {
+        self.positions(|status| matches!(status, ImportStatus::ProbableDuplicate { .. }))
+    }
+
+    /// Returns the positions in the batch of the scrobbles which are too old to submit
+    pub fn too_old(&self) -> Vec<usize> {
+        self.positions(|status| status == ImportStatus::TooOld)
+    }
+
+    pub(crate) fn batch_hash(&self) -> &str {
+        &self.batch_hash
+    }
+
+    fn positions(&self, filter: impl Fn(ImportStatus) -> bool) -> Vec<usize> {
+        (0..self.statuses.len()).filter(|&i| filter(self.statuses[i])).collect()
+    }
+}
@@ -0,0 +1,314 @@
+// Playback state machine deciding when to send Now Playing and Scrobble requests
+use crate::models::metadata::Scrobble;
+use crate::timer::ScrobbleTimer;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What a player should do in response to a playback event, as decided by a [`PlaybackSession`]
+///
+/// [`PlaybackSession`]: struct.PlaybackSession.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlaybackAction {
+    /// Send a Now Playing request for the track, ie. with [`Scrobbler::now_playing`]
+    ///
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    NowPlaying(Scrobble),
+    /// Scrobble the track, ie. with [`Scrobbler::scrobble`]. The `Scrobble`'s timestamp is set to the time the
+    /// track started playing.
+    ///
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    Scrobble(Scrobble),
+}
+
+/// Tracks a player's playback, and decides when to send Now Playing updates and scrobbles.
+///
+/// The player reports playback events to the session, which answers each with the [`PlaybackAction`]s to take, if
+/// any; the player forwards them to a `Scrobbler`. The session itself never sends anything, so its decisions can be
+/// tested without a network.
+///
+/// Sessions follow Last.fm's [scrobbling rules](https://www.last.fm/api/scrobbling#when-is-a-scrobble-a-scrobble),
+/// as implemented by [`ScrobbleTimer`]: a track is scrobbled once it has actually been *played* for long enough, so
+/// time spent paused, or skipped over by seeking forwards, doesn't count. Each play of a track is scrobbled at most
+/// once, even if the listener seeks back and plays it again.
+///
+/// A scrobble is reported by whichever event first finds the track has been played for long enough. Call
+/// [`PlaybackSession::tick`] periodically, ie. every few seconds, for scrobbles to be reported as soon as they're
+/// due rather than when the track next changes.
+///
+/// # Usage
+/// ```ignore
+/// let mut session = PlaybackSession::new();
+///
+/// for action in session.started(&track, Duration::from_secs(180)) {
+///     match action {
+///         PlaybackAction::NowPlaying(scrobble) => { scrobbler.now_playing(&scrobble)?; }
+///         PlaybackAction::Scrobble(scrobble) => { scrobbler.scrobble(&scrobble)?; }
+///     }
+/// }
+/// ```
+///
+/// [`PlaybackAction`]: enum.PlaybackAction.html
+/// [`ScrobbleTimer`]: struct.ScrobbleTimer.html
+/// [`PlaybackSession::tick`]: struct.PlaybackSession.html#method.tick
+#[derive(Clone, Debug, Default)]
+pub struct PlaybackSession {
+    current: Option<Play>,
+}
+
+// The play of the current track
+#[derive(Clone, Debug)]
+struct Play {
+    scrobble: Scrobble,
+    started_at: SystemTime,
+    timer: ScrobbleTimer,
+    playing_since: Option<SystemTime>,
+    position: Duration,
+    scrobbled: bool,
+}
+
+impl Play {
+    // Counts the time played since the last update
+    fn update(&mut self, now: SystemTime) {
+        if let Some(since) = self.playing_since {
+            let played = now.duration_since(since).unwrap_or_default();
+            self.timer.add_played(played);
+            self.position += played;
+            self.playing_since = Some(now);
+        }
+    }
+
+    // Returns the scrobble for this play, the first time it becomes due
+    fn check(&mut self) -> Vec<PlaybackAction> {
+        if self.scrobbled || !self.timer.should_scrobble() {
+            return Vec::new();
+        }
+
+        self.scrobbled = true;
+        let mut scrobble = self.scrobble.clone();
+        let timestamp = self.started_at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        scrobble.with_timestamp(timestamp.as_secs());
+
+        vec![PlaybackAction::Scrobble(scrobble)]
+    }
+}
+
+impl PlaybackSession {
+    /// Creates a session with nothing playing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports that a track of the given duration started playing. Any track already playing is stopped first.
+    pub fn started(&mut self, scrobble: &Scrobble, duration: Duration) -> Vec<PlaybackAction> {
+        self.started_at(scrobble, duration, SystemTime::now())
+    }
+
+    /// Reports that playback moved on to the next track. Equivalent to [`PlaybackSession::started`].
+    ///
+    /// [`PlaybackSession::started`]: struct.PlaybackSession.html#method.started
+    pub fn track_changed(&mut self, next: &Scrobble, duration: Duration) -> Vec<PlaybackAction> {
+        self.started_at(next, duration, SystemTime::now())
+    }
+
+    /// Reports that playback was paused
+    pub fn paused(&mut self) -> Vec<PlaybackAction> {
+        self.paused_at(SystemTime::now())
+    }
+
+    /// Reports that playback resumed after a pause. The Now Playing status is refreshed, as it may have expired
+    /// during the pause.
+    pub fn resumed(&mut self) -> Vec<PlaybackAction> {
+        self.resumed_at(SystemTime::now())
+    }
+
+    /// Reports that the listener jumped to the given position in the current track. Skipped-over time doesn't
+    /// count as played.
+    pub fn seeked(&mut self, position: Duration) -> Vec<PlaybackAction> {
+        self.seeked_at(position, SystemTime::now())
+    }
+
+    /// Reports that playback stopped, ie. the track ended or the player was closed
+    pub fn stopped(&mut self) -> Vec<PlaybackAction> {
+        self.stopped_at(SystemTime::now())
+    }
+
+    /// Checks whether the current track has become due for scrobbling since the last event
+    pub fn tick(&mut self) -> Vec<PlaybackAction> {
+        self.tick_at(SystemTime::now())
+    }
+
+    /// Returns the track currently playing or paused, if any
+    pub fn current(&self) -> Option<&Scrobble> {
+        self.current.as_ref().map(|play| &play.scrobble)
+    }
+
+    /// Returns the playback position in the current track, as of the last event
+    pub fn position(&self) -> Option<Duration> {
+        self.current.as_ref().map(|play| play.position)
+    }
+
+    pub(crate) fn started_at(
+        &mut self,
+        scrobble: &Scrobble,
+        duration: Duration,
+        now: SystemTime,
+    ) -> Vec<PlaybackAction> {
+        let mut actions = self.stopped_at(now);
+        self.current = Some(Play {
+            scrobble: scrobble.clone(),
+            started_at: now,
+            timer: ScrobbleTimer::new(duration).with_media_kind(scrobble.media_kind()),
+            playing_since: Some(now),
+            position: Duration::from_secs(0),
+            scrobbled: false,
+        });
+
+        actions.push(PlaybackAction::NowPlaying(scrobble.clone()));
+        actions
+    }
+
+    pub(crate) fn paused_at(&mut self, now: SystemTime) -> Vec<PlaybackAction> {
+        self.current.as_mut().map_or_else(Vec::new, |play| {
+            play.update(now);
+            play.playing_since = None;
+            play.check()
+        })
+    }
+
+    pub(crate) fn resumed_at(&mut self, now: SystemTime) -> Vec<PlaybackAction> {
+        match &mut self.current {
+            Some(play) if play.playing_since.is_none() => {
+                play.playing_since = Some(now);
+                vec![PlaybackAction::NowPlaying(play.scrobble.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub(crate) fn seeked_at(&mut self, position: Duration, now: SystemTime) -> Vec<PlaybackAction> {
+        self.current.as_mut().map_or_else(Vec::new, |play| {
+            play.update(now);
+            play.position = position;
+            play.check()
+        })
+    }
+
+    pub(crate) fn stopped_at(&mut self, now: SystemTime) -> Vec<PlaybackAction> {
+        self.current.take().map_or_else(Vec::new, |mut play| {
+            play.update(now);
+            play.check()
+        })
+    }
+
+    pub(crate) fn tick_at(&mut self, now: SystemTime) -> Vec<PlaybackAction> {
+        self.current.as_mut().map_or_else(Vec::new, |play| {
+            play.update(now);
+            play.check()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: u64 = 1_500_000_000;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(START + secs)
+    }
+
+    fn track(name: &str) -> Scrobble {
+        Scrobble::new("floyd", name, "old")
+    }
+
+    fn scrobbled(name: &str, started: u64) -> PlaybackAction {
+        let mut scrobble = track(name);
+        scrobble.with_timestamp(START + started);
+        PlaybackAction::Scrobble(scrobble)
+    }
+
+    fn now_playing(name: &str) -> PlaybackAction {
+        PlaybackAction::NowPlaying(track(name))
+    }
+
+    fn three_minutes() -> Duration {
+        Duration::from_mins(3)
+    }
+
+    #[test]
+    fn check_session_scrobbles_once_due() {
+        let mut session = PlaybackSession::new();
+
+        assert_eq!(session.started_at(&track("a"), three_minutes(), at(0)), vec![now_playing("a")]);
+        assert!(session.tick_at(at(89)).is_empty());
+        assert_eq!(session.tick_at(at(90)), vec![scrobbled("a", 0)]);
+        assert!(session.tick_at(at(110)).is_empty());
+        assert_eq!(session.position(), Some(Duration::from_secs(110)));
+        assert!(session.stopped_at(at(180)).is_empty());
+        assert!(session.current().is_none());
+        assert!(session.tick_at(at(200)).is_empty());
+    }
+
+    #[test]
+    fn check_session_seek_past_threshold() {
+        let mut session = PlaybackSession::new();
+        session.started_at(&track("a"), three_minutes(), at(0));
+
+        // Skipping to the end doesn't count as listening to it
+        assert!(session.seeked_at(Duration::from_secs(170), at(5)).is_empty());
+        assert_eq!(session.position(), Some(Duration::from_secs(170)));
+        assert!(session.stopped_at(at(15)).is_empty());
+    }
+
+    #[test]
+    fn check_session_seek_backwards_scrobbles_once() {
+        let mut session = PlaybackSession::new();
+        session.started_at(&track("a"), three_minutes(), at(0));
+
+        assert_eq!(session.seeked_at(Duration::from_secs(0), at(100)), vec![scrobbled("a", 0)]);
+        assert!(session.tick_at(at(200)).is_empty());
+        assert!(session.seeked_at(Duration::from_secs(0), at(250)).is_empty());
+        assert!(session.stopped_at(at(400)).is_empty());
+
+        // A new play of the same track is scrobbled again
+        session.started_at(&track("a"), three_minutes(), at(400));
+        assert_eq!(session.stopped_at(at(490)), vec![scrobbled("a", 400)]);
+    }
+
+    #[test]
+    fn check_session_rapid_track_skipping() {
+        let mut session = PlaybackSession::new();
+
+        assert_eq!(session.started_at(&track("a"), three_minutes(), at(0)), vec![now_playing("a")]);
+        assert_eq!(session.started_at(&track("b"), three_minutes(), at(3)), vec![now_playing("b")]);
+        assert_eq!(session.started_at(&track("c"), three_minutes(), at(5)), vec![now_playing("c")]);
+        assert_eq!(session.current(), Some(&track("c")));
+
+        // Changing track after the scrobble point scrobbles the previous track first
+        assert_eq!(
+            session.started_at(&track("d"), three_minutes(), at(125)),
+            vec![scrobbled("c", 5), now_playing("d")]
+        );
+        // Short tracks are never scrobbled
+        session.started_at(&track("e"), Duration::from_secs(20), at(305));
+        assert!(session.stopped_at(at(325)).is_empty());
+    }
+
+    #[test]
+    fn check_session_pause_heavy_listening() {
+        let mut session = PlaybackSession::new();
+        session.started_at(&track("a"), three_minutes(), at(0));
+
+        assert!(session.paused_at(at(30)).is_empty());
+        assert!(session.tick_at(at(600)).is_empty());
+        assert_eq!(session.resumed_at(at(600)), vec![now_playing("a")]);
+        // Resuming while playing is a no-op
+        assert!(session.resumed_at(at(610)).is_empty());
+        assert!(session.paused_at(at(630)).is_empty());
+        assert!(session.paused_at(at(700)).is_empty());
+        session.resumed_at(at(1200));
+        assert!(session.tick_at(at(1229)).is_empty());
+        assert_eq!(session.paused_at(at(1230)), vec![scrobbled("a", 0)]);
+    }
+}
@@ -0,0 +1,1777 @@
+// Persistent queue of scrobbles awaiting submission
+use crate::chunks::{DailyLimitReached, MAX_CHUNK_SIZE};
+use crate::config::ScrobblerConfig;
+#[cfg(feature = "crypto")]
+use crate::crypto::{self, EncryptionKey};
+use crate::error::{ErrorKind, FileOperation, ScrobblerError};
+use crate::flush::{self, Begin};
+use crate::health::HealthReport;
+use crate::ignored::{DeadLetter, IgnoredPolicy};
+use crate::issues::PreparationReport;
+use crate::matching::TrackKey;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::{IgnoredReason, ScrobbleResponse};
+use crate::retry::RetryPolicy;
+use crate::scrobbler::Scrobbler;
+use crate::submitted::SubmittedScrobble;
+use crate::usage::UsageHistory;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::result;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+type Result<T> = result::Result<T, ScrobblerError>;
+
+const FORMAT_VERSION: u32 = 1;
+
+const STATE_FORMAT_VERSION: u32 = 1;
+
+// How long a flush keeps the scrobbles it's submitting to itself, should it never finish (ie. because its process
+// crashed)
+const IN_FLIGHT_LEASE: Duration = Duration::from_mins(10);
+
+/// A queue of scrobbles awaiting submission, persisted to a file so that plays recorded while offline survive a
+/// restart.
+///
+/// Scrobbles pushed onto the queue are written to disk immediately, and submitted by [`ScrobbleQueue::flush`] in
+/// chronological order. Only scrobbles which Last.fm accepts are removed from the queue; a scrobble which fails or is
+/// ignored stays queued, and is retried by later flushes. Scrobbles which have been queued for so long that Last.fm
+/// would ignore them (older than the `Scrobbler`'s [`Scrobbler::set_max_scrobble_age`], two weeks by default) are
+/// dropped by the next flush, without being submitted. None are dropped if the `Scrobbler` has no maximum age.
+///
+/// A player which scrobbles every play of a track on repeat can have consecutive repeats collapsed; see
+/// [`ScrobbleQueue::set_repeat_policy`].
+///
+/// Scrobbles which Last.fm ignores for reasons which won't pass, such as an ignored artist, can instead be
+/// discarded, set aside as dead letters, or rewritten and retried once; see [`ScrobbleQueue::set_ignored_policy`].
+///
+/// Once Last.fm ignores a scrobble because the daily scrobble limit has been reached, the flush stops, and every
+/// remaining scrobble is parked until the limit is expected to reset (see [`DailyLimitReached`]). Parked scrobbles
+/// aren't counted as failing, so don't back off any further.
+///
+/// The most recent scrobble Last.fm accepted (see [`Scrobbler::last_accepted_scrobble`]) is saved with the queue,
+/// and restored to the `Scrobbler` when the queue is opened again.
+///
+/// # Retry backoff
+/// Each queued scrobble is scheduled independently. After a failed attempt, a scrobble isn't retried until its
+/// backoff has expired; the backoff doubles with each consecutive failure, up to a maximum (see
+/// [`ScrobbleQueue::set_backoff`]). Flushes skip scrobbles which are backing off, while still submitting the others,
+/// so a single permanently-failing scrobble can't hold up the rest of the queue. The schedule is persisted along
+/// with the scrobbles.
+///
+/// # Concurrent flushes
+/// Only one flush of a queue file runs at a time in a process: another handle of the same file, ie. one opened by
+/// a shutdown hook, waits for it to finish (see [`ScrobbleQueue::set_concurrent_flush`]). A flush marks the
+/// scrobbles it's about to submit as in flight in the file, so that a flush in another process leaves them be, for
+/// up to 10 minutes should the flush never finish. Pushes from several handles of one file aren't merged, though:
+/// the most recent save wins.
+///
+/// # Encryption
+/// With the `crypto` feature, [`ScrobbleQueue::open_encrypted`] opens a queue whose file is encrypted with a key
+/// the application supplies, as the file holds the user's listening history. See [`EncryptionKey`].
+///
+/// # Usage
+/// ```ignore
+/// let mut scrobbler = Scrobbler::new(api_key, api_secret);
+/// scrobbler.authenticate_with_session_key(session_key)?;
+///
+/// let mut queue = ScrobbleQueue::open("scrobbles.json", scrobbler)?;
+/// queue.push(&Scrobble::new("Example Artist", "Example Track", "Example Album"))?;
+///
+/// // Later, ie. periodically or when the network comes back
+/// let report = queue.flush()?;
+/// println!("{} scrobbled, {} still queued", report.accepted, queue.len());
+/// ```
+///
+/// # Moving between machines
+/// [`ScrobbleQueue::export_state`] writes the queued scrobbles, the `Scrobbler`'s session and the queue's backoff
+/// settings to a single JSON backup, which [`ScrobbleQueue::import_state`] restores on another machine.
+///
+/// [`ScrobbleQueue::flush`]: struct.ScrobbleQueue.html#method.flush
+/// [`Scrobbler::set_max_scrobble_age`]: struct.Scrobbler.html#method.set_max_scrobble_age
+/// [`ScrobbleQueue::set_repeat_policy`]: struct.ScrobbleQueue.html#method.set_repeat_policy
+/// [`ScrobbleQueue::set_ignored_policy`]: struct.ScrobbleQueue.html#method.set_ignored_policy
+/// [`ScrobbleQueue::set_backoff`]: struct.ScrobbleQueue.html#method.set_backoff
+/// [`ScrobbleQueue::set_concurrent_flush`]: struct.ScrobbleQueue.html#method.set_concurrent_flush
+/// [`ScrobbleQueue::export_state`]: struct.ScrobbleQueue.html#method.export_state
+/// [`ScrobbleQueue::import_state`]: struct.ScrobbleQueue.html#method.import_state
+/// [`DailyLimitReached`]: struct.DailyLimitReached.html
+/// [`ScrobbleQueue::open_encrypted`]: struct.ScrobbleQueue.html#method.open_encrypted
+/// [`EncryptionKey`]: struct.EncryptionKey.html
+/// [`Scrobbler::last_accepted_scrobble`]: struct.Scrobbler.html#method.last_accepted_scrobble
+pub struct ScrobbleQueue {
+    path: PathBuf,
+    scrobbler: Scrobbler,
+    entries: Vec<QueueEntry>,
+    dead_letters: Vec<DeadLetter>,
+    backoff: RetryPolicy,
+    ignored_policies: HashMap<IgnoredReason, IgnoredPolicy>,
+    repeat_policy: Option<RepeatPolicy>,
+    concurrent_flush: ConcurrentFlush,
+    serialize_submissions: bool,
+    last_preparation: PreparationReport,
+    persist_usage: bool,
+    // The usage loaded from the file, until persisting it is enabled
+    persisted_usage: Option<UsageHistory>,
+    #[cfg(feature = "crypto")]
+    key: Option<EncryptionKey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct QueueEntry {
+    scrobble: Scrobble,
+    #[serde(default)]
+    failures: u32,
+    // Seconds since the Unix epoch; the entry isn't retried before then
+    #[serde(default)]
+    next_attempt_at: u64,
+    // Seconds since the Unix epoch; until then a flush is submitting the entry, so no other flush may
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    in_flight_until: Option<u64>,
+    // Whether it's this handle's flush which is submitting the entry
+    #[serde(skip)]
+    claimed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct QueueFile {
+    version: u32,
+    entries: Vec<QueueEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dead_letters: Vec<DeadLetter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<UsageHistory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_accepted: Option<SubmittedScrobble>,
+}
+
+// A backup of a queue and its Scrobbler's session, written by `export_state`
+#[derive(Debug, Serialize, Deserialize)]
+struct StateFile {
+    version: u32,
+    #[serde(default)]
+    username: Option<String>,
+    // Left out unless secrets are exported
+    #[serde(default)]
+    session_key: Option<String>,
+    backoff_base_secs: u64,
+    backoff_max_secs: u64,
+    entries: Vec<QueueEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dead_letters: Vec<DeadLetter>,
+}
+
+// How far a flush got through a chunk of claimed entries
+enum Settled {
+    // Every entry of the chunk was submitted, and this many were removed from the queue
+    Submitted { removed: usize },
+    // The flush stops here, as the daily scrobble limit was reached or nothing more can be sent for now
+    Stopped,
+}
+
+/// How [`ScrobbleQueue::import_state`] combines imported scrobbles with those already queued
+///
+/// [`ScrobbleQueue::import_state`]: struct.ScrobbleQueue.html#method.import_state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateImportMode {
+    /// Adds the imported scrobbles to the queue, in chronological order, skipping any which are already queued
+    Merge,
+    /// Replaces every queued scrobble with the imported ones
+    Replace,
+}
+
+/// Collapses plays of a track on repeat. See [`ScrobbleQueue::set_repeat_policy`].
+///
+/// Consecutive plays of the same track (by artist and track name, ignoring case) within `window` of the first of
+/// them are collapsed into the first `max_kept`. Plays of the track after the window start a new window. A play of
+/// any other track in between ends the repeats, so plays of different tracks are never collapsed together.
+///
+/// [`ScrobbleQueue::set_repeat_policy`]: struct.ScrobbleQueue.html#method.set_repeat_policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepeatPolicy {
+    /// How long after the first of a run of repeats later ones are collapsed
+    pub window: Duration,
+    /// The most plays kept from each window. At least one always is.
+    pub max_kept: usize,
+}
+
+/// What [`ScrobbleQueue::flush`] does when another handle of the same queue file in the process is already
+/// flushing it. See [`ScrobbleQueue::set_concurrent_flush`].
+///
+/// [`ScrobbleQueue::flush`]: struct.ScrobbleQueue.html#method.flush
+/// [`ScrobbleQueue::set_concurrent_flush`]: struct.ScrobbleQueue.html#method.set_concurrent_flush
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConcurrentFlush {
+    /// Waits for the other flush to finish, then reloads the queue and returns the other flush's report. If the
+    /// other flush failed, flushes the queue itself.
+    #[default]
+    Wait,
+    /// Fails straight away with an error of kind `ErrorKind::FlushInProgress`
+    Fail,
+}
+
+/// The outcome of a [`ScrobbleQueue::scrobble`](struct.ScrobbleQueue.html#method.scrobble)
+#[derive(Debug)]
+pub enum ScrobbleOutcome {
+    /// The scrobble was submitted straight away, with this response
+    Sent(ScrobbleResponse),
+    /// The scrobble was added to the queue, behind older scrobbles still waiting to be submitted
+    Queued,
+}
+
+/// The outcome of a [`ScrobbleQueue::flush`](struct.ScrobbleQueue.html#method.flush)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Scrobbles accepted by Last.fm, and removed from the queue
+    pub accepted: usize,
+    /// Scrobbles which failed or were ignored, and were rescheduled
+    pub failed: usize,
+    /// Ignored scrobbles which were removed from the queue, under `IgnoredPolicy::Discard`
+    pub discarded: usize,
+    /// Ignored scrobbles which were moved to the queue's dead letters
+    pub dead_lettered: usize,
+    /// Scrobbles which weren't submitted, because they are backing off from an earlier failure, because the
+    /// `Scrobbler`'s circuit breaker is open, because its estimated usage reached a hard threshold, or because
+    /// another flush of the queue file (ie. in another process) is submitting them
+    pub deferred: usize,
+    /// Scrobbles older than the `Scrobbler`'s maximum scrobble age (by default, played more than two weeks ago),
+    /// which Last.fm would ignore, and which were removed from the queue without being submitted
+    pub expired: usize,
+    /// Repeated plays of a track which were collapsed, and removed from the queue without being submitted. See
+    /// [`RepeatPolicy`](struct.RepeatPolicy.html).
+    pub collapsed: usize,
+    /// Set if the flush stopped because the daily scrobble limit was reached. The remaining scrobbles, including
+    /// the one Last.fm ignored, are parked in the queue until `resume_at`.
+    pub daily_limit: Option<DailyLimitReached>,
+}
+
+impl ScrobbleQueue {
+    /// Opens the queue persisted at `path`, reloading any scrobbles still pending from an earlier run. The file is
+    /// created the first time a scrobble is pushed. Queued scrobbles are submitted with `scrobbler`, which should
+    /// already be authenticated.
+    pub fn open(path: impl Into<PathBuf>, scrobbler: Scrobbler) -> Result<Self> {
+        Self::new(path.into(), scrobbler).load()
+    }
+
+    /// Opens the queue persisted at `path`, as [`ScrobbleQueue::open`] does, with its file encrypted with `key`.
+    /// Requires the `crypto` feature.
+    ///
+    /// Fails with an error of kind `ErrorKind::WrongKey` if the file was encrypted with a different key, or
+    /// `ErrorKind::Tampered` if it has been modified since it was written, or isn't encrypted at all.
+    ///
+    /// [`ScrobbleQueue::open`]: struct.ScrobbleQueue.html#method.open
+    #[cfg(feature = "crypto")]
+    pub fn open_encrypted(path: impl Into<PathBuf>, scrobbler: Scrobbler, key: EncryptionKey) -> Result<Self> {
+        let mut queue = Self::new(path.into(), scrobbler);
+        queue.key = Some(key);
+        queue.load()
+    }
+
+    fn new(path: PathBuf, scrobbler: Scrobbler) -> Self {
+        Self {
+            path,
+            scrobbler,
+            entries: Vec::new(),
+            dead_letters: Vec::new(),
+            backoff: RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::from_secs(30),
+                max_delay: Duration::from_hours(6),
+            },
+            ignored_policies: HashMap::new(),
+            repeat_policy: None,
+            concurrent_flush: ConcurrentFlush::default(),
+            serialize_submissions: false,
+            last_preparation: PreparationReport::default(),
+            persist_usage: false,
+            persisted_usage: None,
+            #[cfg(feature = "crypto")]
+            key: None,
+        }
+    }
+
+    // Reloads the entries persisted by an earlier run, if any
+    fn load(mut self) -> Result<Self> {
+        self.reload()?;
+        Ok(self)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        match File::open(&self.path) {
+            Ok(mut file) => {
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents).map_err(failed(FileOperation::Read, &self.path))?;
+                let contents = self.unseal(&self.path, contents)?;
+                let file = Self::parse(&self.path, &contents)?;
+                self.entries = file.entries;
+                self.dead_letters = file.dead_letters;
+                self.persisted_usage = file.usage;
+                if let Some(submitted) = file.last_accepted {
+                    self.scrobbler.client().restore_last_accepted(submitted);
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(ScrobblerError::persistence(FileOperation::Open, &self.path, err)),
+        }
+
+        Ok(())
+    }
+
+    fn parse(path: &Path, contents: &[u8]) -> Result<QueueFile> {
+        let file: QueueFile = serde_json::from_slice(contents).map_err(|err| {
+            ScrobblerError::new(format!("Invalid scrobble queue {}: {err}", path.display()))
+        })?;
+        if file.version != FORMAT_VERSION {
+            return Err(ScrobblerError::new(format!(
+                "Unsupported scrobble queue version {} in {}",
+                file.version,
+                path.display()
+            )));
+        }
+
+        Ok(file)
+    }
+
+    /// Sets the backoff applied to a scrobble after a failed submission. The first retry waits `base_delay`, and
+    /// the wait doubles after each further failure, up to `max_delay`. Defaults to 30 seconds, up to 6 hours.
+    pub fn set_backoff(&mut self, base_delay: Duration, max_delay: Duration) {
+        self.backoff.base_delay = base_delay;
+        self.backoff.max_delay = max_delay;
+    }
+
+    /// Collapses consecutive plays of a track on repeat into a few scrobbles when flushing, or stops collapsing them
+    /// with `None`, the default. See [`RepeatPolicy`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// // Scrobble a track on repeat at most twice in any half hour
+    /// queue.set_repeat_policy(Some(RepeatPolicy {
+    ///     window: Duration::from_mins(30),
+    ///     max_kept: 2,
+    /// }));
+    /// ```
+    ///
+    /// [`RepeatPolicy`]: struct.RepeatPolicy.html
+    pub fn set_repeat_policy(&mut self, policy: Option<RepeatPolicy>) {
+        self.repeat_policy = policy;
+    }
+
+    /// Persists the `Scrobbler`'s estimated usage (see [`Scrobbler::estimated_usage`]) with the queue, so that the
+    /// requests of earlier runs still count towards it and its [`UsageThresholds`], or stops persisting it. Disabled
+    /// by default. Enabling it adds the usage persisted by the run which last saved the queue, if any.
+    ///
+    /// [`Scrobbler::estimated_usage`]: struct.Scrobbler.html#method.estimated_usage
+    /// [`UsageThresholds`]: struct.UsageThresholds.html
+    pub fn set_persist_usage(&mut self, persist_usage: bool) {
+        self.persist_usage = persist_usage;
+        if let Some(history) = self.persisted_usage.take().filter(|_| persist_usage) {
+            self.scrobbler.client().restore_usage(&history);
+        }
+    }
+
+    /// Sets what [`ScrobbleQueue::flush`] does when another handle of the same queue file, ie. one opened by a
+    /// shutdown hook, is already flushing it. Defaults to `ConcurrentFlush::Wait`. See [`ConcurrentFlush`].
+    ///
+    /// [`ScrobbleQueue::flush`]: struct.ScrobbleQueue.html#method.flush
+    /// [`ConcurrentFlush`]: enum.ConcurrentFlush.html
+    pub fn set_concurrent_flush(&mut self, concurrent_flush: ConcurrentFlush) {
+        self.concurrent_flush = concurrent_flush;
+    }
+
+    /// Keeps scrobbles submitted with [`ScrobbleQueue::scrobble`] from overtaking older ones still in the queue, or
+    /// stops doing so. Disabled by default, in which case [`ScrobbleQueue::scrobble`] always submits straight away.
+    ///
+    /// When enabled, a scrobble is only submitted straight away if the queue is empty. Otherwise it is pushed to
+    /// the queue, and submitted by a later flush after the older scrobbles, so that Last.fm receives plays in
+    /// chronological order. A flush of the queue file by another handle in the process is waited for first.
+    ///
+    /// [`ScrobbleQueue::scrobble`]: struct.ScrobbleQueue.html#method.scrobble
+    pub fn set_serialize_submissions(&mut self, serialize_submissions: bool) {
+        self.serialize_submissions = serialize_submissions;
+    }
+
+    /// Returns whether direct submissions wait their turn behind queued scrobbles. See
+    /// [`ScrobbleQueue::set_serialize_submissions`].
+    ///
+    /// [`ScrobbleQueue::set_serialize_submissions`]: struct.ScrobbleQueue.html#method.set_serialize_submissions
+    pub fn serialize_submissions(&self) -> bool {
+        self.serialize_submissions
+    }
+
+    /// Sets what flushes do with scrobbles which Last.fm ignores for the given reason, instead of retrying them.
+    /// Has no effect for `IgnoredReason::DailyScrobbleLimitExceeded`. See [`IgnoredPolicy`].
+    ///
+    /// [`IgnoredPolicy`]: enum.IgnoredPolicy.html
+    pub fn set_ignored_policy(&mut self, reason: IgnoredReason, policy: IgnoredPolicy) {
+        self.ignored_policies.insert(reason, policy);
+    }
+
+    /// Returns the scrobbles set aside because Last.fm ignored them, in the order they were set aside. They are
+    /// persisted with the queue, but never submitted. See [`IgnoredPolicy::DeadLetter`].
+    ///
+    /// [`IgnoredPolicy::DeadLetter`]: enum.IgnoredPolicy.html#variant.DeadLetter
+    pub fn dead_letters(&self) -> &[DeadLetter] {
+        &self.dead_letters
+    }
+
+    /// Removes every dead letter from the queue, ie. to fix and push them again, and persists the queue
+    pub fn take_dead_letters(&mut self) -> Result<Vec<DeadLetter>> {
+        let dead_letters = mem::take(&mut self.dead_letters);
+        self.save()?;
+        Ok(dead_letters)
+    }
+
+    /// Adds a scrobble to the queue, and persists the queue. A scrobble without a timestamp is stamped with the
+    /// current time, so that it is submitted as played now rather than when the queue is flushed.
+    pub fn push(&mut self, scrobble: &Scrobble) -> Result<()> {
+        let mut scrobble = scrobble.clone();
+        if scrobble.timestamp().is_none() {
+            scrobble.with_timestamp(UNIX_EPOCH.elapsed()?.as_secs());
+        }
+
+        // Keep the queue in chronological order; plays with the same timestamp stay in the order pushed
+        let index = self
+            .entries
+            .partition_point(|entry| entry.scrobble.timestamp() <= scrobble.timestamp());
+        self.entries.insert(
+            index,
+            QueueEntry {
+                scrobble,
+                failures: 0,
+                next_attempt_at: 0,
+                in_flight_until: None,
+                claimed: false,
+            },
+        );
+
+        self.save()
+    }
+
+    /// Submits a scrobble with the queue's `Scrobbler`, as [`Scrobbler::scrobble`] does, or queues it behind older
+    /// scrobbles if submissions are serialized (see [`ScrobbleQueue::set_serialize_submissions`]) and the queue
+    /// isn't empty. Returns `ScrobbleOutcome::Queued` if it was queued.
+    ///
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`ScrobbleQueue::set_serialize_submissions`]: struct.ScrobbleQueue.html#method.set_serialize_submissions
+    pub fn scrobble(&mut self, scrobble: &Scrobble) -> Result<ScrobbleOutcome> {
+        if !self.serialize_submissions {
+            return self.scrobbler.scrobble(scrobble).map(ScrobbleOutcome::Sent);
+        }
+
+        loop {
+            match flush::begin(&self.path) {
+                // Holding the guard, no flush can start until the scrobble has been submitted or queued
+                Begin::Started(_guard) => {
+                    // Another handle may have pushed to or flushed the file since this one last read it
+                    self.reload()?;
+                    if self.entries.is_empty() {
+                        return self.scrobbler.scrobble(scrobble).map(ScrobbleOutcome::Sent);
+                    }
+                    self.push(scrobble)?;
+                    return Ok(ScrobbleOutcome::Queued);
+                }
+                Begin::InProgress(flush) => {
+                    flush.wait();
+                }
+            }
+        }
+    }
+
+    /// Submits every queued scrobble which isn't backing off, oldest first. Accepted scrobbles are removed from the
+    /// queue; the others are rescheduled, or parked if the daily scrobble limit has been reached. Scrobbles too old
+    /// for Last.fm to accept are removed first. Scrobbles are submitted in batches of up to 50, and the queue is
+    /// persisted after each batch. If Last.fm rejects a whole batch, its scrobbles are submitted one by one instead,
+    /// so that one bad scrobble doesn't hold back the others; if the batch can't be sent at all, every scrobble in it
+    /// backs off.
+    ///
+    /// Failed submissions are reported in the returned [`FlushReport`] rather than as an error; an error is
+    /// returned only if the queue can't be persisted. Changes made to the scrobbles before they were submitted are
+    /// kept until the next flush, by [`ScrobbleQueue::last_preparation`].
+    ///
+    /// [`FlushReport`]: struct.FlushReport.html
+    /// [`ScrobbleQueue::last_preparation`]: struct.ScrobbleQueue.html#method.last_preparation
+    pub fn flush(&mut self) -> Result<FlushReport> {
+        self.flush_at(SystemTime::now())
+    }
+
+    // Flushes as if the current time were `now`
+    pub(crate) fn flush_at(&mut self, now: SystemTime) -> Result<FlushReport> {
+        loop {
+            match flush::begin(&self.path) {
+                Begin::Started(guard) => {
+                    let result = self.flush_exclusively_at(now);
+                    guard.finish(result.as_ref().ok().copied());
+                    return result;
+                }
+                Begin::InProgress(_) if self.concurrent_flush == ConcurrentFlush::Fail => {
+                    return Err(ScrobblerError::flush_in_progress(&self.path));
+                }
+                Begin::InProgress(flush) => {
+                    let report = flush.wait();
+                    // The other handle's flush changed the file, so this handle's entries are out of date
+                    self.reload()?;
+                    if let Some(report) = report {
+                        return Ok(report);
+                    }
+                }
+            }
+        }
+    }
+
+    // Flushes while holding the queue file's flush guard, claiming the entries it submits so that flushes in
+    // other processes leave them be. The file is reloaded first, as another handle may have flushed it since this
+    // one last read it.
+    fn flush_exclusively_at(&mut self, now: SystemTime) -> Result<FlushReport> {
+        self.reload()?;
+        let result = self.flush_claimed_at(now);
+        let released = self.release_claims();
+
+        let report = result?;
+        released?;
+        Ok(report)
+    }
+
+    fn flush_claimed_at(&mut self, now: SystemTime) -> Result<FlushReport> {
+        let flushed_at = now;
+        let now = now.duration_since(UNIX_EPOCH)?.as_secs();
+        let mut report = FlushReport::default();
+        self.last_preparation = PreparationReport::default();
+
+        if let Some(max_age) = self.scrobbler.max_scrobble_age() {
+            let oldest = now.saturating_sub(max_age.as_secs());
+            let queued = self.entries.len();
+            self.entries
+                .retain(|entry| entry.scrobble.timestamp().is_none_or(|timestamp| timestamp >= oldest));
+            report.expired = queued - self.entries.len();
+        }
+        if let Some(policy) = self.repeat_policy {
+            report.collapsed = self.collapse_repeats(policy);
+        }
+        let lease_until = now + IN_FLIGHT_LEASE.as_secs();
+        let mut claimed = 0;
+        for entry in &mut self.entries {
+            if entry.next_attempt_at <= now && entry.in_flight_until.is_none_or(|until| until <= now) {
+                entry.in_flight_until = Some(lease_until);
+                entry.claimed = true;
+                claimed += 1;
+            }
+        }
+        if report.expired + report.collapsed + claimed > 0 {
+            self.save()?;
+        }
+
+        // Claimed entries are submitted in batches, in queue order
+        report.deferred = self.entries.iter().filter(|entry| !entry.claimed).count();
+        let mut start = 0;
+        let mut removed = 0;
+        loop {
+            let chunk: Vec<usize> = (start..self.entries.len())
+                .filter(|&i| self.entries[i].claimed)
+                .take(MAX_CHUNK_SIZE)
+                .collect();
+            let Some(&last) = chunk.last() else {
+                break;
+            };
+            // Positions are those in the queue when the flush started
+            let positions: Vec<usize> = chunk.iter().map(|i| i + removed).collect();
+
+            let scrobbles: Vec<Scrobble> = chunk.iter().map(|&i| self.entries[i].scrobble.clone()).collect();
+            let batch = ScrobbleBatch::from(scrobbles);
+            let settled = match self.scrobbler.scrobble_batch_as_of(&batch, flushed_at) {
+                Ok(response) if response.scrobbles.len() == chunk.len() => {
+                    self.last_preparation.append(response.preparation.at_positions(&positions));
+                    let responses = response.scrobbles.into_iter().map(Ok);
+                    self.settle_chunk(&chunk, responses, flushed_at, &mut report)
+                }
+                Err(err) if matches!(err.kind(), ErrorKind::CircuitOpen | ErrorKind::UsageLimitReached) => {
+                    self.settle_chunk(&chunk, std::iter::once(Err(err.kind())), flushed_at, &mut report)
+                }
+                // The batch may have been refused for one scrobble's sake, so each is submitted on its own, lest a
+                // scrobble which always fails hold up the others
+                Err(err) if chunk.len() > 1 && !err.is_retryable() => {
+                    let mut results = Vec::with_capacity(chunk.len());
+                    for (&i, &position) in chunk.iter().zip(&positions) {
+                        let result = self.scrobbler.scrobble_reporting_as_of(&self.entries[i].scrobble, flushed_at);
+                        let result = result.map_err(|err| err.kind()).map(|(response, preparation)| {
+                            self.last_preparation.append(preparation.offset(position));
+                            response
+                        });
+                        let stop = match &result {
+                            Ok(response) => {
+                                response.ignored_reason() == Some(IgnoredReason::DailyScrobbleLimitExceeded)
+                            }
+                            Err(kind) => matches!(kind, ErrorKind::CircuitOpen | ErrorKind::UsageLimitReached),
+                        };
+                        results.push(result);
+                        if stop {
+                            break;
+                        }
+                    }
+                    self.settle_chunk(&chunk, results.into_iter(), flushed_at, &mut report)
+                }
+                // A failure of the whole batch, which backs off every scrobble in it
+                result => {
+                    let kind = result.map_or_else(|err| err.kind(), |_| ErrorKind::Decode);
+                    self.settle_chunk(&chunk, chunk.iter().map(|_| Err(kind)), flushed_at, &mut report)
+                }
+            };
+
+            match settled {
+                Settled::Submitted { removed: chunk_removed } => {
+                    removed += chunk_removed;
+                    start = last + 1 - chunk_removed;
+                    self.save()?;
+                }
+                Settled::Stopped => {
+                    self.save()?;
+                    break;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Applies the result of submitting each entry of a chunk, at the given positions in the queue. Stops once
+    // Last.fm ignores an entry because of the daily scrobble limit, parking it and every entry after it, or once
+    // the circuit breaker or usage thresholds refuse to send one, deferring it and every claimed entry after it.
+    fn settle_chunk(
+        &mut self,
+        chunk: &[usize],
+        results: impl Iterator<Item = result::Result<ScrobbleResponse, ErrorKind>>,
+        flushed_at: SystemTime,
+        report: &mut FlushReport,
+    ) -> Settled {
+        let now = flushed_at.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        let mut removed = 0;
+        let mut limited_at = None;
+        for (&position, result) in chunk.iter().zip(results) {
+            let i = position - removed;
+            match result {
+                Ok(response) if response.accepted() => {
+                    self.entries.remove(i);
+                    removed += 1;
+                    report.accepted += 1;
+                }
+                Ok(response) if response.ignored_reason() == Some(IgnoredReason::DailyScrobbleLimitExceeded) => {
+                    limited_at.get_or_insert(i);
+                }
+                // Last.fm is down, or the usage budget is spent: wait for the circuit to close or the usage to roll
+                // over, rather than backing off every scrobble
+                Err(ErrorKind::CircuitOpen | ErrorKind::UsageLimitReached) => {
+                    report.deferred += self.entries[i..].iter().filter(|entry| entry.claimed).count();
+                    return Settled::Stopped;
+                }
+                Ok(response) if self.handle_ignored(i, &response, flushed_at, report) => {
+                    removed += 1;
+                }
+                _ => {
+                    let entry = &mut self.entries[i];
+                    entry.failures += 1;
+                    entry.next_attempt_at = now + self.backoff.delay(entry.failures).as_secs();
+                    report.failed += 1;
+                }
+            }
+        }
+
+        let Some(first) = limited_at else {
+            return Settled::Submitted { removed };
+        };
+        let limit = DailyLimitReached::new(self.entries.len() - first, flushed_at);
+        let resume_at = limit.resume_at.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        for entry in &mut self.entries[first..] {
+            entry.next_attempt_at = entry.next_attempt_at.max(resume_at);
+        }
+        report.daily_limit = Some(limit);
+        Settled::Stopped
+    }
+
+    // Hands the entries this handle's flush claimed but didn't remove back to any flush
+    fn release_claims(&mut self) -> Result<()> {
+        let mut released = false;
+        for entry in self.entries.iter_mut().filter(|entry| entry.claimed) {
+            entry.in_flight_until = None;
+            entry.claimed = false;
+            released = true;
+        }
+
+        if released {
+            self.save()
+        } else {
+            Ok(())
+        }
+    }
+
+    // Removes the repeats beyond those the policy keeps, returning how many were removed
+    fn collapse_repeats(&mut self, policy: RepeatPolicy) -> usize {
+        let queued = self.entries.len();
+        // The track being repeated, when its current window started, and how many plays it has kept
+        let mut repeats: Option<(TrackKey, u64, usize)> = None;
+        self.entries.retain(|entry| {
+            let params = entry.scrobble.params();
+            let param = |name| params.get(name).unwrap_or_default();
+            let key = TrackKey::new(param("artist"), param("track"));
+            let timestamp = entry.scrobble.timestamp().unwrap_or_default();
+
+            match &mut repeats {
+                Some((track, started, kept))
+                    if *track == key && timestamp.saturating_sub(*started) < policy.window.as_secs() =>
+                {
+                    *kept += 1;
+                    *kept <= policy.max_kept.max(1)
+                }
+                _ => {
+                    repeats = Some((key, timestamp, 1));
+                    true
+                }
+            }
+        });
+
+        queued - self.entries.len()
+    }
+
+    // Applies the policy for the reason Last.fm ignored the scrobble at `i`. Returns true if it was removed from the
+    // queue, or false if it is to be retried as if it had failed.
+    fn handle_ignored(
+        &mut self,
+        i: usize,
+        response: &ScrobbleResponse,
+        flushed_at: SystemTime,
+        report: &mut FlushReport,
+    ) -> bool {
+        let Some(reason) = response.ignored_reason() else {
+            return false;
+        };
+        let policy = self.ignored_policies.get(&reason).cloned().unwrap_or_default();
+
+        match policy {
+            IgnoredPolicy::Retry => false,
+            IgnoredPolicy::Discard => {
+                self.entries.remove(i);
+                report.discarded += 1;
+                true
+            }
+            IgnoredPolicy::DeadLetter => {
+                self.dead_letter(i, reason, report);
+                true
+            }
+            IgnoredPolicy::RewriteAndRetry(rewrite) => {
+                let Some(rewritten) = rewrite(&self.entries[i].scrobble, reason) else {
+                    self.dead_letter(i, reason, report);
+                    return true;
+                };
+                let result = self.scrobbler.scrobble_reporting_as_of(&rewritten, flushed_at);
+                self.entries[i].scrobble = rewritten;
+
+                match result.map(|(response, _)| response.ignored_reason()) {
+                    Ok(None) => {
+                        self.entries.remove(i);
+                        report.accepted += 1;
+                        true
+                    }
+                    // The daily limit resets, and failures may pass, so the rewritten scrobble stays queued
+                    Ok(Some(IgnoredReason::DailyScrobbleLimitExceeded)) | Err(_) => false,
+                    Ok(Some(reason)) => {
+                        self.dead_letter(i, reason, report);
+                        true
+                    }
+                }
+            }
+        }
+    }
+
+    fn dead_letter(&mut self, i: usize, reason: IgnoredReason, report: &mut FlushReport) {
+        let entry = self.entries.remove(i);
+        self.dead_letters.push(DeadLetter {
+            scrobble: entry.scrobble,
+            reason,
+        });
+        report.dead_lettered += 1;
+    }
+
+    /// Returns the number of scrobbles in the queue
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no scrobbles in the queue
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reports whether scrobbling appears to work, as [`Scrobbler::health`] does, along with how many scrobbles are
+    /// waiting in the queue and how long ago the oldest of them was played. Nothing is sent.
+    ///
+    /// [`Scrobbler::health`]: struct.Scrobbler.html#method.health
+    pub fn health(&self) -> HealthReport {
+        self.health_at(SystemTime::now())
+    }
+
+    pub(crate) fn health_at(&self, now: SystemTime) -> HealthReport {
+        let mut report = self.scrobbler.client().health_at(now);
+        report.queue_depth = Some(self.entries.len());
+        // The queue is in chronological order
+        report.oldest_pending_age = self
+            .entries
+            .first()
+            .and_then(|entry| entry.scrobble.timestamp())
+            .map(|timestamp| now.duration_since(UNIX_EPOCH + Duration::from_secs(timestamp)).unwrap_or_default());
+        report
+    }
+
+    /// Returns the changes made to scrobbles before they were submitted by the most recent
+    /// [`ScrobbleQueue::flush`], ie. for display. Positions are those of the scrobbles in the queue when the flush
+    /// started. See [`PreparationReport`].
+    ///
+    /// [`ScrobbleQueue::flush`]: struct.ScrobbleQueue.html#method.flush
+    /// [`PreparationReport`]: struct.PreparationReport.html
+    pub fn last_preparation(&self) -> &PreparationReport {
+        &self.last_preparation
+    }
+
+    /// Returns the `Scrobbler` used to submit queued scrobbles, ie. to send Now Playing requests
+    pub fn scrobbler(&self) -> &Scrobbler {
+        &self.scrobbler
+    }
+
+    /// Applies a [`ScrobblerConfig`] to the `Scrobbler` used to submit queued scrobbles, ie. to leave dry-run mode
+    /// or move to another server, without reopening the queue. Queued scrobbles are kept. See
+    /// [`Scrobbler::apply_config`].
+    ///
+    /// [`ScrobblerConfig`]: struct.ScrobblerConfig.html
+    /// [`Scrobbler::apply_config`]: struct.Scrobbler.html#method.apply_config
+    pub fn apply_config(&mut self, config: &ScrobblerConfig) -> Result<()> {
+        self.scrobbler.apply_config(config)
+    }
+
+    /// Writes a backup of the queue to `path`, as a single versioned JSON document: every queued scrobble with its
+    /// retry schedule, the dead letters, the authenticated user's name, and the queue's backoff settings. Restore it
+    /// with [`ScrobbleQueue::import_state`].
+    ///
+    /// The `Scrobbler`'s session key is included only if `include_secrets` is set, as anyone holding it can scrobble
+    /// as the user. For a queue opened with [`ScrobbleQueue::open_encrypted`], the backup is encrypted with the queue's
+    /// key, and can only be imported by a queue holding the same key.
+    ///
+    /// [`ScrobbleQueue::import_state`]: struct.ScrobbleQueue.html#method.import_state
+    /// [`ScrobbleQueue::open_encrypted`]: struct.ScrobbleQueue.html#method.open_encrypted
+    pub fn export_state(&self, path: impl AsRef<Path>, include_secrets: bool) -> Result<()> {
+        let file = StateFile {
+            version: STATE_FORMAT_VERSION,
+            username: self.scrobbler.username().map(str::to_owned),
+            session_key: self
+                .scrobbler
+                .session_key()
+                .filter(|_| include_secrets)
+                .map(str::to_owned),
+            backoff_base_secs: self.backoff.base_delay.as_secs(),
+            backoff_max_secs: self.backoff.max_delay.as_secs(),
+            entries: self.entries.clone(),
+            dead_letters: self.dead_letters.clone(),
+        };
+        let path = path.as_ref();
+        let contents = serde_json::to_vec_pretty(&file).map_err(|err| err.to_string())?;
+        let contents = self.seal(path, contents)?;
+
+        write_atomically(path, &contents)
+    }
+
+    /// Restores a backup written by [`ScrobbleQueue::export_state`], then persists the queue. The imported
+    /// scrobbles and dead letters are merged with those already queued, or replace them, according to `mode`. Returns
+    /// the number of scrobbles added to the queue.
+    ///
+    /// The queue takes on the backup's backoff settings. If the backup holds a session key, the `Scrobbler` is
+    /// authenticated with it; otherwise its session is left as it was. Fails, changing nothing, if the backup is
+    /// invalid or of an unsupported version, or if the imported queue can't be persisted. An encrypted backup fails to
+    /// import with [`ErrorKind::WrongKey`] or [`ErrorKind::Tampered`], as [`ScrobbleQueue::open_encrypted`] does.
+    ///
+    /// [`ScrobbleQueue::export_state`]: struct.ScrobbleQueue.html#method.export_state
+    /// [`ErrorKind::WrongKey`]: enum.ErrorKind.html#variant.WrongKey
+    /// [`ErrorKind::Tampered`]: enum.ErrorKind.html#variant.Tampered
+    /// [`ScrobbleQueue::open_encrypted`]: struct.ScrobbleQueue.html#method.open_encrypted
+    pub fn import_state(&mut self, path: impl AsRef<Path>, mode: StateImportMode) -> Result<usize> {
+        let path = path.as_ref();
+        let contents = fs::read(path).map_err(failed(FileOperation::Read, path))?;
+        let contents = self.unseal(path, contents)?;
+        let file: StateFile = serde_json::from_slice(&contents)
+            .map_err(|err| ScrobblerError::new(format!("Invalid state backup {}: {err}", path.display())))?;
+        if file.version != STATE_FORMAT_VERSION {
+            return Err(ScrobblerError::new(format!(
+                "Unsupported state backup version {} in {}",
+                file.version,
+                path.display()
+            )));
+        }
+
+        // The imported state is built aside, and only taken on once it has been persisted
+        let mut scrobbler = self.scrobbler.clone();
+        if let Some(session_key) = &file.session_key {
+            scrobbler.authenticate_with_session_key(session_key)?;
+        }
+        if let Some(username) = &file.username {
+            scrobbler.set_username(username);
+        }
+
+        let (mut entries, mut dead_letters) = match mode {
+            StateImportMode::Merge => (self.entries.clone(), self.dead_letters.clone()),
+            StateImportMode::Replace => (Vec::new(), Vec::new()),
+        };
+        let mut added = 0;
+        for entry in file.entries {
+            if entries.iter().any(|queued| queued.scrobble == entry.scrobble) {
+                continue;
+            }
+            let index = entries.partition_point(|queued| queued.scrobble.timestamp() <= entry.scrobble.timestamp());
+            entries.insert(index, entry);
+            added += 1;
+        }
+        for letter in file.dead_letters {
+            if !dead_letters.contains(&letter) {
+                dead_letters.push(letter);
+            }
+        }
+
+        let previous = (
+            mem::replace(&mut self.entries, entries),
+            mem::replace(&mut self.dead_letters, dead_letters),
+        );
+        if let Err(err) = self.save() {
+            (self.entries, self.dead_letters) = previous;
+            return Err(err);
+        }
+        self.scrobbler = scrobbler;
+        self.set_backoff(
+            Duration::from_secs(file.backoff_base_secs),
+            Duration::from_secs(file.backoff_max_secs),
+        );
+
+        Ok(added)
+    }
+
+    fn save(&self) -> Result<()> {
+        let file = QueueFile {
+            version: FORMAT_VERSION,
+            entries: self.entries.clone(),
+            dead_letters: self.dead_letters.clone(),
+            usage: Some(self.scrobbler.client().usage_history()).filter(|_| self.persist_usage),
+            last_accepted: self.scrobbler.client().last_accepted(),
+        };
+        let contents = serde_json::to_vec(&file).map_err(|err| err.to_string())?;
+        let contents = self.seal(&self.path, contents)?;
+
+        write_atomically(&self.path, &contents)
+    }
+
+    // Encrypts the contents of the file at `path`, if the queue was opened with a key
+    #[allow(clippy::unnecessary_wraps, clippy::unused_self)]
+    #[cfg_attr(not(feature = "crypto"), allow(unused_variables))]
+    fn seal(&self, path: &Path, contents: Vec<u8>) -> Result<Vec<u8>> {
+        #[cfg(feature = "crypto")]
+        if let Some(key) = &self.key {
+            return crypto::seal(key, path, &contents);
+        }
+
+        Ok(contents)
+    }
+
+    // Decrypts the contents of the file at `path`, if the queue was opened with a key
+    #[allow(clippy::unnecessary_wraps, clippy::unused_self)]
+    #[cfg_attr(not(feature = "crypto"), allow(unused_variables))]
+    fn unseal(&self, path: &Path, contents: Vec<u8>) -> Result<Vec<u8>> {
+        #[cfg(feature = "crypto")]
+        if let Some(key) = &self.key {
+            return crypto::open(key, path, &contents);
+        }
+
+        Ok(contents)
+    }
+}
+
+// Writes the file to a temporary file, syncs it to disk, then moves it into place, so that a crash mid-write can't
+// leave a truncated file behind
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp_path = path.to_owned().into_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut tmp_file = File::create(&tmp_path).map_err(failed(FileOperation::Open, &tmp_path))?;
+    tmp_file
+        .write_all(contents)
+        .map_err(failed(FileOperation::Write, &tmp_path))?;
+    tmp_file.sync_all().map_err(failed(FileOperation::Fsync, &tmp_path))?;
+    fs::rename(&tmp_path, path).map_err(failed(FileOperation::Rename, path))
+}
+
+// Attaches the operation and path to a failed file operation, for use with `map_err`
+fn failed(operation: FileOperation, path: &Path) -> impl FnOnce(io::Error) -> ScrobblerError + '_ {
+    move |err| ScrobblerError::persistence(operation, path, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::MAX_SCROBBLE_AGE;
+    use crate::circuit::CircuitBreakerPolicy;
+    use crate::usage::{UsageLimits, UsageThresholds};
+    use crate::test_util::{EchoTransport, Fault, FaultyTransport};
+    use std::error::Error;
+    use std::thread;
+
+    const NOW: u64 = 1_500_000_000;
+
+    fn queue_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rustfm-queue-{}-{name}.json", std::process::id()))
+    }
+
+    fn open(path: &Path, echo: &EchoTransport) -> ScrobbleQueue {
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        ScrobbleQueue::open(path, scrobbler).unwrap()
+    }
+
+    fn scrobble(track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new("floyd", track, "old");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    fn at(offset: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(NOW + offset)
+    }
+
+    // The tracks submitted, in order, whether alone or in batches
+    fn submitted_tracks(echo: &EchoTransport) -> Vec<String> {
+        submitted(echo, "track")
+    }
+
+    // The values of `key` for each scrobble submitted, whether alone or in a batch
+    fn submitted(echo: &EchoTransport, key: &str) -> Vec<String> {
+        echo.requests()
+            .iter()
+            .flat_map(|request| {
+                let single = request.params.get(key).map(str::to_owned);
+                let batched = (0..).map_while(|i| request.params.get(&format!("{key}[{i}]")).map(str::to_owned));
+                single.into_iter().chain(batched).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_queue_persists_across_reopen() {
+        let path = queue_path("reopen");
+        let echo = EchoTransport::new();
+
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let mut queue = open(&path, &echo);
+        queue.push(&scrobble("second", now - 10)).unwrap();
+        queue.push(&scrobble("first", now - 20)).unwrap();
+        queue.push(&Scrobble::new("floyd", "now", "old")).unwrap();
+        drop(queue);
+
+        let mut queue = open(&path, &echo);
+        assert_eq!(queue.len(), 3);
+        assert!(echo.requests().is_empty());
+
+        let report = queue.flush().unwrap();
+        assert_eq!(report.accepted, 3);
+        assert!(queue.is_empty());
+        assert_eq!(submitted_tracks(&echo), vec!["first", "second", "now"]);
+        assert!(open(&path, &echo).is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_flushes_in_batches() {
+        let path = queue_path("batches");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        let tracks: Vec<String> = (0..120).map(|i| format!("track {i}")).collect();
+        for (i, track) in tracks.iter().enumerate() {
+            queue.push(&scrobble(track, NOW - 200 + i as u64)).unwrap();
+        }
+
+        // 50, 50 and then 20 scrobbles
+        assert_eq!(queue.flush_at(at(0)).unwrap().accepted, 120);
+        assert_eq!(echo.requests().len(), 3);
+        assert_eq!(submitted_tracks(&echo), tracks);
+        assert!(queue.is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_drops_expired_scrobbles() {
+        let path = queue_path("expired");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        let two_weeks = MAX_SCROBBLE_AGE.as_secs();
+        queue.push(&scrobble("stale", NOW - two_weeks - 1)).unwrap();
+        queue.push(&scrobble("oldest", NOW - two_weeks)).unwrap();
+        queue.push(&scrobble("fresh", NOW - 10)).unwrap();
+
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.expired), (2, 1));
+        assert_eq!(submitted_tracks(&echo), vec!["oldest", "fresh"]);
+        assert!(open(&path, &echo).is_empty());
+
+        // Dropped even while backing off, and the drop is persisted when nothing is submitted
+        let transport = FaultyTransport::new(echo.clone()).fail_request(0, Fault::HttpStatus(500));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let mut queue = ScrobbleQueue::open(&path, scrobbler).unwrap();
+        queue.push(&scrobble("unlucky", NOW - 10)).unwrap();
+        assert_eq!(queue.flush_at(at(0)).unwrap().failed, 1);
+        let report = queue.flush_at(at(two_weeks)).unwrap();
+        assert_eq!((report.expired, report.deferred), (1, 0));
+        assert!(open(&path, &echo).is_empty());
+
+        // The Scrobbler's maximum age applies, and without one nothing expires
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_max_scrobble_age(Some(Duration::from_mins(1)));
+        let mut queue = ScrobbleQueue::open(&path, scrobbler.clone()).unwrap();
+        queue.push(&scrobble("minutes old", NOW - 61)).unwrap();
+        queue.push(&scrobble("recent", NOW - 10)).unwrap();
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.expired), (1, 1));
+
+        scrobbler.set_max_scrobble_age(None);
+        let mut queue = ScrobbleQueue::open(&path, scrobbler).unwrap();
+        queue.push(&scrobble("ancient", NOW - 10 * two_weeks)).unwrap();
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.expired), (1, 0));
+        assert_eq!(submitted_tracks(&echo).last().map(String::as_str), Some("ancient"));
+        assert!(queue.is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_collapses_repeats() {
+        let flush = |name: &str, policy: Option<RepeatPolicy>, tracks: &[(&str, u64)]| {
+            let path = queue_path(name);
+            let echo = EchoTransport::new();
+            let mut queue = open(&path, &echo);
+            queue.set_repeat_policy(policy);
+            for (track, timestamp) in tracks {
+                queue.push(&scrobble(track, *timestamp)).unwrap();
+            }
+            let report = queue.flush_at(at(0)).unwrap();
+            assert!(queue.is_empty());
+            fs::remove_file(&path).unwrap();
+            (report, submitted_tracks(&echo))
+        };
+        let policy = |mins: u64, max_kept: usize| {
+            Some(RepeatPolicy {
+                window: Duration::from_mins(mins),
+                max_kept,
+            })
+        };
+        // Ten plays of the same track over half an hour, then another track
+        let mut repeats: Vec<(&str, u64)> = (0..10).map(|i| ("echoes", NOW - 1800 + i * 180)).collect();
+        repeats.push(("time", NOW - 100));
+
+        let (report, submitted) = flush("repeats-off", None, &repeats);
+        assert_eq!((report.accepted, report.collapsed), (11, 0));
+        assert_eq!(submitted.len(), 11);
+
+        let (report, submitted) = flush("repeats-30-1", policy(30, 1), &repeats);
+        assert_eq!((report.accepted, report.collapsed), (2, 9));
+        assert_eq!(submitted, vec!["echoes", "time"]);
+
+        // Plays at 0, 3, 6 and 9 minutes, then 12 to 21, then 24 and 27
+        let (report, _) = flush("repeats-10-1", policy(10, 1), &repeats);
+        assert_eq!((report.accepted, report.collapsed), (4, 7));
+        let (report, _) = flush("repeats-10-2", policy(10, 2), &repeats);
+        assert_eq!((report.accepted, report.collapsed), (7, 4));
+        let (report, _) = flush("repeats-30-3", policy(30, 3), &repeats);
+        assert_eq!((report.accepted, report.collapsed), (4, 7));
+
+        // Another track in between ends the repeats
+        let tracks = [("echoes", NOW - 300), ("echoes", NOW - 240), ("time", NOW - 180), ("echoes", NOW - 120)];
+        let (report, submitted) = flush("repeats-interleaved", policy(30, 1), &tracks);
+        assert_eq!(report.collapsed, 1);
+        assert_eq!(submitted, vec!["echoes", "time", "echoes"]);
+    }
+
+    #[test]
+    fn check_queue_keeps_last_preparation() {
+        let path = queue_path("preparation");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+
+        let mut blank_album = Scrobble::new("floyd", "second", "  ");
+        blank_album.with_timestamp(NOW - 10);
+        queue.push(&scrobble("first", NOW - 20)).unwrap();
+        queue.push(&blank_album).unwrap();
+        queue.push(&scrobble("third", NOW - 5)).unwrap();
+        assert!(queue.last_preparation().is_empty());
+
+        assert_eq!(queue.flush_at(at(0)).unwrap().accepted, 3);
+        let adjustments = queue.last_preparation().adjustments();
+        assert_eq!(adjustments.len(), 1);
+        assert_eq!((adjustments[0].position, adjustments[0].field.as_str()), (1, "album"));
+        assert_eq!(adjustments[0].old_value.as_deref(), Some("  "));
+        assert_eq!(adjustments[0].new_value, None);
+
+        // Each flush replaces the report
+        queue.push(&scrobble("fourth", NOW)).unwrap();
+        queue.flush_at(at(0)).unwrap();
+        assert!(queue.last_preparation().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_backs_off_failing_entries() {
+        let path = queue_path("backoff");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        queue.set_backoff(Duration::from_secs(10), Duration::from_secs(25));
+
+        // Too long a name, so it fails every time without reaching the transport
+        queue.push(&scrobble(&"bad".repeat(400), NOW - 200)).unwrap();
+        queue.push(&scrobble("good 1", NOW - 100)).unwrap();
+
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (1, 1, 0));
+
+        // Fresh entries keep flowing while the bad one waits out its backoff
+        queue.push(&scrobble("good 2", NOW - 50)).unwrap();
+        let report = queue.flush_at(at(9)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (1, 0, 1));
+
+        // Retried once the backoff expires, then waits twice as long...
+        let report = queue.flush_at(at(10)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (0, 1, 0));
+        assert_eq!(queue.flush_at(at(29)).unwrap().deferred, 1);
+
+        // ...and the schedule survives a restart
+        drop(queue);
+        let mut queue = open(&path, &echo);
+        queue.set_backoff(Duration::from_secs(10), Duration::from_secs(25));
+        assert_eq!(queue.flush_at(at(29)).unwrap().deferred, 1);
+        assert_eq!(queue.flush_at(at(30)).unwrap().failed, 1);
+
+        // Capped at the maximum backoff
+        assert_eq!(queue.flush_at(at(54)).unwrap().deferred, 1);
+        assert_eq!(queue.flush_at(at(55)).unwrap().failed, 1);
+
+        assert_eq!(queue.entries[0].failures, 4);
+        assert_eq!(submitted_tracks(&echo), vec!["good 1", "good 2"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_ignored_policies() {
+        let path = queue_path("ignored");
+        let echo = EchoTransport::new().with_ignored_artist("Unknown Artist");
+        let mut queue = open(&path, &echo);
+        let unknown = |track: &str, timestamp: u64, album_artist: &str| {
+            let mut scrobble = scrobble(track, timestamp);
+            scrobble.with_artists(&["Unknown Artist"]).with_album_artist(album_artist);
+            scrobble
+        };
+
+        // Retried by default
+        queue.push(&unknown("retried", NOW - 100, "")).unwrap();
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.failed), (0, 1));
+        assert_eq!(queue.len(), 1);
+
+        queue.set_ignored_policy(IgnoredReason::ArtistIgnored, IgnoredPolicy::Discard);
+        let report = queue.flush_at(at(60)).unwrap();
+        assert_eq!((report.failed, report.discarded), (0, 1));
+        assert!(queue.is_empty());
+
+        queue.set_ignored_policy(IgnoredReason::ArtistIgnored, IgnoredPolicy::DeadLetter);
+        queue.push(&unknown("dead", NOW - 90, "floyd")).unwrap();
+        let report = queue.flush_at(at(60)).unwrap();
+        assert_eq!((report.failed, report.dead_lettered), (0, 1));
+        assert!(queue.is_empty());
+        assert_eq!(queue.dead_letters()[0].reason, IgnoredReason::ArtistIgnored);
+
+        // Retried as the album artist, which is accepted, or dead-lettered without one
+        let rewrite = IgnoredPolicy::rewrite_and_retry(|scrobble, _| {
+            let mut rewritten = scrobble.clone();
+            rewritten.with_artists(&[scrobble.album_artist()]);
+            Some(rewritten).filter(|_| !scrobble.album_artist().is_empty())
+        });
+        queue.set_ignored_policy(IgnoredReason::ArtistIgnored, rewrite);
+        queue.push(&unknown("rewritten", NOW - 80, "floyd")).unwrap();
+        queue.push(&unknown("no album artist", NOW - 70, "")).unwrap();
+        let report = queue.flush_at(at(60)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.dead_lettered), (1, 0, 1));
+        assert!(queue.is_empty());
+        let artists = submitted(&echo, "artist");
+        assert_eq!(submitted_tracks(&echo)[3..], ["rewritten", "no album artist", "rewritten"]);
+        assert_eq!(artists[3..], ["Unknown Artist", "Unknown Artist", "floyd"]);
+
+        // Dead letters are persisted until taken
+        drop(queue);
+        let mut queue = open(&path, &echo);
+        let dead: Vec<String> = queue
+            .take_dead_letters()
+            .unwrap()
+            .iter()
+            .map(|letter| letter.scrobble.track().to_owned())
+            .collect();
+        assert_eq!(dead, vec!["dead", "no album artist"]);
+        assert!(open(&path, &echo).dead_letters().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_waits_for_open_circuit() {
+        let path = queue_path("circuit");
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone()).fail_request(0, Fault::HttpStatus(503));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_circuit_breaker(Some(CircuitBreakerPolicy {
+            failure_threshold: 1,
+            cooldown: Duration::from_mins(1),
+        }));
+        let mut queue = ScrobbleQueue::open(&path, scrobbler).unwrap();
+        for (i, track) in ["first", "second", "third"].iter().enumerate() {
+            queue.push(&scrobble(track, NOW - 30 + i as u64)).unwrap();
+        }
+
+        // The batch which opened the circuit is backed off, and later scrobbles wait for it to close
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (0, 3, 0));
+        queue.push(&scrobble("fourth", NOW - 10)).unwrap();
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (0, 0, 4));
+        assert_eq!(queue.entries.iter().map(|entry| entry.failures).collect::<Vec<_>>(), vec![1, 1, 1, 0]);
+        assert!(echo.requests().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_concurrent_flushes() {
+        let path = queue_path("concurrent");
+        let echo = EchoTransport::new();
+        let slow = (0..4).map(|_| Fault::Delay(Duration::from_millis(20)));
+        let transport = FaultyTransport::new(echo.clone()).fail_method("track.scrobble", slow);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let open_slow = || ScrobbleQueue::open(&path, scrobbler.clone()).unwrap();
+        let mut queue = open_slow();
+        for (i, track) in ["first", "second", "third"].iter().enumerate() {
+            queue.push(&scrobble(track, NOW - 30 + i as u64)).unwrap();
+        }
+
+        // Two handles of the file, ie. the worker's and a shutdown hook's, flush at once
+        let flushers: Vec<_> = vec![queue, open_slow()]
+            .into_iter()
+            .map(|mut queue| thread::spawn(move || (queue.flush_at(at(0)).unwrap(), queue.len())))
+            .collect();
+        let results: Vec<(FlushReport, usize)> = flushers.into_iter().map(|flusher| flusher.join().unwrap()).collect();
+        assert_eq!(submitted_tracks(&echo), vec!["first", "second", "third"]);
+        // The waiting handle returns the report of the flush it waited for, and reloads the emptied queue
+        assert_eq!(results[0].0, results[1].0);
+        assert_eq!((results[0].0.accepted, results[0].1, results[1].1), (3, 0, 0));
+
+        // Optionally the second flush fails instead of waiting
+        let mut queue = open_slow();
+        queue.push(&scrobble("fourth", NOW - 10)).unwrap();
+        let flusher = thread::spawn(move || queue.flush_at(at(0)).unwrap());
+        thread::sleep(Duration::from_millis(5));
+        let mut impatient = open_slow();
+        impatient.set_concurrent_flush(ConcurrentFlush::Fail);
+        let err = impatient.flush_at(at(0)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::FlushInProgress);
+        assert!(err.is_retryable());
+        assert_eq!(flusher.join().unwrap().accepted, 1);
+        assert_eq!(echo.requests().len(), 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_serializes_submissions() {
+        let path = queue_path("serialize");
+        let echo = EchoTransport::new();
+        let slow = (0..3).map(|_| Fault::Delay(Duration::from_millis(20)));
+        let transport = FaultyTransport::new(echo.clone()).fail_method("track.scrobble", slow);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let mut worker = ScrobbleQueue::open(&path, scrobbler.clone()).unwrap();
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+        for (i, track) in ["first", "second", "third"].iter().enumerate() {
+            worker.push(&scrobble(track, now - 30 + i as u64)).unwrap();
+        }
+
+        // A direct scrobble made while the worker drains the backlog waits for it, rather than overtaking it
+        let mut app = ScrobbleQueue::open(&path, scrobbler).unwrap();
+        app.set_serialize_submissions(true);
+        assert!(app.serialize_submissions());
+        let flusher = thread::spawn(move || (worker.flush().unwrap(), worker));
+        thread::sleep(Duration::from_millis(5));
+        let outcome = app.scrobble(&scrobble("fourth", now - 10)).unwrap();
+        assert!(matches!(outcome, ScrobbleOutcome::Sent(response) if response.accepted()));
+        let (report, mut worker) = flusher.join().unwrap();
+        assert_eq!(report.accepted, 3);
+        assert_eq!(submitted_tracks(&echo), vec!["first", "second", "third", "fourth"]);
+
+        // A scrobble still backing off in the queue holds back later ones, which are queued behind it
+        worker.set_backoff(Duration::from_secs(1), Duration::from_secs(1));
+        worker.push(&scrobble(&"bad".repeat(400), now - 5)).unwrap();
+        assert_eq!(worker.flush().unwrap().failed, 1);
+        let outcome = app.scrobble(&scrobble("fifth", now)).unwrap();
+        assert!(matches!(outcome, ScrobbleOutcome::Queued));
+        assert_eq!(app.len(), 2);
+        assert_eq!(app.entries[1].scrobble.track(), "fifth");
+        assert_eq!(echo.requests().len(), 2);
+
+        // Unless submissions aren't serialized
+        app.set_serialize_submissions(false);
+        assert!(matches!(app.scrobble(&scrobble("sixth", now)).unwrap(), ScrobbleOutcome::Sent(_)));
+        assert_eq!(submitted_tracks(&echo)[4], "sixth");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_leaves_scrobbles_in_flight() {
+        let path = queue_path("in-flight");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        queue.push(&scrobble("first", NOW - 30)).unwrap();
+        queue.push(&scrobble("second", NOW - 20)).unwrap();
+
+        // As a flush in another process would have left the file mid-flush
+        queue.entries[0].in_flight_until = Some(NOW + 600);
+        queue.save().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("\"in_flight_until\":1500000600"));
+
+        let mut queue = open(&path, &echo);
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.deferred), (1, 1));
+        assert_eq!(submitted_tracks(&echo), vec!["second"]);
+        assert!(fs::read_to_string(&path).unwrap().contains("\"in_flight_until\":1500000600"));
+
+        // Once the lease runs out, the scrobble is submitted after all
+        assert_eq!(queue.flush_at(at(599)).unwrap().deferred, 1);
+        assert_eq!(queue.flush_at(at(600)).unwrap().accepted, 1);
+        assert_eq!(submitted_tracks(&echo), vec!["second", "first"]);
+        assert!(queue.is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_usage() {
+        let path = queue_path("usage");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        queue.set_persist_usage(true);
+        queue.scrobbler.set_usage_thresholds(Some(UsageThresholds {
+            hard: UsageLimits { per_day: Some(1), ..UsageLimits::default() },
+            ..UsageThresholds::default()
+        }));
+        for (i, track) in ["first", "second", "third"].iter().enumerate() {
+            queue.push(&scrobble(track, NOW - 30 + i as u64)).unwrap();
+        }
+
+        // Flushing pauses once the hard threshold is reached, without backing anything off
+        assert_eq!(queue.flush_at(at(0)).unwrap().accepted, 3);
+        queue.push(&scrobble("fourth", NOW - 10)).unwrap();
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (0, 0, 1));
+        assert_eq!(queue.entries[0].failures, 0);
+        assert_eq!(echo.requests().len(), 1);
+
+        // The next run counts the requests sent by this one, once it asks to
+        let mut reopened = open(&path, &echo);
+        assert_eq!(reopened.scrobbler().estimated_usage().last_day, 0);
+        reopened.set_persist_usage(true);
+        reopened.set_persist_usage(true);
+        assert_eq!(reopened.scrobbler().estimated_usage().last_day, 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_persists_last_accepted_scrobble() {
+        let path = queue_path("last-accepted");
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        queue.push(&scrobble("first", NOW - 20)).unwrap();
+        queue.push(&scrobble("second", NOW - 10)).unwrap();
+        queue.flush_at(at(0)).unwrap();
+        let last = queue.scrobbler().last_accepted_scrobble().unwrap();
+        assert_eq!((last.track(), last.timestamp()), ("second", NOW - 10));
+
+        // Restored by the next run, with a fresh `Scrobbler`
+        let reopened = open(&path, &echo);
+        assert_eq!(reopened.scrobbler().last_accepted_scrobble(), Some(last));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_queue_state_round_trip() {
+        let (path, backup) = (queue_path("state"), queue_path("state-backup"));
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        queue.scrobbler.set_username("floyd");
+        queue.set_backoff(Duration::from_secs(5), Duration::from_mins(1));
+        // Too long a name, so it fails and is backed off
+        queue.push(&scrobble(&"bad".repeat(400), NOW - 200)).unwrap();
+        queue.push(&scrobble("good", NOW - 100)).unwrap();
+        assert_eq!(queue.flush_at(at(0)).unwrap().failed, 1);
+        queue.push(&scrobble("pending", NOW)).unwrap();
+        let dead = DeadLetter { scrobble: scrobble("dead", NOW - 50), reason: IgnoredReason::ArtistIgnored };
+        queue.dead_letters.push(dead.clone());
+        queue.export_state(&backup, true).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Restored on another machine, with nothing queued
+        let other_path = queue_path("state-other");
+        let mut other = ScrobbleQueue::open(&other_path, Scrobbler::new("api_key", "api_secret")).unwrap();
+        assert_eq!(other.import_state(&backup, StateImportMode::Replace).unwrap(), 2);
+        assert_eq!(other.entries, queue.entries);
+        assert_eq!(other.dead_letters(), std::slice::from_ref(&dead));
+        assert_eq!(other.scrobbler().session_key(), Some("seshkey"));
+        assert_eq!(other.scrobbler().username(), Some("floyd"));
+        assert_eq!(other.backoff, queue.backoff);
+        // ...and persisted there
+        let reopened = ScrobbleQueue::open(&other_path, Scrobbler::new("api_key", "api_secret")).unwrap();
+        assert_eq!(reopened.entries, queue.entries);
+        assert_eq!(reopened.dead_letters(), std::slice::from_ref(&dead));
+
+        // Without secrets, the session is left alone
+        queue.export_state(&backup, false).unwrap();
+        assert!(!fs::read_to_string(&backup).unwrap().contains("seshkey"));
+        let mut other = reopened;
+        other.import_state(&backup, StateImportMode::Replace).unwrap();
+        assert_eq!(other.scrobbler().session_key(), None);
+        assert_eq!(other.entries, queue.entries);
+
+        fs::write(&backup, r#"{"version":2,"backoff_base_secs":1,"backoff_max_secs":1,"entries":[]}"#).unwrap();
+        let err = other.import_state(&backup, StateImportMode::Replace).unwrap_err();
+        assert!(err.to_string().starts_with("Unsupported state backup version 2"), "{}", err);
+        fs::write(&backup, "{}").unwrap();
+        assert!(other.import_state(&backup, StateImportMode::Merge).is_err());
+        assert_eq!(other.len(), 2);
+
+        // Nor is anything changed if the imported queue can't be persisted
+        queue.scrobbler.set_username("other");
+        queue.set_backoff(Duration::from_secs(1), Duration::from_secs(1));
+        queue.push(&scrobble("newer", NOW)).unwrap();
+        queue.export_state(&backup, true).unwrap();
+        let before = other.entries.clone();
+        let other_path = mem::replace(&mut other.path, queue_path("state-missing").join("queue.json"));
+        let err = other.import_state(&backup, StateImportMode::Replace).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Persistence);
+        assert_eq!(other.entries, before);
+        assert_eq!((other.scrobbler().session_key(), other.scrobbler().username()), (None, Some("floyd")));
+        assert_eq!(other.backoff.base_delay, Duration::from_secs(5));
+        other.path = other_path.clone();
+
+        for path in &[path, other_path, backup] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn check_queue_state_import_merges() {
+        let (path, backup) = (queue_path("merge"), queue_path("merge-backup"));
+        let echo = EchoTransport::new();
+        let mut queue = open(&path, &echo);
+        queue.push(&scrobble("second", NOW - 20)).unwrap();
+        queue.push(&scrobble("shared", NOW - 10)).unwrap();
+        let letter = |track| DeadLetter { scrobble: scrobble(track, NOW - 40), reason: IgnoredReason::TrackIgnored };
+        queue.dead_letters = vec![letter("imported"), letter("shared")];
+        queue.export_state(&backup, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut other = open(&path, &echo);
+        other.dead_letters = vec![letter("shared"), letter("kept")];
+        other.push(&scrobble("first", NOW - 30)).unwrap();
+        other.push(&scrobble("shared", NOW - 10)).unwrap();
+        other.push(&scrobble("last", NOW)).unwrap();
+        assert_eq!(other.import_state(&backup, StateImportMode::Merge).unwrap(), 1);
+        assert_eq!(other.len(), 4);
+        assert_eq!(other.dead_letters(), [letter("shared"), letter("kept"), letter("imported")]);
+        // Authenticated before the import, and still is
+        assert_eq!(other.scrobbler().session_key(), Some("seshkey"));
+
+        assert_eq!(other.flush_at(at(0)).unwrap().accepted, 4);
+        assert_eq!(submitted_tracks(&echo), vec!["first", "second", "shared", "last"]);
+
+        for path in &[path, backup] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    fn assert_persistence_error(err: &ScrobblerError, operation: FileOperation, path: &Path, kind: io::ErrorKind) {
+        assert_eq!(err.kind(), ErrorKind::Persistence);
+        assert_eq!(err.file_operation(), Some(operation));
+        assert_eq!(err.path(), Some(path));
+        assert!(err.to_string().contains(&path.display().to_string()), "{}", err);
+
+        let source = err.source().and_then(|source| source.downcast_ref::<io::Error>());
+        assert_eq!(source.map(io::Error::kind), Some(kind));
+    }
+
+    #[test]
+    fn check_queue_parks_at_daily_limit() {
+        let path = queue_path("daily-limit");
+        let echo = EchoTransport::new().with_daily_limit(1);
+        let mut queue = open(&path, &echo);
+        for (i, track) in ["first", "second", "third"].iter().enumerate() {
+            queue.push(&scrobble(track, NOW - 100 + i as u64)).unwrap();
+        }
+
+        // 1,500,000,000 is 02:40 UTC, so the limit is expected to reset 76,800 seconds later
+        let report = queue.flush_at(at(0)).unwrap();
+        assert_eq!((report.accepted, report.failed, report.deferred), (1, 0, 0));
+        let limit = report.daily_limit.unwrap();
+        assert_eq!(limit.remaining, 2);
+        assert_eq!(limit.resume_at, at(76_800));
+        assert_eq!(submitted_tracks(&echo), vec!["first", "second", "third"]);
+
+        // Parked until then, without counting as failures
+        assert!(queue.entries.iter().all(|entry| entry.failures == 0 && entry.next_attempt_at == NOW + 76_800));
+        let report = queue.flush_at(at(76_799)).unwrap();
+        assert_eq!((report.accepted, report.deferred, report.daily_limit), (0, 2, None));
+        assert_eq!(echo.requests().len(), 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn check_encrypted_queue() {
+        let path = queue_path("encrypted");
+        let echo = EchoTransport::new();
+        let scrobbler = open(&path, &echo).scrobbler().clone();
+        let key = || EncryptionKey::new([42; 32]);
+
+        let mut queue = ScrobbleQueue::open_encrypted(&path, scrobbler.clone(), key()).unwrap();
+        queue.push(&scrobble("secret track", NOW)).unwrap();
+        let contents = fs::read(&path).unwrap();
+        assert!(contents.starts_with(b"RFSE"));
+        assert!(!String::from_utf8_lossy(&contents).contains("secret track"));
+
+        let queue = ScrobbleQueue::open_encrypted(&path, scrobbler.clone(), key()).unwrap();
+        assert_eq!(queue.entries[0].scrobble.track(), "secret track");
+
+        let wrong_key = EncryptionKey::new([43; 32]);
+        let err = ScrobbleQueue::open_encrypted(&path, scrobbler.clone(), wrong_key).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::WrongKey);
+        assert_eq!(err.path(), Some(path.as_path()));
+        // An encrypted queue can't be read as plaintext, nor a plaintext one as encrypted
+        assert!(ScrobbleQueue::open(&path, scrobbler.clone()).is_err());
+
+        let mut tampered = contents.clone();
+        let last = tampered.len() - 20;
+        tampered[last] ^= 0x80;
+        fs::write(&path, &tampered).unwrap();
+        let err = ScrobbleQueue::open_encrypted(&path, scrobbler.clone(), key()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Tampered);
+
+        fs::write(&path, br#"{"version":1,"entries":[]}"#).unwrap();
+        let err = ScrobbleQueue::open_encrypted(&path, scrobbler, key()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Tampered);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn check_encrypted_state_backup() {
+        let path = queue_path("encrypted-state");
+        let backup = queue_path("encrypted-state-backup");
+        let echo = EchoTransport::new();
+        let scrobbler = open(&path, &echo).scrobbler().clone();
+        let key = || EncryptionKey::new([42; 32]);
+
+        let mut queue = ScrobbleQueue::open_encrypted(&path, scrobbler.clone(), key()).unwrap();
+        queue.push(&scrobble("secret track", NOW)).unwrap();
+        queue.export_state(&backup, true).unwrap();
+        let contents = fs::read(&backup).unwrap();
+        assert!(contents.starts_with(b"RFSE"));
+        assert!(!String::from_utf8_lossy(&contents).contains("secret track"));
+        assert!(!String::from_utf8_lossy(&contents).contains("seshkey"));
+        fs::remove_file(&path).unwrap();
+
+        let mut other = ScrobbleQueue::open_encrypted(&path, scrobbler.clone(), key()).unwrap();
+        assert_eq!(other.import_state(&backup, StateImportMode::Replace).unwrap(), 1);
+        assert_eq!(other.entries, queue.entries);
+
+        // Tampering is detected, and changes nothing
+        let mut tampered = contents.clone();
+        let last = tampered.len() - 20;
+        tampered[last] ^= 0x80;
+        fs::write(&backup, &tampered).unwrap();
+        let err = other.import_state(&backup, StateImportMode::Replace).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Tampered);
+        assert_eq!(err.path(), Some(backup.as_path()));
+        assert_eq!(other.entries, queue.entries);
+
+        // Nor can it be imported without the key, or with another one
+        fs::write(&backup, &contents).unwrap();
+        let wrong_key = EncryptionKey::new([43; 32]);
+        let mut other = ScrobbleQueue::open_encrypted(queue_path("encrypted-state-other"), scrobbler.clone(), wrong_key)
+            .unwrap();
+        let err = other.import_state(&backup, StateImportMode::Merge).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WrongKey);
+        let mut plain = ScrobbleQueue::open(queue_path("encrypted-state-plain"), scrobbler).unwrap();
+        assert!(plain.import_state(&backup, StateImportMode::Merge).is_err());
+        assert!(plain.is_empty());
+
+        for path in &[path, backup] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn check_queue_missing_directory() {
+        let path = queue_path("missing").join("queue.json");
+        let mut queue = open(&path, &EchoTransport::new());
+
+        let err = queue.push(&scrobble("lost", NOW)).unwrap_err();
+        assert_persistence_error(&err, FileOperation::Open, &path.with_extension("json.tmp"), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_queue_permission_denied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = queue_path("readonly");
+        fs::create_dir_all(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        // Permissions aren't enforced for root, but nobody may create files in /sys
+        let path = if fs::write(dir.join("probe"), "").is_ok() {
+            PathBuf::from("/sys/rustfm-queue.json")
+        } else {
+            dir.join("queue.json")
+        };
+
+        let mut queue = open(&path, &EchoTransport::new());
+        let err = queue.push(&scrobble("denied", NOW)).unwrap_err();
+        assert_persistence_error(
+            &err,
+            FileOperation::Open,
+            &path.with_extension("json.tmp"),
+            io::ErrorKind::PermissionDenied,
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+// Client-side rate limiting of API requests, shared by every clone of a Scrobbler
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A client-side limit on the rate of API requests: at most `requests` every `per`, on average.
+///
+/// Limits are enforced with a token bucket, so up to `requests` requests can be sent in a burst after a quiet
+/// period; after that, requests are spaced out evenly. A request which would exceed the limit waits on the calling
+/// thread until it is within the limit. Every attempt counts, including retries.
+///
+/// Waiting requests are sent in the order they were made, except for now playing updates: they're independent of
+/// the order of scrobbles, so go ahead of any waiting scrobbles (ie. the chunks of a large import), while still
+/// counting towards the limit.
+///
+/// A limit of zero requests, or over a zero period, is treated as no limit.
+///
+/// # Usage
+/// ```ignore
+/// scrobbler.set_rate_limit(Some(RateLimit::last_fm()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Last.fm's documented limit of 5 requests per second, per API key
+    pub fn last_fm() -> Self {
+        Self {
+            requests: 5,
+            per: Duration::from_secs(1),
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.requests == 0 || self.per == Duration::from_secs(0)
+    }
+}
+
+// Which queue a request waits in for the rate limit. Priority requests, ie. now playing updates, which don't need
+// to keep their order with scrobbles, go ahead of every other waiting request, but still take a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Lane {
+    Priority,
+    Normal,
+}
+
+// A request's place in the queue for a token
+#[derive(Debug)]
+pub(crate) struct Ticket {
+    id: u64,
+    lane: Lane,
+}
+
+// A token bucket shared by all the handles to one logical client, handing out tokens to waiting requests in turn:
+// those in the priority lane first, then the others, each in the order they arrived
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    state: Mutex<State>,
+    // Signalled whenever a token is taken or the limit changes, so that the next in turn checks again
+    turn: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    bucket: Option<Bucket>,
+    priority: VecDeque<u64>,
+    normal: VecDeque<u64>,
+    next_ticket: u64,
+}
+
+impl State {
+    fn lane(&mut self, lane: Lane) -> &mut VecDeque<u64> {
+        match lane {
+            Lane::Priority => &mut self.priority,
+            Lane::Normal => &mut self.normal,
+        }
+    }
+
+    // How many requests are ahead of the ticket's, or `None` if it isn't waiting
+    fn position(&self, ticket: &Ticket) -> Option<usize> {
+        match ticket.lane {
+            Lane::Priority => self.priority.iter().position(|id| *id == ticket.id),
+            Lane::Normal => self
+                .normal
+                .iter()
+                .position(|id| *id == ticket.id)
+                .map(|position| self.priority.len() + position),
+        }
+    }
+
+    fn waiting(&self) -> usize {
+        self.priority.len() + self.normal.len()
+    }
+
+    // Takes a token for the ticket if it's its turn, or returns how long it should wait before checking again
+    #[allow(clippy::cast_precision_loss)]
+    fn take_at(&mut self, ticket: &Ticket, now: Instant) -> Result<(), Duration> {
+        let Some(position) = self.position(ticket) else {
+            return Ok(());
+        };
+        let Some(bucket) = self.bucket.as_mut() else {
+            self.lane(ticket.lane).retain(|id| *id != ticket.id);
+            return Ok(());
+        };
+
+        bucket.refill(now);
+        if position == 0 && bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            self.lane(ticket.lane).pop_front();
+            return Ok(());
+        }
+
+        // Roughly when the ticket's turn comes, unless a request ahead of it is taken sooner
+        let needed = position as f64 + 1.0 - bucket.tokens;
+        Err(Duration::from_secs_f64(needed.max(0.0) / bucket.rate()))
+    }
+}
+
+// A ticket waiting for its turn asynchronously, which leaves the queue when the wait ends, however it ends
+#[cfg(feature = "async")]
+struct Waiting<'a> {
+    limiter: &'a RateLimiter,
+    ticket: Ticket,
+}
+
+#[cfg(feature = "async")]
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let mut state = self.limiter.lock();
+        let id = self.ticket.id;
+        state.lane(self.ticket.lane).retain(|waiting| *waiting != id);
+        drop(state);
+        self.limiter.turn.notify_all();
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn capacity(&self) -> f64 {
+        f64::from(self.limit.requests)
+    }
+
+    // Tokens refilled per second
+    fn rate(&self) -> f64 {
+        self.capacity() / self.limit.per.as_secs_f64()
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate()).min(self.capacity());
+        self.updated = self.updated.max(now);
+    }
+}
+
+impl RateLimiter {
+    pub(crate) fn set_limit(&self, limit: Option<RateLimit>) {
+        self.lock().bucket = limit.filter(|limit| !limit.is_unlimited()).map(|limit| Bucket {
+            limit,
+            tokens: f64::from(limit.requests),
+            updated: Instant::now(),
+        });
+        self.turn.notify_all();
+    }
+
+    pub(crate) fn limit(&self) -> Option<RateLimit> {
+        self.lock().bucket.as_ref().map(|bucket| bucket.limit)
+    }
+
+    // Waits until a request in the given lane can be sent within the limit
+    pub(crate) fn acquire(&self, lane: Lane) {
+        let ticket = self.enqueue(lane);
+        let mut state = self.lock();
+        while let Err(wait) = state.take_at(&ticket, Instant::now()) {
+            state = self.turn.wait_timeout(state, wait).unwrap_or_else(PoisonError::into_inner).0;
+        }
+        drop(state);
+        self.turn.notify_all();
+    }
+
+    // Waits as `acquire` does, but without blocking the thread. A wait which is given up on, by dropping the future,
+    // leaves the queue.
+    #[cfg(feature = "async")]
+    pub(crate) async fn acquire_async(&self, lane: Lane) {
+        let waiting = Waiting {
+            limiter: self,
+            ticket: self.enqueue(lane),
+        };
+        loop {
+            let taken = self.lock().take_at(&waiting.ticket, Instant::now());
+            match taken {
+                Ok(()) => return,
+                Err(wait) => crate::async_transport::sleep(wait).await,
+            }
+        }
+    }
+
+    // The fraction of the budget in use: 0.0 when a full burst could be sent, 1.0 when the next request would wait,
+    // and more than 1.0 when callers are already waiting
+    pub(crate) fn utilization(&self) -> Option<f64> {
+        self.utilization_at(Instant::now())
+    }
+
+    // Joins the back of the lane's queue
+    pub(crate) fn enqueue(&self, lane: Lane) -> Ticket {
+        let mut state = self.lock();
+        let id = state.next_ticket;
+        state.next_ticket += 1;
+        if state.bucket.is_some() {
+            state.lane(lane).push_back(id);
+        }
+
+        Ticket { id, lane }
+    }
+
+    // Takes a token for a ticket whose turn it is, or returns how long it should wait before trying again
+    #[cfg(test)]
+    fn take_at(&self, ticket: &Ticket, now: Instant) -> Result<(), Duration> {
+        self.lock().take_at(ticket, now)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn utilization_at(&self, now: Instant) -> Option<f64> {
+        let mut state = self.lock();
+        let waiting = state.waiting() as f64;
+        state.bucket.as_mut().map(|bucket| {
+            bucket.refill(now);
+            ((bucket.capacity() - bucket.tokens + waiting) / bucket.capacity()).max(0.0)
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn limiter(requests: u32, per: Duration) -> RateLimiter {
+        let limiter = RateLimiter::default();
+        limiter.set_limit(Some(RateLimit { requests, per }));
+        limiter
+    }
+
+    // Takes a token at `now` for a new request in the lane, leaving it waiting if it has to
+    fn request(limiter: &RateLimiter, lane: Lane, now: Instant) -> (Ticket, Result<(), Duration>) {
+        let ticket = limiter.enqueue(lane);
+        let taken = limiter.take_at(&ticket, now);
+        (ticket, taken)
+    }
+
+    #[test]
+    fn check_rate_limiter_bursts_then_spaces_requests() {
+        let limiter = limiter(2, Duration::from_secs(1));
+        let start = Instant::now();
+
+        assert_eq!(limiter.utilization_at(start), Some(0.0));
+        assert_eq!(request(&limiter, Lane::Normal, start).1, Ok(()));
+        assert_eq!(request(&limiter, Lane::Normal, start).1, Ok(()));
+        assert_eq!(limiter.utilization_at(start), Some(1.0));
+
+        // Each waiting caller queues behind the last
+        let (third, taken) = request(&limiter, Lane::Normal, start);
+        assert_eq!(taken, Err(Duration::from_millis(500)));
+        let (fourth, taken) = request(&limiter, Lane::Normal, start);
+        assert_eq!(taken, Err(Duration::from_secs(1)));
+        assert_eq!(limiter.utilization_at(start), Some(2.0));
+
+        let refilled = start + Duration::from_millis(500);
+        assert_eq!(limiter.take_at(&fourth, refilled), Err(Duration::from_millis(500)));
+        assert_eq!(limiter.take_at(&third, refilled), Ok(()));
+        assert_eq!(limiter.take_at(&fourth, refilled), Err(Duration::from_millis(500)));
+        assert_eq!(limiter.take_at(&fourth, refilled + Duration::from_millis(500)), Ok(()));
+
+        // Refilled, but never beyond a full burst
+        let later = start + Duration::from_mins(1);
+        assert_eq!(limiter.utilization_at(later), Some(0.0));
+        assert_eq!(request(&limiter, Lane::Normal, later).1, Ok(()));
+        assert_eq!(limiter.utilization_at(later), Some(0.5));
+    }
+
+    #[test]
+    fn check_rate_limiter_priority_lane() {
+        let limiter = limiter(5, Duration::from_secs(1));
+        let slot = Duration::from_millis(200);
+        let start = Instant::now();
+
+        // A long backlog of batch chunks, of which only the first burst can go at once
+        let backlog: Vec<Ticket> = (0..50).map(|_| limiter.enqueue(Lane::Normal)).collect();
+        for ticket in &backlog[..5] {
+            assert_eq!(limiter.take_at(ticket, start), Ok(()));
+        }
+        assert!(limiter.take_at(&backlog[5], start).is_err());
+
+        // A now playing update goes ahead of the backlog, within a slot
+        let requested = start + Duration::from_millis(50);
+        let (now_playing, taken) = request(&limiter, Lane::Priority, requested);
+        assert_eq!(taken, Err(Duration::from_millis(150)));
+        assert_eq!(limiter.take_at(&backlog[5], start + slot), Err(slot));
+        assert_eq!(limiter.take_at(&now_playing, start + slot), Ok(()));
+        assert!(start + slot - requested <= slot);
+
+        // It took a token, so the backlog waits a slot longer
+        assert!(limiter.take_at(&backlog[5], start + slot + slot / 2).is_err());
+        assert_eq!(limiter.take_at(&backlog[5], start + slot * 2), Ok(()));
+    }
+
+    #[test]
+    fn check_rate_limiter_acquire() {
+        let limiter = Arc::new(limiter(20, Duration::from_secs(1)));
+        let started = Instant::now();
+        let backlog: Vec<_> = (0..30)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                thread::spawn(move || limiter.acquire(Lane::Normal))
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(100));
+
+        // With half a second of requests still waiting, a now playing update waits for about a slot (50ms) at most
+        let requested = Instant::now();
+        limiter.acquire(Lane::Priority);
+        assert!(requested.elapsed() < Duration::from_millis(200), "waited {:?}", requested.elapsed());
+        for waiter in backlog {
+            waiter.join().unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(500));
+
+        // Without a limit, nothing waits
+        limiter.set_limit(None);
+        limiter.acquire(Lane::Normal);
+        assert_eq!(limiter.utilization(), None);
+    }
+
+    #[test]
+    fn check_rate_limiter_unlimited() {
+        let limiter = RateLimiter::default();
+        assert_eq!(limiter.limit(), None);
+        assert_eq!(limiter.utilization(), None);
+        assert_eq!(request(&limiter, Lane::Normal, Instant::now()).1, Ok(()));
+
+        limiter.set_limit(Some(RateLimit {
+            requests: 0,
+            per: Duration::from_secs(1),
+        }));
+        assert_eq!(limiter.limit(), None);
+        limiter.set_limit(Some(RateLimit::last_fm()));
+        assert_eq!(limiter.limit(), Some(RateLimit::last_fm()));
+    }
+}
@@ -0,0 +1,200 @@
+// Read-only access to the Last.fm API, either sharing a Scrobbler's client or standalone with just an API key
+use crate::cache::CachePolicy;
+use crate::client::LastFm;
+use crate::error::ScrobblerError;
+use crate::lang::Language;
+use crate::models::responses::{AlbumInfo, ArtistInfo, CorrectionResponse, TrackInfo};
+use crate::params::Params;
+use crate::rate_limit::RateLimit;
+use crate::transport::Transport;
+
+use std::sync::Arc;
+
+type Result<T> = std::result::Result<T, ScrobblerError>;
+
+/// A client for Last.fm's read-only methods, which need no authentication.
+///
+/// A reader created from a [`Scrobbler`] with [`Scrobbler::reader`] shares its transport, credentials, rate limit,
+/// circuit breaker, call log and cache, so reads and submissions are paced together, as Last.fm's limits require.
+/// Other settings are copied when the reader is created. A standalone reader needs only an API key.
+///
+/// # Usage
+/// ```ignore
+/// let reader = scrobbler.reader();
+/// let info = reader.track_info("Cher", "Believe")?;
+///
+/// // Or, without a Scrobbler
+/// let reader = LastFmReader::new(api_key);
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`Scrobbler::reader`]: struct.Scrobbler.html#method.reader
+#[derive(Clone)]
+pub struct LastFmReader {
+    client: LastFm,
+}
+
+impl LastFmReader {
+    /// Creates a standalone reader with the given Last.fm API key
+    pub fn new(api_key: &str) -> Self {
+        Self::from_client(LastFm::new(api_key, ""))
+    }
+
+    /// Creates a standalone reader which sends its API requests through the given [`Transport`]
+    ///
+    /// [`Transport`]: trait.Transport.html
+    pub fn with_transport<T>(api_key: &str, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self::from_client(LastFm::with_transport(api_key, "", Arc::new(transport)))
+    }
+
+    pub(crate) fn from_client(client: LastFm) -> Self {
+        Self { client }
+    }
+
+    /// Looks up a track's metadata and listening statistics. See [`Scrobbler::track_info`].
+    ///
+    /// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+    pub fn track_info(&self, artist: &str, track: &str) -> Result<TrackInfo> {
+        let mut params = Params::new();
+        params.insert_artist(artist);
+        params.insert_track(track);
+
+        self.client.send_track_info(&params)
+    }
+
+    /// Looks up an artist's metadata, listening statistics and biography. See [`Scrobbler::artist_info`].
+    ///
+    /// [`Scrobbler::artist_info`]: struct.Scrobbler.html#method.artist_info
+    pub fn artist_info(&self, artist: &str) -> Result<ArtistInfo> {
+        let mut params = Params::new();
+        params.insert_artist(artist);
+
+        self.client.send_artist_info(&params)
+    }
+
+    /// Looks up an album's metadata, listening statistics and wiki text. See [`Scrobbler::album_info`].
+    ///
+    /// [`Scrobbler::album_info`]: struct.Scrobbler.html#method.album_info
+    pub fn album_info(&self, artist: &str, album: &str) -> Result<AlbumInfo> {
+        let mut params = Params::new();
+        params.insert_artist(artist);
+        params.insert_album(album);
+
+        self.client.send_album_info(&params)
+    }
+
+    /// Looks up the names Last.fm would correct an artist and track to. See [`Scrobbler::track_correction`].
+    ///
+    /// [`Scrobbler::track_correction`]: struct.Scrobbler.html#method.track_correction
+    pub fn track_correction(&self, artist: &str, track: &str) -> Result<CorrectionResponse> {
+        let mut params = Params::new();
+        params.insert_artist(artist);
+        params.insert_track(track);
+
+        self.client.send_track_correction(&params)
+    }
+
+    /// Limits the rate of API requests, or removes the limit with `None`. A reader created from a [`Scrobbler`]
+    /// shares its limit, so this changes the `Scrobbler`'s limit too.
+    ///
+    /// [`Scrobbler`]: struct.Scrobbler.html
+    pub fn set_rate_limit(&self, rate_limit: Option<RateLimit>) {
+        self.client.set_rate_limit(rate_limit);
+    }
+
+    /// Returns the fraction of the rate limit in use. See [`Scrobbler::rate_limit_utilization`].
+    ///
+    /// [`Scrobbler::rate_limit_utilization`]: struct.Scrobbler.html#method.rate_limit_utilization
+    pub fn rate_limit_utilization(&self) -> Option<f64> {
+        self.client.rate_limit_utilization()
+    }
+
+    /// Caches the responses to lookups, or stops caching with `None`. A reader created from a [`Scrobbler`]
+    /// shares its cache, so this changes the `Scrobbler`'s cache too.
+    ///
+    /// [`Scrobbler`]: struct.Scrobbler.html
+    pub fn set_cache(&self, policy: Option<CachePolicy>) {
+        self.client.set_cache(policy);
+    }
+
+    /// Sets whether lookups ask Last.fm to autocorrect names. See [`Scrobbler::set_autocorrect`].
+    ///
+    /// [`Scrobbler::set_autocorrect`]: struct.Scrobbler.html#method.set_autocorrect
+    pub fn set_autocorrect(&mut self, autocorrect: Option<bool>) {
+        self.client.set_autocorrect(autocorrect);
+    }
+
+    /// Sets the language of the text returned by artist and album lookups. See [`Scrobbler::set_language`].
+    ///
+    /// [`Scrobbler::set_language`]: struct.Scrobbler.html#method.set_language
+    pub fn set_language(&mut self, language: Option<Language>) {
+        self.client.set_language(language);
+    }
+
+    /// Returns the API key the reader identifies itself with
+    pub fn api_key(&self) -> &str {
+        self.client.api_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobbler::Scrobbler;
+    use crate::test_util::EchoTransport;
+
+    use std::time::Duration;
+
+    #[test]
+    fn check_reader_shares_scrobbler_client() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        let reader = scrobbler.reader();
+        assert_eq!(reader.api_key(), "api_key");
+
+        // Limited after the reader was created, so the limit must reach the reader too
+        let limit = RateLimit {
+            requests: 2,
+            per: Duration::from_hours(1),
+        };
+        scrobbler.set_rate_limit(Some(limit));
+
+        // The scrobbler isn't authenticated, and the reader doesn't need it to be
+        let info = reader.track_info("Cher", "Believe").unwrap();
+        assert_eq!(info.name(), "Believe");
+        reader.track_info("Cher", "Strong Enough").unwrap();
+
+        // Both reads were paced by the scrobbler's limiter, so its next request would have to wait
+        let utilization = scrobbler.rate_limit_utilization().unwrap();
+        assert!(utilization > 0.99, "utilization {}", utilization);
+        assert!(reader.rate_limit_utilization().unwrap() > 0.99);
+
+        let requests = echo.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|request| request.method() == "track.getInfo"));
+        assert!(requests.iter().all(|request| request.params.get("sk").is_none()));
+    }
+
+    #[test]
+    fn check_standalone_reader() {
+        let echo = EchoTransport::new();
+        let reader = LastFmReader::with_transport("api_key", echo.clone());
+
+        let info = reader.track_info("Cher", "Believe").unwrap();
+        assert_eq!(info.artist(), "Cher");
+
+        // With no API secret there's nothing to sign with, and public reads don't need a signature
+        let request = &echo.requests()[0];
+        assert_eq!(request.params.get("api_key"), Some("api_key"));
+        assert_eq!(request.params.get("api_sig"), None);
+
+        // A standalone reader has its own limiter
+        assert_eq!(reader.rate_limit_utilization(), None);
+        reader.set_rate_limit(Some(RateLimit::last_fm()));
+        reader.track_info("Cher", "Believe").unwrap();
+        assert!(reader.rate_limit_utilization().unwrap() > 0.0);
+    }
+}
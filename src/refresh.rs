@@ -0,0 +1,280 @@
+// Background refresh of the Now Playing status during long tracks
+use crate::models::metadata::Scrobble;
+use crate::service::ScrobbleService;
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+// How long Last.fm shows a track as now playing when the update didn't give its duration
+const DEFAULT_LIFETIME: Duration = Duration::from_mins(5);
+
+// How long before the status would lapse that it's refreshed, and how soon a failed refresh is tried again
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+// How long Last.fm is expected to show a now playing update of `scrobble`
+pub(crate) fn now_playing_lifetime(scrobble: &Scrobble) -> Duration {
+    scrobble
+        .duration()
+        .filter(|&duration| duration > 0)
+        .map_or(DEFAULT_LIFETIME, Duration::from_secs)
+}
+
+// Waits between refreshes; replaced by a simulated clock in tests
+trait Clock: Send + 'static {
+    // Waits for `timeout`, returning false if a stop was signalled first
+    fn wait(&mut self, stop: &Receiver<()>, timeout: Duration) -> bool;
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn wait(&mut self, stop: &Receiver<()>, timeout: Duration) -> bool {
+        matches!(stop.recv_timeout(timeout), Err(RecvTimeoutError::Timeout))
+    }
+}
+
+/// Keeps a track's Now Playing status alive while it plays, by re-sending the Now Playing request periodically from
+/// a background thread.
+///
+/// Last.fm clears a user's Now Playing status once the track's duration has passed, or 5 minutes after the last Now
+/// Playing request if it didn't give one, so during a long track (ie. a 20-minute mix) it can disappear from their
+/// profile well before the track ends. A refresher re-sends the request 30 seconds before the status would lapse
+/// (see [`NowPlayingResponse::expires_at`]), or every `interval` if one is given, until the track's duration has
+/// elapsed, or until the refresher is stopped or dropped, whichever comes first. A refresh which fails is tried
+/// again 30 seconds later, or with an `interval`, at the next one.
+///
+/// The refresher only sends refreshes: send the initial Now Playing request as usual when the track starts. Stop
+/// the refresher (or drop it) as soon as the track is paused, stopped or changed, so that a stale track isn't
+/// refreshed.
+///
+/// # Usage
+/// ```ignore
+/// let scrobbler = Arc::new(scrobbler);
+/// scrobbler.now_playing(&track)?;
+/// let refresher = NowPlayingRefresher::start(scrobbler.clone(), &track, Duration::from_secs(20 * 60));
+/// ...
+/// // When the track changes
+/// refresher.stop();
+/// ```
+///
+/// [`NowPlayingResponse::expires_at`]: responses/struct.NowPlayingResponse.html#method.expires_at
+pub struct NowPlayingRefresher {
+    stop: Option<Sender<()>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl NowPlayingRefresher {
+    /// Starts refreshing the Now Playing status of `scrobble`, a track of the given duration, whenever it's about
+    /// to lapse. The first update is taken to have been sent just now.
+    pub fn start<S>(service: Arc<S>, scrobble: &Scrobble, duration: Duration) -> Self
+    where
+        S: ScrobbleService + Send + Sync + 'static + ?Sized,
+    {
+        Self::spawn(service, scrobble, duration, None, SystemClock)
+    }
+
+    /// Starts refreshing the Now Playing status of `scrobble`, a track of the given duration, every `interval`
+    pub fn start_with_interval<S>(
+        service: Arc<S>,
+        scrobble: &Scrobble,
+        duration: Duration,
+        interval: Duration,
+    ) -> Self
+    where
+        S: ScrobbleService + Send + Sync + 'static + ?Sized,
+    {
+        Self::spawn(service, scrobble, duration, Some(interval), SystemClock)
+    }
+
+    fn spawn<S, C>(
+        service: Arc<S>,
+        scrobble: &Scrobble,
+        duration: Duration,
+        interval: Option<Duration>,
+        mut clock: C,
+    ) -> Self
+    where
+        S: ScrobbleService + Send + Sync + 'static + ?Sized,
+        C: Clock,
+    {
+        let (stop, stopped) = mpsc::channel();
+        let scrobble = scrobble.clone();
+
+        let worker = thread::spawn(move || {
+            let mut elapsed = Duration::from_secs(0);
+            // When the status last set lapses, counted from the start
+            let mut lapses = now_playing_lifetime(&scrobble);
+            loop {
+                let wait = match interval {
+                    Some(interval) => interval,
+                    // Nothing needs refreshing once the status outlasts the track
+                    None if lapses >= duration => return,
+                    None => lapses.saturating_sub(elapsed + REFRESH_MARGIN).max(REFRESH_MARGIN),
+                };
+                // Refreshing after the track has ended would resurrect a track that's no longer playing
+                if elapsed + wait >= duration || !clock.wait(&stopped, wait) {
+                    return;
+                }
+                elapsed += wait;
+
+                let sent = SystemTime::now();
+                let expires_at = service.now_playing(&scrobble).ok().and_then(|response| response.expires_at());
+                if let Some(lifetime) = expires_at.and_then(|expires_at| expires_at.duration_since(sent).ok()) {
+                    // To the second, as the expiry is worked out a moment after sending
+                    lapses = elapsed + Duration::from_secs(lifetime.as_secs());
+                }
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            worker: Some(worker),
+        }
+    }
+
+    /// Stops refreshing, waiting for any refresh in progress to finish. Equivalent to dropping the refresher.
+    pub fn stop(self) {}
+}
+
+impl Drop for NowPlayingRefresher {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the worker, whether it's waiting or has already finished
+        self.stop.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ScrobblerError;
+    use crate::scrobbler::Scrobbler;
+    use crate::test_util::{EchoTransport, MockCall, MockScrobbler};
+    use std::sync::{Mutex, PoisonError};
+
+    // Passes time instantly, recording each wait, and simulating a stop after the given number of waits
+    struct SimulatedClock {
+        waits_before_stop: usize,
+        waited: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl SimulatedClock {
+        fn new(waits_before_stop: usize) -> (Self, Arc<Mutex<Vec<Duration>>>) {
+            let waited = Arc::default();
+            let clock = Self {
+                waits_before_stop,
+                waited: Arc::clone(&waited),
+            };
+            (clock, waited)
+        }
+    }
+
+    impl Clock for SimulatedClock {
+        fn wait(&mut self, _stop: &Receiver<()>, timeout: Duration) -> bool {
+            if self.waits_before_stop == 0 {
+                return false;
+            }
+            self.waits_before_stop -= 1;
+            self.waited.lock().unwrap_or_else(PoisonError::into_inner).push(timeout);
+            true
+        }
+    }
+
+    fn track() -> Scrobble {
+        Scrobble::new("floyd", "echoes", "meddle")
+    }
+
+    fn waits(waited: &Mutex<Vec<Duration>>) -> Vec<Duration> {
+        waited.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    #[test]
+    fn check_now_playing_expiry() {
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", EchoTransport::new());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let mut long = track();
+        long.with_duration(20 * 60);
+
+        for (scrobble, lifetime) in &[(track(), Duration::from_mins(5)), (long, Duration::from_mins(20))] {
+            let before = SystemTime::now();
+            let expires_at = scrobbler.now_playing(scrobble).unwrap().expires_at().unwrap();
+            assert!(expires_at >= before + *lifetime);
+            assert!(expires_at <= SystemTime::now() + *lifetime);
+        }
+    }
+
+    #[test]
+    fn check_refresher_refreshes_before_expiry() {
+        // Without a duration, the status lapses 5 minutes after each update: refreshed at 4:30, 9:00 and 13:30
+        let mock = Arc::new(MockScrobbler::new());
+        let (clock, waited) = SimulatedClock::new(usize::MAX);
+        drop(NowPlayingRefresher::spawn(mock.clone(), &track(), Duration::from_mins(15), None, clock));
+        assert_eq!(mock.calls(), vec![MockCall::NowPlaying(track()); 3]);
+        assert_eq!(waits(&waited), vec![Duration::from_secs(270); 3]);
+
+        // With the track's duration, the status lasts the whole track
+        let mut scrobble = track();
+        scrobble.with_duration(15 * 60);
+        let mock = Arc::new(MockScrobbler::new());
+        let (clock, waited) = SimulatedClock::new(usize::MAX);
+        drop(NowPlayingRefresher::spawn(mock.clone(), &scrobble, Duration::from_mins(15), None, clock));
+        assert!(mock.calls().is_empty());
+        assert!(waits(&waited).is_empty());
+
+        // With a shorter duration, ie. of the part left to play, it's refreshed once that's about to pass
+        scrobble.with_duration(10 * 60);
+        let mock = Arc::new(MockScrobbler::new());
+        let (clock, waited) = SimulatedClock::new(usize::MAX);
+        drop(NowPlayingRefresher::spawn(mock.clone(), &scrobble, Duration::from_mins(15), None, clock));
+        assert_eq!(mock.calls(), vec![MockCall::NowPlaying(scrobble.clone())]);
+        assert_eq!(waits(&waited), vec![Duration::from_secs(570)]);
+
+        // A failed refresh is tried again 30 seconds later, before the status lapses
+        let mock = Arc::new(MockScrobbler::new());
+        mock.push_now_playing_response(Err(ScrobblerError::new("offline".to_owned())));
+        let (clock, waited) = SimulatedClock::new(usize::MAX);
+        drop(NowPlayingRefresher::spawn(mock.clone(), &track(), Duration::from_mins(6), None, clock));
+        assert_eq!(mock.calls().len(), 2);
+        assert_eq!(waits(&waited), vec![Duration::from_secs(270), Duration::from_secs(30)]);
+    }
+
+    #[test]
+    fn check_refresher_refreshes_until_track_ends() {
+        let mock = Arc::new(MockScrobbler::new());
+        let (clock, waited) = SimulatedClock::new(usize::MAX);
+
+        let interval = Duration::from_mins(4);
+        drop(NowPlayingRefresher::spawn(mock.clone(), &track(), Duration::from_mins(15), Some(interval), clock));
+
+        // At 4, 8 and 12 minutes; the track ends before the next one is due
+        assert_eq!(mock.calls(), vec![MockCall::NowPlaying(track()); 3]);
+        assert_eq!(waits(&waited), vec![interval; 3]);
+    }
+
+    #[test]
+    fn check_refresher_stops_when_told() {
+        let mock = Arc::new(MockScrobbler::new());
+        let (clock, _) = SimulatedClock::new(1);
+
+        // Stopped 5 minutes in, while waiting for the second refresh
+        let interval = Some(Duration::from_mins(4));
+        let refresher = NowPlayingRefresher::spawn(mock.clone(), &track(), Duration::from_mins(15), interval, clock);
+        refresher.stop();
+        assert_eq!(mock.calls(), vec![MockCall::NowPlaying(track())]);
+        mock.clear_calls();
+
+        // A real clock, stopped while waiting for the first refresh
+        let refresher = NowPlayingRefresher::start_with_interval(
+            mock.clone(),
+            &track(),
+            Duration::from_mins(15),
+            Duration::from_secs(10),
+        );
+        drop(refresher);
+        assert!(mock.calls().is_empty());
+    }
+}
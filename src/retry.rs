@@ -0,0 +1,134 @@
+// Retry policies for API calls, by class of call
+use crate::events::RequestOutcome;
+
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Controls whether, and how, failed requests are retried. Set for each [`CallClass`] with
+/// [`Scrobbler::set_retry_policy_for`].
+///
+/// A `Scrobbler` retries requests that fail for transient reasons: a network error, a `5xx` response, or a Last.fm
+/// "service offline" (11), "temporarily unavailable" (16) or "rate limit exceeded" (29) error. Authentication
+/// requests are retried only after a network error. The delay before each retry doubles, starting at `base_delay`,
+/// up to `max_delay`. Each delay is shortened by a random amount, of up to half of it, so that clients which failed
+/// together don't all retry at the same moment.
+///
+/// The default policy makes a single attempt, ie. never retries.
+///
+/// [`CallClass`]: enum.CallClass.html
+/// [`Scrobbler::set_retry_policy_for`]: struct.Scrobbler.html#method.set_retry_policy_for
+///
+/// # Usage
+/// ```ignore
+/// scrobbler.set_retry_policy(RetryPolicy {
+///     max_attempts: 3,
+///     base_delay: Duration::from_secs(1),
+///     max_delay: Duration::from_secs(30),
+/// });
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts per request, including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy which never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: Duration::from_secs(0),
+            max_delay: Duration::from_secs(0),
+        }
+    }
+
+    // The delay before retrying after the given (1-based) failed attempt
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    // The delay actually waited after the given failed attempt: somewhere between half of its backoff delay and all
+    // of it
+    pub(crate) fn jittered_delay(&self, attempt: u32) -> Duration {
+        let delay = self.delay(attempt);
+        let half = delay / 2;
+        let spread = u64::try_from(delay.saturating_sub(half).as_nanos()).unwrap_or(u64::MAX);
+        let random = RandomState::new().build_hasher().finish();
+
+        half + Duration::from_nanos(random % spread.saturating_add(1))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// The classes of API call which each have their own [`RetryPolicy`]
+///
+/// [`RetryPolicy`]: struct.RetryPolicy.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallClass {
+    /// Authentication requests. Only retried when no response was received at all: retrying rejected credentials
+    /// can't succeed, and repeated failed logins may get the account locked. By default retried once.
+    Auth,
+    /// Now Playing and Scrobble requests. Never retried by default.
+    Write,
+    /// Read-only requests, ie. track info. Never retried by default.
+    Read,
+}
+
+impl CallClass {
+    pub(crate) fn default_retry_policy(self) -> RetryPolicy {
+        match self {
+            Self::Auth => RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(500),
+                max_delay: Duration::from_millis(500),
+            },
+            Self::Write | Self::Read => RetryPolicy::none(),
+        }
+    }
+
+    // Whether an attempt which failed transiently with the given outcome may be retried
+    pub(crate) fn retries(self, outcome: &RequestOutcome) -> bool {
+        match self {
+            Self::Auth => matches!(outcome, RequestOutcome::TransportError(_)),
+            Self::Write | Self::Read => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_retry_delays() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+        };
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+        assert_eq!(policy.delay(40), Duration::from_millis(350));
+        assert_eq!(RetryPolicy::default().max_attempts, 1);
+
+        for attempt in 1..=5 {
+            let delay = policy.jittered_delay(attempt);
+            assert!(delay >= policy.delay(attempt) / 2 && delay <= policy.delay(attempt), "{:?}", delay);
+        }
+        assert_eq!(RetryPolicy::none().jittered_delay(1), Duration::from_secs(0));
+    }
+}
@@ -1,16 +1,79 @@
-use crate::client::LastFm;
+use crate::audit::CallRecord;
+use crate::builder::{Endpoint, ScrobblerBuilder, ServicePreset};
+use crate::cache::CachePolicy;
+use crate::chunks::{self, BatchChunks, ChunkResult};
+use crate::circuit::{CircuitBreakerPolicy, CircuitState};
+use crate::client::{ApiOperation, LastFm};
+use crate::config::{HttpSettings, ScrobblerConfig};
 use crate::error::ScrobblerError;
-use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::events::{EventObserver, ScrobblerEvent};
+use crate::headers::CallOptions;
+use crate::health::HealthReport;
+use crate::info::ClientInfo;
+use crate::issues::{Adjustment, PreparationReport, ScrobbleIssue};
+use crate::keys::{ApiKey, ApiSecret, SessionKey};
+use crate::lang::Language;
+use crate::love::{BulkLoveOptions, BulkLoveReport, LoveOutcome};
+use crate::media::DEFAULT_MEDIA_KIND_PARAM;
+use crate::models::metadata::{NowPlaying, Scrobble, ScrobbleBatch};
 use crate::models::responses::{
-    BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse,
+    AlbumInfo, ArtistInfo, RecentTrack,
+    BatchScrobbleResponse, CorrectionResponse, NowPlayingResponse, ScrobbleList, ScrobbleResponse, SessionResponse,
+    TrackInfo, UserInfo,
 };
+use crate::params::Params;
+use crate::plan::{ImportPlan, MAX_SCROBBLE_AGE};
+use crate::rate_limit::RateLimit;
+use crate::reader::LastFmReader;
+use crate::retry::{CallClass, RetryPolicy};
+use crate::signing::RequestSigner;
+#[cfg(feature = "event-sink")]
+use crate::sink::EventSink;
+use crate::submitted::SubmittedScrobble;
+use crate::transport::Transport;
+use crate::usage::{EstimatedUsage, UsageThresholds};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::result;
-use std::time::UNIX_EPOCH;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "unicode")]
+use unicode_segmentation::UnicodeSegmentation;
 
 type Result<T> = result::Result<T, ScrobblerError>;
 
+// A submission prepared for sending, by a `Scrobbler` or an `AsyncScrobbler`: either already answered, in dry-run
+// mode, or the parameters to send
+pub(crate) enum Submission<T> {
+    DryRun(T),
+    Send(Params),
+}
+
+// 2002-01-01T00:00:00Z, when Audioscrobbler launched. No genuine scrobble can be older.
+const DEFAULT_MIN_TIMESTAMP: u64 = 1_009_843_200;
+
+// The longest names submitted, in characters: track names, and artist, album and album artist names. Longer ones
+// (ie. podcast episode descriptions used as titles) are rejected or mangled by Last.fm.
+const MAX_TRACK_CHARS: usize = 1024;
+const MAX_NAME_CHARS: usize = 255;
+
+// How far ahead of the current time a timestamp may be, to allow for clocks which are slightly fast
+const MAX_CLOCK_SKEW: Duration = Duration::from_mins(10);
+
+// The least time between the requests sent by `Scrobbler::health_probe`, by default
+const DEFAULT_HEALTH_PROBE_INTERVAL: Duration = Duration::from_mins(5);
+
+// 5000-01-01T00:00:00Z. Later timestamps are almost certainly in milliseconds rather than seconds.
+const MAX_PLAUSIBLE_TIMESTAMP: u64 = 95_617_584_000;
+
+// The most scrobbles `user.getRecentTracks` returns per page
+const MAX_HISTORY_PAGE: u32 = 200;
+
+// The parameters every signed call is sent with, which `Scrobbler::call_signed` doesn't let the caller set
+const RESERVED_PARAMS: [&str; 6] = ["method", "api_key", "sk", "api_sig", "format", "nonce"];
+
 /// A Last.fm Scrobbler client. Submits song play information to Last.fm.
 /// 
 /// This is a client for the Scrobble and Now Playing endpoints on the Last.fm API. It handles API client and user 
@@ -31,8 +94,76 @@ type Result<T> = result::Result<T, ScrobblerError>;
 /// let song = Scrobble::new("Example Artist", "Example Song", "Example Album");
 /// scrobbler.scrobble(song);
 /// ```
+///
+/// # Clones
+/// Clones of a `Scrobbler` share its transport, request signer, rate limit (see [`Scrobbler::set_rate_limit`]),
+/// circuit breaker (see [`Scrobbler::set_circuit_breaker`]), call log (see [`Scrobbler::set_call_log_capacity`]) and
+/// cache (see [`Scrobbler::set_cache`]), so that any number of clones, ie. one per worker thread, draw from the same
+/// request budget. Every other setting, including the session key, is copied: changing it on one clone doesn't
+/// affect the others.
+///
+/// [`Scrobbler::set_rate_limit`]: struct.Scrobbler.html#method.set_rate_limit
+/// [`Scrobbler::set_circuit_breaker`]: struct.Scrobbler.html#method.set_circuit_breaker
+/// [`Scrobbler::set_call_log_capacity`]: struct.Scrobbler.html#method.set_call_log_capacity
+/// [`Scrobbler::set_cache`]: struct.Scrobbler.html#method.set_cache
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone)]
 pub struct Scrobbler {
     client: LastFm,
+    dry_run: bool,
+    dry_run_auth: bool,
+    fail_on_ignored: bool,
+    now_playing_best_effort: bool,
+    min_timestamp: u64,
+    max_scrobble_age: Option<Duration>,
+    truncation: Truncation,
+    strict: bool,
+    media_kind_param: Option<String>,
+    defaults: SubmissionDefaults,
+    preset: Option<ServicePreset>,
+    health_probe_interval: Duration,
+    // The settings of the transport, if the `Scrobbler` created it, so that `apply_config` can replace it
+    http: Option<HttpSettings>,
+}
+
+// Metadata submitted with every track which doesn't set its own. See `ScrobblerBuilder::default_context`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SubmissionDefaults {
+    pub(crate) album_artist: Option<String>,
+    pub(crate) context: Option<String>,
+    pub(crate) chosen_by_user: Option<bool>,
+}
+
+impl SubmissionDefaults {
+    // Fills in whatever the track leaves unset. Now playing updates can't say whether the user chose the track.
+    fn apply<'a>(&self, scrobble: Cow<'a, Scrobble>, now_playing: bool) -> Cow<'a, Scrobble> {
+        let album_artist = self.album_artist.as_deref().filter(|_| scrobble.album_artist().is_empty());
+        let context = self.context.as_deref().filter(|_| scrobble.context().is_none());
+        let chosen_by_user = self.chosen_by_user.filter(|_| !now_playing && scrobble.chosen_by_user().is_none());
+        if album_artist.is_none() && context.is_none() && chosen_by_user.is_none() {
+            return scrobble;
+        }
+
+        let mut scrobble = scrobble.into_owned();
+        if let Some(album_artist) = album_artist {
+            scrobble.with_album_artist(album_artist);
+        }
+        if let Some(context) = context {
+            scrobble.with_context(context);
+        }
+        if let Some(chosen_by_user) = chosen_by_user {
+            scrobble.with_chosen_by_user(chosen_by_user);
+        }
+
+        Cow::Owned(scrobble)
+    }
+}
+
+// How over-long names are handled. See `Scrobbler::set_truncate_long_fields`.
+#[derive(Clone, Copy, Debug, Default)]
+struct Truncation {
+    enabled: bool,
+    ellipsis: bool,
 }
 
 impl Scrobbler {
@@ -58,9 +189,818 @@ impl Scrobbler {
     /// 
     /// [API Account Registration form](https://www.last.fm/api/account/create)
     pub fn new(api_key: &str, api_secret: &str) -> Self {
-        let client = LastFm::new(api_key, api_secret);
+        Self::from_client(LastFm::new(api_key, api_secret)).with_http_settings(HttpSettings::default())
+    }
+
+    /// Creates a new Scrobbler instance which sends its API requests to another server implementing the
+    /// Audioscrobbler 2.0 API, such as Libre.fm (`"https://libre.fm/2.0/"`), instead of Last.fm. Fails if the URL
+    /// is invalid. See [`ScrobblerBuilder::base_url`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut scrobbler = Scrobbler::new_with_host(api_key, api_secret, "https://libre.fm/2.0/")?;
+    /// ```
+    ///
+    /// [`ScrobblerBuilder::base_url`]: struct.ScrobblerBuilder.html#method.base_url
+    pub fn new_with_host(api_key: &str, api_secret: &str, base_url: &str) -> Result<Self> {
+        Self::builder(api_key, api_secret).base_url(base_url).build()
+    }
+
+    /// Creates a new Scrobbler instance which sends its API requests to Libre.fm, and accepts scrobbles of any age as
+    /// Libre.fm does. See [`ServicePreset::LibreFm`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut scrobbler = Scrobbler::new_librefm(api_key, api_secret);
+    /// ```
+    ///
+    /// [`ServicePreset::LibreFm`]: enum.ServicePreset.html#variant.LibreFm
+    pub fn new_librefm(api_key: &str, api_secret: &str) -> Self {
+        let preset = ServicePreset::LibreFm;
+        let mut client = LastFm::new(api_key, api_secret);
+        client.set_endpoint(preset.endpoint());
+        let mut scrobbler = Self::from_client(client).with_http_settings(HttpSettings::default());
+        scrobbler.apply_preset(preset);
+        scrobbler
+    }
+
+    /// Returns a [`ScrobblerBuilder`], to create a Scrobbler with another API server, a request timeout, a proxy
+    /// or a custom `User-Agent`
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut scrobbler = Scrobbler::builder(api_key, api_secret)
+    ///     .timeout(Duration::from_secs(10))
+    ///     .proxy("http://proxy.example:3128")
+    ///     .build()?;
+    /// ```
+    ///
+    /// [`ScrobblerBuilder`]: struct.ScrobblerBuilder.html
+    pub fn builder(api_key: &str, api_secret: &str) -> ScrobblerBuilder {
+        ScrobblerBuilder::new(api_key.into(), api_secret.into())
+    }
+
+    /// Creates a new Scrobbler instance which sends its API requests through the given [`Transport`], instead of
+    /// the default `reqwest`-based one.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let transport = LoggingTransport::new(ReqwestTransport::new());
+    /// let mut scrobbler = Scrobbler::with_transport(api_key, api_secret, transport);
+    /// ```
+    ///
+    /// [`Transport`]: trait.Transport.html
+    pub fn with_transport<T>(api_key: &str, api_secret: &str, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self::from_client(LastFm::with_transport(api_key, api_secret, Arc::new(transport)))
+    }
+
+    /// Creates a new Scrobbler instance from typed credentials. Unlike [`Scrobbler::new`], the key and secret can't
+    /// be passed in each other's place.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let api_key = ApiKey::parse(&env::var("LASTFM_API_KEY")?)?;
+    /// let api_secret = ApiSecret::parse(&env::var("LASTFM_API_SECRET")?)?;
+    /// let mut scrobbler = Scrobbler::with_keys(api_key, api_secret);
+    /// ```
+    ///
+    /// [`Scrobbler::new`]: struct.Scrobbler.html#method.new
+    pub fn with_keys(api_key: ApiKey, api_secret: ApiSecret) -> Self {
+        Self::from_client(LastFm::new(api_key, api_secret)).with_http_settings(HttpSettings::default())
+    }
+
+    /// Creates a new Scrobbler instance from typed credentials, which sends its API requests through the given
+    /// [`Transport`]. See [`Scrobbler::with_keys`] and [`Scrobbler::with_transport`].
+    ///
+    /// [`Transport`]: trait.Transport.html
+    /// [`Scrobbler::with_keys`]: struct.Scrobbler.html#method.with_keys
+    /// [`Scrobbler::with_transport`]: struct.Scrobbler.html#method.with_transport
+    pub fn with_keys_and_transport<T>(api_key: ApiKey, api_secret: ApiSecret, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self::from_client(LastFm::with_transport(api_key, api_secret, Arc::new(transport)))
+    }
+
+    pub(crate) fn client(&self) -> &LastFm {
+        &self.client
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn client_mut(&mut self) -> &mut LastFm {
+        &mut self.client
+    }
+
+    pub(crate) fn from_client(client: LastFm) -> Self {
+        Self {
+            client,
+            dry_run: false,
+            dry_run_auth: false,
+            fail_on_ignored: false,
+            now_playing_best_effort: false,
+            min_timestamp: DEFAULT_MIN_TIMESTAMP,
+            max_scrobble_age: Some(MAX_SCROBBLE_AGE),
+            truncation: Truncation::default(),
+            strict: false,
+            media_kind_param: Some(DEFAULT_MEDIA_KIND_PARAM.to_owned()),
+            defaults: SubmissionDefaults::default(),
+            preset: None,
+            health_probe_interval: DEFAULT_HEALTH_PROBE_INTERVAL,
+            http: None,
+        }
+    }
+
+    // Marks the transport as one the `Scrobbler` created itself, with `http`
+    pub(crate) fn with_http_settings(mut self, http: HttpSettings) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    // Applies the settings of `preset` other than its endpoint
+    pub(crate) fn apply_preset(&mut self, preset: ServicePreset) {
+        self.preset = Some(preset);
+        self.max_scrobble_age = preset.max_scrobble_age();
+    }
+
+    pub(crate) fn set_submission_defaults(&mut self, defaults: SubmissionDefaults) {
+        self.defaults = defaults;
+    }
+
+    /// Enables or disables dry-run mode. Disabled by default.
+    ///
+    /// In dry-run mode, [`Scrobbler::now_playing`], [`Scrobbler::scrobble`] and [`Scrobbler::scrobble_batch`]
+    /// perform all of their usual validation, parameter building and request signing, but never send the request.
+    /// Instead they return a response synthesized from the submitted tracks, reported back uncorrected. Synthesized
+    /// responses are marked as such: their `dry_run()` accessor returns `true`.
+    ///
+    /// This is useful when developing against a real account, to see what would be submitted without polluting the
+    /// account's listening history. Authentication requests are still sent in dry-run mode, so credentials can be
+    /// validated; see [`Scrobbler::set_dry_run_auth`] to stub those out too.
+    ///
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`Scrobbler::set_dry_run_auth`]: struct.Scrobbler.html#method.set_dry_run_auth
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Returns true if dry-run mode is enabled. See [`Scrobbler::set_dry_run`].
+    ///
+    /// [`Scrobbler::set_dry_run`]: struct.Scrobbler.html#method.set_dry_run
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Stubs out authentication while in dry-run mode. Disabled by default; has no effect unless dry-run mode is
+    /// enabled with [`Scrobbler::set_dry_run`].
+    ///
+    /// With both enabled, [`Scrobbler::authenticate_with_password`] and [`Scrobbler::authenticate_with_token`]
+    /// validate and sign the authentication request without sending it, and authenticate the `Scrobbler` with a
+    /// placeholder session key. The placeholder key is not valid with Last.fm, so re-authenticate before disabling
+    /// dry-run mode.
+    ///
+    /// [`Scrobbler::set_dry_run`]: struct.Scrobbler.html#method.set_dry_run
+    /// [`Scrobbler::authenticate_with_password`]: struct.Scrobbler.html#method.authenticate_with_password
+    /// [`Scrobbler::authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
+    pub fn set_dry_run_auth(&mut self, dry_run_auth: bool) {
+        self.dry_run_auth = dry_run_auth;
+    }
+
+    /// Registers a callback which is notified of the lifecycle of every API request the `Scrobbler` makes:
+    /// when it starts, when it finishes and when it is retried or rate limited. Replaces any previously
+    /// registered observer.
+    ///
+    /// The callback is called synchronously, on the thread making the API call, and never while the `Scrobbler`
+    /// holds a lock, so it may safely call back into the `Scrobbler`. Events for a single call are always delivered
+    /// in order; see [`ScrobblerEvent`] for the sequence. Keep the callback cheap, as it delays the request.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// scrobbler.set_event_observer(|event| match event {
+    ///     ScrobblerEvent::RequestStarted { .. } => ui.show_spinner(),
+    ///     ScrobblerEvent::RequestFinished { .. } => ui.hide_spinner(),
+    ///     _ => {}
+    /// });
+    /// ```
+    ///
+    /// [`ScrobblerEvent`]: enum.ScrobblerEvent.html
+    pub fn set_event_observer<F>(&mut self, observer: F)
+    where
+        F: Fn(&ScrobblerEvent) + Send + Sync + 'static,
+    {
+        self.client.set_event_observer(Some(EventObserver::new(observer)));
+    }
+
+    /// Removes the observer registered with [`Scrobbler::set_event_observer`], if any
+    ///
+    /// [`Scrobbler::set_event_observer`]: struct.Scrobbler.html#method.set_event_observer
+    pub fn clear_event_observer(&mut self) {
+        self.client.set_event_observer(None);
+    }
+
+    /// Mirrors every scrobble Last.fm accepts or ignores to `event_sink`, or stops mirroring them if `None`. This
+    /// includes scrobbles submitted by a [`ScrobbleQueue`] wrapping the `Scrobbler`, and by clones made after the
+    /// sink is set. Requires the `event-sink` feature.
+    ///
+    /// Events are delivered in the background; [`EventSink`] describes what's sent, and what happens if the
+    /// endpoint fails.
+    ///
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    /// [`EventSink`]: struct.EventSink.html
+    #[cfg(feature = "event-sink")]
+    pub fn set_event_sink(&mut self, event_sink: Option<EventSink>) {
+        self.client.set_event_sink(event_sink);
+    }
+
+    #[cfg(feature = "event-sink")]
+    pub fn event_sink(&self) -> Option<&EventSink> {
+        self.client.event_sink()
+    }
+
+    /// Sets the prefix of the metrics recorded for this `Scrobbler` and its clones made afterwards, `lastfm` by
+    /// default. Requires the `metrics` feature.
+    ///
+    /// Metrics are recorded through the [`metrics`] facade, so go to whichever recorder the application installs,
+    /// and cost next to nothing if it installs none. With the prefix `lastfm` they are:
+    ///
+    /// * `lastfm_requests_total`, a counter of finished API calls labelled by `method` and `outcome` (`success`,
+    ///   `api_error`, `http_error` or `transport_error`)
+    /// * `lastfm_request_duration_seconds`, a histogram of how long each call took across its attempts, labelled
+    ///   by `method`
+    /// * `lastfm_retries_total`, a counter of retried attempts labelled by `method`
+    /// * `lastfm_scrobbles_accepted_total`, a counter of scrobbles Last.fm accepted
+    /// * `lastfm_scrobbles_ignored_total`, a counter of scrobbles Last.fm ignored, labelled by `reason`
+    ///
+    /// [`metrics`]: https://docs.rs/metrics
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics_prefix(&mut self, prefix: &str) {
+        self.client.set_metrics_prefix(prefix);
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics_prefix(&self) -> &str {
+        self.client.metrics_prefix()
+    }
+
+    /// Sets the policy for retrying Now Playing and Scrobble requests which fail for transient reasons, ie. for
+    /// `CallClass::Write`. By default, requests are never retried. See [`RetryPolicy`].
+    ///
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.client.set_retry_policy(retry_policy);
+    }
+
+    /// Sets the policy for retrying one class of request. See [`CallClass`] for the classes, and what each retries
+    /// by default. Love and Unlove requests, made with [`Scrobbler::love_many`] and [`Scrobbler::unlove_many`],
+    /// are retried under the policy they're given instead.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// // Keep retrying token exchanges while the network is down, for a minute at most
+    /// scrobbler.set_retry_policy_for(CallClass::Auth, RetryPolicy {
+    ///     max_attempts: 7,
+    ///     base_delay: Duration::from_secs(1),
+    ///     max_delay: Duration::from_secs(15),
+    /// });
+    /// ```
+    ///
+    /// [`CallClass`]: enum.CallClass.html
+    /// [`Scrobbler::love_many`]: struct.Scrobbler.html#method.love_many
+    /// [`Scrobbler::unlove_many`]: struct.Scrobbler.html#method.unlove_many
+    pub fn set_retry_policy_for(&mut self, class: CallClass, retry_policy: RetryPolicy) {
+        self.client.set_retry_policy_for(class, retry_policy);
+    }
+
+    /// Returns the policy for retrying one class of request. See [`Scrobbler::set_retry_policy_for`].
+    ///
+    /// [`Scrobbler::set_retry_policy_for`]: struct.Scrobbler.html#method.set_retry_policy_for
+    pub fn retry_policy_for(&self, class: CallClass) -> RetryPolicy {
+        self.client.retry_policy_for(class)
+    }
+
+    /// Limits the rate of API requests, or removes the limit with `None`. There is no limit by default. See
+    /// [`RateLimit`].
+    ///
+    /// The limit is shared with every clone of this `Scrobbler`, and with the [`ScrobbleQueue`] or bulk love
+    /// operations using them: setting it on one clone sets it for all of them, and requests from all of them count
+    /// towards it.
+    ///
+    /// [`RateLimit`]: struct.RateLimit.html
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.client.set_rate_limit(rate_limit);
+    }
+
+    /// Returns how much of the shared rate limit's budget is in use, for diagnostics: 0.0 when a full burst of
+    /// requests could be sent immediately, 1.0 when the next request would have to wait, and more than 1.0 when
+    /// requests are already waiting. Returns `None` if there is no rate limit.
+    pub fn rate_limit_utilization(&self) -> Option<f64> {
+        self.client.rate_limit_utilization()
+    }
+
+    /// Enables a circuit breaker, which stops sending API calls for a while after repeated transient failures, or
+    /// disables it with `None`. Disabled by default. See [`CircuitBreakerPolicy`].
+    ///
+    /// While the circuit is open, calls fail immediately with an error of kind `ErrorKind::CircuitOpen`, which is
+    /// retryable; a [`ScrobbleQueue`] keeps its scrobbles queued, without backing them off, until the circuit
+    /// closes. State changes are reported to the event observer as [`ScrobblerEvent::CircuitStateChanged`]
+    /// events. The circuit breaker is shared with every clone of this `Scrobbler`.
+    ///
+    /// [`CircuitBreakerPolicy`]: struct.CircuitBreakerPolicy.html
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    /// [`ScrobblerEvent::CircuitStateChanged`]: enum.ScrobblerEvent.html#variant.CircuitStateChanged
+    pub fn set_circuit_breaker(&mut self, policy: Option<CircuitBreakerPolicy>) {
+        self.client.set_circuit_breaker(policy);
+    }
+
+    /// Returns the state of the circuit breaker, for diagnostics, or `None` if it's disabled
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.client.circuit_state()
+    }
+
+    /// Sets soft and hard thresholds on the estimated API usage, or removes them with `None`. There are none by
+    /// default. See [`UsageThresholds`].
+    ///
+    /// Requests are counted whether or not there are thresholds, and the count and thresholds are shared with every
+    /// clone of this `Scrobbler`.
+    ///
+    /// [`UsageThresholds`]: struct.UsageThresholds.html
+    pub fn set_usage_thresholds(&mut self, thresholds: Option<UsageThresholds>) {
+        self.client.set_usage_thresholds(thresholds);
+    }
+
+    /// Returns the usage thresholds set with [`Scrobbler::set_usage_thresholds`], if any
+    ///
+    /// [`Scrobbler::set_usage_thresholds`]: struct.Scrobbler.html#method.set_usage_thresholds
+    pub fn usage_thresholds(&self) -> Option<UsageThresholds> {
+        self.client.usage_thresholds()
+    }
+
+    /// Returns how many API requests this `Scrobbler` and its clones have sent over the last minute, hour and day.
+    /// See [`EstimatedUsage`].
+    ///
+    /// [`EstimatedUsage`]: struct.EstimatedUsage.html
+    pub fn estimated_usage(&self) -> EstimatedUsage {
+        self.client.estimated_usage()
+    }
+
+    /// Returns the most recent scrobble (by timestamp) which Last.fm accepted from this `Scrobbler` or its clones,
+    /// ie. to decide how far back to rebuild a local history after a restart. Ignored scrobbles, and those
+    /// submitted in dry run mode, don't count. A [`ScrobbleQueue`] persists it across runs.
+    ///
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    pub fn last_accepted_scrobble(&self) -> Option<SubmittedScrobble> {
+        self.client.last_accepted()
+    }
+
+    /// Caches the responses to read-only lookups, such as [`Scrobbler::track_info`], or stops caching with `None`.
+    /// Disabled by default. See [`CachePolicy`].
+    ///
+    /// A lookup is answered from the cache if an identical one (the same method and parameters, ignoring the case
+    /// of names) was made within the TTL, without sending a request. To skip the cache for particular calls, use
+    /// [`Scrobbler::with_call_options`] with `bypass_cache` set. Changing the policy discards everything cached.
+    /// The cache is shared with every clone of this `Scrobbler`.
+    ///
+    /// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+    /// [`CachePolicy`]: struct.CachePolicy.html
+    /// [`Scrobbler::with_call_options`]: struct.Scrobbler.html#method.with_call_options
+    pub fn set_cache(&mut self, policy: Option<CachePolicy>) {
+        self.client.set_cache(policy);
+    }
+
+    /// Returns the cache policy, or `None` if caching is disabled. See [`Scrobbler::set_cache`].
+    ///
+    /// [`Scrobbler::set_cache`]: struct.Scrobbler.html#method.set_cache
+    pub fn cache_policy(&self) -> Option<CachePolicy> {
+        self.client.cache_policy()
+    }
+
+    /// Discards every cached response, for this `Scrobbler` and every clone of it
+    pub fn clear_cache(&self) {
+        self.client.clear_cache();
+    }
+
+    /// Asks Last.fm to autocorrect, or not to autocorrect, the artist and track names of lookups such as
+    /// [`Scrobbler::track_info`] to its canonical ones, ie. for an importer matching messy tags. With `None`, the
+    /// default, the parameter isn't sent and Last.fm doesn't autocorrect. [`TrackInfo::autocorrected`] tells
+    /// whether a response's names were corrected. Override it for particular calls with
+    /// [`Scrobbler::with_call_options`].
+    ///
+    /// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+    /// [`TrackInfo::autocorrected`]: responses/struct.TrackInfo.html#method.autocorrected
+    /// [`Scrobbler::with_call_options`]: struct.Scrobbler.html#method.with_call_options
+    pub fn set_autocorrect(&mut self, autocorrect: Option<bool>) {
+        self.client.set_autocorrect(autocorrect);
+    }
+
+    /// Returns whether lookups ask Last.fm to autocorrect names. See [`Scrobbler::set_autocorrect`].
+    ///
+    /// [`Scrobbler::set_autocorrect`]: struct.Scrobbler.html#method.set_autocorrect
+    pub fn autocorrect(&self) -> Option<bool> {
+        self.client.autocorrect()
+    }
+
+    /// Asks Last.fm for the biographies and wiki text of [`Scrobbler::artist_info`] and [`Scrobbler::album_info`]
+    /// in the given language, or in English, the default, with `None`. Without a language the `lang` parameter
+    /// isn't sent. Override it for particular calls with [`Scrobbler::with_call_options`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// scrobbler.set_language(Some(Language::parse("de-DE")?));
+    /// let info = scrobbler.artist_info("Kraftwerk")?;
+    /// ```
+    ///
+    /// [`Scrobbler::artist_info`]: struct.Scrobbler.html#method.artist_info
+    /// [`Scrobbler::album_info`]: struct.Scrobbler.html#method.album_info
+    /// [`Scrobbler::with_call_options`]: struct.Scrobbler.html#method.with_call_options
+    pub fn set_language(&mut self, language: Option<Language>) {
+        self.client.set_language(language);
+    }
+
+    /// Returns the language lookups ask for text in. See [`Scrobbler::set_language`].
+    ///
+    /// [`Scrobbler::set_language`]: struct.Scrobbler.html#method.set_language
+    pub fn language(&self) -> Option<&Language> {
+        self.client.language()
+    }
+
+    /// Keeps a log of the last `capacity` API calls, readable with [`Scrobbler::recent_calls`], or stops logging
+    /// with a capacity of 0. Disabled by default. Changing the capacity discards any calls already logged.
+    ///
+    /// The log is for debugging problems in the field, ie. "my plays stopped appearing": it records each call's
+    /// method, outcome, latency and correlation ID, but no parameter values (see [`CallRecord`]). Its memory use is
+    /// fixed by its capacity, and is allocated up front. The log is shared with every clone of this `Scrobbler`.
+    ///
+    /// [`Scrobbler::recent_calls`]: struct.Scrobbler.html#method.recent_calls
+    /// [`CallRecord`]: struct.CallRecord.html
+    pub fn set_call_log_capacity(&mut self, capacity: usize) {
+        self.client.set_call_log_capacity(capacity);
+    }
+
+    /// Returns the calls in the call log, oldest first. Empty unless enabled with
+    /// [`Scrobbler::set_call_log_capacity`]. Calls skipped by dry-run mode are not logged.
+    ///
+    /// [`Scrobbler::set_call_log_capacity`]: struct.Scrobbler.html#method.set_call_log_capacity
+    pub fn recent_calls(&self) -> Vec<CallRecord> {
+        self.client.recent_calls()
+    }
+
+    /// Empties the call log, keeping its capacity
+    pub fn clear_recent_calls(&self) {
+        self.client.clear_recent_calls();
+    }
+
+    /// Sets the extension parameter a [`Scrobble`]'s media kind is sent as, or stops sending it with `None`. By
+    /// default it's sent as `"mediaKind"`, ie. `mediaKind=podcast`.
+    ///
+    /// The media kind is only sent to servers other than Last.fm (see [`Scrobbler::new_with_host`]), which doesn't
+    /// accept it, and only for scrobbles which aren't music. See [`MediaKind`].
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::new_with_host`]: struct.Scrobbler.html#method.new_with_host
+    /// [`MediaKind`]: enum.MediaKind.html
+    pub fn set_media_kind_param(&mut self, name: Option<&str>) {
+        self.media_kind_param = name.map(str::to_owned);
+    }
+
+    /// Makes [`Scrobbler::scrobble`] and [`Scrobbler::scrobble_at`] return an error when Last.fm accepts the
+    /// request but ignores the scrobble. Disabled by default, in which case ignored scrobbles are returned as `Ok`
+    /// responses and can be detected with [`ScrobbleResponse::accepted`].
+    ///
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::scrobble_at`]: struct.Scrobbler.html#method.scrobble_at
+    /// [`ScrobbleResponse::accepted`]: responses/struct.ScrobbleResponse.html#method.accepted
+    pub fn set_fail_on_ignored(&mut self, fail_on_ignored: bool) {
+        self.fail_on_ignored = fail_on_ignored;
+    }
+
+    /// Makes [`Scrobbler::now_playing`] best effort: a now playing update which fails for a transient reason (see
+    /// [`ScrobblerError::is_retryable`]), ie. a timeout, returns an `Ok` response whose [`outcome`] is `Skipped`,
+    /// rather than an error. The failure is still reported to the event observer, as a `NowPlayingSkipped` event.
+    /// Other errors, ie. `NotAuthenticated`, are returned as usual. Disabled by default; scrobbles are never
+    /// affected.
+    ///
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    /// [`ScrobblerError::is_retryable`]: struct.ScrobblerError.html#method.is_retryable
+    /// [`outcome`]: responses/struct.NowPlayingResponse.html#method.outcome
+    pub fn set_now_playing_best_effort(&mut self, best_effort: bool) {
+        self.now_playing_best_effort = best_effort;
+    }
+
+    /// Returns true if now playing updates are best effort. See [`Scrobbler::set_now_playing_best_effort`].
+    ///
+    /// [`Scrobbler::set_now_playing_best_effort`]: struct.Scrobbler.html#method.set_now_playing_best_effort
+    pub fn now_playing_best_effort(&self) -> bool {
+        self.now_playing_best_effort
+    }
+
+    /// Sets the earliest timestamp (seconds since the Unix epoch) accepted for a scrobble. Defaults to 1009843200,
+    /// 2002-01-01 00:00:00 UTC, when Audioscrobbler launched.
+    ///
+    /// Scrobbles timestamped before the minimum are rejected before anything is sent, whether the timestamp was set
+    /// with [`Scrobble::with_timestamp`], passed to [`Scrobbler::scrobble_at`], or defaulted to the current time (ie.
+    /// on a device whose clock hasn't been set). Batches are rejected as a whole if any scrobble in them is too old.
+    /// The minimum only needs lowering for compatible servers which accept older scrobbles, ie. in tests.
+    ///
+    /// [`Scrobble::with_timestamp`]: struct.Scrobble.html#method.with_timestamp
+    /// [`Scrobbler::scrobble_at`]: struct.Scrobbler.html#method.scrobble_at
+    pub fn set_min_timestamp(&mut self, min_timestamp: u64) {
+        self.min_timestamp = min_timestamp;
+    }
+
+    /// Sets how old a scrobble may be, or disables the check with `None`. Defaults to 14 days, beyond which Last.fm
+    /// silently ignores scrobbles rather than failing them.
+    ///
+    /// [`Scrobbler::scrobble`] and [`Scrobbler::scrobble_batch`] return an error of kind
+    /// `ErrorKind::InvalidTimestamp`, without sending anything, for a scrobble timestamped further in the past, as
+    /// well as for one more than 10 minutes in the future, or so far in the future (after the year 5000) that it's
+    /// probably in milliseconds rather than seconds. Only the check of old scrobbles can be disabled, ie. for
+    /// compatible servers which accept them.
+    ///
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    pub fn set_max_scrobble_age(&mut self, max_age: Option<Duration>) {
+        self.max_scrobble_age = max_age;
+    }
+
+    pub(crate) fn max_scrobble_age(&self) -> Option<Duration> {
+        self.max_scrobble_age
+    }
+
+    /// Truncates over-long names to Last.fm's limits, instead of rejecting the scrobble: 1024 characters for track
+    /// names, and 255 for artist, album and album artist names. Disabled by default, in which case
+    /// [`Scrobbler::now_playing`], [`Scrobbler::scrobble`] and [`Scrobbler::scrobble_batch`] return an error for a
+    /// track with a longer name, without sending anything.
+    ///
+    /// Names are cut at a character boundary, so are always valid UTF-8. With the `unicode` feature, they're cut at a
+    /// grapheme cluster boundary, so that a letter is never separated from a combining accent; otherwise a cut may
+    /// split a grapheme cluster. Each truncation is reported to the event observer as a
+    /// [`ScrobblerEvent::FieldTruncated`] event. The `Scrobble`s themselves are left untouched.
+    ///
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`ScrobblerEvent::FieldTruncated`]: enum.ScrobblerEvent.html#variant.FieldTruncated
+    pub fn set_truncate_long_fields(&mut self, truncate_long_fields: bool) {
+        self.truncation.enabled = truncate_long_fields;
+    }
+
+    /// Enables or disables strict mode. Disabled by default.
+    ///
+    /// By default, the crate works around some problems with submitted tracks rather than failing the call: it
+    /// truncates over-long names (if enabled with [`Scrobbler::set_truncate_long_fields`]), stamps scrobbles
+    /// without a timestamp with the current time, and leaves out blank album names. In strict mode, any such
+    /// [`ScrobbleIssue`] fails the call instead, without sending anything, with an error of kind
+    /// `ErrorKind::Strict` which lists every issue found (see [`ScrobblerError::issues`]). This suits pipelines
+    /// which validate an import; [`Scrobbler::validate_batch`] lists the same issues without submitting anything.
+    ///
+    /// In strict mode, [`Scrobbler::scrobble_batch_chunks`] checks the whole import before submitting any of it.
+    ///
+    /// [`Scrobbler::set_truncate_long_fields`]: struct.Scrobbler.html#method.set_truncate_long_fields
+    /// [`ScrobbleIssue`]: enum.ScrobbleIssue.html
+    /// [`ScrobblerError::issues`]: struct.ScrobblerError.html#method.issues
+    /// [`Scrobbler::validate_batch`]: struct.Scrobbler.html#method.validate_batch
+    /// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Ends names truncated by [`Scrobbler::set_truncate_long_fields`] with an ellipsis (`…`), which counts towards
+    /// the field's limit. Disabled by default.
+    ///
+    /// [`Scrobbler::set_truncate_long_fields`]: struct.Scrobbler.html#method.set_truncate_long_fields
+    pub fn set_truncation_ellipsis(&mut self, truncation_ellipsis: bool) {
+        self.truncation.ellipsis = truncation_ellipsis;
+    }
+
+    /// Sets the largest response body accepted from the server, in bytes; 4 MiB by default. Genuine Last.fm
+    /// responses are at most a few KB, so this only guards against misbehaving servers, ie. a broken proxy serving a
+    /// huge error page.
+    ///
+    /// A response which exceeds the limit is abandoned as soon as it does, without being read any further, and the
+    /// call fails with an error of kind `ErrorKind::ResponseTooLarge`. Such calls aren't retried. The limit applies
+    /// to every API call.
+    pub fn set_max_response_size(&mut self, max_response_size: u64) {
+        self.client.set_max_response_size(max_response_size);
+    }
+
+    /// Sends each API call's correlation ID to the server as an `X-Request-Id` header. Disabled by default, as
+    /// Last.fm ignores the header, but it can be useful to match requests logged by a relay or proxy to errors
+    /// returned by the `Scrobbler` (see [`ScrobblerError::correlation_id`]).
+    ///
+    /// [`ScrobblerError::correlation_id`]: struct.ScrobblerError.html#method.correlation_id
+    pub fn set_send_request_id(&mut self, send_request_id: bool) {
+        self.client.set_send_request_id(send_request_id);
+    }
+
+    /// Adds a random `nonce` parameter, a version 4 UUID, to every signed request, and signs it along with the
+    /// rest. Disabled by default. Last.fm ignores the parameter, but a relay can use it to recognise a request it has
+    /// already handled. Retries of a call are sent with the same nonce.
+    ///
+    /// Each call's nonce is kept in the call log (see [`CallRecord::nonce`]).
+    ///
+    /// [`CallRecord::nonce`]: struct.CallRecord.html#method.nonce
+    pub fn set_include_nonce(&mut self, include_nonce: bool) {
+        self.client.set_include_nonce(include_nonce);
+    }
+
+    /// Returns whether signed requests carry a `nonce`. See [`Scrobbler::set_include_nonce`].
+    ///
+    /// [`Scrobbler::set_include_nonce`]: struct.Scrobbler.html#method.set_include_nonce
+    pub fn include_nonce(&self) -> bool {
+        self.client.include_nonce()
+    }
+
+    /// Moves the `Scrobbler` to the new URL whenever the server permanently redirects (`301` or `308`) a call, so that
+    /// every later call is sent there. Disabled by default. The `Scrobbler`'s clones move with it.
+    ///
+    /// Either way, read-only calls follow up to 5 redirects. Signed calls which change anything, such as scrobbles,
+    /// are never re-sent to another URL automatically, as a redirected `POST` often loses its body: they fail with an
+    /// error of kind `ErrorKind::Redirected`, whose [`ScrobblerError::redirect_location`] is the new URL, and aren't
+    /// retried. With this enabled, sending such a call again (ie. from a [`ScrobbleQueue`]) sends it to the new URL.
+    ///
+    /// [`ScrobblerError::redirect_location`]: struct.ScrobblerError.html#method.redirect_location
+    /// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+    pub fn set_update_base_url_on_redirect(&mut self, update_base_url_on_redirect: bool) {
+        self.client.set_update_base_url_on_redirect(update_base_url_on_redirect);
+    }
+
+    /// Sends an extra HTTP header with every API request, replacing any extra header with the same name. Useful for
+    /// relays or proxies which authenticate callers, ie. with an `Authorization: Bearer` header. Fails if `name` or
+    /// `value` isn't legal in an HTTP header.
+    ///
+    /// Headers aren't part of the request signature. Values of headers whose names contain `authorization` or
+    /// `cookie` are never logged. See also [`Scrobbler::with_call_options`], to send headers with only some calls.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// scrobbler.set_extra_header("Authorization", &format!("Bearer {}", relay_token))?;
+    /// ```
+    ///
+    /// [`Scrobbler::with_call_options`]: struct.Scrobbler.html#method.with_call_options
+    pub fn set_extra_header(&mut self, name: &str, value: &str) -> Result<()> {
+        self.client.extra_headers_mut().insert(name, value)
+    }
+
+    /// Stops sending an extra HTTP header set with [`Scrobbler::set_extra_header`]
+    ///
+    /// [`Scrobbler::set_extra_header`]: struct.Scrobbler.html#method.set_extra_header
+    pub fn remove_extra_header(&mut self, name: &str) {
+        self.client.extra_headers_mut().remove(name);
+    }
+
+    /// Returns a clone of the `Scrobbler` which applies the given [`CallOptions`] to every call, for one-off calls
+    /// which need different options. The options' headers are sent in addition to the `Scrobbler`'s extra headers,
+    /// replacing any with the same name. As with any clone, it shares this `Scrobbler`'s transport, rate limit,
+    /// circuit breaker, call log and cache.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut options = CallOptions::default();
+    /// options.headers.insert("X-Relay-Route", "eu")?;
+    /// scrobbler.with_call_options(&options).scrobble(&track)?;
+    /// ```
+    ///
+    /// [`CallOptions`]: struct.CallOptions.html
+    #[must_use]
+    pub fn with_call_options(&self, options: &CallOptions) -> Self {
+        let mut scrobbler = self.clone();
+        scrobbler.client.extra_headers_mut().extend(&options.headers);
+        if options.bypass_cache {
+            scrobbler.client.set_bypass_cache(true);
+        }
+        if options.autocorrect.is_some() {
+            scrobbler.client.set_autocorrect(options.autocorrect);
+        }
+        if options.language.is_some() {
+            scrobbler.client.set_language(options.language.clone());
+        }
+        scrobbler
+    }
+
+    /// Signs requests with the given [`RequestSigner`], instead of the standard Last.fm MD5 signature. Only needed
+    /// for compatible servers which sign requests differently.
+    ///
+    /// [`RequestSigner`]: signing/trait.RequestSigner.html
+    pub fn set_signer<S>(&mut self, signer: S)
+    where
+        S: RequestSigner + 'static,
+    {
+        self.client.set_signer(Arc::new(signer));
+    }
+
+    // Prepares a scrobble for submission, collecting the issues worked around in lenient mode: truncating over-long
+    // names if enabled, leaving out a blank album and, if `untimed` is set, generating a missing timestamp. A track
+    // without an artist or track name can't be worked around. `position` is the scrobble's index in a batch.
+    fn prepare<'a>(
+        &self,
+        scrobble: &'a Scrobble,
+        position: Option<usize>,
+        untimed: bool,
+        issues: &mut Vec<ScrobbleIssue>,
+    ) -> Result<Cow<'a, Scrobble>> {
+        let names = [("artist", scrobble.artist()), ("track", scrobble.track())];
+        if let Some((field, _)) = names.iter().find(|(_, name)| name.trim().is_empty()) {
+            return Err(ScrobblerError::missing_name(position, field));
+        }
+
+        let prepared = self.check_field_lengths(scrobble, position, issues)?;
+        if !scrobble.album().is_empty() && scrobble.album().trim().is_empty() {
+            issues.push(ScrobbleIssue::BlankAlbumOmitted { position });
+        }
+        if untimed && scrobble.timestamp().is_none() {
+            issues.push(ScrobbleIssue::TimestampGenerated { position });
+        }
+
+        Ok(prepared)
+    }
+
+    // The parameters a track is submitted with, including any extension parameters the server accepts
+    fn submission_params(&self, scrobble: &Scrobble) -> Params {
+        let mut params = scrobble.params();
+        if let Some(name) = self.media_kind_param.as_deref() {
+            if self.client.is_custom_host() && !scrobble.media_kind().is_music() {
+                params.insert_extension(name, scrobble.media_kind().as_str());
+            }
+        }
+
+        params
+    }
+
+    // Fails with every issue found in strict mode. Otherwise the issues are worked around, and truncations reported
+    // to the event observer.
+    fn report_issues(&self, issues: &[ScrobbleIssue]) -> Result<()> {
+        if self.strict && !issues.is_empty() {
+            return Err(ScrobblerError::strict(issues.to_vec()));
+        }
+
+        for issue in issues {
+            if let ScrobbleIssue::FieldTruncated {
+                field,
+                original_chars,
+                truncated_chars,
+                ..
+            } = issue
+            {
+                self.client.notify(&ScrobblerEvent::FieldTruncated {
+                    field: field.clone(),
+                    original_chars: *original_chars,
+                    truncated_chars: *truncated_chars,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks the lengths of a scrobble's names, truncating over-long ones if enabled
+    fn check_field_lengths<'a>(
+        &self,
+        scrobble: &'a Scrobble,
+        position: Option<usize>,
+        issues: &mut Vec<ScrobbleIssue>,
+    ) -> Result<Cow<'a, Scrobble>> {
+        let too_long = scrobble
+            .names()
+            .iter()
+            .map(|(field, value)| (*field, value.chars().count()))
+            .find(|(field, chars)| *chars > max_field_chars(field));
+
+        match too_long {
+            None => Ok(Cow::Borrowed(scrobble)),
+            Some((field, chars)) if !self.truncation.enabled => {
+                let subject = position.map_or_else(|| "Scrobble".to_owned(), |i| format!("Scrobble {i} in batch"));
+                Err(ScrobblerError::new(format!(
+                    "{subject} has a {field} name {chars} characters long, over the maximum of {}",
+                    max_field_chars(field)
+                )))
+            }
+            Some(_) => {
+                let mut truncated = scrobble.clone();
+                for (field, value) in truncated.names_mut() {
+                    let original_chars = value.chars().count();
+                    if original_chars > max_field_chars(field) {
+                        *value = truncate_chars(value, max_field_chars(field), self.truncation.ellipsis);
+                        issues.push(ScrobbleIssue::FieldTruncated {
+                            position,
+                            field: field.to_owned(),
+                            original_chars,
+                            truncated_chars: value.chars().count(),
+                        });
+                    }
+                }
+
+                Ok(Cow::Owned(truncated))
+            }
+        }
+    }
 
-        Self { client }
+    fn stub_auth(&self) -> bool {
+        self.dry_run && self.dry_run_auth
     }
 
     /// Authenticates a Last.fm user with the given username and password. 
@@ -87,7 +1027,43 @@ impl Scrobbler {
         password: &str,
     ) -> Result<SessionResponse> {
         self.client.set_user_credentials(username, password);
-        Ok(self.client.authenticate_with_password()?)
+        if self.stub_auth() {
+            return self.client.dry_run_authenticate(&ApiOperation::AuthMobileSession);
+        }
+        self.client.authenticate_with_password()
+    }
+
+    /// Requests an authentication token, the first step of the 'Desktop' Last.fm auth flow. The request is signed
+    /// with the API secret but needs no authenticated user.
+    ///
+    /// The token isn't usable until the user authorizes it, by opening the URL given by
+    /// [`Scrobbler::authorization_url`] in a browser. Once they have, [`Scrobbler::authenticate_with_token`]
+    /// exchanges it for a session; tokens expire unused after 60 minutes.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut scrobbler = Scrobbler::new(...);
+    /// let token = scrobbler.request_auth_token()?;
+    /// println!("Authorize this application at {}", scrobbler.authorization_url(&token));
+    /// // ... once the user has authorized the token
+    /// let response = scrobbler.authenticate_with_token(&token)?;
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [Last.fm Desktop Auth Flow Documentation](https://www.last.fm/api/desktopauth)
+    ///
+    /// [`Scrobbler::authorization_url`]: struct.Scrobbler.html#method.authorization_url
+    /// [`Scrobbler::authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
+    pub fn request_auth_token(&self) -> Result<String> {
+        self.client.request_auth_token()
+    }
+
+    /// Builds the URL at which the user authorizes an authentication token from
+    /// [`Scrobbler::request_auth_token`]. Nothing is sent.
+    ///
+    /// [`Scrobbler::request_auth_token`]: struct.Scrobbler.html#method.request_auth_token
+    pub fn authorization_url(&self, token: &str) -> String {
+        self.client.authorization_url(token)
     }
 
     /// Authenticates a Last.fm user with an authentication token. This method supports both the 'Web' and 'Desktop'
@@ -107,7 +1083,10 @@ impl Scrobbler {
     /// [Last.fm Desktop Auth Flow Documentation](https://www.last.fm/api/desktopauth)
     pub fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse> {
         self.client.set_user_token(token);
-        Ok(self.client.authenticate_with_token()?)
+        if self.stub_auth() {
+            return self.client.dry_run_authenticate(&ApiOperation::AuthWebSession);
+        }
+        self.client.authenticate_with_token()
     }
 
     /// Authenticates a Last.fm user with a session key. 
@@ -116,13 +1095,17 @@ impl Scrobbler {
     /// ```ignore
     /// let mut scrobbler = Scrobbler::new(...);
     /// let session_key = "securely-saved-old-session-key";
-    /// let response = scrobbler.authenticate_with_session_key(session_key);
+    /// scrobbler.authenticate_with_session_key(session_key)?;
     /// ```
     /// 
     /// # Response
     /// This method has no response: the crate expects a valid session key to be provided here and has no way to
     /// indicate if an invalidated key has been used. Clients will need to manually detect any authentication issues
-    /// via API call error responses.
+    /// via API call error responses. An empty key is rejected with an error.
+    ///
+    /// Until a session key is set, by this method or by authenticating, submission methods (ie.
+    /// [`Scrobbler::scrobble`] and [`Scrobbler::now_playing`]) fail with an error of kind
+    /// `ErrorKind::NotAuthenticated`, without sending a request.
     /// 
     /// # A Note on Session Keys
     /// When authenticating successfully with username/password or with an authentication token (
@@ -138,11 +1121,121 @@ impl Scrobbler {
     /// [`authenticate_with_password`]: struct.Scrobbler.html#method.authenticate_with_password
     /// [`authenticate_with_token`]: struct.Scrobbler.html#method.authenticate_with_token
     /// [`Scrobbler::session_key`]: struct.Scrobbler.html#method.session_key
-    pub fn authenticate_with_session_key(&mut self, session_key: &str) {
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::now_playing`]: struct.Scrobbler.html#method.now_playing
+    pub fn authenticate_with_session_key(&mut self, session_key: &str) -> Result<()> {
+        self.client.authenticate_with_session_key(session_key)
+    }
+
+    /// Authenticates with a typed [`SessionKey`], exactly as [`Scrobbler::authenticate_with_session_key`] does.
+    ///
+    /// [`SessionKey`]: struct.SessionKey.html
+    /// [`Scrobbler::authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+    pub fn set_session_key(&mut self, session_key: SessionKey) -> Result<()> {
         self.client.authenticate_with_session_key(session_key)
     }
 
-    /// Registers the given [`Scrobble`]/track as the currently authenticated user's "now playing" track.
+    /// Gets the name of the authenticated user, if known. Authenticating with a password or a token sets it; a
+    /// restored session key doesn't say whose session it is, so see [`Scrobbler::set_username`].
+    ///
+    /// [`Scrobbler::set_username`]: struct.Scrobbler.html#method.set_username
+    pub fn username(&self) -> Option<&str> {
+        self.client.username()
+    }
+
+    /// Sets the name of the authenticated user, for methods which read the user's own history (ie.
+    /// [`Scrobbler::plan_import`]), after authenticating with a session key. Authenticating again replaces it.
+    ///
+    /// [`Scrobbler::plan_import`]: struct.Scrobbler.html#method.plan_import
+    pub fn set_username(&mut self, username: &str) {
+        self.client.set_username(username);
+    }
+
+    /// Checks that the session key is still valid, ie. one restored with
+    /// [`Scrobbler::authenticate_with_session_key`], so that the user can be asked to authenticate again before
+    /// anything is scrobbled rather than after a scrobble fails. Returns the user's profile, as
+    /// [`Scrobbler::user_info`] does, and sets [`Scrobbler::username`] to whose session it is.
+    ///
+    /// A session Last.fm no longer accepts fails with an error which says so, for which
+    /// [`ScrobblerError::is_auth_failure`] is true and [`ScrobblerError::error_code`] is `InvalidSessionKey`; any
+    /// other failure says nothing about the session. Fails with `ErrorKind::NotAuthenticated` without a session key.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// scrobbler.authenticate_with_session_key(&stored_key)?;
+    /// match scrobbler.verify_session() {
+    ///     Ok(user) => println!("Scrobbling as {}", user.name()),
+    ///     Err(err) if err.is_auth_failure() => prompt_to_sign_in(),
+    ///     Err(err) => eprintln!("Couldn't check the session: {}", err),
+    /// }
+    /// ```
+    ///
+    /// [`Scrobbler::authenticate_with_session_key`]: struct.Scrobbler.html#method.authenticate_with_session_key
+    /// [`Scrobbler::user_info`]: struct.Scrobbler.html#method.user_info
+    /// [`Scrobbler::username`]: struct.Scrobbler.html#method.username
+    /// [`ScrobblerError::is_auth_failure`]: struct.ScrobblerError.html#method.is_auth_failure
+    /// [`ScrobblerError::error_code`]: struct.ScrobblerError.html#method.error_code
+    pub fn verify_session(&mut self) -> Result<UserInfo> {
+        let info = self.client.send_user_info().map_err(|err| {
+            if err.is_auth_failure() {
+                err.context("The session is no longer valid")
+            } else {
+                err
+            }
+        })?;
+        self.client.set_username(info.name());
+
+        Ok(info)
+    }
+
+    /// Looks up the authenticated user's profile and listening statistics. Needs an authenticated `Scrobbler`, and
+    /// is never cached.
+    ///
+    /// # Last.fm API Documentation
+    /// [user.getInfo API Method Documentation](https://www.last.fm/api/show/user.getInfo)
+    pub fn user_info(&self) -> Result<UserInfo> {
+        self.client.send_user_info()
+    }
+
+    /// Calls any Last.fm API method, ie. one the crate has no method for, such as `"album.getTags"`, and returns the
+    /// response as it was decoded. Needs an authenticated `Scrobbler`.
+    ///
+    /// The call is signed, includes the session key, and is sent as a `POST`, so it's never cached. It's retried
+    /// under the policy for Now Playing and Scrobble requests (see [`Scrobbler::set_retry_policy_for`]), and
+    /// counts towards the rate limit like any other call. `params` must not set the parameters the `Scrobbler` adds
+    /// itself (`method`, `api_key`, `sk`, `api_sig`, `format` and `nonce`). As the call may change something,
+    /// it isn't sent in dry-run mode, and fails instead.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut params = HashMap::new();
+    /// params.insert("artist".to_owned(), "Cher".to_owned());
+    /// params.insert("album".to_owned(), "Believe".to_owned());
+    /// let tags = scrobbler.call_signed("album.getTags", &params)?;
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [API Method Documentation](https://www.last.fm/api)
+    ///
+    /// [`Scrobbler::set_retry_policy_for`]: struct.Scrobbler.html#method.set_retry_policy_for
+    pub fn call_signed(&self, method: &str, params: &HashMap<String, String>) -> Result<serde_json::Value> {
+        self.check_authenticated()?;
+        if let Some(key) = params.keys().find(|key| RESERVED_PARAMS.contains(&key.as_str())) {
+            return Err(ScrobblerError::reserved_parameter(key, method));
+        }
+        if self.dry_run {
+            return Err(ScrobblerError::dry_run(method));
+        }
+
+        let mut call_params = Params::new();
+        for (key, value) in params {
+            call_params.insert_extension(key, value.as_str());
+        }
+        self.client.send_signed_call(method, call_params)
+    }
+
+    /// Registers the given track as the currently authenticated user's "now playing" track: a [`NowPlaying`], or a
+    /// [`Scrobble`] which is converted to one.
     /// 
     /// Most scrobbling clients will set the now-playing track as soon as the user starts playing it; this makes it 
     /// appear temporarily as the 'now listening' track on the user's profile. However use of this endpoint/method
@@ -164,20 +1257,145 @@ impl Scrobbler {
     /// 
     /// # Response
     /// On success a [`NowPlayingResponse`] is returned. This can often be ignored (as in the example code), but it
-    /// contains information that may be of use to some clients. 
+    /// contains information that may be of use to some clients. Transient failures can be skipped rather than
+    /// returned, with [`Scrobbler::set_now_playing_best_effort`].
+    ///
+    /// # Timestamps
+    /// The status always means "playing now", so can't be backdated: a `Scrobble`'s timestamp (and whether the user
+    /// chose the track) is ignored, and never sent. A [`NowPlaying`] can't hold either.
     /// 
     /// # Last.fm API Documentation
     /// [track.updateNowPlaying API Method Documentation](https://www.last.fm/api/show/track.updateNowPlaying)
     /// 
     /// [Now Playing Request Documentation](https://www.last.fm/api/scrobbling#now-playing-requests)
     /// 
+    /// [`NowPlaying`]: struct.NowPlaying.html
     /// [`Scrobble`]: struct.Scrobble.html
     /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    /// [`Scrobbler::set_now_playing_best_effort`]: struct.Scrobbler.html#method.set_now_playing_best_effort
     /// [`NowPlayingResponse`]: responses/struct.NowPlayingResponse.html
-    pub fn now_playing(&self, scrobble: &Scrobble) -> Result<NowPlayingResponse> {
-        let params = scrobble.as_map();
+    pub fn now_playing(&self, track: impl Into<NowPlaying>) -> Result<NowPlayingResponse> {
+        let track = track.into();
+        let result = self.now_playing_submission(&track).and_then(|submission| match submission {
+            Submission::DryRun(response) => Ok(response),
+            Submission::Send(params) => self
+                .client
+                .send_now_playing(params)
+                .map(|response| response.with_expiry(track.as_scrobble())),
+        });
+        self.now_playing_result(&track, result)
+    }
 
-        Ok(self.client.send_now_playing(&params)?)
+    // Skips a now playing update which failed for a transient reason, if updates are best effort
+    pub(crate) fn now_playing_result(
+        &self,
+        track: &NowPlaying,
+        result: Result<NowPlayingResponse>,
+    ) -> Result<NowPlayingResponse> {
+        match result {
+            Err(err) if self.now_playing_best_effort && err.is_retryable() => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %err, "Skipped now playing update");
+                let reason = err.to_string();
+                self.client.notify(&ScrobblerEvent::NowPlayingSkipped { reason: reason.clone() });
+                Ok(NowPlayingResponse::from_scrobble(track.as_scrobble()).into_skipped(reason))
+            }
+            result => result,
+        }
+    }
+
+    pub(crate) fn now_playing_submission(&self, track: &NowPlaying) -> Result<Submission<NowPlayingResponse>> {
+        self.check_authenticated()?;
+        let mut issues = Vec::new();
+        let scrobble = self.prepare(track.as_scrobble(), None, false, &mut issues)?;
+        self.report_issues(&issues)?;
+        let scrobble = self.defaults.apply(scrobble, true);
+        let params = self.submission_params(&scrobble);
+
+        if self.dry_run {
+            self.client
+                .sign_authenticated_request(&ApiOperation::NowPlaying, params)?;
+            return Ok(Submission::DryRun(NowPlayingResponse::from_scrobble(&scrobble).into_dry_run()));
+        }
+
+        Ok(Submission::Send(params))
+    }
+
+    /// Looks up Last.fm's metadata and listening statistics for a track. Doesn't need an authenticated `Scrobbler`.
+    ///
+    /// The response is cached if a cache is enabled with [`Scrobbler::set_cache`], and an identical lookup made
+    /// while it's cached is answered without sending a request.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let info = scrobbler.track_info("Cher", "Believe")?;
+    /// println!("{} has been played {} times", info.name(), info.playcount());
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [track.getInfo API Method Documentation](https://www.last.fm/api/show/track.getInfo)
+    ///
+    /// [`Scrobbler::set_cache`]: struct.Scrobbler.html#method.set_cache
+    pub fn track_info(&self, artist: &str, track: &str) -> Result<TrackInfo> {
+        self.reader().track_info(artist, track)
+    }
+
+    /// Looks up Last.fm's metadata, listening statistics and biography for an artist, with the biography in the
+    /// language set with [`Scrobbler::set_language`] where Last.fm has it. Doesn't need an authenticated
+    /// `Scrobbler`, and is cached like [`Scrobbler::track_info`].
+    ///
+    /// # Last.fm API Documentation
+    /// [artist.getInfo API Method Documentation](https://www.last.fm/api/show/artist.getInfo)
+    ///
+    /// [`Scrobbler::set_language`]: struct.Scrobbler.html#method.set_language
+    /// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+    pub fn artist_info(&self, artist: &str) -> Result<ArtistInfo> {
+        self.reader().artist_info(artist)
+    }
+
+    /// Looks up Last.fm's metadata, listening statistics and wiki text for an album, with the wiki text in the
+    /// language set with [`Scrobbler::set_language`] where Last.fm has it. Doesn't need an authenticated
+    /// `Scrobbler`, and is cached like [`Scrobbler::track_info`].
+    ///
+    /// # Last.fm API Documentation
+    /// [album.getInfo API Method Documentation](https://www.last.fm/api/show/album.getInfo)
+    ///
+    /// [`Scrobbler::set_language`]: struct.Scrobbler.html#method.set_language
+    /// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+    pub fn album_info(&self, artist: &str, album: &str) -> Result<AlbumInfo> {
+        self.reader().album_info(artist, album)
+    }
+
+    /// Looks up the artist and track names Last.fm would correct the given ones to, ie. to tidy up metadata before
+    /// scrobbling it. Names Last.fm has no correction for are returned as given. Doesn't need an authenticated
+    /// `Scrobbler`, and is cached like [`Scrobbler::track_info`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let correction = scrobbler.track_correction("Guns N Roses", "Sweet Child O' Mine")?;
+    /// let track = Scrobble::new(correction.artist().text(), correction.track().text(), album);
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [track.getCorrection API Method Documentation](https://www.last.fm/api/show/track.getCorrection)
+    ///
+    /// [`Scrobbler::track_info`]: struct.Scrobbler.html#method.track_info
+    pub fn track_correction(&self, artist: &str, track: &str) -> Result<CorrectionResponse> {
+        self.reader().track_correction(artist, track)
+    }
+
+    /// Returns a [`LastFmReader`] for Last.fm's read-only methods, sharing this `Scrobbler`'s transport,
+    /// credentials, rate limit, circuit breaker, call log and cache. Reads need no authentication.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let reader = scrobbler.reader();
+    /// thread::spawn(move || reader.track_info("Cher", "Believe"));
+    /// ```
+    ///
+    /// [`LastFmReader`]: struct.LastFmReader.html
+    pub fn reader(&self) -> LastFmReader {
+        LastFmReader::from_client(self.client.clone())
     }
 
     /// Registers a scrobble (play) of the given [`Scrobble`]/track.
@@ -197,7 +1415,10 @@ impl Scrobbler {
     /// 
     /// # Response
     /// On success a [`ScrobbleResponse`] is returned. This can often be ignored (as in the example code), but it
-    /// contains information that may be of use to some clients. 
+    /// contains information that may be of use to some clients. Note that Last.fm may still have ignored the
+    /// scrobble; check [`ScrobbleResponse::accepted`], or see [`Scrobbler::set_fail_on_ignored`]. If the
+    /// [`Scrobble`] had no timestamp, the current time was used; [`ScrobbleResponse::submitted_timestamp`] returns
+    /// the timestamp actually submitted.
     /// 
     /// # Last.fm API Documentation
     /// [track.scrobble API Method Documention](https://www.last.fm/api/show/track.scrobble)
@@ -207,15 +1428,125 @@ impl Scrobbler {
     /// [`Scrobbler`]: struct.Scrobbler.html
     /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
     /// [`ScrobbleResponse`]: responses/struct.ScrobbleResponse.html
+    /// [`ScrobbleResponse::accepted`]: responses/struct.ScrobbleResponse.html#method.accepted
+    /// [`ScrobbleResponse::submitted_timestamp`]: responses/struct.ScrobbleResponse.html#method.submitted_timestamp
+    /// [`Scrobbler::set_fail_on_ignored`]: struct.Scrobbler.html#method.set_fail_on_ignored
     pub fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse> {
-        let mut params = scrobble.as_map();
-        let current_time = UNIX_EPOCH.elapsed()?;
+        self.scrobble_as_of(scrobble, SystemTime::now())
+    }
 
-        params
-            .entry("timestamp".to_string())
-            .or_insert_with(|| format!("{}", current_time.as_secs()));
+    // Scrobbles as if the current time were `now`
+    pub(crate) fn scrobble_as_of(&self, scrobble: &Scrobble, now: SystemTime) -> Result<ScrobbleResponse> {
+        self.scrobble_reporting_as_of(scrobble, now).map(|(response, _)| response)
+    }
+
+    // Scrobbles as if the current time were `now`, reporting the changes made to the scrobble (at position 0)
+    pub(crate) fn scrobble_reporting_as_of(
+        &self,
+        scrobble: &Scrobble,
+        now: SystemTime,
+    ) -> Result<(ScrobbleResponse, PreparationReport)> {
+        self.submit_scrobble(scrobble, Self::scrobble_timestamp(scrobble, now)?, true, now)
+    }
+
+    // The timestamp a scrobble is submitted with at `now`: its own, or else `now`
+    pub(crate) fn scrobble_timestamp(scrobble: &Scrobble, now: SystemTime) -> Result<u64> {
+        match scrobble.timestamp() {
+            Some(timestamp) => Ok(timestamp),
+            None => Ok(now.duration_since(UNIX_EPOCH)?.as_secs()),
+        }
+    }
+
+    /// Registers a scrobble (play) of the given [`Scrobble`]/track, played at the given `time`.
+    ///
+    /// Behaves exactly like [`Scrobbler::scrobble`], except that the submitted timestamp is taken from `time`.
+    /// **Any timestamp already set on the `Scrobble` is overridden** by `time`. The `Scrobble` itself is left
+    /// untouched, so it can be kept as an immutable, timestamp-free record of the track.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let track = Scrobble::new("Example Artist", "Example Track", "Example Album");
+    /// let started_at = SystemTime::now() - Duration::from_secs(240);
+    /// scrobbler.scrobble_at(&track, started_at)?;
+    /// ```
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::scrobble`]: struct.Scrobbler.html#method.scrobble
+    pub fn scrobble_at(&self, scrobble: &Scrobble, time: SystemTime) -> Result<ScrobbleResponse> {
+        let timestamp = time.duration_since(UNIX_EPOCH)?.as_secs();
+
+        self.submit_scrobble(scrobble, timestamp, false, SystemTime::now()).map(|(response, _)| response)
+    }
+
+    // Submits the scrobble with the given timestamp at `now`, along with the changes made to it. `untimed` is set if
+    // the timestamp was generated for a scrobble without one.
+    fn submit_scrobble(
+        &self,
+        scrobble: &Scrobble,
+        timestamp: u64,
+        untimed: bool,
+        now: SystemTime,
+    ) -> Result<(ScrobbleResponse, PreparationReport)> {
+        let (submission, report) = self.scrobble_submission(scrobble, timestamp, untimed, now)?;
+        let response = match submission {
+            Submission::DryRun(response) => response,
+            Submission::Send(params) => self.scrobble_sent(self.client.send_scrobble(params)?, timestamp)?,
+        };
+
+        Ok((response, report))
+    }
+
+    pub(crate) fn scrobble_submission(
+        &self,
+        scrobble: &Scrobble,
+        timestamp: u64,
+        untimed: bool,
+        now: SystemTime,
+    ) -> Result<(Submission<ScrobbleResponse>, PreparationReport)> {
+        self.check_authenticated()?;
+        if timestamp < self.min_timestamp {
+            return Err(ScrobblerError::invalid_timestamp(format!(
+                "Scrobble timestamp {timestamp} is before the minimum allowed timestamp ({})",
+                self.min_timestamp
+            )));
+        }
+        if let Some(problem) = self.timestamp_problem(timestamp, now)? {
+            return Err(ScrobblerError::invalid_timestamp(format!("Scrobble timestamp {timestamp} is {problem}")));
+        }
+
+        let mut issues = Vec::new();
+        let prepared = self.prepare(scrobble, None, untimed, &mut issues)?;
+        self.report_issues(&issues)?;
+        let adjustments = issues
+            .iter()
+            .map(|issue| Adjustment::new(0, issue, scrobble, &prepared, timestamp))
+            .collect();
+        let report = PreparationReport::new(adjustments);
+        let prepared = self.defaults.apply(prepared, false);
+        let mut params = self.submission_params(&prepared);
+        params.insert_timestamp(timestamp);
+
+        if self.dry_run {
+            self.client
+                .sign_authenticated_request(&ApiOperation::Scrobble, params)?;
+            let response = ScrobbleResponse::from_scrobble(&prepared, timestamp).into_dry_run();
+            return Ok((Submission::DryRun(response), report));
+        }
+
+        Ok((Submission::Send(params), report))
+    }
+
+    // Completes Last.fm's response to a scrobble submitted with `timestamp`
+    pub(crate) fn scrobble_sent(&self, response: ScrobbleResponse, timestamp: u64) -> Result<ScrobbleResponse> {
+        let response = response.with_submitted_timestamp(timestamp);
+        self.client.record_submitted([&response]);
+        if self.fail_on_ignored {
+            if let Some(reason) = response.ignored_reason() {
+                return Err(ScrobblerError::new(format!("Scrobble ignored: {reason}")));
+            }
+        }
 
-        Ok(self.client.send_scrobble(&params)?)
+        Ok(response)
     }
 
     /// Registers a scrobble (play) of a collection of tracks. 
@@ -241,7 +1572,7 @@ impl Scrobbler {
     /// 
     /// # Response
     /// On success, returns a [`ScrobbleBatchResponse`]. This can be ignored by most clients, but contains some data
-    /// that may be of interest.
+    /// that may be of interest, including the timestamp each track was submitted with.
     /// 
     /// # Last.fm API Documentation
     /// [track.scrobble API Method Documention](https://www.last.fm/api/show/track.scrobble)
@@ -252,8 +1583,402 @@ impl Scrobbler {
     /// [`Scrobble`]: struct.Scrobble.html
     /// [`ScrobbleBatchResponse`]: responses/struct.ScrobbleBatchResponse.html
     pub fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
-        let mut params = HashMap::new();
+        self.scrobble_batch_as_of(batch, SystemTime::now())
+    }
+
+    // Scrobbles the batch as if the current time were `now`
+    pub(crate) fn scrobble_batch_as_of(
+        &self,
+        batch: &ScrobbleBatch,
+        now: SystemTime,
+    ) -> Result<BatchScrobbleResponse> {
+        Self::check_batch_size(batch)?;
+        let timestamps = Self::batch_timestamps(batch, now)?;
+
+        self.submit_batch(batch, &timestamps, true, now)
+    }
+
+    // The timestamps a batch is submitted with at `now`: each scrobble's own, or else `now`
+    pub(crate) fn batch_timestamps(batch: &ScrobbleBatch, now: SystemTime) -> Result<Vec<u64>> {
+        let now = now.duration_since(UNIX_EPOCH)?.as_secs();
+        Ok(batch.iter().map(|scrobble| scrobble.timestamp().unwrap_or(now)).collect())
+    }
+
+    /// Checks a batch (or an import of any size) as [`Scrobbler::scrobble_batch`] would, without submitting
+    /// anything, and lists the issues which lenient mode would work around and strict mode would refuse (see
+    /// [`Scrobbler::set_strict`]). Problems which are never worked around, such as a timestamp before the minimum,
+    /// are returned as an error.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// for issue in scrobbler.validate_batch(&import)? {
+    ///     eprintln!("warning: {}", issue);
+    /// }
+    /// ```
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`Scrobbler::set_strict`]: struct.Scrobbler.html#method.set_strict
+    pub fn validate_batch(&self, batch: &ScrobbleBatch) -> Result<Vec<ScrobbleIssue>> {
+        self.validate_batch_as_of(batch, SystemTime::now())
+    }
+
+    // Validates the batch as if the current time were `now`
+    fn validate_batch_as_of(&self, batch: &ScrobbleBatch, now: SystemTime) -> Result<Vec<ScrobbleIssue>> {
+        let timestamps = Self::batch_timestamps(batch, now)?;
+
+        let (_, issues, _) = self.prepare_batch(batch, &timestamps, true, now)?;
+        Ok(issues)
+    }
+
+    /// Registers a scrobble (play) of a collection of tracks, each played at the corresponding time in `times`.
+    ///
+    /// Behaves exactly like [`Scrobbler::scrobble_batch`], except that each track's submitted timestamp is taken
+    /// from `times`, which must hold exactly one time per track in the batch. **Any timestamps already set on the
+    /// `Scrobble`s are overridden.** The batch itself is left untouched.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let batch = ScrobbleBatch::from(vec![first_track, second_track]);
+    /// let times = [first_played_at, second_played_at];
+    /// scrobbler.scrobble_batch_at(&batch, &times)?;
+    /// ```
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    pub fn scrobble_batch_at(
+        &self,
+        batch: &ScrobbleBatch,
+        times: &[SystemTime],
+    ) -> Result<BatchScrobbleResponse> {
+        Self::check_batch_size(batch)?;
+        if times.len() != batch.len() {
+            return Err(ScrobblerError::new(format!(
+                "Scrobble batch has {} scrobbles but {} times were given",
+                batch.len(),
+                times.len()
+            )));
+        }
+
+        let mut timestamps = Vec::with_capacity(times.len());
+        for time in times {
+            timestamps.push(time.duration_since(UNIX_EPOCH)?.as_secs());
+        }
+
+        self.submit_batch(batch, &timestamps, false, SystemTime::now())
+    }
+
+    /// Submits an import of any size in chunks of `chunk_size` scrobbles, returning an iterator which submits the
+    /// next chunk each time it's advanced, so that progress can be reported (or checkpointed) as each chunk
+    /// completes.
+    ///
+    /// Each chunk is submitted as with [`Scrobbler::scrobble_batch`], including any rate limit and retries. A chunk
+    /// which fails for transient reasons is reported, and the import carries on with the next chunk; any other
+    /// failure is reported as the iterator's last item. `chunk_size` must be between 1 and 50.
+    ///
+    /// Last.fm ignores scrobbles once the user's daily scrobble limit is reached, so the import also ends with the
+    /// first chunk which hits it, whose [`ChunkResult::daily_limit`] says how many scrobbles remain and when to
+    /// resume.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// for chunk in scrobbler.scrobble_batch_chunks(&history, 50)? {
+    ///     match chunk.result {
+    ///         Ok(_) => checkpoint(chunk.range.end),
+    ///         Err(err) => eprintln!("Scrobbles {:?} failed: {}", chunk.range, err),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`ChunkResult::daily_limit`]: struct.ChunkResult.html#structfield.daily_limit
+    pub fn scrobble_batch_chunks(&self, batch: &ScrobbleBatch, chunk_size: usize) -> Result<BatchChunks<'_>> {
+        self.check_chunked_import(batch, chunk_size)?;
+
+        Ok(BatchChunks::new(self, batch.iter().cloned().collect(), chunk_size))
+    }
+
+    // Checks that an import can be submitted in chunks of `chunk_size` scrobbles
+    pub(crate) fn check_chunked_import(&self, batch: &ScrobbleBatch, chunk_size: usize) -> Result<()> {
+        self.check_authenticated()?;
+        if !(1..=50).contains(&chunk_size) {
+            return Err(ScrobblerError::new(format!(
+                "Invalid chunk size {chunk_size} (must be between 1 and 50)"
+            )));
+        }
+
+        if self.strict {
+            let issues = self.validate_batch(batch)?;
+            if !issues.is_empty() {
+                return Err(ScrobblerError::strict(issues));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Submits a batch of any size, in chunks of 50 scrobbles one after another, merging the responses into one.
+    /// Behaves as [`Scrobbler::scrobble_batch`] does for each chunk, and the merged response lists every
+    /// scrobble's result in the order the batch holds them.
+    ///
+    /// Submission stops at the first chunk which fails, or which hits Last.fm's daily scrobble limit. The error
+    /// then says how many scrobbles were submitted, and its [`ScrobblerError::import_progress`] can be passed to
+    /// [`BatchChunks::resume_from`] to submit the rest later. Use [`Scrobbler::scrobble_batch_chunks`] instead to
+    /// report progress as each chunk completes.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// match scrobbler.scrobble_batch_chunked(&history) {
+    ///     Ok(response) => println!("{} scrobbles accepted", response.accepted_count()),
+    ///     Err(err) => match err.import_progress() {
+    ///         Some(progress) => save_progress(progress)?,
+    ///         None => return Err(err),
+    ///     },
+    /// }
+    /// ```
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+    /// [`ScrobblerError::import_progress`]: struct.ScrobblerError.html#method.import_progress
+    /// [`BatchChunks::resume_from`]: struct.BatchChunks.html#method.resume_from
+    pub fn scrobble_batch_chunked(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
+        if batch.is_empty() {
+            return Err(ScrobblerError::new("Scrobble batch is empty".to_owned()));
+        }
+
+        let mut chunks = self.scrobble_batch_chunks(batch, 50)?;
+        let mut scrobbles = Vec::with_capacity(batch.len());
+        let mut preparation = PreparationReport::default();
+        let mut dry_run = false;
+        while let Some(chunk) = chunks.next() {
+            let progress = chunks.progress();
+            let stopped = |reason: &str| {
+                format!("{reason} after {} of {} scrobbles were submitted", progress.completed(), batch.len())
+            };
+            let response = match chunk.result {
+                Ok(_) if chunk.daily_limit.is_some() => {
+                    return Err(ScrobblerError::new(stopped("Daily scrobble limit reached"))
+                        .with_import_progress(progress.clone()));
+                }
+                Ok(response) => response,
+                Err(err) => {
+                    return Err(err.context(&stopped("Chunked batch stopped")).with_import_progress(progress.clone()));
+                }
+            };
+
+            dry_run = response.dry_run;
+            preparation.append(response.preparation);
+            scrobbles.extend(response.scrobbles);
+        }
+
+        Ok(BatchScrobbleResponse {
+            scrobbles: ScrobbleList::from(scrobbles),
+            dry_run,
+            preparation,
+        })
+    }
+
+    /// Submits a batch of any size in chunks of 50 scrobbles, sending up to `concurrency` chunks at once from
+    /// separate threads, and returns the result of each chunk submitted, in the order the batch holds them.
+    ///
+    /// Each chunk is submitted as with [`Scrobbler::scrobble_batch`], so a rate limit set with
+    /// [`Scrobbler::set_rate_limit`] is shared by all of them, and paces them when it's reached. No more chunks are
+    /// started once one fails for a reason which isn't transient, or hits Last.fm's daily scrobble limit, but chunks
+    /// already being sent are finished: their results are all returned, and those after a failed chunk may have
+    /// succeeded. As chunks may complete out of order, resuming isn't supported; use
+    /// [`Scrobbler::scrobble_batch_chunks`] to submit an import which may need resuming. `concurrency` must be at
+    /// least 1.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// for chunk in scrobbler.scrobble_batch_concurrent(&backlog, 4)? {
+    ///     if let Err(err) = chunk.result {
+    ///         eprintln!("Scrobbles {:?} failed: {}", chunk.range, err);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Scrobbler::scrobble_batch`]: struct.Scrobbler.html#method.scrobble_batch
+    /// [`Scrobbler::set_rate_limit`]: struct.Scrobbler.html#method.set_rate_limit
+    /// [`Scrobbler::scrobble_batch_chunks`]: struct.Scrobbler.html#method.scrobble_batch_chunks
+    pub fn scrobble_batch_concurrent(&self, batch: &ScrobbleBatch, concurrency: usize) -> Result<Vec<ChunkResult>> {
+        self.check_authenticated()?;
+        if concurrency == 0 {
+            return Err(ScrobblerError::new("Invalid concurrency 0 (must be at least 1)".to_owned()));
+        }
+
+        if self.strict {
+            let issues = self.validate_batch(batch)?;
+            if !issues.is_empty() {
+                return Err(ScrobblerError::strict(issues));
+            }
+        }
+
+        let scrobbles: Vec<Scrobble> = batch.iter().cloned().collect();
+        Ok(chunks::submit_concurrently(self, &scrobbles, concurrency))
+    }
+
+    /// Plans an import without submitting anything, by comparing it with the user's listening history: finds which
+    /// of the batch's scrobbles would be new, which are probably in the history already, and which are too old to
+    /// submit.
+    ///
+    /// The history around the batch's timestamps is fetched with `user.getRecentTracks`, one page of up to 200
+    /// scrobbles per request, for the user named by [`Scrobbler::username`]. A scrobble is a probable duplicate of a
+    /// scrobble in the history with the same artist and track name, ignoring case, timestamped at most `window`
+    /// apart. Untimed scrobbles are planned as if played now, as they would be submitted.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let plan = scrobbler.plan_import(&history, Duration::from_secs(60))?;
+    /// println!("{} new, {} already scrobbled", plan.new_scrobbles().len(), plan.probable_duplicates().len());
+    ///
+    /// let chunks = scrobbler.scrobble_batch_chunks(&history, 50)?.skip_duplicates(&plan)?;
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [user.getRecentTracks API Method Documentation](https://www.last.fm/api/show/user.getRecentTracks)
+    ///
+    /// [`Scrobbler::username`]: struct.Scrobbler.html#method.username
+    pub fn plan_import(&self, batch: &ScrobbleBatch, window: Duration) -> Result<ImportPlan> {
+        self.plan_import_as_of(batch, window, SystemTime::now())
+    }
+
+    // Plans the import as if the current time were `now`
+    pub(crate) fn plan_import_as_of(
+        &self,
+        batch: &ScrobbleBatch,
+        window: Duration,
+        now: SystemTime,
+    ) -> Result<ImportPlan> {
+        let username = self.history_user("Planning an import")?;
+
+        let timestamps = Self::batch_timestamps(batch, now)?;
+        let oldest = now.duration_since(UNIX_EPOCH)?.saturating_sub(MAX_SCROBBLE_AGE).as_secs().max(self.min_timestamp);
+        let submittable = timestamps.iter().filter(|timestamp| **timestamp >= oldest);
+        let history = match (submittable.clone().min(), submittable.max()) {
+            (Some(first), Some(last)) => {
+                let window = window.as_secs();
+                self.listening_history(username, first.saturating_sub(window), last.saturating_add(window))?
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(ImportPlan::new(batch, &timestamps, &history, window, oldest))
+    }
+
+    /// Looks up what's already on the user's profile, ie. to check what a batch interrupted by a crash managed to
+    /// scrobble before submitting it again (see [`ScrobbleBatch::without_scrobbled`]).
+    ///
+    /// Returns up to `limit` of the user's scrobbles, most recent first, timestamped between `from` and `to` (in
+    /// seconds since the Unix epoch, both included) when they're given. The track the user is playing now, if
+    /// any, is listed first whatever the time range, and doesn't count towards the limit; see
+    /// [`RecentTrack::now_playing`]. Up to 200 scrobbles are fetched per request.
+    ///
+    /// The history is that of the user named by [`Scrobbler::username`], which authenticating with a password or
+    /// a token sets, as does [`Scrobbler::verify_session`].
+    ///
+    /// # Usage
+    /// ```ignore
+    /// for track in scrobbler.recent_tracks(10, None, None)? {
+    ///     println!("{} - {} at {:?}", track.artist(), track.name(), track.timestamp());
+    /// }
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [user.getRecentTracks API Method Documentation](https://www.last.fm/api/show/user.getRecentTracks)
+    ///
+    /// [`ScrobbleBatch::without_scrobbled`]: struct.ScrobbleBatch.html#method.without_scrobbled
+    /// [`RecentTrack::now_playing`]: responses/struct.RecentTrack.html#method.now_playing
+    /// [`Scrobbler::username`]: struct.Scrobbler.html#method.username
+    /// [`Scrobbler::verify_session`]: struct.Scrobbler.html#method.verify_session
+    pub fn recent_tracks(&self, limit: u32, from: Option<u64>, to: Option<u64>) -> Result<Vec<RecentTrack>> {
+        let username = self.history_user("Looking up recent tracks")?;
+        let mut tracks = Vec::new();
+        let mut scrobbled = 0;
+        let mut page = 1;
+        while scrobbled < limit {
+            let mut params = Params::new();
+            params.insert_user(username);
+            if let Some(from) = from {
+                params.insert_from(from);
+            }
+            if let Some(to) = to {
+                params.insert_to(to);
+            }
+            params.insert_page(page, limit.min(MAX_HISTORY_PAGE));
+
+            let recent = self.client.send_recent_tracks(&params)?;
+            let last_page = recent.tracks.is_empty() || recent.page.page >= recent.page.total_pages;
+            for track in recent.tracks {
+                // Last.fm lists the track being played now on every page
+                if track.now_playing() {
+                    if page == 1 {
+                        tracks.push(track);
+                    }
+                } else if scrobbled < limit {
+                    scrobbled += 1;
+                    tracks.push(track);
+                }
+            }
+            if last_page {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(tracks)
+    }
 
+    // The name of the user whose history is read, or an error saying `action` needs it
+    fn history_user(&self, action: &str) -> Result<&str> {
+        self.username().ok_or_else(|| {
+            ScrobblerError::new(format!("{action} needs the authenticated user's name; see Scrobbler::set_username"))
+        })
+    }
+
+    // Fetches every track the user scrobbled between two timestamps, page by page
+    fn listening_history(&self, username: &str, from: u64, to: u64) -> Result<Vec<RecentTrack>> {
+        let mut tracks = Vec::new();
+        let mut page = 1;
+        loop {
+            let mut params = Params::new();
+            params.insert_user(username);
+            params.insert_time_range(from, to);
+            params.insert_page(page, MAX_HISTORY_PAGE);
+
+            let recent = self.client.send_recent_tracks(&params)?;
+            let last_page = recent.tracks.is_empty() || recent.page.page >= recent.page.total_pages;
+            tracks.extend(recent.tracks);
+            if last_page {
+                return Ok(tracks);
+            }
+            page += 1;
+        }
+    }
+
+    // Submissions need a session key, so fail fast without one rather than sending a request Last.fm will refuse
+    fn check_authenticated(&self) -> Result<()> {
+        if self.session_key().is_none() {
+            return Err(ScrobblerError::not_authenticated());
+        }
+        Ok(())
+    }
+
+    // Describes why Last.fm would ignore a scrobble with the timestamp, submitted at `now`, if it would
+    fn timestamp_problem(&self, timestamp: u64, now: SystemTime) -> Result<Option<String>> {
+        let now = now.duration_since(UNIX_EPOCH)?.as_secs();
+        let problem = if timestamp > MAX_PLAUSIBLE_TIMESTAMP {
+            Some("after the year 5000, so probably in milliseconds rather than seconds since the Unix epoch".to_owned())
+        } else if timestamp > now + MAX_CLOCK_SKEW.as_secs() {
+            Some(format!("{} seconds in the future", timestamp - now))
+        } else {
+            self.max_scrobble_age
+                .filter(|max_age| timestamp < now.saturating_sub(max_age.as_secs()))
+                .map(|max_age| format!("more than {} days old, so would be ignored", max_age.as_secs() / 86_400))
+        };
+
+        Ok(problem)
+    }
+
+    pub(crate) fn check_batch_size(batch: &ScrobbleBatch) -> Result<()> {
         let batch_count = batch.len();
         if batch_count > 50 {
             return Err(ScrobblerError::new(
@@ -263,21 +1988,237 @@ impl Scrobbler {
             return Err(ScrobblerError::new("Scrobble batch is empty".to_owned()));
         }
 
-        for (i, scrobble) in batch.iter().enumerate() {
-            let mut scrobble_params = scrobble.as_map();
-            let current_time = UNIX_EPOCH.elapsed()?;
-            scrobble_params
-                .entry("timestamp".to_string())
-                .or_insert_with(|| format!("{}", current_time.as_secs()));
+        Ok(())
+    }
+
+    // Prepares each scrobble of a batch for submission at `now` with the given timestamps, collecting their issues
+    // and the changes made to work around them. `untimed` is set if the timestamps of scrobbles without one were
+    // generated.
+    fn prepare_batch<'a>(
+        &self,
+        batch: &'a ScrobbleBatch,
+        timestamps: &[u64],
+        untimed: bool,
+        now: SystemTime,
+    ) -> Result<(Vec<Cow<'a, Scrobble>>, Vec<ScrobbleIssue>, PreparationReport)> {
+        if let Some((i, timestamp)) = timestamps
+            .iter()
+            .enumerate()
+            .find(|(_, timestamp)| **timestamp < self.min_timestamp)
+        {
+            return Err(ScrobblerError::invalid_timestamp(format!(
+                "Scrobble {i} in batch has timestamp {timestamp}, before the minimum allowed timestamp ({})",
+                self.min_timestamp
+            )));
+        }
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            if let Some(problem) = self.timestamp_problem(*timestamp, now)? {
+                return Err(ScrobblerError::invalid_timestamp(format!(
+                    "Scrobble {i} in batch has timestamp {timestamp}, which is {problem}"
+                )));
+            }
+        }
+
+        let mut issues = Vec::new();
+        let mut adjustments = Vec::new();
+        let mut prepared_batch = Vec::with_capacity(batch.len());
+        for (i, (scrobble, timestamp)) in batch.iter().zip(timestamps).enumerate() {
+            let first_issue = issues.len();
+            let prepared = self.prepare(scrobble, Some(i), untimed, &mut issues)?;
+            for issue in &issues[first_issue..] {
+                adjustments.push(Adjustment::new(i, issue, scrobble, &prepared, *timestamp));
+            }
+            prepared_batch.push(self.defaults.apply(prepared, false));
+        }
+
+        Ok((prepared_batch, issues, PreparationReport::new(adjustments)))
+    }
+
+    // Submits the batch with the given timestamps at `now`. `untimed` is set if the timestamps of scrobbles without
+    // one were generated.
+    fn submit_batch(
+        &self,
+        batch: &ScrobbleBatch,
+        timestamps: &[u64],
+        untimed: bool,
+        now: SystemTime,
+    ) -> Result<BatchScrobbleResponse> {
+        let (submission, preparation) = self.batch_submission(batch, timestamps, untimed, now)?;
+        let mut response = match submission {
+            Submission::DryRun(response) => response,
+            Submission::Send(params) => self.batch_sent(self.client.send_batch_scrobbles(params)?, timestamps),
+        };
+        response.preparation = preparation;
+
+        Ok(response)
+    }
+
+    // The dry run response has no preparation report: it's returned separately, for either response
+    pub(crate) fn batch_submission(
+        &self,
+        batch: &ScrobbleBatch,
+        timestamps: &[u64],
+        untimed: bool,
+        now: SystemTime,
+    ) -> Result<(Submission<BatchScrobbleResponse>, PreparationReport)> {
+        self.check_authenticated()?;
+        let (batch, issues, preparation) = self.prepare_batch(batch, timestamps, untimed, now)?;
+        self.report_issues(&issues)?;
+        let mut params = Params::new();
+
+        for (i, (scrobble, timestamp)) in batch.iter().zip(timestamps).enumerate() {
+            let mut scrobble_params = self.submission_params(scrobble);
+            scrobble_params.insert_timestamp(*timestamp);
+            params.insert_batch_entry(i, &scrobble_params);
+        }
+
+        if self.dry_run {
+            self.client
+                .sign_authenticated_request(&ApiOperation::Scrobble, params)?;
+            let scrobbles: Vec<ScrobbleResponse> = batch
+                .iter()
+                .zip(timestamps)
+                .map(|(scrobble, timestamp)| ScrobbleResponse::from_scrobble(scrobble, *timestamp).into_dry_run())
+                .collect();
+            let response = BatchScrobbleResponse {
+                scrobbles: ScrobbleList::from(scrobbles),
+                dry_run: true,
+                preparation: PreparationReport::default(),
+            };
+
+            return Ok((Submission::DryRun(response), preparation));
+        }
+
+        Ok((Submission::Send(params), preparation))
+    }
+
+    // Completes Last.fm's response to a batch submitted with `timestamps`
+    pub(crate) fn batch_sent(&self, response: BatchScrobbleResponse, timestamps: &[u64]) -> BatchScrobbleResponse {
+        let scrobbles: ScrobbleList = response
+            .scrobbles
+            .into_iter()
+            .enumerate()
+            .map(|(i, scrobble)| match timestamps.get(i) {
+                Some(timestamp) => scrobble.with_submitted_timestamp(*timestamp),
+                None => scrobble,
+            })
+            .collect();
+        self.client.record_submitted(scrobbles.iter());
+
+        BatchScrobbleResponse { scrobbles, ..response }
+    }
+
+    /// Loves a track on the authenticated user's profile. Only the [`Scrobble`]'s artist and track are sent.
+    /// Transient failures are retried under the policy for writes (see [`Scrobbler::set_retry_policy`]).
+    ///
+    /// # Usage
+    /// ```ignore
+    /// scrobbler.love(&Scrobble::new("Los Campesinos!", "To Tundra", "No Blues"))?;
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [track.love API Method Documentation](https://www.last.fm/api/show/track.love)
+    ///
+    /// [`Scrobble`]: struct.Scrobble.html
+    /// [`Scrobbler::set_retry_policy`]: struct.Scrobbler.html#method.set_retry_policy
+    pub fn love(&self, scrobble: &Scrobble) -> Result<()> {
+        self.love_scrobble(&ApiOperation::Love, scrobble)
+    }
+
+    /// Unloves a track on the authenticated user's profile. Behaves exactly like [`Scrobbler::love`].
+    ///
+    /// # Last.fm API Documentation
+    /// [track.unlove API Method Documentation](https://www.last.fm/api/show/track.unlove)
+    ///
+    /// [`Scrobbler::love`]: struct.Scrobbler.html#method.love
+    pub fn unlove(&self, scrobble: &Scrobble) -> Result<()> {
+        self.love_scrobble(&ApiOperation::Unlove, scrobble)
+    }
+
+    fn love_scrobble(&self, operation: &ApiOperation, scrobble: &Scrobble) -> Result<()> {
+        self.check_authenticated()?;
+
+        // The names as they'd be scrobbled
+        let params = scrobble.params();
+        let param = |name| params.get(name).unwrap_or_default();
+        let retry_policy = self.client.retry_policy_for(CallClass::Write);
+        self.send_love(operation, param("artist"), param("track"), &retry_policy)
+    }
+
+    fn send_love(&self, operation: &ApiOperation, artist: &str, track: &str, retry_policy: &RetryPolicy) -> Result<()> {
+        let mut params = Params::new();
+        params.insert_artist(artist);
+        params.insert_track(track);
+
+        if self.dry_run {
+            self.client
+                .sign_authenticated_request(operation, params)
+                .map(|_| ())
+        } else {
+            self.client.send_love(operation, params, retry_policy)
+        }
+    }
+
+    /// Loves a list of tracks, given as `(artist, track)` pairs, ie. to import favourites from another service.
+    ///
+    /// Tracks are loved one at a time, paced and retried according to `options` (see [`BulkLoveOptions`]) to stay
+    /// within Last.fm's rate limits. A track which can't be loved doesn't stop the rest: the returned
+    /// [`BulkLoveReport`] holds the outcome for every track. An error is only returned if the `Scrobbler` isn't
+    /// authenticated.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let favourites = vec![("Los Campesinos!".to_owned(), "To Tundra".to_owned())];
+    /// let report = scrobbler.love_many(&favourites, &BulkLoveOptions::default())?;
+    /// for failure in report.failures() {
+    ///     println!("Couldn't love {} - {}", failure.artist, failure.track);
+    /// }
+    /// ```
+    ///
+    /// # Last.fm API Documentation
+    /// [track.love API Method Documentation](https://www.last.fm/api/show/track.love)
+    ///
+    /// [`BulkLoveOptions`]: struct.BulkLoveOptions.html
+    /// [`BulkLoveReport`]: struct.BulkLoveReport.html
+    pub fn love_many(&self, items: &[(String, String)], options: &BulkLoveOptions) -> Result<BulkLoveReport> {
+        self.bulk_love(&ApiOperation::Love, items, options)
+    }
+
+    /// Unloves a list of tracks, given as `(artist, track)` pairs. Behaves exactly like [`Scrobbler::love_many`].
+    ///
+    /// # Last.fm API Documentation
+    /// [track.unlove API Method Documentation](https://www.last.fm/api/show/track.unlove)
+    ///
+    /// [`Scrobbler::love_many`]: struct.Scrobbler.html#method.love_many
+    pub fn unlove_many(&self, items: &[(String, String)], options: &BulkLoveOptions) -> Result<BulkLoveReport> {
+        self.bulk_love(&ApiOperation::Unlove, items, options)
+    }
+
+    fn bulk_love(
+        &self,
+        operation: &ApiOperation,
+        items: &[(String, String)],
+        options: &BulkLoveOptions,
+    ) -> Result<BulkLoveReport> {
+        self.check_authenticated()?;
 
-            for (key, val) in &scrobble_params {
-                // batched parameters need array notation suffix ie.
-                // "artist[1] = "Artist 1", "artist[2]" = "Artist 2"
-                params.insert(format!("{}[{}]", key, i), val.clone());
+        let mut report = BulkLoveReport::default();
+        let mut last_started: Option<Instant> = None;
+        for (artist, track) in items {
+            if let Some(started) = last_started {
+                thread::sleep(options.interval.saturating_sub(started.elapsed()));
             }
+            last_started = Some(Instant::now());
+
+            let result = self.send_love(operation, artist, track, &options.retry_policy);
+            report.outcomes.push(LoveOutcome {
+                artist: artist.clone(),
+                track: track.clone(),
+                result,
+            });
         }
 
-        Ok(self.client.send_batch_scrobbles(&params)?)
+        Ok(report)
     }
 
     /// Gets the session key the client is currently authenticated with. Returns `None` if not authenticated. Valid
@@ -289,12 +2230,224 @@ impl Scrobbler {
     pub fn session_key(&self) -> Option<&str> {
         self.client.session_key()
     }
+
+    /// Returns the URL API requests are sent to. It changes if the server permanently redirects a call, and
+    /// [`Scrobbler::set_update_base_url_on_redirect`] is enabled.
+    ///
+    /// [`Scrobbler::set_update_base_url_on_redirect`]: struct.Scrobbler.html#method.set_update_base_url_on_redirect
+    pub fn base_url(&self) -> String {
+        self.client.base_url()
+    }
+
+    /// Returns the Last.fm API key the `Scrobbler` was created with. API keys identify the application, and aren't
+    /// secret (unlike the API secret, which is never exposed).
+    pub fn api_key(&self) -> &str {
+        self.client.api_key()
+    }
+
+    /// Reports whether scrobbling appears to work, ie. for a watchdog or a health check endpoint: whether the
+    /// `Scrobbler` is authenticated, the state of its circuit breaker, and when an API call through it or any of its
+    /// clones last succeeded and last failed. Nothing is sent. See [`HealthReport`], and [`ScrobbleQueue::health`]
+    /// for a report which includes a queue.
+    ///
+    /// [`HealthReport`]: struct.HealthReport.html
+    /// [`ScrobbleQueue::health`]: struct.ScrobbleQueue.html#method.health
+    pub fn health(&self) -> HealthReport {
+        self.client.health_at(SystemTime::now())
+    }
+
+    /// Reports on the `Scrobbler`'s health as [`Scrobbler::health`] does, after checking the connection to the API
+    /// with a lightweight request (`auth.getToken`, which also checks the API key and secret). To spare the API,
+    /// at most one request is sent in any 5 minutes (see [`Scrobbler::set_health_probe_interval`]) by the
+    /// `Scrobbler` and its clones; the report says whether this call sent one.
+    ///
+    /// [`Scrobbler::health`]: struct.Scrobbler.html#method.health
+    /// [`Scrobbler::set_health_probe_interval`]: struct.Scrobbler.html#method.set_health_probe_interval
+    pub fn health_probe(&self) -> HealthReport {
+        let probed = self.client.claim_health_probe_at(self.health_probe_interval, SystemTime::now());
+        if probed {
+            // The outcome is recorded with the health of every call
+            let _ = self.client.request_auth_token();
+        }
+
+        let mut report = self.health();
+        report.probed = probed;
+        report
+    }
+
+    /// Sets the least time between the requests sent by [`Scrobbler::health_probe`]; 5 minutes by default
+    ///
+    /// [`Scrobbler::health_probe`]: struct.Scrobbler.html#method.health_probe
+    pub fn set_health_probe_interval(&mut self, interval: Duration) {
+        self.health_probe_interval = interval;
+    }
+
+    /// Returns a snapshot of the `Scrobbler`'s configuration, for diagnostics: the endpoint and API key in use, the
+    /// transport's HTTP settings and which optional behaviours are enabled. See [`ClientInfo`].
+    ///
+    /// [`ClientInfo`]: struct.ClientInfo.html
+    pub fn info(&self) -> ClientInfo {
+        let mut info = ClientInfo::new(
+            &self.client.base_url(),
+            self.client.api_key(),
+            self.client.transport_info(),
+            self.client.session_key().is_some(),
+            self.dry_run,
+            self.client.retry_policy(),
+            self.client.rate_limit(),
+        );
+        info.preset = self.preset;
+        info.max_scrobble_age = self.max_scrobble_age;
+        info
+    }
+
+    /// Returns a snapshot of the settings which can be changed while the `Scrobbler` runs, to change and apply with
+    /// [`Scrobbler::apply_config`]. See [`ScrobblerConfig`].
+    ///
+    /// [`Scrobbler::apply_config`]: struct.Scrobbler.html#method.apply_config
+    /// [`ScrobblerConfig`]: struct.ScrobblerConfig.html
+    pub fn config(&self) -> ScrobblerConfig {
+        let http = self.http.clone().unwrap_or_default();
+        ScrobblerConfig {
+            base_url: self.client.base_url(),
+            timeout: http.timeout,
+            proxy: http.proxy,
+            user_agent: http.user_agent,
+            dry_run: self.dry_run,
+            auth_retry_policy: self.client.retry_policy_for(CallClass::Auth),
+            write_retry_policy: self.client.retry_policy_for(CallClass::Write),
+            read_retry_policy: self.client.retry_policy_for(CallClass::Read),
+            rate_limit: self.client.rate_limit(),
+            circuit_breaker: self.client.circuit_breaker(),
+            usage_thresholds: self.client.usage_thresholds(),
+            cache: self.client.cache_policy(),
+            max_response_size: self.client.max_response_size(),
+            fail_on_ignored: self.fail_on_ignored,
+            now_playing_best_effort: self.now_playing_best_effort,
+            strict: self.strict,
+            max_scrobble_age: self.max_scrobble_age,
+            health_probe_interval: self.health_probe_interval,
+        }
+    }
+
+    /// Applies a [`ScrobblerConfig`], ie. one taken with [`Scrobbler::config`] and changed, while the `Scrobbler`
+    /// runs. Either all of it is applied, or (if it fails) none of it. Fails if the base URL or an HTTP setting is
+    /// invalid, and with an error of kind `ErrorKind::NotReloadable` if an HTTP setting is changed on a `Scrobbler`
+    /// created with [`Scrobbler::with_transport`], whose transport it can't recreate.
+    ///
+    /// Settings are only changed if they differ, so that ie. re-applying the same circuit breaker policy doesn't
+    /// reset the circuit. Those shared with the `Scrobbler`'s clones, such as the rate limit and the cache, change
+    /// for all of them. A new base URL or new HTTP settings apply to this `Scrobbler`, and the clones made from it
+    /// afterwards: the HTTP settings are changed by creating a new transport, and clones made earlier carry on with
+    /// the old URL and transport, so that calls they have in flight complete undisturbed.
+    ///
+    /// The TLS backend is chosen when the crate is built, so can never be changed.
+    ///
+    /// [`ScrobblerConfig`]: struct.ScrobblerConfig.html
+    /// [`Scrobbler::config`]: struct.Scrobbler.html#method.config
+    /// [`Scrobbler::with_transport`]: struct.Scrobbler.html#method.with_transport
+    pub fn apply_config(&mut self, config: &ScrobblerConfig) -> Result<()> {
+        // Everything which can fail is done first
+        let base_url = self.client.base_url();
+        let endpoint = if config.base_url == base_url {
+            None
+        } else {
+            Some(Endpoint::parse(&config.base_url)?).filter(|endpoint| endpoint.api_url != base_url)
+        };
+        let http = HttpSettings::of(config);
+        let transport = match &self.http {
+            Some(current) if *current != http => Some(http.transport()?),
+            Some(_) => None,
+            None => match HttpSettings::default().changed(&http) {
+                Some(setting) => return Err(ScrobblerError::not_reloadable(setting)),
+                None => None,
+            },
+        };
+
+        if let Some(endpoint) = endpoint {
+            self.client.set_endpoint(endpoint);
+        }
+        if let Some(transport) = transport {
+            self.client.set_transport(Arc::new(transport));
+            self.http = Some(http);
+        }
+        self.dry_run = config.dry_run;
+        self.client.set_retry_policy_for(CallClass::Auth, config.auth_retry_policy);
+        self.client.set_retry_policy_for(CallClass::Write, config.write_retry_policy);
+        self.client.set_retry_policy_for(CallClass::Read, config.read_retry_policy);
+        if self.client.rate_limit() != config.rate_limit {
+            self.client.set_rate_limit(config.rate_limit);
+        }
+        if self.client.circuit_breaker() != config.circuit_breaker {
+            self.client.set_circuit_breaker(config.circuit_breaker);
+        }
+        if self.client.usage_thresholds() != config.usage_thresholds {
+            self.client.set_usage_thresholds(config.usage_thresholds);
+        }
+        if self.client.cache_policy() != config.cache {
+            self.client.set_cache(config.cache);
+        }
+        self.client.set_max_response_size(config.max_response_size);
+        self.fail_on_ignored = config.fail_on_ignored;
+        self.now_playing_best_effort = config.now_playing_best_effort;
+        self.strict = config.strict;
+        self.max_scrobble_age = config.max_scrobble_age;
+        self.health_probe_interval = config.health_probe_interval;
+        Ok(())
+    }
+}
+
+// The longest name submitted in the field, in characters
+fn max_field_chars(field: &str) -> usize {
+    if field == "track" {
+        MAX_TRACK_CHARS
+    } else {
+        MAX_NAME_CHARS
+    }
+}
+
+// Cuts `value` down to at most `max_chars` characters, the last of which is an ellipsis if `ellipsis` is set. With
+// the `unicode` feature, the cut is moved back to the grapheme cluster boundary before it, if it isn't on one.
+fn truncate_chars(value: &str, max_chars: usize, ellipsis: bool) -> String {
+    let keep = if ellipsis { max_chars - 1 } else { max_chars };
+    let end = value.char_indices().nth(keep).map_or(value.len(), |(index, _)| index);
+    #[cfg(feature = "unicode")]
+    let end = value
+        .grapheme_indices(true)
+        .map(|(index, _)| index)
+        .take_while(|index| *index <= end)
+        .last()
+        .filter(|index| *index > 0)
+        .unwrap_or(end);
+    let mut truncated = value[..end].to_owned();
+    if ellipsis {
+        truncated.push('\u{2026}');
+    }
+    truncated
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::fake::{self, FakeBatch};
+    use crate::audit::CallOutcome;
+    use crate::builder::Endpoint;
+    use crate::chunks::{ChunkResult, ImportProgress};
+    use crate::codes::LastFmErrorCode;
+    use crate::error::ErrorKind;
+    use crate::events::RequestOutcome;
+    use crate::models::responses::{IgnoredReason, NowPlayingOutcome};
+    use crate::media::MediaKind;
+    use crate::signing::{self, Md5Signer};
+    use crate::headers::Headers;
+    use crate::transport::{HttpMethod, HttpRequest, HttpResponse, ReqwestTransport};
+    use crate::usage::{UsageLimits, UsageWindow};
+    use crate::test_util::{EchoTransport, Fault, FaultyTransport, FixtureTransport};
+    use std::convert::TryFrom;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
     use mockito::mock;
     use std::error::Error;
 
@@ -307,21 +2460,15 @@ mod tests {
         assert!(resp.is_err());
 
         let _m = mock("POST", mockito::Matcher::Any)
-            .with_body(
-                r#"
-                {   
-                    "session": {
-                        "key": "key",
-                        "subscriber": 1337,
-                        "name": "foo floyd"
-                    }
-                }
-            "#,
-            )
+            .with_body(fake::session_response("foo floyd").key("key").to_string())
             .create();
 
         let resp = scrobbler.authenticate_with_password("user", "pass");
         assert!(resp.is_ok());
+        // The session says whose it is; a restored key doesn't
+        assert_eq!(scrobbler.username(), Some("foo floyd"));
+        scrobbler.authenticate_with_session_key("key").unwrap();
+        assert_eq!(scrobbler.username(), None);
     }
 
     #[test]
@@ -333,17 +2480,7 @@ mod tests {
         assert!(resp.is_err());
 
         let _m = mock("POST", mockito::Matcher::Any)
-            .with_body(
-                r#"
-                {   
-                    "session": {
-                        "key": "key",
-                        "subscriber": 1337,
-                        "name": "foo floyd"
-                    }
-                }
-            "#,
-            )
+            .with_body(fake::session_response("foo floyd").key("key").to_string())
             .create();
 
         let resp = scrobbler.authenticate_with_token("some_token");
@@ -351,13 +2488,60 @@ mod tests {
     }
 
     #[test]
-    fn check_scrobbler_error() {
-        let err = ScrobblerError::new("test_error".into());
-        let fmt = format!("{}", err);
-        assert_eq!("test_error", fmt);
+    fn check_scrobbler_desktop_auth_flow() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
 
-        let desc = err.description();
-        assert_eq!("test_error", desc);
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "auth.getToken".into()),
+                mockito::Matcher::UrlEncoded("api_key".into(), "api_key".into()),
+                mockito::Matcher::Regex("api_sig=".into()),
+            ]))
+            .with_body(fake::token_response("tok/en+1").to_string())
+            .create();
+        let token = scrobbler.request_auth_token().unwrap();
+        assert_eq!(token, "tok/en+1");
+        m.assert();
+        assert_eq!(
+            scrobbler.authorization_url(&token),
+            "https://www.last.fm/api/auth/?api_key=api_key&token=tok%2Fen%2B1"
+        );
+
+        // Exchanging the token before the user has authorized it
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::UrlEncoded("method".into(), "auth.getSession".into()))
+            .with_status(403)
+            .with_body(fake::error_response(14, "Unauthorized Token - This token has not been authorized").to_string())
+            .create();
+        let err = scrobbler.authenticate_with_token(&token).unwrap_err();
+        assert_eq!(err.api_code(), Some(14));
+        assert_eq!(err.error_code(), Some(LastFmErrorCode::UnauthorizedToken));
+        assert!(err.is_auth_failure());
+        assert_eq!(scrobbler.session_key(), None);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::UrlEncoded("token".into(), "tok/en+1".into()))
+            .with_body(fake::session_response("floyd").to_string())
+            .create();
+        assert_eq!(scrobbler.authenticate_with_token(&token).unwrap().name(), "floyd");
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_status(500)
+            .with_body(fake::error_response(16, "Service temporarily unavailable").to_string())
+            .create();
+        let err = scrobbler.request_auth_token().unwrap_err();
+        assert!(err.to_string().starts_with("Token request failed: "));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn check_scrobbler_error() {
+        let err = ScrobblerError::new("test_error".into());
+        let fmt = format!("{err}");
+        assert_eq!("test_error", fmt);
+
+        let desc = err.description();
+        assert_eq!("test_error", desc);
 
         assert!(err.source().is_none());
     }
@@ -367,17 +2551,7 @@ mod tests {
         let mut scrobbler = Scrobbler::new("api_key", "api_secret");
 
         let _m = mock("POST", mockito::Matcher::Any)
-            .with_body(
-                r#"
-                {   
-                    "session": {
-                        "key": "key",
-                        "subscriber": 1337,
-                        "name": "foo floyd"
-                    }
-                }
-            "#,
-            )
+            .with_body(fake::session_response("foo floyd").key("key").to_string())
             .create();
 
         let resp = scrobbler.authenticate_with_token("some_token");
@@ -388,21 +2562,13 @@ mod tests {
             "old bananas",
             "old bananas",
         );
-        scrobble.with_timestamp(1337);
+        scrobble.with_timestamp(1_337_000_000);
 
         let _m = mock("POST", mockito::Matcher::Any)
             .with_body(
-                r#"
-            { 
-                "nowplaying": {
-                            "artist": [ "0", "foo floyd and the fruit flies" ],
-                            "album": [ "1", "old bananas" ], 
-                            "albumArtist": [ "0", "foo floyd"],
-                            "track": [ "1", "old bananas"], 
-                            "timestamp": "2019-10-04 13:23:40" 
-                        }
-            }
-            "#,
+                fake::now_playing_response("foo floyd and the fruit flies", "old bananas")
+                    .album("old bananas")
+                    .to_string(),
             )
             .create();
 
@@ -410,22 +2576,42 @@ mod tests {
         assert!(resp.is_ok());
     }
 
+    #[test]
+    fn check_scrobbler_now_playing_never_timestamped() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        scrobble.with_timestamp(1_500_000_000).with_chosen_by_user(true).with_duration(215);
+        scrobbler.now_playing(&scrobble).unwrap();
+        scrobbler.now_playing(scrobble.clone()).unwrap();
+
+        let mut track = NowPlaying::new("foo floyd", "old bananas", "bananas");
+        track.with_duration(215);
+        let response = scrobbler.now_playing(&track).unwrap();
+        assert_eq!(response.track().text(), "old bananas");
+        assert_eq!(NowPlaying::from(&scrobble), track);
+
+        let requests = echo.requests();
+        assert_eq!(requests.len(), 3);
+        for request in &requests {
+            assert_eq!(request.method(), "track.updateNowPlaying");
+            assert_eq!(request.params.get("timestamp"), None);
+            assert_eq!(request.params.get("chosenByUser"), None);
+            assert_eq!(request.params.get("duration"), Some("215"));
+        }
+        // The signature covers exactly the same parameters, whichever was passed
+        assert_eq!(requests[0].params, requests[2].params);
+    }
+
     #[test]
     fn check_scrobbler_scrobble() {
         let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
 
         let _m = mock("POST", mockito::Matcher::Any)
-            .with_body(
-                r#"
-                {   
-                    "session": {
-                        "key": "key",
-                        "subscriber": 1337,
-                        "name": "foo floyd"
-                    }
-                }
-            "#,
-            )
+            .with_body(fake::session_response("foo floyd").key("key").to_string())
             .create();
 
         let resp = scrobbler.authenticate_with_token("some_token");
@@ -436,25 +2622,2743 @@ mod tests {
             "old bananas",
             "old bananas",
         );
-        scrobble.with_timestamp(1337);
+        scrobble.with_timestamp(1_337_000_000);
 
         let _m = mock("POST", mockito::Matcher::Any)
             .with_body(
-                r#"
-            { 
-                "scrobbles": [{
-                        "artist": [ "0", "foo floyd and the fruit flies" ],
-                        "album": [ "1", "old bananas" ], 
-                        "albumArtist": [ "0", "foo floyd"],
-                        "track": [ "1", "old bananas"], 
-                        "timestamp": "2019-10-04 13:23:40" 
-                }]
-            }
-            "#,
+                fake::scrobble_response("foo floyd and the fruit flies", "old bananas")
+                    .album("old bananas")
+                    .timestamp(1_337_000_000)
+                    .to_string(),
             )
             .create();
 
         let resp = scrobbler.scrobble(&scrobble);
         assert!(resp.is_ok());
     }
+
+    #[test]
+    fn check_scrobbler_api_error_codes() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let mut scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        scrobble.with_timestamp(1_337_000_000);
+
+        // (code, HTTP status, message, retryable, auth failure)
+        let errors = [
+            (4, 403, "Authentication Failed - You do not have permissions to access the service", false, true),
+            (9, 403, "Invalid session key - Please re-authenticate", false, true),
+            (11, 503, "Service Offline - This service is temporarily offline. Try again later.", true, false),
+            (16, 503, "There was a temporary error processing your request. Please try again", true, false),
+            (29, 429, "Rate Limit Exceeded - Your IP has made too many requests in a short period", true, false),
+        ];
+        for (code, status, message, retryable, auth_failure) in &errors {
+            let _m = mock("POST", mockito::Matcher::Any)
+                .with_status(*status)
+                .with_body(fake::error_response(*code, message).to_string())
+                .create();
+
+            let err = scrobbler.scrobble(&scrobble).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Api);
+            assert_eq!(err.api_code(), Some(*code));
+            assert_eq!(err.http_status(), Some(u16::try_from(*status).unwrap()));
+            assert_eq!(err.is_retryable(), *retryable, "code {code}");
+            assert_eq!(err.is_auth_failure(), *auth_failure, "code {code}");
+            // The message is unchanged from before error codes were exposed
+            assert_eq!(err.to_string(), format!("Scrobble request failed: Non Success status ({status})"));
+        }
+
+        // Failures which aren't Last.fm errors are never authentication failures
+        let _m = mock("POST", mockito::Matcher::Any).with_status(403).with_body("Forbidden").create();
+        let err = scrobbler.scrobble(&scrobble).unwrap_err();
+        assert_eq!((err.kind(), err.http_status(), err.is_auth_failure()), (ErrorKind::Http, Some(403), false));
+    }
+
+    #[test]
+    fn check_scrobbler_batch_wire_params() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut first = Scrobble::new("floyd", "old", "bananas");
+        first.with_timestamp(1_337_000_000);
+        let mut second = Scrobble::new("fruitflies", "new", "more");
+        second.with_timestamp(1_338_000_000);
+        let batch = ScrobbleBatch::from(vec![first, second]);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("api_key".into(), "api_key".into()),
+                mockito::Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+                mockito::Matcher::UrlEncoded("method".into(), "track.scrobble".into()),
+                mockito::Matcher::UrlEncoded("artist[0]".into(), "floyd".into()),
+                mockito::Matcher::UrlEncoded("track[0]".into(), "old".into()),
+                mockito::Matcher::UrlEncoded("album[0]".into(), "bananas".into()),
+                mockito::Matcher::UrlEncoded("timestamp[0]".into(), "1337000000".into()),
+                mockito::Matcher::UrlEncoded("artist[1]".into(), "fruitflies".into()),
+                mockito::Matcher::UrlEncoded("track[1]".into(), "new".into()),
+                mockito::Matcher::UrlEncoded("album[1]".into(), "more".into()),
+                mockito::Matcher::UrlEncoded("timestamp[1]".into(), "1338000000".into()),
+            ]))
+            .with_body(
+                FakeBatch::from_scrobbles(vec![
+                    fake::scrobble_response("floyd", "old")
+                        .album("bananas")
+                        .timestamp(1_337_000_000),
+                    fake::scrobble_response("fruitflies", "new")
+                        .album("more")
+                        .timestamp(1_338_000_000),
+                ])
+                .to_string(),
+            )
+            .create();
+
+        let resp = scrobbler.scrobble_batch(&batch);
+        assert!(resp.is_ok());
+        assert_eq!(resp.unwrap().scrobbles().len(), 2);
+    }
+
+    #[test]
+    fn check_scrobbler_batch_chunked() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let tracks: Vec<Scrobble> = (0..120)
+            .map(|i| {
+                let mut scrobble = Scrobble::new("floyd", &format!("t{i:03}"), "bananas");
+                scrobble.with_timestamp(1_337_000_000 + i);
+                scrobble
+            })
+            .collect();
+        let batch = ScrobbleBatch::from(tracks);
+        // Each chunk's request is told apart by its first track
+        let chunk = |first: &str| {
+            mock("POST", mockito::Matcher::Any)
+                .match_body(mockito::Matcher::UrlEncoded("track[0]".into(), first.into()))
+        };
+
+        {
+            let first = chunk("t000").with_body(fake::batch_response(50, 0).to_string()).create();
+            let second = chunk("t050").with_body(fake::batch_response(49, 1).to_string()).create();
+            let third = chunk("t100").with_body(fake::batch_response(20, 0).to_string()).create();
+
+            let response = scrobbler.scrobble_batch_chunked(&batch).unwrap();
+            assert_eq!(response.scrobbles().len(), 120);
+            assert_eq!((response.accepted_count(), response.ignored_count()), (119, 1));
+            // Submitted timestamps line up with the whole batch
+            assert_eq!(response.scrobbles().iter().last().unwrap().submitted_timestamp(), 1_337_000_119);
+            first.assert();
+            second.assert();
+            third.assert();
+        }
+
+        // The second chunk fails, so the third isn't sent
+        let progress = {
+            let _first = chunk("t000").with_body(fake::batch_response(50, 0).to_string()).create();
+            let _second = chunk("t050")
+                .with_status(400)
+                .with_body(fake::error_response(6, "Invalid parameters").to_string())
+                .create();
+            let third = chunk("t100").expect(0).create();
+
+            let err = scrobbler.scrobble_batch_chunked(&batch).unwrap_err();
+            assert_eq!((err.kind(), err.api_code()), (ErrorKind::Api, Some(6)));
+            assert!(err
+                .to_string()
+                .starts_with("Chunked batch stopped after 50 of 120 scrobbles were submitted: "));
+            third.assert();
+            err.import_progress().unwrap().clone()
+        };
+        assert_eq!(progress.completed(), 50);
+
+        // The rest of the batch can be resumed from the error's progress
+        let second = chunk("t050").with_body(fake::batch_response(50, 0).to_string()).create();
+        let third = chunk("t100").with_body(fake::batch_response(20, 0).to_string()).create();
+        let resumed = scrobbler.scrobble_batch_chunks(&batch, 50).unwrap().resume_from(&progress).unwrap();
+        assert!(resumed.map(|chunk| chunk.result).all(|result| result.is_ok()));
+        second.assert();
+        third.assert();
+
+        let empty = ScrobbleBatch::from(Vec::<Scrobble>::new());
+        assert_eq!(scrobbler.scrobble_batch_chunked(&empty).unwrap_err().to_string(), "Scrobble batch is empty");
+    }
+
+    #[test]
+    fn check_scrobbler_batch_track_metadata() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut detailed = Scrobble::new("floyd", "old", "bananas");
+        detailed
+            .with_timestamp(1_337_000_000)
+            .with_duration(240)
+            .with_track_number(7)
+            .with_mbid("mbid-1")
+            .with_album_artist("Various Artists")
+            .with_chosen_by_user(true);
+        let mut single = Scrobble::without_album("fruitflies", "new");
+        single.with_timestamp(1_338_000_000);
+        scrobbler.scrobble_batch(&ScrobbleBatch::from(vec![detailed, single])).unwrap();
+
+        let params = &echo.requests()[0].params;
+        let entry = |index: usize| -> Vec<(String, String)> {
+            let suffix = format!("[{index}]");
+            let mut entry: Vec<(String, String)> = params
+                .iter()
+                .filter_map(|(key, value)| Some((key.strip_suffix(&suffix)?.to_owned(), value.to_owned())))
+                .collect();
+            entry.sort();
+            entry
+        };
+        let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(key, value)| ((*key).to_owned(), (*value).to_owned())).collect()
+        };
+        assert_eq!(
+            entry(0),
+            pairs(&[
+                ("album", "bananas"),
+                ("albumArtist", "Various Artists"),
+                ("artist", "floyd"),
+                ("chosenByUser", "1"),
+                ("duration", "240"),
+                ("mbid", "mbid-1"),
+                ("timestamp", "1337000000"),
+                ("track", "old"),
+                ("trackNumber", "7"),
+            ])
+        );
+        assert_eq!(entry(1), pairs(&[("artist", "fruitflies"), ("timestamp", "1338000000"), ("track", "new")]));
+    }
+
+    #[test]
+    fn check_scrobbler_media_kind_param() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut podcast = Scrobble::new("floyd", "episode 12", "the show");
+        podcast.with_timestamp(1_337_000_000).with_media_kind(MediaKind::Podcast);
+        let mut music = Scrobble::new("floyd", "fruitflies", "bananas");
+        music.with_timestamp(1_337_000_300);
+
+        // Last.fm doesn't accept the media kind
+        scrobbler.scrobble(&podcast).unwrap();
+        assert_eq!(echo.requests()[0].params.get("mediaKind"), None);
+
+        scrobbler.client.set_endpoint(Endpoint::new(
+            "https://libre.fm/2.0/?format=json".to_owned(),
+            "https://libre.fm/api/auth/".to_owned(),
+        ));
+        scrobbler.scrobble(&podcast).unwrap();
+        scrobbler.now_playing(&podcast).unwrap();
+        scrobbler.scrobble(&music).unwrap();
+        scrobbler.scrobble_batch(&ScrobbleBatch::from(vec![music.clone(), podcast.clone()])).unwrap();
+        let requests = echo.requests();
+        assert_eq!(requests[1].params.get("mediaKind"), Some("podcast"));
+        assert_eq!(requests[2].params.get("mediaKind"), Some("podcast"));
+        assert_eq!(requests[3].params.get("mediaKind"), None);
+        assert_eq!(requests[4].params.get("mediaKind[0]"), None);
+        assert_eq!(requests[4].params.get("mediaKind[1]"), Some("podcast"));
+
+        scrobbler.set_media_kind_param(Some("x-media"));
+        podcast.with_media_kind(MediaKind::Other("lecture".to_owned()));
+        scrobbler.scrobble(&podcast).unwrap();
+        scrobbler.set_media_kind_param(None);
+        scrobbler.scrobble(&podcast).unwrap();
+        let requests = echo.requests();
+        assert_eq!(requests[5].params.get("x-media"), Some("lecture"));
+        assert_eq!(requests[5].params.get("mediaKind"), None);
+        assert_eq!(requests[6].params.get("x-media"), None);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn check_scrobbler_params_match_legacy_map() {
+        use std::collections::HashMap;
+
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let tracks = [
+            ("Simon & Garfunkel", "Cecilia", "Bridge over Troubled Water", Some(1_400_000_000)),
+            ("1+1=2", "100%", "a=b&c=d", Some(1_400_000_100)),
+            ("Björk", "Jóga", "", None),
+            ("🎵 Emoji", "𝄞 Clef", "Sigur Rós ♫", Some(1_400_000_200)),
+            ("floyd", "[0]", "{}", Some(1_400_000_300)),
+        ];
+        let batch: Vec<Scrobble> = tracks
+            .iter()
+            .map(|(artist, track, album, timestamp)| {
+                let mut scrobble = Scrobble::new(artist, track, album);
+                if let Some(timestamp) = timestamp {
+                    scrobble.with_timestamp(*timestamp);
+                }
+                scrobble
+            })
+            .collect();
+        let now = UNIX_EPOCH + Duration::from_secs(1_500_000_042);
+
+        // The parameters the request would have had when they were built from `Scrobble::as_map`
+        let legacy = |entries: Vec<HashMap<String, String>>, indexed: bool| {
+            let mut map: HashMap<String, String> = HashMap::new();
+            for (i, entry) in entries.into_iter().enumerate() {
+                for (key, value) in entry {
+                    map.insert(if indexed { format!("{key}[{i}]") } else { key }, value);
+                }
+            }
+            map.insert("api_key".to_owned(), "api_key".to_owned());
+            map.insert("sk".to_owned(), "seshkey".to_owned());
+            map
+        };
+        let with_timestamp = |scrobble: &Scrobble| {
+            let mut map = scrobble.as_map();
+            map.entry("timestamp".to_owned()).or_insert_with(|| "1500000042".to_owned());
+            map
+        };
+
+        for scrobble in &batch {
+            scrobbler.now_playing(scrobble).unwrap();
+            scrobbler.scrobble_as_of(scrobble, now).unwrap();
+        }
+        scrobbler
+            .scrobble_batch_as_of(&ScrobbleBatch::from(batch.clone()), now)
+            .unwrap();
+
+        let mut expected = Vec::new();
+        for scrobble in &batch {
+            // Now playing requests never carry a timestamp
+            let mut untimed = scrobble.as_map();
+            untimed.remove("timestamp");
+            expected.push(legacy(vec![untimed], false));
+            expected.push(legacy(vec![with_timestamp(scrobble)], false));
+        }
+        expected.push(legacy(batch.iter().map(with_timestamp).collect(), true));
+
+        let requests = echo.requests();
+        assert_eq!(requests.len(), expected.len());
+        for (request, expected) in requests.iter().zip(expected) {
+            let mut params = request.params.clone().into_map();
+            assert!(params.remove("method").is_some());
+            assert!(params.remove("api_sig").is_some());
+            assert_eq!(params, expected);
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_single_item_batch() {
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", EchoTransport::new());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        // Last.fm responds to a batch of one with a bare object rather than an array
+        for tracks in &[vec!["old"], vec!["old", "new"]] {
+            let batch: Vec<Scrobble> = tracks.iter().map(|track| Scrobble::new("floyd", track, "bananas")).collect();
+            let response = scrobbler.scrobble_batch(&ScrobbleBatch::from(batch)).unwrap();
+
+            let responded: Vec<&str> = response.scrobbles().iter().map(|s| s.track().text()).collect();
+            assert_eq!(&responded, tracks);
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_extra_headers() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_extra_header("Authorization", "Bearer relaytoken").unwrap();
+        scrobbler.set_extra_header("X-Relay-Route", "eu").unwrap();
+        assert!(scrobbler.set_extra_header("X-Relay-Route", "eu\nus").is_err());
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        let body = fake::now_playing_response("foo floyd", "old bananas").to_string();
+
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_header("authorization", "Bearer relaytoken")
+            .match_header("x-relay-route", "eu")
+            .with_body(&body)
+            .create();
+        scrobbler.now_playing(&scrobble).unwrap();
+        m.assert();
+
+        // Per-call headers are added to the defaults, replacing any with the same name
+        let mut options = CallOptions::default();
+        options.headers.insert("x-relay-route", "us").unwrap();
+        options.headers.insert("X-Trace", "abc").unwrap();
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_header("authorization", "Bearer relaytoken")
+            .match_header("x-relay-route", "us")
+            .match_header("x-trace", "abc")
+            .with_body(&body)
+            .create();
+        scrobbler.with_call_options(&options).now_playing(&scrobble).unwrap();
+        m.assert();
+
+        // Headers don't affect the signature, and credentials aren't logged
+        let echo = EchoTransport::new();
+        let mut plain = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        plain.authenticate_with_session_key("seshkey").unwrap();
+        plain.now_playing(&scrobble).unwrap();
+        plain.set_extra_header("Authorization", "Bearer relaytoken").unwrap();
+        plain.with_call_options(&options).now_playing(&scrobble).unwrap();
+
+        let requests = echo.requests();
+        assert_eq!(requests[0].params.get("api_sig"), requests[1].params.get("api_sig"));
+        assert!(requests[0].headers.is_empty());
+        assert_eq!(requests[1].headers.len(), 3);
+        assert!(!format!("{:?}", requests[1]).contains("relaytoken"));
+    }
+
+    #[test]
+    fn check_scrobbler_configured_http_client() {
+        use crate::transport::ReqwestTransport;
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let transport = ReqwestTransport::configure_http(|builder| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-configured-by", HeaderValue::from_static("hook"));
+            builder.default_headers(headers)
+        })
+        .unwrap();
+        assert_eq!(transport.info().user_agent, None);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_header("x-configured-by", "hook")
+            .with_body(fake::now_playing_response("foo floyd", "old bananas").to_string())
+            .create();
+        scrobbler
+            .now_playing(Scrobble::new("foo floyd", "old bananas", "bananas"))
+            .unwrap();
+        m.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_http_methods() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_password("user", "pass").unwrap();
+        scrobbler.authenticate_with_token("token").unwrap();
+
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        scrobbler.now_playing(&scrobble).unwrap();
+        scrobbler.scrobble(&scrobble).unwrap();
+        scrobbler.scrobble_batch(&ScrobbleBatch::from(vec![scrobble])).unwrap();
+        let track = vec![("foo floyd".to_owned(), "old bananas".to_owned())];
+        scrobbler.love_many(&track, &BulkLoveOptions::default()).unwrap();
+        scrobbler.unlove_many(&track, &BulkLoveOptions::default()).unwrap();
+
+        // Every wrapped method changes something or authenticates
+        let methods: Vec<(String, HttpMethod)> = echo
+            .requests()
+            .iter()
+            .map(|request| (request.method().to_owned(), request.http_method))
+            .collect();
+        let expected: Vec<(String, HttpMethod)> = [
+            "auth.getMobileSession",
+            "auth.getSession",
+            "track.updateNowPlaying",
+            "track.scrobble",
+            "track.scrobble",
+            "track.love",
+            "track.unlove",
+        ]
+        .iter()
+        .map(|method| ((*method).to_owned(), HttpMethod::Post))
+        .collect();
+        assert_eq!(methods, expected);
+    }
+
+    #[test]
+    fn check_scrobbler_strict_mode() {
+        let scrobble = |track: &str, album: &str, timestamp: Option<u64>| {
+            let mut scrobble = Scrobble::new("floyd", track, album);
+            if let Some(timestamp) = timestamp {
+                scrobble.with_timestamp(timestamp);
+            }
+            scrobble
+        };
+        let tracks = vec![
+            scrobble("fine", "album", Some(1_500_000_000)),
+            scrobble(&"x".repeat(1100), "album", Some(1_500_000_001)),
+            scrobble("blank album", "  ", Some(1_500_000_002)),
+            scrobble("untimed", "album", None),
+        ];
+        let batch = ScrobbleBatch::from(tracks.clone());
+        let expected = vec![
+            ScrobbleIssue::FieldTruncated {
+                position: Some(1),
+                field: "track".to_owned(),
+                original_chars: 1100,
+                truncated_chars: 1024,
+            },
+            ScrobbleIssue::BlankAlbumOmitted { position: Some(2) },
+            ScrobbleIssue::TimestampGenerated { position: Some(3) },
+        ];
+
+        let echo = EchoTransport::new();
+        let mut lenient = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        lenient.set_max_scrobble_age(None);
+        lenient.authenticate_with_session_key("seshkey").unwrap();
+        lenient.set_truncate_long_fields(true);
+        let mut strict = lenient.clone();
+        strict.set_strict(true);
+
+        // Lenient mode works around the issues, and strict mode refuses the same batch with all of them
+        assert_eq!(lenient.validate_batch(&batch).unwrap(), expected);
+        assert_eq!(strict.validate_batch(&batch).unwrap(), expected);
+        assert_eq!(lenient.scrobble_batch(&batch).unwrap().scrobbles().len(), 4);
+        assert_eq!(echo.requests().len(), 1);
+
+        let err = strict.scrobble_batch(&batch).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Strict);
+        assert_eq!(err.issues(), &expected[..]);
+        assert!(err.to_string().starts_with(
+            "Refused in strict mode, with 3 issues: Scrobble 1 in batch has a track name 1100 characters long"
+        ));
+        let err = strict.scrobble_batch_chunks(&batch, 2).err().unwrap();
+        assert_eq!(err.issues(), &expected[..]);
+        assert_eq!(echo.requests().len(), 1);
+
+        // Single scrobbles too, unless their timestamp is given explicitly
+        for track in &tracks {
+            lenient.scrobble(track).unwrap();
+        }
+        assert_eq!(echo.requests().len(), 5);
+        let err = strict.now_playing(&tracks[2]).unwrap_err();
+        assert_eq!(err.issues(), [ScrobbleIssue::BlankAlbumOmitted { position: None }]);
+        let err = strict.scrobble(&tracks[3]).unwrap_err();
+        assert_eq!(err.issues(), [ScrobbleIssue::TimestampGenerated { position: None }]);
+        strict.scrobble_at(&tracks[3], UNIX_EPOCH + Duration::from_secs(1_500_000_003)).unwrap();
+        strict.scrobble(&tracks[0]).unwrap();
+    }
+
+    #[test]
+    fn check_scrobbler_preparation_report() {
+        let mut clean = Scrobble::new("floyd", "fine", "album");
+        clean.with_timestamp(1_500_000_000);
+        let messy = Scrobble::new(&"a".repeat(1100), "long names", &"b".repeat(1030));
+        let mut blank_album = Scrobble::new("floyd", "blank album", "  ");
+        blank_album.with_timestamp(1_500_000_002);
+        let untimed = Scrobble::new("floyd", "untimed", "album");
+        let batch = ScrobbleBatch::from(vec![clean, messy, blank_album, untimed]);
+
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_truncate_long_fields(true);
+
+        let now = UNIX_EPOCH + Duration::from_secs(1_600_000_007);
+        let response = scrobbler.scrobble_batch_as_of(&batch, now).unwrap();
+        let adjustment = |position, field: &str, old_value: Option<String>, new_value: Option<&str>, reason| {
+            Adjustment {
+                position,
+                field: field.to_owned(),
+                old_value,
+                new_value: new_value.map(str::to_owned),
+                reason,
+            }
+        };
+        let truncated = |field: &str, original_chars| ScrobbleIssue::FieldTruncated {
+            position: Some(1),
+            field: field.to_owned(),
+            original_chars,
+            truncated_chars: MAX_NAME_CHARS,
+        };
+        let generated = |position| ScrobbleIssue::TimestampGenerated { position: Some(position) };
+        let expected = [
+            adjustment(1, "artist", Some("a".repeat(1100)), Some(&"a".repeat(255)), truncated("artist", 1100)),
+            adjustment(1, "album", Some("b".repeat(1030)), Some(&"b".repeat(255)), truncated("album", 1030)),
+            adjustment(1, "timestamp", None, Some("1600000007"), generated(1)),
+            adjustment(2, "album", Some("  ".to_owned()), None, ScrobbleIssue::BlankAlbumOmitted { position: Some(2) }),
+            adjustment(3, "timestamp", None, Some("1600000007"), generated(3)),
+        ];
+        let report = response.preparation();
+        assert_eq!(report.adjustments(), &expected[..]);
+        assert_eq!(report.adjustments_at(0).count(), 0);
+        assert_eq!(report.adjustments_at(1).count(), 3);
+
+        // Chunked imports report positions in the whole import
+        let fields: Vec<(usize, String)> = scrobbler
+            .scrobble_batch_chunks(&batch, 2)
+            .unwrap()
+            .flat_map(|chunk| chunk.result.unwrap().preparation().adjustments().to_vec())
+            .map(|adjustment| (adjustment.position, adjustment.field))
+            .collect();
+        let expected_fields: Vec<(usize, String)> = expected
+            .iter()
+            .map(|adjustment| (adjustment.position, adjustment.field.clone()))
+            .collect();
+        assert_eq!(fields, expected_fields);
+
+        // A batch which needed no changes has an empty report
+        let tidy = ScrobbleBatch::from(batch.iter().take(1).cloned().collect::<Vec<_>>());
+        assert!(scrobbler.scrobble_batch(&tidy).unwrap().preparation().is_empty());
+    }
+
+    #[test]
+    fn check_scrobbler_with_keys() {
+        let key = "0123456789abcdef0123456789abcdef";
+        let secret = "fedcba9876543210fedcba9876543210";
+        let session_key = "00112233445566778899aabbccddeeff";
+        let mut scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        scrobble.with_timestamp(1_500_000_000);
+
+        let echo = EchoTransport::new();
+        let api_key = ApiKey::parse(key).unwrap();
+        let api_secret = ApiSecret::parse(secret).unwrap();
+        let mut typed = Scrobbler::with_keys_and_transport(api_key, api_secret, echo.clone());
+        typed.set_max_scrobble_age(None);
+        typed.set_session_key(SessionKey::parse(session_key).unwrap()).unwrap();
+        assert_eq!((typed.api_key(), typed.session_key()), (key, Some(session_key)));
+        typed.scrobble(&scrobble).unwrap();
+
+        let mut untyped = Scrobbler::with_transport(key, secret, echo.clone());
+        untyped.set_max_scrobble_age(None);
+        untyped.authenticate_with_session_key(session_key).unwrap();
+        untyped.scrobble(&scrobble).unwrap();
+
+        let requests = echo.requests();
+        assert_eq!(requests[0].params["api_key"], key);
+        assert_eq!(requests[0].params["sk"], session_key);
+        assert_eq!(requests[0].params, requests[1].params);
+    }
+
+    #[test]
+    fn check_scrobbler_not_authenticated() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        let batch = ScrobbleBatch::from(vec![scrobble.clone()]);
+        let loves = [("foo floyd".to_owned(), "old bananas".to_owned())];
+
+        let errors = [
+            scrobbler.now_playing(&scrobble).unwrap_err(),
+            scrobbler.scrobble(&scrobble).unwrap_err(),
+            scrobbler.scrobble_at(&scrobble, SystemTime::now()).unwrap_err(),
+            scrobbler.scrobble_batch(&batch).unwrap_err(),
+            scrobbler.scrobble_batch_at(&batch, &[SystemTime::now()]).unwrap_err(),
+            scrobbler.scrobble_batch_chunks(&batch, 50).err().unwrap(),
+            scrobbler.love_many(&loves, &BulkLoveOptions::default()).unwrap_err(),
+            scrobbler.unlove_many(&loves, &BulkLoveOptions::default()).unwrap_err(),
+            scrobbler.user_info().unwrap_err(),
+        ];
+        for err in &errors {
+            assert_eq!(err.kind(), ErrorKind::NotAuthenticated);
+            assert_eq!(err.to_string(), "Not authenticated");
+        }
+        scrobbler.set_dry_run(true);
+        assert_eq!(scrobbler.scrobble(&scrobble).unwrap_err().kind(), ErrorKind::NotAuthenticated);
+        assert!(echo.requests().is_empty());
+
+        // An empty session key is rejected, leaving the scrobbler unauthenticated
+        for key in ["", "  "] {
+            let err = scrobbler.authenticate_with_session_key(key).unwrap_err();
+            assert_eq!(err.to_string(), "Invalid session key: the value is empty");
+            assert!(scrobbler.set_session_key(SessionKey::from(key)).is_err());
+            assert_eq!(scrobbler.session_key(), None);
+        }
+
+        scrobbler.set_dry_run(false);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.scrobble(&scrobble).unwrap();
+        assert_eq!(echo.requests().len(), 1);
+    }
+
+    #[test]
+    fn check_scrobbler_verify_session() {
+        use mockito::Matcher;
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        assert_eq!(scrobbler.username(), None);
+
+        // The lookup is signed with the session key, so it's sent in the body rather than the URL
+        let valid = mock("POST", "/")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "user.getInfo".into()),
+                Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+            ]))
+            .with_body(fake::user_info_response("floyd").playcount(1234).to_string())
+            .create();
+        let user = scrobbler.verify_session().unwrap();
+        assert_eq!((user.name(), user.playcount(), user.subscriber()), ("floyd", 1234, false));
+        assert_eq!((user.real_name(), user.country()), (None, None));
+        assert_eq!(user.registered(), Some(UNIX_EPOCH + Duration::from_secs(1_037_793_040)));
+        assert_eq!(scrobbler.username(), Some("floyd"));
+        valid.assert();
+
+        let expired = mock("POST", "/")
+            .with_status(403)
+            .with_body(fake::error_response(9, "Invalid session key - Please re-authenticate").to_string())
+            .create();
+        scrobbler.authenticate_with_session_key("oldkey").unwrap();
+        let err = scrobbler.verify_session().unwrap_err();
+        assert!(err.is_auth_failure());
+        assert_eq!(err.error_code(), Some(LastFmErrorCode::InvalidSessionKey));
+        assert_eq!(
+            err.to_string(),
+            "The session is no longer valid: User info request failed: Non Success status (403)"
+        );
+        assert_eq!(scrobbler.username(), None);
+        expired.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_call_signed() {
+        use mockito::Matcher;
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        let mut params = HashMap::new();
+        params.insert("artist".to_owned(), "Cher".to_owned());
+        params.insert("album".to_owned(), "Believe".to_owned());
+        let err = scrobbler.call_signed("album.getTags", &params).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotAuthenticated);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let tags = mock("POST", "/")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "album.getTags".into()),
+                Matcher::UrlEncoded("artist".into(), "Cher".into()),
+                Matcher::UrlEncoded("album".into(), "Believe".into()),
+                Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+                Matcher::Regex("api_sig=[0-9a-f]{32}".into()),
+            ]))
+            .with_body(r#"{"tags": {"tag": [{"name": "pop"}], "@attr": {"artist": "Cher"}}}"#)
+            .create();
+        let response = scrobbler.call_signed("album.getTags", &params).unwrap();
+        assert_eq!(response["tags"]["tag"][0]["name"], "pop");
+        tags.assert();
+
+        let rejected = mock("POST", "/")
+            .with_status(400)
+            .with_body(fake::error_response(6, "Album not found").to_string())
+            .create();
+        let err = scrobbler.call_signed("album.getTags", &params).unwrap_err();
+        assert_eq!(err.error_code(), Some(LastFmErrorCode::InvalidParameters));
+        assert_eq!(err.to_string(), "album.getTags request failed: Non Success status (400)");
+        rejected.assert();
+
+        // The method name needn't be `'static`, and is logged like any other
+        scrobbler.set_call_log_capacity(1);
+        let method = format!("album.{}", "getTags");
+        let _ = scrobbler.call_signed(&method, &params);
+        assert_eq!(scrobbler.recent_calls()[0].method, "album.getTags");
+
+        // Nothing is sent with a parameter the Scrobbler sets itself, or in dry-run mode
+        params.insert("sk".to_owned(), "otherkey".to_owned());
+        let err = scrobbler.call_signed("album.getTags", &params).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidParameter);
+        assert_eq!(err.to_string(), "The sk parameter is set by the Scrobbler, so can't be passed to album.getTags");
+        params.remove("sk");
+        scrobbler.set_dry_run(true);
+        assert_eq!(scrobbler.call_signed("album.addTags", &params).unwrap_err().kind(), ErrorKind::DryRun);
+    }
+
+    #[test]
+    fn check_scrobbler_redirects() {
+        use mockito::Matcher;
+
+        let server = mockito::server_url();
+        let moved = format!("{server}/moved/2.0/");
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        // A read follows the redirect, with the same parameters
+        let read = mock("GET", "/")
+            .match_query(Matcher::UrlEncoded("method".into(), "track.getInfo".into()))
+            .with_status(301)
+            .with_header("location", &format!("{moved}?format=json&method=track.getInfo"))
+            .create();
+        let moved_read = mock("GET", "/moved/2.0/")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("format".into(), "json".into()),
+                Matcher::UrlEncoded("method".into(), "track.getInfo".into()),
+                Matcher::UrlEncoded("track".into(), "Believe".into()),
+            ]))
+            .with_body(fake::track_info_response("Cher", "Believe").to_string())
+            .create();
+        assert_eq!(scrobbler.track_info("Cher", "Believe").unwrap().name(), "Believe");
+        read.assert();
+        moved_read.assert();
+        assert_eq!(scrobbler.base_url(), server);
+
+        // A signed POST is never sent anywhere else
+        let mut scrobble = Scrobble::new("floyd", "old", "bananas");
+        scrobble.with_timestamp(1_500_000_000);
+        let write = mock("POST", "/")
+            .match_body(Matcher::UrlEncoded("method".into(), "track.scrobble".into()))
+            .with_status(308)
+            .with_header("location", &moved)
+            .expect(2)
+            .create();
+        let err = scrobbler.scrobble(&scrobble).unwrap_err();
+        assert_eq!((err.kind(), err.http_status()), (ErrorKind::Redirected, Some(308)));
+        assert_eq!(err.redirect_location(), Some(moved.as_str()));
+        assert!(!err.is_retryable());
+        assert_eq!(scrobbler.base_url(), server);
+
+        // Unless told to, when the redirect is permanent, for the next call and every clone
+        let reader = scrobbler.clone();
+        scrobbler.set_update_base_url_on_redirect(true);
+        assert_eq!(scrobbler.scrobble(&scrobble).unwrap_err().kind(), ErrorKind::Redirected);
+        write.assert();
+        assert_eq!(scrobbler.base_url(), format!("{moved}?format=json"));
+        assert_eq!(reader.base_url(), scrobbler.base_url());
+
+        let moved_write = mock("POST", "/moved/2.0/")
+            .match_query(Matcher::UrlEncoded("format".into(), "json".into()))
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "track.scrobble".into()),
+                Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+            ]))
+            .with_body(fake::scrobble_response("floyd", "old").to_string())
+            .create();
+        scrobbler.scrobble(&scrobble).unwrap();
+        moved_write.assert();
+
+        // A read redirected too many times gives up
+        let looping = mock("GET", "/moved/2.0/")
+            .match_query(Matcher::UrlEncoded("method".into(), "track.getInfo".into()))
+            .with_status(302)
+            .with_header("location", "/moved/2.0/")
+            .expect(6)
+            .create();
+        let err = scrobbler.track_info("Cher", "Believe").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Redirected);
+        looping.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_track_info_cache() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+
+        // Without a cache, every lookup is sent
+        let info = scrobbler.track_info("Cher", "Believe").unwrap();
+        assert_eq!((info.artist(), info.name()), ("Cher", "Believe"));
+        scrobbler.track_info("Cher", "Believe").unwrap();
+        assert_eq!(echo.requests().len(), 2);
+        assert_eq!(echo.requests()[0].method(), "track.getInfo");
+        assert_eq!(echo.requests()[0].http_method, HttpMethod::Get);
+        assert_eq!(scrobbler.cache_policy(), None);
+
+        let policy = CachePolicy {
+            capacity: 8,
+            ttl: Duration::from_mins(1),
+        };
+        scrobbler.set_cache(Some(policy));
+        assert_eq!(scrobbler.cache_policy(), Some(policy));
+        scrobbler.track_info("Cher", "Believe").unwrap();
+        let cached = scrobbler.track_info("cher", "believe").unwrap();
+        assert_eq!(cached.name(), "Believe");
+        assert_eq!(echo.requests().len(), 3);
+
+        // Clones share the cache, unless told to bypass it
+        scrobbler.clone().track_info("Cher", "Believe").unwrap();
+        assert_eq!(echo.requests().len(), 3);
+        let bypass = CallOptions {
+            bypass_cache: true,
+            ..CallOptions::default()
+        };
+        scrobbler.with_call_options(&bypass).track_info("Cher", "Believe").unwrap();
+        assert_eq!(echo.requests().len(), 4);
+
+        scrobbler.clear_cache();
+        scrobbler.track_info("Cher", "Believe").unwrap();
+        assert_eq!(echo.requests().len(), 5);
+
+        // Writes are never cached
+        scrobbler.authenticate_with_session_key("session_key").unwrap();
+        let scrobble = Scrobble::new("Cher", "Believe", "Believe");
+        scrobbler.scrobble(&scrobble).unwrap();
+        scrobbler.scrobble(&scrobble).unwrap();
+        assert_eq!(echo.requests().len(), 7);
+    }
+
+    #[test]
+    fn check_scrobbler_autocorrect() {
+        use mockito::Matcher;
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_autocorrect(Some(true));
+        assert_eq!(scrobbler.autocorrect(), Some(true));
+        let body = fake::track_info_response("Cher", "Believe").to_string();
+        let lookup = |autocorrect: &str| {
+            mock("GET", Matcher::Any)
+                .match_query(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("method".into(), "track.getInfo".into()),
+                    Matcher::UrlEncoded("autocorrect".into(), autocorrect.into()),
+                ]))
+                .with_body(&body)
+                .expect(1)
+                .create()
+        };
+
+        let corrected = lookup("1");
+        let info = scrobbler.track_info("Chr", "Beleive").unwrap();
+        corrected.assert();
+        assert!(info.autocorrected());
+        assert_eq!((info.artist(), info.name()), ("Cher", "Believe"));
+
+        // Case doesn't count as a correction
+        let info = scrobbler.track_info("cher", "believe").unwrap();
+        assert!(!info.autocorrected());
+
+        let options = CallOptions {
+            autocorrect: Some(false),
+            ..CallOptions::default()
+        };
+        let uncorrected = lookup("0");
+        let info = scrobbler.with_call_options(&options).track_info("Chr", "Beleive").unwrap();
+        uncorrected.assert();
+        assert!(!info.autocorrected());
+
+        // By default the parameter isn't sent at all
+        let echo = EchoTransport::new();
+        let scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        assert!(!scrobbler.track_info("Chr", "Beleive").unwrap().autocorrected());
+        assert_eq!(echo.requests()[0].params.get("autocorrect"), None);
+    }
+
+    #[test]
+    fn check_scrobbler_language() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        let lang = |index: usize| echo.requests()[index].params.get("lang").map(str::to_owned);
+
+        // Without a language the parameter is left out entirely
+        assert_eq!(scrobbler.language(), None);
+        scrobbler.artist_info("Kraftwerk").unwrap();
+        assert_eq!(lang(0), None);
+
+        scrobbler.set_language(Some(Language::parse("de-DE").unwrap()));
+        assert_eq!(scrobbler.language().map(Language::code), Some("de"));
+        let info = scrobbler.album_info("Kraftwerk", "Computerwelt").unwrap();
+        assert_eq!((info.artist(), info.name()), ("Kraftwerk", "Computerwelt"));
+        scrobbler.artist_info("Kraftwerk").unwrap();
+        assert_eq!((lang(1).as_deref(), lang(2).as_deref()), (Some("de"), Some("de")));
+        assert_eq!(echo.requests()[1].method(), "album.getInfo");
+
+        // Overridden per call, and sent only with the lookups which take it
+        let options = CallOptions {
+            language: Some(Language::parse("fra").unwrap()),
+            ..CallOptions::default()
+        };
+        scrobbler.with_call_options(&options).artist_info("Kraftwerk").unwrap();
+        assert_eq!(lang(3).as_deref(), Some("fr"));
+        scrobbler.reader().album_info("Kraftwerk", "Computerwelt").unwrap();
+        assert_eq!(lang(4).as_deref(), Some("de"));
+        scrobbler.track_info("Kraftwerk", "Computerliebe").unwrap();
+        assert_eq!(lang(5), None);
+
+        scrobbler.set_language(None);
+        scrobbler.album_info("Kraftwerk", "Computerwelt").unwrap();
+        assert_eq!(lang(6), None);
+    }
+
+    #[test]
+    fn check_scrobbler_now_playing_best_effort() {
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone())
+            .fail_method("track.updateNowPlaying", vec![Fault::Timeout, Fault::Timeout, Fault::ApiError(9)]);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        let events = observe(&mut scrobbler);
+        let track = Scrobble::new("Kraftwerk", "Computerliebe", "Computerwelt");
+
+        // Updates are strict by default
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        assert_eq!(scrobbler.now_playing(&track).unwrap_err().kind(), ErrorKind::Transport);
+
+        scrobbler.set_now_playing_best_effort(true);
+        let response = scrobbler.now_playing(&track).unwrap();
+        let NowPlayingOutcome::Skipped(reason) = response.outcome() else {
+            panic!("the timed out update should be skipped");
+        };
+        assert_eq!(response.track().text(), "Computerliebe");
+        let skipped = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, ScrobblerEvent::NowPlayingSkipped { reason: skipped } if skipped == reason))
+            .count();
+        assert_eq!(skipped, 1);
+
+        // Errors which retrying won't fix are still returned
+        let err = scrobbler.now_playing(&track).unwrap_err();
+        assert!(err.is_auth_failure());
+        assert_eq!(*scrobbler.now_playing(&track).unwrap().outcome(), NowPlayingOutcome::Sent);
+
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo);
+        scrobbler.set_now_playing_best_effort(true);
+        let err = scrobbler.now_playing(&track).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotAuthenticated);
+    }
+
+    #[test]
+    fn check_scrobbler_last_accepted_scrobble() {
+        let echo = EchoTransport::new().with_ignored_artist("Kraftwerk");
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_max_scrobble_age(None);
+        let track = |artist: &str, track: &str, timestamp: u64| {
+            let mut scrobble = Scrobble::new(artist, track, "Computerwelt");
+            scrobble.with_timestamp(timestamp);
+            scrobble
+        };
+        assert_eq!(scrobbler.last_accepted_scrobble(), None);
+
+        scrobbler.scrobble(&track("Karl Bartos", "Numbers", 1_500_000_000)).unwrap();
+        let last = scrobbler.last_accepted_scrobble().unwrap();
+        assert_eq!((last.artist(), last.track(), last.album()), ("Karl Bartos", "Numbers", "Computerwelt"));
+        assert_eq!(last.timestamp(), 1_500_000_000);
+
+        // Ignored scrobbles don't count, even when newer
+        scrobbler.scrobble(&track("Kraftwerk", "Computerliebe", 1_500_000_100)).unwrap();
+        assert_eq!(scrobbler.last_accepted_scrobble().unwrap().track(), "Numbers");
+
+        // The newest accepted scrobble of a batch, whatever its position
+        let batch = ScrobbleBatch::from(vec![
+            track("Karl Bartos", "Heimcomputer", 1_500_000_300),
+            track("Kraftwerk", "Taschenrechner", 1_500_000_400),
+            track("Karl Bartos", "Computerwelt", 1_500_000_200),
+        ]);
+        scrobbler.scrobble_batch(&batch).unwrap();
+        let last = scrobbler.clone().last_accepted_scrobble().unwrap();
+        assert_eq!((last.track(), last.timestamp()), ("Heimcomputer", 1_500_000_300));
+
+        // An older scrobble accepted later doesn't replace it
+        scrobbler.scrobble(&track("Karl Bartos", "Nummern", 1_499_999_000)).unwrap();
+        assert_eq!(scrobbler.last_accepted_scrobble(), Some(last));
+    }
+
+    #[test]
+    fn check_scrobbler_submission_defaults() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_max_scrobble_age(None);
+        let param = |index: usize, key: &str| echo.requests()[index].params.get(key).map(str::to_owned);
+        let track = Scrobble::new("Kraftwerk", "Computerliebe", "Computerwelt");
+
+        // Without defaults nothing is added
+        scrobbler.scrobble(&track).unwrap();
+        for key in &["context", "chosenByUser", "albumArtist"] {
+            assert_eq!(param(0, key), None, "{key}");
+        }
+
+        scrobbler.set_submission_defaults(SubmissionDefaults {
+            album_artist: Some("Various Artists".to_owned()),
+            context: Some("radio".to_owned()),
+            chosen_by_user: Some(false),
+        });
+        scrobbler.scrobble(&track).unwrap();
+        assert_eq!(param(1, "context").as_deref(), Some("radio"));
+        assert_eq!(param(1, "chosenByUser").as_deref(), Some("0"));
+        assert_eq!(param(1, "albumArtist").as_deref(), Some("Various Artists"));
+
+        // The track's own values win
+        let mut own = track.clone();
+        own.with_context("playlist").with_chosen_by_user(true).with_album_artist("Ralf und Florian");
+        scrobbler.scrobble(&own).unwrap();
+        assert_eq!(param(2, "context").as_deref(), Some("playlist"));
+        assert_eq!(param(2, "chosenByUser").as_deref(), Some("1"));
+        assert_eq!(param(2, "albumArtist").as_deref(), Some("Ralf und Florian"));
+
+        scrobbler.scrobble_batch(&ScrobbleBatch::from(vec![own, track.clone()])).unwrap();
+        assert_eq!(param(3, "context[0]").as_deref(), Some("playlist"));
+        assert_eq!(param(3, "context[1]").as_deref(), Some("radio"));
+        assert_eq!(param(3, "chosenByUser[1]").as_deref(), Some("0"));
+        assert_eq!(param(3, "albumArtist[1]").as_deref(), Some("Various Artists"));
+
+        // Now playing updates don't take `chosenByUser`
+        scrobbler.now_playing(&track).unwrap();
+        assert_eq!(echo.requests()[4].method(), "track.updateNowPlaying");
+        assert_eq!(param(4, "context").as_deref(), Some("radio"));
+        assert_eq!(param(4, "albumArtist").as_deref(), Some("Various Artists"));
+        assert_eq!(param(4, "chosenByUser"), None);
+    }
+
+    #[test]
+    fn check_scrobbler_revalidates_cache() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        // Responses expire at once, so every lookup after the first revalidates the cached one
+        scrobbler.set_cache(Some(CachePolicy {
+            capacity: 8,
+            ttl: Duration::ZERO,
+        }));
+        let body = fake::track_info_response("Cher", "Believe").to_string();
+
+        let fetch = mock("GET", mockito::Matcher::Any)
+            .with_header("etag", "W/\"v1\"")
+            .with_body(&body)
+            .expect(1)
+            .create();
+        assert_eq!(scrobbler.track_info("Cher", "Believe").unwrap().name(), "Believe");
+
+        // A 304 has no body of its own: the cached response is used again
+        let not_modified = mock("GET", mockito::Matcher::Any)
+            .match_header("if-none-match", "W/\"v1\"")
+            .with_status(304)
+            .expect(2)
+            .create();
+        assert_eq!(scrobbler.track_info("cher", "believe").unwrap().name(), "Believe");
+        assert_eq!(scrobbler.track_info("Cher", "Believe").unwrap().name(), "Believe");
+        fetch.assert();
+        not_modified.assert();
+        assert!(scrobbler.recent_calls().iter().all(|call| call.outcome == CallOutcome::Success));
+
+        // Responses without validators are fetched again in full
+        scrobbler.clear_cache();
+        let unconditional = mock("GET", mockito::Matcher::Any)
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_body(&body)
+            .expect(2)
+            .create();
+        scrobbler.track_info("Cher", "Believe").unwrap();
+        scrobbler.track_info("Cher", "Believe").unwrap();
+        unconditional.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_batch_chunks() {
+        let tracks: Vec<Scrobble> = (0..7).map(|i| Scrobble::new("floyd", &format!("track {i}"), "")).collect();
+        let batch = ScrobbleBatch::from(tracks);
+        let failing_request = |fault: Fault| {
+            let transport = FaultyTransport::new(EchoTransport::new()).fail_request(1, fault);
+            let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+            scrobbler.authenticate_with_session_key("seshkey").unwrap();
+            scrobbler
+        };
+        let summary = |chunks: BatchChunks<'_>| -> Vec<_> {
+            chunks
+                .map(|chunk| (chunk.range, chunk.result.map(|response| response.scrobbles().len())))
+                .map(|(range, result)| (range, result.map_err(|err| err.http_status())))
+                .collect()
+        };
+
+        // A transient failure is reported, and the import carries on...
+        let scrobbler = failing_request(Fault::HttpStatus(503));
+        assert_eq!(
+            summary(scrobbler.scrobble_batch_chunks(&batch, 3).unwrap()),
+            vec![(0..3, Ok(3)), (3..6, Err(Some(503))), (6..7, Ok(1))]
+        );
+
+        // ...but any other failure ends it
+        let scrobbler = failing_request(Fault::HttpStatus(400));
+        assert_eq!(
+            summary(scrobbler.scrobble_batch_chunks(&batch, 3).unwrap()),
+            vec![(0..3, Ok(3)), (3..6, Err(Some(400)))]
+        );
+
+        assert!(scrobbler.scrobble_batch_chunks(&batch, 0).is_err());
+        assert!(scrobbler.scrobble_batch_chunks(&batch, 51).is_err());
+    }
+
+    #[test]
+    fn check_scrobbler_batch_concurrent() {
+        // Counts the requests being sent at once
+        #[derive(Default)]
+        struct InFlight {
+            echo: EchoTransport,
+            current: AtomicUsize,
+            peak: AtomicUsize,
+        }
+
+        impl Transport for Arc<InFlight> {
+            fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+                let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(current, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(50));
+                self.current.fetch_sub(1, Ordering::SeqCst);
+                self.echo.send(request)
+            }
+        }
+
+        let tracks: Vec<Scrobble> = (0..230).map(|i| Scrobble::new("floyd", &format!("track {i}"), "")).collect();
+        let batch = ScrobbleBatch::from(tracks);
+        let in_flight = Arc::new(InFlight::default());
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", in_flight.clone());
+        assert!(scrobbler.scrobble_batch_concurrent(&batch, 3).unwrap_err().to_string().contains("Not authenticated"));
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        assert!(scrobbler.scrobble_batch_concurrent(&batch, 0).is_err());
+
+        // Every chunk is returned in order, whichever finished first
+        let chunks = scrobbler.scrobble_batch_concurrent(&batch, 3).unwrap();
+        let summary: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| (chunk.range, chunk.result.unwrap().scrobbles().len()))
+            .collect();
+        assert_eq!(summary, vec![(0..50, 50), (50..100, 50), (100..150, 50), (150..200, 50), (200..230, 30)]);
+        assert_eq!(in_flight.echo.requests().len(), 5);
+        assert_eq!(in_flight.peak.load(Ordering::SeqCst), 3);
+
+        // No more chunks are started once the daily limit is hit
+        let echo = EchoTransport::new().with_daily_limit(120);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let chunks = scrobbler.scrobble_batch_concurrent(&batch, 1).unwrap();
+        assert_eq!(echo.requests().len(), 3);
+        let remaining: Vec<_> = chunks.iter().map(|chunk| chunk.daily_limit.map(|limit| limit.remaining)).collect();
+        assert_eq!(remaining, [None, None, Some(110)]);
+
+        // Nor once a chunk fails for good
+        let transport = FaultyTransport::new(EchoTransport::new()).fail_request(0, Fault::HttpStatus(400));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let chunks = scrobbler.scrobble_batch_concurrent(&batch, 1).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].result.as_ref().unwrap_err().http_status(), Some(400));
+    }
+
+    #[test]
+    fn check_scrobbler_batch_chunks_daily_limit() {
+        let tracks: Vec<Scrobble> = (0..8).map(|i| Scrobble::new("floyd", &format!("track {i}"), "")).collect();
+        let echo = EchoTransport::new().with_daily_limit(4);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let started = SystemTime::now();
+        let chunks: Vec<ChunkResult> = scrobbler
+            .scrobble_batch_chunks(&ScrobbleBatch::from(tracks), 3)
+            .unwrap()
+            .collect();
+
+        // The limit is hit partway through the second chunk, and the third is never sent
+        assert_eq!(echo.requests().len(), 2);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].daily_limit, None);
+        assert_eq!(chunks[1].range, 3..6);
+        assert!(chunks[1].result.is_ok());
+
+        let limit = chunks[1].daily_limit.unwrap();
+        assert_eq!(limit.remaining, 4);
+        let resume_at = limit.resume_at.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert_eq!(resume_at % 86_400, 0);
+        assert!(limit.resume_at > started);
+        assert!(limit.resume_at <= started + Duration::from_hours(24));
+    }
+
+    #[test]
+    fn check_scrobbler_batch_chunks_resume() {
+        let tracks: Vec<Scrobble> = (0..10).map(|i| Scrobble::new("floyd", &format!("track {i}"), "")).collect();
+        let batch = ScrobbleBatch::from(tracks);
+        let submitted = |echo: &EchoTransport| -> Vec<String> {
+            echo.requests()
+                .iter()
+                .flat_map(|request| (0..3).filter_map(move |i| request.params.get(&format!("track[{i}]"))))
+                .map(str::to_owned)
+                .collect()
+        };
+
+        // The third chunk fails, ending the first run
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone()).fail_request(2, Fault::HttpStatus(400));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut saved = Vec::new();
+        let chunks = scrobbler.scrobble_batch_chunks(&batch, 3).unwrap();
+        let results: Vec<bool> = chunks
+            .on_progress(|progress| saved.push(serde_json::to_string(progress).unwrap()))
+            .map(|chunk| chunk.result.is_ok())
+            .collect();
+        assert_eq!(results, vec![true, true, false]);
+        assert_eq!(saved.len(), 2);
+
+        let progress: ImportProgress = serde_json::from_str(saved.last().unwrap()).unwrap();
+        assert_eq!(progress.completed(), 6);
+
+        // Resuming submits exactly the rest
+        let resumed = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", resumed.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let chunks = scrobbler.scrobble_batch_chunks(&batch, 3).unwrap().resume_from(&progress).unwrap();
+        let ranges: Vec<_> = chunks.map(|chunk| chunk.range).collect();
+        assert_eq!(ranges, vec![6..9, 9..10]);
+
+        let all: Vec<String> = submitted(&echo).into_iter().chain(submitted(&resumed)).collect();
+        let expected: Vec<String> = (0..10).map(|i| format!("track {i}")).collect();
+        assert_eq!(all, expected);
+
+        // Progress is tied to the batch it was recorded for
+        let other = ScrobbleBatch::from(vec![("floyd", "track 0", "")]);
+        assert!(scrobbler.scrobble_batch_chunks(&other, 3).unwrap().resume_from(&progress).is_err());
+    }
+
+    #[test]
+    fn check_scrobbler_plan_import() {
+        use crate::plan::ImportStatus::{New, ProbableDuplicate, TooOld};
+
+        let now = 1_600_000_000;
+        let played = |i: u64| now - 600 * (i + 1);
+        let mut tracks: Vec<Scrobble> = (0..9).map(|i| Scrobble::new("floyd", &format!("track {i}"), "")).collect();
+        for (i, track) in (0..).zip(tracks.iter_mut()) {
+            track.with_timestamp(played(i));
+        }
+        tracks[8].with_timestamp(now - 15 * 24 * 60 * 60);
+        // Played twice in a row, but only scrobbled once
+        let mut replayed = tracks[0].clone();
+        replayed.with_timestamp(played(0) + 10);
+        tracks.push(replayed);
+        let batch = ScrobbleBatch::from(tracks);
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let at = UNIX_EPOCH + Duration::from_secs(now);
+        let err = scrobbler.plan_import_as_of(&batch, Duration::from_mins(1), at).unwrap_err();
+        assert!(err.to_string().contains("Scrobbler::set_username"), "{}", err);
+        scrobbler.set_username("floyd");
+
+        // The history overlaps the first half of the batch, with the names spelled differently and the timestamps
+        // not quite matching; track 5 was scrobbled, but too long ago to be the same play
+        let history = [
+            ("Floyd", "Track 4", played(4) - 30),
+            ("floyd", "track 3", played(3)),
+            ("floyd", " track 2 ", played(2) + 60),
+            ("FLOYD", "TRACK 1", played(1)),
+            ("floyd", "track 0", played(0) - 5),
+            ("floyd", "track 5", played(5) - 3600),
+        ];
+        let page = |page: &str| {
+            mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("method".into(), "user.getRecentTracks".into()),
+                mockito::Matcher::UrlEncoded("user".into(), "floyd".into()),
+                mockito::Matcher::UrlEncoded("from".into(), (played(7) - 60).to_string()),
+                mockito::Matcher::UrlEncoded("to".into(), (played(0) + 10 + 60).to_string()),
+                mockito::Matcher::UrlEncoded("page".into(), page.into()),
+            ])
+        };
+        // Last.fm lists the track being played now on every page
+        let body = |tracks: &[(&str, &str, u64)], page| {
+            fake::recent_tracks_response("floyd", tracks).now_playing("floyd", "track 9").page(page, 2).to_string()
+        };
+        let first = mock("GET", mockito::Matcher::Any)
+            .match_query(page("1"))
+            .with_body(body(&history[..3], 1))
+            .create();
+        let second = mock("GET", mockito::Matcher::Any)
+            .match_query(page("2"))
+            .with_body(body(&history[3..], 2))
+            .create();
+
+        let plan = scrobbler.plan_import_as_of(&batch, Duration::from_mins(1), at).unwrap();
+        first.assert();
+        second.assert();
+        let duplicate = |timestamp| ProbableDuplicate {
+            existing_timestamp: timestamp,
+        };
+        assert_eq!(
+            plan.statuses(),
+            [
+                duplicate(played(0) - 5),
+                duplicate(played(1)),
+                duplicate(played(2) + 60),
+                duplicate(played(3)),
+                duplicate(played(4) - 30),
+                New,
+                New,
+                New,
+                TooOld,
+                New,
+            ]
+        );
+        assert_eq!(plan.new_scrobbles(), vec![5, 6, 7, 9]);
+        assert_eq!(plan.probable_duplicates(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(plan.too_old(), vec![8]);
+
+        // Submitting with the plan leaves the duplicates out
+        let echo = EchoTransport::new();
+        let mut submitter = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        submitter.set_max_scrobble_age(None);
+        submitter.authenticate_with_session_key("seshkey").unwrap();
+        let chunks = submitter.scrobble_batch_chunks(&batch, 50).unwrap().skip_duplicates(&plan).unwrap();
+        let results: Vec<ChunkResult> = chunks.collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].range, 0..5);
+        let request = &echo.requests()[0];
+        let names: Vec<&str> = (0..5).filter_map(|i| request.params.get(&format!("track[{i}]"))).collect();
+        assert_eq!(names, ["track 5", "track 6", "track 7", "track 8", "track 0"]);
+        assert!(request.params.get("track[5]").is_none());
+
+        // A plan only applies to the batch it was made for, before the import starts
+        let other = ScrobbleBatch::from(vec![("floyd", "track 0", "")]);
+        assert!(submitter.scrobble_batch_chunks(&other, 50).unwrap().skip_duplicates(&plan).is_err());
+        let mut started = submitter.scrobble_batch_chunks(&batch, 1).unwrap();
+        started.next();
+        assert!(started.skip_duplicates(&plan).is_err());
+    }
+
+    #[test]
+    fn check_scrobbler_recent_tracks() {
+        use mockito::Matcher;
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let err = scrobbler.recent_tracks(3, None, None).unwrap_err();
+        assert!(err.to_string().starts_with("Looking up recent tracks needs the authenticated user's name"));
+        scrobbler.set_username("floyd");
+
+        let page = |page: &str| {
+            Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "user.getRecentTracks".into()),
+                Matcher::UrlEncoded("user".into(), "floyd".into()),
+                Matcher::UrlEncoded("from".into(), "1500000000".into()),
+                Matcher::UrlEncoded("limit".into(), "3".into()),
+                Matcher::UrlEncoded("page".into(), page.into()),
+            ])
+        };
+        let body = |tracks: &[(&str, &str, u64)], page| {
+            fake::recent_tracks_response("floyd", tracks).now_playing("floyd", "now").page(page, 3).to_string()
+        };
+        let first = mock("GET", Matcher::Any)
+            .match_query(page("1"))
+            .with_body(body(&[("floyd", "fifth", 1_500_001_200), ("floyd", "fourth", 1_500_000_900)], 1))
+            .create();
+        let second = mock("GET", Matcher::Any)
+            .match_query(page("2"))
+            .with_body(body(&[("floyd", "third", 1_500_000_600), ("floyd", "second", 1_500_000_300)], 2))
+            .create();
+        let third = mock("GET", Matcher::Any).match_query(page("3")).expect(0).create();
+
+        // Only the first page's now playing track is listed, and it doesn't count towards the limit
+        let recent = scrobbler.recent_tracks(3, Some(1_500_000_000), None).unwrap();
+        let names: Vec<(&str, Option<u64>)> = recent.iter().map(|track| (track.name(), track.timestamp())).collect();
+        assert_eq!(
+            names,
+            [
+                ("now", None),
+                ("fifth", Some(1_500_001_200)),
+                ("fourth", Some(1_500_000_900)),
+                ("third", Some(1_500_000_600)),
+            ]
+        );
+        assert!(recent[0].now_playing() && !recent[1].now_playing());
+        assert_eq!((recent[1].artist(), recent[1].album()), ("floyd", None));
+        first.assert();
+        second.assert();
+        third.assert();
+
+        // A batch resubmitted after a crash keeps only what didn't make it
+        let mut pending: Vec<Scrobble> = ["third", "fourth", "fifth", "sixth"]
+            .iter()
+            .zip(0..)
+            .map(|(track, i)| {
+                let mut scrobble = Scrobble::new("Floyd", track, "");
+                scrobble.with_timestamp(1_500_000_600 + i * 300 + 5);
+                scrobble
+            })
+            .collect();
+        pending.push(Scrobble::new("floyd", "now", ""));
+        let remaining = ScrobbleBatch::from(pending).without_scrobbled(&recent, Duration::from_secs(30));
+        let names: Vec<&str> = remaining.iter().map(Scrobble::track).collect();
+        assert_eq!(names, ["sixth", "now"]);
+    }
+
+    #[test]
+    fn check_scrobbler_special_characters() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let cases = [
+            ("Simon & Garfunkel", "Cecilia", "Bridge over Troubled Water"),
+            ("1+1=2", "100%", "a=b&c=d"),
+            ("Björk", "Jóga", "Homogenic"),
+            ("🎵 Emoji", "𝄞 Clef", "Sigur Rós ♫"),
+        ];
+
+        for (artist, track, album) in &cases {
+            // The signature is computed over the raw, not the encoded, values
+            let base = format!(
+                "album{album}api_keyapi_keyartist{artist}methodtrack.updateNowPlayingskseshkeytrack{track}api_secret"
+            );
+            let signature = format!("{:x}", md5::compute(base));
+
+            let m = mock("POST", mockito::Matcher::Any)
+                .match_body(mockito::Matcher::AllOf(vec![
+                    mockito::Matcher::UrlEncoded("artist".into(), (*artist).to_owned()),
+                    mockito::Matcher::UrlEncoded("track".into(), (*track).to_owned()),
+                    mockito::Matcher::UrlEncoded("album".into(), (*album).to_owned()),
+                    mockito::Matcher::UrlEncoded("api_sig".into(), signature),
+                ]))
+                .with_body(fake::now_playing_response(artist, track).album(album).to_string())
+                .create();
+
+            let resp = scrobbler.now_playing(Scrobble::new(artist, track, album)).unwrap();
+            assert_eq!(resp.artist().text(), *artist);
+            m.assert();
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_omits_empty_album() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut no_album = Scrobble::new("floyd", "bananas", "");
+        no_album.with_timestamp(1_500_000_000);
+        let mut blank_album = Scrobble::new("floyd", "echoes", " \t ");
+        blank_album.with_timestamp(1_500_000_100);
+        let mut album = Scrobble::new("floyd", "money", "dark side");
+        album.with_timestamp(1_500_000_200);
+
+        scrobbler.now_playing(&no_album).unwrap();
+        scrobbler.scrobble(&blank_album).unwrap();
+        scrobbler
+            .scrobble_batch(&ScrobbleBatch::from(vec![no_album, album, blank_album]))
+            .unwrap();
+
+        let requests = echo.requests();
+        assert!(!requests[0].params.contains_key("album"));
+        assert!(!requests[1].params.contains_key("album"));
+        assert!(!requests[2].params.contains_key("album[0]"));
+        assert_eq!(requests[2].params.get("album[1]"), Some("dark side"));
+        assert!(!requests[2].params.contains_key("album[2]"));
+        assert!(!requests[2].params.to_form_body().contains("album[0]"));
+
+        // The body and the signature agree on which parameters were sent
+        for request in &requests {
+            let method = request.params.get("method").unwrap();
+            let signature = signing::sign(&Md5Signer, method, &request.params, "api_secret");
+            assert_eq!(request.params.get("api_sig"), Some(signature.as_str()));
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_album_combinations() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        // Album, album artist, and the album keys expected in the request
+        let cases: [(&str, &str, &[&str]); 4] = [
+            ("Now 37", "", &["album"]),
+            ("", "Various Artists", &["albumArtist"]),
+            ("Now 37", "Various Artists", &["album", "albumArtist"]),
+            ("", "", &[]),
+        ];
+
+        for (album, album_artist, expected) in &cases {
+            let mut scrobble = Scrobble::without_album("Blur", "Song 2");
+            scrobble.with_album(album).with_album_artist(album_artist).with_timestamp(1_500_000_000);
+            let mut track = NowPlaying::without_album("Blur", "Song 2");
+            track.with_album(album).with_album_artist(album_artist);
+            scrobbler.scrobble(&scrobble).unwrap();
+            scrobbler.now_playing(&track).unwrap();
+
+            let requests = echo.requests();
+            for request in &requests[requests.len() - 2..] {
+                let body = request.params.to_form_body();
+                let sent: Vec<&str> = ["album", "albumArtist"]
+                    .iter()
+                    .copied()
+                    .filter(|key| body.split('&').any(|pair| pair.starts_with(&format!("{key}="))))
+                    .collect();
+                assert_eq!(sent, *expected, "{album} / {album_artist}");
+
+                let signed: Vec<&str> = signing::signed_param_names(&request.params, &Md5Signer)
+                    .into_iter()
+                    .filter(|key| key.starts_with("album"))
+                    .collect();
+                assert_eq!(signed, *expected, "{album} / {album_artist}");
+                let method = request.params.get("method").unwrap();
+                let signature = signing::sign(&Md5Signer, method, &request.params, "api_secret");
+                assert_eq!(request.params.get("api_sig"), Some(signature.as_str()));
+            }
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_submitted_timestamp() {
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", EchoTransport::new());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1_500_000_123);
+
+        let untimed = Scrobble::new("foo floyd", "old bananas", "bananas");
+        let mut timed = Scrobble::new("foo floyd", "new bananas", "bananas");
+        timed.with_timestamp(1_400_000_000);
+
+        // Generated from the clock when the scrobble has no timestamp, and the explicit one otherwise
+        assert_eq!(scrobbler.scrobble_as_of(&untimed, now).unwrap().submitted_timestamp(), 1_500_000_123);
+        assert_eq!(scrobbler.scrobble_as_of(&timed, now).unwrap().submitted_timestamp(), 1_400_000_000);
+
+        let batch = ScrobbleBatch::from(vec![untimed.clone(), timed.clone()]);
+        let response = scrobbler.scrobble_batch_as_of(&batch, now).unwrap();
+        let timestamps: Vec<_> = response.scrobbles().iter().map(ScrobbleResponse::submitted_timestamp).collect();
+        assert_eq!(timestamps, vec![1_500_000_123, 1_400_000_000]);
+
+        // Also reported in dry-run mode
+        scrobbler.set_dry_run(true);
+        assert_eq!(scrobbler.scrobble_as_of(&untimed, now).unwrap().submitted_timestamp(), 1_500_000_123);
+        let response = scrobbler.scrobble_batch_as_of(&batch, now).unwrap();
+        let timestamps: Vec<_> = response.scrobbles().iter().map(ScrobbleResponse::submitted_timestamp).collect();
+        assert_eq!(timestamps, vec![1_500_000_123, 1_400_000_000]);
+    }
+
+    #[test]
+    fn check_scrobbler_max_response_size() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_max_response_size(1024);
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        });
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+
+        // Declared too large, and never retried
+        let m = mock("POST", mockito::Matcher::Any)
+            .with_status(502)
+            .with_body("<html>".repeat(10_000))
+            .expect(1)
+            .create();
+        let err = scrobbler.now_playing(&scrobble).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseTooLarge);
+        assert_eq!(err.response_size_limit(), Some(1024));
+        assert_eq!(err.content_length(), Some(60_000));
+        assert!(err.to_string().contains("maximum size of 1024 bytes (declared size: 60000 bytes)"));
+        m.assert();
+
+        // Streamed without a declared size, and abandoned part way through
+        let m = mock("POST", mockito::Matcher::Any)
+            .with_body_from_fn(|body| {
+                for _ in 0..1_000 {
+                    body.write_all(&[b'x'; 1024])?;
+                }
+                Ok(())
+            })
+            .create();
+        let err = scrobbler.now_playing(&scrobble).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResponseTooLarge);
+        assert_eq!(err.content_length(), None);
+        m.assert();
+
+        // Right at the limit is fine
+        let body = fake::now_playing_response("foo floyd", "old bananas").album("bananas").to_string();
+        let _m = mock("POST", mockito::Matcher::Any).with_body(&body).create();
+        scrobbler.set_max_response_size(body.len() as u64);
+        assert!(scrobbler.now_playing(&scrobble).is_ok());
+    }
+
+    #[test]
+    fn check_scrobbler_dry_run() {
+        let m = mock("POST", mockito::Matcher::Any).expect(0).create();
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.set_dry_run(true);
+        assert!(scrobbler.is_dry_run());
+
+        // Submissions are still validated in dry-run mode
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        assert!(scrobbler.scrobble(&scrobble).is_err());
+
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let resp = scrobbler.now_playing(&scrobble).unwrap();
+        assert!(resp.dry_run());
+        assert_eq!(resp.artist().text(), "foo floyd");
+
+        let mut timestamped = scrobble.clone();
+        timestamped.with_timestamp(1_337_000_000);
+        let resp = scrobbler.scrobble(&timestamped).unwrap();
+        assert!(resp.dry_run());
+        assert_eq!(resp.track().text(), "old bananas");
+        assert_eq!(resp.timestamp(), "1337000000");
+
+        let batch = ScrobbleBatch::from(vec![timestamped, scrobble]);
+        let resp = scrobbler.scrobble_batch(&batch).unwrap();
+        assert!(resp.dry_run());
+        assert_eq!(resp.scrobbles().len(), 2);
+        assert_eq!(resp.scrobbles().iter().next().unwrap().timestamp(), "1337000000");
+        assert!(resp.scrobbles().iter().all(ScrobbleResponse::dry_run));
+
+        assert!(scrobbler.scrobble_batch(&ScrobbleBatch::new()).is_err());
+
+        m.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_dry_run_auth() {
+        let m = mock("POST", mockito::Matcher::Any).expect(0).create();
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_dry_run(true);
+        scrobbler.set_dry_run_auth(true);
+
+        assert!(scrobbler.authenticate_with_password("", "").is_err());
+        assert!(scrobbler.authenticate_with_password("user", "pass").is_ok());
+        assert!(scrobbler.session_key().is_some());
+        assert!(scrobbler.authenticate_with_token("some_token").is_ok());
+
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        assert!(scrobbler.scrobble(&scrobble).unwrap().dry_run());
+
+        m.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_dry_run_sends_auth() {
+        let m = mock("POST", mockito::Matcher::Any)
+            .with_body(fake::session_response("foo floyd").key("key").to_string())
+            .expect(1)
+            .create();
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_dry_run(true);
+
+        assert!(scrobbler.authenticate_with_password("user", "pass").is_ok());
+        assert_eq!(scrobbler.session_key(), Some("key"));
+
+        let scrobble = Scrobble::new("foo floyd", "old bananas", "bananas");
+        assert!(scrobbler.scrobble(&scrobble).unwrap().dry_run());
+
+        m.assert();
+    }
+
+    #[test]
+    fn check_scrobbler_replays_fixtures() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/scrobbler");
+        let mut scrobbler =
+            Scrobbler::with_transport("api_key", "api_secret", FixtureTransport::replay(fixtures));
+        scrobbler.set_max_scrobble_age(None);
+
+        let resp = scrobbler.authenticate_with_password("user", "pass").unwrap();
+        assert_eq!(resp.name(), "user");
+
+        let mut scrobble = Scrobble::new("Los Campesinos!", "To Tundra", "No Blues");
+        scrobble.with_timestamp(1_337_000_000);
+
+        let resp = scrobbler.scrobble(&scrobble).unwrap();
+        assert_eq!(resp.artist().text(), "Los Campesinos!");
+        assert_eq!(resp.timestamp(), "1337000000");
+    }
+
+    fn observe(scrobbler: &mut Scrobbler) -> Arc<std::sync::Mutex<Vec<ScrobblerEvent>>> {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observed = Arc::clone(&events);
+        scrobbler.set_event_observer(move |event| observed.lock().unwrap().push(event.clone()));
+
+        events
+    }
+
+    #[test]
+    fn check_scrobbler_events_for_retried_call() {
+        let transport = FaultyTransport::new(EchoTransport::new()).fail_request(0, Fault::ApiError(29));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        });
+        let events = observe(&mut scrobbler);
+
+        let mut scrobble = Scrobble::new("floyd", "bananas", "old");
+        scrobble.with_timestamp(1_337_000_000);
+        assert!(scrobbler.scrobble(&scrobble).is_ok());
+
+        let events = events.lock().unwrap();
+        let ScrobblerEvent::RateLimited { wait: delay } = events[1] else {
+            panic!("Unexpected event {:?}", events[1])
+        };
+        // Jittered from the policy's 1ms
+        assert!(delay >= Duration::from_micros(500) && delay <= Duration::from_millis(1));
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[0],
+            ScrobblerEvent::RequestStarted {
+                method: "track.scrobble".to_owned()
+            }
+        );
+        assert_eq!(
+            events[2],
+            ScrobblerEvent::RetryScheduled {
+                method: "track.scrobble".to_owned(),
+                attempt: 1,
+                delay,
+            }
+        );
+        let ScrobblerEvent::RequestFinished {
+            method,
+            outcome,
+            elapsed,
+        } = &events[3]
+        else {
+            panic!("Unexpected event {:?}", events[3])
+        };
+        assert_eq!(method, "track.scrobble");
+        assert_eq!(*outcome, RequestOutcome::Success);
+        assert!(*elapsed >= delay);
+    }
+
+    #[test]
+    fn check_scrobbler_events_without_retry() {
+        let transport = FaultyTransport::new(EchoTransport::new()).fail_request(0, Fault::HttpStatus(503));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let events = observe(&mut scrobbler);
+
+        let scrobble = Scrobble::new("floyd", "bananas", "old");
+        assert!(scrobbler.now_playing(&scrobble).is_err());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[1],
+            ScrobblerEvent::RequestFinished {
+                outcome: RequestOutcome::HttpStatus(503),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn check_scrobbler_retries_transient_faults() {
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone()).fail_method(
+            "track.scrobble",
+            vec![Fault::Timeout, Fault::ConnectError, Fault::ApiError(16), Fault::ApiError(9)],
+        );
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        });
+
+        let mut scrobble = Scrobble::new("floyd", "bananas", "old");
+        scrobble.with_timestamp(1_337_000_000);
+
+        // Timeout, connection error, then a 3rd attempt which is temporarily unavailable: out of attempts
+        assert!(scrobbler.scrobble(&scrobble).is_err());
+        // Invalid session key is permanent, and isn't retried
+        assert!(scrobbler.scrobble(&scrobble).is_err());
+        assert!(echo.requests().is_empty());
+
+        assert!(scrobbler.scrobble(&scrobble).is_ok());
+        assert_eq!(echo.requests().len(), 1);
+    }
+
+    #[test]
+    fn check_scrobbler_retries_server_failures() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+        });
+        let scrobble = Scrobble::new("floyd", "bananas", "old");
+
+        let m = mock("POST", mockito::Matcher::Any).with_status(503).expect(3).create();
+        assert!(scrobbler.scrobble(&scrobble).is_err());
+        m.assert();
+
+        let m = mock("POST", mockito::Matcher::Any)
+            .with_status(503)
+            .with_body(fake::error_response(11, "Service Offline").to_string())
+            .expect(3)
+            .create();
+        assert!(scrobbler.now_playing(&scrobble).is_err());
+        m.assert();
+
+        // Permanent errors are sent once
+        for code in &[10, 9] {
+            let m = mock("POST", mockito::Matcher::Any)
+                .with_status(403)
+                .with_body(fake::error_response(*code, "Invalid").to_string())
+                .expect(1)
+                .create();
+            let batch = ScrobbleBatch::from(vec![scrobble.clone()]);
+            assert!(scrobbler.scrobble_batch(&batch).is_err());
+            m.assert();
+        }
+
+        // Succeeds on the third attempt, with the timestamp generated before the first
+        let transport = FaultyTransport::new(ReqwestTransport::new())
+            .fail_request(0, Fault::ConnectError)
+            .fail_request(1, Fault::ApiError(29));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_call_log_capacity(1);
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(600),
+            max_delay: Duration::from_millis(600),
+        });
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let m = mock("POST", mockito::Matcher::Any)
+            .match_body(mockito::Matcher::Regex(format!("timestamp=({started}|{})", started + 1)))
+            .with_body(fake::scrobble_response("floyd", "bananas").to_string())
+            .expect(1)
+            .create();
+        assert!(scrobbler.scrobble(&scrobble).is_ok());
+        m.assert();
+        assert_eq!(scrobbler.recent_calls()[0].attempts, 3);
+    }
+
+    #[test]
+    fn check_scrobbler_timestamp_window() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let now = SystemTime::now();
+        let played = |offset: i64| {
+            let mut scrobble = Scrobble::new("floyd", "bananas", "old");
+            match u64::try_from(offset) {
+                Ok(ahead) => scrobble.with_time(now + Duration::from_secs(ahead)),
+                Err(_) => scrobble.with_time(now - Duration::from_secs(offset.unsigned_abs())),
+            };
+            scrobble
+        };
+        let days = |days: i64| days * 24 * 60 * 60;
+
+        // A few minutes ahead is put down to clock skew
+        assert!(scrobbler.scrobble(&played(-240)).is_ok());
+        assert!(scrobbler.scrobble(&played(300)).is_ok());
+        assert!(scrobbler.scrobble(&played(-days(14) + 60)).is_ok());
+
+        let err = scrobbler.scrobble(&played(3600)).unwrap_err();
+        assert!(err.to_string().ends_with("seconds in the future"), "{}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidTimestamp);
+        let err = scrobbler.scrobble(&played(-days(14) - 60)).unwrap_err();
+        assert!(err.to_string().ends_with("is more than 14 days old, so would be ignored"), "{}", err);
+        let mut millis = Scrobble::new("floyd", "bananas", "old");
+        millis.with_timestamp(now.duration_since(UNIX_EPOCH).unwrap().as_secs() * 1000);
+        let err = scrobbler.scrobble(&millis).unwrap_err();
+        assert!(err.to_string().contains("probably in milliseconds"), "{}", err);
+
+        let batch = ScrobbleBatch::from(vec![played(-600), played(-days(30))]);
+        let err = scrobbler.scrobble_batch(&batch).unwrap_err();
+        assert!(err.to_string().starts_with("Scrobble 1 in batch has timestamp"), "{}", err);
+        assert!(err.to_string().ends_with("which is more than 14 days old, so would be ignored"), "{}", err);
+        assert_eq!(err.kind(), ErrorKind::InvalidTimestamp);
+        assert_eq!(echo.requests().len(), 3);
+
+        // Old scrobbles can be allowed, but never ones from the future
+        scrobbler.set_max_scrobble_age(None);
+        assert!(scrobbler.scrobble_batch(&batch).is_ok());
+        assert!(scrobbler.scrobble(&millis).is_err());
+        assert!(scrobbler.scrobble_at(&played(0), now + Duration::from_hours(1)).is_err());
+    }
+
+    #[test]
+    fn check_scrobbler_missing_name() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let err = scrobbler.now_playing(Scrobble::new("", "bananas", "old")).unwrap_err();
+        assert_eq!((err.kind(), err.to_string().as_str()), (ErrorKind::MissingName, "Scrobble has no artist name"));
+        let batch = ScrobbleBatch::from(vec![Scrobble::new("floyd", "bananas", ""), Scrobble::new("floyd", "  ", "")]);
+        let err = scrobbler.validate_batch(&batch).unwrap_err();
+        assert_eq!(err.to_string(), "Scrobble 1 in batch has no track name");
+        assert_eq!(scrobbler.scrobble_batch(&batch).unwrap_err().kind(), ErrorKind::MissingName);
+        assert!(echo.requests().is_empty());
+
+        // The artist can be given as a list of artists instead
+        let mut credited = Scrobble::new("", "bananas", "");
+        credited.with_artists(&["floyd", "kraftwerk"]);
+        assert!(scrobbler.scrobble(&credited).is_ok());
+    }
+
+    #[test]
+    fn check_scrobbler_min_timestamp() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut oldest = Scrobble::new("floyd", "bananas", "old");
+        oldest.with_timestamp(DEFAULT_MIN_TIMESTAMP);
+        let mut too_old = Scrobble::new("floyd", "bananas", "old");
+        too_old.with_timestamp(DEFAULT_MIN_TIMESTAMP - 1);
+
+        assert_eq!(scrobbler.scrobble(&oldest).unwrap().timestamp(), "1009843200");
+        let err = scrobbler.scrobble(&too_old).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Scrobble timestamp 1009843199 is before the minimum allowed timestamp (1009843200)"
+        );
+        let epoch = UNIX_EPOCH + Duration::from_secs(0);
+        assert!(scrobbler.scrobble_at(&oldest, epoch).is_err());
+
+        let batch = ScrobbleBatch::from(vec![oldest.clone(), too_old.clone()]);
+        let err = scrobbler.scrobble_batch(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Scrobble 1 in batch has timestamp 1009843199, before the minimum allowed timestamp (1009843200)"
+        );
+        assert_eq!(err.kind(), ErrorKind::InvalidTimestamp);
+        assert_eq!(echo.requests().len(), 1);
+
+        // Lowered for a compatible server
+        scrobbler.set_min_timestamp(0);
+        assert!(scrobbler.scrobble(&too_old).is_ok());
+        assert!(scrobbler.scrobble_batch(&batch).is_ok());
+    }
+
+    #[test]
+    fn check_scrobbler_long_fields() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let events = observe(&mut scrobbler);
+
+        // Multi-byte characters, so that byte and character lengths differ
+        let at_limit = "é".repeat(MAX_TRACK_CHARS);
+        let name_at_limit = "é".repeat(MAX_NAME_CHARS);
+        let over_limit = format!("{}🎵", "é".repeat(MAX_TRACK_CHARS));
+        let far_over = "🎵".repeat(10_000);
+
+        let mut scrobble = Scrobble::new(&name_at_limit, &at_limit, &name_at_limit);
+        scrobble.with_timestamp(1_500_000_000);
+        assert!(scrobbler.scrobble(&scrobble).is_ok());
+        assert_eq!(echo.requests()[0].params["artist"], name_at_limit);
+        assert_eq!(echo.requests()[0].params["track"], at_limit);
+
+        // Each field has its own limit
+        let mut long_artist = Scrobble::new(&format!("{name_at_limit}🎵"), "echoes", "old");
+        long_artist.with_timestamp(1_500_000_000);
+        let err = scrobbler.scrobble(&long_artist).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Scrobble has a artist name 256 characters long, over the maximum of 255"
+        );
+
+        // Rejected by default, without sending anything
+        let mut too_long = Scrobble::new("floyd", &over_limit, "old");
+        too_long.with_timestamp(1_500_000_000);
+        let err = scrobbler.now_playing(&too_long).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Scrobble has a track name 1025 characters long, over the maximum of 1024"
+        );
+        assert!(scrobbler.scrobble(&too_long).is_err());
+        let batch = ScrobbleBatch::from(vec![scrobble.clone(), too_long.clone()]);
+        let err = scrobbler.scrobble_batch(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Scrobble 1 in batch has a track name 1025 characters long, over the maximum of 1024"
+        );
+        assert_eq!(echo.requests().len(), 1);
+
+        scrobbler.set_truncate_long_fields(true);
+        events.lock().unwrap().clear();
+        let response = scrobbler.now_playing(&too_long).unwrap();
+        assert_eq!(response.track().text(), at_limit);
+        assert_eq!(
+            events.lock().unwrap()[0],
+            ScrobblerEvent::FieldTruncated {
+                field: "track".to_owned(),
+                original_chars: 1025,
+                truncated_chars: 1024,
+            }
+        );
+        // The scrobble itself is untouched
+        assert_eq!(too_long.track(), over_limit);
+
+        let mut far_too_long = Scrobble::new(&far_over, "echoes", &far_over);
+        far_too_long.with_timestamp(1_500_000_000);
+        scrobbler.set_truncation_ellipsis(true);
+        let batch = ScrobbleBatch::from(vec![scrobble.clone(), far_too_long]);
+        assert!(scrobbler.scrobble_batch(&batch).is_ok());
+
+        let params = &echo.requests()[2].params;
+        let expected = format!("{}…", "🎵".repeat(MAX_NAME_CHARS - 1));
+        assert_eq!(params["artist[0]"], name_at_limit);
+        assert_eq!(params["artist[1]"], expected);
+        assert_eq!(params["album[1]"], expected);
+        assert_eq!(params["artist[1]"].chars().count(), MAX_NAME_CHARS);
+        assert_eq!(params["track[1]"], "echoes");
+        let truncated: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                ScrobblerEvent::FieldTruncated { field, original_chars, .. } => Some((field.clone(), *original_chars)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            truncated,
+            vec![("track".to_owned(), 1025), ("artist".to_owned(), 10_000), ("album".to_owned(), 10_000)]
+        );
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn check_scrobbler_truncation_keeps_graphemes() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_truncate_long_fields(true);
+        let events = observe(&mut scrobbler);
+
+        // Cutting at the limit would separate the last "e" from its combining accent, so both are left out
+        let kept = "a".repeat(MAX_TRACK_CHARS - 1);
+        let track = format!("{kept}e\u{301} and the rest");
+        scrobbler.now_playing(Scrobble::new("floyd", &track, "old")).unwrap();
+        assert_eq!(echo.requests()[0].params["track"], kept);
+        assert_eq!(
+            events.lock().unwrap()[0],
+            ScrobblerEvent::FieldTruncated {
+                field: "track".to_owned(),
+                original_chars: MAX_TRACK_CHARS + 14,
+                truncated_chars: MAX_TRACK_CHARS - 1,
+            }
+        );
+
+        // As with an ellipsis, which takes the place of the whole cluster
+        scrobbler.set_truncation_ellipsis(true);
+        let kept = "a".repeat(MAX_TRACK_CHARS - 2);
+        let track = format!("{kept}e\u{301} and the rest");
+        scrobbler.now_playing(Scrobble::new("floyd", &track, "old")).unwrap();
+        assert_eq!(echo.requests()[1].params["track"], format!("{kept}…"));
+    }
+
+    #[test]
+    fn check_scrobbler_rate_limit_shared_by_clones() {
+        // Records when each request reached the transport
+        #[derive(Clone, Default)]
+        struct TimedTransport(Arc<std::sync::Mutex<Vec<Instant>>>);
+
+        impl Transport for TimedTransport {
+            fn send(&self, request: &HttpRequest) -> result::Result<HttpResponse, ScrobblerError> {
+                self.0.lock().unwrap().push(Instant::now());
+                EchoTransport::new().send(request)
+            }
+        }
+
+        let transport = TimedTransport::default();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        assert_eq!(scrobbler.rate_limit_utilization(), None);
+
+        // Limited after cloning, so the limit must reach the clone too
+        let clone = Arc::new(scrobbler.clone());
+        let limit = RateLimit {
+            requests: 5,
+            per: Duration::from_millis(100),
+        };
+        scrobbler.set_rate_limit(Some(limit));
+        let scrobbler = Arc::new(scrobbler);
+        assert_eq!(clone.info().rate_limit, Some(limit));
+
+        let start = Instant::now();
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                let scrobbler = if i % 2 == 0 { Arc::clone(&scrobbler) } else { Arc::clone(&clone) };
+                thread::spawn(move || {
+                    for _ in 0..5 {
+                        scrobbler.now_playing(Scrobble::new("floyd", "bananas", "old")).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // A burst of 5, then one request every 20ms
+        let mut sent = transport.0.lock().unwrap().clone();
+        sent.sort();
+        assert_eq!(sent.len(), 20);
+        for (i, at) in sent.iter().enumerate().skip(5) {
+            // Allowing for rounding in the limiter's arithmetic
+            let elapsed = at.duration_since(start) + Duration::from_millis(1);
+            let earliest = Duration::from_millis(20) * u32::try_from(i - 4).unwrap();
+            assert!(elapsed >= earliest, "request {} sent after {:?}", i, elapsed);
+        }
+        assert!(scrobbler.rate_limit_utilization().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn check_scrobbler_auth_retries() {
+        // Request 2, the retry of the dropped connection, succeeds
+        let transport = FaultyTransport::new(EchoTransport::new())
+            .fail_request(0, Fault::ApiError(4))
+            .fail_request(1, Fault::ConnectError)
+            .fail_request(3, Fault::HttpStatus(503))
+            .fail_request(4, Fault::Timeout)
+            .fail_request(5, Fault::ConnectError)
+            .fail_request(6, Fault::ConnectError);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.set_call_log_capacity(10);
+        assert_eq!(scrobbler.retry_policy_for(CallClass::Auth).max_attempts, 2);
+        assert_eq!(scrobbler.retry_policy_for(CallClass::Write), RetryPolicy::none());
+        // Submissions may be retried on anything transient; authentication still isn't
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        });
+
+        // Rejected credentials aren't retried
+        let err = scrobbler.authenticate_with_password("user", "hunter2").unwrap_err();
+        assert_eq!(err.api_code(), Some(4));
+        // A dropped connection is retried once
+        scrobbler.authenticate_with_password("user", "hunter2").unwrap();
+        // Neither is a response from the server, even a transient one
+        let err = scrobbler.authenticate_with_password("user", "hunter2").unwrap_err();
+        assert_eq!(err.http_status(), Some(503));
+        // Nor a second network error
+        let err = scrobbler.authenticate_with_password("user", "hunter2").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Transport);
+
+        let attempts: Vec<_> = scrobbler.recent_calls().iter().map(|call| call.attempts).collect();
+        assert_eq!(attempts, vec![1, 2, 1, 2]);
+
+        scrobbler.set_retry_policy_for(CallClass::Auth, RetryPolicy::none());
+        assert!(scrobbler.authenticate_with_password("user", "hunter2").is_err());
+        assert_eq!(scrobbler.recent_calls().last().unwrap().attempts, 1);
+    }
+
+    #[test]
+    fn check_scrobbler_recent_calls() {
+        let transport = FaultyTransport::new(EchoTransport::new()).fail_method("track.love", vec![Fault::ApiError(6)]);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_password("user", "hunter2").unwrap();
+        assert!(scrobbler.recent_calls().is_empty());
+
+        scrobbler.set_call_log_capacity(3);
+        let mut scrobble = Scrobble::new("floyd", "bananas", "old");
+        scrobble.with_timestamp(1_500_000_000);
+        scrobbler.authenticate_with_password("user", "hunter2").unwrap();
+        scrobbler.now_playing(&scrobble).unwrap();
+        scrobbler.scrobble(&scrobble).unwrap();
+        scrobbler
+            .scrobble_batch(&ScrobbleBatch::from(vec![scrobble.clone(), scrobble.clone()]))
+            .unwrap();
+        let report = scrobbler.love_many(
+            &[("floyd".to_owned(), "bananas".to_owned())],
+            &BulkLoveOptions {
+                interval: Duration::from_millis(0),
+                retry_policy: RetryPolicy::none(),
+            },
+        )
+        .unwrap();
+
+        // The oldest, the authentication and Now Playing calls, were evicted
+        let calls = scrobbler.recent_calls();
+        let methods: Vec<_> = calls.iter().map(|call| call.method).collect();
+        assert_eq!(methods, vec!["track.scrobble", "track.scrobble", "track.love"]);
+        assert_eq!(calls[1].track_count, 2);
+        assert_eq!(calls[2].outcome, CallOutcome::ApiError(LastFmErrorCode::InvalidParameters));
+        assert_eq!(calls[2].attempts, 1);
+        let err = report.outcomes[0].result.as_ref().unwrap_err();
+        assert_eq!(err.correlation_id(), Some(calls[2].correlation_id()));
+
+        let logged = format!("{calls:?}");
+        let session_key = scrobbler.session_key().unwrap().to_owned();
+        for secret in &["api_secret", "hunter2", &session_key, "floyd", "bananas"] {
+            assert!(!logged.contains(secret), "{} in {}", secret, logged);
+        }
+
+        scrobbler.clear_recent_calls();
+        assert!(scrobbler.recent_calls().is_empty());
+        scrobbler.now_playing(&scrobble).unwrap();
+        assert_eq!(scrobbler.clone().recent_calls().len(), 1);
+    }
+
+    #[test]
+    fn check_scrobbler_circuit_breaker() {
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone()).fail_method(
+            "track.updateNowPlaying",
+            vec![Fault::HttpStatus(503), Fault::ConnectError, Fault::Timeout],
+        );
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let events = observe(&mut scrobbler);
+        assert_eq!(scrobbler.circuit_state(), None);
+        scrobbler.set_circuit_breaker(Some(CircuitBreakerPolicy {
+            failure_threshold: 2,
+            cooldown: Duration::from_millis(50),
+        }));
+        let clone = scrobbler.clone();
+        let scrobble = Scrobble::new("floyd", "bananas", "old");
+        let circuit_events = || -> Vec<CircuitState> {
+            events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|event| match event {
+                    ScrobblerEvent::CircuitStateChanged { state } => Some(*state),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // Two consecutive failures open the circuit, for every clone
+        assert!(scrobbler.now_playing(&scrobble).is_err());
+        assert_eq!(scrobbler.circuit_state(), Some(CircuitState::Closed));
+        assert!(scrobbler.now_playing(&scrobble).is_err());
+        assert_eq!(clone.circuit_state(), Some(CircuitState::Open));
+        let err = clone.now_playing(&scrobble).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CircuitOpen);
+        assert!(err.is_retryable());
+        assert_eq!(circuit_events(), vec![CircuitState::Open]);
+
+        // A failed probe reopens it; a successful one closes it
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(scrobbler.circuit_state(), Some(CircuitState::HalfOpen));
+        assert!(scrobbler.now_playing(&scrobble).is_err());
+        assert_eq!(scrobbler.now_playing(&scrobble).unwrap_err().kind(), ErrorKind::CircuitOpen);
+        thread::sleep(Duration::from_millis(60));
+        assert!(scrobbler.now_playing(&scrobble).is_ok());
+        assert_eq!(clone.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(
+            circuit_events(),
+            vec![
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed
+            ]
+        );
+
+        // Calls rejected by the open circuit were never sent
+        let started = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, ScrobblerEvent::RequestStarted { .. }))
+            .count();
+        assert_eq!(started, 4);
+        assert_eq!(echo.requests().len(), 1);
+    }
+
+    #[test]
+    fn check_scrobbler_usage_thresholds() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let events = observe(&mut scrobbler);
+        let mut clone = scrobbler.clone();
+        let scrobble = Scrobble::new("floyd", "bananas", "old");
+
+        // Requests are counted for every clone, with or without thresholds
+        scrobbler.now_playing(&scrobble).unwrap();
+        assert_eq!(clone.estimated_usage().last_day, 1);
+        assert_eq!(clone.usage_thresholds(), None);
+
+        let thresholds = UsageThresholds {
+            soft: UsageLimits { per_hour: Some(2), ..UsageLimits::default() },
+            hard: UsageLimits { per_day: Some(3), ..UsageLimits::default() },
+        };
+        clone.set_usage_thresholds(Some(thresholds));
+        assert_eq!(scrobbler.usage_thresholds(), Some(thresholds));
+        scrobbler.now_playing(&scrobble).unwrap();
+        clone.now_playing(&scrobble).unwrap();
+
+        // The soft threshold warns once, as it's reached; the hard one refuses calls without sending them
+        let err = scrobbler.now_playing(&scrobble).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UsageLimitReached);
+        assert!(err.is_retryable());
+        let msg = "Now playing request failed: Estimated usage reached the limit of 3 requests per day";
+        assert_eq!(err.to_string(), msg);
+        assert_eq!(echo.requests().len(), 3);
+        let warnings: Vec<ScrobblerEvent> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, ScrobblerEvent::UsageThresholdReached { .. }))
+            .cloned()
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![ScrobblerEvent::UsageThresholdReached {
+                window: UsageWindow::Hour,
+                estimated: 2,
+                threshold: 2,
+            }]
+        );
+
+        scrobbler.set_usage_thresholds(None);
+        scrobbler.now_playing(&scrobble).unwrap();
+        assert_eq!(clone.estimated_usage().get(UsageWindow::Day), 4);
+    }
+
+    #[test]
+    fn check_scrobbler_love() {
+        use mockito::Matcher;
+
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        let scrobble = Scrobble::new("Los Campesinos!", "To Tundra", "No Blues");
+        assert_eq!(scrobbler.love(&scrobble).unwrap_err().kind(), ErrorKind::NotAuthenticated);
+        assert_eq!(scrobbler.unlove(&scrobble).unwrap_err().kind(), ErrorKind::NotAuthenticated);
+
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let love = mock("POST", Matcher::Any)
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "track.love".into()),
+                Matcher::UrlEncoded("artist".into(), "Los Campesinos!".into()),
+                Matcher::UrlEncoded("track".into(), "To Tundra".into()),
+                Matcher::UrlEncoded("sk".into(), "seshkey".into()),
+            ]))
+            .with_body("{}")
+            .expect(1)
+            .create();
+        scrobbler.love(&scrobble).unwrap();
+        love.assert();
+
+        let unlove = mock("POST", Matcher::Any)
+            .match_body(Matcher::UrlEncoded("method".into(), "track.unlove".into()))
+            .with_body("{}")
+            .expect(1)
+            .create();
+        scrobbler.unlove(&scrobble).unwrap();
+        unlove.assert();
+
+        let _m = mock("POST", Matcher::Any)
+            .with_status(400)
+            .with_body(fake::error_response(6, "Track not found").to_string())
+            .create();
+        let err = scrobbler.love(&scrobble).unwrap_err();
+        assert_eq!(err.api_code(), Some(6));
+        assert!(err.to_string().starts_with("Love request failed"), "{}", err);
+    }
+
+    #[test]
+    fn check_scrobbler_track_correction() {
+        use mockito::Matcher;
+
+        // Corrections need no session
+        let scrobbler = Scrobbler::new("api_key", "api_secret");
+        let m = mock("GET", Matcher::Any)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "track.getCorrection".into()),
+                Matcher::UrlEncoded("artist".into(), "Guns N Roses".into()),
+                Matcher::UrlEncoded("track".into(), "Sweet Child O' Mine".into()),
+            ]))
+            .with_body(include_str!("../tests/fixtures/corrections/track_correction.json"))
+            .create();
+
+        let correction = scrobbler.track_correction("Guns N Roses", "Sweet Child O' Mine").unwrap();
+        m.assert();
+        assert!(correction.is_corrected());
+        assert!(correction.artist().corrected());
+        assert_eq!(correction.artist().text(), "Guns N' Roses");
+        assert!(!correction.track().corrected());
+        assert_eq!(correction.track().text(), "Sweet Child O' Mine");
+    }
+
+    #[test]
+    fn check_scrobbler_love_many() {
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone())
+            .fail_request(0, Fault::HttpStatus(503))
+            .fail_request(2, Fault::ApiError(6));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+
+        let items: Vec<(String, String)> = vec![
+            ("floyd".into(), "bananas".into()),
+            ("floyd".into(), "missing".into()),
+            ("floyd".into(), "echoes".into()),
+        ];
+        let options = BulkLoveOptions {
+            interval: Duration::from_millis(0),
+            retry_policy: RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(0),
+                max_delay: Duration::from_millis(0),
+            },
+        };
+
+        assert!(scrobbler.love_many(&items, &options).is_err());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        // The first item succeeds on retry; the second fails permanently, without being retried
+        let report = scrobbler.love_many(&items, &options).unwrap();
+        assert_eq!(report.outcomes.len(), 3);
+        assert_eq!(report.succeeded(), 2);
+        let failures: Vec<&LoveOutcome> = report.failures().collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].track, "missing");
+        assert!(failures[0].result.as_ref().unwrap_err().to_string().starts_with("Love request failed"));
+
+        let loved: Vec<String> = echo.requests().iter().map(|req| req.params["track"].clone()).collect();
+        assert_eq!(loved, vec!["bananas", "echoes"]);
+        assert!(echo.requests().iter().all(|req| req.method() == "track.love"));
+
+        let report = scrobbler.unlove_many(&items[..1], &options).unwrap();
+        assert_eq!(report.succeeded(), 1);
+        assert_eq!(echo.requests().last().unwrap().method(), "track.unlove");
+    }
+
+    #[test]
+    fn check_scrobbler_correlation_ids() {
+        // Fails every request, recording the request ID sent with each attempt
+        #[derive(Clone, Default)]
+        struct Unavailable(Arc<std::sync::Mutex<Vec<Option<String>>>>);
+
+        impl Transport for Unavailable {
+            fn send(&self, request: &HttpRequest) -> result::Result<HttpResponse, ScrobblerError> {
+                self.0.lock().unwrap().push(request.request_id.clone());
+                Ok(HttpResponse {
+                    status: 503,
+                    body: String::new(),
+                    headers: Headers::new(),
+                })
+            }
+        }
+
+        let transport = Unavailable::default();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        });
+        let scrobble = Scrobble::new("floyd", "bananas", "old");
+
+        // Not sent by default, but still reported on the error
+        let err = scrobbler.now_playing(&scrobble).unwrap_err();
+        assert!(err.correlation_id().is_some());
+        assert_eq!(*transport.0.lock().unwrap(), vec![None, None, None]);
+        transport.0.lock().unwrap().clear();
+
+        scrobbler.set_send_request_id(true);
+        let err = scrobbler.scrobble(&scrobble).unwrap_err();
+        let id = err.correlation_id().unwrap().to_owned();
+        assert_eq!(id.len(), 8);
+        assert_eq!(*transport.0.lock().unwrap(), vec![Some(id.clone()); 3]);
+
+        // Each logical call gets a new ID
+        let err = scrobbler.scrobble(&scrobble).unwrap_err();
+        assert_ne!(err.correlation_id().unwrap(), id);
+    }
+
+    #[test]
+    fn check_scrobbler_include_nonce() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.set_call_log_capacity(4);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        let mut scrobble = Scrobble::new("floyd", "fruitflies", "bananas");
+        scrobble.with_timestamp(1_337_000_000);
+
+        // Off by default
+        assert!(!scrobbler.include_nonce());
+        scrobbler.scrobble(&scrobble).unwrap();
+        assert_eq!(echo.requests()[0].params.get("nonce"), None);
+        assert_eq!(scrobbler.recent_calls()[0].nonce(), None);
+
+        scrobbler.set_include_nonce(true);
+        scrobbler.scrobble(&scrobble).unwrap();
+        scrobbler.scrobble(&scrobble).unwrap();
+        let requests = echo.requests();
+        let nonces: Vec<&str> = requests[1..].iter().map(|request| request.params.get("nonce").unwrap()).collect();
+        for nonce in &nonces {
+            let groups: Vec<usize> = nonce.split('-').map(str::len).collect();
+            assert_eq!(groups, [8, 4, 4, 4, 12]);
+            assert_eq!(nonce.as_bytes()[14], b'4');
+            assert!(matches!(nonce.as_bytes()[19], b'8' | b'9' | b'a' | b'b'), "{}", nonce);
+        }
+        assert_ne!(nonces[0], nonces[1]);
+        // The nonce is signed, so identical scrobbles get different signatures
+        assert_ne!(requests[1].params.get("api_sig"), requests[2].params.get("api_sig"));
+
+        let calls = scrobbler.recent_calls();
+        assert_eq!(calls[1].nonce(), Some(nonces[0]));
+        assert_eq!(calls[2].nonce(), Some(nonces[1]));
+    }
+
+    #[test]
+    fn check_scrobbler_custom_signer() {
+        // Records the parameters it's asked to sign, and leaves the album out of signatures
+        #[derive(Clone, Default)]
+        struct RecordingSigner(Arc<std::sync::Mutex<Vec<String>>>);
+
+        impl RequestSigner for RecordingSigner {
+            fn sign(&self, params: &[(&str, &str)], api_secret: &str) -> String {
+                let pairs: Vec<String> = params.iter().map(|(key, value)| format!("{key}={value}")).collect();
+                self.0.lock().unwrap().push(pairs.join("&"));
+                format!("custom-{api_secret}")
+            }
+
+            fn is_excluded(&self, key: &str) -> bool {
+                matches!(key, "api_sig" | "format" | "album")
+            }
+        }
+
+        let echo = EchoTransport::new();
+        let signer = RecordingSigner::default();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_signer(signer.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        scrobbler.now_playing(Scrobble::new("floyd", "bananas", "old")).unwrap();
+
+        assert_eq!(
+            *signer.0.lock().unwrap(),
+            vec!["api_key=api_key&artist=floyd&method=track.updateNowPlaying&sk=seshkey&track=bananas"]
+        );
+
+        let request = echo.requests().pop().unwrap();
+        assert_eq!(request.params.get("api_sig"), Some("custom-api_secret"));
+        assert_eq!(request.params.get("album"), Some("old"));
+    }
+
+    #[test]
+    fn check_scrobbler_garbage_response() {
+        let transport = FaultyTransport::new(EchoTransport::new())
+            .fail_method("track.updateNowPlaying", vec![Fault::GarbageBody]);
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let scrobble = Scrobble::new("floyd", "bananas", "old");
+        assert!(scrobbler.now_playing(&scrobble).is_err());
+        assert!(scrobbler.now_playing(&scrobble).is_ok());
+    }
+
+    #[test]
+    fn check_scrobbler_chaos_never_loses_or_duplicates() {
+        // xorshift, so the fault pattern is reproducible without pulling in an RNG crate
+        let mut seed: u64 = 0x5eed_1337_cafe_f00d;
+        let mut next_random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        let faults = [
+            Fault::Timeout,
+            Fault::ConnectError,
+            Fault::HttpStatus(503),
+            Fault::ApiError(11),
+            Fault::ApiError(29),
+            Fault::GarbageBody,
+        ];
+        let scrobble_count: usize = 50;
+        let max_attempts: u32 = 3;
+
+        let echo = EchoTransport::new();
+        let mut transport = FaultyTransport::new(echo.clone());
+        for index in 0..scrobble_count * max_attempts as usize {
+            // A 50% failure rate
+            let roll = usize::try_from(next_random() % 12).unwrap();
+            if let Some(fault) = faults.get(roll) {
+                transport = transport.fail_request(index, fault.clone());
+            }
+        }
+
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        });
+
+        let mut results = Vec::new();
+        for i in 0..scrobble_count {
+            let mut scrobble = Scrobble::new("floyd", &format!("track{i}"), "old");
+            scrobble.with_timestamp(1_337_000_000 + i as u64);
+            results.push(scrobbler.scrobble(&scrobble).is_ok());
+        }
+
+        let delivered = echo.requests();
+        for (i, ok) in results.into_iter().enumerate() {
+            let track = format!("track{i}");
+            let deliveries = delivered
+                .iter()
+                .filter(|request| request.params.get("track") == Some(track.as_str()))
+                .count();
+
+            // Never delivered twice; every success was delivered exactly once
+            assert!(deliveries <= 1, "{} delivered {} times", track, deliveries);
+            if ok {
+                assert_eq!(deliveries, 1, "{track} reported ok but not delivered");
+            }
+        }
+    }
+
+    #[test]
+    fn check_scrobbler_scrobble_at() {
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut scrobble = Scrobble::new("floyd", "bananas", "old");
+        scrobble.with_timestamp(1_337_000_000);
+        let original = scrobble.clone();
+        let played_at = UNIX_EPOCH + Duration::from_secs(1_500_000_123);
+
+        let resp = scrobbler.scrobble_at(&scrobble, played_at).unwrap();
+        assert_eq!(resp.timestamp(), "1500000123");
+        assert_eq!(echo.requests()[0].params["timestamp"], "1500000123");
+        assert_eq!(scrobble, original);
+
+        let batch = ScrobbleBatch::from(vec![scrobble.clone(), Scrobble::new("fruitflies", "new", "more")]);
+        let times = [played_at, played_at + Duration::from_secs(245)];
+        let resp = scrobbler.scrobble_batch_at(&batch, &times).unwrap();
+        assert_eq!(resp.scrobbles().len(), 2);
+
+        let params = &echo.requests()[1].params;
+        assert_eq!(params["timestamp[0]"], "1500000123");
+        assert_eq!(params["timestamp[1]"], "1500000368");
+        assert_eq!(batch.iter().next(), Some(&original));
+
+        assert!(scrobbler.scrobble_batch_at(&batch, &times[..1]).is_err());
+        assert_eq!(echo.requests().len(), 2);
+    }
+
+    #[test]
+    fn check_scrobbler_ignored_scrobble() {
+        let mut scrobbler = Scrobbler::new("api_key", "api_secret");
+        scrobbler.set_max_scrobble_age(None);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+
+        let mut scrobble = Scrobble::new("floyd", "bananas", "old");
+        scrobble.with_timestamp(1_337_000_000);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(fake::scrobble_response("floyd", "bananas").timestamp(1_337_000_000).to_string())
+            .create();
+
+        let resp = scrobbler.scrobble(&scrobble).unwrap();
+        assert!(resp.accepted());
+        assert_eq!(resp.ignored_reason(), None);
+
+        let _m = mock("POST", mockito::Matcher::Any)
+            .with_body(
+                fake::scrobble_response("floyd", "bananas")
+                    .timestamp(1_337_000_000)
+                    .ignored(IgnoredReason::TimestampTooOld)
+                    .to_string(),
+            )
+            .create();
+
+        let resp = scrobbler.scrobble(&scrobble).unwrap();
+        assert!(!resp.accepted());
+        assert_eq!(resp.ignored_reason(), Some(IgnoredReason::TimestampTooOld));
+
+        scrobbler.set_fail_on_ignored(true);
+        let err = scrobbler.scrobble(&scrobble).unwrap_err();
+        assert_eq!(err.to_string(), "Scrobble ignored: Timestamp was too old");
+    }
 }
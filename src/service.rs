@@ -0,0 +1,170 @@
+use crate::error::ScrobblerError;
+#[cfg(feature = "listenbrainz")]
+use crate::listenbrainz::ListenBrainz;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::{
+    BatchScrobbleResponse, NowPlayingResponse, ScrobbleResponse, SessionResponse,
+};
+use crate::scrobbler::Scrobbler;
+
+use std::result;
+
+type Result<T> = result::Result<T, ScrobblerError>;
+
+/// The scrobbling operations offered by a [`Scrobbler`], as a trait.
+///
+/// Applications can hold a `Box<dyn ScrobbleService>` (or be generic over `ScrobbleService`) rather than a concrete
+/// [`Scrobbler`], which allows a different implementation to be substituted in tests. The trait is object-safe and
+/// is implemented by [`Scrobbler`], with every method delegating to the `Scrobbler` method of the same name.
+///
+/// With the `test-util` feature enabled, the crate provides [`MockScrobbler`]: an implementation which never touches
+/// the network, records every call made to it and returns programmable responses.
+///
+/// # Example
+#[cfg_attr(feature = "test-util", doc = "```")]
+#[cfg_attr(not(feature = "test-util"), doc = "```ignore")]
+/// use rustfm_scrobble::{Scrobble, ScrobbleService};
+/// use rustfm_scrobble::test_util::{MockCall, MockScrobbler};
+///
+/// // Application code, written against the trait
+/// fn play(service: &dyn ScrobbleService, track: &Scrobble) -> bool {
+///     service.scrobble(track).is_ok()
+/// }
+///
+/// // A test of it, against the mock
+/// let mock = MockScrobbler::new();
+/// let track = Scrobble::new("Example Artist", "Example Track", "Example Album");
+///
+/// assert!(play(&mock, &track));
+/// assert_eq!(mock.calls(), vec![MockCall::Scrobble(track)]);
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`MockScrobbler`]: test_util/struct.MockScrobbler.html
+pub trait ScrobbleService {
+    /// See [`Scrobbler::authenticate_with_password`](struct.Scrobbler.html#method.authenticate_with_password)
+    fn authenticate_with_password(&mut self, username: &str, password: &str) -> Result<SessionResponse>;
+
+    /// See [`Scrobbler::authenticate_with_token`](struct.Scrobbler.html#method.authenticate_with_token)
+    fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse>;
+
+    /// See [`Scrobbler::authenticate_with_session_key`](struct.Scrobbler.html#method.authenticate_with_session_key)
+    fn authenticate_with_session_key(&mut self, session_key: &str) -> Result<()>;
+
+    /// See [`Scrobbler::session_key`](struct.Scrobbler.html#method.session_key)
+    fn session_key(&self) -> Option<&str>;
+
+    /// See [`Scrobbler::now_playing`](struct.Scrobbler.html#method.now_playing)
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<NowPlayingResponse>;
+
+    /// See [`Scrobbler::scrobble`](struct.Scrobbler.html#method.scrobble)
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse>;
+
+    /// See [`Scrobbler::scrobble_batch`](struct.Scrobbler.html#method.scrobble_batch)
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse>;
+}
+
+impl ScrobbleService for Scrobbler {
+    fn authenticate_with_password(&mut self, username: &str, password: &str) -> Result<SessionResponse> {
+        Scrobbler::authenticate_with_password(self, username, password)
+    }
+
+    fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse> {
+        Scrobbler::authenticate_with_token(self, token)
+    }
+
+    fn authenticate_with_session_key(&mut self, session_key: &str) -> Result<()> {
+        Scrobbler::authenticate_with_session_key(self, session_key)
+    }
+
+    fn session_key(&self) -> Option<&str> {
+        Scrobbler::session_key(self)
+    }
+
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<NowPlayingResponse> {
+        Scrobbler::now_playing(self, scrobble)
+    }
+
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse> {
+        Scrobbler::scrobble(self, scrobble)
+    }
+
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
+        Scrobbler::scrobble_batch(self, batch)
+    }
+}
+
+/// A service scrobbles can be submitted to, so that an application can let its users choose where their listens
+/// go, ie. Last.fm or `ListenBrainz`, and submit them the same way whichever it is.
+///
+/// It's implemented by [`Scrobbler`] and, with the `listenbrainz` feature, [`ListenBrainz`], each delegating to its
+/// own method of the same name. Only whether a submission succeeded is returned, as each service responds
+/// differently. A `Scrobbler` submits batches of any size with [`Scrobbler::scrobble_batch_chunked`], and reports
+/// scrobbles Last.fm ignored as a success, unless [`Scrobbler::set_fail_on_ignored`] is set.
+///
+/// # Usage
+/// ```ignore
+/// let target: Box<dyn ScrobbleTarget> = match settings.service {
+///     Service::LastFm => Box::new(scrobbler),
+///     Service::ListenBrainz => Box::new(ListenBrainz::new(&settings.token)),
+/// };
+/// target.scrobble(&track)?;
+/// ```
+///
+/// [`Scrobbler`]: struct.Scrobbler.html
+/// [`ListenBrainz`]: struct.ListenBrainz.html
+/// [`Scrobbler::scrobble_batch_chunked`]: struct.Scrobbler.html#method.scrobble_batch_chunked
+/// [`Scrobbler::set_fail_on_ignored`]: struct.Scrobbler.html#method.set_fail_on_ignored
+pub trait ScrobbleTarget {
+    /// Tells the service the given track is playing now
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<()>;
+
+    /// Submits a single scrobble
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<()>;
+
+    /// Submits a batch of scrobbles, of any size
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()>;
+}
+
+impl ScrobbleTarget for Scrobbler {
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        Scrobbler::now_playing(self, scrobble).map(drop)
+    }
+
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<()> {
+        Scrobbler::scrobble(self, scrobble).map(drop)
+    }
+
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()> {
+        Scrobbler::scrobble_batch_chunked(self, batch).map(drop)
+    }
+}
+
+#[cfg(feature = "listenbrainz")]
+impl ScrobbleTarget for ListenBrainz {
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        ListenBrainz::now_playing(self, scrobble)
+    }
+
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<()> {
+        ListenBrainz::scrobble(self, scrobble)
+    }
+
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<()> {
+        ListenBrainz::scrobble_batch(self, batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_scrobbler_as_service() {
+        let mut service: Box<dyn ScrobbleService> = Box::new(Scrobbler::new("api_key", "api_secret"));
+        assert_eq!(service.session_key(), None);
+
+        service.authenticate_with_session_key("seshkey").unwrap();
+        assert_eq!(service.session_key(), Some("seshkey"));
+    }
+}
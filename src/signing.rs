@@ -0,0 +1,306 @@
+//! Last.fm API request signing.
+//!
+//! Every authenticated Last.fm API request carries an `api_sig` parameter: the MD5 hash of a *signature base
+//! string* built from the request's parameters, followed by the application's API secret. The base string is every
+//! parameter except `api_sig`, `format` and `callback`, concatenated as `<key><value>` pairs ordered by key.
+//!
+//! The crate signs requests itself, with the [`Md5Signer`]. Servers which implement the Last.fm protocol but sign
+//! requests differently (ie. with SHA-256) can be supported with a custom [`RequestSigner`], set with
+//! [`Scrobbler::set_signer`]. This module also helps debug signature mismatches (Last.fm error 13, "Invalid method
+//! signature supplied") by exposing the base string the crate signs, and relays which accept Last.fm-style requests
+//! check the signatures of those they receive, with [`verify`].
+//!
+//! [`Md5Signer`]: struct.Md5Signer.html
+//! [`RequestSigner`]: trait.RequestSigner.html
+//! [`Scrobbler::set_signer`]: ../struct.Scrobbler.html#method.set_signer
+//! [`verify`]: fn.verify.html
+//!
+//! [Signing Calls Documentation](https://www.last.fm/api/desktopauth#_6-sign-your-calls)
+use crate::params::Params;
+
+use std::collections::BTreeMap;
+
+// Parameters sent with a request but excluded from its signature
+const UNSIGNED_PARAMS: &[&str] = &["api_sig", "format", "callback"];
+
+// Parameters whose values are redacted from logged base strings
+const SECRET_PARAMS: &[&str] = &["password", "sk", "token"];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Computes the `api_sig` signature of API requests.
+///
+/// A signer is given the request's signed parameters, already filtered with [`RequestSigner::is_excluded`] and
+/// ordered by key, and the application's API secret. The default signer is [`Md5Signer`], the Last.fm scheme.
+///
+/// # Example
+/// ```ignore
+/// struct Sha256Signer;
+///
+/// impl RequestSigner for Sha256Signer {
+///     fn sign(&self, params: &[(&str, &str)], api_secret: &str) -> String {
+///         let mut hasher = Sha256::new();
+///         for (key, value) in params {
+///             hasher.update(key);
+///             hasher.update(value);
+///         }
+///         hasher.update(api_secret);
+///         format!("{:x}", hasher.finalize())
+///     }
+/// }
+///
+/// scrobbler.set_signer(Sha256Signer);
+/// ```
+///
+/// [`RequestSigner::is_excluded`]: trait.RequestSigner.html#method.is_excluded
+/// [`Md5Signer`]: struct.Md5Signer.html
+pub trait RequestSigner: Send + Sync {
+    /// Returns the signature of a request with the given signed parameters, as `(key, value)` pairs ordered by key
+    fn sign(&self, params: &[(&str, &str)], api_secret: &str) -> String;
+
+    /// Returns true if the named parameter is sent with requests but left out of their signatures. By default,
+    /// `api_sig`, `format` and `callback` are excluded.
+    fn is_excluded(&self, key: &str) -> bool {
+        UNSIGNED_PARAMS.contains(&key)
+    }
+}
+
+/// The standard Last.fm [`RequestSigner`]: the MD5 hash of the parameters' `<key><value>` pairs, followed by the API
+/// secret, as lowercase hex.
+///
+/// [`RequestSigner`]: trait.RequestSigner.html
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Md5Signer;
+
+impl RequestSigner for Md5Signer {
+    fn sign(&self, params: &[(&str, &str)], api_secret: &str) -> String {
+        format!("{:x}", md5_digest(params.iter().copied(), api_secret))
+    }
+}
+
+// The pairs are streamed straight into the MD5 context, rather than being collected into an intermediate string
+fn md5_digest<'a>(params: impl Iterator<Item = (&'a str, &'a str)>, api_secret: &str) -> md5::Digest {
+    let mut sig = md5::Context::new();
+    for (key, value) in params {
+        sig.consume(key);
+        sig.consume(value);
+    }
+    sig.consume(api_secret);
+    sig.compute()
+}
+
+/// Returns true if `provided_sig` is the [`Md5Signer`] signature of a request with the given parameters, signed with
+/// `api_secret`. For relays and servers which accept Last.fm-style requests.
+///
+/// `params` are every parameter the request was sent with, including `method`; `api_sig`, `format` and `callback`
+/// are left out of the signature, as when signing. The hex digits of `provided_sig` may be of either case. The
+/// signatures are compared in constant time, so the time taken doesn't reveal how much of a forged one is right,
+/// and nothing is allocated.
+///
+/// # Example
+/// ```ignore
+/// let params: BTreeMap<&str, &str> = form_urlencoded::parse(body).collect();
+/// if !signing::verify(&params, &api_secret, params.get("api_sig").unwrap_or(&"")) {
+///     return Response::forbidden();
+/// }
+/// ```
+///
+/// [`Md5Signer`]: struct.Md5Signer.html
+pub fn verify(params: &BTreeMap<&str, &str>, api_secret: &str, provided_sig: &str) -> bool {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    let provided = provided_sig.as_bytes();
+    let signed = params
+        .iter()
+        .map(|(key, value)| (*key, *value))
+        .filter(|(key, _)| !Md5Signer.is_excluded(key));
+    let digest = md5_digest(signed, api_secret);
+    if provided.len() != digest.len() * 2 {
+        return false;
+    }
+
+    let mut difference = 0;
+    for (byte, pair) in digest.iter().zip(provided.chunks(2)) {
+        difference |= HEX[usize::from(byte >> 4)] ^ pair[0].to_ascii_lowercase();
+        difference |= HEX[usize::from(byte & 0xf)] ^ pair[1].to_ascii_lowercase();
+    }
+    difference == 0
+}
+
+/// Returns the signature base string for the given request parameters, which must include `method`, as signed by
+/// the [`Md5Signer`](struct.Md5Signer.html). The API secret, which is appended to the base string when signing, is
+/// not included.
+///
+/// Compare the output with the base string built by another client (or by hand) to find out why a signature is
+/// rejected. Note that the output contains the values of every parameter, including secrets such as the session key
+/// and password: don't log it.
+///
+/// # Example
+/// ```ignore
+/// let base = rustfm_scrobble::signing::debug_base_string(&params);
+/// assert_eq!(base, "api_keyxxxxmethodtrack.scrobblesk...");
+/// ```
+pub fn debug_base_string(params: &Params) -> String {
+    base_string(params, &Md5Signer)
+}
+
+// The signature base string of a request, as signed by the given signer
+pub(crate) fn base_string(params: &Params, signer: &dyn RequestSigner) -> String {
+    signed_params(params, signer).fold(String::new(), |mut base, (key, value)| {
+        base.push_str(key);
+        base.push_str(value);
+        base
+    })
+}
+
+// Computes the `api_sig` for a request. `method` is merged in at its ordered position; any `method` parameter
+// already in `params` is ignored.
+pub(crate) fn sign(signer: &dyn RequestSigner, method: &str, params: &Params, api_secret: &str) -> String {
+    let mut pairs: Vec<(&str, &str)> = signed_params(params, signer)
+        .filter(|(key, _)| *key != "method")
+        .collect();
+    if !signer.is_excluded("method") {
+        let position = pairs.partition_point(|(key, _)| *key < "method");
+        pairs.insert(position, ("method", method));
+    }
+
+    signer.sign(&pairs, api_secret)
+}
+
+// The names of the parameters included in a request's signature, in signing order
+pub(crate) fn signed_param_names<'a>(params: &'a Params, signer: &'a dyn RequestSigner) -> Vec<&'a str> {
+    signed_params(params, signer).map(|(key, _)| key).collect()
+}
+
+// The signature base string of a request, with secret values redacted so that it is safe to log
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) fn redacted_base_string(params: &Params, signer: &dyn RequestSigner) -> String {
+    signed_params(params, signer).fold(String::new(), |mut base, (key, value)| {
+        base.push_str(key);
+        base.push_str(if SECRET_PARAMS.contains(&key) { REDACTED } else { value });
+        base
+    })
+}
+
+fn signed_params<'a>(
+    params: &'a Params,
+    signer: &'a dyn RequestSigner,
+) -> impl Iterator<Item = (&'a str, &'a str)> {
+    params.iter().filter(move |(key, _)| !signer.is_excluded(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_params() -> Params {
+        let mut params = Params::new();
+        params.insert("method", "track.scrobble");
+        params.insert("api_key", "Key");
+        params.insert("sk", "SomeKey");
+        params.insert("track", "old bananas");
+        params.insert("api_sig", "0123456789abcdef");
+        params
+    }
+
+    #[test]
+    fn check_debug_base_string() {
+        let params = request_params();
+
+        assert_eq!(
+            debug_base_string(&params),
+            "api_keyKeymethodtrack.scrobbleskSomeKeytrackold bananas"
+        );
+        assert_eq!(
+            redacted_base_string(&params, &Md5Signer),
+            "api_keyKeymethodtrack.scrobblesk[REDACTED]trackold bananas"
+        );
+        assert_eq!(
+            signed_param_names(&params, &Md5Signer),
+            vec!["api_key", "method", "sk", "track"]
+        );
+    }
+
+    #[test]
+    fn check_verify() {
+        use crate::models::metadata::Scrobble;
+        use crate::scrobbler::Scrobbler;
+        use crate::test_util::EchoTransport;
+
+        // A request as the crate's own client signs it
+        let echo = EchoTransport::new();
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo.clone());
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.scrobble(&Scrobble::new("Sigur Rós", "Hoppípolla", "Takk...")).unwrap();
+        let request = echo.requests().remove(0);
+        let params: BTreeMap<&str, &str> = request.params.iter().collect();
+        let sig = params["api_sig"];
+
+        assert!(verify(&params, "api_secret", sig));
+        assert!(verify(&params, "api_secret", &sig.to_uppercase()));
+        // Parameters which aren't signed can change
+        let mut unsigned = params.clone();
+        unsigned.insert("format", "xml");
+        unsigned.insert("callback", "handle");
+        unsigned.remove("api_sig");
+        assert!(verify(&unsigned, "api_secret", sig));
+
+        let tampered = |key, value: Option<&'static str>| {
+            let mut tampered = params.clone();
+            match value {
+                Some(value) => tampered.insert(key, value),
+                None => tampered.remove(key),
+            };
+            tampered
+        };
+        assert!(!verify(&tampered("artist", Some("Sigur Ros")), "api_secret", sig));
+        assert!(!verify(&tampered("method", Some("track.love")), "api_secret", sig));
+        assert!(!verify(&tampered("sk", Some("stolen")), "api_secret", sig));
+        assert!(!verify(&tampered("album", None), "api_secret", sig));
+        assert!(!verify(&tampered("duration", Some("300")), "api_secret", sig));
+        assert!(!verify(&params, "other_secret", sig));
+        assert!(!verify(&params, "api_secret", &sig[..31]));
+        assert!(!verify(&params, "api_secret", &format!("{sig}0")));
+        assert!(!verify(&params, "api_secret", &sig.replace(&sig[..1], "g")));
+        assert!(!verify(&params, "api_secret", ""));
+    }
+
+    #[test]
+    fn check_sign_matches_base_string() {
+        let params = request_params();
+        let base = debug_base_string(&params) + "Secret";
+
+        assert_eq!(
+            sign(&Md5Signer, "track.scrobble", &params, "Secret"),
+            format!("{:x}", md5::compute(base))
+        );
+    }
+
+    // Signs with the base string itself, excluding the session key too
+    struct PlainSigner;
+
+    impl RequestSigner for PlainSigner {
+        fn sign(&self, params: &[(&str, &str)], api_secret: &str) -> String {
+            let pairs: Vec<String> = params.iter().map(|(key, value)| format!("{key}={value}")).collect();
+            format!("{}|{api_secret}", pairs.join("&"))
+        }
+
+        fn is_excluded(&self, key: &str) -> bool {
+            key == "sk" || UNSIGNED_PARAMS.contains(&key)
+        }
+    }
+
+    #[test]
+    fn check_sign_with_custom_signer() {
+        let mut params = request_params();
+        params.insert("format", "json");
+        params.insert("album", "old");
+
+        // `method` is signed in order, with the given value rather than the one in `params`
+        assert_eq!(
+            sign(&PlainSigner, "track.love", &params, "Secret"),
+            "album=old&api_key=Key&method=track.love&track=old bananas|Secret"
+        );
+        assert_eq!(signed_param_names(&params, &PlainSigner), vec!["album", "api_key", "method", "track"]);
+    }
+}
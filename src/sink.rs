@@ -0,0 +1,313 @@
+// Scrobble events POSTed as JSON to an endpoint of the caller's own, ie. to mirror scrobbles into analytics
+use crate::headers::Headers;
+use crate::models::responses::{IgnoredReason, ScrobbleResponse};
+use crate::params::Params;
+use crate::transport::{HttpMethod, HttpRequest, ReqwestTransport, Transport};
+
+use serde::Serialize;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+// Events beyond this many waiting to be delivered are dropped
+const MAX_PENDING: usize = 256;
+
+const MAX_ATTEMPTS: u32 = 3;
+
+const RETRY_DELAY: Duration = Duration::from_millis(200);
+
+// Responses are never read, so there's no need to buffer much of one
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// Mirrors the scrobbles Last.fm accepts or ignores to an endpoint of your own, ie. a local analytics service, by
+/// sending a small JSON document to its URL for each, as a `POST`. See [`Scrobbler::set_event_sink`]. Requires the
+/// `event-sink` feature.
+///
+/// Each event looks like this. Names are those Last.fm recorded, after any corrections, the timestamp is the one
+/// the scrobble was submitted with, and the `correlation_id` is that of the API call which submitted it (see
+/// [`ScrobbleResponse::correlation_id`]). `ignored_reason` is the code Last.fm ignored the scrobble with, and is
+/// only sent with `"outcome": "ignored"`.
+/// ```json
+/// {"outcome": "accepted", "artist": "Björk", "track": "Hyperballad", "album": "Post", "album_artist": "",
+///  "timestamp": 1500000000, "correlation_id": "1f0c2e9a7d3b4c51"}
+/// ```
+///
+/// Events are delivered in order, on a background thread, so an endpoint which is slow or down never delays or
+/// fails a scrobble. A delivery which fails to connect, or isn't answered with a `2xx` status, is attempted up to
+/// 3 times, 200 milliseconds apart, and then dropped. Events are dropped too while 256 are already waiting.
+///
+/// Clones share the background thread, which delivers what's waiting and stops once every clone is dropped,
+/// including those held by a `Scrobbler`.
+///
+/// # Usage
+/// ```ignore
+/// let sink = EventSink::new("http://localhost:8080/scrobbles");
+/// scrobbler.set_event_sink(Some(sink.clone()));
+/// // ...
+/// sink.wait_idle(Duration::from_secs(5));
+/// ```
+///
+/// [`Scrobbler::set_event_sink`]: struct.Scrobbler.html#method.set_event_sink
+/// [`ScrobbleResponse::correlation_id`]: responses/struct.ScrobbleResponse.html#method.correlation_id
+#[derive(Clone)]
+pub struct EventSink(Arc<SinkHandle>);
+
+struct SinkHandle {
+    url: String,
+    sender: SyncSender<HttpRequest>,
+    deliveries: Arc<Deliveries>,
+}
+
+// What has become of the events sent to the background thread
+#[derive(Default)]
+struct Deliveries {
+    counts: Mutex<DeliveryCounts>,
+    idle: Condvar,
+}
+
+#[derive(Default)]
+struct DeliveryCounts {
+    pending: usize,
+    delivered: u64,
+    dropped: u64,
+}
+
+#[derive(Serialize)]
+struct ScrobbleEvent<'a> {
+    outcome: &'static str,
+    artist: &'a str,
+    track: &'a str,
+    album: &'a str,
+    album_artist: &'a str,
+    timestamp: u64,
+    correlation_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ignored_reason: Option<u32>,
+}
+
+impl EventSink {
+    /// Creates a sink delivering to `url` with the default [`ReqwestTransport`]
+    ///
+    /// [`ReqwestTransport`]: struct.ReqwestTransport.html
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_transport(url, ReqwestTransport::new())
+    }
+
+    /// Creates a sink delivering to `url` with the given transport
+    pub fn with_transport<T>(url: impl Into<String>, transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(MAX_PENDING);
+        let deliveries = Arc::new(Deliveries::default());
+        let worker = Arc::clone(&deliveries);
+        thread::spawn(move || deliver(&receiver, &transport, &worker));
+
+        Self(Arc::new(SinkHandle {
+            url: url.into(),
+            sender,
+            deliveries,
+        }))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.0.url
+    }
+
+    /// Returns the number of events delivered so far
+    pub fn delivered(&self) -> u64 {
+        self.0.deliveries.lock().delivered
+    }
+
+    /// Returns the number of events dropped so far, after failing every attempt or finding too many waiting
+    pub fn dropped(&self) -> u64 {
+        self.0.deliveries.lock().dropped
+    }
+
+    /// Waits up to `timeout` for every event emitted so far to be delivered or dropped, ie. before exiting.
+    /// Returns false if some were still waiting.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let deliveries = &self.0.deliveries;
+        let (counts, _) = deliveries
+            .idle
+            .wait_timeout_while(deliveries.lock(), timeout, |counts| counts.pending > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+        counts.pending == 0
+    }
+
+    // Queues an event for each scrobble among `responses` which was sent. Dry run responses never were.
+    pub(crate) fn emit<'a>(&self, responses: impl IntoIterator<Item = &'a ScrobbleResponse>) {
+        for response in responses.into_iter().filter(|response| !response.dry_run()) {
+            let event = ScrobbleEvent {
+                outcome: if response.accepted() { "accepted" } else { "ignored" },
+                artist: response.artist().text(),
+                track: response.track().text(),
+                album: response.album().text(),
+                album_artist: response.album_artist().text(),
+                timestamp: response.submitted_timestamp(),
+                correlation_id: response.correlation_id(),
+                ignored_reason: response.ignored_reason().map(IgnoredReason::code),
+            };
+
+            // Counted before sending, so that the background thread can't finish it first
+            self.0.deliveries.lock().pending += 1;
+            if self.0.sender.try_send(self.request(&event)).is_err() {
+                self.0.deliveries.finish(false);
+            }
+        }
+    }
+
+    fn request(&self, event: &ScrobbleEvent<'_>) -> HttpRequest {
+        HttpRequest {
+            http_method: HttpMethod::Post,
+            url: self.0.url.clone(),
+            params: Params::new(),
+            request_id: None,
+            max_response_size: MAX_RESPONSE_SIZE,
+            headers: Headers::new(),
+            json_body: serde_json::to_string(event).ok(),
+        }
+    }
+}
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSink").field("url", &self.0.url).finish()
+    }
+}
+
+impl Deliveries {
+    fn lock(&self) -> MutexGuard<'_, DeliveryCounts> {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn finish(&self, delivered: bool) {
+        let mut counts = self.lock();
+        counts.pending -= 1;
+        if delivered {
+            counts.delivered += 1;
+        } else {
+            counts.dropped += 1;
+        }
+        self.idle.notify_all();
+    }
+}
+
+// Runs on the background thread until every sender is dropped
+fn deliver(receiver: &Receiver<HttpRequest>, transport: &dyn Transport, deliveries: &Deliveries) {
+    for request in receiver {
+        let delivered = (0..MAX_ATTEMPTS).any(|attempt| {
+            if attempt > 0 {
+                thread::sleep(RETRY_DELAY);
+            }
+            transport
+                .send(&request)
+                .is_ok_and(|response| (200..300).contains(&response.status))
+        });
+        deliveries.finish(delivered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::metadata::{Scrobble, ScrobbleBatch};
+    use crate::scrobbler::Scrobbler;
+    use crate::test_util::EchoTransport;
+    use mockito::{mock, Matcher};
+    use serde_json::{json, Value};
+    use std::time::UNIX_EPOCH;
+
+    // An hour ago, well within the age of scrobbles Last.fm accepts
+    fn played() -> u64 {
+        UNIX_EPOCH.elapsed().unwrap().as_secs() - 3600
+    }
+
+    fn scrobbler(echo: EchoTransport, sink: &EventSink) -> Scrobbler {
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", echo);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_event_sink(Some(sink.clone()));
+        scrobbler
+    }
+
+    fn scrobble(artist: &str, track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new(artist, track, "bananas");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_event_sink_payloads() {
+        let event = |outcome: &str, artist: &str, timestamp: u64| {
+            mock("POST", "/events")
+                .match_header("content-type", "application/json")
+                .match_body(Matcher::PartialJson(json!({
+                    "outcome": outcome,
+                    "artist": artist,
+                    "track": "fruitflies",
+                    "album": "bananas",
+                    "timestamp": timestamp,
+                })))
+                .with_status(204)
+                .expect(1)
+                .create()
+        };
+        let played = played();
+        let accepted = event("accepted", "floyd", played);
+        let ignored = event("ignored", "nickelback", played + 60);
+        let from_batch = event("accepted", "floyd", played + 120);
+
+        let sink = EventSink::new(format!("{}/events", mockito::server_url()));
+        let echo = EchoTransport::new().with_ignored_artist("nickelback");
+        let mut scrobbler = scrobbler(echo, &sink);
+        scrobbler.scrobble(&scrobble("floyd", "fruitflies", played)).unwrap();
+        scrobbler.scrobble(&scrobble("nickelback", "fruitflies", played + 60)).unwrap();
+        let batch = ScrobbleBatch::from(vec![scrobble("floyd", "fruitflies", played + 120)]);
+        scrobbler.scrobble_batch(&batch).unwrap();
+
+        // Dry runs are never sent, so aren't mirrored
+        scrobbler.set_dry_run(true);
+        scrobbler.scrobble(&scrobble("floyd", "fruitflies", played + 180)).unwrap();
+
+        assert!(sink.wait_idle(Duration::from_secs(5)));
+        assert_eq!((sink.delivered(), sink.dropped()), (3, 0));
+        accepted.assert();
+        ignored.assert();
+        from_batch.assert();
+    }
+
+    #[test]
+    fn check_event_sink_drops_failed_deliveries() {
+        // The echo transport answers anything but an API call with a 400
+        let endpoint = EchoTransport::new();
+        let sink = EventSink::with_transport("http://localhost:9/events", endpoint.clone());
+        let scrobbler = scrobbler(EchoTransport::new().with_ignored_artist("nickelback"), &sink);
+        let played = played();
+        let response = scrobbler.scrobble(&scrobble("nickelback", "fruitflies", played)).unwrap();
+        assert!(!response.accepted());
+
+        assert!(sink.wait_idle(Duration::from_secs(5)));
+        assert_eq!((sink.delivered(), sink.dropped()), (0, 1));
+        let attempts = endpoint.requests();
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts.iter().all(|attempt| *attempt == attempts[0]));
+        assert_eq!(attempts[0].url, "http://localhost:9/events");
+
+        let payload: Value = serde_json::from_str(attempts[0].json_body.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "outcome": "ignored",
+                "artist": "nickelback",
+                "track": "fruitflies",
+                "album": "bananas",
+                "album_artist": "",
+                "timestamp": played,
+                "correlation_id": response.correlation_id().unwrap(),
+                "ignored_reason": 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,161 @@
+// A summary of a batch of scrobbles, ie. to show before submitting an import
+use crate::matching::TrackKey;
+use crate::models::metadata::ScrobbleBatch;
+use crate::plan::MAX_SCROBBLE_AGE;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A summary of a [`ScrobbleBatch`], ie. to show the user before submitting an import. See
+/// [`ScrobbleBatch::stats`].
+///
+/// Artists and tracks are told apart by their [`TrackKey`], ignoring case, whitespace and other differences in
+/// presentation; a track is the same only if its artist is too. The `Display` output is a block of one line per figure.
+///
+/// # Usage
+/// ```ignore
+/// println!("{}", import.stats());
+/// // Scrobbles: 1200
+/// // Artists: 85
+/// // ...
+/// ```
+///
+/// [`TrackKey`]: struct.TrackKey.html
+/// [`ScrobbleBatch`]: struct.ScrobbleBatch.html
+/// [`ScrobbleBatch::stats`]: struct.ScrobbleBatch.html#method.stats
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BatchStats {
+    /// The number of scrobbles in the batch
+    pub entries: usize,
+    pub distinct_artists: usize,
+    /// The number of distinct tracks, by artist and track name
+    pub distinct_tracks: usize,
+    /// The earliest timestamp set on a scrobble, in seconds since the Unix epoch
+    pub earliest: Option<u64>,
+    /// The latest timestamp set on a scrobble, in seconds since the Unix epoch
+    pub latest: Option<u64>,
+    /// The number of scrobbles without a timestamp, which are submitted with the time they're scrobbled at
+    pub missing_timestamp: usize,
+    pub missing_duration: usize,
+    /// The number of scrobbles whose album is empty or only whitespace, so isn't sent
+    pub missing_album: usize,
+    /// The number of scrobbles timestamped more than two weeks before the time the stats are for, which Last.fm
+    /// would ignore
+    pub too_old: usize,
+}
+
+impl BatchStats {
+    pub(crate) fn new(batch: &ScrobbleBatch, now: SystemTime) -> Self {
+        let oldest = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.saturating_sub(MAX_SCROBBLE_AGE).as_secs());
+        let mut artists = HashSet::new();
+        let mut tracks = HashSet::new();
+        let mut stats = Self::default();
+
+        for scrobble in batch {
+            let track = TrackKey::from(scrobble);
+            artists.insert(track.artist().to_owned());
+            tracks.insert(track);
+
+            stats.entries += 1;
+            match scrobble.timestamp() {
+                Some(timestamp) => {
+                    stats.earliest = Some(stats.earliest.map_or(timestamp, |earliest| earliest.min(timestamp)));
+                    stats.latest = Some(stats.latest.map_or(timestamp, |latest| latest.max(timestamp)));
+                    if timestamp < oldest {
+                        stats.too_old += 1;
+                    }
+                }
+                None => stats.missing_timestamp += 1,
+            }
+            if scrobble.duration().is_none() {
+                stats.missing_duration += 1;
+            }
+            if scrobble.album().trim().is_empty() {
+                stats.missing_album += 1;
+            }
+        }
+
+        stats.distinct_artists = artists.len();
+        stats.distinct_tracks = tracks.len();
+        stats
+    }
+}
+
+impl fmt::Display for BatchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Scrobbles: {}", self.entries)?;
+        writeln!(f, "Artists: {}", self.distinct_artists)?;
+        writeln!(f, "Tracks: {}", self.distinct_tracks)?;
+        match (self.earliest, self.latest) {
+            (Some(earliest), Some(latest)) => writeln!(f, "Time range: {earliest} to {latest} (Unix time)")?,
+            _ => writeln!(f, "Time range: none")?,
+        }
+        writeln!(f, "Without a timestamp: {}", self.missing_timestamp)?;
+        writeln!(f, "Without a duration: {}", self.missing_duration)?;
+        writeln!(f, "Without an album: {}", self.missing_album)?;
+        write!(f, "Too old to scrobble: {}", self.too_old)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::metadata::Scrobble;
+    use std::time::Duration;
+
+    const NOW: u64 = 1_500_000_000;
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn scrobble(artist: &str, track: &str, album: &str, timestamp: Option<u64>, duration: Option<u64>) -> Scrobble {
+        let mut scrobble = Scrobble::new(artist, track, album);
+        if let Some(timestamp) = timestamp {
+            scrobble.with_timestamp(timestamp);
+        }
+        if let Some(duration) = duration {
+            scrobble.with_duration(duration);
+        }
+        scrobble
+    }
+
+    #[test]
+    fn check_batch_stats() {
+        let batch = ScrobbleBatch::from(vec![
+            scrobble("Kraftwerk", "Computerliebe", "Computerwelt", Some(NOW - DAY), Some(435)),
+            scrobble("kraftwerk ", "computerliebe", "Computerwelt", Some(NOW - 20 * DAY), None),
+            scrobble("Kraftwerk", "Numbers", " ", None, Some(199)),
+            scrobble("Karl Bartos", "Numbers", "", Some(NOW - 15 * DAY), None),
+            scrobble("Karl Bartos", "Atomium", "Off the Record", Some(NOW + 60), Some(230)),
+        ]);
+        let stats = batch.stats_at(UNIX_EPOCH + Duration::from_secs(NOW));
+        assert_eq!(
+            stats,
+            BatchStats {
+                entries: 5,
+                distinct_artists: 2,
+                distinct_tracks: 4,
+                earliest: Some(NOW - 20 * DAY),
+                latest: Some(NOW + 60),
+                missing_timestamp: 1,
+                missing_duration: 2,
+                missing_album: 2,
+                too_old: 2,
+            }
+        );
+        assert_eq!(
+            stats.to_string(),
+            "Scrobbles: 5\nArtists: 2\nTracks: 4\nTime range: 1498272000 to 1500000060 (Unix time)\n\
+             Without a timestamp: 1\nWithout a duration: 2\nWithout an album: 2\nToo old to scrobble: 2"
+        );
+
+        // Nothing is too old a fortnight earlier
+        assert_eq!(batch.stats_at(UNIX_EPOCH + Duration::from_secs(NOW - 14 * DAY)).too_old, 0);
+
+        let empty = ScrobbleBatch::from(Vec::<Scrobble>::new()).stats();
+        assert_eq!(empty, BatchStats::default());
+        assert!(empty.to_string().contains("Time range: none\n"));
+    }
+}
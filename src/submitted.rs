@@ -0,0 +1,80 @@
+// The most recent scrobble Last.fm accepted, for working out how much history to rebuild after a restart
+use crate::models::responses::ScrobbleResponse;
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, PoisonError};
+
+/// A scrobble which Last.fm accepted. See [`Scrobbler::last_accepted_scrobble`].
+///
+/// The names are those Last.fm recorded the play under, after any corrections, and the timestamp is the one the
+/// scrobble was submitted with.
+///
+/// [`Scrobbler::last_accepted_scrobble`]: struct.Scrobbler.html#method.last_accepted_scrobble
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmittedScrobble {
+    artist: String,
+    track: String,
+    #[serde(default)]
+    album: String,
+    timestamp: u64,
+}
+
+impl SubmittedScrobble {
+    pub(crate) fn from_response(response: &ScrobbleResponse) -> Self {
+        Self {
+            artist: response.artist().text().to_owned(),
+            track: response.track().text().to_owned(),
+            album: response.album().text().to_owned(),
+            timestamp: response.submitted_timestamp(),
+        }
+    }
+
+    /// Returns the artist name, as Last.fm recorded it
+    pub fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    /// Returns the track name, as Last.fm recorded it
+    pub fn track(&self) -> &str {
+        &self.track
+    }
+
+    /// Returns the album name, as Last.fm recorded it, or an empty string if there was none
+    pub fn album(&self) -> &str {
+        &self.album
+    }
+
+    /// Returns the timestamp the scrobble was submitted with, in seconds since the Unix epoch
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+// The newest scrobble accepted through a client or any of its clones, by timestamp
+#[derive(Debug, Default)]
+pub(crate) struct LastAccepted(Mutex<Option<SubmittedScrobble>>);
+
+impl LastAccepted {
+    // Records the scrobbles Last.fm accepted among `responses`. Dry run responses were never sent.
+    pub(crate) fn record<'a>(&self, responses: impl IntoIterator<Item = &'a ScrobbleResponse>) {
+        let newest = responses
+            .into_iter()
+            .filter(|response| response.accepted() && !response.dry_run())
+            .max_by_key(|response| response.submitted_timestamp());
+        if let Some(response) = newest {
+            self.restore(SubmittedScrobble::from_response(response));
+        }
+    }
+
+    // Keeps `submitted`, unless a newer scrobble has already been accepted
+    pub(crate) fn restore(&self, submitted: SubmittedScrobble) {
+        let mut last = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if last.as_ref().is_none_or(|last| last.timestamp <= submitted.timestamp) {
+            *last = Some(submitted);
+        }
+    }
+
+    pub(crate) fn get(&self) -> Option<SubmittedScrobble> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
@@ -0,0 +1,151 @@
+// Metrics of API calls and their scrobbles, recorded through the `metrics` facade
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::events::RequestOutcome;
+use crate::models::responses::{IgnoredReason, ScrobbleResponse};
+
+pub(crate) const DEFAULT_METRICS_PREFIX: &str = "lastfm";
+
+// Records metrics named after a prefix, ie. `lastfm_requests_total`. Each is a no-op unless the application has
+// installed a recorder.
+#[derive(Clone, Debug)]
+pub(crate) struct Metrics {
+    prefix: Arc<str>,
+}
+
+impl Metrics {
+    pub(crate) fn new(prefix: &str) -> Self {
+        Self { prefix: prefix.into() }
+    }
+
+    pub(crate) fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn name(&self, metric: &str) -> String {
+        format!("{}_{metric}", self.prefix)
+    }
+
+    // Counts a finished call by method and outcome, and records how long it took, across all of its attempts
+    pub(crate) fn record_request(&self, method: &str, outcome: &RequestOutcome, elapsed: Duration) {
+        let labels = [("method", method.to_owned()), ("outcome", outcome_label(outcome).to_owned())];
+        metrics::counter!(self.name("requests_total"), &labels).increment(1);
+        metrics::histogram!(self.name("request_duration_seconds"), "method" => method.to_owned())
+            .record(elapsed.as_secs_f64());
+    }
+
+    pub(crate) fn record_retry(&self, method: &str) {
+        metrics::counter!(self.name("retries_total"), "method" => method.to_owned()).increment(1);
+    }
+
+    // Counts each submitted scrobble as accepted, or as ignored for its reason
+    pub(crate) fn record_scrobbles<'a>(&self, responses: impl IntoIterator<Item = &'a ScrobbleResponse>) {
+        for response in responses {
+            match response.ignored_reason() {
+                None => metrics::counter!(self.name("scrobbles_accepted_total")).increment(1),
+                Some(reason) => {
+                    metrics::counter!(self.name("scrobbles_ignored_total"), "reason" => reason_label(reason))
+                        .increment(1);
+                }
+            }
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new(DEFAULT_METRICS_PREFIX)
+    }
+}
+
+fn outcome_label(outcome: &RequestOutcome) -> &'static str {
+    match outcome {
+        RequestOutcome::Success => "success",
+        RequestOutcome::ApiError(_) => "api_error",
+        RequestOutcome::HttpStatus(_) => "http_error",
+        RequestOutcome::TransportError(_) => "transport_error",
+    }
+}
+
+fn reason_label(reason: IgnoredReason) -> &'static str {
+    match reason {
+        IgnoredReason::ArtistIgnored => "artist_ignored",
+        IgnoredReason::TrackIgnored => "track_ignored",
+        IgnoredReason::TimestampTooOld => "timestamp_too_old",
+        IgnoredReason::TimestampTooNew => "timestamp_too_new",
+        IgnoredReason::DailyScrobbleLimitExceeded => "daily_limit_exceeded",
+        IgnoredReason::Other(_) => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::metadata::Scrobble;
+    use crate::retry::RetryPolicy;
+    use crate::scrobbler::Scrobbler;
+    use crate::test_util::{EchoTransport, Fault, FaultyTransport};
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use std::time::Duration;
+
+    #[test]
+    fn check_metrics_recorded() {
+        let echo = EchoTransport::new().with_ignored_artist("floyd");
+        let transport = FaultyTransport::new(echo).fail_request(1, Fault::HttpStatus(503));
+        let mut scrobbler = Scrobbler::with_transport("api_key", "api_secret", transport);
+        scrobbler.authenticate_with_session_key("seshkey").unwrap();
+        scrobbler.set_retry_policy(RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        });
+        assert_eq!(scrobbler.metrics_prefix(), "lastfm");
+        scrobbler.set_metrics_prefix("player");
+
+        // Recorded to nowhere without a recorder
+        scrobbler.scrobble(&Scrobble::new("foo", "first", "bananas")).unwrap();
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        // Including the retried failure of the first request here
+        metrics::with_local_recorder(&recorder, || {
+            scrobbler.scrobble(&Scrobble::new("foo", "second", "bananas")).unwrap();
+            scrobbler.scrobble(&Scrobble::new("floyd", "third", "bananas")).unwrap();
+            scrobbler.now_playing(Scrobble::new("foo", "fourth", "bananas")).unwrap();
+        });
+
+        let mut metrics: Vec<(String, Vec<String>, String)> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let labels = key.key().labels().map(|label| format!("{}={}", label.key(), label.value())).collect();
+                let value = match value {
+                    DebugValue::Counter(count) => count.to_string(),
+                    DebugValue::Histogram(samples) => format!("{} samples", samples.len()),
+                    DebugValue::Gauge(gauge) => gauge.to_string(),
+                };
+                (key.key().name().to_owned(), labels, value)
+            })
+            .collect();
+        metrics.sort();
+
+        let expected = [
+            ("player_request_duration_seconds", vec!["method=track.scrobble"], "2 samples"),
+            ("player_request_duration_seconds", vec!["method=track.updateNowPlaying"], "1 samples"),
+            ("player_requests_total", vec!["method=track.scrobble", "outcome=success"], "2"),
+            ("player_requests_total", vec!["method=track.updateNowPlaying", "outcome=success"], "1"),
+            ("player_retries_total", vec!["method=track.scrobble"], "1"),
+            ("player_scrobbles_accepted_total", vec![], "1"),
+            ("player_scrobbles_ignored_total", vec!["reason=artist_ignored"], "1"),
+        ];
+        let expected: Vec<(String, Vec<String>, String)> = expected
+            .iter()
+            .map(|(name, labels, value)| {
+                ((*name).to_owned(), labels.iter().map(|&label| label.to_owned()).collect(), (*value).to_owned())
+            })
+            .collect();
+        assert_eq!(metrics, expected);
+    }
+}
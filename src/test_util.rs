@@ -0,0 +1,14 @@
+//! Utilities for testing applications built on this crate.
+//!
+//! Only available with the `test-util` feature enabled. Nothing in this module is needed at runtime; it exists so
+//! that scrobbling logic can be unit tested without talking to Last.fm.
+mod echo;
+pub mod fake;
+mod fault;
+mod fixture;
+mod mock;
+
+pub use self::echo::EchoTransport;
+pub use self::fault::{Fault, FaultyTransport};
+pub use self::fixture::FixtureTransport;
+pub use self::mock::{MockCall, MockScrobbler};
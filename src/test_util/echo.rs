@@ -0,0 +1,161 @@
+use super::fake::{self, FakeBatch, FakeScrobble};
+use crate::error::ScrobblerError;
+use crate::headers::Headers;
+use crate::models::responses::IgnoredReason;
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A [`Transport`] which stands in for Last.fm, answering every request without touching the network.
+///
+/// Authentication requests succeed with a [`fake::session_response`] for the submitted username, and token requests
+/// with a [`fake::token_response`]; Now Playing and
+/// Scrobble requests succeed, echoing the submitted tracks back uncorrected, as do Love and Unlove requests, and
+/// Track Info requests. Recent Tracks requests find an empty history, and User Info requests a user who has
+/// scrobbled nothing. Every request received is recorded.
+///
+/// With [`EchoTransport::with_daily_limit`], scrobbles beyond a limit are ignored instead, as Last.fm does once a
+/// user reaches the daily scrobble limit, and with [`EchoTransport::with_ignored_artist`], scrobbles of an artist
+/// Last.fm refuses to scrobble are.
+///
+/// Clones share their record of requests, so a clone can be kept to inspect the requests that reached the
+/// transport after the original has been handed to a `Scrobbler` (or wrapped by another transport).
+///
+/// # Example
+/// ```ignore
+/// let echo = EchoTransport::new();
+/// let mut scrobbler = Scrobbler::with_transport(api_key, api_secret, echo.clone());
+/// scrobbler.authenticate_with_password("user", "pass")?;
+///
+/// assert_eq!(echo.requests()[0].method(), "auth.getMobileSession");
+/// ```
+///
+/// [`Transport`]: ../trait.Transport.html
+/// [`fake::session_response`]: fake/fn.session_response.html
+/// [`EchoTransport::with_daily_limit`]: struct.EchoTransport.html#method.with_daily_limit
+/// [`EchoTransport::with_ignored_artist`]: struct.EchoTransport.html#method.with_ignored_artist
+#[derive(Clone, Debug, Default)]
+pub struct EchoTransport {
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+    daily_limit: Option<usize>,
+    ignored_artists: Vec<String>,
+}
+
+impl EchoTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignores every scrobble after the first `limit` received, as `IgnoredReason::DailyScrobbleLimitExceeded`.
+    /// The limit never resets.
+    #[must_use]
+    pub fn with_daily_limit(mut self, limit: usize) -> Self {
+        self.daily_limit = Some(limit);
+        self
+    }
+
+    /// Ignores every scrobble of the given artist, as `IgnoredReason::ArtistIgnored`, as Last.fm does for artist
+    /// names such as `"Unknown Artist"`
+    #[must_use]
+    pub fn with_ignored_artist(mut self, artist: &str) -> Self {
+        self.ignored_artists.push(artist.to_owned());
+        self
+    }
+
+    /// Returns every request received so far, in order
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    // The number of scrobbles submitted in the given requests
+    fn scrobble_count(requests: &[HttpRequest]) -> usize {
+        requests
+            .iter()
+            .filter(|request| request.method() == "track.scrobble")
+            .map(|request| request.params.iter().filter(|(key, _)| key.starts_with("artist")).count())
+            .sum()
+    }
+
+    // `submitted` is the number of scrobbles submitted in earlier requests
+    fn response(&self, request: &HttpRequest, submitted: usize) -> HttpResponse {
+        let params = &request.params;
+        let param = |key: &str| params.get(key).unwrap_or_default();
+        let ignore = |i: usize, artist: &str, scrobble: FakeScrobble| match self.daily_limit {
+            Some(limit) if submitted + i >= limit => scrobble.ignored(IgnoredReason::DailyScrobbleLimitExceeded),
+            _ if self.ignored_artists.iter().any(|ignored| ignored == artist) => {
+                scrobble.ignored(IgnoredReason::ArtistIgnored)
+            }
+            _ => scrobble,
+        };
+
+        let body = match request.method() {
+            "auth.getMobileSession" | "auth.getSession" => {
+                let name = params.get("username").unwrap_or("user");
+                fake::session_response(name).to_string()
+            }
+            "auth.getToken" => fake::token_response("echo-token").to_string(),
+            "track.updateNowPlaying" => fake::now_playing_response(param("artist"), param("track"))
+                .album(param("album"))
+                .to_string(),
+            "track.scrobble" if params.contains_key("artist[0]") => {
+                let scrobbles = (0..)
+                    .map(|i| {
+                        let indexed = |key: &str| params.get(&format!("{key}[{i}]")).map(str::to_owned);
+                        indexed("artist").map(|artist| {
+                            let scrobble = fake::scrobble_response(&artist, &indexed("track").unwrap_or_default())
+                                .album(&indexed("album").unwrap_or_default())
+                                .timestamp(indexed("timestamp").and_then(|t| t.parse().ok()).unwrap_or(0));
+                            ignore(i, &artist, scrobble)
+                        })
+                    })
+                    .take_while(Option::is_some)
+                    .flatten()
+                    .collect();
+
+                FakeBatch::from_scrobbles(scrobbles).to_string()
+            }
+            "track.scrobble" => {
+                let scrobble = fake::scrobble_response(param("artist"), param("track"))
+                    .album(param("album"))
+                    .timestamp(param("timestamp").parse().unwrap_or(0));
+                ignore(0, param("artist"), scrobble).to_string()
+            }
+            "track.love" | "track.unlove" => "{}".to_owned(),
+            "track.getInfo" => fake::track_info_response(param("artist"), param("track")).to_string(),
+            "artist.getInfo" => fake::artist_info_response(param("artist")).to_string(),
+            "album.getInfo" => fake::album_info_response(param("artist"), param("album")).to_string(),
+            "user.getRecentTracks" => fake::recent_tracks_response(param("user"), &[]).to_string(),
+            "user.getInfo" => fake::user_info_response(params.get("user").unwrap_or("user")).to_string(),
+            method => {
+                let message = format!("Invalid Method - No method with that name ({method})");
+                return HttpResponse {
+                    status: 400,
+                    body: fake::error_response(3, &message).to_string(),
+                    headers: Headers::new(),
+                };
+            }
+        };
+
+        HttpResponse {
+            status: 200,
+            body,
+            headers: Headers::new(),
+        }
+    }
+}
+
+impl Transport for EchoTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ScrobblerError> {
+        let submitted = {
+            let mut requests = self.requests.lock().unwrap_or_else(PoisonError::into_inner);
+            let submitted = Self::scrobble_count(&requests);
+            requests.push(request.clone());
+            submitted
+        };
+
+        Ok(self.response(request, submitted))
+    }
+}
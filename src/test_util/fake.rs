@@ -0,0 +1,924 @@
+//! Canned Last.fm API response bodies.
+//!
+//! Each builder produces a response body in the exact shape the Last.fm API returns it, for use with a mock HTTP
+//! server (such as `mockito`) standing in for Last.fm. Builders start from a successful, uncorrected response and
+//! can be adjusted before being rendered, either as a JSON string with `to_string()` or as a `serde_json::Value`
+//! with `to_value()`.
+//!
+//! Builders can also produce the response types themselves with `to_response()`, exactly as a `Scrobbler` would
+//! decode them, for mocks which implement [`ScrobbleService`](../../trait.ScrobbleService.html) directly. The
+//! response types can't be constructed any other way outside this crate.
+//!
+//! # Example
+//! ```ignore
+//! use rustfm_scrobble::responses::values::IgnoredReason;
+//! use rustfm_scrobble::test_util::fake;
+//!
+//! let body = fake::scrobble_response("Example Artist", "Example Track")
+//!     .album("Example Album")
+//!     .ignored(IgnoredReason::TimestampTooOld)
+//!     .to_string();
+//! ```
+use crate::issues::PreparationReport;
+use crate::models::responses::{
+    AlbumInfo, AlbumInfoWrapper, ArtistInfo, ArtistInfoWrapper, AuthResponse, BatchScrobbleResponse,
+    BatchScrobbleResponseWrapper, IgnoredReason, NowPlayingResponse, NowPlayingResponseWrapper, ScrobbleResponse,
+    RecentTrack, RecentTracksWrapper, ScrobbleResponseWrapper, SessionResponse, TrackInfo, TrackInfoWrapper, UserInfo,
+    UserInfoWrapper,
+};
+
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Builds the response to a User Info request for the given user, who has scrobbled nothing and isn't a subscriber
+pub fn user_info_response(name: &str) -> FakeUserInfo {
+    FakeUserInfo {
+        name: name.to_owned(),
+        playcount: 0,
+        subscriber: false,
+    }
+}
+
+/// Builds the response to a successful authentication request for the given user
+pub fn session_response(name: &str) -> FakeSession {
+    FakeSession {
+        name: name.to_owned(),
+        key: "fake-session-key".to_owned(),
+        subscriber: false,
+    }
+}
+
+/// Builds the response to a Now Playing request for the given track
+pub fn now_playing_response(artist: &str, track: &str) -> FakeNowPlaying {
+    FakeNowPlaying {
+        track: FakeTrack::new(artist, track),
+    }
+}
+
+/// Builds the response to a single Scrobble request for the given track
+pub fn scrobble_response(artist: &str, track: &str) -> FakeScrobble {
+    FakeScrobble {
+        track: FakeTrack::new(artist, track),
+        timestamp: 0,
+    }
+}
+
+/// Builds the response to a Track Info request for the given track, with no album and no listeners
+pub fn track_info_response(artist: &str, track: &str) -> FakeTrackInfo {
+    FakeTrackInfo {
+        artist: artist.to_owned(),
+        track: track.to_owned(),
+        album: None,
+        listeners: 0,
+        playcount: 0,
+    }
+}
+
+/// Builds the response to an Artist Info request for the given artist, with no listeners and no biography
+pub fn artist_info_response(artist: &str) -> FakeArtistInfo {
+    FakeArtistInfo {
+        artist: artist.to_owned(),
+        listeners: 0,
+        playcount: 0,
+        bio: None,
+    }
+}
+
+/// Builds the response to an Album Info request for the given album, with no listeners and no wiki text
+pub fn album_info_response(artist: &str, album: &str) -> FakeAlbumInfo {
+    FakeAlbumInfo {
+        artist: artist.to_owned(),
+        album: album.to_owned(),
+        listeners: 0,
+        playcount: 0,
+        wiki: None,
+    }
+}
+
+/// Builds the response to a Recent Tracks request for the given user's history, holding the given scrobbles as
+/// `(artist, track, timestamp)`, most recent first as Last.fm sends them. The response is the history's only page
+/// unless set with [`FakeRecentTracks::page`].
+///
+/// [`FakeRecentTracks::page`]: struct.FakeRecentTracks.html#method.page
+pub fn recent_tracks_response(user: &str, scrobbles: &[(&str, &str, u64)]) -> FakeRecentTracks {
+    FakeRecentTracks {
+        user: user.to_owned(),
+        tracks: scrobbles
+            .iter()
+            .map(|(artist, track, timestamp)| FakeRecentTrack::new(artist, track, Some(*timestamp)))
+            .collect(),
+        page: 1,
+        total_pages: 1,
+    }
+}
+
+/// Builds the response to a batch Scrobble request, with `accepted` accepted scrobbles followed by `ignored`
+/// scrobbles ignored as `IgnoredReason::TimestampTooOld`. Use [`FakeBatch::from_scrobbles`] for control over the
+/// individual entries.
+///
+/// [`FakeBatch::from_scrobbles`]: struct.FakeBatch.html#method.from_scrobbles
+pub fn batch_response(accepted: usize, ignored: usize) -> FakeBatch {
+    let accepted = (0..accepted).map(|i| {
+        scrobble_response(&format!("Artist {i}"), &format!("Track {i}")).timestamp(i as u64)
+    });
+    let ignored = (0..ignored).map(|i| {
+        scrobble_response(&format!("Ignored Artist {i}"), &format!("Ignored Track {i}"))
+            .timestamp(i as u64)
+            .ignored(IgnoredReason::TimestampTooOld)
+    });
+
+    FakeBatch::from_scrobbles(accepted.chain(ignored).collect())
+}
+
+/// Builds the response to an auth.getToken request, issuing the given token
+pub fn token_response(token: &str) -> Value {
+    json!({
+        "token": token,
+    })
+}
+
+/// Builds a Last.fm API error response, ie. `error_response(9, "Invalid session key - Please re-authenticate")`.
+/// Note that the API sends these with a non-success HTTP status.
+///
+/// [API Error Codes Documentation](https://www.last.fm/api/errorcodes)
+pub fn error_response(code: u32, message: &str) -> Value {
+    json!({
+        "error": code,
+        "message": message,
+    })
+}
+
+/// A canned authentication response. See [`session_response`](fn.session_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeSession {
+    name: String,
+    key: String,
+    subscriber: bool,
+}
+
+impl FakeSession {
+    /// Sets the session key returned (defaults to `"fake-session-key"`)
+    #[must_use]
+    pub fn key(mut self, key: &str) -> Self {
+        key.clone_into(&mut self.key);
+        self
+    }
+
+    /// Marks the user as a Last.fm subscriber
+    #[must_use]
+    pub fn subscriber(mut self, subscriber: bool) -> Self {
+        self.subscriber = subscriber;
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        json!({
+            "session": {
+                "name": self.name,
+                "key": self.key,
+                "subscriber": i32::from(self.subscriber),
+            }
+        })
+    }
+
+    /// Decodes the response into a `SessionResponse`
+    pub fn to_response(&self) -> SessionResponse {
+        decode::<AuthResponse>(self.to_value()).session
+    }
+}
+
+// The artist/track/album metadata shared by Now Playing and Scrobble responses
+#[derive(Clone, Debug)]
+struct FakeTrack {
+    artist: FakeField,
+    track: FakeField,
+    album: FakeField,
+    album_artist: FakeField,
+    ignored: Option<IgnoredReason>,
+}
+
+#[derive(Clone, Debug)]
+struct FakeField {
+    text: String,
+    corrected: bool,
+}
+
+impl FakeField {
+    fn uncorrected(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            corrected: false,
+        }
+    }
+
+    fn corrected(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            corrected: true,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        json!({
+            "corrected": if self.corrected { "1" } else { "0" },
+            "#text": self.text,
+        })
+    }
+}
+
+impl FakeTrack {
+    fn new(artist: &str, track: &str) -> Self {
+        Self {
+            artist: FakeField::uncorrected(artist),
+            track: FakeField::uncorrected(track),
+            album: FakeField::uncorrected(""),
+            album_artist: FakeField::uncorrected(""),
+            ignored: None,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        let (code, message) = match self.ignored {
+            Some(reason) => (reason.code(), reason.message()),
+            None => (0, ""),
+        };
+
+        json!({
+            "artist": self.artist.to_value(),
+            "track": self.track.to_value(),
+            "album": self.album.to_value(),
+            "albumArtist": self.album_artist.to_value(),
+            "ignoredMessage": {
+                "code": code.to_string(),
+                "#text": message,
+            },
+        })
+    }
+}
+
+// Setters shared by the Now Playing and Scrobble builders, which both wrap a `FakeTrack`
+macro_rules! fake_track_setters {
+    () => {
+        /// Sets the album (defaults to empty)
+        #[must_use]
+        pub fn album(mut self, album: &str) -> Self {
+            self.track.album = FakeField::uncorrected(album);
+            self
+        }
+
+        /// Sets the album artist (defaults to empty)
+        #[must_use]
+        pub fn album_artist(mut self, album_artist: &str) -> Self {
+            self.track.album_artist = FakeField::uncorrected(album_artist);
+            self
+        }
+
+        /// Reports the artist as corrected by Last.fm to the given name
+        #[must_use]
+        pub fn corrected_artist(mut self, artist: &str) -> Self {
+            self.track.artist = FakeField::corrected(artist);
+            self
+        }
+
+        /// Reports the track as corrected by Last.fm to the given name
+        #[must_use]
+        pub fn corrected_track(mut self, track: &str) -> Self {
+            self.track.track = FakeField::corrected(track);
+            self
+        }
+
+        /// Reports the album as corrected by Last.fm to the given name
+        #[must_use]
+        pub fn corrected_album(mut self, album: &str) -> Self {
+            self.track.album = FakeField::corrected(album);
+            self
+        }
+
+        /// Reports the submission as ignored by Last.fm, for the given reason
+        #[must_use]
+        pub fn ignored(mut self, reason: IgnoredReason) -> Self {
+            self.track.ignored = Some(reason);
+            self
+        }
+    };
+}
+
+/// A canned Now Playing response. See [`now_playing_response`](fn.now_playing_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeNowPlaying {
+    track: FakeTrack,
+}
+
+impl FakeNowPlaying {
+    fake_track_setters!();
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        json!({ "nowplaying": self.track.to_value() })
+    }
+
+    /// Decodes the response into a `NowPlayingResponse`
+    pub fn to_response(&self) -> NowPlayingResponse {
+        decode::<NowPlayingResponseWrapper>(self.to_value()).nowplaying
+    }
+}
+
+/// A canned Scrobble response. See [`scrobble_response`](fn.scrobble_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeScrobble {
+    track: FakeTrack,
+    timestamp: u64,
+}
+
+impl FakeScrobble {
+    fake_track_setters!();
+
+    /// Sets the scrobble timestamp (defaults to `0`)
+    #[must_use]
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        json!({
+            "scrobbles": {
+                "scrobble": self.entry_value(),
+                "@attr": attr_value(std::slice::from_ref(self)),
+            }
+        })
+    }
+
+    /// Decodes the response into a `ScrobbleResponse`
+    pub fn to_response(&self) -> ScrobbleResponse {
+        let decoded = decode::<ScrobbleResponseWrapper>(self.to_value());
+        decoded
+            .scrobbles
+            .scrobble
+            .with_counts(decoded.scrobbles.counts)
+            .with_submitted_timestamp(self.timestamp)
+    }
+
+    // The scrobble itself, as it appears inside the `scrobbles` envelope
+    fn entry_value(&self) -> Value {
+        let mut value = self.track.to_value();
+        value["timestamp"] = Value::String(self.timestamp.to_string());
+        value
+    }
+}
+
+/// A canned batch Scrobble response. See [`batch_response`](fn.batch_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeBatch {
+    scrobbles: Vec<FakeScrobble>,
+}
+
+impl FakeBatch {
+    /// Builds a batch response out of individual scrobble responses, with the accepted/ignored counts derived from
+    /// the scrobbles themselves
+    pub fn from_scrobbles(scrobbles: Vec<FakeScrobble>) -> Self {
+        Self { scrobbles }
+    }
+
+    /// Renders the response body as a JSON value.
+    ///
+    /// As with the real API, a batch holding a single scrobble is rendered as an object rather than an array.
+    pub fn to_value(&self) -> Value {
+        let scrobble = match self.scrobbles.as_slice() {
+            [single] => single.entry_value(),
+            scrobbles => scrobbles.iter().map(FakeScrobble::entry_value).collect(),
+        };
+
+        json!({
+            "scrobbles": {
+                "scrobble": scrobble,
+                "@attr": attr_value(&self.scrobbles),
+            }
+        })
+    }
+
+    /// Decodes the response into a `BatchScrobbleResponse`
+    pub fn to_response(&self) -> BatchScrobbleResponse {
+        let scrobbles = decode::<BatchScrobbleResponseWrapper>(self.to_value()).scrobbles.into_reconciled();
+        BatchScrobbleResponse {
+            scrobbles: scrobbles
+                .into_iter()
+                .zip(&self.scrobbles)
+                .map(|(response, fake)| response.with_submitted_timestamp(fake.timestamp))
+                .collect(),
+            dry_run: false,
+            preparation: PreparationReport::default(),
+        }
+    }
+}
+
+/// A canned Track Info response. See [`track_info_response`](fn.track_info_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeTrackInfo {
+    artist: String,
+    track: String,
+    album: Option<String>,
+    listeners: u64,
+    playcount: u64,
+}
+
+impl FakeTrackInfo {
+    /// Sets the album (defaults to none)
+    #[must_use]
+    pub fn album(mut self, album: &str) -> Self {
+        self.album = Some(album.to_owned());
+        self
+    }
+
+    /// Sets the number of listeners and the play count (both default to `0`)
+    #[must_use]
+    pub fn statistics(mut self, listeners: u64, playcount: u64) -> Self {
+        self.listeners = listeners;
+        self.playcount = playcount;
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        let url = |path: &str| format!("https://www.last.fm/music/{}", path.replace(' ', "+"));
+        let mut track = json!({
+            "name": self.track,
+            "mbid": "",
+            "url": url(&format!("{}/_/{}", self.artist, self.track)),
+            "duration": "0",
+            "listeners": self.listeners.to_string(),
+            "playcount": self.playcount.to_string(),
+            "artist": {
+                "name": self.artist,
+                "url": url(&self.artist),
+            },
+        });
+        if let Some(album) = &self.album {
+            track["album"] = json!({
+                "artist": self.artist,
+                "title": album,
+                "url": url(&format!("{}/{album}", self.artist)),
+            });
+        }
+
+        json!({ "track": track })
+    }
+
+    /// Decodes the response into a `TrackInfo`
+    pub fn to_response(&self) -> TrackInfo {
+        decode::<TrackInfoWrapper>(self.to_value()).track
+    }
+}
+
+/// A canned Artist Info response. See [`artist_info_response`](fn.artist_info_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeArtistInfo {
+    artist: String,
+    listeners: u64,
+    playcount: u64,
+    bio: Option<(String, String)>,
+}
+
+impl FakeArtistInfo {
+    /// Sets the number of listeners and the play count (both default to `0`)
+    #[must_use]
+    pub fn statistics(mut self, listeners: u64, playcount: u64) -> Self {
+        self.listeners = listeners;
+        self.playcount = playcount;
+        self
+    }
+
+    /// Sets the summary and full text of the biography (defaults to none)
+    #[must_use]
+    pub fn bio(mut self, summary: &str, content: &str) -> Self {
+        self.bio = Some((summary.to_owned(), content.to_owned()));
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        let mut artist = json!({
+            "name": self.artist,
+            "mbid": "",
+            "url": format!("https://www.last.fm/music/{}", self.artist.replace(' ', "+")),
+            "stats": {
+                "listeners": self.listeners.to_string(),
+                "playcount": self.playcount.to_string(),
+            },
+        });
+        if let Some((summary, content)) = &self.bio {
+            artist["bio"] = json!({ "summary": summary, "content": content });
+        }
+
+        json!({ "artist": artist })
+    }
+
+    /// Decodes the response into an `ArtistInfo`
+    pub fn to_response(&self) -> ArtistInfo {
+        decode::<ArtistInfoWrapper>(self.to_value()).artist
+    }
+}
+
+/// A canned Album Info response. See [`album_info_response`](fn.album_info_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeAlbumInfo {
+    artist: String,
+    album: String,
+    listeners: u64,
+    playcount: u64,
+    wiki: Option<(String, String)>,
+}
+
+impl FakeAlbumInfo {
+    /// Sets the number of listeners and the play count (both default to `0`)
+    #[must_use]
+    pub fn statistics(mut self, listeners: u64, playcount: u64) -> Self {
+        self.listeners = listeners;
+        self.playcount = playcount;
+        self
+    }
+
+    /// Sets the summary and full text of the wiki (defaults to none)
+    #[must_use]
+    pub fn wiki(mut self, summary: &str, content: &str) -> Self {
+        self.wiki = Some((summary.to_owned(), content.to_owned()));
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        let mut album = json!({
+            "name": self.album,
+            "artist": self.artist,
+            "mbid": "",
+            "url": format!("https://www.last.fm/music/{}/{}", self.artist, self.album).replace(' ', "+"),
+            "listeners": self.listeners.to_string(),
+            "playcount": self.playcount.to_string(),
+        });
+        if let Some((summary, content)) = &self.wiki {
+            album["wiki"] = json!({ "summary": summary, "content": content });
+        }
+
+        json!({ "album": album })
+    }
+
+    /// Decodes the response into an `AlbumInfo`
+    pub fn to_response(&self) -> AlbumInfo {
+        decode::<AlbumInfoWrapper>(self.to_value()).album
+    }
+}
+
+/// A canned Recent Tracks response. See [`recent_tracks_response`](fn.recent_tracks_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeRecentTracks {
+    user: String,
+    tracks: Vec<FakeRecentTrack>,
+    page: u32,
+    total_pages: u32,
+}
+
+#[derive(Clone, Debug)]
+struct FakeRecentTrack {
+    artist: String,
+    track: String,
+    // Unset for the track being played now
+    timestamp: Option<u64>,
+}
+
+impl FakeRecentTrack {
+    fn new(artist: &str, track: &str, timestamp: Option<u64>) -> Self {
+        Self {
+            artist: artist.to_owned(),
+            track: track.to_owned(),
+            timestamp,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        let mut track = json!({
+            "artist": { "mbid": "", "#text": self.artist },
+            "streamable": "0",
+            "mbid": "",
+            "album": { "mbid": "", "#text": "" },
+            "name": self.track,
+            "url": format!("https://www.last.fm/music/{}/_/{}", self.artist, self.track).replace(' ', "+"),
+        });
+        match self.timestamp {
+            Some(timestamp) => track["date"] = json!({ "uts": timestamp.to_string() }),
+            None => track["@attr"] = json!({ "nowplaying": "true" }),
+        }
+
+        track
+    }
+}
+
+impl FakeRecentTracks {
+    /// Lists the given track first, as the track being played now. Last.fm lists it on every page, whatever the
+    /// time range requested.
+    #[must_use]
+    pub fn now_playing(mut self, artist: &str, track: &str) -> Self {
+        self.tracks.insert(0, FakeRecentTrack::new(artist, track, None));
+        self
+    }
+
+    /// Sets which page of the history the response is, out of `total_pages` (defaults to the only page)
+    #[must_use]
+    pub fn page(mut self, page: u32, total_pages: u32) -> Self {
+        self.page = page;
+        self.total_pages = total_pages;
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        let scrobbled = self.tracks.iter().filter(|track| track.timestamp.is_some()).count();
+
+        json!({
+            "recenttracks": {
+                "track": self.tracks.iter().map(FakeRecentTrack::to_value).collect::<Vec<_>>(),
+                "@attr": {
+                    "user": self.user,
+                    "page": self.page.to_string(),
+                    "perPage": "200",
+                    "totalPages": self.total_pages.to_string(),
+                    "total": scrobbled.to_string(),
+                },
+            }
+        })
+    }
+
+    /// Decodes the response into the `RecentTrack`s it lists
+    pub fn to_response(&self) -> Vec<RecentTrack> {
+        decode::<RecentTracksWrapper>(self.to_value()).recenttracks.tracks
+    }
+}
+
+/// A canned User Info response. See [`user_info_response`](fn.user_info_response.html).
+#[derive(Clone, Debug)]
+pub struct FakeUserInfo {
+    name: String,
+    playcount: u64,
+    subscriber: bool,
+}
+
+impl FakeUserInfo {
+    /// Sets the number of tracks scrobbled (defaults to `0`)
+    #[must_use]
+    pub fn playcount(mut self, playcount: u64) -> Self {
+        self.playcount = playcount;
+        self
+    }
+
+    /// Sets whether the user is a subscriber (defaults to `false`)
+    #[must_use]
+    pub fn subscriber(mut self, subscriber: bool) -> Self {
+        self.subscriber = subscriber;
+        self
+    }
+
+    /// Renders the response body as a JSON value
+    pub fn to_value(&self) -> Value {
+        json!({
+            "user": {
+                "name": self.name,
+                "realname": "",
+                "url": format!("https://www.last.fm/user/{}", self.name),
+                "country": "None",
+                "subscriber": if self.subscriber { "1" } else { "0" },
+                "playcount": self.playcount.to_string(),
+                "artist_count": "0",
+                "track_count": "0",
+                "album_count": "0",
+                "playlists": "0",
+                "type": "user",
+                "registered": {"unixtime": "1037793040", "#text": 1_037_793_040},
+            }
+        })
+    }
+
+    /// Decodes the response into a `UserInfo`
+    pub fn to_response(&self) -> UserInfo {
+        decode::<UserInfoWrapper>(self.to_value()).user
+    }
+}
+
+// Fakes always render valid responses, so decoding them can't fail
+fn decode<T: serde::de::DeserializeOwned>(value: Value) -> T {
+    serde_json::from_value(value).expect("fake response should decode")
+}
+
+fn attr_value(scrobbles: &[FakeScrobble]) -> Value {
+    let ignored = scrobbles.iter().filter(|s| s.track.ignored.is_some()).count();
+
+    json!({
+        "accepted": scrobbles.len() - ignored,
+        "ignored": ignored,
+    })
+}
+
+macro_rules! display_as_json {
+    ($($fake:ty),*) => {
+        $(
+            impl fmt::Display for $fake {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "{}", self.to_value())
+                }
+            }
+        )*
+    };
+}
+
+display_as_json!(
+    FakeSession,
+    FakeNowPlaying,
+    FakeScrobble,
+    FakeBatch,
+    FakeTrackInfo,
+    FakeArtistInfo,
+    FakeAlbumInfo,
+    FakeRecentTracks,
+    FakeUserInfo
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_fake_session_parses() {
+        let body = session_response("user").key("seshkey").subscriber(true).to_string();
+        let decoded: AuthResponse = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(decoded.session.name(), "user");
+        assert_eq!(decoded.session.key(), "seshkey");
+        assert_eq!(decoded.session.subscriber(), 1);
+
+        let session = session_response("user").to_response();
+        assert_eq!(session.name(), "user");
+        assert_eq!(session.key(), "fake-session-key");
+        assert_eq!(session.subscriber(), 0);
+    }
+
+    #[test]
+    fn check_fake_now_playing_parses() {
+        let body = now_playing_response("Bjork", "Joga")
+            .corrected_artist("Björk")
+            .album("Homogenic")
+            .to_string();
+        let decoded: NowPlayingResponseWrapper = serde_json::from_str(&body).unwrap();
+
+        assert!(decoded.nowplaying.artist().corrected());
+        assert_eq!(decoded.nowplaying.artist().text(), "Björk");
+        assert!(!decoded.nowplaying.album().corrected());
+        assert_eq!(decoded.nowplaying.album().text(), "Homogenic");
+
+        let response = now_playing_response("Bjork", "Joga").album_artist("Björk").to_response();
+        assert_eq!(response.artist().text(), "Bjork");
+        assert_eq!(response.track().text(), "Joga");
+        assert_eq!(response.album().text(), "");
+        assert_eq!(response.album_artist().text(), "Björk");
+        assert!(!response.track().corrected());
+        assert!(!response.dry_run());
+    }
+
+    #[test]
+    fn check_fake_scrobble_parses() {
+        let fake = scrobble_response("foo floyd", "old bananas")
+            .timestamp(1337)
+            .ignored(IgnoredReason::TimestampTooOld);
+        let value = fake.to_value();
+
+        assert_eq!(value["scrobbles"]["@attr"]["ignored"], 1);
+        assert_eq!(value["scrobbles"]["scrobble"]["ignoredMessage"]["code"], "3");
+
+        let decoded: ScrobbleResponseWrapper = serde_json::from_str(&fake.to_string()).unwrap();
+        assert_eq!(decoded.scrobbles.scrobble.track().text(), "old bananas");
+        assert_eq!(decoded.scrobbles.scrobble.timestamp(), "1337");
+
+        let response = fake.to_response();
+        assert_eq!(response.artist().text(), "foo floyd");
+        assert_eq!(response.album().text(), "");
+        assert_eq!(response.album_artist().text(), "");
+        assert_eq!(response.timestamp(), "1337");
+        assert!(!response.accepted());
+        assert_eq!(response.ignored_reason(), Some(IgnoredReason::TimestampTooOld));
+    }
+
+    #[test]
+    fn check_fake_batch_parses() {
+        let value = batch_response(3, 2).to_value();
+        assert_eq!(value["scrobbles"]["@attr"]["accepted"], 3);
+        assert_eq!(value["scrobbles"]["@attr"]["ignored"], 2);
+
+        let decoded: BatchScrobbleResponseWrapper = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.scrobbles.scrobbles.len(), 5);
+
+        let response = batch_response(3, 2).to_response();
+        assert_eq!(response.scrobbles().len(), 5);
+        assert_eq!(response.scrobbles().iter().filter(|s| s.accepted()).count(), 3);
+        assert!(!response.dry_run());
+        assert_eq!(response.into_scrobbles().len(), 5);
+
+        // A single scrobble is sent as a bare object, but is decoded just like an array of one
+        let mut value = batch_response(1, 0).to_value();
+        assert!(value["scrobbles"]["scrobble"].is_object());
+        for _ in 0..2 {
+            let decoded: BatchScrobbleResponseWrapper = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(decoded.scrobbles.scrobbles.len(), 1);
+            assert_eq!(decoded.scrobbles.scrobbles.iter().next().unwrap().track().text(), "Track 0");
+            value["scrobbles"]["scrobble"] = Value::Array(vec![value["scrobbles"]["scrobble"].take()]);
+        }
+    }
+
+    #[test]
+    fn check_fake_track_info_parses() {
+        let info = track_info_response("Cher", "Believe").album("Believe").statistics(1200, 34000).to_response();
+        assert_eq!((info.artist(), info.name(), info.album()), ("Cher", "Believe", Some("Believe")));
+        assert_eq!((info.listeners(), info.playcount()), (1200, 34000));
+        assert_eq!((info.mbid(), info.duration()), (None, None));
+        assert_eq!(info.url(), "https://www.last.fm/music/Cher/_/Believe");
+
+        let mut value = track_info_response("Cher", "Believe").to_value();
+        value["track"]["duration"] = json!("239000");
+        value["track"]["listeners"] = json!("");
+        let decoded: TrackInfoWrapper = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.track.duration(), Some(std::time::Duration::from_secs(239)));
+        assert_eq!(decoded.track.listeners(), 0);
+        assert_eq!(decoded.track.album(), None);
+    }
+
+    #[test]
+    fn check_fake_artist_and_album_info_parse() {
+        let info = artist_info_response("Kraftwerk")
+            .statistics(900, 45000)
+            .bio("Kraftwerk ist eine Band.", "Kraftwerk ist eine Band aus Düsseldorf.")
+            .to_response();
+        assert_eq!((info.name(), info.listeners(), info.playcount()), ("Kraftwerk", 900, 45000));
+        assert_eq!(info.bio_summary(), Some("Kraftwerk ist eine Band."));
+        assert_eq!(info.bio(), Some("Kraftwerk ist eine Band aus Düsseldorf."));
+        assert_eq!(info.url(), "https://www.last.fm/music/Kraftwerk");
+        assert_eq!(artist_info_response("Kraftwerk").to_response().bio(), None);
+
+        let mut value = album_info_response("Kraftwerk", "Trans Europa Express").to_value();
+        value["album"]["wiki"] = json!({ "summary": "", "content": " " });
+        let decoded: AlbumInfoWrapper = serde_json::from_value(value).unwrap();
+        assert_eq!((decoded.album.artist(), decoded.album.name()), ("Kraftwerk", "Trans Europa Express"));
+        assert_eq!((decoded.album.wiki_summary(), decoded.album.wiki()), (None, None));
+        assert_eq!(decoded.album.url(), "https://www.last.fm/music/Kraftwerk/Trans+Europa+Express");
+        let info = album_info_response("Kraftwerk", "Computerwelt").wiki("Summary", "Content").to_response();
+        assert_eq!((info.wiki_summary(), info.wiki()), (Some("Summary"), Some("Content")));
+    }
+
+    #[test]
+    fn check_fake_recent_tracks_parses() {
+        let scrobbles = [("Cher", "Believe", 1_500_000_600), ("Blur", "Song 2", 1_500_000_000)];
+        let body = recent_tracks_response("floyd", &scrobbles)
+            .now_playing("Cher", "Strong Enough")
+            .page(2, 3)
+            .to_string();
+        let decoded: RecentTracksWrapper = serde_json::from_str(&body).unwrap();
+        let recent = decoded.recenttracks;
+        assert_eq!((recent.page.page, recent.page.total_pages), (2, 3));
+        assert_eq!(recent.tracks.len(), 3);
+        assert_eq!((recent.tracks[0].name.as_str(), recent.tracks[0].timestamp()), ("Strong Enough", None));
+        assert_eq!((recent.tracks[1].artist(), recent.tracks[1].timestamp()), ("Cher", Some(1_500_000_600)));
+
+        // An empty page, and a page of one track, sent as a bare object
+        let empty: RecentTracksWrapper =
+            serde_json::from_value(recent_tracks_response("floyd", &[]).to_value()).unwrap();
+        assert!(empty.recenttracks.tracks.is_empty());
+        let mut value = recent_tracks_response("floyd", &[("Blur", "Song 2", 1_500_000_000)]).to_value();
+        value["recenttracks"]["track"] = value["recenttracks"]["track"][0].take();
+        let single: RecentTracksWrapper = serde_json::from_value(value).unwrap();
+        assert_eq!(single.recenttracks.tracks[0].name, "Song 2");
+
+        let tracks = recent_tracks_response("floyd", &scrobbles).now_playing("Cher", "Strong Enough").to_response();
+        assert_eq!((tracks[0].name(), tracks[0].now_playing()), ("Strong Enough", true));
+        assert_eq!((tracks[2].artist(), tracks[2].album(), tracks[2].now_playing()), ("Blur", None, false));
+    }
+
+    #[test]
+    fn check_fake_user_info_parses() {
+        let info = user_info_response("floyd").playcount(1234).subscriber(true).to_response();
+        assert_eq!((info.name(), info.playcount(), info.subscriber()), ("floyd", 1234, true));
+        assert_eq!((info.real_name(), info.country()), (None, None));
+        assert_eq!(info.url(), "https://www.last.fm/user/floyd");
+
+        let mut value = user_info_response("floyd").to_value();
+        value["user"]["realname"] = json!("Floyd");
+        value["user"]["country"] = json!("United Kingdom");
+        value["user"]["registered"] = json!({"unixtime": 0, "#text": 0});
+        let decoded: UserInfoWrapper = serde_json::from_value(value).unwrap();
+        assert_eq!((decoded.user.real_name(), decoded.user.country()), (Some("Floyd"), Some("United Kingdom")));
+        assert_eq!(decoded.user.registered(), None);
+    }
+
+    #[test]
+    fn check_fake_error_response() {
+        let value = error_response(9, "Invalid session key");
+        assert_eq!(value["error"], 9);
+        assert_eq!(value["message"], "Invalid session key");
+    }
+}
@@ -0,0 +1,220 @@
+use super::fake;
+use crate::error::ScrobblerError;
+use crate::headers::Headers;
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// A failure injected by a [`FaultyTransport`](struct.FaultyTransport.html)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The request times out. It never reaches the inner transport.
+    Timeout,
+    /// The connection can't be established. The request never reaches the inner transport.
+    ConnectError,
+    /// The server responds with the given HTTP status and an empty body. The request never reaches the inner
+    /// transport.
+    HttpStatus(u16),
+    /// The server responds with a Last.fm error, ie. `ApiError(29)` for "rate limit exceeded". The request never
+    /// reaches the inner transport.
+    ApiError(u32),
+    /// The request reaches the inner transport, but the response body is replaced with something that isn't JSON
+    GarbageBody,
+    /// The request is delayed by the given duration, then sent to the inner transport as normal
+    Delay(Duration),
+}
+
+/// A [`Transport`] which wraps another transport and injects failures according to a script.
+///
+/// Faults are scripted either by request index (the `n`th request the transport receives, counting from 0, across
+/// all methods), or by method (the next calls to a given API method, in order). A request matching both an indexed
+/// fault and a method fault gets the indexed one, and the method fault stays queued. Requests with no scripted
+/// fault are passed through to the inner transport, such as an [`EchoTransport`].
+///
+/// # Example
+/// ```ignore
+/// let transport = FaultyTransport::new(EchoTransport::new())
+///     .fail_request(0, Fault::Timeout)
+///     .fail_method("track.scrobble", vec![Fault::HttpStatus(503), Fault::GarbageBody]);
+///
+/// let mut scrobbler = Scrobbler::with_transport(api_key, api_secret, transport);
+/// ```
+///
+/// [`Transport`]: ../trait.Transport.html
+/// [`EchoTransport`]: struct.EchoTransport.html
+pub struct FaultyTransport {
+    inner: Box<dyn Transport>,
+    script: Mutex<FaultScript>,
+}
+
+#[derive(Default)]
+struct FaultScript {
+    requests: usize,
+    by_index: HashMap<usize, Fault>,
+    by_method: HashMap<String, VecDeque<Fault>>,
+}
+
+impl FaultScript {
+    // Counts the request, and takes the fault scripted for it, if any
+    fn next_fault(&mut self, method: &str) -> Option<Fault> {
+        let index = self.requests;
+        self.requests += 1;
+
+        self.by_index.remove(&index).or_else(|| {
+            self.by_method
+                .get_mut(method)
+                .and_then(VecDeque::pop_front)
+        })
+    }
+}
+
+impl FaultyTransport {
+    /// Wraps `inner`, initially with no faults scripted
+    pub fn new<T>(inner: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self {
+            inner: Box::new(inner),
+            script: Mutex::new(FaultScript::default()),
+        }
+    }
+
+    /// Scripts a fault for the request with the given index, counting from 0. Replaces any fault already
+    /// scripted for that index.
+    #[must_use]
+    pub fn fail_request(self, index: usize, fault: Fault) -> Self {
+        self.script().by_index.insert(index, fault);
+        self
+    }
+
+    /// Scripts faults for the next calls to the given API method, ie. `"track.scrobble"`, one fault per call.
+    /// Faults are appended to any already scripted for the method.
+    #[must_use]
+    pub fn fail_method<I>(self, method: &str, faults: I) -> Self
+    where
+        I: IntoIterator<Item = Fault>,
+    {
+        self.script()
+            .by_method
+            .entry(method.to_owned())
+            .or_default()
+            .extend(faults);
+        self
+    }
+
+    /// Returns the number of requests received so far, including those that failed
+    pub fn request_count(&self) -> usize {
+        self.script().requests
+    }
+
+    fn script(&self) -> MutexGuard<'_, FaultScript> {
+        self.script.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Transport for FaultyTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ScrobblerError> {
+        // Take the fault and release the lock before doing anything slow
+        let fault = self.script().next_fault(request.method());
+
+        match fault {
+            None => self.inner.send(request),
+            Some(Fault::Timeout) => Err(ScrobblerError::new(
+                "Injected fault: request timed out".to_owned(),
+            )),
+            Some(Fault::ConnectError) => Err(ScrobblerError::new(
+                "Injected fault: connection refused".to_owned(),
+            )),
+            Some(Fault::HttpStatus(status)) => Ok(HttpResponse {
+                status,
+                body: String::new(),
+                headers: Headers::new(),
+            }),
+            Some(Fault::ApiError(code)) => Ok(HttpResponse {
+                status: if code == 29 { 429 } else { 400 },
+                body: fake::error_response(code, "Injected fault").to_string(),
+                headers: Headers::new(),
+            }),
+            Some(Fault::GarbageBody) => self.inner.send(request).map(|resp| HttpResponse {
+                body: "<html><body>502 Bad Gateway</body></html>".to_owned(),
+                ..resp
+            }),
+            Some(Fault::Delay(delay)) => {
+                thread::sleep(delay);
+                self.inner.send(request)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::Headers;
+    use crate::transport::HttpMethod;
+    use crate::params::Params;
+    use crate::test_util::EchoTransport;
+
+    fn request(method: &str) -> HttpRequest {
+        let mut params = Params::new();
+        params.insert("method", method.to_owned());
+        params.insert("artist", "floyd");
+        params.insert("track", "bananas");
+
+        HttpRequest {
+            http_method: HttpMethod::Post,
+            url: "https://ws.audioscrobbler.com/2.0/?format=json".to_owned(),
+            params,
+            request_id: None,
+            max_response_size: 1024 * 1024,
+            headers: Headers::new(),
+            json_body: None,
+        }
+    }
+
+    #[test]
+    fn check_faults_by_index_and_method() {
+        let echo = EchoTransport::new();
+        let transport = FaultyTransport::new(echo.clone())
+            .fail_request(1, Fault::Timeout)
+            .fail_method("track.scrobble", vec![Fault::HttpStatus(503), Fault::GarbageBody]);
+
+        // Index 0: first scripted scrobble fault
+        let resp = transport.send(&request("track.scrobble")).unwrap();
+        assert_eq!(resp.status, 503);
+        // Index 1: indexed fault takes priority, the method's remaining fault stays queued
+        assert!(transport.send(&request("track.scrobble")).is_err());
+        // Index 2: not a scrobble, passed through untouched
+        let resp = transport.send(&request("track.updateNowPlaying")).unwrap();
+        assert_eq!(resp.status, 200);
+        // Index 3: garbage, after reaching the inner transport
+        let resp = transport.send(&request("track.scrobble")).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&resp.body).is_err());
+        // Index 4: script exhausted
+        let resp = transport.send(&request("track.scrobble")).unwrap();
+        assert!(resp.body.contains("bananas"));
+
+        assert_eq!(transport.request_count(), 5);
+        assert_eq!(echo.requests().len(), 3);
+    }
+
+    #[test]
+    fn check_fault_api_error_and_delay() {
+        let transport = FaultyTransport::new(EchoTransport::new())
+            .fail_request(0, Fault::ApiError(29))
+            .fail_request(1, Fault::Delay(Duration::from_millis(5)));
+
+        let resp = transport.send(&request("track.scrobble")).unwrap();
+        assert_eq!(resp.status, 429);
+        assert!(resp.body.contains("\"error\":29"));
+
+        let started = std::time::Instant::now();
+        let resp = transport.send(&request("track.scrobble")).unwrap();
+        assert_eq!(resp.status, 200);
+        assert!(started.elapsed() >= Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::error::{FileOperation, ScrobblerError};
+use crate::headers::Headers;
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Request parameters which identify the application or user, rather than the request. They are never written to
+// fixtures, and are ignored when matching requests against fixtures.
+const SECRET_PARAMS: &[&str] = &["api_key", "api_sig", "password", "sk", "token"];
+
+const REDACTED: &str = "REDACTED";
+
+/// A [`Transport`] which records API responses to fixture files, or replays them from fixture files.
+///
+/// In record mode, every request is passed through to a real transport and the request fingerprint and response
+/// are written to the fixture directory. In replay mode, no request ever reaches the network: responses are served
+/// from the fixture directory, and a request with no matching fixture panics, naming the fixture file it expected.
+///
+/// Requests are matched on their API method plus their (sorted) non-secret parameters. Secrets - the API key, API
+/// signature, session key, password and auth token - are scrubbed from fixtures and ignored when matching, so
+/// fixtures recorded with real credentials replay with any credentials, and are safe to commit. Session keys in
+/// recorded authentication responses are scrubbed too.
+///
+/// Scrobble requests include a timestamp, which must be fixed (see `Scrobble::with_timestamp`) for a recorded
+/// request to match on replay.
+///
+/// # Fixture format
+/// Each distinct request is stored in its own file, named `<method>-<fingerprint>.json`, where the fingerprint is
+/// the hex MD5 hash of the method and the non-secret parameters. Files are pretty-printed JSON of the form:
+///
+/// ```json
+/// {
+///   "request": {
+///     "method": "track.scrobble",
+///     "params": { "album": "No Blues", "artist": "Los Campesinos!", "timestamp": "1337", "track": "To Tundra" }
+///   },
+///   "response": {
+///     "status": 200,
+///     "body": "{\"scrobbles\":{...}}"
+///   }
+/// }
+/// ```
+///
+/// Fixtures can be edited or written by hand; `request` is only used to check for fingerprint collisions.
+///
+/// # Example
+/// ```ignore
+/// // Record once, against the real API...
+/// let transport = FixtureTransport::record("tests/fixtures", ReqwestTransport::new());
+/// // ...then replay in every test run after that
+/// let transport = FixtureTransport::replay("tests/fixtures");
+///
+/// let mut scrobbler = Scrobbler::with_transport(api_key, api_secret, transport);
+/// ```
+///
+/// [`Transport`]: ../trait.Transport.html
+pub struct FixtureTransport {
+    dir: PathBuf,
+    recorder: Option<Box<dyn Transport>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Fixture {
+    request: FixtureRequest,
+    response: FixtureResponse,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FixtureRequest {
+    method: String,
+    params: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FixtureResponse {
+    status: u16,
+    body: String,
+}
+
+impl FixtureTransport {
+    /// Creates a transport which sends requests via `inner`, recording each response to the fixture directory.
+    /// The directory is created if it doesn't exist, and existing fixtures for the same request are overwritten.
+    pub fn record<T>(dir: impl Into<PathBuf>, inner: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self {
+            dir: dir.into(),
+            recorder: Some(Box::new(inner)),
+        }
+    }
+
+    /// Creates a transport which serves responses from the fixture directory
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            recorder: None,
+        }
+    }
+
+    fn fixture_path(&self, request: &FixtureRequest) -> PathBuf {
+        let mut fingerprint = md5::Context::new();
+        fingerprint.consume(&request.method);
+        for (key, value) in &request.params {
+            fingerprint.consume(key);
+            fingerprint.consume(value);
+        }
+
+        self.dir
+            .join(format!("{}-{:x}.json", request.method, fingerprint.compute()))
+    }
+
+    fn record_response(
+        &self,
+        inner: &dyn Transport,
+        request: &HttpRequest,
+    ) -> Result<HttpResponse, ScrobblerError> {
+        let response = inner.send(request)?;
+
+        let fixture = Fixture {
+            request: FixtureRequest::from(request),
+            response: FixtureResponse {
+                status: response.status,
+                body: scrub_body(&response.body),
+            },
+        };
+        let path = self.fixture_path(&fixture.request);
+
+        write_fixture(&path, &fixture)?;
+
+        Ok(response)
+    }
+
+    fn replay_response(&self, request: &HttpRequest) -> HttpResponse {
+        let request = FixtureRequest::from(request);
+        let path = self.fixture_path(&request);
+
+        let contents = fs::read_to_string(&path).unwrap_or_else(|err| {
+            panic!(
+                "No fixture for {} request with params {:?}: failed to read {}: {err}",
+                request.method,
+                request.params,
+                path.display()
+            )
+        });
+        let fixture: Fixture = serde_json::from_str(&contents)
+            .unwrap_or_else(|err| panic!("Invalid fixture {}: {err}", path.display()));
+
+        assert_eq!(
+            fixture.request,
+            request,
+            "Fixture {} was recorded for a different request",
+            path.display()
+        );
+
+        HttpResponse {
+            status: fixture.response.status,
+            body: fixture.response.body,
+            headers: Headers::new(),
+        }
+    }
+}
+
+impl Transport for FixtureTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ScrobblerError> {
+        match &self.recorder {
+            Some(inner) => self.record_response(inner.as_ref(), request),
+            None => Ok(self.replay_response(request)),
+        }
+    }
+}
+
+impl From<&HttpRequest> for FixtureRequest {
+    fn from(request: &HttpRequest) -> Self {
+        let params = request
+            .params
+            .iter()
+            .filter(|(key, _)| *key != "method" && !SECRET_PARAMS.contains(key))
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+
+        Self {
+            method: request.method().to_owned(),
+            params,
+        }
+    }
+}
+
+// Removes the session key from authentication responses. Other bodies are recorded as-is.
+fn scrub_body(body: &str) -> String {
+    let Ok(mut value) = serde_json::from_str::<Value>(body) else {
+        return body.to_owned();
+    };
+
+    match value.pointer_mut("/session/key") {
+        Some(key) => {
+            *key = Value::String(REDACTED.to_owned());
+            value.to_string()
+        }
+        None => body.to_owned(),
+    }
+}
+
+fn write_fixture(path: &Path, fixture: &Fixture) -> Result<(), ScrobblerError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| ScrobblerError::persistence(FileOperation::CreateDir, dir, err))?;
+    }
+    let contents = serde_json::to_string_pretty(fixture)
+        .map_err(|err| ScrobblerError::new(format!("Failed to serialize fixture {}: {err}", path.display())))?;
+
+    fs::write(path, contents + "\n").map_err(|err| ScrobblerError::persistence(FileOperation::Write, path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::Headers;
+    use crate::transport::HttpMethod;
+    use crate::params::Params;
+    use crate::test_util::fake;
+    use std::sync::Mutex;
+
+    // Stands in for the network, counting the requests it answers
+    struct CannedTransport {
+        body: String,
+        sent: Mutex<usize>,
+    }
+
+    impl Transport for CannedTransport {
+        fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, ScrobblerError> {
+            *self.sent.lock().unwrap() += 1;
+            Ok(HttpResponse {
+                status: 200,
+                body: self.body.clone(),
+                headers: Headers::new(),
+            })
+        }
+    }
+
+    fn auth_request(password: &str) -> HttpRequest {
+        let mut params = Params::new();
+        params.insert("method", "auth.getMobileSession");
+        params.insert("api_key", "api_key");
+        params.insert("api_sig", "signature");
+        params.insert("username", "user");
+        params.insert("password", password);
+
+        HttpRequest {
+            http_method: HttpMethod::Post,
+            url: "https://ws.audioscrobbler.com/2.0/?format=json".to_owned(),
+            params,
+            request_id: None,
+            max_response_size: 1024 * 1024,
+            headers: Headers::new(),
+            json_body: None,
+        }
+    }
+
+    #[test]
+    fn check_fixture_record_and_replay() {
+        let dir = std::env::temp_dir().join(format!("rustfm-fixtures-{}", std::process::id()));
+        let body = fake::session_response("user").key("secret-key").to_string();
+
+        let recorder = FixtureTransport::record(
+            &dir,
+            CannedTransport {
+                body,
+                sent: Mutex::new(0),
+            },
+        );
+        let live = recorder.send(&auth_request("hunter2")).unwrap();
+        assert!(live.body.contains("secret-key"));
+
+        let entries: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 1);
+        let contents = fs::read_to_string(&entries[0]).unwrap();
+        assert!(!contents.contains("secret-key"));
+        assert!(!contents.contains("hunter2"));
+        assert!(contents.contains("\"username\": \"user\""));
+
+        // Secrets don't take part in matching
+        let replayer = FixtureTransport::replay(&dir);
+        let response = replayer.send(&auth_request("other-password")).unwrap();
+        assert_eq!(response.status, 200);
+        assert!(response.body.contains(REDACTED));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "No fixture for auth.getMobileSession request")]
+    fn check_fixture_replay_unmatched() {
+        let replayer = FixtureTransport::replay("/nonexistent/rustfm-fixtures");
+        let _ = replayer.send(&auth_request("hunter2"));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::error::ScrobblerError;
+use crate::issues::PreparationReport;
+use crate::keys::SessionKey;
+use crate::models::metadata::{Scrobble, ScrobbleBatch};
+use crate::models::responses::{
+    BatchScrobbleResponse, NowPlayingResponse, ScrobbleList, ScrobbleResponse, SessionResponse,
+};
+use crate::service::ScrobbleService;
+
+use std::collections::VecDeque;
+use std::result;
+use std::sync::{Mutex, MutexGuard};
+
+type Result<T> = result::Result<T, ScrobblerError>;
+
+/// A call made to a [`MockScrobbler`], as recorded by the mock.
+///
+/// Secrets are not recorded: password authentication only records the username.
+///
+/// [`MockScrobbler`]: struct.MockScrobbler.html
+#[derive(Clone, Debug, PartialEq)]
+pub enum MockCall {
+    AuthenticateWithPassword { username: String },
+    AuthenticateWithToken { token: String },
+    AuthenticateWithSessionKey { session_key: String },
+    NowPlaying(Scrobble),
+    Scrobble(Scrobble),
+    ScrobbleBatch(Vec<Scrobble>),
+}
+
+#[derive(Default)]
+struct MockState {
+    calls: Vec<MockCall>,
+    session_responses: VecDeque<Result<SessionResponse>>,
+    now_playing_responses: VecDeque<Result<NowPlayingResponse>>,
+    scrobble_responses: VecDeque<Result<ScrobbleResponse>>,
+    batch_responses: VecDeque<Result<BatchScrobbleResponse>>,
+}
+
+/// A [`ScrobbleService`] implementation for tests, which never touches the network.
+///
+/// Every call is recorded and can be inspected with [`MockScrobbler::calls`]. Responses (or errors) are programmed
+/// per operation with the `push_*` methods and are returned in the order they were pushed. When no response has
+/// been programmed for an operation the mock succeeds, echoing the submitted track back as an uncorrected response.
+///
+/// # Example
+/// ```ignore
+/// let mut mock = MockScrobbler::new();
+/// mock.push_scrobble_response(Err(ScrobblerError::new("Rate limit exceeded".into())));
+///
+/// let track = Scrobble::new("Example Artist", "Example Track", "Example Album");
+/// assert!(mock.scrobble(&track).is_err());
+/// assert!(mock.scrobble(&track).is_ok());
+/// assert_eq!(mock.calls().len(), 2);
+/// ```
+///
+/// [`ScrobbleService`]: ../trait.ScrobbleService.html
+/// [`MockScrobbler::calls`]: struct.MockScrobbler.html#method.calls
+#[derive(Default)]
+pub struct MockScrobbler {
+    session_key: Option<String>,
+    state: Mutex<MockState>,
+}
+
+impl MockScrobbler {
+    /// Creates an unauthenticated mock with no programmed responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every call made to the mock so far, in order
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    /// Forgets all calls recorded so far
+    pub fn clear_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    /// Queues the result of the next authentication call (password or token)
+    pub fn push_session_response(&self, response: Result<SessionResponse>) {
+        self.state().session_responses.push_back(response);
+    }
+
+    /// Queues the result of the next `now_playing` call
+    pub fn push_now_playing_response(&self, response: Result<NowPlayingResponse>) {
+        self.state().now_playing_responses.push_back(response);
+    }
+
+    /// Queues the result of the next `scrobble` call
+    pub fn push_scrobble_response(&self, response: Result<ScrobbleResponse>) {
+        self.state().scrobble_responses.push_back(response);
+    }
+
+    /// Queues the result of the next `scrobble_batch` call
+    pub fn push_batch_response(&self, response: Result<BatchScrobbleResponse>) {
+        self.state().batch_responses.push_back(response);
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // A panicking test thread must not hide the calls it made from the assertions of another
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn authenticate(&mut self, call: MockCall, name: &str) -> Result<SessionResponse> {
+        let response = {
+            let mut state = self.state();
+            state.calls.push(call);
+            state.session_responses.pop_front()
+        };
+
+        let response = response.unwrap_or_else(|| {
+            Ok(SessionResponse {
+                key: "mock-session-key".to_owned(),
+                subscriber: 0,
+                name: name.to_owned(),
+            })
+        });
+
+        if let Ok(session) = &response {
+            self.session_key = Some(session.key.clone());
+        }
+
+        response
+    }
+}
+
+impl ScrobbleService for MockScrobbler {
+    fn authenticate_with_password(&mut self, username: &str, _password: &str) -> Result<SessionResponse> {
+        let call = MockCall::AuthenticateWithPassword {
+            username: username.to_owned(),
+        };
+        self.authenticate(call, username)
+    }
+
+    fn authenticate_with_token(&mut self, token: &str) -> Result<SessionResponse> {
+        let call = MockCall::AuthenticateWithToken {
+            token: token.to_owned(),
+        };
+        self.authenticate(call, "mock-user")
+    }
+
+    fn authenticate_with_session_key(&mut self, session_key: &str) -> Result<()> {
+        self.state().calls.push(MockCall::AuthenticateWithSessionKey {
+            session_key: session_key.to_owned(),
+        });
+        SessionKey::from(session_key).non_empty()?;
+        self.session_key = Some(session_key.to_owned());
+        Ok(())
+    }
+
+    fn session_key(&self) -> Option<&str> {
+        self.session_key.as_deref()
+    }
+
+    fn now_playing(&self, scrobble: &Scrobble) -> Result<NowPlayingResponse> {
+        let mut state = self.state();
+        state.calls.push(MockCall::NowPlaying(scrobble.clone()));
+        state
+            .now_playing_responses
+            .pop_front()
+            .unwrap_or_else(|| Ok(NowPlayingResponse::from_scrobble(scrobble)))
+    }
+
+    fn scrobble(&self, scrobble: &Scrobble) -> Result<ScrobbleResponse> {
+        let mut state = self.state();
+        state.calls.push(MockCall::Scrobble(scrobble.clone()));
+        state
+            .scrobble_responses
+            .pop_front()
+            .unwrap_or_else(|| Ok(ScrobbleResponse::from_scrobble(scrobble, scrobble.timestamp().unwrap_or(0))))
+    }
+
+    fn scrobble_batch(&self, batch: &ScrobbleBatch) -> Result<BatchScrobbleResponse> {
+        let mut state = self.state();
+        state
+            .calls
+            .push(MockCall::ScrobbleBatch(batch.iter().cloned().collect()));
+        state.batch_responses.pop_front().unwrap_or_else(|| {
+            let scrobbles: Vec<ScrobbleResponse> = batch
+                .iter()
+                .map(|scrobble| ScrobbleResponse::from_scrobble(scrobble, scrobble.timestamp().unwrap_or(0)))
+                .collect();
+
+            Ok(BatchScrobbleResponse {
+                scrobbles: ScrobbleList::from(scrobbles),
+                dry_run: false,
+                preparation: PreparationReport::default(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(service: &dyn ScrobbleService, track: &Scrobble) -> bool {
+        service.now_playing(track).is_ok() && service.scrobble(track).is_ok()
+    }
+
+    #[test]
+    fn check_mock_records_calls() {
+        let mut mock = MockScrobbler::new();
+        let track = Scrobble::new("foo floyd", "old bananas", "bananas");
+
+        let session = mock.authenticate_with_password("user", "hunter2").unwrap();
+        assert_eq!(session.name(), "user");
+        assert_eq!(mock.session_key(), Some("mock-session-key"));
+
+        assert!(play(&mock, &track));
+        let batch = ScrobbleBatch::from(vec![track.clone(), track.clone()]);
+        let resp = mock.scrobble_batch(&batch).unwrap();
+        assert_eq!(resp.scrobbles().len(), 2);
+
+        assert_eq!(
+            mock.calls(),
+            vec![
+                MockCall::AuthenticateWithPassword {
+                    username: "user".into()
+                },
+                MockCall::NowPlaying(track.clone()),
+                MockCall::Scrobble(track.clone()),
+                MockCall::ScrobbleBatch(vec![track.clone(), track]),
+            ]
+        );
+
+        mock.clear_calls();
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn check_mock_programmed_responses() {
+        let mock = MockScrobbler::new();
+        let track = Scrobble::new("foo floyd", "old bananas", "bananas");
+
+        mock.push_scrobble_response(Err(ScrobblerError::new("Rate limit exceeded".into())));
+        mock.push_scrobble_response(Ok(ScrobbleResponse::from_scrobble(&track, 1337)));
+
+        let err = mock.scrobble(&track).unwrap_err();
+        assert_eq!(err.to_string(), "Rate limit exceeded");
+        assert_eq!(mock.scrobble(&track).unwrap().timestamp(), "1337");
+        // Queue exhausted: back to echoing the track
+        assert_eq!(mock.scrobble(&track).unwrap().artist.text, "foo floyd");
+    }
+
+    #[test]
+    fn check_mock_failed_authentication() {
+        let mut mock = MockScrobbler::new();
+        mock.push_session_response(Err(ScrobblerError::new("Authentication failed".into())));
+
+        assert!(mock.authenticate_with_token("token").is_err());
+        assert_eq!(mock.session_key(), None);
+        assert!(mock.authenticate_with_session_key("").is_err());
+        assert_eq!(mock.session_key(), None);
+
+        mock.authenticate_with_session_key("seshkey").unwrap();
+        assert_eq!(mock.session_key(), Some("seshkey"));
+    }
+}
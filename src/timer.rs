@@ -0,0 +1,223 @@
+// Last.fm's scrobble eligibility rule, applied to tracked play time
+use crate::media::MediaKind;
+
+use std::time::{Duration, Instant};
+
+// Tracks this short, or shorter, are never scrobbled
+const MIN_TRACK_DURATION: Duration = Duration::from_secs(30);
+
+// A track is scrobbled after half its duration has been played, or this long, whichever comes first
+const MAX_SCROBBLE_POINT: Duration = Duration::from_mins(4);
+
+/// Tracks how long a track has been played for, to decide when it should be scrobbled.
+///
+/// Last.fm's [scrobbling rules](https://www.last.fm/api/scrobbling#when-is-a-scrobble-a-scrobble) say that a track
+/// should only be scrobbled once it has been played for half of its duration, or for 4 minutes, whichever comes
+/// first; tracks of 30 seconds or shorter should never be scrobbled. Long-form media, such as podcasts and
+/// audiobooks, aren't scrobbled until half of them has been played, however long they are; see
+/// [`ScrobbleTimer::with_media_kind`].
+///
+/// Play time can be fed in directly, with [`ScrobbleTimer::add_played`], or measured by the timer between playback
+/// events: [`ScrobbleTimer::start`], [`ScrobbleTimer::pause`] and [`ScrobbleTimer::resume`]. Time spent paused
+/// doesn't count towards the scrobble point.
+///
+/// # Usage
+/// ```ignore
+/// let mut timer = ScrobbleTimer::new(Duration::from_secs(180));
+/// timer.start();
+/// ...
+/// if timer.should_scrobble() {
+///     scrobbler.scrobble(&track)?;
+/// }
+/// ```
+///
+/// [`ScrobbleTimer::with_media_kind`]: struct.ScrobbleTimer.html#method.with_media_kind
+/// [`ScrobbleTimer::add_played`]: struct.ScrobbleTimer.html#method.add_played
+/// [`ScrobbleTimer::start`]: struct.ScrobbleTimer.html#method.start
+/// [`ScrobbleTimer::pause`]: struct.ScrobbleTimer.html#method.pause
+/// [`ScrobbleTimer::resume`]: struct.ScrobbleTimer.html#method.resume
+#[derive(Clone, Debug)]
+pub struct ScrobbleTimer {
+    duration: Duration,
+    played: Duration,
+    playing_since: Option<Instant>,
+    long_form: bool,
+}
+
+impl ScrobbleTimer {
+    /// Creates a timer for a track of the given duration, with nothing played yet
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            played: Duration::from_secs(0),
+            playing_since: None,
+            long_form: false,
+        }
+    }
+
+    /// Applies the scrobble point for the given kind of media. Listens of [long-form](enum.MediaKind.html) media
+    /// are only scrobbled once half of them has been played, without the 4 minute cap, so that dipping into a long
+    /// episode doesn't count as listening to it.
+    #[must_use]
+    pub fn with_media_kind(mut self, media_kind: &MediaKind) -> Self {
+        self.long_form = media_kind.is_long_form();
+        self
+    }
+
+    /// Starts measuring play time. Equivalent to [`ScrobbleTimer::resume`].
+    ///
+    /// [`ScrobbleTimer::resume`]: struct.ScrobbleTimer.html#method.resume
+    pub fn start(&mut self) {
+        self.resume_at(Instant::now());
+    }
+
+    /// Stops measuring play time, adding the time played since the timer was started or resumed. Does nothing if
+    /// the timer is already paused.
+    pub fn pause(&mut self) {
+        self.pause_at(Instant::now());
+    }
+
+    /// Resumes measuring play time after a pause. Does nothing if the timer is already running.
+    pub fn resume(&mut self) {
+        self.resume_at(Instant::now());
+    }
+
+    /// Adds play time measured by the caller, ie. from a player's position updates
+    pub fn add_played(&mut self, played: Duration) {
+        self.played += played;
+    }
+
+    /// Returns true while the timer is measuring play time
+    pub fn is_running(&self) -> bool {
+        self.playing_since.is_some()
+    }
+
+    /// Returns the total play time so far
+    pub fn played(&self) -> Duration {
+        self.played_at(Instant::now())
+    }
+
+    /// Returns the duration of the track
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns true if the track is long enough to be scrobbled at all, ie. longer than 30 seconds
+    pub fn is_eligible(&self) -> bool {
+        self.duration > MIN_TRACK_DURATION
+    }
+
+    /// Returns the play time after which the track should be scrobbled: half its duration, or 4 minutes, whichever
+    /// is shorter; long-form media always takes half its duration. Tracks which aren't
+    /// [eligible](#method.is_eligible) are never scrobbled, whatever their play time.
+    pub fn scrobble_point(&self) -> Duration {
+        if self.long_form {
+            self.duration / 2
+        } else {
+            (self.duration / 2).min(MAX_SCROBBLE_POINT)
+        }
+    }
+
+    /// Returns true once the track has been played for long enough to be scrobbled
+    pub fn should_scrobble(&self) -> bool {
+        self.should_scrobble_at(Instant::now())
+    }
+
+    pub(crate) fn pause_at(&mut self, now: Instant) {
+        if let Some(since) = self.playing_since.take() {
+            self.played += now.saturating_duration_since(since);
+        }
+    }
+
+    pub(crate) fn resume_at(&mut self, now: Instant) {
+        if self.playing_since.is_none() {
+            self.playing_since = Some(now);
+        }
+    }
+
+    pub(crate) fn played_at(&self, now: Instant) -> Duration {
+        match self.playing_since {
+            Some(since) => self.played + now.saturating_duration_since(since),
+            None => self.played,
+        }
+    }
+
+    pub(crate) fn should_scrobble_at(&self, now: Instant) -> bool {
+        self.is_eligible() && self.played_at(now) >= self.scrobble_point()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn check_short_tracks_never_scrobble() {
+        let mut timer = ScrobbleTimer::new(secs(30));
+        timer.add_played(secs(30));
+
+        assert!(!timer.is_eligible());
+        assert!(!timer.should_scrobble());
+        assert!(ScrobbleTimer::new(secs(31)).is_eligible());
+    }
+
+    #[test]
+    fn check_scrobble_points() {
+        let mut timer = ScrobbleTimer::new(secs(180));
+        assert_eq!(timer.scrobble_point(), secs(90));
+        timer.add_played(secs(89));
+        assert!(!timer.should_scrobble());
+        timer.add_played(secs(1));
+        assert!(timer.should_scrobble());
+
+        let mut timer = ScrobbleTimer::new(secs(2 * 3600));
+        assert_eq!(timer.scrobble_point(), Duration::from_mins(4));
+        timer.add_played(secs(239));
+        assert!(!timer.should_scrobble());
+        timer.add_played(secs(1));
+        assert!(timer.should_scrobble());
+    }
+
+    #[test]
+    fn check_long_form_scrobble_point() {
+        let mut timer = ScrobbleTimer::new(secs(2 * 3600)).with_media_kind(&MediaKind::Podcast);
+        assert_eq!(timer.scrobble_point(), secs(3600));
+        timer.add_played(Duration::from_mins(4));
+        assert!(!timer.should_scrobble());
+        timer.add_played(Duration::from_mins(56));
+        assert!(timer.should_scrobble());
+
+        // Short long-form media, and other kinds of media, follow the usual rules
+        assert_eq!(ScrobbleTimer::new(secs(180)).with_media_kind(&MediaKind::Audiobook).scrobble_point(), secs(90));
+        assert!(!ScrobbleTimer::new(secs(30)).with_media_kind(&MediaKind::Podcast).is_eligible());
+        let other = MediaKind::Other("lecture".to_owned());
+        assert_eq!(ScrobbleTimer::new(secs(2 * 3600)).with_media_kind(&other).scrobble_point(), Duration::from_mins(4));
+    }
+
+    #[test]
+    fn check_play_time_across_pauses() {
+        let start = Instant::now();
+        let mut timer = ScrobbleTimer::new(secs(180));
+
+        timer.resume_at(start);
+        timer.pause_at(start + secs(60));
+        // Paused for 10 minutes, which doesn't count
+        timer.resume_at(start + secs(660));
+        // Resuming a running timer doesn't restart the measurement
+        timer.resume_at(start + secs(670));
+
+        assert!(timer.is_running());
+        assert_eq!(timer.played_at(start + secs(680)), secs(80));
+        assert!(!timer.should_scrobble_at(start + secs(689)));
+        assert!(timer.should_scrobble_at(start + secs(690)));
+
+        timer.pause_at(start + secs(700));
+        timer.pause_at(start + secs(800));
+        assert!(!timer.is_running());
+        assert_eq!(timer.played_at(start + secs(900)), secs(100));
+    }
+}
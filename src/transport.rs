@@ -0,0 +1,375 @@
+// HTTP transport used to deliver Last.fm API requests
+use crate::error::ScrobblerError;
+use crate::headers::Headers;
+use crate::params::Params;
+
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, USER_AGENT};
+use reqwest::{Client, ClientBuilder, Proxy, RedirectPolicy};
+use std::io::Read;
+use std::time::Duration;
+
+/// A signed Last.fm API request, ready to be sent.
+///
+/// A request is sent with the given `http_method`. A `POST` sends `params` to `url` as a form-encoded body (see
+/// [`Params::to_form_body`]), while a `GET` sends them in the query string of [`HttpRequest::query_url`]. The API
+/// method being called is included in the params, as `"method"`.
+///
+/// Methods which change anything, and authentication methods, are always sent as a `POST`. Read-only methods are
+/// sent as a `GET`, so that caching proxies can cache them, unless the URL would be longer than 2,000 bytes, in
+/// which case they fall back to a `POST`.
+///
+/// `request_id` is the correlation ID of the API call, set only when the `Scrobbler` is configured to send it (see
+/// [`Scrobbler::set_send_request_id`]); transports should send it as an `X-Request-Id` header. Retries of a call
+/// share its ID.
+///
+/// `max_response_size` is the largest response body accepted, in bytes (see [`Scrobbler::set_max_response_size`]).
+/// Transports should stop reading a response as soon as it's exceeded, and return
+/// [`ScrobblerError::response_too_large`]. A `Scrobbler` rejects larger bodies from transports which don't.
+///
+/// `headers` are extra HTTP headers to send (see [`Scrobbler::set_extra_header`]). They are already validated.
+///
+/// `json_body`, when set, is sent as the body of a `POST` in place of `params`, with a `Content-Type` of
+/// `application/json`. API calls never set it; it's used to deliver events to an `EventSink`, with the
+/// `event-sink` feature.
+///
+/// [`Params::to_form_body`]: struct.Params.html#method.to_form_body
+/// [`HttpRequest::query_url`]: struct.HttpRequest.html#method.query_url
+/// [`Scrobbler::set_send_request_id`]: struct.Scrobbler.html#method.set_send_request_id
+/// [`Scrobbler::set_max_response_size`]: struct.Scrobbler.html#method.set_max_response_size
+/// [`ScrobblerError::response_too_large`]: struct.ScrobblerError.html#method.response_too_large
+/// [`Scrobbler::set_extra_header`]: struct.Scrobbler.html#method.set_extra_header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub http_method: HttpMethod,
+    pub url: String,
+    pub params: Params,
+    pub request_id: Option<String>,
+    pub max_response_size: u64,
+    pub headers: Headers,
+    pub json_body: Option<String>,
+}
+
+impl HttpRequest {
+    /// Returns the name of the API method being called, ie. `"track.scrobble"`
+    pub fn method(&self) -> &str {
+        self.params.get("method").unwrap_or_default()
+    }
+
+    /// Returns `url` with the form-encoded params appended to its query string, as requested by a `GET`
+    pub fn query_url(&self) -> String {
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        format!("{}{separator}{}", self.url, self.params.to_form_body())
+    }
+}
+
+/// The HTTP method an [`HttpRequest`](struct.HttpRequest.html) is sent with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HttpMethod {
+    /// The params are sent in the URL's query string
+    Get,
+    /// The params are sent as a form-encoded body
+    Post,
+}
+
+impl HttpMethod {
+    // Read-only methods are sent as a `GET` unless that would make the URL too long for some servers and proxies
+    pub(crate) fn choose(read_only: bool, url: &str, params: &Params) -> Self {
+        // The URL, a separator, and the encoded params
+        if read_only && url.len() + 1 + params.to_form_body().len() <= MAX_GET_URL_LEN {
+            Self::Get
+        } else {
+            Self::Post
+        }
+    }
+}
+
+// A conservative limit, well below the 8KB many servers accept
+const MAX_GET_URL_LEN: usize = 2000;
+
+/// The response received to an [`HttpRequest`](struct.HttpRequest.html)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+    /// The response's headers. Only `ETag` and `Last-Modified` are used, to revalidate cached responses; transports
+    /// which leave them out simply have their responses fetched again once they expire.
+    pub headers: Headers,
+}
+
+/// Sends Last.fm API requests over HTTP.
+///
+/// A `Scrobbler` uses [`ReqwestTransport`] unless given a different transport with [`Scrobbler::with_transport`].
+/// Custom transports can be used to wrap the default one (ie. to log or record requests), or to replace the network
+/// altogether in tests.
+///
+/// Implementations return `Err` only when no response could be obtained at all; non-success HTTP statuses are
+/// reported as an `Ok` response and handled by the `Scrobbler`. Redirects should be returned as they are, rather
+/// than followed, so that the `Scrobbler` can follow only those which are safe to (see
+/// [`Scrobbler::set_update_base_url_on_redirect`]).
+///
+/// [`Scrobbler::set_update_base_url_on_redirect`]: struct.Scrobbler.html#method.set_update_base_url_on_redirect
+/// [`ReqwestTransport`]: struct.ReqwestTransport.html
+/// [`Scrobbler::with_transport`]: struct.Scrobbler.html#method.with_transport
+pub trait Transport: Send + Sync {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ScrobblerError>;
+
+    /// Describes the transport's HTTP configuration, for diagnostics (see [`Scrobbler::info`]). By default nothing
+    /// is known about it.
+    ///
+    /// [`Scrobbler::info`]: struct.Scrobbler.html#method.info
+    fn info(&self) -> TransportInfo {
+        TransportInfo::default()
+    }
+}
+
+/// Diagnostic details of a [`Transport`]'s HTTP configuration. Fields are `None` when not known.
+///
+/// [`Transport`]: trait.Transport.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransportInfo {
+    /// The `User-Agent` header sent with requests
+    pub user_agent: Option<String>,
+    /// The URL of the proxy requests are sent through. Only its host and port are ever reported by a `Scrobbler`,
+    /// so it may include credentials.
+    pub proxy: Option<String>,
+    /// The TLS implementation used, ie. `"native-tls"`
+    pub tls_backend: Option<String>,
+}
+
+// `reqwest` sends its own name and version as the User-Agent unless told otherwise (reported here without the
+// patch version, which depends on the resolved dependency)
+pub(crate) const REQWEST_USER_AGENT: &str = "reqwest/0.9";
+
+// The only TLS backend this crate builds `reqwest` with
+pub(crate) const REQWEST_TLS_BACKEND: &str = "native-tls";
+
+/// The default [`Transport`](trait.Transport.html), backed by a `reqwest` client. The clients it creates itself
+/// don't follow redirects.
+#[derive(Debug)]
+pub struct ReqwestTransport {
+    client: Client,
+    // Only known for clients created by the transport itself
+    user_agent: Option<String>,
+    proxy: Option<String>,
+}
+
+impl ReqwestTransport {
+    /// Creates a transport with `reqwest`'s default settings, other than redirects
+    ///
+    /// # Panics
+    /// Panics if the TLS backend can't be initialized, as `reqwest::Client::new` does
+    pub fn new() -> Self {
+        Self {
+            client: client_builder().build().expect("failed to build the HTTP client"),
+            user_agent: Some(REQWEST_USER_AGENT.to_owned()),
+            proxy: None,
+        }
+    }
+
+    // Creates a transport whose client has the given settings, any of which may be left as `reqwest`'s default
+    pub(crate) fn with_settings(
+        timeout: Option<Duration>,
+        proxy: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<Self, ScrobblerError> {
+        let mut builder = client_builder();
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = proxy {
+            let proxy = Proxy::all(proxy).map_err(|err| ScrobblerError::new(format!("Invalid proxy URL: {err}")))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(user_agent) = user_agent {
+            let value = HeaderValue::from_str(user_agent)
+                .map_err(|_| ScrobblerError::new(format!("Invalid User-Agent: {user_agent:?}")))?;
+            let mut headers = HeaderMap::new();
+            headers.insert(USER_AGENT, value);
+            builder = builder.default_headers(headers);
+        }
+        let client = builder
+            .build()
+            .map_err(|err| ScrobblerError::new(format!("Failed to build HTTP client: {err}")))?;
+
+        Ok(Self {
+            client,
+            user_agent: Some(user_agent.unwrap_or(REQWEST_USER_AGENT).to_owned()),
+            proxy: proxy.map(str::to_owned),
+        })
+    }
+
+    /// Creates a transport using an existing, preconfigured `reqwest` client. The client's user agent and proxy
+    /// settings can't be inspected, so they aren't reported by [`Transport::info`]. `reqwest` clients follow
+    /// redirects by default, turning a redirected scrobble into a `GET` without its parameters, so the client
+    /// should be built with `RedirectPolicy::none()`.
+    ///
+    /// [`Transport::info`]: trait.Transport.html#method.info
+    pub fn with_client(client: Client) -> Self {
+        Self {
+            client,
+            user_agent: None,
+            proxy: None,
+        }
+    }
+
+    /// Creates a transport whose `reqwest` client is built by `configure`, an escape hatch for HTTP settings the
+    /// crate doesn't wrap itself (ie. TCP keepalive, or the local address to bind to). `configure` is given a
+    /// fresh `ClientBuilder` which doesn't follow redirects, and returns it with its settings applied.
+    ///
+    /// The builder is passed on as-is: settings which break the Last.fm API, such as a default `Content-Type`
+    /// header, are the caller's responsibility. As with [`ReqwestTransport::with_client`], the client's user agent
+    /// and proxy settings aren't reported by [`Transport::info`]. Fails if the configured client can't be built.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let transport = ReqwestTransport::configure_http(|builder| builder.tcp_nodelay())?;
+    /// let scrobbler = Scrobbler::with_transport(api_key, api_secret, transport);
+    /// ```
+    ///
+    /// [`ReqwestTransport::with_client`]: struct.ReqwestTransport.html#method.with_client
+    /// [`Transport::info`]: trait.Transport.html#method.info
+    pub fn configure_http<F>(configure: F) -> Result<Self, ScrobblerError>
+    where
+        F: FnOnce(ClientBuilder) -> ClientBuilder,
+    {
+        let client = configure(client_builder())
+            .build()
+            .map_err(|err| ScrobblerError::new(format!("Failed to build HTTP client: {err}")))?;
+
+        Ok(Self::with_client(client))
+    }
+}
+
+// A builder for the clients the transport creates, which leave redirects to the `Scrobbler`
+fn client_builder() -> ClientBuilder {
+    Client::builder().redirect(RedirectPolicy::none())
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, ScrobblerError> {
+        let mut builder = match request.http_method {
+            HttpMethod::Get => self.client.get(&request.query_url()),
+            HttpMethod::Post => match &request.json_body {
+                Some(json) => self
+                    .client
+                    .post(&request.url)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(json.clone()),
+                None => self
+                    .client
+                    .post(&request.url)
+                    .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(request.params.to_form_body()),
+            },
+        };
+        if let Some(request_id) = &request.request_id {
+            builder = builder.header("X-Request-Id", request_id.as_str());
+        }
+        for (name, value) in request.headers.iter() {
+            builder = builder.header(name, value);
+        }
+
+        let mut resp = builder
+            .send()
+            .map_err(|err| ScrobblerError::new(err.to_string()))?;
+
+        // Refuse to buffer a body declared too large, and stop reading one which turns out to be
+        let limit = request.max_response_size;
+        if let Some(length) = resp.content_length().filter(|length| *length > limit) {
+            return Err(ScrobblerError::response_too_large(limit, Some(length)));
+        }
+        let mut bytes = Vec::new();
+        (&mut resp)
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(|_| ScrobblerError::new("Failed to read response body".to_string()))?;
+        if bytes.len() as u64 > limit {
+            return Err(ScrobblerError::response_too_large(limit, resp.content_length()));
+        }
+        let body = String::from_utf8(bytes)
+            .map_err(|_| ScrobblerError::new("Failed to read response body".to_string()))?;
+
+        Ok(HttpResponse {
+            status: resp.status().as_u16(),
+            body,
+            headers: Headers::from_response(resp.headers()),
+        })
+    }
+
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            user_agent: self.user_agent.clone(),
+            proxy: self.proxy.clone(),
+            tls_backend: Some(REQWEST_TLS_BACKEND.to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, Matcher};
+
+    fn request(http_method: HttpMethod, params: Params) -> HttpRequest {
+        HttpRequest {
+            http_method,
+            url: format!("{}/2.0/?format=json", mockito::server_url()),
+            params,
+            request_id: None,
+            max_response_size: 1024 * 1024,
+            headers: Headers::new(),
+            json_body: None,
+        }
+    }
+
+    #[test]
+    fn check_choose_http_method() {
+        let url = "https://ws.audioscrobbler.com/2.0/?format=json";
+        let mut params = Params::new();
+        params.insert("method", "track.getInfo");
+        params.insert("artist", "Björk");
+
+        assert_eq!(HttpMethod::choose(true, url, &params), HttpMethod::Get);
+        assert_eq!(HttpMethod::choose(false, url, &params), HttpMethod::Post);
+
+        // Every byte of the value is percent-encoded, tripling its length
+        params.insert("track", "é".repeat(400));
+        assert_eq!(HttpMethod::choose(true, url, &params), HttpMethod::Post);
+    }
+
+    #[test]
+    fn check_reqwest_transport_get() {
+        let mut params = Params::new();
+        params.insert("method", "track.getInfo");
+        params.insert("artist", "Simon & Garfunkel");
+        params.insert("track", "100% a+b=c?");
+
+        let _m = mock(
+            "GET",
+            "/2.0/?format=json&artist=Simon%20%26%20Garfunkel&method=track.getInfo&track=100%25%20a%2Bb%3Dc%3F",
+        )
+        .match_header("content-type", Matcher::Missing)
+        .match_body("")
+        .with_body("{}")
+        .create();
+
+        let response = ReqwestTransport::new().send(&request(HttpMethod::Get, params.clone())).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "{}");
+
+        // The same request as a POST carries the params in its body instead
+        let _m = mock("POST", "/2.0/?format=json")
+            .match_header("content-type", "application/x-www-form-urlencoded")
+            .match_body(params.to_form_body().as_str())
+            .with_body("{\"posted\": true}")
+            .create();
+        let response = ReqwestTransport::new().send(&request(HttpMethod::Post, params)).unwrap();
+        assert_eq!(response.body, "{\"posted\": true}");
+    }
+}
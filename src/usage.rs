@@ -0,0 +1,347 @@
+// Bookkeeping of how many API requests a client has sent recently, shared by every clone of a Scrobbler
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+const MINUTE_SECS: u64 = 60;
+const HOUR_SECS: u64 = 60 * MINUTE_SECS;
+const DAY_SECS: u64 = 24 * HOUR_SECS;
+
+/// A rolling window over which API requests are counted. See [`EstimatedUsage`].
+///
+/// [`EstimatedUsage`]: struct.EstimatedUsage.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UsageWindow {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl UsageWindow {
+    /// Returns the length of the window
+    pub fn duration(self) -> Duration {
+        Duration::from_secs(self.secs())
+    }
+
+    fn secs(self) -> u64 {
+        match self {
+            Self::Minute => MINUTE_SECS,
+            Self::Hour => HOUR_SECS,
+            Self::Day => DAY_SECS,
+        }
+    }
+
+    const ALL: [Self; 3] = [Self::Minute, Self::Hour, Self::Day];
+}
+
+impl fmt::Display for UsageWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+        })
+    }
+}
+
+/// How many API requests a `Scrobbler` and its clones have sent over the last minute, hour and day. Returned by
+/// [`Scrobbler::estimated_usage`].
+///
+/// This is the client's own bookkeeping, not anything Last.fm reports: requests sent by other processes using the
+/// same API key aren't counted, and nor are requests sent before the `Scrobbler` was created, unless a
+/// [`ScrobbleQueue`] persists them (see [`ScrobbleQueue::set_persist_usage`]). Every attempt counts, including
+/// retries. The last minute is counted to the second; the last hour and day are counted in whole minutes, so may
+/// include up to a minute more.
+///
+/// [`Scrobbler::estimated_usage`]: struct.Scrobbler.html#method.estimated_usage
+/// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+/// [`ScrobbleQueue::set_persist_usage`]: struct.ScrobbleQueue.html#method.set_persist_usage
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EstimatedUsage {
+    pub last_minute: u32,
+    pub last_hour: u32,
+    pub last_day: u32,
+}
+
+impl EstimatedUsage {
+    /// Returns the number of requests counted over the given window
+    pub fn get(&self, window: UsageWindow) -> u32 {
+        match window {
+            UsageWindow::Minute => self.last_minute,
+            UsageWindow::Hour => self.last_hour,
+            UsageWindow::Day => self.last_day,
+        }
+    }
+}
+
+/// Limits on the number of requests in each [`UsageWindow`], with `None` for no limit. See [`UsageThresholds`].
+///
+/// [`UsageWindow`]: enum.UsageWindow.html
+/// [`UsageThresholds`]: struct.UsageThresholds.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UsageLimits {
+    pub per_minute: Option<u32>,
+    pub per_hour: Option<u32>,
+    pub per_day: Option<u32>,
+}
+
+impl UsageLimits {
+    /// Returns the limit on the given window, if any
+    pub fn get(&self, window: UsageWindow) -> Option<u32> {
+        match window {
+            UsageWindow::Minute => self.per_minute,
+            UsageWindow::Hour => self.per_hour,
+            UsageWindow::Day => self.per_day,
+        }
+    }
+}
+
+/// Thresholds on a `Scrobbler`'s [`EstimatedUsage`]. Set with [`Scrobbler::set_usage_thresholds`].
+///
+/// When a request takes the estimated usage of a window up to its `soft` limit, a
+/// [`ScrobblerEvent::UsageThresholdReached`] event warns the event observer, and the request is sent as normal.
+/// Once the estimated usage of a window reaches its `hard` limit, calls fail immediately with an error of kind
+/// `ErrorKind::UsageLimitReached`, without sending anything, until enough requests have rolled out of the window.
+/// The error is retryable, and a [`ScrobbleQueue`] pauses flushing instead of backing its scrobbles off. The limits
+/// are checked before each call, so the retries of a call may go over a hard limit.
+///
+/// Last.fm doesn't publish any limits other than a rate of 5 requests a second (see [`RateLimit`]), so the
+/// thresholds are whatever the application considers prudent.
+///
+/// # Usage
+/// ```ignore
+/// scrobbler.set_usage_thresholds(Some(UsageThresholds {
+///     soft: UsageLimits { per_hour: Some(1_000), ..UsageLimits::default() },
+///     hard: UsageLimits { per_day: Some(10_000), ..UsageLimits::default() },
+/// }));
+/// ```
+///
+/// [`EstimatedUsage`]: struct.EstimatedUsage.html
+/// [`Scrobbler::set_usage_thresholds`]: struct.Scrobbler.html#method.set_usage_thresholds
+/// [`ScrobblerEvent::UsageThresholdReached`]: enum.ScrobblerEvent.html#variant.UsageThresholdReached
+/// [`ScrobbleQueue`]: struct.ScrobbleQueue.html
+/// [`RateLimit`]: struct.RateLimit.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UsageThresholds {
+    pub soft: UsageLimits,
+    pub hard: UsageLimits,
+}
+
+// The requests counted per second over the last minute, and per minute over the last day, keyed by seconds and
+// minutes since the Unix epoch. Persisted by a `ScrobbleQueue`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UsageHistory {
+    seconds: BTreeMap<u64, u32>,
+    minutes: BTreeMap<u64, u32>,
+}
+
+impl UsageHistory {
+    fn record(&mut self, now: u64) {
+        *self.seconds.entry(now).or_insert(0) += 1;
+        *self.minutes.entry(now / MINUTE_SECS).or_insert(0) += 1;
+    }
+
+    // Forgets requests which have rolled out of every window
+    fn expire(&mut self, now: u64) {
+        self.seconds.retain(|second, _| now.saturating_sub(*second) < MINUTE_SECS);
+        self.minutes.retain(|minute, _| now.saturating_sub(minute * MINUTE_SECS) < DAY_SECS);
+    }
+
+    fn usage(&self, now: u64) -> EstimatedUsage {
+        // A minute counts towards a window if it started within it
+        let within = |counts: &BTreeMap<u64, u32>, unit: u64, window: UsageWindow| {
+            counts
+                .iter()
+                .filter(|(start, _)| now.saturating_sub(*start * unit) < window.secs())
+                .map(|(_, count)| *count)
+                .fold(0_u32, u32::saturating_add)
+        };
+
+        EstimatedUsage {
+            last_minute: within(&self.seconds, 1, UsageWindow::Minute),
+            last_hour: within(&self.minutes, MINUTE_SECS, UsageWindow::Hour),
+            last_day: within(&self.minutes, MINUTE_SECS, UsageWindow::Day),
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (counts, other) in [(&mut self.seconds, &other.seconds), (&mut self.minutes, &other.minutes)] {
+            for (start, count) in other {
+                let total = counts.entry(*start).or_insert(0);
+                *total = total.saturating_add(*count);
+            }
+        }
+    }
+}
+
+// The estimated usage shared by all the handles to one logical client
+#[derive(Debug, Default)]
+pub(crate) struct UsageTracker {
+    state: Mutex<UsageState>,
+}
+
+#[derive(Debug, Default)]
+struct UsageState {
+    thresholds: Option<UsageThresholds>,
+    history: UsageHistory,
+}
+
+impl UsageTracker {
+    pub(crate) fn set_thresholds(&self, thresholds: Option<UsageThresholds>) {
+        self.lock().thresholds = thresholds;
+    }
+
+    pub(crate) fn thresholds(&self) -> Option<UsageThresholds> {
+        self.lock().thresholds
+    }
+
+    pub(crate) fn usage_at(&self, now: u64) -> EstimatedUsage {
+        let mut state = self.lock();
+        state.history.expire(now);
+        state.history.usage(now)
+    }
+
+    // Checks that no hard limit has been reached, returning the window and limit of the first one which has
+    pub(crate) fn check_at(&self, now: u64) -> Result<(), (UsageWindow, u32)> {
+        let mut state = self.lock();
+        let Some(hard) = state.thresholds.map(|thresholds| thresholds.hard) else {
+            return Ok(());
+        };
+        state.history.expire(now);
+        let usage = state.history.usage(now);
+        let reached = UsageWindow::ALL.iter().find_map(|window| {
+            let limit = hard.get(*window)?;
+            Some((*window, limit)).filter(|_| usage.get(*window) >= limit)
+        });
+
+        reached.map_or(Ok(()), Err)
+    }
+
+    // Counts a request, returning the usage of each window whose soft limit it reached
+    pub(crate) fn record_at(&self, now: u64) -> Vec<(UsageWindow, u32, u32)> {
+        let mut state = self.lock();
+        state.history.expire(now);
+        let before = state.history.usage(now);
+        state.history.record(now);
+        let after = state.history.usage(now);
+
+        let Some(soft) = state.thresholds.map(|thresholds| thresholds.soft) else {
+            return Vec::new();
+        };
+        UsageWindow::ALL
+            .iter()
+            .filter_map(|window| {
+                let limit = soft.get(*window)?;
+                let reached = before.get(*window) < limit && after.get(*window) >= limit;
+                Some((*window, after.get(*window), limit)).filter(|_| reached)
+            })
+            .collect()
+    }
+
+    pub(crate) fn history_at(&self, now: u64) -> UsageHistory {
+        let mut state = self.lock();
+        state.history.expire(now);
+        state.history.clone()
+    }
+
+    // Adds requests counted elsewhere, ie. by an earlier run
+    pub(crate) fn restore(&self, history: &UsageHistory) {
+        self.lock().history.merge(history);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, UsageState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_500_000_000;
+
+    fn usage(last_minute: u32, last_hour: u32, last_day: u32) -> EstimatedUsage {
+        EstimatedUsage {
+            last_minute,
+            last_hour,
+            last_day,
+        }
+    }
+
+    #[test]
+    fn check_usage_windows_roll_over() {
+        let tracker = UsageTracker::default();
+        assert_eq!(tracker.usage_at(NOW), EstimatedUsage::default());
+
+        // `NOW` is the start of a minute
+        tracker.record_at(NOW);
+        tracker.record_at(NOW + 30);
+        assert_eq!(tracker.usage_at(NOW + 30), usage(2, 2, 2));
+        // The first rolls out of the minute, but not the hour or day
+        assert_eq!(tracker.usage_at(NOW + 60), usage(1, 2, 2));
+        tracker.record_at(NOW + 90);
+        assert_eq!(tracker.usage_at(NOW + 90), usage(1, 3, 3));
+
+        // The hour and day are counted in whole minutes, so the first two roll out together
+        assert_eq!(tracker.usage_at(NOW + HOUR_SECS - 1), usage(0, 3, 3));
+        assert_eq!(tracker.usage_at(NOW + HOUR_SECS), usage(0, 1, 3));
+        assert_eq!(tracker.usage_at(NOW + HOUR_SECS + 60), usage(0, 0, 3));
+        tracker.record_at(NOW + DAY_SECS / 2);
+        assert_eq!(tracker.usage_at(NOW + DAY_SECS), usage(0, 0, 2));
+        assert_eq!(tracker.usage_at(NOW + DAY_SECS + 60), usage(0, 0, 1));
+        assert_eq!(tracker.usage_at(NOW + 2 * DAY_SECS), EstimatedUsage::default());
+        assert_eq!(tracker.history_at(NOW + 2 * DAY_SECS), UsageHistory::default());
+    }
+
+    #[test]
+    fn check_usage_thresholds() {
+        let tracker = UsageTracker::default();
+        tracker.set_thresholds(Some(UsageThresholds {
+            soft: UsageLimits {
+                per_minute: Some(2),
+                per_hour: Some(3),
+                per_day: None,
+            },
+            hard: UsageLimits {
+                per_hour: Some(4),
+                ..UsageLimits::default()
+            },
+        }));
+
+        assert_eq!(tracker.record_at(NOW), vec![]);
+        assert_eq!(tracker.record_at(NOW + 1), vec![(UsageWindow::Minute, 2, 2)]);
+        // Each threshold is reported once, as it's reached
+        assert_eq!(tracker.record_at(NOW + 2), vec![(UsageWindow::Hour, 3, 3)]);
+        assert_eq!(tracker.check_at(NOW + 2), Ok(()));
+        assert_eq!(tracker.record_at(NOW + 3), vec![]);
+        assert_eq!(tracker.check_at(NOW + 3), Err((UsageWindow::Hour, 4)));
+
+        // Until the window has rolled over, when the minute's threshold can be reached again
+        assert_eq!(tracker.check_at(NOW + HOUR_SECS + MINUTE_SECS), Ok(()));
+        assert_eq!(tracker.record_at(NOW + HOUR_SECS + MINUTE_SECS), vec![]);
+        assert_eq!(
+            tracker.record_at(NOW + HOUR_SECS + MINUTE_SECS),
+            vec![(UsageWindow::Minute, 2, 2)]
+        );
+
+        tracker.set_thresholds(None);
+        assert_eq!(tracker.record_at(NOW + HOUR_SECS + MINUTE_SECS), vec![]);
+    }
+
+    #[test]
+    fn check_usage_history_restore() {
+        let tracker = UsageTracker::default();
+        tracker.record_at(NOW);
+        tracker.record_at(NOW + 10);
+        let json = serde_json::to_string(&tracker.history_at(NOW + 10)).unwrap();
+
+        let restored = UsageTracker::default();
+        restored.record_at(NOW + 20);
+        restored.restore(&serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.usage_at(NOW + 20), usage(3, 3, 3));
+        assert_eq!(UsageWindow::Hour.duration(), Duration::from_hours(1));
+        assert_eq!(UsageWindow::Day.to_string(), "day");
+    }
+}
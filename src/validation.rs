@@ -0,0 +1,234 @@
+// Last.fm's rules for what counts as a scrobble, checked before anything is sent
+use crate::media::MediaKind;
+use crate::models::metadata::ScrobbleBatch;
+use crate::timer::ScrobbleTimer;
+
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+use std::fmt;
+use std::time::Duration;
+
+/// What should be sent for a play, as decided by [`ScrobbleValidator::eligibility`]
+///
+/// [`ScrobbleValidator::eligibility`]: struct.ScrobbleValidator.html#method.eligibility
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Eligibility {
+    /// The track has been played for long enough to be scrobbled
+    Scrobble,
+    /// The track should only be reported as Now Playing: it's 30 seconds long or shorter, or hasn't been played
+    /// for long enough yet
+    NowPlayingOnly,
+}
+
+/// A problem with a batch found by [`ScrobbleValidator::validate_batch`]. `position` is the scrobble's index in
+/// the batch.
+///
+/// [`ScrobbleValidator::validate_batch`]: struct.ScrobbleValidator.html#method.validate_batch
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// The artist or track name (`field`) is empty, or only whitespace
+    EmptyField { position: usize, field: String },
+    /// The scrobble's timestamp is earlier than that of a scrobble before it in the batch (`previous`)
+    TimestampOutOfOrder {
+        position: usize,
+        timestamp: u64,
+        previous: u64,
+    },
+}
+
+impl ValidationError {
+    /// Returns the index in the batch of the scrobble with the problem
+    pub fn position(&self) -> usize {
+        match self {
+            Self::EmptyField { position, .. } | Self::TimestampOutOfOrder { position, .. } => *position,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Scrobble {} in batch", self.position())?;
+
+        match self {
+            Self::EmptyField { field, .. } => write!(f, " has no {field} name"),
+            Self::TimestampOutOfOrder { timestamp, previous, .. } => {
+                write!(f, " has timestamp {timestamp}, earlier than the previous scrobble's {previous}")
+            }
+        }
+    }
+}
+
+impl StdError for ValidationError {}
+
+/// Checks plays and batches against Last.fm's
+/// [scrobbling rules](https://www.last.fm/api/scrobbling#when-is-a-scrobble-a-scrobble) before anything is sent.
+///
+/// [`ScrobbleValidator::eligibility`] decides whether a play should be scrobbled, or only sent as Now Playing,
+/// from the track's duration and how long it was played for, following the same rules as [`ScrobbleTimer`].
+/// [`ScrobbleValidator::validate_batch`] finds every scrobble of a batch with an empty artist or track name, or
+/// with a timestamp earlier than the scrobble before it.
+///
+/// # Usage
+/// ```ignore
+/// let validator = ScrobbleValidator::new();
+/// match validator.eligibility(Duration::from_secs(180), played) {
+///     Eligibility::Scrobble => { scrobbler.scrobble(&track)?; }
+///     Eligibility::NowPlayingOnly => { scrobbler.now_playing(&track)?; }
+/// }
+///
+/// if let Err(errors) = validator.validate_batch(&batch) {
+///     for error in errors {
+///         eprintln!("{error}");
+///     }
+/// }
+/// ```
+///
+/// [`ScrobbleValidator::eligibility`]: struct.ScrobbleValidator.html#method.eligibility
+/// [`ScrobbleValidator::validate_batch`]: struct.ScrobbleValidator.html#method.validate_batch
+/// [`ScrobbleTimer`]: struct.ScrobbleTimer.html
+#[derive(Clone, Debug, Default)]
+pub struct ScrobbleValidator {
+    media_kind: MediaKind,
+}
+
+impl ScrobbleValidator {
+    /// Creates a validator applying the scrobble point for music
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the scrobble point for the given kind of media. See [`ScrobbleTimer::with_media_kind`].
+    ///
+    /// [`ScrobbleTimer::with_media_kind`]: struct.ScrobbleTimer.html#method.with_media_kind
+    #[must_use]
+    pub fn with_media_kind(mut self, media_kind: MediaKind) -> Self {
+        self.media_kind = media_kind;
+        self
+    }
+
+    /// Decides whether a play of a track of the given duration, played for `played`, should be scrobbled: only if
+    /// the track is longer than 30 seconds, and was played for at least half its duration or 4 minutes
+    pub fn eligibility(&self, duration: Duration, played: Duration) -> Eligibility {
+        let mut timer = ScrobbleTimer::new(duration).with_media_kind(&self.media_kind);
+        timer.add_played(played);
+
+        if timer.should_scrobble() {
+            Eligibility::Scrobble
+        } else {
+            Eligibility::NowPlayingOnly
+        }
+    }
+
+    /// Checks every scrobble of the batch, returning all of the problems found, in the order of the scrobbles.
+    /// Scrobbles without a timestamp aren't checked for ordering.
+    pub fn validate_batch(&self, batch: &ScrobbleBatch) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut latest = None;
+
+        for (position, scrobble) in batch.iter().enumerate() {
+            let names = [("artist", scrobble.artist()), ("track", scrobble.track())];
+            errors.extend(names.iter().filter(|(_, name)| name.trim().is_empty()).map(|(field, _)| {
+                ValidationError::EmptyField {
+                    position,
+                    field: (*field).to_owned(),
+                }
+            }));
+
+            if let Some(timestamp) = scrobble.timestamp() {
+                match latest {
+                    Some(previous) if timestamp < previous => errors.push(ValidationError::TimestampOutOfOrder {
+                        position,
+                        timestamp,
+                        previous,
+                    }),
+                    _ => latest = Some(timestamp),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::metadata::Scrobble;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn played_at(artist: &str, track: &str, timestamp: u64) -> Scrobble {
+        let mut scrobble = Scrobble::new(artist, track, "");
+        scrobble.with_timestamp(timestamp);
+        scrobble
+    }
+
+    #[test]
+    fn check_eligibility() {
+        let validator = ScrobbleValidator::new();
+        assert_eq!(validator.eligibility(secs(180), secs(89)), Eligibility::NowPlayingOnly);
+        assert_eq!(validator.eligibility(secs(180), secs(90)), Eligibility::Scrobble);
+        assert_eq!(validator.eligibility(secs(30), secs(30)), Eligibility::NowPlayingOnly);
+        assert_eq!(validator.eligibility(secs(3600), Duration::from_mins(4)), Eligibility::Scrobble);
+
+        let podcasts = ScrobbleValidator::new().with_media_kind(MediaKind::Podcast);
+        assert_eq!(podcasts.eligibility(secs(3600), Duration::from_mins(4)), Eligibility::NowPlayingOnly);
+        assert_eq!(podcasts.eligibility(secs(3600), Duration::from_mins(30)), Eligibility::Scrobble);
+    }
+
+    #[test]
+    fn check_validate_batch() {
+        let validator = ScrobbleValidator::new();
+        let mut untimed = Scrobble::new(" ", "", "bananas");
+        untimed.with_artists(&["floyd"]);
+        let batch = ScrobbleBatch::from(vec![
+            played_at("floyd", "first", 200),
+            played_at("", "second", 100),
+            untimed,
+            played_at("floyd", "  ", 300),
+            played_at("floyd", "fourth", 250),
+        ]);
+
+        let errors = validator.validate_batch(&batch).unwrap_err();
+        let expected = vec![
+            ValidationError::EmptyField {
+                position: 1,
+                field: "artist".to_owned(),
+            },
+            ValidationError::TimestampOutOfOrder {
+                position: 1,
+                timestamp: 100,
+                previous: 200,
+            },
+            ValidationError::EmptyField {
+                position: 2,
+                field: "track".to_owned(),
+            },
+            ValidationError::EmptyField {
+                position: 3,
+                field: "track".to_owned(),
+            },
+            ValidationError::TimestampOutOfOrder {
+                position: 4,
+                timestamp: 250,
+                previous: 300,
+            },
+        ];
+        assert_eq!(errors, expected);
+        let message = "Scrobble 1 in batch has timestamp 100, earlier than the previous scrobble's 200";
+        assert_eq!(errors[1].to_string(), message);
+        assert_eq!(errors[2].position(), 2);
+
+        // Equal timestamps are in order
+        let batch = ScrobbleBatch::from(vec![played_at("floyd", "first", 100), played_at("floyd", "second", 100)]);
+        assert!(validator.validate_batch(&batch).is_ok());
+        assert!(validator.validate_batch(&ScrobbleBatch::from(Vec::<Scrobble>::new())).is_ok());
+    }
+}
@@ -0,0 +1,229 @@
+// XML response fallback, for servers which ignore `format=json`
+//
+// Rather than duplicating every response type, XML envelopes are converted into the JSON document the API would
+// have sent for `format=json`, which is then decoded as normal:
+//
+// * an element with child elements becomes an object keyed by child name; repeated children become an array, and
+//   the element's attributes are collected under `"@attr"`
+// * a text-only element with attributes becomes an object of its attributes, with the text under `"#text"`
+// * a text-only element without attributes becomes a string
+// * an error envelope, `<lfm status="failed"><error code="9">...</error></lfm>`, becomes
+//   `{"error": 9, "message": "..."}`
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+// Returns true if the body is an XML document rather than JSON
+pub fn is_xml(body: &str) -> bool {
+    let body = body.trim_start();
+    body.starts_with("<?xml") || body.starts_with("<lfm")
+}
+
+// Converts an `<lfm>` XML envelope into its JSON equivalent
+pub fn to_json(body: &str) -> Result<Value, String> {
+    let root = parse(body)?;
+    if root.name != "lfm" {
+        return Err(format!("Unexpected XML root element <{}>", root.name));
+    }
+
+    if let Some(error) = root.children.iter().find(|child| child.name == "error") {
+        let code = error
+            .attr("code")
+            .and_then(|code| code.parse::<u32>().ok())
+            .ok_or("XML error response is missing an error code")?;
+
+        return Ok(serde_json::json!({
+            "error": code,
+            "message": error.text,
+        }));
+    }
+
+    Ok(children_to_value(&root.children))
+}
+
+fn parse(body: &str) -> Result<Element, String> {
+    let mut reader = Reader::from_str(body);
+    reader.trim_text(true);
+
+    let mut stack: Vec<Element> = Vec::new();
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|err| format!("Invalid XML response: {err}"))?;
+
+        match event {
+            Event::Start(start) => stack.push(Element::from_start(&start)?),
+            Event::Empty(start) => {
+                let element = Element::from_start(&start)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|err| format!("Invalid XML response: {err}"))?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(_) => {
+                let element = stack.pop().ok_or("Invalid XML response: unbalanced tags")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            Event::Eof => return Err("Invalid XML response: unexpected end of document".to_owned()),
+            _ => {}
+        }
+    }
+}
+
+impl Element {
+    fn from_start(start: &BytesStart<'_>) -> Result<Self, String> {
+        let mut attrs = Vec::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(|err| format!("Invalid XML response: {err}"))?;
+            let value = attr
+                .unescape_value()
+                .map_err(|err| format!("Invalid XML response: {err}"))?;
+            attrs.push((
+                String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+                value.into_owned(),
+            ));
+        }
+
+        Ok(Self {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            attrs,
+            ..Self::default()
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn attrs_to_map(&self) -> Map<String, Value> {
+        self.attrs
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+            .collect()
+    }
+
+    fn to_value(&self) -> Value {
+        if !self.children.is_empty() {
+            let mut value = children_to_value(&self.children);
+            if !self.attrs.is_empty() {
+                value["@attr"] = Value::Object(self.attrs_to_map());
+            }
+            value
+        } else if !self.attrs.is_empty() {
+            let mut map = self.attrs_to_map();
+            map.insert("#text".to_owned(), Value::String(self.text.clone()));
+            Value::Object(map)
+        } else {
+            Value::String(self.text.clone())
+        }
+    }
+}
+
+fn children_to_value(children: &[Element]) -> Value {
+    let mut map = Map::new();
+    for child in children {
+        let value = child.to_value();
+        match map.get_mut(&child.name) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                map.insert(child.name.clone(), value);
+            }
+        }
+    }
+
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::responses::{
+        AuthResponse, BatchScrobbleResponseWrapper, IgnoredReason, ScrobbleResponseWrapper,
+    };
+
+    #[test]
+    fn check_xml_session() {
+        let body = include_str!("../tests/fixtures/xml/session.xml");
+        assert!(is_xml(body));
+
+        let decoded: AuthResponse = serde_json::from_value(to_json(body).unwrap()).unwrap();
+        assert_eq!(decoded.session.name(), "foofloyd");
+        assert_eq!(decoded.session.key(), "d580d57f32848f5dcf574d1ce18d78b2");
+        assert_eq!(decoded.session.subscriber(), 0);
+    }
+
+    #[test]
+    fn check_xml_scrobble() {
+        let body = include_str!("../tests/fixtures/xml/scrobble.xml");
+
+        let decoded: ScrobbleResponseWrapper = serde_json::from_value(to_json(body).unwrap()).unwrap();
+        let scrobble = decoded.scrobbles.scrobble;
+        assert_eq!(scrobble.artist().text(), "Björk");
+        assert!(scrobble.artist().corrected());
+        assert_eq!(scrobble.track().text(), "Jóga & Bachelorette");
+        assert_eq!(scrobble.album().text(), "");
+        assert_eq!(scrobble.timestamp(), "1287140447");
+        assert_eq!(scrobble.ignored_reason(), Some(IgnoredReason::TimestampTooOld));
+        assert_eq!(decoded.scrobbles.counts.unwrap().ignored, 1);
+    }
+
+    #[test]
+    fn check_xml_batch() {
+        let body = include_str!("../tests/fixtures/xml/batch.xml");
+
+        let decoded: BatchScrobbleResponseWrapper = serde_json::from_value(to_json(body).unwrap()).unwrap();
+        assert_eq!(decoded.scrobbles.scrobbles.len(), 2);
+
+        // A lone <scrobble> element becomes an object, not an array
+        let body = include_str!("../tests/fixtures/xml/batch_single.xml");
+        let decoded: BatchScrobbleResponseWrapper = serde_json::from_value(to_json(body).unwrap()).unwrap();
+        assert_eq!(decoded.scrobbles.scrobbles.len(), 1);
+    }
+
+    #[test]
+    fn check_xml_error() {
+        let body = include_str!("../tests/fixtures/xml/error.xml");
+
+        let value = to_json(body).unwrap();
+        assert_eq!(value["error"], 9);
+        assert_eq!(value["message"], "Invalid session key - Please re-authenticate");
+    }
+
+    #[test]
+    fn check_xml_invalid() {
+        assert!(!is_xml(r#"{"session": {}}"#));
+        assert!(to_json("<lfm status=\"ok\"><session>").is_err());
+        assert!(to_json("<html></html>").is_err());
+    }
+}